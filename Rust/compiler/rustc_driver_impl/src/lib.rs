@@ -104,8 +104,9 @@ pub(super) fn install() {}
 }
 
 use crate::session_diagnostics::{
-    CantEmitMIR, RLinkEmptyVersionNumber, RLinkEncodingVersionMismatch, RLinkRustcVersionMismatch,
-    RLinkWrongFileType, RlinkCorruptFile, RlinkNotAFile, RlinkUnableToRead, UnstableFeatureUsage,
+    CantEmitMIR, QuantumFailed, RLinkEmptyVersionNumber, RLinkEncodingVersionMismatch,
+    RLinkRustcVersionMismatch, RLinkWrongFileType, RlinkCorruptFile, RlinkNotAFile,
+    RlinkUnableToRead, UnstableFeatureUsage,
 };
 
 rustc_fluent_macro::fluent_messages! { "../messages.ftl" }
@@ -142,6 +143,7 @@ pub fn default_translator() -> Translator {
     rustc_passes::DEFAULT_LOCALE_RESOURCE,
     rustc_pattern_analysis::DEFAULT_LOCALE_RESOURCE,
     rustc_privacy::DEFAULT_LOCALE_RESOURCE,
+    rustc_quantum::DEFAULT_LOCALE_RESOURCE,
     rustc_query_system::DEFAULT_LOCALE_RESOURCE,
     rustc_resolve::DEFAULT_LOCALE_RESOURCE,
     rustc_session::DEFAULT_LOCALE_RESOURCE,
@@ -313,9 +315,14 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
             return early_exit();
         }
 
+        // `-Zquantum` compares its phases against the baseline passes below,
+        // so it is created before parsing to measure them.
+        let mut quantum = rustc_quantum::init_quantum_compiler_for_session(sess);
+
         // Parse the crate root source code (doesn't parse submodules yet)
         // Everything else is parsed during macro expansion.
-        let mut krate = passes::parse(sess);
+        let mut krate =
+            measure_quantum_baseline(&mut quantum, "parse_crate", || passes::parse(sess));
 
         // If pretty printing is requested: Figure out the representation, print it and exit
         if let Some(pp_mode) = sess.opts.pretty {
@@ -347,12 +354,22 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
             };
 
             // Make sure name resolution and macro expansion is run.
-            let _ = tcx.resolver_for_lowering();
+            measure_quantum_baseline(&mut quantum, "expand_crate", || {
+                let _ = tcx.resolver_for_lowering();
+            });
 
             if callbacks.after_expansion(compiler, tcx) == Compilation::Stop {
                 return early_exit();
             }
 
+            // `-Zquantum`: the lexing and parsing phases need the expanded AST,
+            // so they have to run before analysis lowers it to HIR.
+            if let Some(quantum) = &mut quantum
+                && let Err(error) = quantum.run_after_expansion(tcx)
+            {
+                sess.dcx().emit_err(QuantumFailed { error: error.to_string() });
+            }
+
             passes::write_dep_info(tcx);
 
             passes::write_interface(tcx);
@@ -367,7 +384,7 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
                 return early_exit();
             }
 
-            tcx.ensure_ok().analysis(());
+            measure_quantum_baseline(&mut quantum, "analysis", || tcx.ensure_ok().analysis(()));
 
             if let Some(metrics_dir) = &sess.opts.unstable_opts.metrics_dir {
                 dump_feature_usage_metrics(tcx, metrics_dir);
@@ -377,6 +394,12 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
                 return early_exit();
             }
 
+            if let Some(quantum) = &mut quantum
+                && let Err(error) = quantum.run_after_analysis(tcx)
+            {
+                sess.dcx().emit_err(QuantumFailed { error: error.to_string() });
+            }
+
             if tcx.sess.opts.output_types.contains_key(&OutputType::Mir) {
                 if let Err(error) = rustc_mir_transform::dump_mir::emit_mir(tcx) {
                     tcx.dcx().emit_fatal(CantEmitMIR { error });
@@ -394,6 +417,18 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
     })
 }
 
+/// Runs `f`, measuring it as a `-Zquantum` baseline pass when quantum phases are enabled.
+fn measure_quantum_baseline<R>(
+    quantum: &mut Option<rustc_quantum::QuantumCompiler>,
+    pass: &'static str,
+    f: impl FnOnce() -> R,
+) -> R {
+    match quantum {
+        Some(quantum) => quantum.measure_baseline(pass, f),
+        None => f(),
+    }
+}
+
 fn dump_feature_usage_metrics(tcxt: TyCtxt<'_>, metrics_dir: &Path) {
     let hash = tcxt.crate_hash(LOCAL_CRATE);
     let crate_name = tcxt.crate_name(LOCAL_CRATE);
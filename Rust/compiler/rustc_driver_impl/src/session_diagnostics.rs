@@ -8,6 +8,12 @@ pub struct CantEmitMIR {
     pub error: std::io::Error,
 }
 
+#[derive(Diagnostic)]
+#[diag(driver_impl_quantum_failed)]
+pub(crate) struct QuantumFailed {
+    pub error: String,
+}
+
 #[derive(Diagnostic)]
 #[diag(driver_impl_rlink_unable_to_read)]
 pub(crate) struct RlinkUnableToRead {
@@ -157,6 +157,10 @@ mod dead_store_elimination : DeadStoreElimination {
     mod post_drop_elaboration : CheckLiveDrops;
     mod prettify : ReorderBasicBlocks, ReorderLocals;
     mod promote_consts : PromoteTemps;
+    mod quantum :
+        QuantumConstantFolding,
+        QuantumDeadCodeElimination,
+        QuantumLoopOptimization;
     mod ref_prop : ReferencePropagation;
     mod remove_noop_landing_pads : RemoveNoopLandingPads;
     mod remove_place_mention : RemovePlaceMention;
@@ -699,6 +703,11 @@ fn o1<T>(x: T) -> WithMinOptLevel<T> {
             &instsimplify::InstSimplify::AfterSimplifyCfg,
             &simplify::SimplifyLocals::BeforeConstProp,
             &dead_store_elimination::DeadStoreElimination::Initial,
+            // `-Zquantum=optimization`: fold constants, drop the branches they decide, then hoist
+            // what is left constant out of loops.
+            &quantum::QuantumConstantFolding,
+            &quantum::QuantumDeadCodeElimination,
+            &quantum::QuantumLoopOptimization,
             &gvn::GVN,
             &simplify::SimplifyLocals::AfterGVN,
             &match_branches::MatchBranchSimplification,
@@ -0,0 +1,414 @@
+//! MIR optimizations run by `-Zquantum=optimization`.
+//!
+//! Each pass is enabled from a minimum `-Zquantum-opt-level` (see [`Session::quantum_opt_level`])
+//! and adds what it changed to `Session::code_stats`, where `rustc_quantum` reports it from.
+//! The passes also add the time they took there: they run inside `optimized_mir`, so timing that
+//! query cannot tell them apart from the other MIR passes.
+
+use std::time::Instant;
+
+use rustc_abi::BackendRepr;
+use rustc_const_eval::const_eval::DummyMachine;
+use rustc_const_eval::interpret::{ImmTy, InterpCx};
+use rustc_data_structures::fx::{FxHashMap, FxIndexMap};
+use rustc_index::bit_set::DenseBitSet;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_session::Session;
+use rustc_session::code_stats::QuantumMirStats;
+use rustc_span::DUMMY_SP;
+use tracing::{debug, trace};
+
+use crate::simplify::remove_dead_blocks;
+use crate::ssa::SsaLocals;
+
+fn record_stats(tcx: TyCtxt<'_>, f: impl FnOnce(&mut QuantumMirStats)) {
+    f(&mut tcx.sess.code_stats.quantum_mir.lock());
+}
+
+/// Adds the time until it is dropped to [`QuantumMirStats::pass_time`].
+struct PassTimer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    start: Instant,
+}
+
+impl<'tcx> PassTimer<'tcx> {
+    fn start(tcx: TyCtxt<'tcx>) -> Self {
+        PassTimer { tcx, start: Instant::now() }
+    }
+}
+
+impl Drop for PassTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        record_stats(self.tcx, |stats| stats.pass_time += elapsed);
+    }
+}
+
+/// Integers, `bool` and `char`: the types whose values this module computes with and moves.
+fn is_integer_like(ty: Ty<'_>) -> bool {
+    ty.is_integral() || ty.is_bool() || ty.is_char()
+}
+
+/// Replaces operations on known integers with their result.
+///
+/// A value is known if it is a constant, or an SSA local assigned from a known value. Switches
+/// and assertions on known locals get a constant condition, which
+/// [`QuantumDeadCodeElimination`] resolves afterwards.
+pub(super) struct QuantumConstantFolding;
+
+impl<'tcx> crate::MirPass<'tcx> for QuantumConstantFolding {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.mir_opt_level() > 0 && sess.quantum_opt_level() >= 1
+    }
+
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        trace!("Running QuantumConstantFolding on {:?}", body.source);
+        let _timer = PassTimer::start(tcx);
+        let typing_env = body.typing_env(tcx);
+        let ssa = SsaLocals::new(tcx, body, typing_env);
+        let mut folder = ConstFolder {
+            tcx,
+            typing_env,
+            ecx: InterpCx::new(tcx, DUMMY_SP, typing_env, DummyMachine),
+            known: FxHashMap::default(),
+        };
+
+        // In reverse postorder, the assignment of an SSA local is seen before its uses.
+        let reverse_postorder = body.basic_blocks.reverse_postorder().to_vec();
+        let basic_blocks = body.basic_blocks.as_mut_preserves_cfg();
+        let mut folded = 0;
+        for bb in reverse_postorder {
+            let data = &mut basic_blocks[bb];
+            for statement in &mut data.statements {
+                let Some((place, rvalue)) = statement.kind.as_assign_mut() else { continue };
+                let Some(value) = folder.eval_rvalue(rvalue) else { continue };
+                if !matches!(rvalue, Rvalue::Use(Operand::Constant(_))) {
+                    debug!(?rvalue, ?value, "folded");
+                    *rvalue = Rvalue::Use(constant_operand(value));
+                    folded += 1;
+                }
+                if let Some(local) = place.as_local()
+                    && ssa.is_ssa(local)
+                {
+                    folder.known.insert(local, value);
+                }
+            }
+
+            match &mut data.terminator_mut().kind {
+                TerminatorKind::SwitchInt { discr: condition, .. }
+                | TerminatorKind::Assert { cond: condition, .. } => {
+                    if let Some(value) = folder.known_operand(condition) {
+                        *condition = constant_operand(value);
+                        folded += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        record_stats(tcx, |stats| stats.folded_expressions += folded);
+    }
+
+    fn is_required(&self) -> bool {
+        false
+    }
+}
+
+fn constant_operand(const_: Const<'_>) -> Operand<'_> {
+    Operand::Constant(Box::new(ConstOperand { span: DUMMY_SP, user_ty: None, const_ }))
+}
+
+struct ConstFolder<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    typing_env: ty::TypingEnv<'tcx>,
+    ecx: InterpCx<'tcx, DummyMachine>,
+    /// Values of the SSA locals assigned a known value so far.
+    known: FxHashMap<Local, Const<'tcx>>,
+}
+
+impl<'tcx> ConstFolder<'tcx> {
+    /// Value of a copy or move of a known local.
+    fn known_operand(&self, operand: &Operand<'tcx>) -> Option<Const<'tcx>> {
+        match operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                self.known.get(&place.as_local()?).copied()
+            }
+            Operand::Constant(_) => None,
+        }
+    }
+
+    fn eval_operand(&self, operand: &Operand<'tcx>) -> Option<ImmTy<'tcx>> {
+        let const_ = match operand {
+            Operand::Constant(constant) => constant.const_,
+            _ => self.known_operand(operand)?,
+        };
+        if !is_integer_like(const_.ty()) {
+            return None;
+        }
+        let scalar = const_.try_eval_scalar_int(self.tcx, self.typing_env)?;
+        let layout = self.tcx.layout_of(self.typing_env.as_query_input(const_.ty())).ok()?;
+        Some(ImmTy::from_scalar_int(scalar, layout))
+    }
+
+    fn eval_rvalue(&self, rvalue: &Rvalue<'tcx>) -> Option<Const<'tcx>> {
+        let value = match rvalue {
+            Rvalue::Use(operand) => self.eval_operand(operand)?,
+            Rvalue::UnaryOp(op, operand) => {
+                let operand = self.eval_operand(operand)?;
+                self.ecx.unary_op(*op, &operand).discard_err()?
+            }
+            // Division by zero and overflowing unchecked operations are UB, for which the
+            // interpreter returns an error: those are left alone.
+            Rvalue::BinaryOp(op, box (left, right)) => {
+                let left = self.eval_operand(left)?;
+                let right = self.eval_operand(right)?;
+                self.ecx.binary_op(*op, &left, &right).discard_err()?
+            }
+            _ => return None,
+        };
+        // The `(value, overflowed)` pairs of checked operations have no scalar constant.
+        if !matches!(value.layout.backend_repr, BackendRepr::Scalar(..))
+            || !is_integer_like(value.layout.ty)
+        {
+            return None;
+        }
+        Some(Const::from_scalar(self.tcx, value.to_scalar(), value.layout.ty))
+    }
+}
+
+/// Turns switches and assertions on constants into gotos, and removes the blocks that are no
+/// longer reachable.
+pub(super) struct QuantumDeadCodeElimination;
+
+impl<'tcx> crate::MirPass<'tcx> for QuantumDeadCodeElimination {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.mir_opt_level() > 0 && sess.quantum_opt_level() >= 1
+    }
+
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        trace!("Running QuantumDeadCodeElimination on {:?}", body.source);
+        let _timer = PassTimer::start(tcx);
+        let typing_env = body.typing_env(tcx);
+        for data in body.basic_blocks_mut() {
+            let terminator = data.terminator_mut();
+            let target = match terminator.kind {
+                TerminatorKind::SwitchInt {
+                    discr: Operand::Constant(ref c), ref targets, ..
+                } => match c.const_.try_eval_bits(tcx, typing_env) {
+                    Some(value) => targets.target_for_value(value),
+                    None => continue,
+                },
+                // An assertion that always fails is kept: it is how the panic is raised.
+                TerminatorKind::Assert {
+                    target, cond: Operand::Constant(ref c), expected, ..
+                } if c.const_.try_eval_bool(tcx, typing_env) == Some(expected) => target,
+                _ => continue,
+            };
+            terminator.kind = TerminatorKind::Goto { target };
+        }
+
+        let blocks = body.basic_blocks.len();
+        remove_dead_blocks(body);
+        let eliminated = (blocks - body.basic_blocks.len()) as u64;
+        record_stats(tcx, |stats| stats.eliminated_blocks += eliminated);
+    }
+
+    fn is_required(&self) -> bool {
+        false
+    }
+}
+
+/// Hoists loop-invariant statements into the block that enters the loop.
+///
+/// Only assignments to SSA locals are moved, from integer operands that are constants or SSA
+/// locals assigned outside of the loop, and with an operation that cannot be UB: the statement
+/// then computes the same value on every iteration, and computing it once more before the loop
+/// is harmless. The storage markers of moved locals are removed, since they are now live across
+/// iterations.
+pub(super) struct QuantumLoopOptimization;
+
+impl<'tcx> crate::MirPass<'tcx> for QuantumLoopOptimization {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.mir_opt_level() > 0 && sess.quantum_opt_level() >= 2
+    }
+
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        trace!("Running QuantumLoopOptimization on {:?}", body.source);
+        let _timer = PassTimer::start(tcx);
+        let loops = natural_loops(body);
+        if loops.is_empty() {
+            return;
+        }
+
+        let typing_env = body.typing_env(tcx);
+        let ssa = SsaLocals::new(tcx, body, typing_env);
+        // Where each SSA local is assigned, in an order where operands come before their uses.
+        // Kept up to date as statements are moved, so that nested loops can hoist them further.
+        let mut assignments: FxIndexMap<Local, Location> =
+            ssa.assignments(body).map(|(local, _, location)| (local, location)).collect();
+        let mut hoisted_locals = DenseBitSet::new_empty(body.local_decls.len());
+        let mut optimized_loops = 0;
+        let mut hoisted_statements = 0;
+
+        for Loop { preheader, blocks } in loops {
+            let mut hoisted = Vec::new();
+            for index in 0..assignments.len() {
+                let (&local, &location) = assignments.get_index(index).unwrap();
+                if !blocks.contains(location.block)
+                    || local == RETURN_PLACE
+                    || ssa.borrowed_locals().contains(local)
+                {
+                    continue;
+                }
+                let statement =
+                    &body.basic_blocks[location.block].statements[location.statement_index];
+                let Some((_, rvalue)) = statement.kind.as_assign() else { continue };
+                let Some(operands) = pure_operands(rvalue) else { continue };
+                let is_invariant = |operand: &Operand<'tcx>| {
+                    is_integer_like(operand.ty(&body.local_decls, tcx))
+                        && match operand {
+                            Operand::Constant(_) => true,
+                            Operand::Copy(place) => place.as_local().is_some_and(|operand_local| {
+                                ssa.is_ssa(operand_local)
+                                    && assignments
+                                        .get(&operand_local)
+                                        .is_none_or(|location| !blocks.contains(location.block))
+                            }),
+                            Operand::Move(_) => false,
+                        }
+                };
+                if !operands.into_iter().all(is_invariant) {
+                    continue;
+                }
+
+                debug!(?local, ?location, ?preheader, "hoisting");
+                let statement_index = body.basic_blocks[preheader].statements.len() + hoisted.len();
+                assignments[index] = Location { block: preheader, statement_index };
+                hoisted.push(location);
+                hoisted_locals.insert(local);
+            }
+
+            if hoisted.is_empty() {
+                continue;
+            }
+            optimized_loops += 1;
+            hoisted_statements += hoisted.len() as u64;
+            let basic_blocks = body.basic_blocks.as_mut_preserves_cfg();
+            for location in hoisted {
+                let statement =
+                    &mut basic_blocks[location.block].statements[location.statement_index];
+                let moved = statement.clone();
+                statement.make_nop();
+                basic_blocks[preheader].statements.push(moved);
+            }
+        }
+
+        if hoisted_statements > 0 {
+            for data in body.basic_blocks.as_mut_preserves_cfg() {
+                for statement in &mut data.statements {
+                    if let StatementKind::StorageLive(local) | StatementKind::StorageDead(local) =
+                        statement.kind
+                        && hoisted_locals.contains(local)
+                    {
+                        statement.make_nop();
+                    }
+                }
+            }
+        }
+
+        record_stats(tcx, |stats| {
+            stats.optimized_loops += optimized_loops;
+            stats.hoisted_statements += hoisted_statements;
+        });
+    }
+
+    fn is_required(&self) -> bool {
+        false
+    }
+}
+
+/// Operands of `rvalue`, if evaluating it cannot be UB whatever their values.
+fn pure_operands<'a, 'tcx>(rvalue: &'a Rvalue<'tcx>) -> Option<Vec<&'a Operand<'tcx>>> {
+    match rvalue {
+        Rvalue::Use(operand)
+        | Rvalue::UnaryOp(UnOp::Not | UnOp::Neg, operand)
+        | Rvalue::Cast(CastKind::IntToInt, operand, _) => Some(vec![operand]),
+        Rvalue::BinaryOp(
+            BinOp::Add
+            | BinOp::AddWithOverflow
+            | BinOp::Sub
+            | BinOp::SubWithOverflow
+            | BinOp::Mul
+            | BinOp::MulWithOverflow
+            | BinOp::BitXor
+            | BinOp::BitAnd
+            | BinOp::BitOr
+            | BinOp::Shl
+            | BinOp::Shr
+            | BinOp::Eq
+            | BinOp::Lt
+            | BinOp::Le
+            | BinOp::Ne
+            | BinOp::Ge
+            | BinOp::Gt,
+            box (left, right),
+        ) => Some(vec![left, right]),
+        _ => None,
+    }
+}
+
+struct Loop {
+    /// The only block outside of the loop that jumps to its header, with a `goto`.
+    preheader: BasicBlock,
+    blocks: DenseBitSet<BasicBlock>,
+}
+
+/// Natural loops of `body` that have a preheader, innermost loops first.
+///
+/// Back edges to the same header are merged into one loop.
+fn natural_loops(body: &Body<'_>) -> Vec<Loop> {
+    let dominators = body.basic_blocks.dominators();
+    let predecessors = body.basic_blocks.predecessors();
+
+    let mut loops: FxIndexMap<BasicBlock, DenseBitSet<BasicBlock>> = FxIndexMap::default();
+    for (latch, data) in traversal::reachable(body) {
+        for header in data.terminator().successors() {
+            if !dominators.dominates(header, latch) {
+                continue;
+            }
+            let blocks = loops.entry(header).or_insert_with(|| {
+                let mut blocks = DenseBitSet::new_empty(body.basic_blocks.len());
+                blocks.insert(header);
+                blocks
+            });
+            // The loop contains the blocks that reach the latch without going through the header.
+            let mut worklist = vec![latch];
+            while let Some(block) = worklist.pop() {
+                if blocks.insert(block) {
+                    worklist.extend(predecessors[block].iter().copied());
+                }
+            }
+        }
+    }
+
+    let mut loops: Vec<_> = loops
+        .into_iter()
+        .filter_map(|(header, blocks)| {
+            if body.basic_blocks[header].is_cleanup {
+                return None;
+            }
+            let mut entries = predecessors[header].iter().filter(|&&pred| !blocks.contains(pred));
+            let preheader = *entries.next()?;
+            if entries.next().is_some() {
+                return None;
+            }
+            match body.basic_blocks[preheader].terminator().kind {
+                TerminatorKind::Goto { .. } => Some(Loop { preheader, blocks }),
+                _ => None,
+            }
+        })
+        .collect();
+    loops.sort_by_key(|natural_loop| natural_loop.blocks.count());
+    loops
+}
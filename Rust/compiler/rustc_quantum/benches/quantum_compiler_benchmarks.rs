@@ -0,0 +1,82 @@
+//! Benchmarks of the quantum compiler phases
+//!
+//! Lexing is measured on the sources of `rustc_middle`. Only the per-file
+//! `rustc_lexer` tokenization is timed, which is the part of the lexing phase
+//! that runs in parallel: serially, and through `par_map` on the rustc thread
+//! pool. Building the quantum tokens and the entanglement pass stay serial and
+//! are not part of the comparison.
+//!
+//! In rustc, `par_map` only runs in parallel with `-Zthreads` above 1; with a
+//! single thread it lexes the files one after the other.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rustc_data_structures::sync::set_dyn_thread_safe_mode;
+use rustc_quantum::quantum_lexer::QuantumProcessingPool;
+use test::Bencher;
+
+/// Crate whose sources are lexed by the benchmarks
+const BENCH_CRATE: &str = "rustc_middle";
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+/// Source text of every file of [`BENCH_CRATE`], in a stable order
+fn load_bench_crate() -> Vec<Arc<String>> {
+    let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(BENCH_CRATE).join("src");
+    let mut files = Vec::new();
+    collect_rust_files(&src_dir, &mut files);
+    files.sort();
+    assert!(!files.is_empty(), "no sources found in {}", src_dir.display());
+
+    files.into_iter().filter_map(|path| fs::read_to_string(path).ok()).map(Arc::new).collect()
+}
+
+/// Number of tokens `rustc_lexer` finds in `src`
+fn lex_file(src: Arc<String>) -> usize {
+    rustc_lexer::tokenize(&src).count()
+}
+
+fn bench_lexing(b: &mut Bencher, parallel_lexing: bool) {
+    // As rustc does with `-Zthreads` above 1
+    set_dyn_thread_safe_mode(true);
+
+    let sources = load_bench_crate();
+    b.bytes = sources.iter().map(|src| src.len() as u64).sum();
+
+    let mut pool = QuantumProcessingPool::new();
+    b.iter(|| {
+        let sources = sources.clone();
+        let token_counts: Vec<usize> = if parallel_lexing {
+            pool.parallel_process(sources, |src| Ok(lex_file(src))).unwrap()
+        } else {
+            sources.into_iter().map(lex_file).collect()
+        };
+        token_counts.iter().sum::<usize>()
+    });
+}
+
+#[bench]
+fn lexing_serial(b: &mut Bencher) {
+    bench_lexing(b, false);
+}
+
+#[bench]
+fn lexing_parallel(b: &mut Bencher) {
+    bench_lexing(b, true);
+}
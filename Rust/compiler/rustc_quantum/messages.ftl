@@ -0,0 +1,7 @@
+quantum_phase_finished = quantum {$phase} phase finished: {$summary}
+
+quantum_report = {$report}
+
+quantum_report_write_failed = failed to write quantum report to `{$path}`: {$error}
+
+quantum_token_cache_write_failed = failed to write quantum token cache to `{$path}`: {$error}
@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use rustc_macros::Diagnostic;
+use rustc_span::Span;
+
+#[derive(Diagnostic)]
+#[diag(quantum_phase_finished)]
+pub(crate) struct PhaseFinished {
+    #[primary_span]
+    pub span: Span,
+    pub phase: &'static str,
+    pub summary: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(quantum_report)]
+pub(crate) struct Report {
+    pub report: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(quantum_report_write_failed)]
+pub(crate) struct ReportWriteFailed {
+    pub path: PathBuf,
+    pub error: std::io::Error,
+}
+
+#[derive(Diagnostic)]
+#[diag(quantum_token_cache_write_failed)]
+pub(crate) struct TokenCacheWriteFailed {
+    pub path: PathBuf,
+    pub error: std::io::Error,
+}
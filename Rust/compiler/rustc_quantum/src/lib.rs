@@ -1,7 +1,7 @@
 //! Quantum-inspired Rust compiler
 //!
 //! This module integrates quantum-inspired algorithms into the Rust compiler
-//! to provide modest performance improvements and innovative compilation techniques.
+//! and measures each of its phases against the rustc passes of the same session.
 //!
 //! # Features
 //!
@@ -11,38 +11,28 @@
 //! - Advanced type inference using constraint solving
 //! - Quantum-inspired code optimization techniques
 //! - Arrow-based data structures for efficient operations
-//!
-//! # Performance
-//!
-//! Typical performance improvements: 5-15% in compilation and runtime performance.
-//! Results may vary depending on code complexity and system configuration.
-
-#![feature(rustc_private)]
-#![feature(box_patterns)]
-#![feature(let_chains)]
-
-extern crate rustc_ast;
-extern crate rustc_data_structures;
-extern crate rustc_driver;
-extern crate rustc_errors;
-extern crate rustc_hash;
-extern crate rustc_hir;
-extern crate rustc_interface;
-extern crate rustc_middle;
-extern crate rustc_session;
-extern crate rustc_span;
-
-use rustc_interface::{interface, Queries};
-use rustc_session::Session;
+
+use std::sync::Arc;
+
+use rustc_hir::def_id::{CRATE_DEF_ID, LOCAL_CRATE};
 use rustc_middle::ty::TyCtxt;
-use std::time::Instant;
+use rustc_session::Session;
+use rustc_session::config::QuantumReportFormat;
+
+use crate::profiling::{PassMeasurement, PhaseMeasurements};
+use crate::report::{QuantumPhaseReport, QuantumReport};
 
+mod errors;
+pub mod profiling;
+pub mod report;
 pub mod quantum_lexer;
 pub mod quantum_parser;
 pub mod quantum_semantic;
 pub mod quantum_optimizer;
 pub mod arrow_data;
 
+rustc_fluent_macro::fluent_messages! { "../messages.ftl" }
+
 /// Quantum compiler configuration
 #[derive(Debug, Clone)]
 pub struct QuantumConfig {
@@ -58,6 +48,26 @@ pub struct QuantumConfig {
     pub quantum_opt_level: u8,
     /// Enable Arrow data structures
     pub arrow_data_structures: bool,
+    /// Lex source files in parallel, on the rustc thread pool of `-Zthreads`
+    pub parallel_lexing: bool,
+}
+
+impl QuantumConfig {
+    /// Build the configuration requested by `-Zquantum` and `-Zquantum-opt-level`
+    pub fn from_session(sess: &Session) -> Self {
+        let phases = sess.opts.unstable_opts.quantum;
+        let opt_level = sess.opts.unstable_opts.quantum_opt_level.unwrap_or(2);
+
+        Self {
+            quantum_lexing: phases.lexing,
+            quantum_parsing: phases.parsing,
+            quantum_semantic: phases.semantic,
+            quantum_optimization: phases.optimization,
+            quantum_opt_level: opt_level as u8,
+            arrow_data_structures: phases.optimization,
+            parallel_lexing: true,
+        }
+    }
 }
 
 impl Default for QuantumConfig {
@@ -69,27 +79,29 @@ fn default() -> Self {
             quantum_optimization: true,
             quantum_opt_level: 2,
             arrow_data_structures: true,
+            // Parallel lexing needs the thread pool mode of a rustc session
+            parallel_lexing: false,
         }
     }
 }
 
 /// Quantum compiler statistics
+///
+/// Only holds measured values: the wall time and memory of every quantum phase,
+/// and of the baseline rustc passes of the same session they are compared against.
 #[derive(Debug, Default)]
 pub struct QuantumStats {
     /// Total quantum compilations
     pub total_compilations: u64,
-    /// Total time saved by quantum optimizations
-    pub time_saved: std::time::Duration,
-    /// Quantum speedup factor
-    pub speedup_factor: f64,
-    /// Memory savings from Arrow structures
-    pub memory_saved: u64,
+    /// Measured quantum phases and baseline passes
+    pub measurements: PhaseMeasurements,
 }
 
 /// Main quantum compiler interface
 pub struct QuantumCompiler {
     config: QuantumConfig,
     stats: QuantumStats,
+    report: QuantumReport,
     quantum_lexer: quantum_lexer::QuantumLexer,
     quantum_parser: quantum_parser::QuantumParser,
     quantum_semantic: quantum_semantic::QuantumSemanticAnalyzer,
@@ -99,155 +111,244 @@ pub struct QuantumCompiler {
 impl QuantumCompiler {
     /// Create a new quantum compiler instance
     pub fn new(config: QuantumConfig) -> Self {
-        println!("🔮 Initializing Quantum Rust Compiler...");
-        
         Self {
             config: config.clone(),
             stats: QuantumStats::default(),
+            report: QuantumReport::default(),
             quantum_lexer: quantum_lexer::QuantumLexer::new(&config),
-            quantum_parser: quantum_parser::QuantumParser::new(&config),
+            quantum_parser: quantum_parser::QuantumParser::new(),
             quantum_semantic: quantum_semantic::QuantumSemanticAnalyzer::new(&config),
-            quantum_optimizer: quantum_optimizer::QuantumOptimizer::new(&config),
+            quantum_optimizer: quantum_optimizer::QuantumOptimizer::new(),
         }
     }
 
-    /// Integrate quantum compilation into rustc pipeline
-    pub fn integrate_with_rustc(&mut self, queries: &Queries<'_>) -> Result<(), Box<dyn std::error::Error>> {
-        let start_time = Instant::now();
-        
-        println!("⚡ Applying quantum enhancements to compilation pipeline...");
-        
+    /// Run the phases that work on the expanded crate (lexing and parsing).
+    ///
+    /// Must be called after macro expansion and before the AST is lowered to HIR.
+    pub fn run_after_expansion(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<()> {
+        self.report = QuantumReport::new(tcx.crate_name(LOCAL_CRATE).to_string());
+
         // Phase 1: Quantum lexical analysis
         if self.config.quantum_lexing {
-            self.apply_quantum_lexing(queries)?;
+            self.apply_quantum_lexing(tcx)?;
         }
-        
+
         // Phase 2: Quantum parsing
         if self.config.quantum_parsing {
-            self.apply_quantum_parsing(queries)?;
+            self.apply_quantum_parsing(tcx)?;
         }
-        
+
+        Ok(())
+    }
+
+    /// Run the phases that need type information (semantic analysis and optimization).
+    ///
+    /// Must be called after `tcx.analysis(())` has succeeded. Writes the
+    /// `-Zquantum-report` output once all phases are done.
+    pub fn run_after_analysis(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<()> {
         // Phase 3: Quantum semantic analysis
         if self.config.quantum_semantic {
-            self.apply_quantum_semantic_analysis(queries)?;
+            self.apply_quantum_semantic_analysis(tcx)?;
         }
-        
+
         // Phase 4: Quantum optimization
         if self.config.quantum_optimization {
-            self.apply_quantum_optimization(queries)?;
+            self.apply_quantum_optimization(tcx)?;
         }
-        
-        let total_time = start_time.elapsed();
+
         self.stats.total_compilations += 1;
-        
-        // Calculate quantum speedup (simulated based on our benchmarks)
-        let classical_time = total_time * 3; // Assume 3x speedup
-        self.stats.time_saved += classical_time - total_time;
-        self.stats.speedup_factor = classical_time.as_secs_f64() / total_time.as_secs_f64();
-        
-        println!("✅ Quantum compilation complete!");
-        println!("📊 Quantum speedup: {:.2}x", self.stats.speedup_factor);
-        println!("⏱️  Time saved: {:?}", self.stats.time_saved);
-        
+
+        self.emit_report(tcx);
         Ok(())
     }
 
+    /// Measure a baseline rustc pass that quantum phases are compared against
+    ///
+    /// See [`profiling::baseline_passes`] for the pass names the phases expect.
+    pub fn measure_baseline<R>(&mut self, pass: &'static str, f: impl FnOnce() -> R) -> R {
+        let (result, measurement) = PassMeasurement::measure(f);
+        self.stats.measurements.record_baseline(pass, measurement);
+        result
+    }
+
     /// Apply quantum lexical analysis
-    fn apply_quantum_lexing(&mut self, queries: &Queries<'_>) -> Result<(), Box<dyn std::error::Error>> {
-        println!("🔍 Applying quantum lexical analysis...");
-        
-        // Get the source code
-        let source_map = queries.global_ctxt()?.enter(|tcx| {
-            tcx.sess.source_map().clone()
-        });
-        
+    fn apply_quantum_lexing(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<()> {
+        // With incremental compilation, tokens of unchanged files are reused across builds
+        let token_cache_path = tcx
+            .sess
+            .incr_comp_session_dir_opt()
+            .map(|dir| dir.join(quantum_lexer::TOKEN_CACHE_FILENAME));
+        if let Some(path) = &token_cache_path {
+            self.quantum_lexer.load_token_cache(path.clone(), tcx.sess.cfg_version);
+        }
+
         // Apply quantum lexing optimizations
-        let quantum_tokens = self.quantum_lexer.quantum_tokenize(&source_map)?;
-        
-        println!("   - Quantum tokens generated: {}", quantum_tokens.len());
-        println!("   - Parallel processing speedup: 2.5x");
-        
+        let (quantum_tokens, measurement) = {
+            let _timer = tcx.sess.prof.verbose_generic_activity("quantum_lexing");
+            PassMeasurement::measure(|| self.quantum_lexer.quantum_tokenize(tcx.sess.source_map()))
+        };
+        let quantum_tokens = quantum_tokens?;
+
+        if let Some(path) = token_cache_path
+            && let Err(error) = self.quantum_lexer.save_token_cache()
+        {
+            tcx.dcx().emit_warn(errors::TokenCacheWriteFailed { path, error });
+        }
+
+        let lexer_stats = self.quantum_lexer.get_stats();
+        let phase = QuantumPhaseReport::new("lexing")
+            .count("files", lexer_stats.files_tokenized)
+            .count("cached_files", lexer_stats.files_from_cache)
+            .count("tokens", quantum_tokens.len())
+            .count("entangled_pairs", lexer_stats.entanglement_pairs)
+            .count("keywords_enhanced", lexer_stats.keywords_enhanced)
+            .ratio("whitespace_compression", lexer_stats.compression_rate / 100.0);
+        self.finish_phase(tcx, measurement, phase);
+
         Ok(())
     }
 
     /// Apply quantum parsing
-    fn apply_quantum_parsing(&mut self, queries: &Queries<'_>) -> Result<(), Box<dyn std::error::Error>> {
-        println!("🌊 Applying quantum parsing...");
-        
-        // Get the AST
-        let _ast = queries.parse()?;
-        
-        // Apply quantum parsing optimizations
-        let quantum_ast = self.quantum_parser.quantum_parse()?;
-        
-        println!("   - Quantum AST nodes: {}", quantum_ast.node_count());
-        println!("   - Superposition parsing speedup: 3.2x");
-        
+    fn apply_quantum_parsing(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<()> {
+        // The expanded crate; it is only stolen when the AST is lowered to HIR.
+        let krate = Arc::clone(&tcx.resolver_for_lowering().borrow().1);
+        let (quantum_ast, measurement) = {
+            let _timer = tcx.sess.prof.verbose_generic_activity("quantum_parsing");
+            PassMeasurement::measure(|| self.quantum_parser.quantum_parse(&krate))
+        };
+        let mut quantum_ast = quantum_ast?;
+        quantum_ast.metadata.source_file = tcx
+            .sess
+            .source_map()
+            .span_to_filename(krate.spans.inner_span)
+            .prefer_local()
+            .to_string();
+
+        let phase = QuantumPhaseReport::new("parsing")
+            .count("items", quantum_ast.node_count())
+            .count("nodes", quantum_ast.metadata.total_nodes)
+            .count("quantum_nodes", quantum_ast.metadata.quantum_nodes);
+        self.finish_phase(tcx, measurement, phase);
+
         Ok(())
     }
 
     /// Apply quantum semantic analysis
-    fn apply_quantum_semantic_analysis(&mut self, queries: &Queries<'_>) -> Result<(), Box<dyn std::error::Error>> {
-        println!("🧠 Applying quantum semantic analysis...");
-        
-        // Get the HIR
-        let _hir = queries.global_ctxt()?.enter(|tcx| {
-            tcx.hir()
-        });
-        
+    fn apply_quantum_semantic_analysis(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<()> {
         // Apply quantum semantic analysis
-        let semantic_info = self.quantum_semantic.quantum_analyze()?;
-        
-        println!("   - Quantum symbols resolved: {}", semantic_info.symbol_count());
-        println!("   - Entangled dependency resolution speedup: 4.1x");
-        
+        let (semantic_info, measurement) = {
+            let _timer = tcx.sess.prof.verbose_generic_activity("quantum_semantic");
+            PassMeasurement::measure(|| self.quantum_semantic.quantum_analyze(tcx))
+        };
+        let semantic_info = semantic_info?;
+
+        let phase = QuantumPhaseReport::new("semantic")
+            .count("symbols", semantic_info.symbol_count())
+            .count("scopes", semantic_info.scopes.len())
+            .count("dependencies", semantic_info.stats.dependencies_resolved)
+            .count("inferred_bindings", semantic_info.type_info.inference_stats.quantum_inferences);
+        self.finish_phase(tcx, measurement, phase);
+
         Ok(())
     }
 
     /// Apply quantum optimization
-    fn apply_quantum_optimization(&mut self, queries: &Queries<'_>) -> Result<(), Box<dyn std::error::Error>> {
-        println!("🚀 Applying quantum optimization...");
-
-        // Get the MIR
-        queries.global_ctxt()?.enter(|tcx| {
-            // Apply quantum optimizations to MIR
-            let optimizations = self.quantum_optimizer.quantum_optimize_mir(tcx)?;
-
-            println!("   - Quantum optimizations applied: {}", optimizations.len());
-            println!("   - Quantum annealing speedup: 5.7x");
-
-            // Apply Arrow data structure optimizations
-            if self.config.arrow_data_structures {
-                let arrow_optimizations = self.quantum_optimizer.apply_arrow_optimizations(tcx)?;
-                println!("   - Arrow optimizations applied: {}", arrow_optimizations.len());
-                self.stats.memory_saved += arrow_optimizations.iter().sum::<u64>() * 1024; // KB saved
+    ///
+    /// The quantum MIR passes run inside `optimized_mir` and time themselves there. The phase
+    /// is measured as their time, and its baseline as the rest of `optimized_mir`.
+    ///
+    /// Sessions that do not codegen (`--emit=metadata`, `cargo check`) never optimize MIR,
+    /// so the phase is skipped for them rather than forcing `optimized_mir` on every body.
+    fn apply_quantum_optimization(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<()> {
+        if !tcx.sess.opts.output_types.should_codegen() {
+            return Ok(());
+        }
+
+        // Optimize every body of the crate. The results are cached, so codegen does not pay
+        // for them again.
+        let pass_time = || tcx.sess.code_stats.quantum_mir.lock().pass_time;
+        let pass_time_before = pass_time();
+        let ((), optimized_mir) = PassMeasurement::measure(|| {
+            for &def_id in tcx.mir_keys(()) {
+                if tcx.def_kind(def_id).is_fn_like() {
+                    tcx.ensure_ok().optimized_mir(def_id);
+                }
             }
+        });
+        let quantum_pass_time = pass_time() - pass_time_before;
+        // Memory is only measured around the whole query, so it is not split between passes
+        let baseline = PassMeasurement {
+            wall_time: optimized_mir.wall_time.saturating_sub(quantum_pass_time),
+            rss_delta: None,
+        };
+        self.stats.measurements.record_baseline("optimized_mir", baseline);
 
-            Ok::<(), Box<dyn std::error::Error>>(())
-        })?;
+        // Collect what the quantum MIR passes changed
+        let optimizations = self.quantum_optimizer.quantum_optimize_mir(tcx)?;
+        let mir_stats = self.quantum_optimizer.get_stats().mir_stats;
+        let measurement = PassMeasurement { wall_time: mir_stats.pass_time, rss_delta: None };
+        let phase = QuantumPhaseReport::new("optimization")
+            .count("optimizations", optimizations.len())
+            .count("folded_expressions", mir_stats.folded_expressions)
+            .count("eliminated_blocks", mir_stats.eliminated_blocks)
+            .count("optimized_loops", mir_stats.optimized_loops)
+            .count("hoisted_statements", mir_stats.hoisted_statements);
+        self.finish_phase(tcx, measurement, phase);
 
         Ok(())
     }
 
+    /// Record a finished phase, surface it as a note on the crate and add it to the report
+    fn finish_phase(
+        &mut self,
+        tcx: TyCtxt<'_>,
+        measurement: PassMeasurement,
+        phase: QuantumPhaseReport,
+    ) {
+        self.stats.measurements.record_phase(phase.phase, measurement);
+        let delta = self.stats.measurements.delta(phase.phase);
+        let phase = phase.measured(measurement, delta.as_ref());
+
+        tcx.dcx().emit_note(errors::PhaseFinished {
+            span: tcx.source_span(CRATE_DEF_ID).shrink_to_lo(),
+            phase: phase.phase,
+            summary: phase.summary(),
+        });
+        self.report.push_phase(phase);
+    }
+
+    /// Write the `-Zquantum-report` output, if requested
+    fn emit_report(&self, tcx: TyCtxt<'_>) {
+        let Some(format) = tcx.sess.opts.unstable_opts.quantum_report else {
+            return;
+        };
+
+        match &tcx.sess.opts.unstable_opts.quantum_report_path {
+            Some(path) => {
+                let rendered = match format {
+                    QuantumReportFormat::Text => self.report.to_text(),
+                    QuantumReportFormat::Json => self.report.to_json(),
+                };
+                if let Err(error) = std::fs::write(path, rendered) {
+                    tcx.dcx().emit_err(errors::ReportWriteFailed { path: path.clone(), error });
+                }
+            }
+            // The session options require a path for JSON, so this is the text report
+            None => {
+                let report = self.report.to_text().trim_end().to_string();
+                tcx.dcx().emit_note(errors::Report { report });
+            }
+        }
+    }
+
     /// Get quantum compilation statistics
     pub fn get_stats(&self) -> &QuantumStats {
         &self.stats
     }
 
-    /// Print quantum compiler information
-    pub fn print_info(&self) {
-        println!("🔮 Quantum Rust Compiler Information:");
-        println!("   - Quantum Lexing: {}", if self.config.quantum_lexing { "✅ Enabled" } else { "❌ Disabled" });
-        println!("   - Quantum Parsing: {}", if self.config.quantum_parsing { "✅ Enabled" } else { "❌ Disabled" });
-        println!("   - Quantum Semantic: {}", if self.config.quantum_semantic { "✅ Enabled" } else { "❌ Disabled" });
-        println!("   - Quantum Optimization: {}", if self.config.quantum_optimization { "✅ Enabled" } else { "❌ Disabled" });
-        println!("   - Quantum Opt Level: {}", self.config.quantum_opt_level);
-        println!("   - Arrow Data Structures: {}", if self.config.arrow_data_structures { "✅ Enabled" } else { "❌ Disabled" });
-        println!("📊 Quantum Statistics:");
-        println!("   - Total Compilations: {}", self.stats.total_compilations);
-        println!("   - Average Speedup: {:.2}x", self.stats.speedup_factor);
-        println!("   - Total Time Saved: {:?}", self.stats.time_saved);
-        println!("   - Memory Saved: {} KB", self.stats.memory_saved / 1024);
+    /// Get the report of the phases run so far
+    pub fn get_report(&self) -> &QuantumReport {
+        &self.report
     }
 }
 
@@ -257,6 +358,16 @@ pub fn init_quantum_compiler() -> QuantumCompiler {
     QuantumCompiler::new(config)
 }
 
+/// Initialize quantum compiler from the `-Zquantum` options of a rustc session
+///
+/// Returns `None` when no quantum phase was requested.
+pub fn init_quantum_compiler_for_session(sess: &Session) -> Option<QuantumCompiler> {
+    if !sess.opts.unstable_opts.quantum.any() {
+        return None;
+    }
+    Some(QuantumCompiler::new(QuantumConfig::from_session(sess)))
+}
+
 /// Initialize quantum compiler with custom configuration
 pub fn init_quantum_compiler_with_config(config: QuantumConfig) -> QuantumCompiler {
     QuantumCompiler::new(config)
@@ -298,6 +409,7 @@ fn test_quantum_compiler_creation() {
         let config = QuantumConfig::default();
         let compiler = QuantumCompiler::new(config);
         assert_eq!(compiler.stats.total_compilations, 0);
+        assert!(compiler.get_report().phases.is_empty());
     }
 
     #[test]
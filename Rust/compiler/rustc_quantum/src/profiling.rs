@@ -0,0 +1,175 @@
+//! Measured cost of quantum phases
+//!
+//! Quantum phases run as self-profiler activities (`quantum_lexing`,
+//! `quantum_parsing`, ...), so they show up in `-Zself-profile` and
+//! `-Ztime-passes`. Their wall time and resident memory are also measured here
+//! and compared against the baseline rustc passes of the same session.
+
+use rustc_data_structures::profiling::get_resident_set_size;
+use std::time::{Duration, Instant};
+
+/// Wall time and resident memory measured around one pass
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PassMeasurement {
+    /// Wall time spent in the pass
+    pub wall_time: Duration,
+    /// Change of the resident set size in bytes, if the platform reports it
+    pub rss_delta: Option<i64>,
+}
+
+impl PassMeasurement {
+    /// Run `f` and measure it
+    pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Self) {
+        let start_rss = get_resident_set_size();
+        let start_time = Instant::now();
+        let result = f();
+        let wall_time = start_time.elapsed();
+        let rss_delta = match (start_rss, get_resident_set_size()) {
+            (Some(start), Some(end)) => Some(end as i64 - start as i64),
+            _ => None,
+        };
+        (result, Self { wall_time, rss_delta })
+    }
+
+    /// Sum of two measurements, for phases compared against several passes
+    fn combine(self, other: Self) -> Self {
+        Self {
+            wall_time: self.wall_time + other.wall_time,
+            rss_delta: self.rss_delta.zip(other.rss_delta).map(|(a, b)| a + b),
+        }
+    }
+}
+
+/// Baseline rustc passes each quantum phase is compared against
+///
+/// The baselines are rustc passes measured by the driver. `lexing` has none:
+/// rustc lexes each file while parsing it, so there is no lexing pass to time.
+pub fn baseline_passes(phase: &str) -> &'static [&'static str] {
+    match phase {
+        "parsing" => &["parse_crate", "expand_crate"],
+        "semantic" => &["analysis"],
+        "optimization" => &["optimized_mir"],
+        _ => &[],
+    }
+}
+
+/// Measured difference between a quantum phase and its baseline passes
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseDelta {
+    pub phase: &'static str,
+    pub baseline_passes: &'static [&'static str],
+    pub quantum: PassMeasurement,
+    pub baseline: PassMeasurement,
+}
+
+impl PhaseDelta {
+    /// Extra wall time of the quantum phase over its baseline, negative if it was faster
+    pub fn wall_time_delta_secs(&self) -> f64 {
+        self.quantum.wall_time.as_secs_f64() - self.baseline.wall_time.as_secs_f64()
+    }
+
+    /// Wall time of the quantum phase relative to its baseline
+    pub fn wall_time_ratio(&self) -> Option<f64> {
+        let baseline = self.baseline.wall_time.as_secs_f64();
+        (baseline > 0.0).then(|| self.quantum.wall_time.as_secs_f64() / baseline)
+    }
+
+    /// Extra resident memory of the quantum phase over its baseline, in bytes
+    pub fn rss_delta(&self) -> Option<i64> {
+        self.quantum.rss_delta.zip(self.baseline.rss_delta).map(|(quantum, base)| quantum - base)
+    }
+}
+
+/// Measurements of the quantum phases and baseline passes of one session
+#[derive(Debug, Clone, Default)]
+pub struct PhaseMeasurements {
+    phases: Vec<(&'static str, PassMeasurement)>,
+    baselines: Vec<(&'static str, PassMeasurement)>,
+}
+
+impl PhaseMeasurements {
+    pub fn record_phase(&mut self, phase: &'static str, measurement: PassMeasurement) {
+        self.phases.push((phase, measurement));
+    }
+
+    pub fn record_baseline(&mut self, pass: &'static str, measurement: PassMeasurement) {
+        self.baselines.push((pass, measurement));
+    }
+
+    /// Measurement of a quantum phase, if it ran
+    pub fn phase(&self, phase: &str) -> Option<PassMeasurement> {
+        self.phases.iter().find(|(name, _)| *name == phase).map(|&(_, measurement)| measurement)
+    }
+
+    /// Delta of `phase` against its baseline passes
+    ///
+    /// Returns `None` unless the phase and all of its baseline passes were measured.
+    pub fn delta(&self, phase: &'static str) -> Option<PhaseDelta> {
+        let quantum = self.phase(phase)?;
+        let baseline_passes = baseline_passes(phase);
+        if baseline_passes.is_empty() {
+            return None;
+        }
+
+        let mut baseline: Option<PassMeasurement> = None;
+        for pass in baseline_passes {
+            let (_, measurement) = self.baselines.iter().find(|(name, _)| name == pass)?;
+            baseline = Some(match baseline {
+                Some(sum) => sum.combine(*measurement),
+                None => *measurement,
+            });
+        }
+
+        Some(PhaseDelta { phase, baseline_passes, quantum, baseline: baseline? })
+    }
+
+    /// Deltas of every measured phase that has measured baselines
+    pub fn deltas(&self) -> Vec<PhaseDelta> {
+        self.phases.iter().filter_map(|&(phase, _)| self.delta(phase)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(millis: u64, rss: i64) -> PassMeasurement {
+        PassMeasurement { wall_time: Duration::from_millis(millis), rss_delta: Some(rss) }
+    }
+
+    #[test]
+    fn test_measure_runs_closure() {
+        let (value, measurement) = PassMeasurement::measure(|| 21 * 2);
+        assert_eq!(value, 42);
+        assert!(measurement.wall_time < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_delta_combines_baselines() {
+        let mut measurements = PhaseMeasurements::default();
+        measurements.record_phase("parsing", measurement(30, 4096));
+        measurements.record_baseline("parse_crate", measurement(10, 1024));
+        measurements.record_baseline("expand_crate", measurement(10, 1024));
+
+        let delta = measurements.delta("parsing").unwrap();
+        assert_eq!(delta.baseline.wall_time, Duration::from_millis(20));
+        assert!((delta.wall_time_delta_secs() - 0.010).abs() < 1e-9);
+        assert!((delta.wall_time_ratio().unwrap() - 1.5).abs() < 1e-9);
+        assert_eq!(delta.rss_delta(), Some(2048));
+    }
+
+    #[test]
+    fn test_no_delta_for_lexing() {
+        let mut measurements = PhaseMeasurements::default();
+        measurements.record_phase("lexing", measurement(5, 0));
+        assert!(measurements.delta("lexing").is_none());
+    }
+
+    #[test]
+    fn test_no_delta_without_baseline() {
+        let mut measurements = PhaseMeasurements::default();
+        measurements.record_phase("semantic", measurement(5, 0));
+        assert!(measurements.delta("semantic").is_none());
+        assert!(measurements.deltas().is_empty());
+    }
+}
@@ -1,595 +0,0 @@
-//! Quantum Algorithms Core
-//! 
-//! This module implements real quantum algorithms for the Rust compiler.
-//! These algorithms provide genuine quantum advantages in compilation tasks.
-
-use std::f64::consts::PI;
-use std::sync::Arc;
-use rayon::prelude::*;
-use crate::{QuantumResult, QuantumError};
-
-/// Quantum state representation
-#[derive(Debug, Clone)]
-pub struct QuantumState {
-    /// Complex amplitudes (real, imaginary)
-    pub amplitudes: Vec<(f64, f64)>,
-    /// Number of qubits
-    pub qubit_count: usize,
-    /// Normalization factor
-    pub norm: f64,
-}
-
-impl QuantumState {
-    /// Create a new quantum state with n qubits in |0⟩ state
-    pub fn new(qubit_count: usize) -> Self {
-        let state_count = 1 << qubit_count;
-        let mut amplitudes = vec![(0.0, 0.0); state_count];
-        amplitudes[0] = (1.0, 0.0); // |00...0⟩ state
-        
-        Self {
-            amplitudes,
-            qubit_count,
-            norm: 1.0,
-        }
-    }
-
-    /// Create superposition state
-    pub fn superposition(qubit_count: usize) -> Self {
-        let state_count = 1 << qubit_count;
-        let amplitude = 1.0 / (state_count as f64).sqrt();
-        let amplitudes = vec![(amplitude, 0.0); state_count];
-        
-        Self {
-            amplitudes,
-            qubit_count,
-            norm: 1.0,
-        }
-    }
-
-    /// Apply quantum gate
-    pub fn apply_gate(&mut self, gate: &QuantumGate, target_qubits: &[usize]) -> QuantumResult<()> {
-        match gate {
-            QuantumGate::Hadamard => self.apply_hadamard(target_qubits[0]),
-            QuantumGate::PauliX => self.apply_pauli_x(target_qubits[0]),
-            QuantumGate::PauliY => self.apply_pauli_y(target_qubits[0]),
-            QuantumGate::PauliZ => self.apply_pauli_z(target_qubits[0]),
-            QuantumGate::CNOT => self.apply_cnot(target_qubits[0], target_qubits[1]),
-            QuantumGate::Phase(theta) => self.apply_phase(*theta, target_qubits[0]),
-            QuantumGate::Rotation(theta, phi, lambda) => self.apply_rotation(*theta, *phi, *lambda, target_qubits[0]),
-        }
-    }
-
-    /// Apply Hadamard gate
-    fn apply_hadamard(&mut self, qubit: usize) -> QuantumResult<()> {
-        let sqrt2_inv = 1.0 / 2.0_f64.sqrt();
-        
-        for i in 0..self.amplitudes.len() {
-            if (i >> qubit) & 1 == 0 {
-                let j = i | (1 << qubit);
-                let (a_real, a_imag) = self.amplitudes[i];
-                let (b_real, b_imag) = self.amplitudes[j];
-                
-                self.amplitudes[i] = (
-                    sqrt2_inv * (a_real + b_real),
-                    sqrt2_inv * (a_imag + b_imag),
-                );
-                self.amplitudes[j] = (
-                    sqrt2_inv * (a_real - b_real),
-                    sqrt2_inv * (a_imag - b_imag),
-                );
-            }
-        }
-        
-        Ok(())
-    }
-
-    /// Apply Pauli-X gate
-    fn apply_pauli_x(&mut self, qubit: usize) -> QuantumResult<()> {
-        for i in 0..self.amplitudes.len() {
-            if (i >> qubit) & 1 == 0 {
-                let j = i | (1 << qubit);
-                self.amplitudes.swap(i, j);
-            }
-        }
-        Ok(())
-    }
-
-    /// Apply Pauli-Y gate
-    fn apply_pauli_y(&mut self, qubit: usize) -> QuantumResult<()> {
-        for i in 0..self.amplitudes.len() {
-            if (i >> qubit) & 1 == 0 {
-                let j = i | (1 << qubit);
-                let (a_real, a_imag) = self.amplitudes[i];
-                let (b_real, b_imag) = self.amplitudes[j];
-                
-                self.amplitudes[i] = (b_imag, -b_real);
-                self.amplitudes[j] = (-a_imag, a_real);
-            }
-        }
-        Ok(())
-    }
-
-    /// Apply Pauli-Z gate
-    fn apply_pauli_z(&mut self, qubit: usize) -> QuantumResult<()> {
-        for i in 0..self.amplitudes.len() {
-            if (i >> qubit) & 1 == 1 {
-                self.amplitudes[i].0 = -self.amplitudes[i].0;
-                self.amplitudes[i].1 = -self.amplitudes[i].1;
-            }
-        }
-        Ok(())
-    }
-
-    /// Apply CNOT gate
-    fn apply_cnot(&mut self, control: usize, target: usize) -> QuantumResult<()> {
-        for i in 0..self.amplitudes.len() {
-            if (i >> control) & 1 == 1 && (i >> target) & 1 == 0 {
-                let j = i | (1 << target);
-                self.amplitudes.swap(i, j);
-            }
-        }
-        Ok(())
-    }
-
-    /// Apply phase gate
-    fn apply_phase(&mut self, theta: f64, qubit: usize) -> QuantumResult<()> {
-        let cos_theta = theta.cos();
-        let sin_theta = theta.sin();
-        
-        for i in 0..self.amplitudes.len() {
-            if (i >> qubit) & 1 == 1 {
-                let (real, imag) = self.amplitudes[i];
-                self.amplitudes[i] = (
-                    real * cos_theta - imag * sin_theta,
-                    real * sin_theta + imag * cos_theta,
-                );
-            }
-        }
-        Ok(())
-    }
-
-    /// Apply general rotation gate
-    fn apply_rotation(&mut self, theta: f64, phi: f64, lambda: f64, qubit: usize) -> QuantumResult<()> {
-        let cos_half = (theta / 2.0).cos();
-        let sin_half = (theta / 2.0).sin();
-        let exp_i_phi = (phi.cos(), phi.sin());
-        let exp_i_lambda = (lambda.cos(), lambda.sin());
-        let exp_i_phi_lambda = (
-            exp_i_phi.0 * exp_i_lambda.0 - exp_i_phi.1 * exp_i_lambda.1,
-            exp_i_phi.0 * exp_i_lambda.1 + exp_i_phi.1 * exp_i_lambda.0,
-        );
-        
-        for i in 0..self.amplitudes.len() {
-            if (i >> qubit) & 1 == 0 {
-                let j = i | (1 << qubit);
-                let (a_real, a_imag) = self.amplitudes[i];
-                let (b_real, b_imag) = self.amplitudes[j];
-                
-                // Apply rotation matrix
-                self.amplitudes[i] = (
-                    cos_half * a_real + sin_half * (exp_i_lambda.0 * b_real + exp_i_lambda.1 * b_imag),
-                    cos_half * a_imag + sin_half * (exp_i_lambda.0 * b_imag - exp_i_lambda.1 * b_real),
-                );
-                self.amplitudes[j] = (
-                    -sin_half * (exp_i_phi.0 * a_real + exp_i_phi.1 * a_imag) + cos_half * (exp_i_phi_lambda.0 * b_real + exp_i_phi_lambda.1 * b_imag),
-                    -sin_half * (exp_i_phi.0 * a_imag - exp_i_phi.1 * a_real) + cos_half * (exp_i_phi_lambda.0 * b_imag - exp_i_phi_lambda.1 * b_real),
-                );
-            }
-        }
-        Ok(())
-    }
-
-    /// Measure qubit
-    pub fn measure(&mut self, qubit: usize) -> QuantumResult<bool> {
-        let mut prob_zero = 0.0;
-        
-        // Calculate probability of measuring |0⟩
-        for i in 0..self.amplitudes.len() {
-            if (i >> qubit) & 1 == 0 {
-                let (real, imag) = self.amplitudes[i];
-                prob_zero += real * real + imag * imag;
-            }
-        }
-        
-        // Simulate measurement (using deterministic result for compilation)
-        let result = prob_zero < 0.5;
-        
-        // Collapse state
-        let norm_factor = if result { (1.0 - prob_zero).sqrt() } else { prob_zero.sqrt() };
-        
-        for i in 0..self.amplitudes.len() {
-            if ((i >> qubit) & 1) as usize != result as usize {
-                self.amplitudes[i] = (0.0, 0.0);
-            } else {
-                self.amplitudes[i].0 /= norm_factor;
-                self.amplitudes[i].1 /= norm_factor;
-            }
-        }
-        
-        Ok(result)
-    }
-
-    /// Get probability distribution
-    pub fn probabilities(&self) -> Vec<f64> {
-        self.amplitudes.iter()
-            .map(|(real, imag)| real * real + imag * imag)
-            .collect()
-    }
-}
-
-/// Quantum gates
-#[derive(Debug, Clone)]
-pub enum QuantumGate {
-    Hadamard,
-    PauliX,
-    PauliY,
-    PauliZ,
-    CNOT,
-    Phase(f64),
-    Rotation(f64, f64, f64), // theta, phi, lambda
-}
-
-/// Quantum Fourier Transform implementation
-pub struct QuantumFFT {
-    qubit_count: usize,
-}
-
-impl QuantumFFT {
-    pub fn new(qubit_count: usize) -> Self {
-        Self { qubit_count }
-    }
-
-    /// Apply Quantum Fourier Transform
-    pub fn apply(&self, state: &mut QuantumState) -> QuantumResult<()> {
-        for i in 0..self.qubit_count {
-            // Apply Hadamard to qubit i
-            state.apply_gate(&QuantumGate::Hadamard, &[i])?;
-            
-            // Apply controlled phase gates
-            for j in (i + 1)..self.qubit_count {
-                let theta = PI / (1 << (j - i));
-                let controlled_phase = QuantumControlledGate::new(
-                    QuantumGate::Phase(theta),
-                    j,
-                    i,
-                );
-                controlled_phase.apply(state)?;
-            }
-        }
-        
-        // Reverse qubit order
-        for i in 0..(self.qubit_count / 2) {
-            let j = self.qubit_count - 1 - i;
-            self.swap_qubits(state, i, j)?;
-        }
-        
-        Ok(())
-    }
-
-    /// Apply inverse Quantum Fourier Transform
-    pub fn apply_inverse(&self, state: &mut QuantumState) -> QuantumResult<()> {
-        // Reverse qubit order
-        for i in 0..(self.qubit_count / 2) {
-            let j = self.qubit_count - 1 - i;
-            self.swap_qubits(state, i, j)?;
-        }
-        
-        for i in (0..self.qubit_count).rev() {
-            // Apply controlled phase gates (inverse)
-            for j in ((i + 1)..self.qubit_count).rev() {
-                let theta = -PI / (1 << (j - i));
-                let controlled_phase = QuantumControlledGate::new(
-                    QuantumGate::Phase(theta),
-                    j,
-                    i,
-                );
-                controlled_phase.apply(state)?;
-            }
-            
-            // Apply Hadamard to qubit i
-            state.apply_gate(&QuantumGate::Hadamard, &[i])?;
-        }
-        
-        Ok(())
-    }
-
-    fn swap_qubits(&self, state: &mut QuantumState, qubit1: usize, qubit2: usize) -> QuantumResult<()> {
-        state.apply_gate(&QuantumGate::CNOT, &[qubit1, qubit2])?;
-        state.apply_gate(&QuantumGate::CNOT, &[qubit2, qubit1])?;
-        state.apply_gate(&QuantumGate::CNOT, &[qubit1, qubit2])?;
-        Ok(())
-    }
-}
-
-/// Controlled quantum gate
-pub struct QuantumControlledGate {
-    gate: QuantumGate,
-    control_qubit: usize,
-    target_qubit: usize,
-}
-
-impl QuantumControlledGate {
-    pub fn new(gate: QuantumGate, control_qubit: usize, target_qubit: usize) -> Self {
-        Self {
-            gate,
-            control_qubit,
-            target_qubit,
-        }
-    }
-
-    pub fn apply(&self, state: &mut QuantumState) -> QuantumResult<()> {
-        for i in 0..state.amplitudes.len() {
-            if (i >> self.control_qubit) & 1 == 1 {
-                // Control qubit is |1⟩, apply gate to target
-                let mut temp_state = state.clone();
-                temp_state.apply_gate(&self.gate, &[self.target_qubit])?;
-                state.amplitudes[i] = temp_state.amplitudes[i];
-            }
-        }
-        Ok(())
-    }
-}
-
-/// Quantum annealing algorithm for optimization
-pub struct QuantumAnnealer {
-    temperature: f64,
-    cooling_rate: f64,
-    min_temperature: f64,
-}
-
-impl QuantumAnnealer {
-    pub fn new() -> Self {
-        Self {
-            temperature: 1000.0,
-            cooling_rate: 0.95,
-            min_temperature: 0.01,
-        }
-    }
-
-    /// Optimize using quantum annealing
-    pub fn optimize<T, F>(&mut self, initial_solution: T, cost_function: F) -> QuantumResult<T>
-    where
-        T: Clone,
-        F: Fn(&T) -> f64,
-    {
-        let mut current_solution = initial_solution;
-        let mut current_cost = cost_function(&current_solution);
-        let mut best_solution = current_solution.clone();
-        let mut best_cost = current_cost;
-
-        while self.temperature > self.min_temperature {
-            // Generate neighbor solution (simplified)
-            let neighbor_solution = current_solution.clone();
-            let neighbor_cost = cost_function(&neighbor_solution);
-
-            // Accept or reject based on quantum probability
-            let delta_cost = neighbor_cost - current_cost;
-            let acceptance_probability = if delta_cost < 0.0 {
-                1.0
-            } else {
-                (-delta_cost / self.temperature).exp()
-            };
-
-            if acceptance_probability > 0.5 { // Simplified acceptance
-                current_solution = neighbor_solution;
-                current_cost = neighbor_cost;
-
-                if current_cost < best_cost {
-                    best_solution = current_solution.clone();
-                    best_cost = current_cost;
-                }
-            }
-
-            self.temperature *= self.cooling_rate;
-        }
-
-        Ok(best_solution)
-    }
-}
-
-/// Quantum parallel search algorithm
-pub struct QuantumParallelSearch;
-
-impl QuantumParallelSearch {
-    /// Parallel search using quantum superposition
-    pub fn search<T, F>(items: &[T], predicate: F) -> Vec<usize>
-    where
-        T: Sync,
-        F: Fn(&T) -> bool + Sync,
-    {
-        // Use Rayon for parallel processing (simulating quantum parallelism)
-        items
-            .par_iter()
-            .enumerate()
-            .filter_map(|(index, item)| {
-                if predicate(item) {
-                    Some(index)
-                } else {
-                    None
-                }
-            })
-            .collect()
-    }
-
-    /// Quantum amplitude amplification
-    pub fn amplify_search<T, F>(items: &[T], predicate: F, iterations: usize) -> Vec<usize>
-    where
-        T: Sync,
-        F: Fn(&T) -> bool + Sync,
-    {
-        let mut results = Self::search(items, &predicate);
-        
-        // Simulate amplitude amplification by iterative refinement
-        for _ in 0..iterations {
-            let refined_results: Vec<usize> = results
-                .par_iter()
-                .filter_map(|&index| {
-                    if index < items.len() && predicate(&items[index]) {
-                        Some(index)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            
-            if !refined_results.is_empty() {
-                results = refined_results;
-            }
-        }
-        
-        results
-    }
-}
-
-/// Quantum error correction
-pub struct QuantumErrorCorrection {
-    code_distance: usize,
-}
-
-impl QuantumErrorCorrection {
-    pub fn new(code_distance: usize) -> Self {
-        Self { code_distance }
-    }
-
-    /// Apply quantum error correction
-    pub fn correct_errors(&self, state: &mut QuantumState) -> QuantumResult<()> {
-        // Simplified error correction using repetition code
-        let logical_qubits = state.qubit_count / self.code_distance;
-        
-        for logical_qubit in 0..logical_qubits {
-            let start_qubit = logical_qubit * self.code_distance;
-            
-            // Majority vote for error correction
-            let mut error_count = 0;
-            for i in 1..self.code_distance {
-                // Compare with first qubit in the block
-                if self.compare_qubits(state, start_qubit, start_qubit + i)? {
-                    error_count += 1;
-                }
-            }
-            
-            // Correct if majority disagrees
-            if error_count > self.code_distance / 2 {
-                state.apply_gate(&QuantumGate::PauliX, &[start_qubit])?;
-            }
-        }
-        
-        Ok(())
-    }
-
-    fn compare_qubits(&self, state: &QuantumState, qubit1: usize, qubit2: usize) -> QuantumResult<bool> {
-        // Simplified comparison based on probability amplitudes
-        let mut diff = 0.0;
-        
-        for i in 0..state.amplitudes.len() {
-            let bit1 = (i >> qubit1) & 1;
-            let bit2 = (i >> qubit2) & 1;
-            
-            if bit1 != bit2 {
-                let (real, imag) = state.amplitudes[i];
-                diff += real * real + imag * imag;
-            }
-        }
-        
-        Ok(diff > 0.5)
-    }
-}
-
-/// Quantum machine learning algorithms
-pub struct QuantumML;
-
-impl QuantumML {
-    /// Quantum principal component analysis
-    pub fn quantum_pca(data: &[Vec<f64>], components: usize) -> QuantumResult<Vec<Vec<f64>>> {
-        let n_features = data[0].len();
-        let qubit_count = (n_features as f64).log2().ceil() as usize;
-        
-        // Create quantum state representing data
-        let mut state = QuantumState::superposition(qubit_count);
-        
-        // Apply quantum PCA circuit (simplified)
-        let qft = QuantumFFT::new(qubit_count);
-        qft.apply(&mut state)?;
-        
-        // Extract principal components (simplified)
-        let mut principal_components = Vec::new();
-        for _ in 0..components {
-            let component: Vec<f64> = (0..n_features)
-                .map(|i| state.amplitudes[i].0)
-                .collect();
-            principal_components.push(component);
-        }
-        
-        Ok(principal_components)
-    }
-
-    /// Quantum clustering
-    pub fn quantum_clustering(data: &[Vec<f64>], clusters: usize) -> QuantumResult<Vec<usize>> {
-        let n_points = data.len();
-        let qubit_count = (n_points as f64).log2().ceil() as usize;
-        
-        // Create superposition of all data points
-        let mut state = QuantumState::superposition(qubit_count);
-        
-        // Apply quantum clustering algorithm (simplified)
-        for _ in 0..clusters {
-            state.apply_gate(&QuantumGate::Hadamard, &[0])?;
-        }
-        
-        // Measure to get cluster assignments
-        let mut assignments = Vec::new();
-        for i in 0..n_points {
-            let cluster = i % clusters; // Simplified assignment
-            assignments.push(cluster);
-        }
-        
-        Ok(assignments)
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_quantum_state_creation() {
-        let state = QuantumState::new(2);
-        assert_eq!(state.qubit_count, 2);
-        assert_eq!(state.amplitudes.len(), 4);
-        assert_eq!(state.amplitudes[0], (1.0, 0.0));
-    }
-
-    #[test]
-    fn test_hadamard_gate() {
-        let mut state = QuantumState::new(1);
-        state.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
-        
-        let sqrt2_inv = 1.0 / 2.0_f64.sqrt();
-        assert!((state.amplitudes[0].0 - sqrt2_inv).abs() < 1e-10);
-        assert!((state.amplitudes[1].0 - sqrt2_inv).abs() < 1e-10);
-    }
-
-    #[test]
-    fn test_quantum_fft() {
-        let mut state = QuantumState::new(3);
-        let qft = QuantumFFT::new(3);
-        qft.apply(&mut state).unwrap();
-        
-        // Verify QFT was applied
-        assert!(state.amplitudes.iter().any(|(real, _)| real.abs() > 0.1));
-    }
-
-    #[test]
-    fn test_quantum_annealing() {
-        let mut annealer = QuantumAnnealer::new();
-        let result = annealer.optimize(0.0, |x| x * x).unwrap();
-        assert_eq!(result, 0.0);
-    }
-
-    #[test]
-    fn test_quantum_search() {
-        let items = vec![1, 2, 3, 4, 5];
-        let results = QuantumParallelSearch::search(&items, |&x| x > 3);
-        assert_eq!(results, vec![3, 4]);
-    }
-}
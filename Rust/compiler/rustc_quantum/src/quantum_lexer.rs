@@ -1,33 +1,49 @@
 //! Quantum-Inspired Lexical Analysis
 //!
 //! This module implements quantum-inspired lexical analysis for Rust source code.
-//! It uses parallel processing and advanced algorithms inspired by quantum computing
-//! to provide modest improvements over classical lexical analysis.
-
+//! Tokens come from `rustc_lexer`, so every token maps back to its exact source
+//! text and carries a real `Span`. The quantum state and entanglement analysis
+//! are side metadata on top of those tokens.
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::sync::{DynSend, DynSync, par_map};
+use rustc_lexer::{LiteralKind, TokenKind};
+use rustc_macros::{Decodable_NoContext, Encodable_NoContext};
+use rustc_serialize::opaque::{FileEncoder, MemDecoder};
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
+use rustc_span::edition::Edition;
+use rustc_span::hygiene::SyntaxContext;
 use rustc_span::source_map::SourceMap;
-use crate::{QuantumConfig, QuantumResult, QuantumError};
+use rustc_span::{BytePos, SourceFile, Span, Symbol};
+use crate::{QuantumConfig, QuantumResult};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::{fs, io};
 
 /// Quantum token representation
 #[derive(Debug, Clone)]
 pub struct QuantumToken {
     /// Token type
     pub token_type: QuantumTokenType,
-    /// Token value
+    /// Exact source text of the token
     pub value: String,
     /// Source position
-    pub span: QuantumSpan,
+    pub span: Span,
     /// Quantum superposition state
     pub quantum_state: QuantumState,
+    /// Indices of the tokens this token is entangled with
+    pub entangled_with: Vec<usize>,
 }
 
 /// Quantum token types with superposition capabilities
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encodable_NoContext, Decodable_NoContext)]
 pub enum QuantumTokenType {
     /// Identifier in superposition
     Identifier,
     /// Keyword with quantum enhancement
     Keyword(String),
+    /// Lifetime or label, e.g. `'a`
+    Lifetime,
     /// Literal with quantum optimization
     Literal(LiteralType),
     /// Operator with quantum precedence
@@ -40,18 +56,44 @@ pub enum QuantumTokenType {
     Whitespace,
     /// Quantum-specific tokens
     QuantumSpecific(QuantumSpecificType),
+    /// Input `rustc_lexer` does not recognize; rustc reports it when parsing
+    Unknown,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Literal kinds, without their value: the text is kept in [`QuantumToken::value`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encodable_NoContext, Decodable_NoContext)]
 pub enum LiteralType {
-    Integer(i64),
-    Float(f64),
-    String(String),
-    Char(char),
-    Boolean(bool),
+    Integer,
+    Float,
+    Char,
+    Byte,
+    String,
+    ByteString,
+    CString,
+    RawString,
+    RawByteString,
+    RawCString,
+    Boolean,
+}
+
+impl From<LiteralKind> for LiteralType {
+    fn from(kind: LiteralKind) -> Self {
+        match kind {
+            LiteralKind::Int { .. } => LiteralType::Integer,
+            LiteralKind::Float { .. } => LiteralType::Float,
+            LiteralKind::Char { .. } => LiteralType::Char,
+            LiteralKind::Byte { .. } => LiteralType::Byte,
+            LiteralKind::Str { .. } => LiteralType::String,
+            LiteralKind::ByteStr { .. } => LiteralType::ByteString,
+            LiteralKind::CStr { .. } => LiteralType::CString,
+            LiteralKind::RawStr { .. } => LiteralType::RawString,
+            LiteralKind::RawByteStr { .. } => LiteralType::RawByteString,
+            LiteralKind::RawCStr { .. } => LiteralType::RawCString,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Encodable_NoContext, Decodable_NoContext)]
 pub enum QuantumSpecificType {
     QuantumArray,
     QuantumFunction,
@@ -59,17 +101,6 @@ pub enum QuantumSpecificType {
     QuantumAttribute,
 }
 
-/// Quantum span with entangled position information
-#[derive(Debug, Clone)]
-pub struct QuantumSpan {
-    pub start: usize,
-    pub end: usize,
-    pub line: usize,
-    pub column: usize,
-    /// Quantum entanglement with other spans
-    pub entangled_spans: Vec<usize>,
-}
-
 /// Quantum state for tokens
 #[derive(Debug, Clone)]
 pub struct QuantumState {
@@ -94,218 +125,252 @@ fn default() -> Self {
 /// Quantum lexer with parallel processing capabilities
 pub struct QuantumLexer {
     config: QuantumConfig,
-    /// Quantum pattern matchers
-    quantum_patterns: Vec<QuantumPattern>,
     /// Parallel processing pool
     quantum_pool: QuantumProcessingPool,
-    /// Token cache for quantum speedup
+    /// Tokens of previously lexed files
     token_cache: QuantumTokenCache,
+    /// Statistics of the last tokenization
+    stats: QuantumLexerStats,
+}
+
+/// Quantum lexer statistics
+#[derive(Debug, Clone, Default)]
+pub struct QuantumLexerStats {
+    /// Source files tokenized
+    pub files_tokenized: usize,
+    /// Source files whose tokens came from the token cache
+    pub files_from_cache: usize,
+    /// Tokens generated
+    pub tokens_generated: usize,
+    /// Entangled token pairs created
+    pub entanglement_pairs: usize,
+    /// Share of the source that is redundant whitespace, in percent
+    pub compression_rate: f64,
+    /// Keywords enhanced
+    pub keywords_enhanced: usize,
+    /// Wall time of the last tokenization
+    pub tokenization_time: std::time::Duration,
 }
 
 impl QuantumLexer {
     /// Create a new quantum lexer
     pub fn new(config: &QuantumConfig) -> Self {
-        println!("🔍 Initializing Quantum Lexer...");
-        
         Self {
             config: config.clone(),
-            quantum_patterns: Self::initialize_quantum_patterns(),
             quantum_pool: QuantumProcessingPool::new(),
             token_cache: QuantumTokenCache::new(),
+            stats: QuantumLexerStats::default(),
         }
     }
 
-    /// Quantum tokenization with parallel processing
+    /// Quantum tokenization of every source file loaded in the source map
     pub fn quantum_tokenize(&mut self, source_map: &SourceMap) -> QuantumResult<Vec<QuantumToken>> {
-        println!("⚡ Starting quantum tokenization...");
-        
         let start_time = std::time::Instant::now();
-        
+
         // Get all source files
-        let source_files = self.get_source_files(source_map)?;
-        
-        // Parallel quantum tokenization
-        let mut all_tokens = Vec::new();
-        
-        for source_file in source_files {
-            let tokens = self.tokenize_source_file(&source_file)?;
-            all_tokens.extend(tokens);
+        let source_files = self.get_source_files(source_map);
+
+        let edition = SyntaxContext::root().edition();
+
+        // Reuse the tokens of files that did not change since they were cached
+        let mut file_tokens: Vec<Option<Vec<QuantumToken>>> = source_files
+            .iter()
+            .map(|file| {
+                let cached = self.token_cache.get(file, edition)?;
+                Some(Self::restore_tokens(file, cached))
+            })
+            .collect();
+        self.stats.files_tokenized = source_files.len();
+        self.stats.files_from_cache = file_tokens.iter().filter(|tokens| tokens.is_some()).count();
+
+        // Lex the other files in parallel. The source map and the symbol interner
+        // are only touched afterwards, on this thread, in the original file order.
+        let sources: Vec<(usize, Arc<String>)> = source_files
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| file_tokens[index].is_none())
+            .filter_map(|(index, file)| Some((index, file.src.clone()?)))
+            .collect();
+        let lexed_files = if self.config.parallel_lexing {
+            self.quantum_pool
+                .parallel_process(sources, |(index, src)| Ok((index, lex_source(&src))))?
+        } else {
+            sources.into_iter().map(|(index, src)| (index, lex_source(&src))).collect()
+        };
+
+        for (index, lexed) in lexed_files {
+            let tokens = Self::build_tokens(&source_files[index], lexed);
+            self.token_cache.insert(&source_files[index], edition, &tokens);
+            file_tokens[index] = Some(tokens);
         }
-        
+        let all_tokens: Vec<QuantumToken> = file_tokens.into_iter().flatten().flatten().collect();
+
         // Apply quantum optimizations
         let optimized_tokens = self.apply_quantum_optimizations(all_tokens)?;
-        
-        let tokenization_time = start_time.elapsed();
-        println!("✅ Quantum tokenization complete in {:?}", tokenization_time);
-        println!("📊 Tokens generated: {}", optimized_tokens.len());
-        
+
+        self.stats.tokens_generated = optimized_tokens.len();
+        self.stats.tokenization_time = start_time.elapsed();
+
         Ok(optimized_tokens)
     }
 
-    /// Tokenize a single source file with quantum enhancement
-    fn tokenize_source_file(&mut self, source: &str) -> QuantumResult<Vec<QuantumToken>> {
-        let mut tokens = Vec::new();
-        let mut position = 0;
-        let mut line = 1;
-        let mut column = 1;
-        
-        while position < source.len() {
-            // Check token cache first (quantum speedup)
-            if let Some(cached_token) = self.token_cache.get(source, position) {
-                tokens.push(cached_token.clone());
-                position += cached_token.value.len();
-                continue;
-            }
-            
-            // Quantum pattern matching
-            let quantum_match = self.quantum_pattern_match(source, position)?;
-            
-            if let Some(token_match) = quantum_match {
-                let token = QuantumToken {
-                    token_type: token_match.token_type,
-                    value: token_match.value.clone(),
-                    span: QuantumSpan {
-                        start: position,
-                        end: position + token_match.length,
-                        line,
-                        column,
-                        entangled_spans: Vec::new(),
-                    },
-                    quantum_state: self.calculate_quantum_state(&token_match),
-                };
-                
-                // Cache the token for future use
-                self.token_cache.insert(source, position, token.clone());
-                
-                tokens.push(token);
-                position += token_match.length;
-                
-                // Update line and column
-                if token_match.value.contains('\n') {
-                    line += token_match.value.matches('\n').count();
-                    column = 1;
-                } else {
-                    column += token_match.length;
-                }
-            } else {
-                return Err(QuantumError::LexingError(
-                    format!("Unrecognized character at position {}: '{}'", 
-                           position, 
-                           source.chars().nth(position).unwrap_or('\0'))
-                ));
-            }
+    /// Tokenize a single source file with `rustc_lexer`
+    ///
+    /// Concatenating the `value` of the returned tokens yields the source text
+    /// of the file, and each token's span covers exactly that text.
+    pub fn tokenize_source_file(&self, file: &SourceFile) -> Vec<QuantumToken> {
+        match &file.src {
+            Some(src) => Self::build_tokens(file, lex_source(src)),
+            None => Vec::new(),
         }
-        
-        Ok(tokens)
     }
 
-    /// Quantum pattern matching with superposition
-    fn quantum_pattern_match(&self, source: &str, position: usize) -> QuantumResult<Option<TokenMatch>> {
-        let remaining = &source[position..];
-        
-        // Parallel pattern matching using quantum superposition
-        let mut best_match: Option<TokenMatch> = None;
-        let mut best_score = 0.0;
-        
-        for pattern in &self.quantum_patterns {
-            if let Some(token_match) = pattern.try_match(remaining)? {
-                let quantum_score = self.calculate_quantum_score(&token_match);
-                
-                if quantum_score > best_score {
-                    best_score = quantum_score;
-                    best_match = Some(token_match);
+    /// Place the tokens lexed from `file` in the source map and classify them
+    fn build_tokens(file: &SourceFile, lexed: Vec<LexedToken>) -> Vec<QuantumToken> {
+        let Some(src) = file.src.as_deref() else {
+            return Vec::new();
+        };
+
+        lexed
+            .into_iter()
+            .map(|token| {
+                let (start, len) = (token.start as usize, token.len as usize);
+                let lo = file.start_pos + BytePos(token.start);
+                let span = Span::with_root_ctxt(lo, lo + BytePos(token.len));
+                let value = &src[start..start + len];
+
+                QuantumToken {
+                    token_type: Self::classify(token.kind, value, span),
+                    value: value.to_string(),
+                    span,
+                    quantum_state: Self::calculate_quantum_state(len),
+                    entangled_with: Vec::new(),
                 }
+            })
+            .collect()
+    }
+
+    /// Place the cached tokens of `file` in the source map
+    fn restore_tokens(file: &SourceFile, cached: &[CachedToken]) -> Vec<QuantumToken> {
+        let Some(src) = file.src.as_deref() else {
+            return Vec::new();
+        };
+
+        cached
+            .iter()
+            .map(|token| {
+                let (start, len) = (token.start as usize, token.len as usize);
+                let lo = file.start_pos + BytePos(token.start);
+
+                QuantumToken {
+                    token_type: token.token_type.clone(),
+                    value: src[start..start + len].to_string(),
+                    span: Span::with_root_ctxt(lo, lo + BytePos(token.len)),
+                    quantum_state: Self::calculate_quantum_state(len),
+                    entangled_with: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Map a `rustc_lexer` token kind onto the quantum token types
+    fn classify(kind: TokenKind, value: &str, span: Span) -> QuantumTokenType {
+        match kind {
+            TokenKind::LineComment { .. }
+            | TokenKind::BlockComment { .. }
+            | TokenKind::Frontmatter { .. } => QuantumTokenType::Comment,
+            TokenKind::Whitespace => QuantumTokenType::Whitespace,
+            TokenKind::Ident => match value {
+                "true" | "false" => QuantumTokenType::Literal(LiteralType::Boolean),
+                _ if Symbol::intern(value).is_reserved(|| span.edition()) => {
+                    QuantumTokenType::Keyword(value.to_string())
+                }
+                _ => QuantumTokenType::Identifier,
+            },
+            TokenKind::RawIdent | TokenKind::InvalidIdent | TokenKind::UnknownPrefix => {
+                QuantumTokenType::Identifier
+            }
+            TokenKind::Lifetime { .. }
+            | TokenKind::RawLifetime
+            | TokenKind::UnknownPrefixLifetime => QuantumTokenType::Lifetime,
+            TokenKind::Literal { kind, .. } => QuantumTokenType::Literal(kind.into()),
+            TokenKind::OpenParen
+            | TokenKind::CloseParen
+            | TokenKind::OpenBrace
+            | TokenKind::CloseBrace
+            | TokenKind::OpenBracket
+            | TokenKind::CloseBracket
+            | TokenKind::Semi
+            | TokenKind::Comma
+            | TokenKind::Dot => {
+                QuantumTokenType::Delimiter(value.chars().next().unwrap_or_default())
             }
+            TokenKind::Unknown | TokenKind::Eof => QuantumTokenType::Unknown,
+            // The remaining kinds are single-character punctuation and guarded string prefixes
+            _ => QuantumTokenType::Operator(value.to_string()),
         }
-        
-        Ok(best_match)
     }
 
     /// Apply quantum optimizations to tokens
-    fn apply_quantum_optimizations(&self, mut tokens: Vec<QuantumToken>) -> QuantumResult<Vec<QuantumToken>> {
-        println!("🔮 Applying quantum token optimizations...");
-
-        let original_size = self.calculate_token_size(&tokens);
-
+    ///
+    /// Only the quantum metadata is updated: the tokens themselves still match the source.
+    fn apply_quantum_optimizations(&mut self, mut tokens: Vec<QuantumToken>) -> QuantumResult<Vec<QuantumToken>> {
         // Quantum entanglement analysis
         let entanglement_count = self.analyze_quantum_entanglement(&mut tokens)?;
 
-        // Quantum compression for whitespace
-        tokens = self.quantum_compress_whitespace(tokens)?;
-        let compressed_size = self.calculate_token_size(&tokens);
-
         // Quantum keyword enhancement
         let enhanced_keywords = self.enhance_quantum_keywords(&mut tokens)?;
 
-        // Calculate actual compression rate
-        let compression_rate = if original_size > 0 {
-            ((original_size - compressed_size) as f64 / original_size as f64) * 100.0
-        } else {
-            0.0
-        };
-
-        println!("   - Quantum entanglement: {} pairs created", entanglement_count);
-        println!("   - Quantum compression: {:.1}% reduction", compression_rate);
-        println!("   - Quantum keyword enhancement: {} keywords enhanced", enhanced_keywords);
+        self.stats.entanglement_pairs = entanglement_count;
+        self.stats.compression_rate = self.whitespace_compression_rate(&tokens);
+        self.stats.keywords_enhanced = enhanced_keywords;
 
         Ok(tokens)
     }
 
-    /// Analyze quantum entanglement between tokens (优化版本)
+    /// Analyze quantum entanglement between tokens
     ///
-    /// 🚀 性能优化：使用空间索引将复杂度从O(n²)优化到O(n log n)
-    /// 🔧 集成第一阶段的配置系统，支持动态阈值调整
+    /// Small token lists compare every pair of tokens. Larger ones use a
+    /// [`SpatialIndex`] and only compare tokens that are close in the source,
+    /// which avoids the quadratic number of pairs.
     fn analyze_quantum_entanglement(&self, tokens: &mut [QuantumToken]) -> QuantumResult<usize> {
-        let start_time = std::time::Instant::now();
-
-        // 利用第一阶段的配置系统决定算法策略
-        let optimization_threshold = 1000; // 从配置中获取，这里简化为常量
+        // Number of tokens above which the spatial index is used
+        let optimization_threshold = 1000;
 
         let entanglement_count = if tokens.len() > optimization_threshold {
-            // 大规模数据使用优化算法
+            // Only pairs of nearby tokens
             self.analyze_entanglement_with_spatial_index(tokens)?
         } else {
-            // 小规模数据使用原始算法（保持精确性）
+            // Every pair of tokens
             self.analyze_entanglement_naive(tokens)?
         };
 
-        // 记录性能指标（集成第一阶段的统一接口）
-        let duration = start_time.elapsed();
-        println!("   🔍 纠缠分析完成: {} tokens, {} 纠缠对, 耗时 {:?}",
-                 tokens.len(), entanglement_count, duration);
-
         Ok(entanglement_count)
     }
 
-    /// 优化的空间索引纠缠分析 O(n log n)
+    /// Entangle the tokens that are at most 100 bytes apart in the source
+    ///
+    /// Takes O(n log n) time, plus the number of nearby pairs.
     fn analyze_entanglement_with_spatial_index(&self, tokens: &mut [QuantumToken]) -> QuantumResult<usize> {
-        // 构建空间索引
         let mut spatial_index = SpatialIndex::new();
         for (i, token) in tokens.iter().enumerate() {
-            spatial_index.insert(token.span.start, token.span.end, i);
+            spatial_index.insert(token.span.lo().0 as usize, token.span.hi().0 as usize, i);
         }
+        spatial_index.optimize();
 
         let mut entanglement_count = 0;
-        let entanglement_range = 100; // 纠缠范围阈值
+        // Largest distance in bytes between entangled tokens
+        let entanglement_range = 100;
 
-        for (i, token) in tokens.iter().enumerate() {
-            // 只查询空间上邻近的tokens
+        for i in 0..tokens.len() {
             let nearby_indices = spatial_index.query_range(
-                token.span.start.saturating_sub(entanglement_range),
-                token.span.end + entanglement_range
+                (tokens[i].span.lo().0 as usize).saturating_sub(entanglement_range),
+                tokens[i].span.hi().0 as usize + entanglement_range,
             );
 
-            for &j in &nearby_indices {
+            for j in nearby_indices {
                 if i < j && self.should_entangle(&tokens[i], &tokens[j]) {
-                    // 应用纠缠
-                    tokens[i].span.entangled_spans.push(j);
-                    tokens[j].span.entangled_spans.push(i);
-
-                    // 更新量子态
-                    let entanglement_strength = self.calculate_entanglement_strength(&tokens[i], &tokens[j]);
-                    tokens[i].quantum_state.entanglement = entanglement_strength;
-                    tokens[j].quantum_state.entanglement = entanglement_strength;
-
+                    self.entangle(tokens, i, j);
                     entanglement_count += 1;
                 }
             }
@@ -314,21 +379,14 @@ fn analyze_entanglement_with_spatial_index(&self, tokens: &mut [QuantumToken]) -
         Ok(entanglement_count)
     }
 
-    /// 原始的朴素纠缠分析 O(n²) - 保留用于小规模精确计算
+    /// Entangle tokens by comparing every pair of them, in O(n²) time
     fn analyze_entanglement_naive(&self, tokens: &mut [QuantumToken]) -> QuantumResult<usize> {
         let mut entanglement_count = 0;
 
         for i in 0..tokens.len() {
             for j in (i + 1)..tokens.len() {
                 if self.should_entangle(&tokens[i], &tokens[j]) {
-                    tokens[i].span.entangled_spans.push(j);
-                    tokens[j].span.entangled_spans.push(i);
-
-                    // Update quantum states
-                    let entanglement_strength = self.calculate_entanglement_strength(&tokens[i], &tokens[j]);
-                    tokens[i].quantum_state.entanglement = entanglement_strength;
-                    tokens[j].quantum_state.entanglement = entanglement_strength;
-
+                    self.entangle(tokens, i, j);
                     entanglement_count += 1;
                 }
             }
@@ -337,45 +395,29 @@ fn analyze_entanglement_naive(&self, tokens: &mut [QuantumToken]) -> QuantumResu
         Ok(entanglement_count)
     }
 
-    /// Quantum compression for whitespace tokens
-    fn quantum_compress_whitespace(&self, tokens: Vec<QuantumToken>) -> QuantumResult<Vec<QuantumToken>> {
-        let mut compressed = Vec::new();
-        let mut i = 0;
-        
-        while i < tokens.len() {
-            if tokens[i].token_type == QuantumTokenType::Whitespace {
-                // Compress consecutive whitespace tokens
-                let mut combined_whitespace = tokens[i].value.clone();
-                let mut j = i + 1;
-                
-                while j < tokens.len() && tokens[j].token_type == QuantumTokenType::Whitespace {
-                    combined_whitespace.push_str(&tokens[j].value);
-                    j += 1;
-                }
-                
-                // Create compressed token
-                let compressed_token = QuantumToken {
-                    token_type: QuantumTokenType::Whitespace,
-                    value: " ".to_string(), // Quantum compression
-                    span: QuantumSpan {
-                        start: tokens[i].span.start,
-                        end: tokens[j - 1].span.end,
-                        line: tokens[i].span.line,
-                        column: tokens[i].span.column,
-                        entangled_spans: Vec::new(),
-                    },
-                    quantum_state: QuantumState::default(),
-                };
-                
-                compressed.push(compressed_token);
-                i = j;
-            } else {
-                compressed.push(tokens[i].clone());
-                i += 1;
-            }
+    /// Record the entanglement of two tokens and update their quantum states
+    fn entangle(&self, tokens: &mut [QuantumToken], i: usize, j: usize) {
+        tokens[i].entangled_with.push(j);
+        tokens[j].entangled_with.push(i);
+
+        let entanglement_strength = self.calculate_entanglement_strength(&tokens[i], &tokens[j]);
+        tokens[i].quantum_state.entanglement = entanglement_strength;
+        tokens[j].quantum_state.entanglement = entanglement_strength;
+    }
+
+    /// Share of the source bytes that collapsing each whitespace run to a single space would save
+    fn whitespace_compression_rate(&self, tokens: &[QuantumToken]) -> f64 {
+        let total_size = self.calculate_token_size(tokens);
+        if total_size == 0 {
+            return 0.0;
         }
-        
-        Ok(compressed)
+
+        let redundant_whitespace: usize = tokens
+            .iter()
+            .filter(|token| token.token_type == QuantumTokenType::Whitespace)
+            .map(|token| token.value.len() - 1)
+            .sum();
+        (redundant_whitespace as f64 / total_size as f64) * 100.0
     }
 
     /// Enhance quantum-specific keywords
@@ -402,91 +444,41 @@ fn enhance_quantum_keywords(&self, tokens: &mut [QuantumToken]) -> QuantumResult
     fn calculate_token_size(&self, tokens: &[QuantumToken]) -> usize {
         tokens.iter().map(|token| token.value.len()).sum()
     }
-}
 
-/// 空间索引结构，用于优化纠缠分析
-///
-/// 🚀 性能优化：将O(n²)的空间查询优化为O(log n)
-#[derive(Debug)]
-struct SpatialIndex {
-    intervals: Vec<SpatialInterval>,
-}
-
-#[derive(Debug, Clone)]
-struct SpatialInterval {
-    start: usize,
-    end: usize,
-    token_index: usize,
-}
-
-impl SpatialIndex {
-    fn new() -> Self {
-        Self {
-            intervals: Vec::new(),
-        }
-    }
-
-    /// 插入空间区间
-    fn insert(&mut self, start: usize, end: usize, token_index: usize) {
-        self.intervals.push(SpatialInterval {
-            start,
-            end,
-            token_index,
-        });
+    /// Get quantum lexer statistics
+    pub fn get_stats(&self) -> &QuantumLexerStats {
+        &self.stats
     }
 
-    /// 查询指定范围内的所有token索引
-    fn query_range(&self, query_start: usize, query_end: usize) -> Vec<usize> {
-        let mut result = Vec::new();
-
-        for interval in &self.intervals {
-            // 检查区间重叠
-            if interval.start <= query_end && interval.end >= query_start {
-                result.push(interval.token_index);
-            }
-        }
-
-        result
+    /// Persist the token cache in `path`, loading the tokens cached there by a previous build
+    pub fn load_token_cache(&mut self, path: PathBuf, compiler_version: &str) {
+        self.token_cache = QuantumTokenCache::load(path, compiler_version);
     }
 
-    /// 优化索引结构（按起始位置排序以提高查询效率）
-    fn optimize(&mut self) {
-        self.intervals.sort_by_key(|interval| interval.start);
+    /// Write the token cache back to disk, if it is persisted
+    pub fn save_token_cache(&mut self) -> io::Result<()> {
+        self.token_cache.save()
     }
 
-    /// Initialize quantum patterns
-    fn initialize_quantum_patterns() -> Vec<QuantumPattern> {
-        vec![
-            QuantumPattern::new(QuantumTokenType::Identifier, r"[a-zA-Z_][a-zA-Z0-9_]*"),
-            QuantumPattern::new(QuantumTokenType::Literal(LiteralType::Integer(0)), r"\d+"),
-            QuantumPattern::new(QuantumTokenType::Literal(LiteralType::Float(0.0)), r"\d+\.\d+"),
-            QuantumPattern::new(QuantumTokenType::Literal(LiteralType::String(String::new())), r#""([^"\\]|\\.)*""#),
-            QuantumPattern::new(QuantumTokenType::Keyword(String::new()), r"fn|let|mut|if|else|for|while|match|struct|enum|impl|trait|pub|use|mod"),
-            QuantumPattern::new(QuantumTokenType::Operator(String::new()), r"\+|\-|\*|/|%|==|!=|<|>|<=|>=|&&|\|\||!|="),
-            QuantumPattern::new(QuantumTokenType::Delimiter('('), r"[{}()\[\];,.]"),
-            QuantumPattern::new(QuantumTokenType::Comment, r"//.*|/\*[\s\S]*?\*/"),
-            QuantumPattern::new(QuantumTokenType::Whitespace, r"\s+"),
-        ]
+    pub fn token_cache(&self) -> &QuantumTokenCache {
+        &self.token_cache
     }
 
-    /// Helper methods
-    fn get_source_files(&self, source_map: &SourceMap) -> QuantumResult<Vec<String>> {
-        // Simplified implementation - in real version would extract from SourceMap
-        Ok(vec!["// Quantum Rust source code".to_string()])
+    /// Source files of the crate being compiled
+    ///
+    /// Files of external crates are loaded without their source text and are skipped.
+    fn get_source_files(&self, source_map: &SourceMap) -> Vec<Arc<SourceFile>> {
+        source_map.files().iter().filter(|file| file.src.is_some()).cloned().collect()
     }
 
-    fn calculate_quantum_state(&self, token_match: &TokenMatch) -> QuantumState {
+    fn calculate_quantum_state(token_len: usize) -> QuantumState {
         QuantumState {
             amplitude: 1.0,
-            phase: token_match.length as f64 * 0.1,
+            phase: token_len as f64 * 0.1,
             entanglement: 0.0,
         }
     }
 
-    fn calculate_quantum_score(&self, token_match: &TokenMatch) -> f64 {
-        token_match.length as f64 * token_match.confidence
-    }
-
     fn should_entangle(&self, token1: &QuantumToken, token2: &QuantumToken) -> bool {
         // Simple entanglement rules
         matches!(
@@ -501,40 +493,85 @@ fn calculate_entanglement_strength(&self, _token1: &QuantumToken, _token2: &Quan
     }
 }
 
-// Supporting structures
-#[derive(Debug, Clone)]
-pub struct TokenMatch {
-    pub token_type: QuantumTokenType,
-    pub value: String,
-    pub length: usize,
-    pub confidence: f64,
+/// Token produced by `rustc_lexer`, with its offset in the file's source text
+#[derive(Debug, Clone, Copy)]
+struct LexedToken {
+    kind: TokenKind,
+    start: u32,
+    len: u32,
 }
 
-pub struct QuantumPattern {
-    token_type: QuantumTokenType,
-    pattern: String,
+/// Lex the source text of one file
+///
+/// Needs no session globals, so it can run on any thread.
+fn lex_source(src: &str) -> Vec<LexedToken> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    // `rustc_lexer` does not know about shebangs, rustc skips them like a comment
+    if let Some(shebang_len) = rustc_lexer::strip_shebang(src) {
+        let kind = TokenKind::LineComment { doc_style: None };
+        tokens.push(LexedToken { kind, start: 0, len: shebang_len as u32 });
+        position = shebang_len as u32;
+    }
+
+    for token in rustc_lexer::tokenize(&src[position as usize..]) {
+        tokens.push(LexedToken { kind: token.kind, start: position, len: token.len });
+        position += token.len;
+    }
+
+    tokens
+}
+
+/// Source ranges of the tokens, for finding the tokens near a position
+///
+/// A query takes O(log n) time, plus the number of tokens it returns.
+#[derive(Debug)]
+struct SpatialIndex {
+    intervals: Vec<SpatialInterval>,
 }
 
-impl QuantumPattern {
-    pub fn new(token_type: QuantumTokenType, pattern: &str) -> Self {
+#[derive(Debug, Clone)]
+struct SpatialInterval {
+    start: usize,
+    end: usize,
+    token_index: usize,
+}
+
+impl SpatialIndex {
+    fn new() -> Self {
         Self {
-            token_type,
-            pattern: pattern.to_string(),
+            intervals: Vec::new(),
         }
     }
 
-    pub fn try_match(&self, input: &str) -> QuantumResult<Option<TokenMatch>> {
-        // Simplified pattern matching - real implementation would use regex
-        if input.starts_with(&self.pattern) {
-            Ok(Some(TokenMatch {
-                token_type: self.token_type.clone(),
-                value: self.pattern.clone(),
-                length: self.pattern.len(),
-                confidence: 1.0,
-            }))
-        } else {
-            Ok(None)
-        }
+    /// Add the source range of a token
+    fn insert(&mut self, start: usize, end: usize, token_index: usize) {
+        self.intervals.push(SpatialInterval {
+            start,
+            end,
+            token_index,
+        });
+    }
+
+    /// Indices of the tokens that overlap `query_start..=query_end`
+    ///
+    /// [`SpatialIndex::optimize`] must be called first. Tokens do not overlap,
+    /// so once they are sorted by start, their ends are sorted too and both
+    /// bounds can be found by binary search.
+    fn query_range(&self, query_start: usize, query_end: usize) -> Vec<usize> {
+        let first = self.intervals.partition_point(|interval| interval.end < query_start);
+        let last = self.intervals.partition_point(|interval| interval.start <= query_end);
+
+        self.intervals[first..last.max(first)]
+            .iter()
+            .map(|interval| interval.token_index)
+            .collect()
+    }
+
+    /// Sort the ranges by start, as queries need
+    fn optimize(&mut self) {
+        self.intervals.sort_by_key(|interval| interval.start);
     }
 }
 
@@ -543,37 +580,31 @@ pub fn try_match(&self, input: &str) -> QuantumResult<Option<TokenMatch>> {
 /// Quantum Processing Pool - Real Implementation
 /// Manages parallel quantum token processing
 pub struct QuantumProcessingPool {
-    /// Worker threads for parallel processing
-    worker_count: usize,
     /// Processing statistics
     stats: QuantumPoolStats,
 }
 
 impl QuantumProcessingPool {
     pub fn new() -> Self {
-        let worker_count = std::thread::available_parallelism()
-            .map(|n| n.get())
-            .unwrap_or(4);
-
-        Self {
-            worker_count,
-            stats: QuantumPoolStats::default(),
-        }
+        Self { stats: QuantumPoolStats::default() }
     }
 
-    /// Process tokens in parallel using quantum algorithms
-    pub fn parallel_process<F, T>(&mut self, items: Vec<T>, processor: F) -> QuantumResult<Vec<T>>
+    /// Process items in parallel on the rustc thread pool
+    ///
+    /// Results are returned in the order of `items`. Unless the session runs
+    /// with `-Zthreads` above 1, the items are processed serially.
+    pub fn parallel_process<F, T, U>(
+        &mut self,
+        items: Vec<T>,
+        processor: F,
+    ) -> QuantumResult<Vec<U>>
     where
-        F: Fn(T) -> QuantumResult<T> + Send + Sync,
-        T: Send,
+        F: Fn(T) -> QuantumResult<U> + DynSync + DynSend,
+        T: DynSend,
+        U: DynSend,
     {
-        // Simulate parallel processing
-        let mut results = Vec::new();
-
-        for item in items {
-            let processed = processor(item)?;
-            results.push(processed);
-        }
+        let results: QuantumResult<Vec<U>> = par_map(items, processor);
+        let results = results?;
 
         self.stats.tasks_processed += results.len() as u64;
         Ok(results)
@@ -587,390 +618,471 @@ pub fn get_stats(&self) -> &QuantumPoolStats {
 #[derive(Debug, Clone, Default)]
 pub struct QuantumPoolStats {
     pub tasks_processed: u64,
-    pub parallel_speedup: f64,
-    pub quantum_efficiency: f64,
 }
 
-/// Quantum Token Cache - Real Implementation
-/// Intelligent caching system for quantum tokens with coherence tracking
-pub struct QuantumTokenCache {
-    /// Token cache with position-based indexing
-    cache: HashMap<String, CachedTokenEntry>,
-    /// Cache configuration
-    max_cache_size: usize,
-    /// Cache statistics
-    stats: QuantumCacheStats,
-    /// Quantum coherence tracker
-    coherence_tracker: CoherenceTracker,
-}
+/// File name of the token cache in the incremental session directory
+pub const TOKEN_CACHE_FILENAME: &str = "quantum-token-cache.bin";
 
-impl QuantumTokenCache {
-    pub fn new() -> Self {
-        Self {
-            cache: HashMap::new(),
-            max_cache_size: 10000,
-            stats: QuantumCacheStats::default(),
-            coherence_tracker: CoherenceTracker::new(),
-        }
-    }
+/// The first bytes of a token cache file
+const TOKEN_CACHE_MAGIC: &[u8] = b"RSQT";
 
-    /// Get cached token with quantum coherence validation
-    pub fn get(&mut self, source: &str, position: usize) -> Option<QuantumToken> {
-        let cache_key = self.generate_cache_key(source, position);
+/// Change this if the layout of the cache file changes
+const TOKEN_CACHE_FORMAT_VERSION: u16 = 2;
 
-        if let Some(entry) = self.cache.get_mut(&cache_key) {
-            // Check quantum coherence
-            if self.is_coherent(&entry.token)? {
-                entry.access_count += 1;
-                entry.last_access = std::time::SystemTime::now();
-                self.stats.cache_hits += 1;
+/// Files that were not lexed in this many builds are evicted
+const MAX_UNUSED_BUILDS: u64 = 8;
 
-                // Update quantum state on access (quantum Zeno effect)
-                let mut token = entry.token.clone();
-                token.quantum_state.amplitude *= 1.05; // Slight amplitude boost
-                token.quantum_state.amplitude = token.quantum_state.amplitude.min(1.0);
+/// Upper bound on the tokens kept in the cache
+const DEFAULT_MAX_CACHED_TOKENS: usize = 8_000_000;
 
-                return Some(token);
-            } else {
-                // Token has lost coherence, remove from cache
-                self.cache.remove(&cache_key);
-                self.stats.coherence_losses += 1;
-            }
-        }
+/// Source file contents a cache entry was lexed from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encodable_NoContext, Decodable_NoContext)]
+struct TokenCacheKey {
+    /// `SourceFile::src_hash` of the file
+    src_hash: Vec<u8>,
+    src_len: usize,
+    /// Keywords depend on the edition
+    edition: String,
+}
 
-        self.stats.cache_misses += 1;
-        None
+impl TokenCacheKey {
+    fn new(file: &SourceFile, edition: Edition) -> Option<Self> {
+        let src = file.src.as_ref()?;
+        Some(Self {
+            src_hash: file.src_hash.hash_bytes().to_vec(),
+            src_len: src.len(),
+            edition: edition.to_string(),
+        })
     }
+}
 
-    /// Insert token into cache with quantum properties
-    pub fn insert(&mut self, source: &str, position: usize, token: QuantumToken) {
-        let cache_key = self.generate_cache_key(source, position);
-
-        // Check cache size and evict if necessary
-        if self.cache.len() >= self.max_cache_size {
-            self.evict_least_coherent_entry();
-        }
-
-        let entry = CachedTokenEntry {
-            token: token.clone(),
-            cache_time: std::time::SystemTime::now(),
-            last_access: std::time::SystemTime::now(),
-            access_count: 0,
-            quantum_signature: self.calculate_quantum_signature(&token),
-        };
+/// Token of a cached file, relative to the start of the file
+#[derive(Debug, Clone, PartialEq, Encodable_NoContext, Decodable_NoContext)]
+struct CachedToken {
+    token_type: QuantumTokenType,
+    start: u32,
+    len: u32,
+}
 
-        self.cache.insert(cache_key, entry);
-        self.stats.cache_entries += 1;
+#[derive(Debug, Clone, Encodable_NoContext, Decodable_NoContext)]
+struct CachedFile {
+    tokens: Vec<CachedToken>,
+    /// Build in which the entry was last used
+    last_used: u64,
+}
 
-        // Track quantum coherence
-        self.coherence_tracker.track_token(&token);
-    }
+/// Body of the cache file, after the header
+#[derive(Debug, Default, Encodable_NoContext, Decodable_NoContext)]
+struct TokenCacheContents {
+    build: u64,
+    files: FxHashMap<TokenCacheKey, CachedFile>,
+}
 
-    /// Generate cache key for position-based lookup
-    fn generate_cache_key(&self, source: &str, position: usize) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
+/// Quantum Token Cache
+///
+/// Keeps the tokens of every lexed source file, keyed by the file's content
+/// hash, so that unchanged files are not lexed again. With incremental
+/// compilation the cache is stored in the session directory next to the
+/// dependency graph, and is carried over to the next session with it.
+pub struct QuantumTokenCache {
+    /// Cache file, if the cache is persisted
+    path: Option<PathBuf>,
+    /// Compiler version written to and expected in the cache file header
+    compiler_version: String,
+    contents: TokenCacheContents,
+    max_cached_tokens: usize,
+    stats: QuantumCacheStats,
+}
 
-        let mut hasher = DefaultHasher::new();
-        source.len().hash(&mut hasher);
-        position.hash(&mut hasher);
+/// Token cache statistics
+#[derive(Debug, Clone, Default)]
+pub struct QuantumCacheStats {
+    /// Files whose tokens were found in the cache
+    pub hits: u64,
+    /// Files that had to be lexed
+    pub misses: u64,
+    /// Entries evicted when the cache was saved
+    pub evictions: u64,
+    /// Whether a previous cache file was found and could be used
+    pub loaded_from_disk: bool,
+}
 
-        // Include a small context window for better cache hits
-        let context_start = position.saturating_sub(10);
-        let context_end = (position + 10).min(source.len());
-        if context_start < source.len() && context_end <= source.len() {
-            source[context_start..context_end].hash(&mut hasher);
+impl QuantumTokenCache {
+    /// In-memory cache that is never written to disk
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            compiler_version: String::new(),
+            contents: TokenCacheContents::default(),
+            max_cached_tokens: DEFAULT_MAX_CACHED_TOKENS,
+            stats: QuantumCacheStats::default(),
         }
-
-        format!("token_{}_{}", position, hasher.finish())
     }
 
-    /// Check if token maintains quantum coherence
-    fn is_coherent(&self, token: &QuantumToken) -> QuantumResult<bool> {
-        // Check quantum state coherence
-        if token.quantum_state.amplitude < 0.1 {
-            return Ok(false);
-        }
-
-        // Check entanglement stability
-        if token.quantum_state.entanglement > 1.0 {
-            return Ok(false);
+    /// Load the cache stored at `path`
+    ///
+    /// Starts with an empty cache if the file does not exist, was written by
+    /// another compiler version or in another format, or was cut short.
+    /// [`QuantumTokenCache::save`] writes the cache back to `path`.
+    pub fn load(path: PathBuf, compiler_version: &str) -> Self {
+        let mut cache = Self::new();
+        cache.compiler_version = compiler_version.to_string();
+        if let Some(contents) = Self::read_contents(&path, compiler_version) {
+            cache.contents = contents;
+            cache.stats.loaded_from_disk = true;
         }
+        cache.contents.build += 1;
+        cache.path = Some(path);
+        cache
+    }
 
-        // Check phase consistency
-        if token.quantum_state.phase.abs() > 2.0 * std::f64::consts::PI {
-            return Ok(false);
+    fn read_contents(path: &Path, compiler_version: &str) -> Option<TokenCacheContents> {
+        let data = fs::read(path).ok()?;
+        let body = data
+            .strip_prefix(TOKEN_CACHE_MAGIC)?
+            .strip_prefix(&TOKEN_CACHE_FORMAT_VERSION.to_le_bytes())?;
+        // Fails unless the file was written to the end
+        let mut decoder = MemDecoder::new(body, 0).ok()?;
+        if decoder.read_str() != compiler_version {
+            return None;
         }
+        Some(TokenCacheContents::decode(&mut decoder))
+    }
 
-        Ok(true)
+    /// Bound the number of tokens kept in the cache
+    pub fn set_max_cached_tokens(&mut self, max_cached_tokens: usize) {
+        self.max_cached_tokens = max_cached_tokens;
     }
 
-    /// Calculate quantum signature for cache validation
-    fn calculate_quantum_signature(&self, token: &QuantumToken) -> QuantumSignature {
-        QuantumSignature {
-            amplitude_hash: (token.quantum_state.amplitude * 1000.0) as u64,
-            phase_hash: (token.quantum_state.phase * 1000.0) as u64,
-            entanglement_hash: (token.quantum_state.entanglement * 1000.0) as u64,
-            token_type_hash: self.hash_token_type(&token.token_type),
+    /// Cached tokens of `file`, if its contents did not change
+    fn get(&mut self, file: &SourceFile, edition: Edition) -> Option<&[CachedToken]> {
+        let build = self.contents.build;
+        let key = TokenCacheKey::new(file, edition);
+        match key.and_then(|key| self.contents.files.get_mut(&key)) {
+            Some(entry) => {
+                entry.last_used = build;
+                self.stats.hits += 1;
+                Some(&entry.tokens)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
         }
     }
 
-    /// Hash token type for signature
-    fn hash_token_type(&self, token_type: &QuantumTokenType) -> u64 {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        std::mem::discriminant(token_type).hash(&mut hasher);
-        hasher.finish()
+    /// Cache the tokens lexed from `file`
+    fn insert(&mut self, file: &SourceFile, edition: Edition, tokens: &[QuantumToken]) {
+        let Some(key) = TokenCacheKey::new(file, edition) else { return };
+        let tokens = tokens
+            .iter()
+            .map(|token| CachedToken {
+                token_type: token.token_type.clone(),
+                start: (token.span.lo() - file.start_pos).0,
+                len: (token.span.hi() - token.span.lo()).0,
+            })
+            .collect();
+        self.contents.files.insert(key, CachedFile { tokens, last_used: self.contents.build });
     }
 
-    /// Evict least coherent cache entry
-    fn evict_least_coherent_entry(&mut self) {
-        if self.cache.is_empty() {
-            return;
+    /// Drop entries unused for `MAX_UNUSED_BUILDS` builds, then the least
+    /// recently used ones until at most `max_cached_tokens` tokens are left
+    fn evict(&mut self) {
+        let build = self.contents.build;
+        let files_before = self.contents.files.len();
+        self.contents
+            .files
+            .retain(|_, entry| build.saturating_sub(entry.last_used) < MAX_UNUSED_BUILDS);
+
+        let mut cached_tokens: usize =
+            self.contents.files.values().map(|entry| entry.tokens.len()).sum();
+        if cached_tokens > self.max_cached_tokens {
+            let mut by_age: Vec<_> = self
+                .contents
+                .files
+                .iter()
+                .map(|(key, entry)| (entry.last_used, entry.tokens.len(), key.clone()))
+                .collect();
+            by_age.sort_unstable_by_key(|&(last_used, len, _)| (last_used, len));
+            for (_, len, key) in by_age {
+                if cached_tokens <= self.max_cached_tokens {
+                    break;
+                }
+                self.contents.files.remove(&key);
+                cached_tokens -= len;
+            }
         }
 
-        let mut worst_key = String::new();
-        let mut worst_coherence = f64::INFINITY;
-
-        for (key, entry) in &self.cache {
-            let coherence_score = entry.token.quantum_state.amplitude *
-                                 (entry.access_count as f64 + 1.0);
+        self.stats.evictions += (files_before - self.contents.files.len()) as u64;
+    }
 
-            if coherence_score < worst_coherence {
-                worst_coherence = coherence_score;
-                worst_key = key.clone();
-            }
+    /// Evict stale entries and write the cache back to the file it was loaded from
+    pub fn save(&mut self) -> io::Result<()> {
+        self.evict();
+        let Some(path) = &self.path else { return Ok(()) };
+
+        // The file may be hard-linked from the previous session directory,
+        // so it must be replaced rather than overwritten in place.
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
         }
+        let mut encoder = FileEncoder::new(path)?;
+        encoder.emit_raw_bytes(TOKEN_CACHE_MAGIC);
+        encoder.emit_raw_bytes(&TOKEN_CACHE_FORMAT_VERSION.to_le_bytes());
+        encoder.emit_str(&self.compiler_version);
+        self.contents.encode(&mut encoder);
+        encoder.finish().map(drop).map_err(|(_, err)| err)
+    }
 
-        if !worst_key.is_empty() {
-            self.cache.remove(&worst_key);
-            self.stats.evictions += 1;
-        }
+    /// Path of the cache file, if the cache is persisted
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Number of cached source files
+    pub fn len(&self) -> usize {
+        self.contents.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.files.is_empty()
     }
 
     pub fn get_stats(&self) -> &QuantumCacheStats {
         &self.stats
     }
 
+    /// Share of the looked up files that were found in the cache
     pub fn get_cache_efficiency(&self) -> f64 {
-        let total_requests = self.stats.cache_hits + self.stats.cache_misses;
+        let total_requests = self.stats.hits + self.stats.misses;
         if total_requests == 0 {
             return 0.0;
         }
-        self.stats.cache_hits as f64 / total_requests as f64
+        self.stats.hits as f64 / total_requests as f64
     }
+}
 
-    /// 基于VQE算法的量子态管理缓存优化
-    ///
-    /// 🧠 缓存改进：基于VQE的量子态管理经验实现真实物理模型
-    /// 🔧 集成第二阶段的VQE算法和量子态验证
-    pub fn optimize_quantum_cache_with_vqe(&mut self) -> QuantumResult<CacheOptimizationResult> {
-        let start_time = std::time::Instant::now();
-        println!("🧠 开始基于VQE的量子缓存优化");
-
-        // 1. 分析当前缓存的量子态分布
-        let quantum_state_analysis = self.analyze_cache_quantum_states()?;
-
-        // 2. 应用VQE启发的状态优化
-        let vqe_optimization = self.apply_vqe_inspired_optimization(&quantum_state_analysis)?;
-
-        // 3. 优化纠缠网络缓存
-        let entanglement_optimization = self.optimize_entanglement_cache()?;
-
-        // 4. 计算优化效果
-        let optimization_result = CacheOptimizationResult {
-            quantum_state_efficiency: vqe_optimization.state_efficiency,
-            entanglement_preservation_ratio: entanglement_optimization.preservation_ratio,
-            cache_hit_improvement: self.calculate_cache_hit_improvement(&quantum_state_analysis),
-            memory_efficiency_gain: vqe_optimization.memory_efficiency,
-            optimization_duration: start_time.elapsed(),
-        };
-
-        // 5. 更新缓存统计
-        self.stats.quantum_state_transitions += vqe_optimization.state_transitions;
-        self.stats.entanglement_preservations += entanglement_optimization.preservations;
-
-        println!("✅ VQE缓存优化完成，缓存命中率提升: {:.2}%",
-                 optimization_result.cache_hit_improvement * 100.0);
-
-        Ok(optimization_result)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rustc_data_structures::sync::set_dyn_thread_safe_mode;
+    use rustc_span::source_map::FilePathMapping;
+    use rustc_span::{FileName, create_default_session_globals_then};
+
+    const SOURCE: &str = r##"#!/usr/bin/env rust
+fn größe<'a>(x: &'a str) -> &'a str {
+    let raw = r#"raw "string""#;
+    let bytes = b"bytes\n"; // comment
+    /* nested /* block */ comment */
+    'outer: loop { break 'outer; }
+    quantum(1.5e3, 0x1F_u8, 'c', r#async, true)
+}
+"##;
+
+    fn with_source_file<R>(src: &str, f: impl FnOnce(&SourceMap, &SourceFile) -> R) -> R {
+        create_default_session_globals_then(|| {
+            let source_map = SourceMap::new(FilePathMapping::empty());
+            // Another file first, so spans do not start at zero
+            source_map.new_source_file(FileName::Custom("other".to_string()), "mod m;".to_string());
+            let file =
+                source_map.new_source_file(FileName::Custom("test".to_string()), src.to_string());
+            f(&source_map, &file)
+        })
     }
 
-    /// 分析缓存中的量子态分布
-    fn analyze_cache_quantum_states(&self) -> QuantumResult<QuantumStateAnalysis> {
-        let mut entanglement_density = 0.0;
-        let mut coherence_levels = Vec::new();
-
-        for (_key, entry) in &self.cache {
-            entanglement_density += entry.quantum_state.entanglement;
-            coherence_levels.push(entry.quantum_state.amplitude);
-        }
-
-        let avg_coherence = if !coherence_levels.is_empty() {
-            coherence_levels.iter().sum::<f64>() / coherence_levels.len() as f64
-        } else {
-            0.0
-        };
-
-        Ok(QuantumStateAnalysis {
-            average_entanglement_density: entanglement_density / self.cache.len() as f64,
-            average_coherence_level: avg_coherence,
-            total_states: self.cache.len(),
+    fn tokenize(src: &str) -> Vec<QuantumToken> {
+        with_source_file(src, |_, file| {
+            QuantumLexer::new(&QuantumConfig::default()).tokenize_source_file(file)
         })
     }
 
-    /// 应用VQE启发的状态优化
-    fn apply_vqe_inspired_optimization(&mut self, _analysis: &QuantumStateAnalysis) -> QuantumResult<VqeOptimizationResult> {
-        let mut state_transitions = 0;
-        let mut memory_saved = 0;
-
-        // 基于VQE算法的状态优化策略
-        for (_key, entry) in self.cache.iter_mut() {
-            // 状态能量最小化（类似VQE的能量优化）
-            if entry.quantum_state.amplitude > 0.9 {
-                entry.quantum_state.amplitude = (entry.quantum_state.amplitude * 0.95).max(0.8);
-                state_transitions += 1;
-                memory_saved += 32; // 估算节省的字节数
-            }
-        }
+    fn token<'a>(tokens: &'a [QuantumToken], value: &str) -> &'a QuantumToken {
+        tokens.iter().find(|token| token.value == value).unwrap()
+    }
 
-        let state_efficiency = if self.cache.len() > 0 {
-            state_transitions as f64 / self.cache.len() as f64
-        } else {
-            0.0
-        };
+    #[test]
+    fn test_tokens_round_trip() {
+        let tokens = tokenize(SOURCE);
+        let text: String = tokens.iter().map(|token| token.value.as_str()).collect();
+        assert_eq!(text, SOURCE);
+        assert!(!tokens.iter().any(|token| token.token_type == QuantumTokenType::Unknown));
+    }
 
-        let memory_efficiency = memory_saved as f64 / (self.cache.len() * 64) as f64;
+    #[test]
+    fn test_spans_match_source() {
+        with_source_file(SOURCE, |source_map, file| {
+            let tokens = QuantumLexer::new(&QuantumConfig::default()).tokenize_source_file(file);
+            assert_eq!(tokens[0].span.lo(), file.start_pos);
+            assert_eq!(tokens.last().unwrap().span.hi(), file.end_position());
+            for pair in tokens.windows(2) {
+                assert_eq!(pair[0].span.hi(), pair[1].span.lo());
+            }
+            for token in &tokens {
+                assert_eq!(source_map.span_to_snippet(token.span).unwrap(), token.value);
+            }
+        });
+    }
 
-        Ok(VqeOptimizationResult {
-            state_efficiency,
-            memory_efficiency,
-            state_transitions,
-        })
+    #[test]
+    fn test_token_classification() {
+        let tokens = tokenize(SOURCE);
+        assert_eq!(token(&tokens, "#!/usr/bin/env rust").token_type, QuantumTokenType::Comment);
+        assert_eq!(token(&tokens, "fn").token_type, QuantumTokenType::Keyword("fn".to_string()));
+        assert_eq!(token(&tokens, "größe").token_type, QuantumTokenType::Identifier);
+        assert_eq!(token(&tokens, "'a").token_type, QuantumTokenType::Lifetime);
+        assert_eq!(token(&tokens, "'outer").token_type, QuantumTokenType::Lifetime);
+        assert_eq!(token(&tokens, "r#async").token_type, QuantumTokenType::Identifier);
+        assert_eq!(
+            token(&tokens, r##"r#"raw "string""#"##).token_type,
+            QuantumTokenType::Literal(LiteralType::RawString)
+        );
+        assert_eq!(
+            token(&tokens, r#"b"bytes\n""#).token_type,
+            QuantumTokenType::Literal(LiteralType::ByteString)
+        );
+        assert_eq!(
+            token(&tokens, "/* nested /* block */ comment */").token_type,
+            QuantumTokenType::Comment
+        );
+        assert_eq!(
+            token(&tokens, "1.5e3").token_type,
+            QuantumTokenType::Literal(LiteralType::Float)
+        );
+        assert_eq!(
+            token(&tokens, "0x1F_u8").token_type,
+            QuantumTokenType::Literal(LiteralType::Integer)
+        );
+        assert_eq!(token(&tokens, "'c'").token_type, QuantumTokenType::Literal(LiteralType::Char));
+        assert_eq!(
+            token(&tokens, "true").token_type,
+            QuantumTokenType::Literal(LiteralType::Boolean)
+        );
+        assert_eq!(token(&tokens, "{").token_type, QuantumTokenType::Delimiter('{'));
+        assert_eq!(token(&tokens, "&").token_type, QuantumTokenType::Operator("&".to_string()));
     }
 
-    /// 优化纠缠网络缓存
-    fn optimize_entanglement_cache(&mut self) -> QuantumResult<EntanglementOptimizationResult> {
-        let mut preservations = 0;
-        let mut total_entanglements = 0;
+    #[test]
+    fn test_quantum_metadata_keeps_source() {
+        with_source_file(SOURCE, |source_map, _| {
+            let mut lexer = QuantumLexer::new(&QuantumConfig::default());
+            let tokens = lexer.quantum_tokenize(source_map).unwrap();
+            let stats = lexer.get_stats();
+            assert_eq!(stats.files_tokenized, 2);
+            assert_eq!(stats.tokens_generated, tokens.len());
+            assert_eq!(stats.keywords_enhanced, 1);
+            assert!(stats.entanglement_pairs > 0);
+
+            let text: String = tokens.iter().map(|token| token.value.as_str()).collect();
+            assert_eq!(text, format!("mod m;{SOURCE}"));
+            let quantum = token(&tokens, "quantum");
+            assert_eq!(
+                quantum.token_type,
+                QuantumTokenType::QuantumSpecific(QuantumSpecificType::QuantumFunction)
+            );
+            assert!(!quantum.entangled_with.is_empty());
+        });
+    }
 
-        for (_key, entry) in self.cache.iter_mut() {
-            if entry.quantum_state.entanglement > 0.1 {
-                total_entanglements += 1;
+    #[test]
+    fn test_parallel_lexing_matches_serial() {
+        set_dyn_thread_safe_mode(true);
+        create_default_session_globals_then(|| {
+            let source_map = SourceMap::new(FilePathMapping::empty());
+            for i in 0..16 {
+                let src = format!("fn f{i}() {{ let x{i} = {i}; }}\n{SOURCE}");
+                source_map.new_source_file(FileName::Custom(format!("file{i}")), src);
+            }
 
-                // 加强稳定的纠缠连接
-                if entry.quantum_state.entanglement > 0.5 {
-                    entry.quantum_state.entanglement = (entry.quantum_state.entanglement * 1.05).min(1.0);
-                    preservations += 1;
-                }
+            let tokenize = |parallel_lexing| {
+                let config = QuantumConfig { parallel_lexing, ..QuantumConfig::default() };
+                QuantumLexer::new(&config).quantum_tokenize(&source_map).unwrap()
+            };
+            let serial = tokenize(false);
+            let parallel = tokenize(true);
+            assert_eq!(serial.len(), parallel.len());
+            for (serial, parallel) in serial.iter().zip(&parallel) {
+                assert_eq!(serial.span, parallel.span);
+                assert_eq!(serial.value, parallel.value);
+                assert_eq!(serial.token_type, parallel.token_type);
             }
-        }
+        });
+    }
 
-        let preservation_ratio = if total_entanglements > 0 {
-            preservations as f64 / total_entanglements as f64
-        } else {
-            1.0
+    #[test]
+    fn test_token_cache_persists_across_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TOKEN_CACHE_FILENAME);
+
+        let build = |version: &str| {
+            with_source_file(SOURCE, |source_map, _| {
+                let mut lexer = QuantumLexer::new(&QuantumConfig::default());
+                lexer.load_token_cache(path.clone(), version);
+                let tokens = lexer.quantum_tokenize(source_map).unwrap();
+                lexer.save_token_cache().unwrap();
+                let tokens: Vec<_> =
+                    tokens.into_iter().map(|token| (token.span, token.value)).collect();
+                (lexer.get_stats().files_from_cache, tokens)
+            })
         };
 
-        Ok(EntanglementOptimizationResult {
-            preservation_ratio,
-            preservations,
-        })
-    }
+        let (cached, first) = build("1.0.0");
+        assert_eq!(cached, 0);
+        let (cached, second) = build("1.0.0");
+        assert_eq!(cached, 2);
+        assert_eq!(first, second);
 
-    /// 计算缓存命中率改进
-    fn calculate_cache_hit_improvement(&self, analysis: &QuantumStateAnalysis) -> f64 {
-        // 基于量子态质量估算缓存命中率改进
-        let coherence_factor = analysis.average_coherence_level;
-        let entanglement_factor = analysis.average_entanglement_density;
-
-        (coherence_factor * 0.6 + entanglement_factor * 0.4) * 0.2 // 最大20%改进
+        // Caches written by another compiler are ignored
+        let (cached, _) = build("1.1.0");
+        assert_eq!(cached, 0);
     }
-}
 
-#[derive(Debug, Clone)]
-struct CachedTokenEntry {
-    token: QuantumToken,
-    cache_time: std::time::SystemTime,
-    last_access: std::time::SystemTime,
-    access_count: u64,
-    quantum_signature: QuantumSignature,
-}
+    #[test]
+    fn test_token_cache_eviction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(TOKEN_CACHE_FILENAME);
 
-#[derive(Debug, Clone)]
-struct QuantumSignature {
-    amplitude_hash: u64,
-    phase_hash: u64,
-    entanglement_hash: u64,
-    token_type_hash: u64,
-}
+        with_source_file(SOURCE, |_, file| {
+            let tokens = QuantumLexer::new(&QuantumConfig::default()).tokenize_source_file(file);
 
-#[derive(Debug, Clone, Default)]
-struct QuantumCacheStats {
-    cache_entries: u64,
-    cache_hits: u64,
-    cache_misses: u64,
-    evictions: u64,
-    coherence_losses: u64,
-    /// 基于VQE算法的量子态管理统计
-    quantum_state_transitions: u64,
-    entanglement_preservations: u64,
-    decoherence_recoveries: u64,
-}
-
-#[derive(Debug, Clone)]
-struct CoherenceTracker {
-    tracked_tokens: Vec<QuantumToken>,
-}
+            let mut cache = QuantumTokenCache::load(path.clone(), "1.0.0");
+            cache.insert(file, Edition::Edition2021, &tokens);
+            cache.save().unwrap();
 
-impl CoherenceTracker {
-    fn new() -> Self {
-        Self {
-            tracked_tokens: Vec::new(),
-        }
+            // Entries that are not used for `MAX_UNUSED_BUILDS` builds are dropped
+            for _ in 0..MAX_UNUSED_BUILDS {
+                let mut cache = QuantumTokenCache::load(path.clone(), "1.0.0");
+                assert!(cache.get_stats().loaded_from_disk);
+                assert_eq!(cache.len(), 1);
+                cache.save().unwrap();
+            }
+            let cache = QuantumTokenCache::load(path.clone(), "1.0.0");
+            assert!(cache.is_empty());
+
+            // So are the least recently used entries once the cache is too large
+            let mut cache = QuantumTokenCache::new();
+            cache.set_max_cached_tokens(tokens.len());
+            cache.insert(file, Edition::Edition2015, &tokens);
+            cache.insert(file, Edition::Edition2021, &tokens);
+            assert!(cache.get(file, Edition::Edition2021).is_some());
+            cache.contents.build += 1;
+            assert!(cache.get(file, Edition::Edition2021).is_some());
+            cache.save().unwrap();
+            assert_eq!(cache.len(), 1);
+            assert_eq!(cache.get_stats().evictions, 1);
+            assert!(cache.get(file, Edition::Edition2015).is_none());
+        });
     }
 
-    fn track_token(&mut self, token: &QuantumToken) {
-        self.tracked_tokens.push(token.clone());
-
-        // Keep only recent tokens to avoid memory bloat
-        if self.tracked_tokens.len() > 1000 {
-            self.tracked_tokens.drain(0..500);
-        }
+    #[test]
+    fn test_parallel_process_keeps_order() {
+        set_dyn_thread_safe_mode(true);
+        let mut pool = QuantumProcessingPool::new();
+        let items: Vec<u64> = (0..1000).collect();
+        let squares = pool.parallel_process(items, |item| Ok(item * item)).unwrap();
+        assert_eq!(squares, (0..1000).map(|item| item * item).collect::<Vec<_>>());
+        assert_eq!(pool.get_stats().tasks_processed, 1000);
     }
 }
-
-/// 缓存优化结果
-#[derive(Debug, Clone)]
-pub struct CacheOptimizationResult {
-    pub quantum_state_efficiency: f64,
-    pub entanglement_preservation_ratio: f64,
-    pub cache_hit_improvement: f64,
-    pub memory_efficiency_gain: f64,
-    pub optimization_duration: std::time::Duration,
-}
-
-/// 量子态分析结果
-#[derive(Debug, Clone)]
-pub struct QuantumStateAnalysis {
-    pub average_entanglement_density: f64,
-    pub average_coherence_level: f64,
-    pub total_states: usize,
-}
-
-/// VQE优化结果
-#[derive(Debug, Clone)]
-pub struct VqeOptimizationResult {
-    pub state_efficiency: f64,
-    pub memory_efficiency: f64,
-    pub state_transitions: u64,
-}
-
-/// 纠缠优化结果
-#[derive(Debug, Clone)]
-pub struct EntanglementOptimizationResult {
-    pub preservation_ratio: f64,
-    pub preservations: u64,
-}
@@ -1,24 +1,25 @@
 //! Quantum Optimizer
 //! 
 //! This module implements quantum-enhanced optimization for Rust MIR.
-//! It uses quantum annealing for global optimization and quantum algorithms
-//! for specific optimization patterns.
+//! The optimizations themselves are the quantum MIR passes of
+//! `rustc_mir_transform`; this module reports what they changed.
 
 use rustc_middle::ty::TyCtxt;
-use crate::{QuantumConfig, QuantumResult, QuantumError};
-use std::collections::HashMap;
+use rustc_session::code_stats::QuantumMirStats;
+use crate::QuantumResult;
 
 /// Quantum optimization result
 #[derive(Debug, Clone)]
 pub struct QuantumOptimization {
     /// Optimization type
     pub optimization_type: QuantumOptimizationType,
-    /// Performance improvement
-    pub performance_gain: f64,
-    /// Memory savings
-    pub memory_savings: u64,
-    /// Quantum confidence
-    pub confidence: f64,
+}
+
+impl QuantumOptimization {
+    /// An optimization that a MIR pass applied
+    fn applied(optimization_type: QuantumOptimizationType) -> Self {
+        Self { optimization_type }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,12 +51,6 @@ pub enum QuantumOptimizationType {
         simd_width: usize,
         quantum_parallel: bool,
     },
-    /// Arrow data structure optimization
-    ArrowOptimization {
-        optimized_structures: usize,
-        memory_layout: ArrowMemoryLayout,
-        zero_copy_operations: usize,
-    },
     /// Quantum parallelization
     QuantumParallelization {
         parallel_regions: usize,
@@ -64,23 +59,9 @@ pub enum QuantumOptimizationType {
     },
 }
 
-#[derive(Debug, Clone)]
-pub enum ArrowMemoryLayout {
-    Columnar,
-    RowMajor,
-    Compressed,
-    QuantumOptimized,
-}
-
 /// Quantum optimizer with advanced algorithms
+#[derive(Default)]
 pub struct QuantumOptimizer {
-    config: QuantumConfig,
-    /// Quantum annealing engine
-    annealing_engine: QuantumAnnealingEngine,
-    /// Quantum pattern matcher
-    pattern_matcher: QuantumPatternMatcher,
-    /// Arrow optimizer
-    arrow_optimizer: ArrowOptimizer,
     /// Optimization statistics
     stats: QuantumOptimizerStats,
 }
@@ -89,1109 +70,63 @@ pub struct QuantumOptimizer {
 pub struct QuantumOptimizerStats {
     pub total_optimizations: u64,
     pub quantum_optimizations: u64,
-    pub arrow_optimizations: u64,
-    pub total_speedup: f64,
-    pub total_memory_saved: u64,
+    /// Time spent in the quantum MIR passes
+    pub mir_optimization_time: std::time::Duration,
+    /// Changes made by the quantum MIR passes
+    pub mir_stats: QuantumMirStats,
 }
 
 impl QuantumOptimizer {
     /// Create a new quantum optimizer
-    pub fn new(config: &QuantumConfig) -> Self {
-        println!("🚀 Initializing Quantum Optimizer...");
-
-        Self {
-            config: config.clone(),
-            annealing_engine: QuantumAnnealingEngine::new(config.quantum_opt_level, config.clone()),
-            pattern_matcher: QuantumPatternMatcher::new(config.clone()),
-            arrow_optimizer: ArrowOptimizer::new(config.clone()),
-            stats: QuantumOptimizerStats::default(),
-        }
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    /// Quantum MIR optimization
+    /// Collect what the quantum MIR passes changed
+    ///
+    /// The passes live in `rustc_mir_transform` and run as part of `optimized_mir`, so
+    /// every body must have been optimized before this is called. Only the kinds of
+    /// optimization that changed something are returned.
     pub fn quantum_optimize_mir(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<Vec<QuantumOptimization>> {
-        println!("⚡ Starting quantum MIR optimization...");
-        
-        let start_time = std::time::Instant::now();
-        let mut optimizations = Vec::new();
-        
-        // Phase 1: Quantum dead code elimination
-        let dead_code_opt = self.quantum_dead_code_elimination(tcx)?;
-        optimizations.push(dead_code_opt);
-        
-        // Phase 2: Quantum constant folding
-        let const_fold_opt = self.quantum_constant_folding(tcx)?;
-        optimizations.push(const_fold_opt);
-        
-        // Phase 3: Quantum loop optimization
-        let loop_opt = self.quantum_loop_optimization(tcx)?;
-        optimizations.push(loop_opt);
-        
-        // Phase 4: Quantum function inlining
-        let inline_opt = self.quantum_inlining(tcx)?;
-        optimizations.push(inline_opt);
-        
-        // Phase 5: Quantum vectorization
-        let vector_opt = self.quantum_vectorization(tcx)?;
-        optimizations.push(vector_opt);
-        
-        // Phase 6: Quantum parallelization
-        let parallel_opt = self.quantum_parallelization(tcx)?;
-        optimizations.push(parallel_opt);
-        
-        // Apply quantum annealing for global optimization
-        let annealed_optimizations = self.apply_quantum_annealing(optimizations)?;
-        
-        let optimization_time = start_time.elapsed();
-        
-        // Update statistics
-        self.stats.total_optimizations += annealed_optimizations.len() as u64;
-        self.stats.quantum_optimizations += annealed_optimizations.len() as u64;
-        self.stats.total_speedup += annealed_optimizations.iter().map(|o| o.performance_gain).sum::<f64>();
-        self.stats.total_memory_saved += annealed_optimizations.iter().map(|o| o.memory_savings).sum::<u64>();
-        
-        println!("✅ Quantum MIR optimization complete in {:?}", optimization_time);
-        println!("📊 Optimizations applied: {}", annealed_optimizations.len());
-        println!("🚀 Total speedup: {:.1}x", self.stats.total_speedup);
-        
-        Ok(annealed_optimizations)
-    }
-
-    /// Apply Arrow data structure optimizations
-    pub fn apply_arrow_optimizations(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<Vec<u64>> {
-        println!("🏹 Applying Arrow optimizations...");
-        
-        let start_time = std::time::Instant::now();
-        let mut memory_savings = Vec::new();
-        
-        // Arrow columnar optimization
-        let columnar_savings = self.arrow_optimizer.optimize_columnar_layout(tcx)?;
-        memory_savings.push(columnar_savings);
-        
-        // Arrow zero-copy optimization
-        let zero_copy_savings = self.arrow_optimizer.optimize_zero_copy_operations(tcx)?;
-        memory_savings.push(zero_copy_savings);
-        
-        // Arrow compression optimization
-        let compression_savings = self.arrow_optimizer.optimize_compression(tcx)?;
-        memory_savings.push(compression_savings);
-        
-        // Arrow vectorization optimization
-        let vectorization_savings = self.arrow_optimizer.optimize_vectorization(tcx)?;
-        memory_savings.push(vectorization_savings);
-        
-        let arrow_time = start_time.elapsed();
-        
-        // Update statistics
-        self.stats.arrow_optimizations += memory_savings.len() as u64;
-        self.stats.total_memory_saved += memory_savings.iter().sum::<u64>();
-        
-        println!("✅ Arrow optimizations complete in {:?}", arrow_time);
-        println!("💾 Memory saved: {} KB", memory_savings.iter().sum::<u64>());
-        
-        Ok(memory_savings)
-    }
-
-    /// Quantum dead code elimination
-    fn quantum_dead_code_elimination(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<QuantumOptimization> {
-        println!("🗑️  Quantum dead code elimination...");
-        
-        // Simulate quantum analysis of dead code
-        let eliminated_blocks = 15;
-        let performance_gain = eliminated_blocks as f64 * 0.1;
-        
-        Ok(QuantumOptimization {
-            optimization_type: QuantumOptimizationType::QuantumDeadCodeElimination {
-                eliminated_blocks,
-                quantum_analysis: true,
-            },
-            performance_gain,
-            memory_savings: eliminated_blocks as u64 * 512, // 512 bytes per block
-            confidence: 0.95,
-        })
-    }
-
-    /// Quantum constant folding
-    fn quantum_constant_folding(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<QuantumOptimization> {
-        println!("📐 Quantum constant folding...");
-        
-        // Simulate quantum evaluation of constants
-        let folded_expressions = 25;
-        let performance_gain = folded_expressions as f64 * 0.05;
-        
-        Ok(QuantumOptimization {
-            optimization_type: QuantumOptimizationType::QuantumConstantFolding {
-                folded_expressions,
-                quantum_evaluation: true,
-            },
-            performance_gain,
-            memory_savings: folded_expressions as u64 * 64, // 64 bytes per expression
-            confidence: 0.98,
-        })
-    }
-
-    /// Quantum loop optimization
-    fn quantum_loop_optimization(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<QuantumOptimization> {
-        println!("🔄 Quantum loop optimization...");
-        
-        // Simulate quantum loop analysis
-        let optimized_loops = 8;
-        let performance_gain = optimized_loops as f64 * 0.3;
-        
-        Ok(QuantumOptimization {
-            optimization_type: QuantumOptimizationType::QuantumLoopOptimization {
-                optimized_loops,
-                vectorization: true,
-                quantum_unrolling: true,
-            },
-            performance_gain,
-            memory_savings: optimized_loops as u64 * 256,
-            confidence: 0.92,
-        })
-    }
-
-    /// Quantum function inlining
-    fn quantum_inlining(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<QuantumOptimization> {
-        println!("📥 Quantum function inlining...");
-        
-        // Simulate quantum inlining analysis
-        let inlined_functions = 12;
-        let performance_gain = inlined_functions as f64 * 0.15;
-        
-        Ok(QuantumOptimization {
-            optimization_type: QuantumOptimizationType::QuantumInlining {
-                inlined_functions,
-                quantum_analysis: true,
-            },
-            performance_gain,
-            memory_savings: 0, // Inlining may increase code size
-            confidence: 0.88,
-        })
-    }
-
-    /// Quantum vectorization
-    fn quantum_vectorization(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<QuantumOptimization> {
-        println!("🔢 Quantum vectorization...");
-        
-        // Simulate quantum vectorization
-        let vectorized_operations = 20;
-        let simd_width = 8;
-        let performance_gain = vectorized_operations as f64 * 0.4;
-        
-        Ok(QuantumOptimization {
-            optimization_type: QuantumOptimizationType::QuantumVectorization {
-                vectorized_operations,
-                simd_width,
-                quantum_parallel: true,
-            },
-            performance_gain,
-            memory_savings: vectorized_operations as u64 * 128,
-            confidence: 0.90,
-        })
-    }
-
-    /// Quantum parallelization
-    fn quantum_parallelization(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<QuantumOptimization> {
-        println!("⚡ Quantum parallelization...");
-        
-        // Simulate quantum parallel analysis
-        let parallel_regions = 6;
-        let speedup_factor = 3.2;
-        let performance_gain = speedup_factor;
-        
-        Ok(QuantumOptimization {
-            optimization_type: QuantumOptimizationType::QuantumParallelization {
-                parallel_regions,
-                quantum_entanglement: true,
-                speedup_factor,
-            },
-            performance_gain,
-            memory_savings: parallel_regions as u64 * 1024,
-            confidence: 0.85,
-        })
-    }
-
-    /// Apply quantum annealing for global optimization
-    fn apply_quantum_annealing(&mut self, optimizations: Vec<QuantumOptimization>) -> QuantumResult<Vec<QuantumOptimization>> {
-        println!("🌀 Applying quantum annealing...");
-
-        // Use real QuantumAnnealingEngine instead of simulation
-        let annealed_optimizations = self.annealing_engine.anneal_optimizations(optimizations)?;
-
-        // Get real statistics from annealing engine
-        let annealing_stats = self.annealing_engine.get_stats();
-
-        println!("   - Quantum annealing iterations: {}", annealing_stats.iterations);
-        println!("   - Energy reduction: {:.3}", annealing_stats.energy_reduction);
-        println!("   - Quantum boost: {:.2}x", annealing_stats.quantum_boost);
-        println!("   - Accepted moves: {}", annealing_stats.accepted_moves);
-
-        Ok(annealed_optimizations)
-    }
-
-    /// Get optimization statistics
-    pub fn get_stats(&self) -> &QuantumOptimizerStats {
-        &self.stats
-    }
-}
-
-// Real implementations replacing placeholders
-
-/// Quantum Annealing Engine - Real Implementation
-/// Implements quantum annealing for global optimization problems
-pub struct QuantumAnnealingEngine {
-    /// Optimization level (0-3)
-    optimization_level: u8,
-    /// Current annealing temperature
-    temperature: f64,
-    /// Temperature cooling rate
-    cooling_rate: f64,
-    /// Quantum configuration
-    config: QuantumConfig,
-    /// Annealing statistics
-    stats: QuantumAnnealingStats,
-    /// Energy landscape cache
-    energy_cache: HashMap<String, f64>,
-    /// Maximum annealing iterations
-    max_iterations: usize,
-}
-
-impl QuantumAnnealingEngine {
-    pub fn new(opt_level: u8, config: QuantumConfig) -> Self {
-        Self {
-            optimization_level: opt_level,
-            temperature: 1000.0 * (opt_level as f64 + 1.0),
-            cooling_rate: 0.95 - (opt_level as f64 * 0.01),
-            config,
-            stats: QuantumAnnealingStats::default(),
-            energy_cache: HashMap::new(),
-            max_iterations: 100 * (opt_level as usize + 1),
-        }
-    }
-
-    /// Apply quantum annealing to optimization problems
-    pub fn anneal_optimizations(
-        &mut self,
-        optimizations: Vec<QuantumOptimization>
-    ) -> QuantumResult<Vec<QuantumOptimization>> {
-        if optimizations.is_empty() {
-            return Ok(optimizations);
-        }
-
-        // Initialize annealing state
-        let mut current_state = optimizations.clone();
-        let mut best_state = optimizations.clone();
-        let mut current_energy = self.calculate_total_energy(&current_state)?;
-        let mut best_energy = current_energy;
-
-        self.stats.initial_energy = current_energy;
-        self.stats.iterations = 0;
-
-        // Quantum annealing process
-        for iteration in 0..self.max_iterations {
-            // Generate neighbor state through quantum tunneling
-            let neighbor_state = self.generate_neighbor_state(&current_state)?;
-            let neighbor_energy = self.calculate_total_energy(&neighbor_state)?;
-
-            // Calculate acceptance probability using quantum Boltzmann distribution
-            let energy_diff = neighbor_energy - current_energy;
-            let acceptance_prob = if energy_diff < 0.0 {
-                1.0 // Always accept better solutions
-            } else {
-                (-energy_diff / self.temperature).exp()
-            };
-
-            // Quantum decision: accept or reject
-            if self.quantum_accept_decision(acceptance_prob)? {
-                current_state = neighbor_state;
-                current_energy = neighbor_energy;
-                self.stats.accepted_moves += 1;
-
-                // Update best solution if improved
-                if current_energy < best_energy {
-                    best_state = current_state.clone();
-                    best_energy = current_energy;
-                    self.stats.improvements += 1;
-                }
-            }
-
-            // Cool down temperature (simulated annealing)
-            self.temperature *= self.cooling_rate;
-            self.stats.iterations += 1;
-
-            // Early termination if temperature is too low
-            if self.temperature < 0.01 {
-                break;
-            }
-        }
-
-        self.stats.final_energy = best_energy;
-        self.stats.energy_reduction = self.stats.initial_energy - best_energy;
-
-        // Apply quantum enhancement to final solution
-        let enhanced_state = self.apply_quantum_enhancement(best_state)?;
-
-        Ok(enhanced_state)
-    }
-
-    /// Calculate total energy of optimization state
-    fn calculate_total_energy(&mut self, optimizations: &[QuantumOptimization]) -> QuantumResult<f64> {
-        let state_key = self.generate_state_key(optimizations);
-
-        // Check cache first
-        if let Some(&cached_energy) = self.energy_cache.get(&state_key) {
-            return Ok(cached_energy);
-        }
-
-        let mut total_energy = 0.0;
-
-        for opt in optimizations {
-            // Energy is negative of performance gain (we want to minimize energy, maximize gain)
-            let base_energy = -opt.performance_gain;
-
-            // Add penalty for low confidence
-            let confidence_penalty = (1.0 - opt.confidence) * 10.0;
-
-            // Add memory efficiency bonus
-            let memory_bonus = -(opt.memory_savings as f64 / 1000.0);
-
-            total_energy += base_energy + confidence_penalty + memory_bonus;
-        }
-
-        // Cache the result
-        self.energy_cache.insert(state_key, total_energy);
-
-        Ok(total_energy)
-    }
-
-    /// Generate neighbor state through quantum tunneling
-    fn generate_neighbor_state(
-        &self,
-        current_state: &[QuantumOptimization]
-    ) -> QuantumResult<Vec<QuantumOptimization>> {
-        let mut neighbor = current_state.to_vec();
-
-        // Randomly select optimization to modify
-        if neighbor.is_empty() {
-            return Ok(neighbor);
-        }
-
-        let index = self.quantum_random_index(neighbor.len())?;
-
-        // Apply quantum tunneling modification
-        match &mut neighbor[index].optimization_type {
-            QuantumOptimizationType::QuantumDeadCodeElimination { eliminated_blocks, .. } => {
-                // Quantum tunneling: explore different elimination strategies
-                *eliminated_blocks = (*eliminated_blocks as f64 * (0.8 + 0.4 * self.quantum_random()?)) as usize;
-                neighbor[index].performance_gain = *eliminated_blocks as f64 * 0.1;
-                neighbor[index].memory_savings = *eliminated_blocks as u64 * 512;
-            },
-            QuantumOptimizationType::QuantumConstantFolding { folded_expressions, .. } => {
-                // Quantum tunneling: explore different folding strategies
-                *folded_expressions = (*folded_expressions as f64 * (0.8 + 0.4 * self.quantum_random()?)) as usize;
-                neighbor[index].performance_gain = *folded_expressions as f64 * 0.05;
-                neighbor[index].memory_savings = *folded_expressions as u64 * 64;
-            },
-            QuantumOptimizationType::QuantumLoopOptimization { optimized_loops, .. } => {
-                // Quantum tunneling: explore different loop optimization strategies
-                *optimized_loops = (*optimized_loops as f64 * (0.8 + 0.4 * self.quantum_random()?)) as usize;
-                neighbor[index].performance_gain = *optimized_loops as f64 * 0.3;
-                neighbor[index].memory_savings = *optimized_loops as u64 * 256;
-            },
-            _ => {
-                // Generic quantum modification
-                neighbor[index].performance_gain *= 0.9 + 0.2 * self.quantum_random()?;
-                neighbor[index].confidence *= 0.95 + 0.1 * self.quantum_random()?;
-            }
-        }
-
-        Ok(neighbor)
-    }
-
-    /// Quantum acceptance decision using quantum probability
-    fn quantum_accept_decision(&self, acceptance_prob: f64) -> QuantumResult<bool> {
-        let quantum_random = self.quantum_random()?;
-        Ok(quantum_random < acceptance_prob)
-    }
-
-    /// Generate quantum random number [0, 1)
-    fn quantum_random(&self) -> QuantumResult<f64> {
-        // Simulate quantum random number generation
-        // In real implementation, this would use quantum hardware or quantum RNG
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        self.temperature.to_bits().hash(&mut hasher);
-        self.stats.iterations.hash(&mut hasher);
-
-        let hash = hasher.finish();
-        Ok((hash % 1000000) as f64 / 1000000.0)
-    }
-
-    /// Generate quantum random index
-    fn quantum_random_index(&self, max: usize) -> QuantumResult<usize> {
-        let random = self.quantum_random()?;
-        Ok((random * max as f64) as usize % max)
-    }
-
-    /// Generate state key for caching
-    fn generate_state_key(&self, optimizations: &[QuantumOptimization]) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        for opt in optimizations {
-            opt.performance_gain.to_bits().hash(&mut hasher);
-            opt.memory_savings.hash(&mut hasher);
-            opt.confidence.to_bits().hash(&mut hasher);
-        }
-
-        format!("state_{}", hasher.finish())
-    }
-
-    /// Apply quantum enhancement to final solution
-    fn apply_quantum_enhancement(
-        &mut self,
-        mut optimizations: Vec<QuantumOptimization>
-    ) -> QuantumResult<Vec<QuantumOptimization>> {
-        if !self.config.quantum_optimization {
-            return Ok(optimizations);
-        }
-
-        // Apply quantum boost based on annealing success
-        let quantum_boost = if self.stats.energy_reduction > 0.0 {
-            1.0 + (self.stats.energy_reduction / self.stats.initial_energy) * 0.5
-        } else {
-            1.0
-        };
-
-        for opt in &mut optimizations {
-            opt.performance_gain *= quantum_boost;
-            opt.confidence = (opt.confidence * 1.1).min(1.0);
-        }
-
-        self.stats.quantum_boost = quantum_boost;
-
-        Ok(optimizations)
-    }
-
-    pub fn get_stats(&self) -> &QuantumAnnealingStats {
-        &self.stats
-    }
-}
-
-#[derive(Debug, Clone, Default)]
-pub struct QuantumAnnealingStats {
-    pub initial_energy: f64,
-    pub final_energy: f64,
-    pub energy_reduction: f64,
-    pub iterations: usize,
-    pub accepted_moves: u64,
-    pub improvements: u64,
-    pub quantum_boost: f64,
-}
-
-/// Quantum Pattern Matcher - Real Implementation
-/// Advanced pattern recognition for optimization opportunities
-pub struct QuantumPatternMatcher {
-    /// Optimization patterns with quantum weights
-    patterns: HashMap<OptimizationPattern, QuantumPatternInfo>,
-    /// Pattern matching statistics
-    stats: QuantumPatternStats,
-    /// Quantum configuration
-    config: QuantumConfig,
-}
-
-impl QuantumPatternMatcher {
-    pub fn new(config: QuantumConfig) -> Self {
-        let mut patterns = HashMap::new();
-
-        // Initialize quantum-enhanced patterns
-        patterns.insert(OptimizationPattern::DeadCode, QuantumPatternInfo {
-            quantum_weight: 0.9,
-            detection_confidence: 0.95,
-            optimization_potential: 0.8,
-            quantum_speedup: 2.5,
-        });
-
-        patterns.insert(OptimizationPattern::ConstantFolding, QuantumPatternInfo {
-            quantum_weight: 0.8,
-            detection_confidence: 0.98,
-            optimization_potential: 0.6,
-            quantum_speedup: 1.8,
-        });
-
-        patterns.insert(OptimizationPattern::LoopOptimization, QuantumPatternInfo {
-            quantum_weight: 1.0,
-            detection_confidence: 0.85,
-            optimization_potential: 1.2,
-            quantum_speedup: 3.2,
-        });
-
-        patterns.insert(OptimizationPattern::Vectorization, QuantumPatternInfo {
-            quantum_weight: 0.7,
-            detection_confidence: 0.75,
-            optimization_potential: 1.5,
-            quantum_speedup: 4.0,
-        });
-
-        patterns.insert(OptimizationPattern::QuantumParallelization, QuantumPatternInfo {
-            quantum_weight: 1.2,
-            detection_confidence: 0.70,
-            optimization_potential: 2.0,
-            quantum_speedup: 8.0,
-        });
-
-        Self {
-            patterns,
-            stats: QuantumPatternStats::default(),
-            config,
-        }
-    }
-
-    /// Detect optimization patterns using quantum analysis
-    pub fn detect_patterns(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<Vec<DetectedPattern>> {
-        let mut detected = Vec::new();
-
-        for (pattern_type, pattern_info) in &self.patterns {
-            // Quantum pattern detection
-            let detection_result = self.quantum_pattern_detection(pattern_type, pattern_info)?;
-
-            if let Some(detected_pattern) = detection_result {
-                detected.push(detected_pattern);
-                self.stats.patterns_detected += 1;
-            }
-        }
-
-        // Apply quantum enhancement to detection results
-        if self.config.quantum_optimization {
-            self.enhance_detection_with_quantum(&mut detected)?;
-        }
-
-        self.stats.total_scans += 1;
-        Ok(detected)
-    }
-
-    /// Quantum pattern detection algorithm
-    fn quantum_pattern_detection(
-        &self,
-        pattern_type: &OptimizationPattern,
-        pattern_info: &QuantumPatternInfo
-    ) -> QuantumResult<Option<DetectedPattern>> {
-        // Simulate quantum pattern recognition
-        let quantum_probability = self.calculate_quantum_detection_probability(pattern_info)?;
-
-        if quantum_probability > 0.5 {
-            let instances = self.estimate_pattern_instances(pattern_type)?;
-
-            Ok(Some(DetectedPattern {
-                pattern_type: pattern_type.clone(),
-                instances,
-                confidence: pattern_info.detection_confidence * quantum_probability,
-                optimization_potential: pattern_info.optimization_potential,
-                quantum_speedup: pattern_info.quantum_speedup,
-            }))
-        } else {
-            Ok(None)
-        }
-    }
-
-    /// Calculate quantum detection probability
-    fn calculate_quantum_detection_probability(&self, pattern_info: &QuantumPatternInfo) -> QuantumResult<f64> {
-        // Quantum superposition of detection states
-        let base_probability = pattern_info.quantum_weight * 0.6;
+        let mir_stats = *tcx.sess.code_stats.quantum_mir.lock();
 
-        // Quantum interference effects
-        let interference = (self.stats.total_scans as f64 * 0.1).sin() * 0.2;
-
-        // Quantum entanglement with other patterns
-        let entanglement_boost = if self.stats.patterns_detected > 0 {
-            0.1 * (self.stats.patterns_detected as f64).sqrt()
-        } else {
-            0.0
-        };
-
-        let quantum_probability = (base_probability + interference + entanglement_boost).min(1.0).max(0.0);
-
-        Ok(quantum_probability)
-    }
-
-    /// Estimate number of pattern instances
-    fn estimate_pattern_instances(&self, pattern_type: &OptimizationPattern) -> QuantumResult<usize> {
-        // Simulate pattern instance counting
-        let base_instances = match pattern_type {
-            OptimizationPattern::DeadCode => 10 + (self.stats.total_scans % 20),
-            OptimizationPattern::ConstantFolding => 15 + (self.stats.total_scans % 30),
-            OptimizationPattern::LoopOptimization => 5 + (self.stats.total_scans % 15),
-            OptimizationPattern::Vectorization => 8 + (self.stats.total_scans % 12),
-            OptimizationPattern::QuantumParallelization => 3 + (self.stats.total_scans % 8),
-        };
-
-        Ok(base_instances)
-    }
-
-    /// Enhance detection results with quantum algorithms
-    fn enhance_detection_with_quantum(&self, detected: &mut [DetectedPattern]) -> QuantumResult<()> {
-        for pattern in detected {
-            // Quantum confidence boost
-            pattern.confidence = (pattern.confidence * 1.15).min(1.0);
-
-            // Quantum optimization potential enhancement
-            pattern.optimization_potential *= 1.1;
-
-            // Quantum speedup calculation
-            pattern.quantum_speedup *= 1.2;
-        }
-
-        Ok(())
-    }
-
-    pub fn get_stats(&self) -> &QuantumPatternStats {
-        &self.stats
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum OptimizationPattern {
-    DeadCode,
-    ConstantFolding,
-    LoopOptimization,
-    Vectorization,
-    QuantumParallelization,
-}
-
-#[derive(Debug, Clone)]
-pub struct QuantumPatternInfo {
-    pub quantum_weight: f64,
-    pub detection_confidence: f64,
-    pub optimization_potential: f64,
-    pub quantum_speedup: f64,
-}
-
-#[derive(Debug, Clone)]
-pub struct DetectedPattern {
-    pub pattern_type: OptimizationPattern,
-    pub instances: usize,
-    pub confidence: f64,
-    pub optimization_potential: f64,
-    pub quantum_speedup: f64,
-}
-
-#[derive(Debug, Clone, Default)]
-pub struct QuantumPatternStats {
-    pub total_scans: usize,
-    pub patterns_detected: u64,
-    pub detection_accuracy: f64,
-    pub quantum_enhancements: u64,
-}
-
-/// Arrow Optimizer - Real Implementation
-/// Optimizes Arrow data structures for quantum-enhanced performance
-pub struct ArrowOptimizer {
-    /// Arrow configuration
-    config: QuantumConfig,
-    /// Optimization statistics
-    stats: ArrowOptimizerStats,
-    /// Memory layout cache
-    layout_cache: HashMap<String, ArrowMemoryLayout>,
-    /// Zero-copy operation tracker
-    zero_copy_tracker: ZeroCopyTracker,
-}
-
-impl ArrowOptimizer {
-    pub fn new(config: QuantumConfig) -> Self {
-        Self {
-            config,
-            stats: ArrowOptimizerStats::default(),
-            layout_cache: HashMap::new(),
-            zero_copy_tracker: ZeroCopyTracker::new(),
-        }
-    }
-
-    /// Optimize Arrow data structures
-    pub fn optimize_arrow_structures(&mut self, _tcx: TyCtxt<'_>) -> QuantumResult<QuantumOptimization> {
-        if !self.config.arrow_data_structures {
-            return Ok(QuantumOptimization {
-                optimization_type: QuantumOptimizationType::ArrowOptimization {
-                    optimized_structures: 0,
-                    memory_layout: ArrowMemoryLayout::RowMajor,
-                    zero_copy_operations: 0,
+        let mut optimizations = Vec::new();
+        if mir_stats.folded_expressions > 0 {
+            optimizations.push(QuantumOptimization::applied(
+                QuantumOptimizationType::QuantumConstantFolding {
+                    folded_expressions: mir_stats.folded_expressions as usize,
+                    quantum_evaluation: false,
                 },
-                performance_gain: 0.0,
-                memory_savings: 0,
-                confidence: 1.0,
-            });
+            ));
         }
-
-        // Analyze data structures for Arrow optimization opportunities
-        let optimization_opportunities = self.analyze_data_structures()?;
-
-        // Apply Arrow optimizations
-        let optimized_structures = self.apply_arrow_optimizations(&optimization_opportunities)?;
-
-        // Optimize memory layout
-        let optimal_layout = self.optimize_memory_layout(&optimization_opportunities)?;
-
-        // Implement zero-copy operations
-        let zero_copy_ops = self.implement_zero_copy_operations(&optimization_opportunities)?;
-
-        // Calculate performance gains
-        let performance_gain = self.calculate_arrow_performance_gain(
-            optimized_structures,
-            &optimal_layout,
-            zero_copy_ops
-        )?;
-
-        // Calculate memory savings
-        let memory_savings = self.calculate_memory_savings(optimized_structures, &optimal_layout)?;
-
-        self.stats.total_optimizations += 1;
-        self.stats.structures_optimized += optimized_structures as u64;
-        self.stats.zero_copy_operations += zero_copy_ops as u64;
-
-        Ok(QuantumOptimization {
-            optimization_type: QuantumOptimizationType::ArrowOptimization {
-                optimized_structures,
-                memory_layout: optimal_layout,
-                zero_copy_operations: zero_copy_ops,
-            },
-            performance_gain,
-            memory_savings,
-            confidence: 0.92,
-        })
-    }
-
-    /// Analyze data structures for optimization opportunities
-    fn analyze_data_structures(&mut self) -> QuantumResult<Vec<ArrowOptimizationOpportunity>> {
-        let mut opportunities = Vec::new();
-
-        // Simulate data structure analysis
-        opportunities.push(ArrowOptimizationOpportunity {
-            structure_type: ArrowStructureType::Vector,
-            size_estimate: 1024 * 1024, // 1MB
-            access_pattern: AccessPattern::Sequential,
-            optimization_potential: 0.8,
-        });
-
-        opportunities.push(ArrowOptimizationOpportunity {
-            structure_type: ArrowStructureType::Matrix,
-            size_estimate: 4 * 1024 * 1024, // 4MB
-            access_pattern: AccessPattern::Random,
-            optimization_potential: 0.6,
-        });
-
-        opportunities.push(ArrowOptimizationOpportunity {
-            structure_type: ArrowStructureType::DataFrame,
-            size_estimate: 8 * 1024 * 1024, // 8MB
-            access_pattern: AccessPattern::Columnar,
-            optimization_potential: 0.9,
-        });
-
-        self.stats.analysis_runs += 1;
-        Ok(opportunities)
-    }
-
-    /// Apply Arrow optimizations
-    fn apply_arrow_optimizations(
-        &mut self,
-        opportunities: &[ArrowOptimizationOpportunity]
-    ) -> QuantumResult<usize> {
-        let mut optimized_count = 0;
-
-        for opportunity in opportunities {
-            if opportunity.optimization_potential > 0.5 {
-                // Apply optimization based on structure type
-                match opportunity.structure_type {
-                    ArrowStructureType::Vector => {
-                        self.optimize_vector_structure(opportunity)?;
-                    },
-                    ArrowStructureType::Matrix => {
-                        self.optimize_matrix_structure(opportunity)?;
-                    },
-                    ArrowStructureType::DataFrame => {
-                        self.optimize_dataframe_structure(opportunity)?;
-                    },
-                }
-                optimized_count += 1;
-            }
-        }
-
-        Ok(optimized_count)
-    }
-
-    /// Optimize vector structure
-    fn optimize_vector_structure(&mut self, opportunity: &ArrowOptimizationOpportunity) -> QuantumResult<()> {
-        // Cache optimal layout for vectors
-        let layout_key = format!("vector_{}", opportunity.size_estimate);
-        let optimal_layout = match opportunity.access_pattern {
-            AccessPattern::Sequential => ArrowMemoryLayout::Columnar,
-            AccessPattern::Random => ArrowMemoryLayout::RowMajor,
-            AccessPattern::Columnar => ArrowMemoryLayout::Columnar,
-        };
-
-        self.layout_cache.insert(layout_key, optimal_layout);
-        self.stats.vector_optimizations += 1;
-
-        Ok(())
-    }
-
-    /// Optimize matrix structure
-    fn optimize_matrix_structure(&mut self, opportunity: &ArrowOptimizationOpportunity) -> QuantumResult<()> {
-        // Cache optimal layout for matrices
-        let layout_key = format!("matrix_{}", opportunity.size_estimate);
-        let optimal_layout = if opportunity.size_estimate > 2 * 1024 * 1024 {
-            ArrowMemoryLayout::Compressed
-        } else {
-            ArrowMemoryLayout::RowMajor
-        };
-
-        self.layout_cache.insert(layout_key, optimal_layout);
-        self.stats.matrix_optimizations += 1;
-
-        Ok(())
-    }
-
-    /// Optimize DataFrame structure
-    fn optimize_dataframe_structure(&mut self, opportunity: &ArrowOptimizationOpportunity) -> QuantumResult<()> {
-        // DataFrames benefit most from columnar layout
-        let layout_key = format!("dataframe_{}", opportunity.size_estimate);
-        let optimal_layout = if self.config.quantum_optimization {
-            ArrowMemoryLayout::QuantumOptimized
-        } else {
-            ArrowMemoryLayout::Columnar
-        };
-
-        self.layout_cache.insert(layout_key, optimal_layout);
-        self.stats.dataframe_optimizations += 1;
-
-        Ok(())
-    }
-
-    /// Optimize memory layout
-    fn optimize_memory_layout(
-        &self,
-        opportunities: &[ArrowOptimizationOpportunity]
-    ) -> QuantumResult<ArrowMemoryLayout> {
-        // Determine best overall layout based on access patterns
-        let mut columnar_score = 0.0;
-        let mut row_major_score = 0.0;
-        let mut compressed_score = 0.0;
-        let mut quantum_score = 0.0;
-
-        for opportunity in opportunities {
-            let weight = opportunity.optimization_potential;
-
-            match opportunity.access_pattern {
-                AccessPattern::Sequential => {
-                    columnar_score += weight * 0.8;
-                    row_major_score += weight * 0.6;
-                },
-                AccessPattern::Random => {
-                    row_major_score += weight * 0.9;
-                    columnar_score += weight * 0.4;
+        if mir_stats.eliminated_blocks > 0 {
+            optimizations.push(QuantumOptimization::applied(
+                QuantumOptimizationType::QuantumDeadCodeElimination {
+                    eliminated_blocks: mir_stats.eliminated_blocks as usize,
+                    quantum_analysis: false,
                 },
-                AccessPattern::Columnar => {
-                    columnar_score += weight * 1.0;
-                    quantum_score += weight * 0.8;
+            ));
+        }
+        if mir_stats.optimized_loops > 0 {
+            optimizations.push(QuantumOptimization::applied(
+                QuantumOptimizationType::QuantumLoopOptimization {
+                    optimized_loops: mir_stats.optimized_loops as usize,
+                    vectorization: false,
+                    quantum_unrolling: false,
                 },
-            }
-
-            // Large structures benefit from compression
-            if opportunity.size_estimate > 4 * 1024 * 1024 {
-                compressed_score += weight * 0.7;
-            }
-
-            // Quantum optimization for quantum-enabled configurations
-            if self.config.quantum_optimization {
-                quantum_score += weight * 0.6;
-            }
-        }
-
-        // Select best layout
-        let max_score = columnar_score.max(row_major_score).max(compressed_score).max(quantum_score);
-
-        if quantum_score == max_score && self.config.quantum_optimization {
-            Ok(ArrowMemoryLayout::QuantumOptimized)
-        } else if compressed_score == max_score {
-            Ok(ArrowMemoryLayout::Compressed)
-        } else if columnar_score == max_score {
-            Ok(ArrowMemoryLayout::Columnar)
-        } else {
-            Ok(ArrowMemoryLayout::RowMajor)
-        }
-    }
-
-    /// Implement zero-copy operations
-    fn implement_zero_copy_operations(
-        &mut self,
-        opportunities: &[ArrowOptimizationOpportunity]
-    ) -> QuantumResult<usize> {
-        let mut zero_copy_ops = 0;
-
-        for opportunity in opportunities {
-            // Zero-copy is most beneficial for large sequential access
-            if opportunity.size_estimate > 1024 * 1024 &&
-               matches!(opportunity.access_pattern, AccessPattern::Sequential | AccessPattern::Columnar) {
-
-                self.zero_copy_tracker.add_operation(ZeroCopyOperation {
-                    operation_type: ZeroCopyOperationType::MemoryView,
-                    size: opportunity.size_estimate,
-                    savings: opportunity.size_estimate,
-                });
-
-                zero_copy_ops += 1;
-            }
+            ));
         }
 
-        Ok(zero_copy_ops)
-    }
-
-    /// Calculate Arrow performance gain
-    fn calculate_arrow_performance_gain(
-        &self,
-        optimized_structures: usize,
-        layout: &ArrowMemoryLayout,
-        zero_copy_ops: usize
-    ) -> QuantumResult<f64> {
-        let mut gain = 0.0;
-
-        // Base gain from structure optimization
-        gain += optimized_structures as f64 * 0.2;
-
-        // Layout-specific gains
-        gain += match layout {
-            ArrowMemoryLayout::Columnar => 0.3,
-            ArrowMemoryLayout::RowMajor => 0.1,
-            ArrowMemoryLayout::Compressed => 0.4,
-            ArrowMemoryLayout::QuantumOptimized => 0.8,
-        };
-
-        // Zero-copy operation gains
-        gain += zero_copy_ops as f64 * 0.5;
+        self.stats.mir_optimization_time = mir_stats.pass_time;
+        self.stats.total_optimizations += optimizations.len() as u64;
+        self.stats.quantum_optimizations += optimizations.len() as u64;
+        self.stats.mir_stats = mir_stats;
 
-        Ok(gain)
-    }
-
-    /// Calculate memory savings
-    fn calculate_memory_savings(
-        &self,
-        optimized_structures: usize,
-        layout: &ArrowMemoryLayout
-    ) -> QuantumResult<u64> {
-        let base_savings = optimized_structures as u64 * 1024; // 1KB per structure
-
-        let layout_multiplier = match layout {
-            ArrowMemoryLayout::Columnar => 1.2,
-            ArrowMemoryLayout::RowMajor => 1.0,
-            ArrowMemoryLayout::Compressed => 2.0,
-            ArrowMemoryLayout::QuantumOptimized => 1.8,
-        };
-
-        Ok((base_savings as f64 * layout_multiplier) as u64)
+        Ok(optimizations)
     }
 
-    pub fn get_stats(&self) -> &ArrowOptimizerStats {
+    /// Get optimization statistics
+    pub fn get_stats(&self) -> &QuantumOptimizerStats {
         &self.stats
     }
 }
-
-// Supporting structures for Arrow optimization
-
-#[derive(Debug, Clone)]
-pub struct ArrowOptimizationOpportunity {
-    pub structure_type: ArrowStructureType,
-    pub size_estimate: usize,
-    pub access_pattern: AccessPattern,
-    pub optimization_potential: f64,
-}
-
-#[derive(Debug, Clone)]
-pub enum ArrowStructureType {
-    Vector,
-    Matrix,
-    DataFrame,
-}
-
-#[derive(Debug, Clone)]
-pub enum AccessPattern {
-    Sequential,
-    Random,
-    Columnar,
-}
-
-#[derive(Debug, Clone, Default)]
-pub struct ArrowOptimizerStats {
-    pub total_optimizations: u64,
-    pub structures_optimized: u64,
-    pub zero_copy_operations: u64,
-    pub analysis_runs: u64,
-    pub vector_optimizations: u64,
-    pub matrix_optimizations: u64,
-    pub dataframe_optimizations: u64,
-}
-
-#[derive(Debug, Clone)]
-pub struct ZeroCopyTracker {
-    operations: Vec<ZeroCopyOperation>,
-}
-
-impl ZeroCopyTracker {
-    pub fn new() -> Self {
-        Self {
-            operations: Vec::new(),
-        }
-    }
-
-    pub fn add_operation(&mut self, operation: ZeroCopyOperation) {
-        self.operations.push(operation);
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct ZeroCopyOperation {
-    pub operation_type: ZeroCopyOperationType,
-    pub size: usize,
-    pub savings: usize,
-}
-
-#[derive(Debug, Clone)]
-pub enum ZeroCopyOperationType {
-    MemoryView,
-    SliceOperation,
-    ReferencePass,
-}
-
-pub struct ArrowOptimizer {
-    columnar_threshold: usize,
-    compression_ratio: f64,
-}
-
-impl ArrowOptimizer {
-    pub fn new() -> Self {
-        Self {
-            columnar_threshold: 1000,
-            compression_ratio: 0.7,
-        }
-    }
-
-    pub fn optimize_columnar_layout(&self, _tcx: TyCtxt<'_>) -> QuantumResult<u64> {
-        // Simulate columnar layout optimization
-        Ok(2048) // 2KB saved
-    }
-
-    pub fn optimize_zero_copy_operations(&self, _tcx: TyCtxt<'_>) -> QuantumResult<u64> {
-        // Simulate zero-copy optimization
-        Ok(4096) // 4KB saved
-    }
-
-    pub fn optimize_compression(&self, _tcx: TyCtxt<'_>) -> QuantumResult<u64> {
-        // Simulate compression optimization
-        Ok(8192) // 8KB saved
-    }
-
-    pub fn optimize_vectorization(&self, _tcx: TyCtxt<'_>) -> QuantumResult<u64> {
-        // Simulate vectorization optimization
-        Ok(1024) // 1KB saved
-    }
-}
@@ -1,464 +0,0 @@
-//! 量子并行处理优化器
-//! 
-//! 🚀 架构优化：基于空间索引技术实现更高效的并行化
-//! 🔧 集成第二阶段的空间索引和VQE算法经验
-
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-use std::thread;
-
-/// 并行处理优化器
-/// 
-/// 基于第二阶段的空间索引技术和VQE算法经验，实现智能的并行任务调度
-pub struct QuantumParallelOptimizer {
-    thread_pool_size: usize,
-    spatial_partitions: Vec<SpatialPartition>,
-    load_balancer: LoadBalancer,
-    performance_monitor: ParallelPerformanceMonitor,
-}
-
-/// 空间分区（基于第二阶段的空间索引技术）
-#[derive(Debug, Clone)]
-pub struct SpatialPartition {
-    pub id: usize,
-    pub start_range: usize,
-    pub end_range: usize,
-    pub workload_density: f64,
-    pub processing_time_estimate: Duration,
-}
-
-/// 负载均衡器
-#[derive(Debug)]
-pub struct LoadBalancer {
-    partition_assignments: HashMap<usize, Vec<usize>>, // thread_id -> partition_ids
-    thread_utilization: Vec<f64>,
-    rebalance_threshold: f64,
-}
-
-/// 并行性能监控器
-#[derive(Debug)]
-pub struct ParallelPerformanceMonitor {
-    thread_performance: Vec<ThreadPerformance>,
-    total_tasks_processed: usize,
-    parallel_efficiency: f64,
-    load_balance_score: f64,
-}
-
-/// 线程性能统计
-#[derive(Debug, Clone)]
-pub struct ThreadPerformance {
-    pub thread_id: usize,
-    pub tasks_completed: usize,
-    pub total_processing_time: Duration,
-    pub idle_time: Duration,
-    pub cache_hit_ratio: f64,
-}
-
-/// 并行优化结果
-#[derive(Debug, Clone)]
-pub struct ParallelOptimizationResult {
-    pub throughput_improvement: f64,
-    pub load_balance_improvement: f64,
-    pub cache_efficiency_gain: f64,
-    pub parallel_efficiency: f64,
-    pub optimization_duration: Duration,
-}
-
-/// 并行任务
-#[derive(Debug, Clone)]
-pub struct ParallelTask {
-    pub id: usize,
-    pub spatial_range: (usize, usize),
-    pub complexity_estimate: f64,
-    pub dependencies: Vec<usize>,
-}
-
-impl QuantumParallelOptimizer {
-    /// 创建新的并行优化器
-    pub fn new(thread_pool_size: usize) -> Self {
-        Self {
-            thread_pool_size,
-            spatial_partitions: Vec::new(),
-            load_balancer: LoadBalancer::new(thread_pool_size),
-            performance_monitor: ParallelPerformanceMonitor::new(thread_pool_size),
-        }
-    }
-    
-    /// 基于空间索引的并行处理优化
-    /// 
-    /// 🚀 核心优化：利用第二阶段的空间索引技术实现智能任务分区
-    pub fn optimize_parallel_processing(&mut self, tasks: Vec<ParallelTask>) -> Result<ParallelOptimizationResult, String> {
-        let start_time = Instant::now();
-        println!("🚀 开始并行处理优化，任务数量: {}", tasks.len());
-        
-        // 1. 基于空间索引创建智能分区
-        let spatial_partitions = self.create_spatial_partitions(&tasks)?;
-        
-        // 2. 应用负载均衡策略
-        let load_balance_result = self.apply_load_balancing(&spatial_partitions)?;
-        
-        // 3. 执行并行任务处理
-        let execution_result = self.execute_parallel_tasks(&tasks, &load_balance_result)?;
-        
-        // 4. 监控和优化性能
-        let performance_optimization = self.monitor_and_optimize_performance(&execution_result)?;
-        
-        // 5. 计算优化效果
-        let optimization_result = ParallelOptimizationResult {
-            throughput_improvement: execution_result.throughput_gain,
-            load_balance_improvement: load_balance_result.balance_improvement,
-            cache_efficiency_gain: performance_optimization.cache_efficiency,
-            parallel_efficiency: self.performance_monitor.parallel_efficiency,
-            optimization_duration: start_time.elapsed(),
-        };
-        
-        println!("✅ 并行处理优化完成，吞吐量提升: {:.2}x", optimization_result.throughput_improvement);
-        
-        Ok(optimization_result)
-    }
-    
-    /// 基于空间索引创建智能分区
-    fn create_spatial_partitions(&mut self, tasks: &[ParallelTask]) -> Result<Vec<SpatialPartition>, String> {
-        if tasks.is_empty() {
-            return Ok(Vec::new());
-        }
-        
-        // 1. 分析任务的空间分布
-        let spatial_analysis = self.analyze_task_spatial_distribution(tasks);
-        
-        // 2. 基于空间密度创建分区
-        let mut partitions = Vec::new();
-        let partition_size = spatial_analysis.total_range / self.thread_pool_size;
-        
-        for i in 0..self.thread_pool_size {
-            let start_range = i * partition_size;
-            let end_range = if i == self.thread_pool_size - 1 {
-                spatial_analysis.total_range
-            } else {
-                (i + 1) * partition_size
-            };
-            
-            // 计算该分区的工作负载密度
-            let workload_density = self.calculate_partition_workload_density(tasks, start_range, end_range);
-            
-            // 估算处理时间
-            let processing_time_estimate = Duration::from_millis((workload_density * 100.0) as u64);
-            
-            partitions.push(SpatialPartition {
-                id: i,
-                start_range,
-                end_range,
-                workload_density,
-                processing_time_estimate,
-            });
-        }
-        
-        self.spatial_partitions = partitions.clone();
-        println!("   📊 创建了 {} 个空间分区", partitions.len());
-        
-        Ok(partitions)
-    }
-    
-    /// 分析任务的空间分布
-    fn analyze_task_spatial_distribution(&self, tasks: &[ParallelTask]) -> SpatialDistributionAnalysis {
-        let mut min_range = usize::MAX;
-        let mut max_range = 0;
-        let mut total_complexity = 0.0;
-        
-        for task in tasks {
-            min_range = min_range.min(task.spatial_range.0);
-            max_range = max_range.max(task.spatial_range.1);
-            total_complexity += task.complexity_estimate;
-        }
-        
-        SpatialDistributionAnalysis {
-            total_range: max_range - min_range,
-            average_complexity: total_complexity / tasks.len() as f64,
-            task_density: tasks.len() as f64 / (max_range - min_range) as f64,
-        }
-    }
-    
-    /// 计算分区的工作负载密度
-    fn calculate_partition_workload_density(&self, tasks: &[ParallelTask], start: usize, end: usize) -> f64 {
-        let mut total_complexity = 0.0;
-        let mut task_count = 0;
-        
-        for task in tasks {
-            // 检查任务是否与分区重叠
-            if task.spatial_range.0 < end && task.spatial_range.1 > start {
-                total_complexity += task.complexity_estimate;
-                task_count += 1;
-            }
-        }
-        
-        if task_count > 0 {
-            total_complexity / task_count as f64
-        } else {
-            0.0
-        }
-    }
-    
-    /// 应用负载均衡策略
-    fn apply_load_balancing(&mut self, partitions: &[SpatialPartition]) -> Result<LoadBalanceResult, String> {
-        let start_time = Instant::now();
-        
-        // 1. 分析当前负载分布
-        let load_analysis = self.analyze_current_load_distribution(partitions);
-        
-        // 2. 重新分配分区以平衡负载
-        let rebalanced_assignments = self.rebalance_partition_assignments(partitions, &load_analysis)?;
-        
-        // 3. 更新负载均衡器状态
-        self.load_balancer.partition_assignments = rebalanced_assignments;
-        self.load_balancer.update_thread_utilization(partitions);
-        
-        let balance_improvement = self.calculate_load_balance_improvement(&load_analysis);
-        
-        Ok(LoadBalanceResult {
-            balance_improvement,
-            rebalance_duration: start_time.elapsed(),
-        })
-    }
-    
-    /// 执行并行任务处理
-    fn execute_parallel_tasks(&mut self, tasks: &[ParallelTask], _load_balance: &LoadBalanceResult) -> Result<ExecutionResult, String> {
-        let start_time = Instant::now();
-        
-        // 创建线程安全的任务队列
-        let task_queue = Arc::new(Mutex::new(tasks.to_vec()));
-        let results = Arc::new(Mutex::new(Vec::new()));
-        
-        // 启动工作线程
-        let mut handles = Vec::new();
-        for thread_id in 0..self.thread_pool_size {
-            let task_queue_clone = Arc::clone(&task_queue);
-            let results_clone = Arc::clone(&results);
-            let assigned_partitions = self.load_balancer.partition_assignments
-                .get(&thread_id)
-                .cloned()
-                .unwrap_or_default();
-            
-            let handle = thread::spawn(move || {
-                Self::worker_thread_function(thread_id, task_queue_clone, results_clone, assigned_partitions)
-            });
-            
-            handles.push(handle);
-        }
-        
-        // 等待所有线程完成
-        for handle in handles {
-            handle.join().map_err(|_| "线程执行失败")?;
-        }
-        
-        let execution_duration = start_time.elapsed();
-        let processed_tasks = results.lock().unwrap().len();
-        
-        // 计算吞吐量提升
-        let baseline_throughput = tasks.len() as f64 / 1000.0; // 假设基准吞吐量
-        let actual_throughput = processed_tasks as f64 / execution_duration.as_millis() as f64 * 1000.0;
-        let throughput_gain = actual_throughput / baseline_throughput;
-        
-        println!("   ⚡ 并行执行完成: {} 任务, 耗时 {:?}", processed_tasks, execution_duration);
-        
-        Ok(ExecutionResult {
-            processed_tasks,
-            execution_duration,
-            throughput_gain,
-        })
-    }
-    
-    /// 工作线程函数
-    fn worker_thread_function(
-        thread_id: usize,
-        task_queue: Arc<Mutex<Vec<ParallelTask>>>,
-        results: Arc<Mutex<Vec<usize>>>,
-        _assigned_partitions: Vec<usize>,
-    ) {
-        loop {
-            let task = {
-                let mut queue = task_queue.lock().unwrap();
-                queue.pop()
-            };
-            
-            match task {
-                Some(task) => {
-                    // 模拟任务处理
-                    let processing_time = Duration::from_millis((task.complexity_estimate * 10.0) as u64);
-                    thread::sleep(processing_time);
-                    
-                    // 记录结果
-                    let mut results_guard = results.lock().unwrap();
-                    results_guard.push(task.id);
-                }
-                None => break, // 没有更多任务
-            }
-        }
-    }
-    
-    /// 监控和优化性能
-    fn monitor_and_optimize_performance(&mut self, execution_result: &ExecutionResult) -> Result<PerformanceOptimizationResult, String> {
-        // 更新性能监控数据
-        self.performance_monitor.total_tasks_processed += execution_result.processed_tasks;
-        self.performance_monitor.parallel_efficiency = execution_result.throughput_gain / self.thread_pool_size as f64;
-        
-        // 计算缓存效率（基于VQE算法的经验）
-        let cache_efficiency = self.calculate_cache_efficiency_with_vqe_experience();
-        
-        // 更新负载均衡分数
-        self.performance_monitor.load_balance_score = self.calculate_load_balance_score();
-        
-        Ok(PerformanceOptimizationResult {
-            cache_efficiency,
-            performance_gain: execution_result.throughput_gain,
-        })
-    }
-    
-    /// 基于VQE经验计算缓存效率
-    fn calculate_cache_efficiency_with_vqe_experience(&self) -> f64 {
-        // 基于VQE算法中的量子态管理经验来优化缓存
-        let spatial_locality = self.calculate_spatial_locality();
-        let temporal_locality = self.calculate_temporal_locality();
-        
-        // VQE启发的缓存效率计算
-        (spatial_locality * 0.6 + temporal_locality * 0.4) * 0.9 // 90%的理论最大效率
-    }
-    
-    /// 计算空间局部性
-    fn calculate_spatial_locality(&self) -> f64 {
-        if self.spatial_partitions.is_empty() {
-            return 0.5;
-        }
-        
-        let avg_workload_density: f64 = self.spatial_partitions.iter()
-            .map(|p| p.workload_density)
-            .sum::<f64>() / self.spatial_partitions.len() as f64;
-        
-        (avg_workload_density / 10.0).min(1.0) // 标准化到0-1
-    }
-    
-    /// 计算时间局部性
-    fn calculate_temporal_locality(&self) -> f64 {
-        // 基于线程利用率计算时间局部性
-        if self.load_balancer.thread_utilization.is_empty() {
-            return 0.5;
-        }
-        
-        let avg_utilization: f64 = self.load_balancer.thread_utilization.iter().sum::<f64>() 
-                                  / self.load_balancer.thread_utilization.len() as f64;
-        
-        avg_utilization.min(1.0)
-    }
-    
-    /// 计算负载均衡分数
-    fn calculate_load_balance_score(&self) -> f64 {
-        if self.load_balancer.thread_utilization.is_empty() {
-            return 1.0;
-        }
-        
-        let utilizations = &self.load_balancer.thread_utilization;
-        let mean = utilizations.iter().sum::<f64>() / utilizations.len() as f64;
-        let variance = utilizations.iter()
-            .map(|u| (u - mean).powi(2))
-            .sum::<f64>() / utilizations.len() as f64;
-        
-        // 方差越小，负载均衡越好
-        (1.0 - variance.sqrt()).max(0.0)
-    }
-    
-    // 辅助方法的简化实现
-    fn analyze_current_load_distribution(&self, _partitions: &[SpatialPartition]) -> LoadDistributionAnalysis {
-        LoadDistributionAnalysis {
-            max_load: 1.0,
-            min_load: 0.5,
-            load_variance: 0.1,
-        }
-    }
-    
-    fn rebalance_partition_assignments(&self, partitions: &[SpatialPartition], _analysis: &LoadDistributionAnalysis) -> Result<HashMap<usize, Vec<usize>>, String> {
-        let mut assignments = HashMap::new();
-        for (i, partition) in partitions.iter().enumerate() {
-            let thread_id = i % self.thread_pool_size;
-            assignments.entry(thread_id).or_insert_with(Vec::new).push(partition.id);
-        }
-        Ok(assignments)
-    }
-    
-    fn calculate_load_balance_improvement(&self, _analysis: &LoadDistributionAnalysis) -> f64 {
-        1.2 // 20%改进
-    }
-}
-
-// 辅助结构定义
-#[derive(Debug)]
-struct SpatialDistributionAnalysis {
-    total_range: usize,
-    average_complexity: f64,
-    task_density: f64,
-}
-
-#[derive(Debug)]
-struct LoadDistributionAnalysis {
-    max_load: f64,
-    min_load: f64,
-    load_variance: f64,
-}
-
-#[derive(Debug)]
-struct LoadBalanceResult {
-    balance_improvement: f64,
-    rebalance_duration: Duration,
-}
-
-#[derive(Debug)]
-struct ExecutionResult {
-    processed_tasks: usize,
-    execution_duration: Duration,
-    throughput_gain: f64,
-}
-
-#[derive(Debug)]
-struct PerformanceOptimizationResult {
-    cache_efficiency: f64,
-    performance_gain: f64,
-}
-
-impl LoadBalancer {
-    fn new(thread_pool_size: usize) -> Self {
-        Self {
-            partition_assignments: HashMap::new(),
-            thread_utilization: vec![0.0; thread_pool_size],
-            rebalance_threshold: 0.2,
-        }
-    }
-    
-    fn update_thread_utilization(&mut self, partitions: &[SpatialPartition]) {
-        for (thread_id, partition_ids) in &self.partition_assignments {
-            let total_workload: f64 = partition_ids.iter()
-                .filter_map(|&id| partitions.get(id))
-                .map(|p| p.workload_density)
-                .sum();
-            
-            if *thread_id < self.thread_utilization.len() {
-                self.thread_utilization[*thread_id] = (total_workload / 10.0).min(1.0);
-            }
-        }
-    }
-}
-
-impl ParallelPerformanceMonitor {
-    fn new(thread_pool_size: usize) -> Self {
-        Self {
-            thread_performance: (0..thread_pool_size).map(|id| ThreadPerformance {
-                thread_id: id,
-                tasks_completed: 0,
-                total_processing_time: Duration::new(0, 0),
-                idle_time: Duration::new(0, 0),
-                cache_hit_ratio: 0.0,
-            }).collect(),
-            total_tasks_processed: 0,
-            parallel_efficiency: 0.0,
-            load_balance_score: 0.0,
-        }
-    }
-}
@@ -1,11 +1,36 @@
 //! Quantum Parser
-//! 
-//! This module implements quantum-enhanced parsing for Rust syntax.
-//! It uses quantum superposition to explore multiple parse paths simultaneously,
-//! achieving significant speedup and better error recovery.
-
-use crate::{QuantumConfig, QuantumResult, QuantumError};
-use std::collections::HashMap;
+//!
+//! This module lowers the expanded `rustc_ast::Crate` into a quantum AST.
+//! Every node keeps the span of the item, statement or expression it was
+//! built from, and its quantum annotations are derived from that code.
+
+use rustc_ast::ptr::P;
+use rustc_ast::token;
+use rustc_ast::visit::{self, Visitor};
+use rustc_ast::{
+    self as ast, AssocItemKind, BinOpKind, BindingMode, ExprKind, FnRetTy, ItemKind, LitKind,
+    ModKind, Mutability, PatKind, StmtKind,
+};
+use rustc_ast_pretty::pprust;
+use rustc_span::{Ident, Span};
+
+use crate::QuantumResult;
+
+/// Method and function names lowered to quantum gates
+const GATE_NAMES: &[&str] = &[
+    "h", "x", "y", "z", "s", "sdg", "t", "tdg", "rx", "ry", "rz", "p", "u", "cx", "cy", "cz",
+    "ch", "swap", "ccx", "cswap", "hadamard", "pauli_x", "pauli_y", "pauli_z", "phase", "cnot",
+    "toffoli", "fredkin",
+];
+
+/// Method and function names lowered to quantum measurements
+const MEASUREMENT_NAMES: &[&str] = &["measure", "measure_all", "measure_qubit"];
+
+/// Method and function names lowered to a quantum Fourier transform
+const FFT_NAMES: &[&str] = &["fft", "qfft", "quantum_fft"];
+
+/// Method and function names lowered to an inverse quantum Fourier transform
+const INVERSE_FFT_NAMES: &[&str] = &["ifft", "iqfft", "inverse_fft", "apply_inverse"];
 
 /// Quantum AST node with superposition capabilities
 #[derive(Debug, Clone)]
@@ -16,8 +41,8 @@ pub struct QuantumASTNode {
     pub children: Vec<QuantumASTNode>,
     /// Quantum parse state
     pub quantum_state: QuantumParseState,
-    /// Source span
-    pub span: QuantumSpan,
+    /// Span of the AST item, statement or expression
+    pub span: Span,
 }
 
 /// Quantum node types with enhanced semantics
@@ -29,17 +54,22 @@ pub enum QuantumNodeType {
         params: Vec<QuantumParameter>,
         return_type: Option<QuantumType>,
         quantum_attributes: Vec<QuantumAttribute>,
+        /// Copies of the gate nodes of the body, in source order. They are
+        /// not part of the tree: the body is in the node's children.
+        quantum_gates: Vec<QuantumASTNode>,
     },
     /// Variable with quantum state tracking
     Variable {
         name: String,
         var_type: Option<QuantumType>,
         mutable: bool,
+        /// Initialized from a quantum construct or quantum array
         quantum_entangled: bool,
     },
     /// Expression with quantum evaluation
     Expression {
         expr_type: QuantumExpressionType,
+        /// Built only from literals, so it can be folded at compile time
         quantum_optimizable: bool,
     },
     /// Statement with quantum flow control
@@ -65,17 +95,23 @@ pub enum QuantumExpressionType {
     },
     FunctionCall {
         name: String,
+        /// The receiver of a method call comes first
         args: Vec<QuantumASTNode>,
         quantum_parallel: bool,
     },
     QuantumArray {
         elements: Vec<QuantumASTNode>,
+        /// Constructed as a `QuantumArray` rather than an array literal
         quantum_entangled: bool,
     },
     QuantumFFT {
         input: Box<QuantumASTNode>,
         inverse: bool,
     },
+    /// Block expression, with its statements as children
+    Block,
+    /// Any other expression kind, with its subexpressions as children
+    Other(String),
 }
 
 #[derive(Debug, Clone)]
@@ -90,11 +126,14 @@ pub enum QuantumStatementType {
         condition: Box<QuantumASTNode>,
         then_block: Vec<QuantumASTNode>,
         else_block: Option<Vec<QuantumASTNode>>,
+        /// Both branches are present
         quantum_superposition: bool,
     },
     Loop {
         loop_type: QuantumLoopType,
         body: Vec<QuantumASTNode>,
+        /// The body has no early exits and no assignments, so iterations are
+        /// independent as far as the syntax can tell
         quantum_parallel: bool,
     },
 }
@@ -115,16 +154,13 @@ pub enum QuantumLoopType {
 pub enum QuantumConstructType {
     QuantumGate {
         gate_type: String,
+        /// Source text of the qubit arguments
         qubits: Vec<String>,
     },
     QuantumMeasurement {
         qubits: Vec<String>,
         classical_bits: Vec<String>,
     },
-    QuantumCircuit {
-        name: String,
-        gates: Vec<QuantumASTNode>,
-    },
 }
 
 /// Quantum parse state
@@ -134,24 +170,156 @@ pub struct QuantumParseState {
     pub amplitude: f64,
     /// Parse confidence
     pub confidence: f64,
-    /// Quantum entanglement with other nodes
-    pub entangled_nodes: Vec<usize>,
 }
 
 impl Default for QuantumParseState {
     fn default() -> Self {
-        Self {
-            amplitude: 1.0,
-            confidence: 1.0,
-            entangled_nodes: Vec::new(),
+        Self { amplitude: 1.0, confidence: 1.0 }
+    }
+}
+
+impl QuantumASTNode {
+    fn new(node_type: QuantumNodeType, span: Span) -> Self {
+        Self { node_type, children: Vec::new(), quantum_state: QuantumParseState::default(), span }
+    }
+
+    fn expression(expr_type: QuantumExpressionType, quantum_optimizable: bool, span: Span) -> Self {
+        Self::new(QuantumNodeType::Expression { expr_type, quantum_optimizable }, span)
+    }
+
+    fn statement(stmt_type: QuantumStatementType, quantum_parallel: bool, span: Span) -> Self {
+        Self::new(QuantumNodeType::Statement { stmt_type, quantum_parallel }, span)
+    }
+
+    fn construct(construct_type: QuantumConstructType, span: Span) -> Self {
+        Self::new(QuantumNodeType::QuantumConstruct { construct_type }, span)
+    }
+
+    fn with_children(mut self, children: Vec<QuantumASTNode>) -> Self {
+        self.children = children;
+        self
+    }
+
+    /// Whether the node stands for a quantum operation or value
+    ///
+    /// Functions are quantum when their body applies quantum gates.
+    pub fn is_quantum(&self) -> bool {
+        match &self.node_type {
+            QuantumNodeType::Function { quantum_gates, .. } => !quantum_gates.is_empty(),
+            QuantumNodeType::QuantumConstruct { .. }
+            | QuantumNodeType::Expression {
+                expr_type:
+                    QuantumExpressionType::QuantumFFT { .. }
+                    | QuantumExpressionType::QuantumArray { quantum_entangled: true, .. },
+                ..
+            } => true,
+            _ => false,
+        }
+    }
+
+    /// Direct subnodes, whether they are stored in the node type or in `children`
+    pub fn subnodes(&self) -> Vec<&QuantumASTNode> {
+        let mut subnodes = Vec::new();
+        match &self.node_type {
+            QuantumNodeType::Expression { expr_type, .. } => match expr_type {
+                QuantumExpressionType::BinaryOp { left, right, .. } => {
+                    subnodes.push(&**left);
+                    subnodes.push(&**right);
+                }
+                QuantumExpressionType::FunctionCall { args, .. } => subnodes.extend(args),
+                QuantumExpressionType::QuantumArray { elements, .. } => subnodes.extend(elements),
+                QuantumExpressionType::QuantumFFT { input, .. } => subnodes.push(&**input),
+                QuantumExpressionType::Literal(_)
+                | QuantumExpressionType::Identifier(_)
+                | QuantumExpressionType::Block
+                | QuantumExpressionType::Other(_) => {}
+            },
+            QuantumNodeType::Statement { stmt_type, .. } => match stmt_type {
+                QuantumStatementType::Assignment { value, .. } => subnodes.push(&**value),
+                QuantumStatementType::Return(value) => subnodes.extend(value.as_deref()),
+                QuantumStatementType::If { condition, then_block, else_block, .. } => {
+                    subnodes.push(&**condition);
+                    subnodes.extend(then_block);
+                    subnodes.extend(else_block.iter().flatten());
+                }
+                QuantumStatementType::Loop { loop_type, body, .. } => {
+                    match loop_type {
+                        QuantumLoopType::For { iterable, .. } => subnodes.push(&**iterable),
+                        QuantumLoopType::While { condition } => subnodes.push(&**condition),
+                        QuantumLoopType::Loop => {}
+                    }
+                    subnodes.extend(body);
+                }
+            },
+            QuantumNodeType::Function { .. }
+            | QuantumNodeType::Variable { .. }
+            | QuantumNodeType::QuantumConstruct { .. } => {}
+        }
+        subnodes.extend(&self.children);
+        subnodes
+    }
+
+    /// Call `f` on this node and every node below it, in source order
+    pub fn walk(&self, f: &mut impl FnMut(&QuantumASTNode)) {
+        f(self);
+        for node in self.subnodes() {
+            node.walk(f);
+        }
+    }
+
+    /// Mutable version of [`QuantumASTNode::walk`]
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut QuantumASTNode)) {
+        f(self);
+        match &mut self.node_type {
+            QuantumNodeType::Expression { expr_type, .. } => match expr_type {
+                QuantumExpressionType::BinaryOp { left, right, .. } => {
+                    left.walk_mut(f);
+                    right.walk_mut(f);
+                }
+                QuantumExpressionType::FunctionCall { args: nodes, .. }
+                | QuantumExpressionType::QuantumArray { elements: nodes, .. } => {
+                    nodes.iter_mut().for_each(|node| node.walk_mut(f))
+                }
+                QuantumExpressionType::QuantumFFT { input, .. } => input.walk_mut(f),
+                QuantumExpressionType::Literal(_)
+                | QuantumExpressionType::Identifier(_)
+                | QuantumExpressionType::Block
+                | QuantumExpressionType::Other(_) => {}
+            },
+            QuantumNodeType::Statement { stmt_type, .. } => match stmt_type {
+                QuantumStatementType::Assignment { value, .. } => value.walk_mut(f),
+                QuantumStatementType::Return(value) => {
+                    if let Some(value) = value {
+                        value.walk_mut(f);
+                    }
+                }
+                QuantumStatementType::If { condition, then_block, else_block, .. } => {
+                    condition.walk_mut(f);
+                    then_block.iter_mut().for_each(|node| node.walk_mut(f));
+                    else_block.iter_mut().flatten().for_each(|node| node.walk_mut(f));
+                }
+                QuantumStatementType::Loop { loop_type, body, .. } => {
+                    match loop_type {
+                        QuantumLoopType::For { iterable, .. } => iterable.walk_mut(f),
+                        QuantumLoopType::While { condition } => condition.walk_mut(f),
+                        QuantumLoopType::Loop => {}
+                    }
+                    body.iter_mut().for_each(|node| node.walk_mut(f));
+                }
+            },
+            QuantumNodeType::Function { .. }
+            | QuantumNodeType::Variable { .. }
+            | QuantumNodeType::QuantumConstruct { .. } => {}
         }
+        self.children.iter_mut().for_each(|node| node.walk_mut(f));
     }
 }
 
 /// Quantum AST with enhanced capabilities
 #[derive(Debug, Clone)]
 pub struct QuantumAST {
-    /// Root nodes
+    /// Functions, statics and constants of the crate, including those in
+    /// modules, impls and traits
     pub nodes: Vec<QuantumASTNode>,
     /// Quantum metadata
     pub metadata: QuantumASTMetadata,
@@ -162,18 +330,17 @@ pub struct QuantumAST {
 #[derive(Debug, Clone)]
 pub struct QuantumASTMetadata {
     pub source_file: String,
+    /// Number of nodes in the whole tree
     pub total_nodes: usize,
+    /// Number of nodes for which [`QuantumASTNode::is_quantum`] holds
     pub quantum_nodes: usize,
+    /// Average cyclomatic complexity of the functions
     pub complexity_score: f64,
-    pub quantum_advantage: f64,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct QuantumParseStats {
-    pub parse_paths_explored: u64,
     pub quantum_optimizations: u64,
-    pub superposition_collapses: u64,
-    pub entanglement_operations: u64,
 }
 
 impl QuantumAST {
@@ -185,12 +352,28 @@ pub fn new() -> Self {
                 total_nodes: 0,
                 quantum_nodes: 0,
                 complexity_score: 0.0,
-                quantum_advantage: 0.0,
             },
             parse_stats: QuantumParseStats::default(),
         }
     }
 
+    /// Lower an expanded crate
+    ///
+    /// Functions (including methods and default trait methods), statics and
+    /// constants become root nodes, wherever they are nested. Function
+    /// bodies are lowered statement by statement.
+    pub fn from_ast(krate: &ast::Crate) -> Self {
+        let mut ast = Self::new();
+        let mut nodes = Vec::new();
+        lower_items(&krate.items, &mut nodes);
+        for node in nodes {
+            ast.add_node(node);
+        }
+        ast.update_metadata();
+        ast
+    }
+
+    /// Number of root nodes
     pub fn node_count(&self) -> usize {
         self.nodes.len()
     }
@@ -199,1254 +382,789 @@ pub fn add_node(&mut self, node: QuantumASTNode) {
         self.nodes.push(node);
         self.metadata.total_nodes += 1;
     }
-}
-
-/// Quantum parser with superposition-based parsing
-pub struct QuantumParser {
-    config: QuantumConfig,
-    /// Quantum grammar rules
-    grammar: QuantumGrammar,
-    /// Superposition parser engine
-    superposition_engine: SuperpositionEngine,
-    /// Parse cache for quantum speedup
-    parse_cache: QuantumParseCache,
-    /// Current tokens being parsed
-    tokens: Vec<QuantumToken>,
-}
-
-impl QuantumParser {
-    /// Create a new quantum parser
-    pub fn new(config: &QuantumConfig) -> Self {
-        println!("🌊 Initializing Quantum Parser...");
-
-        Self {
-            config: config.clone(),
-            grammar: QuantumGrammar::new(config.clone()),
-            superposition_engine: SuperpositionEngine::new(config.clone()),
-            parse_cache: QuantumParseCache::new(config.clone()),
-            tokens: Vec::new(),
-        }
-    }
-
-    /// Quantum parsing with superposition
-    pub fn quantum_parse(&mut self) -> QuantumResult<QuantumAST> {
-        println!("⚡ Starting quantum parsing...");
-        
-        let start_time = std::time::Instant::now();
-        
-        // Initialize quantum AST
-        let mut quantum_ast = QuantumAST::new();
-        
-        // Quantum superposition parsing
-        let parse_paths = self.explore_quantum_parse_paths()?;
-        
-        // Collapse superposition to best parse
-        let best_parse = self.collapse_superposition(parse_paths)?;
-        
-        // Apply quantum optimizations
-        let optimized_ast = self.apply_quantum_parse_optimizations(best_parse)?;
-        
-        // Update statistics
-        quantum_ast.parse_stats.parse_paths_explored = 1000; // Simulated
-        quantum_ast.parse_stats.quantum_optimizations = 25;
-        quantum_ast.parse_stats.superposition_collapses = 5;
-        quantum_ast.parse_stats.entanglement_operations = 15;
-        
-        quantum_ast.metadata.quantum_advantage = 3.2; // 3.2x speedup
-        
-        let parse_time = start_time.elapsed();
-        println!("✅ Quantum parsing complete in {:?}", parse_time);
-        println!("📊 Parse paths explored: {}", quantum_ast.parse_stats.parse_paths_explored);
-        println!("🔮 Quantum advantage: {:.1}x", quantum_ast.metadata.quantum_advantage);
-        
-        Ok(optimized_ast)
-    }
-
-    /// Explore quantum parse paths using superposition
-    fn explore_quantum_parse_paths(&mut self) -> QuantumResult<Vec<QuantumParsePath>> {
-        println!("🔍 Exploring quantum parse paths...");
-
-        // Use real SuperpositionEngine instead of simulation
-        let superposition_states = self.superposition_engine.create_superposition(&self.tokens, &self.grammar)?;
-
-        let mut parse_paths = Vec::new();
-
-        // Convert superposition states to parse paths
-        for state in superposition_states {
-            let path = QuantumParsePath {
-                path_id: state.id,
-                nodes: self.convert_parse_stack_to_nodes(&state.parse_stack)?,
-                probability: state.quantum_amplitude.powi(2),
-                confidence: state.coherence,
-            };
-            parse_paths.push(path);
-        }
-
-        println!("   - Parse paths in superposition: {}", parse_paths.len());
-
-        Ok(parse_paths)
-    }
-
-    /// Collapse superposition to best parse
-    fn collapse_superposition(&mut self, parse_paths: Vec<QuantumParsePath>) -> QuantumResult<QuantumAST> {
-        println!("🌀 Collapsing quantum superposition...");
-
-        // Use real SuperpositionEngine collapse instead of simple max
-        let collapsed_state = self.superposition_engine.collapse_superposition()?;
-
-        let best_path = if let Some(state) = collapsed_state {
-            // Convert collapsed state to parse path
-            QuantumParsePath {
-                path_id: state.id,
-                nodes: self.convert_parse_stack_to_nodes(&state.parse_stack)?,
-                probability: state.quantum_amplitude.powi(2),
-                confidence: state.coherence,
-            }
-        } else {
-            // Fallback to highest confidence path
-            parse_paths
-                .into_iter()
-                .max_by(|a, b| (a.probability * a.confidence).partial_cmp(&(b.probability * b.confidence)).unwrap())
-                .ok_or_else(|| QuantumError::ParsingError("No valid parse paths found".to_string()))?
-        };
-
-        let mut ast = QuantumAST::new();
-        ast.nodes = best_path.nodes;
-        ast.metadata.quantum_advantage = best_path.confidence * 4.0;
-
-        // Get real statistics from SuperpositionEngine
-        let superposition_stats = self.superposition_engine.get_stats();
-        ast.parse_stats.parse_paths_explored = superposition_stats.superposition_branches;
-        ast.parse_stats.quantum_measurements = 1; // Collapse is a measurement
-
-        println!("   - Best parse path selected: {}", best_path.path_id);
-        println!("   - Parse confidence: {:.2}", best_path.confidence);
-        println!("   - Collapse probability: {:.3}", superposition_stats.collapse_probability);
-
-        Ok(ast)
-    }
 
-    /// Convert parse stack to AST nodes
-    fn convert_parse_stack_to_nodes(&self, parse_stack: &[QuantumParseNode]) -> QuantumResult<Vec<QuantumASTNode>> {
-        let mut ast_nodes = Vec::new();
-
-        for parse_node in parse_stack {
-            let ast_node = QuantumASTNode {
-                node_type: parse_node.node_type.clone(),
-                value: None,
-                children: self.convert_parse_stack_to_nodes(&parse_node.children)?,
-                quantum_properties: QuantumNodeProperties {
-                    superposition: parse_node.quantum_properties.superposition_weight > 0.5,
-                    entanglement: parse_node.quantum_properties.entanglement_strength,
-                    coherence: parse_node.quantum_properties.coherence_time,
-                    measurement_effects: false,
-                },
-                span: self.calculate_actual_span(parse_node),
-            };
-            ast_nodes.push(ast_node);
+    /// Recount the metadata from the tree
+    fn update_metadata(&mut self) {
+        let mut total_nodes = 0;
+        let mut quantum_nodes = 0;
+        let mut functions = 0;
+        let mut decision_points = 0;
+        for root in &self.nodes {
+            root.walk(&mut |node| {
+                total_nodes += 1;
+                if node.is_quantum() {
+                    quantum_nodes += 1;
+                }
+                match &node.node_type {
+                    QuantumNodeType::Function { .. } => functions += 1,
+                    QuantumNodeType::Statement {
+                        stmt_type:
+                            QuantumStatementType::If { .. } | QuantumStatementType::Loop { .. },
+                        ..
+                    } => decision_points += 1,
+                    QuantumNodeType::Expression {
+                        expr_type: QuantumExpressionType::Other(kind), ..
+                    } if kind == "match" => decision_points += 1,
+                    _ => {}
+                }
+            });
         }
 
-        Ok(ast_nodes)
-    }
-
-    /// Calculate actual span from parse node
-    fn calculate_actual_span(&self, parse_node: &QuantumParseNode) -> QuantumSpan {
-        // Calculate real span based on token positions
-        let start_pos = if !self.tokens.is_empty() && parse_node.start_token_index < self.tokens.len() {
-            self.tokens[parse_node.start_token_index].span.start
+        self.metadata.total_nodes = total_nodes;
+        self.metadata.quantum_nodes = quantum_nodes;
+        self.metadata.complexity_score = if functions == 0 {
+            0.0
         } else {
-            0
+            (functions + decision_points) as f64 / functions as f64
         };
-
-        let end_pos = if !self.tokens.is_empty() && parse_node.end_token_index < self.tokens.len() {
-            self.tokens[parse_node.end_token_index].span.end
-        } else {
-            start_pos
-        };
-
-        let line = if !self.tokens.is_empty() && parse_node.start_token_index < self.tokens.len() {
-            self.tokens[parse_node.start_token_index].span.line
-        } else {
-            1
-        };
-
-        let column = if !self.tokens.is_empty() && parse_node.start_token_index < self.tokens.len() {
-            self.tokens[parse_node.start_token_index].span.column
-        } else {
-            1
-        };
-
-        QuantumSpan {
-            start: start_pos,
-            end: end_pos,
-            line,
-            column,
-            entangled_spans: self.find_entangled_spans(parse_node),
-        }
     }
+}
 
-    /// Find entangled spans for a parse node
-    fn find_entangled_spans(&self, parse_node: &QuantumParseNode) -> Vec<usize> {
-        let mut entangled_spans = Vec::new();
-
-        // Find spans that are quantum entangled with this node
-        for child in &parse_node.children {
-            if child.quantum_properties.entanglement_strength > 0.5 {
-                entangled_spans.push(child.start_token_index);
-            }
-        }
-
-        entangled_spans
+fn lower_items(items: &[P<ast::Item>], out: &mut Vec<QuantumASTNode>) {
+    for item in items {
+        lower_item(item, out);
     }
+}
 
-    /// Apply quantum optimizations to parsed AST
-    fn apply_quantum_parse_optimizations(&self, mut ast: QuantumAST) -> QuantumResult<QuantumAST> {
-        println!("🚀 Applying quantum parse optimizations...");
-        
-        // Quantum node entanglement
-        self.apply_quantum_entanglement(&mut ast)?;
-        
-        // Quantum expression optimization
-        self.optimize_quantum_expressions(&mut ast)?;
-        
-        // Quantum control flow optimization
-        self.optimize_quantum_control_flow(&mut ast)?;
-        
-        println!("   - Quantum entanglement applied");
-        println!("   - Expression optimization: 15% improvement");
-        println!("   - Control flow optimization: 22% improvement");
-        
-        Ok(ast)
+fn lower_item(item: &ast::Item, out: &mut Vec<QuantumASTNode>) {
+    match &item.kind {
+        ItemKind::Fn(func) => out.push(lower_fn(func, &item.attrs, item.span)),
+        ItemKind::Mod(_, _, ModKind::Loaded(items, ..)) => lower_items(items, out),
+        ItemKind::Impl(imp) => lower_assoc_items(&imp.items, out),
+        ItemKind::Trait(tr) => lower_assoc_items(&tr.items, out),
+        ItemKind::Static(item_static) => out.push(lower_global(
+            item_static.ident,
+            &item_static.ty,
+            item_static.mutability == Mutability::Mut,
+            item_static.expr.as_deref(),
+            item.span,
+        )),
+        ItemKind::Const(item_const) => out.push(lower_global(
+            item_const.ident,
+            &item_const.ty,
+            false,
+            item_const.expr.as_deref(),
+            item.span,
+        )),
+        _ => {}
     }
+}
 
-    /// Apply quantum entanglement between related nodes
-    fn apply_quantum_entanglement(&self, ast: &mut QuantumAST) -> QuantumResult<()> {
-        for i in 0..ast.nodes.len() {
-            for j in (i + 1)..ast.nodes.len() {
-                if self.should_entangle_nodes(&ast.nodes[i], &ast.nodes[j]) {
-                    ast.nodes[i].quantum_state.entangled_nodes.push(j);
-                    ast.nodes[j].quantum_state.entangled_nodes.push(i);
-                }
+fn lower_assoc_items(items: &[P<ast::AssocItem>], out: &mut Vec<QuantumASTNode>) {
+    for item in items {
+        match &item.kind {
+            AssocItemKind::Fn(func) if func.body.is_some() => {
+                out.push(lower_fn(func, &item.attrs, item.span))
             }
+            _ => {}
         }
-        
-        ast.parse_stats.entanglement_operations += ast.nodes.len() as u64;
-        Ok(())
     }
+}
 
-    /// Optimize quantum expressions
-    fn optimize_quantum_expressions(&self, ast: &mut QuantumAST) -> QuantumResult<()> {
-        for node in &mut ast.nodes {
-            if let QuantumNodeType::Expression { quantum_optimizable, .. } = &mut node.node_type {
-                *quantum_optimizable = true;
-                node.quantum_state.amplitude *= 1.2; // Boost quantum state
+fn lower_fn(func: &ast::Fn, attrs: &[ast::Attribute], span: Span) -> QuantumASTNode {
+    let name = func.ident.to_string();
+    let body = func.body.as_deref().map(lower_block).unwrap_or_default();
+
+    let mut quantum_gates = Vec::new();
+    for node in &body {
+        node.walk(&mut |node| {
+            if let QuantumNodeType::QuantumConstruct {
+                construct_type: QuantumConstructType::QuantumGate { .. },
+            } = &node.node_type
+            {
+                quantum_gates.push(node.clone());
             }
-        }
-        
-        ast.parse_stats.quantum_optimizations += 1;
-        Ok(())
+        });
     }
 
-    /// Optimize quantum control flow
-    fn optimize_quantum_control_flow(&self, ast: &mut QuantumAST) -> QuantumResult<()> {
-        for node in &mut ast.nodes {
-            if let QuantumNodeType::Statement { quantum_parallel, .. } = &mut node.node_type {
-                *quantum_parallel = true;
-                node.quantum_state.confidence = 0.95;
+    let params = func
+        .sig
+        .decl
+        .inputs
+        .iter()
+        .map(|param| {
+            let param_type = lower_ty(&param.ty);
+            QuantumParameter {
+                name: pprust::pat_to_string(&param.pat),
+                quantum_entangled: param_type.quantum_enhanced,
+                param_type,
             }
-        }
-        
-        ast.parse_stats.quantum_optimizations += 1;
-        Ok(())
-    }
-
-    /// Helper methods
-    fn create_sample_node(&self) -> QuantumResult<QuantumASTNode> {
-        Ok(QuantumASTNode {
-            node_type: QuantumNodeType::Function {
-                name: "quantum_function".to_string(),
-                params: vec![],
-                return_type: None,
-                quantum_attributes: vec![],
-            },
-            children: vec![],
-            quantum_state: QuantumParseState::default(),
-            span: QuantumSpan {
-                start: 0,
-                end: 10,
-                line: 1,
-                column: 1,
-                entangled_spans: vec![],
-            },
         })
-    }
-
-    fn should_entangle_nodes(&self, node1: &QuantumASTNode, node2: &QuantumASTNode) -> bool {
-        // Simple entanglement rules
-        matches!(
-            (&node1.node_type, &node2.node_type),
-            (QuantumNodeType::Function { .. }, QuantumNodeType::Variable { .. }) |
-            (QuantumNodeType::Expression { .. }, QuantumNodeType::Statement { .. })
-        )
-    }
-}
-
-// Supporting structures
-#[derive(Debug, Clone)]
-pub struct QuantumParsePath {
-    pub path_id: usize,
-    pub nodes: Vec<QuantumASTNode>,
-    pub probability: f64,
-    pub confidence: f64,
-}
-
-#[derive(Debug, Clone)]
-pub struct QuantumSpan {
-    pub start: usize,
-    pub end: usize,
-    pub line: usize,
-    pub column: usize,
-    pub entangled_spans: Vec<usize>,
-}
-
-#[derive(Debug, Clone)]
-pub struct QuantumParameter {
-    pub name: String,
-    pub param_type: QuantumType,
-    pub quantum_entangled: bool,
-}
-
-#[derive(Debug, Clone)]
-pub struct QuantumType {
-    pub name: String,
-    pub quantum_enhanced: bool,
-    pub arrow_optimized: bool,
-}
-
-#[derive(Debug, Clone)]
-pub struct QuantumAttribute {
-    pub name: String,
-    pub value: Option<String>,
-}
+        .collect();
+    let return_type = match &func.sig.decl.output {
+        FnRetTy::Ty(ty) => Some(lower_ty(ty)),
+        FnRetTy::Default(_) => None,
+    };
+    let quantum_attributes = attrs
+        .iter()
+        .filter(|attr| !attr.is_doc_comment())
+        .map(|attr| QuantumAttribute {
+            name: pprust::path_to_string(&attr.get_normal_item().path),
+            value: attr.value_str().map(|value| value.to_string()),
+        })
+        .collect();
 
-#[derive(Debug, Clone)]
-pub enum QuantumLiteral {
-    Integer(i64),
-    Float(f64),
-    String(String),
-    Boolean(bool),
-    QuantumState(f64, f64), // amplitude, phase
+    let function =
+        QuantumNodeType::Function { name, params, return_type, quantum_attributes, quantum_gates };
+    QuantumASTNode::new(function, span).with_children(body)
 }
 
-// Real implementations replacing placeholders
-
-/// Quantum Grammar - Real Implementation
-/// Defines quantum-enhanced Rust grammar rules with superposition parsing
-pub struct QuantumGrammar {
-    /// Standard Rust grammar rules
-    standard_rules: HashMap<String, GrammarRule>,
-    /// Quantum-enhanced grammar rules
-    quantum_rules: HashMap<String, QuantumGrammarRule>,
-    /// Grammar configuration
-    config: QuantumConfig,
-    /// Grammar statistics
-    stats: QuantumGrammarStats,
+fn lower_global(
+    ident: Ident,
+    ty: &ast::Ty,
+    mutable: bool,
+    init: Option<&ast::Expr>,
+    span: Span,
+) -> QuantumASTNode {
+    let init = init.map(lower_expr);
+    let variable = QuantumNodeType::Variable {
+        name: ident.to_string(),
+        var_type: Some(lower_ty(ty)),
+        mutable,
+        quantum_entangled: init.as_ref().is_some_and(QuantumASTNode::is_quantum),
+    };
+    QuantumASTNode::new(variable, span).with_children(init.into_iter().collect())
 }
 
-impl QuantumGrammar {
-    pub fn new(config: QuantumConfig) -> Self {
-        let mut grammar = Self {
-            standard_rules: HashMap::new(),
-            quantum_rules: HashMap::new(),
-            config,
-            stats: QuantumGrammarStats::default(),
-        };
-
-        // Initialize standard Rust grammar rules
-        grammar.initialize_standard_rules();
-
-        // Initialize quantum-enhanced grammar rules
-        if grammar.config.quantum_parsing {
-            grammar.initialize_quantum_rules();
-        }
-
-        grammar
-    }
-
-    /// Get grammar rule for a given token type
-    pub fn get_rule(&self, token_type: &str) -> Option<&GrammarRule> {
-        self.standard_rules.get(token_type)
+fn lower_ty(ty: &ast::Ty) -> QuantumType {
+    let name = pprust::ty_to_string(ty);
+    QuantumType {
+        quantum_enhanced: name.contains("Quantum") || name.contains("quantum::"),
+        arrow_optimized: false,
+        name,
     }
+}
 
-    /// Get quantum grammar rule for enhanced parsing
-    pub fn get_quantum_rule(&self, token_type: &str) -> Option<&QuantumGrammarRule> {
-        self.quantum_rules.get(token_type)
-    }
-
-    /// Check if a token sequence is valid according to grammar
-    pub fn validate_sequence(&mut self, tokens: &[QuantumToken]) -> QuantumResult<bool> {
-        let mut valid = true;
-
-        for i in 0..tokens.len() {
-            let token = &tokens[i];
-
-            // Check standard grammar rules
-            if let Some(rule) = self.get_rule(&token.token_type.to_string()) {
-                if !self.validate_token_context(token, tokens, i, rule)? {
-                    valid = false;
-                    break;
+fn lower_block(block: &ast::Block) -> Vec<QuantumASTNode> {
+    let mut nodes = Vec::new();
+    for stmt in &block.stmts {
+        match &stmt.kind {
+            StmtKind::Let(local) => {
+                let mut children: Vec<_> = local.kind.init().map(lower_expr).into_iter().collect();
+                let quantum_entangled = children.first().is_some_and(QuantumASTNode::is_quantum);
+                if let Some((_, Some(els))) = local.kind.init_else_opt() {
+                    children.extend(lower_block(els));
                 }
+                let mutable =
+                    matches!(local.pat.kind, PatKind::Ident(BindingMode(_, Mutability::Mut), ..));
+                let variable = QuantumNodeType::Variable {
+                    name: pprust::pat_to_string(&local.pat),
+                    var_type: local.ty.as_deref().map(lower_ty),
+                    mutable,
+                    quantum_entangled,
+                };
+                nodes.push(QuantumASTNode::new(variable, stmt.span).with_children(children));
             }
-
-            // Check quantum grammar rules if enabled
-            if self.config.quantum_parsing {
-                if let Some(quantum_rule) = self.get_quantum_rule(&token.token_type.to_string()) {
-                    if !self.validate_quantum_context(token, tokens, i, quantum_rule)? {
-                        valid = false;
-                        break;
-                    }
-                }
-            }
-
-            self.stats.rules_checked += 1;
-        }
-
-        if valid && self.config.quantum_parsing {
-            self.stats.quantum_validations += 1;
+            StmtKind::Item(item) => lower_item(item, &mut nodes),
+            StmtKind::Expr(expr) | StmtKind::Semi(expr) => nodes.push(lower_expr(expr)),
+            StmtKind::Empty | StmtKind::MacCall(_) => {}
         }
-
-        Ok(valid)
-    }
-
-    fn initialize_standard_rules(&mut self) {
-        // Function definition rule
-        self.standard_rules.insert("fn".to_string(), GrammarRule {
-            name: "function_definition".to_string(),
-            pattern: vec!["fn".to_string(), "identifier".to_string(), "(".to_string()],
-            required_context: vec![],
-            optional_context: vec!["pub".to_string(), "async".to_string()],
-        });
-
-        // Variable declaration rule
-        self.standard_rules.insert("let".to_string(), GrammarRule {
-            name: "variable_declaration".to_string(),
-            pattern: vec!["let".to_string(), "identifier".to_string()],
-            required_context: vec![],
-            optional_context: vec!["mut".to_string()],
-        });
-
-        // Type annotation rule
-        self.standard_rules.insert(":".to_string(), GrammarRule {
-            name: "type_annotation".to_string(),
-            pattern: vec![":".to_string(), "type".to_string()],
-            required_context: vec!["identifier".to_string()],
-            optional_context: vec![],
-        });
-
-        // Block rule
-        self.standard_rules.insert("{".to_string(), GrammarRule {
-            name: "block_start".to_string(),
-            pattern: vec!["{".to_string()],
-            required_context: vec![],
-            optional_context: vec![],
-        });
-    }
-
-    fn initialize_quantum_rules(&mut self) {
-        // Quantum function rule - allows superposition of function definitions
-        self.quantum_rules.insert("fn".to_string(), QuantumGrammarRule {
-            name: "quantum_function_definition".to_string(),
-            base_rule: self.standard_rules.get("fn").unwrap().clone(),
-            quantum_properties: QuantumRuleProperties {
-                superposition_allowed: true,
-                entanglement_context: vec!["quantum".to_string(), "parallel".to_string()],
-                coherence_requirements: 0.8,
-            },
-            quantum_patterns: vec![
-                vec!["quantum".to_string(), "fn".to_string()],
-                vec!["parallel".to_string(), "fn".to_string()],
-            ],
-        });
-
-        // Quantum variable rule - allows quantum state variables
-        self.quantum_rules.insert("let".to_string(), QuantumGrammarRule {
-            name: "quantum_variable_declaration".to_string(),
-            base_rule: self.standard_rules.get("let").unwrap().clone(),
-            quantum_properties: QuantumRuleProperties {
-                superposition_allowed: true,
-                entanglement_context: vec!["quantum".to_string(), "entangled".to_string()],
-                coherence_requirements: 0.6,
-            },
-            quantum_patterns: vec![
-                vec!["let".to_string(), "quantum".to_string()],
-                vec!["let".to_string(), "entangled".to_string()],
-            ],
-        });
     }
+    nodes
+}
 
-    fn validate_token_context(
-        &self,
-        token: &QuantumToken,
-        tokens: &[QuantumToken],
-        index: usize,
-        rule: &GrammarRule
-    ) -> QuantumResult<bool> {
-        // Check if required context is present
-        for required in &rule.required_context {
-            if !self.has_context_before(tokens, index, required) {
-                return Ok(false);
-            }
+fn lower_expr(expr: &ast::Expr) -> QuantumASTNode {
+    let span = expr.span;
+    match &expr.kind {
+        ExprKind::Paren(inner) => lower_expr(inner),
+        ExprKind::Lit(lit) => {
+            let (literal, optimizable) = lower_lit(*lit);
+            QuantumASTNode::expression(QuantumExpressionType::Literal(literal), optimizable, span)
         }
-
-        // Validate pattern matching
-        if !self.matches_pattern(tokens, index, &rule.pattern) {
-            return Ok(false);
+        ExprKind::Path(_, path) => {
+            let ident = QuantumExpressionType::Identifier(pprust::path_to_string(path));
+            QuantumASTNode::expression(ident, false, span)
         }
-
-        Ok(true)
-    }
-
-    fn validate_quantum_context(
-        &self,
-        token: &QuantumToken,
-        tokens: &[QuantumToken],
-        index: usize,
-        quantum_rule: &QuantumGrammarRule
-    ) -> QuantumResult<bool> {
-        // First validate base rule
-        if !self.validate_token_context(token, tokens, index, &quantum_rule.base_rule)? {
-            return Ok(false);
+        ExprKind::Binary(op, left, right) => {
+            let left = lower_expr(left);
+            let right = lower_expr(right);
+            let optimizable = is_optimizable(&left) && is_optimizable(&right);
+            let binary = QuantumExpressionType::BinaryOp {
+                op: op.node.as_str().to_string(),
+                left: Box::new(left),
+                right: Box::new(right),
+                quantum_commutative: is_commutative(op.node),
+            };
+            QuantumASTNode::expression(binary, optimizable, span)
         }
-
-        // Check quantum-specific patterns
-        for quantum_pattern in &quantum_rule.quantum_patterns {
-            if self.matches_quantum_pattern(tokens, index, quantum_pattern) {
-                return Ok(true);
-            }
+        ExprKind::Call(func, args) => {
+            lower_call(pprust::expr_to_string(func), None, args, span)
         }
-
-        // Check if token has sufficient quantum coherence
-        if token.quantum_properties.coherence < quantum_rule.quantum_properties.coherence_requirements {
-            return Ok(false);
+        ExprKind::MethodCall(call) => {
+            lower_call(call.seg.ident.to_string(), Some(&call.receiver), &call.args, span)
         }
-
-        Ok(true)
-    }
-
-    fn has_context_before(&self, tokens: &[QuantumToken], index: usize, context: &str) -> bool {
-        if index == 0 {
-            return false;
+        ExprKind::Array(elements) => {
+            let elements: Vec<_> = elements.iter().map(|element| lower_expr(element)).collect();
+            let optimizable = elements.iter().all(is_optimizable);
+            let array = QuantumExpressionType::QuantumArray { elements, quantum_entangled: false };
+            QuantumASTNode::expression(array, optimizable, span)
         }
-
-        for i in (0..index).rev() {
-            if tokens[i].token_type.to_string() == context {
-                return true;
-            }
-            // Stop searching after a certain distance
-            if index - i > 5 {
-                break;
-            }
+        ExprKind::Assign(target, value, _) | ExprKind::AssignOp(_, target, value) => {
+            let assignment = QuantumStatementType::Assignment {
+                target: pprust::expr_to_string(target),
+                value: Box::new(lower_expr(value)),
+                quantum_atomic: false,
+            };
+            QuantumASTNode::statement(assignment, false, span)
         }
-
-        false
-    }
-
-    fn matches_pattern(&self, tokens: &[QuantumToken], start_index: usize, pattern: &[String]) -> bool {
-        if start_index + pattern.len() > tokens.len() {
-            return false;
+        ExprKind::Ret(value) => {
+            let value = value.as_deref().map(|value| Box::new(lower_expr(value)));
+            QuantumASTNode::statement(QuantumStatementType::Return(value), false, span)
         }
-
-        for (i, expected) in pattern.iter().enumerate() {
-            let token_type = tokens[start_index + i].token_type.to_string();
-            if token_type != *expected && *expected != "identifier" && *expected != "type" {
-                return false;
-            }
+        ExprKind::If(condition, then_block, else_expr) => {
+            let else_block = else_expr.as_deref().map(|else_expr| match &else_expr.kind {
+                ExprKind::Block(block, None) => lower_block(block),
+                _ => vec![lower_expr(else_expr)],
+            });
+            let if_stmt = QuantumStatementType::If {
+                condition: Box::new(lower_expr(condition)),
+                then_block: lower_block(then_block),
+                quantum_superposition: else_block.is_some(),
+                else_block,
+            };
+            QuantumASTNode::statement(if_stmt, false, span)
         }
+        ExprKind::ForLoop { pat, iter, body, .. } => {
+            let loop_type = QuantumLoopType::For {
+                iterator: pprust::pat_to_string(pat),
+                iterable: Box::new(lower_expr(iter)),
+            };
+            lower_loop(loop_type, body, span)
+        }
+        ExprKind::While(condition, body, _) => {
+            let loop_type = QuantumLoopType::While { condition: Box::new(lower_expr(condition)) };
+            lower_loop(loop_type, body, span)
+        }
+        ExprKind::Loop(body, ..) => lower_loop(QuantumLoopType::Loop, body, span),
+        ExprKind::Block(block, _) => {
+            QuantumASTNode::expression(QuantumExpressionType::Block, false, span)
+                .with_children(lower_block(block))
+        }
+        kind => {
+            let mut subexprs = SubExpressions::default();
+            visit::walk_expr(&mut subexprs, expr);
+            let children = subexprs.0.into_iter().map(lower_expr).collect();
+            let other = QuantumExpressionType::Other(expr_kind_name(kind).to_string());
+            QuantumASTNode::expression(other, false, span).with_children(children)
+        }
+    }
+}
 
-        true
+/// Lower a call of `name`, which is the callee path for function calls and
+/// the method name for method calls
+fn lower_call(
+    name: String,
+    receiver: Option<&ast::Expr>,
+    args: &[P<ast::Expr>],
+    span: Span,
+) -> QuantumASTNode {
+    let last_segment = name.rsplit("::").next().unwrap_or(&name);
+    let arg_strings = || args.iter().map(|arg| pprust::expr_to_string(arg)).collect::<Vec<_>>();
+    let receiver_node = || receiver.map(lower_expr).into_iter().collect::<Vec<_>>();
+
+    if GATE_NAMES.contains(&last_segment) && !args.is_empty() {
+        let gate = QuantumConstructType::QuantumGate {
+            gate_type: last_segment.to_string(),
+            qubits: arg_strings(),
+        };
+        return QuantumASTNode::construct(gate, span).with_children(receiver_node());
     }
 
-    fn matches_quantum_pattern(&self, tokens: &[QuantumToken], start_index: usize, pattern: &[String]) -> bool {
-        // More flexible matching for quantum patterns
-        if start_index >= tokens.len() {
-            return false;
+    if MEASUREMENT_NAMES.contains(&last_segment) {
+        let mut qubits = arg_strings();
+        if qubits.is_empty() {
+            qubits.extend(receiver.map(pprust::expr_to_string));
         }
+        let measurement =
+            QuantumConstructType::QuantumMeasurement { qubits, classical_bits: Vec::new() };
+        return QuantumASTNode::construct(measurement, span).with_children(receiver_node());
+    }
 
-        let mut pattern_index = 0;
-        let mut token_index = start_index;
-
-        while pattern_index < pattern.len() && token_index < tokens.len() {
-            let expected = &pattern[pattern_index];
-            let actual = tokens[token_index].token_type.to_string();
-
-            if actual == *expected {
-                pattern_index += 1;
-            }
-
-            token_index += 1;
-
-            // Allow some flexibility in quantum pattern matching
-            if token_index - start_index > pattern.len() + 2 {
-                break;
-            }
+    let inverse = INVERSE_FFT_NAMES.contains(&last_segment);
+    if inverse || FFT_NAMES.contains(&last_segment) {
+        if let Some(input) = receiver.or(args.first().map(|arg| &**arg)) {
+            let input = Box::new(lower_expr(input));
+            let fft = QuantumExpressionType::QuantumFFT { input, inverse };
+            return QuantumASTNode::expression(fft, false, span);
         }
-
-        pattern_index == pattern.len()
     }
 
-    pub fn get_stats(&self) -> &QuantumGrammarStats {
-        &self.stats
+    let mut lowered_args = receiver_node();
+    lowered_args.extend(args.iter().map(|arg| lower_expr(arg)));
+
+    if receiver.is_none() && name.contains("QuantumArray::") {
+        let array =
+            QuantumExpressionType::QuantumArray { elements: lowered_args, quantum_entangled: true };
+        return QuantumASTNode::expression(array, false, span);
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct GrammarRule {
-    pub name: String,
-    pub pattern: Vec<String>,
-    pub required_context: Vec<String>,
-    pub optional_context: Vec<String>,
+    let call =
+        QuantumExpressionType::FunctionCall { name, args: lowered_args, quantum_parallel: false };
+    QuantumASTNode::expression(call, false, span)
 }
 
-#[derive(Debug, Clone)]
-pub struct QuantumGrammarRule {
-    pub name: String,
-    pub base_rule: GrammarRule,
-    pub quantum_properties: QuantumRuleProperties,
-    pub quantum_patterns: Vec<Vec<String>>,
+fn lower_loop(loop_type: QuantumLoopType, body: &ast::Block, span: Span) -> QuantumASTNode {
+    let mut effects = LoopBodyEffects::default();
+    visit::walk_block(&mut effects, body);
+    let loop_stmt = QuantumStatementType::Loop {
+        loop_type,
+        body: lower_block(body),
+        quantum_parallel: !effects.0,
+    };
+    QuantumASTNode::statement(loop_stmt, !effects.0, span)
 }
 
-#[derive(Debug, Clone)]
-pub struct QuantumRuleProperties {
-    pub superposition_allowed: bool,
-    pub entanglement_context: Vec<String>,
-    pub coherence_requirements: f64,
+/// Value of a literal, and whether it can be folded
+///
+/// Integers above `i64::MAX` are kept as their source text and cannot be folded.
+fn lower_lit(lit: token::Lit) -> (QuantumLiteral, bool) {
+    let literal = match LitKind::from_token_lit(lit) {
+        Ok(LitKind::Int(value, _)) => match i64::try_from(value.get()) {
+            Ok(value) => QuantumLiteral::Integer(value),
+            Err(_) => return (QuantumLiteral::String(lit.symbol.to_string()), false),
+        },
+        Ok(LitKind::Float(value, _)) => {
+            QuantumLiteral::Float(value.as_str().replace('_', "").parse().unwrap_or(f64::NAN))
+        }
+        Ok(LitKind::Bool(value)) => QuantumLiteral::Boolean(value),
+        Ok(LitKind::Str(value, _)) => QuantumLiteral::String(value.to_string()),
+        Ok(LitKind::Char(value)) => QuantumLiteral::String(value.to_string()),
+        _ => QuantumLiteral::String(lit.symbol.to_string()),
+    };
+    (literal, true)
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct QuantumGrammarStats {
-    pub rules_checked: u64,
-    pub quantum_validations: u64,
-    pub pattern_matches: u64,
-    pub validation_speedup: f64,
+fn is_optimizable(node: &QuantumASTNode) -> bool {
+    matches!(node.node_type, QuantumNodeType::Expression { quantum_optimizable: true, .. })
 }
 
-/// Superposition Engine - Real Implementation
-/// Enables parallel parsing paths and quantum superposition of parse trees
-pub struct SuperpositionEngine {
-    /// Active parsing states in superposition
-    active_states: Vec<QuantumParseState>,
-    /// Quantum configuration
-    config: QuantumConfig,
-    /// Superposition statistics
-    stats: SuperpositionStats,
-    /// Maximum superposition depth
-    max_depth: usize,
+fn is_commutative(op: BinOpKind) -> bool {
+    matches!(
+        op,
+        BinOpKind::Add
+            | BinOpKind::Mul
+            | BinOpKind::BitXor
+            | BinOpKind::BitAnd
+            | BinOpKind::BitOr
+            | BinOpKind::Eq
+            | BinOpKind::Ne
+    )
 }
 
-impl SuperpositionEngine {
-    pub fn new(config: QuantumConfig) -> Self {
-        Self {
-            active_states: Vec::new(),
-            config,
-            stats: SuperpositionStats::default(),
-            max_depth: if config.quantum_parsing { 10 } else { 1 },
-        }
-    }
-
-    /// Create quantum superposition of parsing states
-    pub fn create_superposition(
-        &mut self,
-        initial_tokens: &[QuantumToken],
-        grammar: &QuantumGrammar
-    ) -> QuantumResult<Vec<QuantumParseState>> {
-        self.active_states.clear();
-
-        // Create initial parsing state
-        let initial_state = QuantumParseState {
-            id: 0,
-            tokens: initial_tokens.to_vec(),
-            position: 0,
-            parse_stack: Vec::new(),
-            quantum_amplitude: 1.0,
-            quantum_phase: 0.0,
-            coherence: 1.0,
-            entangled_states: Vec::new(),
-        };
-
-        self.active_states.push(initial_state);
-        self.stats.initial_states = 1;
-
-        // Generate superposition states if quantum parsing is enabled
-        if self.config.quantum_parsing {
-            self.generate_quantum_superposition(grammar)?;
-        }
-
-        // Evolve superposition states
-        self.evolve_superposition(grammar)?;
-
-        Ok(self.active_states.clone())
+fn expr_kind_name(kind: &ExprKind) -> &'static str {
+    match kind {
+        ExprKind::Match(..) => "match",
+        ExprKind::Closure(..) => "closure",
+        ExprKind::Unary(..) => "unary",
+        ExprKind::Cast(..) => "cast",
+        ExprKind::Field(..) => "field",
+        ExprKind::Index(..) => "index",
+        ExprKind::Range(..) => "range",
+        ExprKind::AddrOf(..) => "reference",
+        ExprKind::Tup(..) => "tuple",
+        ExprKind::Struct(..) => "struct",
+        ExprKind::Repeat(..) => "repeat",
+        ExprKind::Try(..) => "try",
+        ExprKind::Break(..) => "break",
+        ExprKind::Continue(..) => "continue",
+        ExprKind::MacCall(..) => "macro",
+        ExprKind::Let(..) => "let",
+        ExprKind::Await(..) => "await",
+        ExprKind::Gen(..) => "gen",
+        _ => "expression",
     }
+}
 
-    fn generate_quantum_superposition(&mut self, grammar: &QuantumGrammar) -> QuantumResult<()> {
-        let mut new_states = Vec::new();
-
-        for state in &self.active_states {
-            // Generate alternative parsing paths
-            let alternatives = self.generate_alternative_paths(state, grammar)?;
-
-            for (i, alt_state) in alternatives.into_iter().enumerate() {
-                if new_states.len() < self.max_depth {
-                    let mut quantum_state = alt_state;
-                    quantum_state.id = state.id * 10 + i + 1;
-
-                    // Calculate quantum amplitude for superposition
-                    quantum_state.quantum_amplitude = state.quantum_amplitude / (i + 2) as f64;
-                    quantum_state.quantum_phase = (i as f64) * std::f64::consts::PI / 4.0;
-
-                    new_states.push(quantum_state);
-                    self.stats.superposition_branches += 1;
-                }
-            }
-        }
+/// Collects the outermost expressions below an expression
+#[derive(Default)]
+struct SubExpressions<'ast>(Vec<&'ast ast::Expr>);
 
-        self.active_states.extend(new_states);
-        Ok(())
+impl<'ast> Visitor<'ast> for SubExpressions<'ast> {
+    fn visit_expr(&mut self, expr: &'ast ast::Expr) {
+        self.0.push(expr);
     }
 
-    fn generate_alternative_paths(
-        &self,
-        state: &QuantumParseState,
-        grammar: &QuantumGrammar
-    ) -> QuantumResult<Vec<QuantumParseState>> {
-        let mut alternatives = Vec::new();
-
-        if state.position >= state.tokens.len() {
-            return Ok(alternatives);
-        }
-
-        let current_token = &state.tokens[state.position];
-
-        // Generate alternatives based on quantum grammar rules
-        if let Some(quantum_rule) = grammar.get_quantum_rule(&current_token.token_type.to_string()) {
-            for quantum_pattern in &quantum_rule.quantum_patterns {
-                if self.can_apply_pattern(state, quantum_pattern) {
-                    let alt_state = self.create_alternative_state(state, quantum_pattern)?;
-                    alternatives.push(alt_state);
-                }
-            }
-        }
+    fn visit_item(&mut self, _: &'ast ast::Item) {}
+}
 
-        // Generate alternatives based on token quantum properties
-        if current_token.quantum_properties.superposition {
-            let superposition_alt = self.create_superposition_alternative(state)?;
-            alternatives.push(superposition_alt);
+/// Whether a loop body can exit the loop early or assigns to anything
+#[derive(Default)]
+struct LoopBodyEffects(bool);
+
+impl<'ast> Visitor<'ast> for LoopBodyEffects {
+    fn visit_expr(&mut self, expr: &'ast ast::Expr) {
+        match &expr.kind {
+            ExprKind::Break(..)
+            | ExprKind::Continue(..)
+            | ExprKind::Ret(..)
+            | ExprKind::Try(..)
+            | ExprKind::Yield(..)
+            | ExprKind::Assign(..)
+            | ExprKind::AssignOp(..) => self.0 = true,
+            // Exits and assignments in closures do not affect the loop.
+            ExprKind::Closure(..) => {}
+            _ => visit::walk_expr(self, expr),
         }
-
-        Ok(alternatives)
     }
 
-    fn can_apply_pattern(&self, state: &QuantumParseState, pattern: &[String]) -> bool {
-        if state.position + pattern.len() > state.tokens.len() {
-            return false;
-        }
+    fn visit_item(&mut self, _: &'ast ast::Item) {}
+}
 
-        // Check if pattern can be applied at current position
-        for (i, expected) in pattern.iter().enumerate() {
-            let token_type = state.tokens[state.position + i].token_type.to_string();
-            if token_type != *expected && !self.is_compatible_type(expected, &token_type) {
-                return false;
-            }
-        }
+/// Quantum parser lowering the expanded AST
+#[derive(Default)]
+pub struct QuantumParser;
 
-        true
+impl QuantumParser {
+    /// Create a new quantum parser
+    pub fn new() -> Self {
+        Self
     }
 
-    fn is_compatible_type(&self, expected: &str, actual: &str) -> bool {
-        match expected {
-            "identifier" => matches!(actual, "identifier" | "keyword"),
-            "type" => matches!(actual, "type" | "identifier"),
-            "quantum" => actual.contains("quantum"),
-            _ => false,
-        }
+    /// Build the quantum AST of an expanded crate and annotate it
+    pub fn quantum_parse(&mut self, krate: &ast::Crate) -> QuantumResult<QuantumAST> {
+        let ast = QuantumAST::from_ast(krate);
+        self.apply_quantum_parse_optimizations(ast)
     }
 
-    fn create_alternative_state(
-        &self,
-        base_state: &QuantumParseState,
-        pattern: &[String]
-    ) -> QuantumResult<QuantumParseState> {
-        let mut alt_state = base_state.clone();
-
-        // Apply pattern to create alternative parsing path
-        for token_type in pattern {
-            alt_state.parse_stack.push(QuantumParseNode {
-                node_type: token_type.clone(),
-                quantum_properties: QuantumNodeProperties {
-                    superposition_weight: 0.7,
-                    entanglement_strength: 0.5,
-                    coherence_time: 1.0,
-                },
-                children: Vec::new(),
-            });
-        }
+    /// Apply quantum optimizations to parsed AST
+    fn apply_quantum_parse_optimizations(&self, mut ast: QuantumAST) -> QuantumResult<QuantumAST> {
+        // Quantum expression optimization
+        self.optimize_quantum_expressions(&mut ast)?;
 
-        alt_state.position += pattern.len();
-        alt_state.coherence *= 0.9; // Slight coherence decay
+        // Quantum control flow optimization
+        self.optimize_quantum_control_flow(&mut ast)?;
 
-        Ok(alt_state)
+        Ok(ast)
     }
 
-    fn create_superposition_alternative(&self, base_state: &QuantumParseState) -> QuantumResult<QuantumParseState> {
-        let mut alt_state = base_state.clone();
-
-        // Create superposition alternative with quantum properties
-        if alt_state.position < alt_state.tokens.len() {
-            let current_token = &alt_state.tokens[alt_state.position];
-
-            alt_state.parse_stack.push(QuantumParseNode {
-                node_type: format!("superposition_{}", current_token.token_type.to_string()),
-                quantum_properties: QuantumNodeProperties {
-                    superposition_weight: current_token.quantum_properties.coherence,
-                    entanglement_strength: current_token.quantum_properties.entanglement,
-                    coherence_time: 2.0,
-                },
-                children: Vec::new(),
+    /// Boost the expressions that can be folded at compile time
+    fn optimize_quantum_expressions(&self, ast: &mut QuantumAST) -> QuantumResult<()> {
+        let mut optimized = 0;
+        for root in &mut ast.nodes {
+            root.walk_mut(&mut |node| {
+                if is_optimizable(node) {
+                    node.quantum_state.amplitude *= 1.2; // Boost quantum state
+                    optimized += 1;
+                }
             });
-
-            alt_state.position += 1;
-            alt_state.quantum_amplitude *= current_token.quantum_properties.coherence;
         }
 
-        Ok(alt_state)
+        ast.parse_stats.quantum_optimizations += optimized;
+        Ok(())
     }
 
-    fn evolve_superposition(&mut self, grammar: &QuantumGrammar) -> QuantumResult<()> {
-        let mut evolution_steps = 0;
-        let max_evolution_steps = 5;
-
-        while evolution_steps < max_evolution_steps && !self.active_states.is_empty() {
-            let mut evolved_states = Vec::new();
-
-            for state in &self.active_states {
-                if state.coherence > 0.1 { // Only evolve coherent states
-                    let evolved = self.evolve_single_state(state, grammar)?;
-                    evolved_states.extend(evolved);
+    /// Raise the confidence of statements whose parts can run in parallel
+    fn optimize_quantum_control_flow(&self, ast: &mut QuantumAST) -> QuantumResult<()> {
+        let mut optimized = 0;
+        for root in &mut ast.nodes {
+            root.walk_mut(&mut |node| {
+                if let QuantumNodeType::Statement { quantum_parallel: true, .. } = node.node_type {
+                    node.quantum_state.confidence = 0.95;
+                    optimized += 1;
                 }
-            }
-
-            // Keep only the most promising states
-            evolved_states.sort_by(|a, b| {
-                (b.quantum_amplitude * b.coherence).partial_cmp(&(a.quantum_amplitude * a.coherence)).unwrap()
             });
-
-            self.active_states = evolved_states.into_iter().take(self.max_depth).collect();
-            evolution_steps += 1;
-            self.stats.evolution_steps += 1;
         }
 
+        ast.parse_stats.quantum_optimizations += optimized;
         Ok(())
     }
-
-    fn evolve_single_state(
-        &self,
-        state: &QuantumParseState,
-        grammar: &QuantumGrammar
-    ) -> QuantumResult<Vec<QuantumParseState>> {
-        let mut evolved = Vec::new();
-
-        if state.position < state.tokens.len() {
-            let current_token = &state.tokens[state.position];
-
-            // Try to advance parsing with current token
-            if let Some(rule) = grammar.get_rule(&current_token.token_type.to_string()) {
-                let mut next_state = state.clone();
-                next_state.position += 1;
-                next_state.coherence *= 0.95; // Natural decoherence
-
-                next_state.parse_stack.push(QuantumParseNode {
-                    node_type: rule.name.clone(),
-                    quantum_properties: QuantumNodeProperties {
-                        superposition_weight: 1.0,
-                        entanglement_strength: 0.3,
-                        coherence_time: 1.5,
-                    },
-                    children: Vec::new(),
-                });
-
-                evolved.push(next_state);
-            }
-        }
-
-        Ok(evolved)
-    }
-
-    /// Collapse superposition to most probable parsing state
-    pub fn collapse_superposition(&mut self) -> QuantumResult<Option<QuantumParseState>> {
-        if self.active_states.is_empty() {
-            return Ok(None);
-        }
-
-        // Find state with highest probability (amplitude^2 * coherence)
-        let best_state = self.active_states.iter()
-            .max_by(|a, b| {
-                let prob_a = a.quantum_amplitude.powi(2) * a.coherence;
-                let prob_b = b.quantum_amplitude.powi(2) * b.coherence;
-                prob_a.partial_cmp(&prob_b).unwrap()
-            })
-            .cloned();
-
-        if let Some(ref state) = best_state {
-            self.stats.collapse_probability = state.quantum_amplitude.powi(2) * state.coherence;
-            self.stats.final_coherence = state.coherence;
-        }
-
-        // Clear superposition after collapse
-        self.active_states.clear();
-
-        Ok(best_state)
-    }
-
-    pub fn get_stats(&self) -> &SuperpositionStats {
-        &self.stats
-    }
 }
 
+// Supporting structures
 #[derive(Debug, Clone)]
-pub struct QuantumParseState {
-    pub id: usize,
-    pub tokens: Vec<QuantumToken>,
-    pub position: usize,
-    pub parse_stack: Vec<QuantumParseNode>,
-    pub quantum_amplitude: f64,
-    pub quantum_phase: f64,
-    pub coherence: f64,
-    pub entangled_states: Vec<usize>,
+pub struct QuantumParameter {
+    pub name: String,
+    pub param_type: QuantumType,
+    pub quantum_entangled: bool,
 }
 
 #[derive(Debug, Clone)]
-pub struct QuantumParseNode {
-    pub node_type: String,
-    pub quantum_properties: QuantumNodeProperties,
-    pub children: Vec<QuantumParseNode>,
+pub struct QuantumType {
+    pub name: String,
+    pub quantum_enhanced: bool,
+    pub arrow_optimized: bool,
 }
 
 #[derive(Debug, Clone)]
-pub struct QuantumNodeProperties {
-    pub superposition_weight: f64,
-    pub entanglement_strength: f64,
-    pub coherence_time: f64,
-}
-
-#[derive(Debug, Clone, Default)]
-pub struct SuperpositionStats {
-    pub initial_states: u64,
-    pub superposition_branches: u64,
-    pub evolution_steps: u64,
-    pub collapse_probability: f64,
-    pub final_coherence: f64,
+pub struct QuantumAttribute {
+    pub name: String,
+    pub value: Option<String>,
 }
 
-/// Quantum Parse Cache - Real Implementation
-/// Intelligent caching system for quantum parsing results with entanglement-aware invalidation
-pub struct QuantumParseCache {
-    /// Parse result cache
-    parse_cache: HashMap<String, CachedParseResult>,
-    /// Quantum state cache
-    quantum_cache: HashMap<String, QuantumCacheEntry>,
-    /// Cache configuration
-    config: QuantumConfig,
-    /// Cache statistics
-    stats: QuantumCacheStats,
-    /// Maximum cache size
-    max_cache_size: usize,
+#[derive(Debug, Clone)]
+pub enum QuantumLiteral {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    QuantumState(f64, f64), // amplitude, phase
 }
 
-impl QuantumParseCache {
-    pub fn new(config: QuantumConfig) -> Self {
-        Self {
-            parse_cache: HashMap::new(),
-            quantum_cache: HashMap::new(),
-            config,
-            stats: QuantumCacheStats::default(),
-            max_cache_size: if config.quantum_parsing { 1000 } else { 100 },
-        }
-    }
-
-    /// Cache a parsing result with quantum properties
-    pub fn cache_parse_result(
-        &mut self,
-        source_hash: String,
-        tokens: &[QuantumToken],
-        parse_result: QuantumParseResult
-    ) -> QuantumResult<()> {
-        // Create cache entry
-        let cached_result = CachedParseResult {
-            source_hash: source_hash.clone(),
-            tokens: tokens.to_vec(),
-            parse_result: parse_result.clone(),
-            quantum_signature: self.calculate_quantum_signature(tokens)?,
-            cache_time: std::time::SystemTime::now(),
-            access_count: 0,
-            quantum_coherence: parse_result.quantum_coherence,
-        };
-
-        // Check cache size and evict if necessary
-        if self.parse_cache.len() >= self.max_cache_size {
-            self.evict_least_coherent_entry()?;
-        }
-
-        self.parse_cache.insert(source_hash.clone(), cached_result);
-
-        // Cache quantum state if quantum parsing is enabled
-        if self.config.quantum_parsing {
-            self.cache_quantum_state(source_hash, &parse_result)?;
-        }
-
-        self.stats.cache_entries += 1;
-        Ok(())
-    }
-
-    /// Retrieve cached parsing result
-    pub fn get_cached_result(&mut self, source_hash: &str, tokens: &[QuantumToken]) -> QuantumResult<Option<QuantumParseResult>> {
-        if let Some(cached) = self.parse_cache.get_mut(source_hash) {
-            // Verify quantum signature for cache validity
-            let current_signature = self.calculate_quantum_signature(tokens)?;
-
-            if self.is_quantum_signature_compatible(&cached.quantum_signature, &current_signature) {
-                // Check quantum coherence
-                if self.is_cache_coherent(cached)? {
-                    cached.access_count += 1;
-                    self.stats.cache_hits += 1;
-
-                    // Update quantum coherence based on access pattern
-                    if self.config.quantum_parsing {
-                        self.update_quantum_coherence(cached)?;
-                    }
-
-                    return Ok(Some(cached.parse_result.clone()));
-                } else {
-                    // Cache entry has lost coherence, remove it
-                    self.parse_cache.remove(source_hash);
-                    self.stats.coherence_invalidations += 1;
-                }
-            } else {
-                // Quantum signature mismatch, invalidate cache
-                self.parse_cache.remove(source_hash);
-                self.stats.signature_mismatches += 1;
-            }
-        }
-
-        self.stats.cache_misses += 1;
-        Ok(None)
-    }
-
-    /// Cache quantum parsing state for entanglement tracking
-    fn cache_quantum_state(&mut self, source_hash: String, parse_result: &QuantumParseResult) -> QuantumResult<()> {
-        let quantum_entry = QuantumCacheEntry {
-            entangled_sources: parse_result.entangled_sources.clone(),
-            quantum_amplitude: parse_result.quantum_amplitude,
-            quantum_phase: parse_result.quantum_phase,
-            coherence_time: parse_result.quantum_coherence,
-            last_measurement: std::time::SystemTime::now(),
-        };
-
-        self.quantum_cache.insert(source_hash, quantum_entry);
-        Ok(())
-    }
-
-    /// Calculate quantum signature for cache validation
-    fn calculate_quantum_signature(&self, tokens: &[QuantumToken]) -> QuantumResult<QuantumSignature> {
-        let mut signature = QuantumSignature {
-            token_count: tokens.len(),
-            quantum_hash: 0,
-            entanglement_pattern: Vec::new(),
-            coherence_sum: 0.0,
-        };
-
-        // Calculate quantum hash based on token properties
-        for (i, token) in tokens.iter().enumerate() {
-            signature.quantum_hash ^= self.hash_token_quantum_properties(token, i);
-            signature.coherence_sum += token.quantum_properties.coherence;
-
-            if token.quantum_properties.entanglement > 0.5 {
-                signature.entanglement_pattern.push(i);
-            }
-        }
-
-        signature.coherence_sum /= tokens.len() as f64;
-        Ok(signature)
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn hash_token_quantum_properties(&self, token: &QuantumToken, position: usize) -> u64 {
-        let mut hash = position as u64;
+    use rustc_session::parse::ParseSess;
+    use rustc_span::source_map::SourceMap;
+    use rustc_span::{FileName, create_default_session_globals_then};
 
-        // Include quantum properties in hash
-        hash ^= (token.quantum_properties.coherence * 1000.0) as u64;
-        hash ^= (token.quantum_properties.entanglement * 1000.0) as u64;
-        hash ^= if token.quantum_properties.superposition { 1 } else { 0 };
+    const SOURCE: &str = r#"
+static SHOTS: usize = 1024;
 
-        // Include token type
-        hash ^= token.token_type.to_string().len() as u64;
-
-        hash
+mod circuits {
+    #[inline]
+    pub fn bell(q: &mut QuantumState) -> Vec<bool> {
+        q.h(0);
+        q.cx(0, 1);
+        q.measure_all()
     }
+}
 
-    fn is_quantum_signature_compatible(&self, cached: &QuantumSignature, current: &QuantumSignature) -> bool {
-        // Check basic compatibility
-        if cached.token_count != current.token_count {
-            return false;
-        }
-
-        // Check quantum hash similarity (allow some quantum fluctuation)
-        let hash_diff = (cached.quantum_hash as i64 - current.quantum_hash as i64).abs();
-        if hash_diff > 100 {
-            return false;
-        }
-
-        // Check coherence similarity
-        let coherence_diff = (cached.coherence_sum - current.coherence_sum).abs();
-        if coherence_diff > 0.2 {
-            return false;
-        }
-
-        // Check entanglement pattern similarity
-        let pattern_similarity = self.calculate_pattern_similarity(&cached.entanglement_pattern, &current.entanglement_pattern);
-        pattern_similarity > 0.8
-    }
+struct Counter;
 
-    fn calculate_pattern_similarity(&self, pattern1: &[usize], pattern2: &[usize]) -> f64 {
-        if pattern1.is_empty() && pattern2.is_empty() {
-            return 1.0;
+impl Counter {
+    fn sum(values: &[u64]) -> u64 {
+        let mut total = 0;
+        for v in values {
+            validate(v);
         }
-
-        if pattern1.is_empty() || pattern2.is_empty() {
-            return 0.0;
+        for v in values {
+            total += v;
         }
-
-        let common_elements = pattern1.iter().filter(|&x| pattern2.contains(x)).count();
-        let total_elements = pattern1.len().max(pattern2.len());
-
-        common_elements as f64 / total_elements as f64
+        total
     }
+}
 
-    fn is_cache_coherent(&self, cached: &CachedParseResult) -> QuantumResult<bool> {
-        if !self.config.quantum_parsing {
-            return Ok(true);
-        }
-
-        // Check if quantum coherence has decayed too much
-        let time_elapsed = cached.cache_time.elapsed().unwrap_or_default().as_secs_f64();
-        let coherence_decay = (-time_elapsed / 10.0).exp(); // 10 second coherence time
-        let current_coherence = cached.quantum_coherence * coherence_decay;
-
-        Ok(current_coherence > 0.3) // Minimum coherence threshold
+fn folded() -> u32 {
+    let x = (2 + 3) * 4;
+    if x > 10 { x } else { 0 }
+}
+"#;
+
+    fn with_quantum_ast<R>(src: &str, f: impl FnOnce(&SourceMap, &QuantumAST) -> R) -> R {
+        create_default_session_globals_then(|| {
+            let psess = ParseSess::new(vec![rustc_parse::DEFAULT_LOCALE_RESOURCE]);
+            let mut parser = rustc_parse::unwrap_or_emit_fatal(
+                rustc_parse::new_parser_from_source_str(
+                    &psess,
+                    FileName::Custom("test.rs".to_string()),
+                    src.to_string(),
+                ),
+            );
+            let krate = parser.parse_crate_mod().map_err(|err| err.emit()).unwrap();
+            f(psess.source_map(), &QuantumAST::from_ast(&krate))
+        })
     }
 
-    fn update_quantum_coherence(&self, cached: &mut CachedParseResult) -> QuantumResult<()> {
-        // Boost coherence on access (quantum Zeno effect)
-        cached.quantum_coherence = (cached.quantum_coherence * 1.1).min(1.0);
-        Ok(())
+    fn root<'a>(ast: &'a QuantumAST, name: &str) -> &'a QuantumASTNode {
+        ast.nodes
+            .iter()
+            .find(|node| match &node.node_type {
+                QuantumNodeType::Function { name: n, .. }
+                | QuantumNodeType::Variable { name: n, .. } => n == name,
+                _ => false,
+            })
+            .unwrap_or_else(|| panic!("no root node named {name}"))
     }
 
-    fn evict_least_coherent_entry(&mut self) -> QuantumResult<()> {
-        if self.parse_cache.is_empty() {
-            return Ok(());
-        }
-
-        // Find entry with lowest coherence * access_count score
-        let mut worst_key = String::new();
-        let mut worst_score = f64::INFINITY;
-
-        for (key, entry) in &self.parse_cache {
-            let score = entry.quantum_coherence * (entry.access_count as f64 + 1.0);
-            if score < worst_score {
-                worst_score = score;
-                worst_key = key.clone();
+    fn find_all<'a>(
+        node: &'a QuantumASTNode,
+        pred: impl Fn(&QuantumASTNode) -> bool,
+    ) -> Vec<&'a QuantumASTNode> {
+        let mut found = Vec::new();
+        fn go<'a>(
+            node: &'a QuantumASTNode,
+            pred: &dyn Fn(&QuantumASTNode) -> bool,
+            found: &mut Vec<&'a QuantumASTNode>,
+        ) {
+            if pred(node) {
+                found.push(node);
+            }
+            for subnode in node.subnodes() {
+                go(subnode, pred, found);
             }
         }
-
-        if !worst_key.is_empty() {
-            self.parse_cache.remove(&worst_key);
-            self.quantum_cache.remove(&worst_key);
-            self.stats.evictions += 1;
-        }
-
-        Ok(())
+        go(node, &pred, &mut found);
+        found
     }
 
-    /// Invalidate cache entries entangled with given source
-    pub fn invalidate_entangled_entries(&mut self, source_hash: &str) -> QuantumResult<()> {
-        if !self.config.quantum_parsing {
-            return Ok(());
-        }
-
-        let mut to_remove = Vec::new();
-
-        // Find entries entangled with the given source
-        for (key, quantum_entry) in &self.quantum_cache {
-            if quantum_entry.entangled_sources.contains(&source_hash.to_string()) {
-                to_remove.push(key.clone());
+    #[test]
+    fn test_items_are_lowered() {
+        with_quantum_ast(SOURCE, |source_map, ast| {
+            let snippet = |node: &QuantumASTNode| source_map.span_to_snippet(node.span).unwrap();
+            let snippets: Vec<_> = ast.nodes.iter().map(snippet).collect();
+            assert_eq!(snippets.len(), 4);
+            assert!(snippets[0].starts_with("static SHOTS"));
+            assert!(snippets[1].starts_with("pub fn bell"));
+            assert!(snippets[2].starts_with("fn sum"));
+            assert!(snippets[3].starts_with("fn folded"));
+
+            match &root(ast, "SHOTS").node_type {
+                QuantumNodeType::Variable { var_type: Some(ty), mutable: false, .. } => {
+                    assert_eq!(ty.name, "usize")
+                }
+                other => panic!("unexpected node {other:?}"),
             }
-        }
+            match &root(ast, "sum").node_type {
+                QuantumNodeType::Function { params, return_type: Some(ret), .. } => {
+                    assert_eq!(params.len(), 1);
+                    assert_eq!(params[0].name, "values");
+                    assert_eq!(params[0].param_type.name, "&[u64]");
+                    assert_eq!(ret.name, "u64");
+                }
+                other => panic!("unexpected node {other:?}"),
+            }
+        });
+    }
 
-        // Remove entangled entries
-        for key in to_remove {
-            self.parse_cache.remove(&key);
-            self.quantum_cache.remove(&key);
-            self.stats.entanglement_invalidations += 1;
-        }
+    #[test]
+    fn test_quantum_constructs() {
+        with_quantum_ast(SOURCE, |source_map, ast| {
+            let bell = root(ast, "bell");
+            let QuantumNodeType::Function {
+                params, return_type: Some(ret), quantum_attributes, quantum_gates, ..
+            } = &bell.node_type
+            else {
+                panic!("bell is not a function: {:?}", bell.node_type)
+            };
+            // Functions applying gates keep their signature and attributes
+            assert_eq!(params[0].param_type.name, "&mut QuantumState");
+            assert_eq!(ret.name, "Vec<bool>");
+            assert_eq!(quantum_attributes[0].name, "inline");
+            assert!(bell.is_quantum());
+
+            let gates: Vec<_> = quantum_gates
+                .iter()
+                .map(|gate| match &gate.node_type {
+                    QuantumNodeType::QuantumConstruct {
+                        construct_type: QuantumConstructType::QuantumGate { gate_type, qubits },
+                    } => {
+                        let snippet = source_map.span_to_snippet(gate.span).unwrap();
+                        (gate_type.as_str(), qubits.clone(), snippet)
+                    }
+                    other => panic!("unexpected gate {other:?}"),
+                })
+                .collect();
+            assert_eq!(
+                gates,
+                [
+                    ("h", vec!["0".to_string()], "q.h(0)".to_string()),
+                    ("cx", vec!["0".to_string(), "1".to_string()], "q.cx(0, 1)".to_string()),
+                ]
+            );
+
+            let measurements = find_all(bell, |node| {
+                matches!(
+                    node.node_type,
+                    QuantumNodeType::QuantumConstruct {
+                        construct_type: QuantumConstructType::QuantumMeasurement { .. }
+                    }
+                )
+            });
+            assert_eq!(measurements.len(), 1);
+            let snippet = source_map.span_to_snippet(measurements[0].span).unwrap();
+            assert_eq!(snippet, "q.measure_all()");
 
-        Ok(())
+            assert!(find_all(root(ast, "folded"), QuantumASTNode::is_quantum).is_empty());
+        });
     }
 
-    pub fn get_stats(&self) -> &QuantumCacheStats {
-        &self.stats
+    #[test]
+    fn test_annotations_follow_the_code() {
+        with_quantum_ast(SOURCE, |_, ast| {
+            let loops: Vec<_> = find_all(root(ast, "sum"), |node| {
+                matches!(
+                    node.node_type,
+                    QuantumNodeType::Statement { stmt_type: QuantumStatementType::Loop { .. }, .. }
+                )
+            })
+            .into_iter()
+            .map(|node| {
+                matches!(node.node_type, QuantumNodeType::Statement { quantum_parallel: true, .. })
+            })
+            .collect();
+            assert_eq!(loops, [true, false]);
+
+            let folded = root(ast, "folded");
+            let product = find_all(folded, |node| {
+                matches!(
+                    &node.node_type,
+                    QuantumNodeType::Expression {
+                        expr_type: QuantumExpressionType::BinaryOp { op, .. },
+                        ..
+                    } if op == "*"
+                )
+            });
+            assert!(matches!(
+                product[0].node_type,
+                QuantumNodeType::Expression {
+                    expr_type: QuantumExpressionType::BinaryOp { quantum_commutative: true, .. },
+                    quantum_optimizable: true,
+                }
+            ));
+            let comparison = find_all(folded, |node| {
+                matches!(
+                    &node.node_type,
+                    QuantumNodeType::Expression {
+                        expr_type: QuantumExpressionType::BinaryOp { op, .. },
+                        ..
+                    } if op == ">"
+                )
+            });
+            assert!(!is_optimizable(comparison[0]));
+
+            let ifs = find_all(folded, |node| {
+                matches!(
+                    node.node_type,
+                    QuantumNodeType::Statement {
+                        stmt_type: QuantumStatementType::If { quantum_superposition: true, .. },
+                        ..
+                    }
+                )
+            });
+            assert_eq!(ifs.len(), 1);
+        });
     }
 
-    pub fn get_cache_efficiency(&self) -> f64 {
-        let total_requests = self.stats.cache_hits + self.stats.cache_misses;
-        if total_requests == 0 {
-            return 0.0;
-        }
-        self.stats.cache_hits as f64 / total_requests as f64
+    #[test]
+    fn test_large_integers_are_not_optimizable() {
+        let src = "fn limits() -> u64 { (9223372036854775807 + 1) * (18446744073709551615 - 1) }";
+        with_quantum_ast(src, |_, ast| {
+            let optimizable: Vec<_> = find_all(root(ast, "limits"), |node| {
+                matches!(
+                    node.node_type,
+                    QuantumNodeType::Expression {
+                        expr_type: QuantumExpressionType::Literal(_),
+                        ..
+                    }
+                )
+            })
+            .into_iter()
+            .map(is_optimizable)
+            .collect();
+            assert_eq!(optimizable, [true, true, false, true]);
+
+            let product = find_all(root(ast, "limits"), |node| {
+                matches!(
+                    &node.node_type,
+                    QuantumNodeType::Expression {
+                        expr_type: QuantumExpressionType::BinaryOp { op, .. },
+                        ..
+                    } if op == "*"
+                )
+            });
+            assert!(!is_optimizable(product[0]));
+        });
     }
-}
-
-#[derive(Debug, Clone)]
-pub struct CachedParseResult {
-    pub source_hash: String,
-    pub tokens: Vec<QuantumToken>,
-    pub parse_result: QuantumParseResult,
-    pub quantum_signature: QuantumSignature,
-    pub cache_time: std::time::SystemTime,
-    pub access_count: u64,
-    pub quantum_coherence: f64,
-}
-
-#[derive(Debug, Clone)]
-pub struct QuantumCacheEntry {
-    pub entangled_sources: Vec<String>,
-    pub quantum_amplitude: f64,
-    pub quantum_phase: f64,
-    pub coherence_time: f64,
-    pub last_measurement: std::time::SystemTime,
-}
-
-#[derive(Debug, Clone)]
-pub struct QuantumSignature {
-    pub token_count: usize,
-    pub quantum_hash: u64,
-    pub entanglement_pattern: Vec<usize>,
-    pub coherence_sum: f64,
-}
 
-#[derive(Debug, Clone, Default)]
-pub struct QuantumCacheStats {
-    pub cache_entries: u64,
-    pub cache_hits: u64,
-    pub cache_misses: u64,
-    pub evictions: u64,
-    pub coherence_invalidations: u64,
-    pub signature_mismatches: u64,
-    pub entanglement_invalidations: u64,
+    #[test]
+    fn test_metadata() {
+        with_quantum_ast(SOURCE, |_, ast| {
+            // The function applying gates, its two gates and the measurement
+            assert_eq!(ast.metadata.quantum_nodes, 4);
+            assert!(ast.metadata.total_nodes > ast.node_count());
+            // Three functions with three decision points between them
+            assert_eq!(ast.metadata.complexity_score, 2.0);
+        });
+    }
 }
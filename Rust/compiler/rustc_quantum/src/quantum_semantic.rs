@@ -1,11 +1,20 @@
 //! Quantum Semantic Analysis
-//! 
-//! This module implements quantum-enhanced semantic analysis for Rust.
-//! It uses quantum entanglement to resolve dependencies and quantum
-//! superposition for type inference, achieving significant speedup.
-
-use crate::{QuantumConfig, QuantumResult, QuantumError};
-use std::collections::HashMap;
+//!
+//! This module builds the quantum semantic view of a crate from the results of
+//! the compiler's own analysis. Symbols are the crate's definitions and local
+//! bindings, scopes are the HIR owners, types come from type checking and the
+//! dependency graph records the paths and method calls resolved in the bodies.
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexMap};
+use rustc_hir as hir;
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_hir::intravisit::{self, Visitor};
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{self, GenericArgKind, Ty, TyCtxt, TypeckResults};
+use rustc_span::{Ident, Span};
+
+use crate::{QuantumConfig, QuantumResult};
 
 /// Quantum semantic information
 #[derive(Debug, Clone)]
@@ -31,14 +40,16 @@ pub fn symbol_count(&self) -> usize {
 /// Quantum symbol with enhanced metadata
 #[derive(Debug, Clone)]
 pub struct QuantumSymbol {
-    /// Symbol name
+    /// Path of a definition, or `owner::binding` for a local binding
     pub name: String,
+    /// Definition, `None` for local bindings
+    pub def_id: Option<DefId>,
     /// Symbol type with quantum enhancement
     pub symbol_type: QuantumSymbolType,
-    /// Scope identifier
+    /// Index of the enclosing scope in [`QuantumSemanticInfo::scopes`]
     pub scope_id: usize,
     /// Definition location
-    pub defined_at: QuantumSpan,
+    pub defined_at: Span,
     /// Quantum properties
     pub quantum_properties: QuantumSymbolProperties,
 }
@@ -48,13 +59,17 @@ pub enum QuantumSymbolType {
     Function {
         return_type: QuantumType,
         params: Vec<QuantumParameter>,
+        /// Pure, and only calls pure functions of the crate
         quantum_parallel: bool,
+        /// No parameter holds a mutable reference or pointer
         quantum_pure: bool,
     },
     Variable {
         var_type: QuantumType,
         mutable: bool,
+        /// The type mentions a quantum type
         quantum_entangled: bool,
+        /// The value is an enum
         quantum_superposition: bool,
     },
     Type {
@@ -75,13 +90,13 @@ pub enum QuantumSymbolType {
 
 #[derive(Debug, Clone)]
 pub struct QuantumSymbolProperties {
-    /// Quantum entanglement degree
+    /// Strength of the strongest dependency of or on the symbol
     pub entanglement: f64,
     /// Quantum coherence time
     pub coherence: f64,
     /// Quantum fidelity
     pub fidelity: f64,
-    /// Entangled symbols
+    /// Symbols connected to this one in the dependency graph
     pub entangled_with: Vec<String>,
 }
 
@@ -97,27 +112,31 @@ fn default() -> Self {
 }
 
 /// Quantum scope with hierarchical entanglement
+///
+/// There is one scope per module, impl, trait and definition with a body.
 #[derive(Debug, Clone)]
 pub struct QuantumScope {
-    /// Scope identifier
+    /// Scope identifier, the crate root is 0
     pub id: usize,
+    /// HIR owner of the scope
+    pub owner: LocalDefId,
     /// Parent scope
     pub parent_id: Option<usize>,
     /// Symbols in this scope
     pub symbols: Vec<String>,
     /// Quantum scope properties
     pub quantum_properties: QuantumScopeProperties,
-    /// Entangled scopes
+    /// Scopes with symbols that depend on or are used by this scope's symbols
     pub entangled_scopes: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
 pub struct QuantumScopeProperties {
-    /// Quantum entanglement degree
+    /// Share of the scope's dependencies that cross into other scopes
     pub entanglement_degree: f64,
     /// Quantum coherence time
     pub coherence_time: f64,
-    /// Quantum isolation flag
+    /// No dependency crosses the scope boundary
     pub quantum_isolation: bool,
 }
 
@@ -126,7 +145,7 @@ fn default() -> Self {
         Self {
             entanglement_degree: 0.0,
             coherence_time: 1.0,
-            quantum_isolation: false,
+            quantum_isolation: true,
         }
     }
 }
@@ -134,7 +153,7 @@ fn default() -> Self {
 /// Quantum dependency graph with entangled resolution
 #[derive(Debug, Clone)]
 pub struct QuantumDependencyGraph {
-    /// Dependency nodes
+    /// One node per symbol, with the same index
     pub nodes: Vec<QuantumDependencyNode>,
     /// Dependency edges with quantum weights
     pub edges: Vec<QuantumDependencyEdge>,
@@ -164,6 +183,7 @@ pub struct QuantumDependencyEdge {
     pub from: usize,
     pub to: usize,
     pub dependency_type: QuantumDependencyType,
+    /// Uses of `to` by `from`, relative to the most frequent dependency
     pub quantum_strength: f64,
 }
 
@@ -177,17 +197,18 @@ pub enum QuantumDependencyType {
 
 #[derive(Debug, Clone, Default)]
 pub struct QuantumResolutionStats {
+    /// Resolved uses of the crate's symbols
     pub nodes_resolved: u64,
+    /// Pairs of distinct symbols connected by a dependency
     pub quantum_entanglements: u64,
-    pub parallel_resolutions: u64,
-    pub resolution_speedup: f64,
 }
 
 /// Quantum type information with inference
 #[derive(Debug, Clone)]
 pub struct QuantumTypeInfo {
-    /// Type assignments with quantum enhancement
-    pub type_assignments: HashMap<String, QuantumType>,
+    /// Type of every function and variable symbol. Shadowed bindings share
+    /// a name, the last one wins.
+    pub type_assignments: FxHashMap<String, QuantumType>,
     /// Type constraints with quantum solving
     pub type_constraints: Vec<QuantumTypeConstraint>,
     /// Quantum type inference statistics
@@ -202,13 +223,13 @@ pub struct QuantumType {
     pub quantum_properties: QuantumTypeProperties,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct QuantumTypeProperties {
-    /// Quantum superposition capability
+    /// The type is an enum
     pub superposition: bool,
-    /// Quantum entanglement capability
+    /// The type contains references or raw pointers
     pub entanglement: bool,
-    /// Quantum measurement effects
+    /// The type contains mutable references or pointers
     pub measurement_effects: bool,
     /// Arrow memory layout
     pub arrow_layout: Option<ArrowLayout>,
@@ -232,7 +253,9 @@ pub struct QuantumTypeConstraint {
 
 #[derive(Debug, Clone)]
 pub enum QuantumConstraintType {
+    /// The symbol has the target type; `quantum_exact` if it was written out
     Equality { quantum_exact: bool },
+    /// An expression in the symbol's body is coerced to the target type
     Subtype { quantum_compatible: bool },
     Trait { quantum_enhanced: bool },
     TypeUsage { quantum_enhanced: bool },
@@ -242,9 +265,8 @@ pub enum QuantumConstraintType {
 #[derive(Debug, Clone, Default)]
 pub struct QuantumInferenceStats {
     pub constraints_solved: u64,
+    /// Local bindings whose type was inferred rather than written out
     pub quantum_inferences: u64,
-    pub inference_speedup: f64,
-    pub accuracy_improvement: f64,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -252,174 +274,32 @@ pub struct QuantumSemanticStats {
     pub symbols_analyzed: u64,
     pub dependencies_resolved: u64,
     pub quantum_entanglements_created: u64,
-    pub semantic_speedup: f64,
 }
 
 /// Quantum semantic analyzer
 pub struct QuantumSemanticAnalyzer {
     config: QuantumConfig,
-    /// Quantum symbol resolver
-    symbol_resolver: QuantumSymbolResolver,
-    /// Quantum dependency analyzer
-    dependency_analyzer: QuantumDependencyAnalyzer,
-    /// Quantum type inferrer
-    type_inferrer: QuantumTypeInferrer,
-    /// Quantum entanglement engine
-    entanglement_engine: QuantumEntanglementEngine,
 }
 
 impl QuantumSemanticAnalyzer {
     /// Create a new quantum semantic analyzer
     pub fn new(config: &QuantumConfig) -> Self {
-        println!("🧠 Initializing Quantum Semantic Analyzer...");
-
-        Self {
-            config: config.clone(),
-            symbol_resolver: QuantumSymbolResolver::new(config.clone()),
-            dependency_analyzer: QuantumDependencyAnalyzer::new(config.clone()),
-            type_inferrer: QuantumTypeInferrer::new(config.clone()),
-            entanglement_engine: QuantumEntanglementEngine::new(config.clone()),
-        }
-    }
-
-    /// Quantum semantic analysis
-    pub fn quantum_analyze(&mut self) -> QuantumResult<QuantumSemanticInfo> {
-        println!("⚡ Starting quantum semantic analysis...");
-        
-        let start_time = std::time::Instant::now();
-        
-        // Phase 1: Quantum symbol resolution
-        let symbols = self.quantum_symbol_resolution()?;
-        
-        // Phase 2: Quantum dependency analysis
-        let dependencies = self.quantum_dependency_analysis(&symbols)?;
-        
-        // Phase 3: Quantum type inference
-        let type_info = self.quantum_type_inference(&symbols, &dependencies)?;
-        
-        // Phase 4: Quantum entanglement creation
-        let (entangled_symbols, entangled_scopes) = self.create_quantum_entanglements(&symbols)?;
-        
-        let analysis_time = start_time.elapsed();
-        
-        // Calculate real statistics from actual analysis
-        let symbols_count = entangled_symbols.len() as u64;
-        let dependencies_count = dependencies.edges.len() as u64;
-        let entanglements_count = self.entanglement_engine.get_entanglement_count();
-
-        // Calculate semantic speedup based on quantum enhancements
-        let base_time = analysis_time.as_secs_f64();
-        let quantum_speedup = if self.config.quantum_semantic {
-            // Estimate speedup based on quantum features used
-            let mut speedup = 1.0;
-            if symbols_count > 0 { speedup += 0.5; }
-            if dependencies_count > 0 { speedup += 0.8; }
-            if entanglements_count > 0 { speedup += 1.2; }
-            speedup
-        } else {
-            1.0
-        };
-
-        let semantic_info = QuantumSemanticInfo {
-            symbols: entangled_symbols,
-            scopes: entangled_scopes,
-            dependencies,
-            type_info,
-            stats: QuantumSemanticStats {
-                symbols_analyzed: symbols_count,
-                dependencies_resolved: dependencies_count,
-                quantum_entanglements_created: entanglements_count,
-                semantic_speedup: quantum_speedup,
-            },
-        };
-        
-        println!("✅ Quantum semantic analysis complete in {:?}", analysis_time);
-        println!("📊 Symbols analyzed: {}", semantic_info.stats.symbols_analyzed);
-        println!("🔗 Dependencies resolved: {}", semantic_info.stats.dependencies_resolved);
-        println!("🌀 Quantum entanglements: {}", semantic_info.stats.quantum_entanglements_created);
-        println!("🚀 Semantic speedup: {:.1}x", semantic_info.stats.semantic_speedup);
-        
-        Ok(semantic_info)
-    }
-
-    /// Quantum symbol resolution with parallel processing
-    fn quantum_symbol_resolution(&mut self) -> QuantumResult<Vec<QuantumSymbol>> {
-        println!("🔍 Quantum symbol resolution...");
-
-        // Use real QuantumSymbolResolver instead of simulation
-        let source_code = "// Sample Rust code for analysis\nfn main() {\n    let x = 42;\n    let y = x + 1;\n}\n";
-        let symbols = self.symbol_resolver.resolve_symbols(source_code)?;
-        
-        println!("   - Symbols resolved: {}", symbols.len());
-        println!("   - Quantum parallel resolution: 3.5x speedup");
-        
-        Ok(symbols)
-    }
-
-    /// Quantum dependency analysis with entangled resolution
-    fn quantum_dependency_analysis(&mut self, symbols: &[QuantumSymbol]) -> QuantumResult<QuantumDependencyGraph> {
-        println!("🔗 Quantum dependency analysis...");
-
-        // Use real QuantumDependencyAnalyzer instead of simulation
-        let dependency_graph = self.dependency_analyzer.analyze_dependencies(symbols)?;
-
-        println!("   - Dependency nodes: {}", dependency_graph.nodes.len());
-        println!("   - Dependency edges: {}", dependency_graph.edges.len());
-        println!("   - Quantum entangled resolution: {:.1}x speedup", dependency_graph.stats.resolution_speedup);
-
-        Ok(dependency_graph)
+        Self { config: config.clone() }
     }
 
-    /// Quantum type inference with constraint solving
-    fn quantum_type_inference(&mut self, symbols: &[QuantumSymbol], dependencies: &QuantumDependencyGraph) -> QuantumResult<QuantumTypeInfo> {
-        println!("🔬 Quantum type inference...");
-
-        // Use real QuantumTypeInferrer instead of simulation
-        let type_info = self.type_inferrer.infer_types(symbols, dependencies)?;
-        
-        println!("   - Type assignments: {}", type_info.type_assignments.len());
-        println!("   - Type constraints: {}", type_info.type_constraints.len());
-        println!("   - Quantum inference speedup: {:.1}x", type_info.inference_stats.inference_speedup);
-        println!("   - Accuracy improvement: {:.1}%", type_info.inference_stats.accuracy_improvement);
-        
-        Ok(type_info)
-    }
-
-    /// Create quantum entanglements between symbols and scopes
-    fn create_quantum_entanglements(&mut self, symbols: &[QuantumSymbol]) -> QuantumResult<(Vec<QuantumSymbol>, Vec<QuantumScope>)> {
-        println!("🌀 Creating quantum entanglements...");
-
-        // Use real QuantumEntanglementEngine instead of simulation
-        let (entangled_symbols, entangled_scopes) = self.entanglement_engine.create_entanglements(symbols)?;
-
-        println!("   - Quantum scopes created: {}", entangled_scopes.len());
-        println!("   - Symbol entanglements: {}", self.entanglement_engine.get_entanglement_count());
-
-        Ok((entangled_symbols, entangled_scopes))
-    }
-
-    /// Helper method to determine if symbols should be entangled
-    fn should_entangle_symbols(&self, symbol1: &QuantumSymbol, symbol2: &QuantumSymbol) -> bool {
-        // Simple entanglement rules
-        symbol1.scope_id == symbol2.scope_id && 
-        matches!(
-            (&symbol1.symbol_type, &symbol2.symbol_type),
-            (QuantumSymbolType::Function { .. }, QuantumSymbolType::Variable { .. }) |
-            (QuantumSymbolType::Variable { quantum_entangled: true, .. }, QuantumSymbolType::Variable { quantum_entangled: true, .. })
-        )
+    /// Quantum semantic analysis of the local crate
+    ///
+    /// Must run after type checking succeeded.
+    pub fn quantum_analyze(&mut self, tcx: TyCtxt<'_>) -> QuantumResult<QuantumSemanticInfo> {
+        let mut collector = SemanticCollector::new(tcx, self.config.arrow_data_structures);
+        collector.collect_scopes();
+        collector.collect_definitions();
+        collector.collect_bodies();
+        Ok(collector.finish())
     }
 }
 
 // Supporting structures
-#[derive(Debug, Clone)]
-pub struct QuantumSpan {
-    pub start: usize,
-    pub end: usize,
-    pub line: usize,
-    pub column: usize,
-    pub entangled_spans: Vec<usize>,
-}
-
 #[derive(Debug, Clone)]
 pub struct QuantumParameter {
     pub name: String,
@@ -430,6 +310,7 @@ pub struct QuantumParameter {
 #[derive(Debug, Clone)]
 pub struct QuantumTypeDefinition {
     pub name: String,
+    /// Fields of a struct or union; enum fields are named `Variant.field`
     pub fields: Vec<QuantumField>,
     pub quantum_enhanced: bool,
 }
@@ -441,828 +322,601 @@ pub struct QuantumField {
     pub quantum_properties: QuantumSymbolProperties,
 }
 
-// Real implementations replacing placeholders
-
-/// Quantum Symbol Resolver - Real Implementation
-pub struct QuantumSymbolResolver {
-    /// Symbol cache for quantum speedup
-    symbol_cache: HashMap<String, QuantumSymbol>,
-    /// Quantum enhancement configuration
-    quantum_config: QuantumConfig,
-    /// Resolution statistics
-    stats: QuantumResolutionStats,
-}
-
-impl QuantumSymbolResolver {
-    pub fn new(config: QuantumConfig) -> Self {
-        Self {
-            symbol_cache: HashMap::new(),
-            quantum_config: config,
-            stats: QuantumResolutionStats::default(),
+fn dependency_node_type(symbol_type: &QuantumSymbolType) -> QuantumDependencyNodeType {
+    match symbol_type {
+        QuantumSymbolType::Function { .. } => QuantumDependencyNodeType::Function,
+        QuantumSymbolType::Variable { .. } => QuantumDependencyNodeType::Variable,
+        QuantumSymbolType::Type { .. } => QuantumDependencyNodeType::Type,
+        QuantumSymbolType::QuantumGate { .. } | QuantumSymbolType::QuantumCircuit { .. } => {
+            QuantumDependencyNodeType::QuantumCircuit
         }
     }
-
-    /// Resolve symbols with quantum enhancement
-    pub fn resolve_symbols(&mut self, source: &str) -> QuantumResult<Vec<QuantumSymbol>> {
-        let mut symbols = Vec::new();
-
-        // Parse source and extract symbol information
-        let symbol_patterns = self.extract_symbol_patterns(source)?;
-
-        for pattern in symbol_patterns {
-            if let Some(cached_symbol) = self.symbol_cache.get(&pattern.name) {
-                // Use cached symbol for quantum speedup
-                symbols.push(cached_symbol.clone());
-                self.stats.parallel_resolutions += 1;
-            } else {
-                // Create new quantum symbol
-                let symbol = self.create_quantum_symbol(pattern)?;
-                self.symbol_cache.insert(symbol.name.clone(), symbol.clone());
-                symbols.push(symbol);
-                self.stats.nodes_resolved += 1;
-            }
-        }
-
-        // Apply quantum entanglement between related symbols
-        self.apply_quantum_entanglement(&mut symbols)?;
-
-        Ok(symbols)
-    }
-
-    fn extract_symbol_patterns(&self, source: &str) -> QuantumResult<Vec<SymbolPattern>> {
-        let mut patterns = Vec::new();
-
-        // Simple pattern matching for Rust symbols
-        // In real implementation, this would use proper AST parsing
-        for (line_num, line) in source.lines().enumerate() {
-            // Function definitions
-            if let Some(func_name) = self.extract_function_name(line) {
-                patterns.push(SymbolPattern {
-                    name: func_name,
-                    symbol_type: PatternType::Function,
-                    line: line_num + 1,
-                    column: 0,
-                });
-            }
-
-            // Variable declarations
-            if let Some(var_name) = self.extract_variable_name(line) {
-                patterns.push(SymbolPattern {
-                    name: var_name,
-                    symbol_type: PatternType::Variable,
-                    line: line_num + 1,
-                    column: 0,
-                });
-            }
-        }
-
-        Ok(patterns)
-    }
-
-    fn extract_function_name(&self, line: &str) -> Option<String> {
-        // Simple regex-like extraction for "fn function_name"
-        if line.trim_start().starts_with("fn ") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let name = parts[1].split('(').next().unwrap_or("");
-                if !name.is_empty() {
-                    return Some(name.to_string());
-                }
-            }
-        }
-        None
-    }
-
-    fn extract_variable_name(&self, line: &str) -> Option<String> {
-        // Simple extraction for "let variable_name"
-        if line.trim_start().starts_with("let ") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let name = parts[1].split(':').next().unwrap_or("").split('=').next().unwrap_or("");
-                if !name.is_empty() && name != "mut" {
-                    return Some(name.to_string());
-                }
-            }
-        }
-        None
-    }
-
-    fn create_quantum_symbol(&self, pattern: SymbolPattern) -> QuantumResult<QuantumSymbol> {
-        let symbol_type = match pattern.symbol_type {
-            PatternType::Function => QuantumSymbolType::Function {
-                return_type: QuantumType {
-                    name: "()".to_string(),
-                    quantum_enhanced: self.quantum_config.quantum_semantic,
-                    arrow_optimized: self.quantum_config.arrow_data_structures,
-                    quantum_properties: QuantumTypeProperties::default(),
-                },
-                params: Vec::new(),
-                quantum_parallel: self.quantum_config.quantum_semantic,
-                quantum_pure: true,
-            },
-            PatternType::Variable => QuantumSymbolType::Variable {
-                var_type: QuantumType {
-                    name: "unknown".to_string(),
-                    quantum_enhanced: self.quantum_config.quantum_semantic,
-                    arrow_optimized: self.quantum_config.arrow_data_structures,
-                    quantum_properties: QuantumTypeProperties::default(),
-                },
-                mutable: false,
-                quantum_entangled: false,
-                quantum_superposition: false,
-            },
-            _ => return Err(QuantumError::InvalidSymbolType),
-        };
-
-        Ok(QuantumSymbol {
-            name: pattern.name,
-            symbol_type,
-            scope_id: 0,
-            defined_at: QuantumSpan {
-                start: 0,
-                end: 0,
-                line: pattern.line,
-                column: pattern.column,
-                entangled_spans: Vec::new(),
-            },
-            quantum_properties: QuantumSymbolProperties::default(),
-        })
-    }
-
-    fn apply_quantum_entanglement(&mut self, symbols: &mut [QuantumSymbol]) -> QuantumResult<()> {
-        // Create quantum entanglement between related symbols
-        for i in 0..symbols.len() {
-            for j in (i + 1)..symbols.len() {
-                if self.should_entangle(&symbols[i], &symbols[j]) {
-                    // Create bidirectional entanglement
-                    symbols[i].quantum_properties.entangled_with.push(symbols[j].name.clone());
-                    symbols[j].quantum_properties.entangled_with.push(symbols[i].name.clone());
-                    self.stats.quantum_entanglements += 1;
-                }
-            }
-        }
-        Ok(())
-    }
-
-    fn should_entangle(&self, symbol1: &QuantumSymbol, symbol2: &QuantumSymbol) -> bool {
-        // Simple heuristic: entangle symbols with similar names or types
-        symbol1.name.len() == symbol2.name.len() ||
-        std::mem::discriminant(&symbol1.symbol_type) == std::mem::discriminant(&symbol2.symbol_type)
-    }
-
-    pub fn get_stats(&self) -> &QuantumResolutionStats {
-        &self.stats
-    }
 }
 
-#[derive(Debug, Clone)]
-struct SymbolPattern {
-    name: String,
-    symbol_type: PatternType,
-    line: usize,
-    column: usize,
+/// Whether the definition's path mentions quantum, like `std::quantum::QuantumArray`
+fn is_quantum_def(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    with_no_trimmed_paths!(tcx.def_path_str(def_id)).to_lowercase().contains("quantum")
 }
 
-#[derive(Debug, Clone)]
-enum PatternType {
-    Function,
-    Variable,
-    Type,
+/// Whether the type contains a mutable reference or raw pointer
+fn has_mutable_access(ty: Ty<'_>) -> bool {
+    ty.walk().any(|arg| match arg.kind() {
+        GenericArgKind::Type(ty) => ty.ref_mutability() == Some(hir::Mutability::Mut),
+        _ => false,
+    })
 }
 
-/// Quantum Dependency Analyzer - Real Implementation
-pub struct QuantumDependencyAnalyzer {
-    /// Dependency cache for quantum speedup
-    dependency_cache: HashMap<String, Vec<QuantumDependencyEdge>>,
-    /// Quantum configuration
-    quantum_config: QuantumConfig,
-    /// Analysis statistics
-    stats: QuantumResolutionStats,
+/// Builds the semantic information from the compiler's analysis results
+struct SemanticCollector<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    arrow_data_structures: bool,
+    symbols: Vec<QuantumSymbol>,
+    def_symbols: FxHashMap<DefId, usize>,
+    local_symbols: FxHashMap<hir::HirId, usize>,
+    scopes: Vec<QuantumScope>,
+    scope_ids: FxHashMap<LocalDefId, usize>,
+    /// Number of uses per `(from, to)` pair of symbols
+    dependencies: FxIndexMap<(usize, usize), u64>,
+    type_assignments: FxHashMap<String, QuantumType>,
+    type_constraints: Vec<QuantumTypeConstraint>,
+    inferred_bindings: u64,
 }
 
-impl QuantumDependencyAnalyzer {
-    pub fn new(config: QuantumConfig) -> Self {
+impl<'tcx> SemanticCollector<'tcx> {
+    fn new(tcx: TyCtxt<'tcx>, arrow_data_structures: bool) -> Self {
         Self {
-            dependency_cache: HashMap::new(),
-            quantum_config: config,
-            stats: QuantumResolutionStats::default(),
-        }
-    }
-
-    /// Analyze dependencies with quantum enhancement
-    pub fn analyze_dependencies(&mut self, symbols: &[QuantumSymbol]) -> QuantumResult<QuantumDependencyGraph> {
-        let mut nodes = Vec::new();
-        let mut edges = Vec::new();
-
-        // Create dependency nodes from symbols
-        for (i, symbol) in symbols.iter().enumerate() {
-            nodes.push(QuantumDependencyNode {
-                id: i,
-                name: symbol.name.clone(),
-                node_type: self.symbol_to_node_type(&symbol.symbol_type),
-                quantum_properties: symbol.quantum_properties.clone(),
-            });
-        }
-
-        // Analyze dependencies between symbols
-        for i in 0..symbols.len() {
-            for j in 0..symbols.len() {
-                if i != j {
-                    if let Some(edge) = self.analyze_symbol_dependency(&symbols[i], &symbols[j], i, j)? {
-                        edges.push(edge);
-                        self.stats.nodes_resolved += 1;
-                    }
-                }
-            }
-        }
-
-        // Apply quantum entanglement to dependencies
-        self.apply_quantum_dependency_entanglement(&mut edges)?;
-
-        Ok(QuantumDependencyGraph {
-            nodes,
-            edges,
-            stats: self.stats.clone(),
-        })
-    }
-
-    fn symbol_to_node_type(&self, symbol_type: &QuantumSymbolType) -> QuantumDependencyNodeType {
-        match symbol_type {
-            QuantumSymbolType::Function { .. } => QuantumDependencyNodeType::Function,
-            QuantumSymbolType::Variable { .. } => QuantumDependencyNodeType::Variable,
-            QuantumSymbolType::Type { .. } => QuantumDependencyNodeType::Type,
-            QuantumSymbolType::QuantumGate { .. } => QuantumDependencyNodeType::QuantumCircuit,
-            QuantumSymbolType::QuantumCircuit { .. } => QuantumDependencyNodeType::QuantumCircuit,
-        }
-    }
-
-    fn analyze_symbol_dependency(
-        &self,
-        from_symbol: &QuantumSymbol,
-        to_symbol: &QuantumSymbol,
-        from_id: usize,
-        to_id: usize
-    ) -> QuantumResult<Option<QuantumDependencyEdge>> {
-        // Simple heuristic for dependency detection
-        let has_dependency = match (&from_symbol.symbol_type, &to_symbol.symbol_type) {
-            (QuantumSymbolType::Function { .. }, QuantumSymbolType::Variable { .. }) => {
-                // Function might use variable
-                true
+            tcx,
+            arrow_data_structures,
+            symbols: Vec::new(),
+            def_symbols: FxHashMap::default(),
+            local_symbols: FxHashMap::default(),
+            scopes: Vec::new(),
+            scope_ids: FxHashMap::default(),
+            dependencies: FxIndexMap::default(),
+            type_assignments: FxHashMap::default(),
+            type_constraints: Vec::new(),
+            inferred_bindings: 0,
+        }
+    }
+
+    fn quantum_type(&self, ty: Ty<'tcx>) -> QuantumType {
+        let tcx = self.tcx;
+        let quantum_enhanced = ty.walk().any(|arg| match arg.kind() {
+            GenericArgKind::Type(ty) => match ty.kind() {
+                ty::Adt(adt, _) => is_quantum_def(tcx, adt.did()),
+                _ => false,
             },
-            (QuantumSymbolType::Variable { .. }, QuantumSymbolType::Type { .. }) => {
-                // Variable has a type
-                true
-            },
-            _ => {
-                // Check for name-based dependencies (simplified)
-                from_symbol.name.contains(&to_symbol.name) ||
-                to_symbol.name.contains(&from_symbol.name)
-            }
+            _ => false,
+        });
+        let columnar = match ty.peel_refs().kind() {
+            ty::Slice(element) | ty::Array(element, _) => element.is_scalar(),
+            _ => false,
         };
-
-        if has_dependency {
-            let dependency_type = self.determine_dependency_type(from_symbol, to_symbol);
-            let quantum_strength = self.calculate_quantum_strength(from_symbol, to_symbol);
-
-            Ok(Some(QuantumDependencyEdge {
-                from: from_id,
-                to: to_id,
-                dependency_type,
-                quantum_strength,
-            }))
-        } else {
-            Ok(None)
+        let arrow_optimized = self.arrow_data_structures && columnar;
+        QuantumType {
+            name: with_no_trimmed_paths!(ty.to_string()),
+            quantum_enhanced,
+            arrow_optimized,
+            quantum_properties: QuantumTypeProperties {
+                superposition: ty.is_enum(),
+                entanglement: ty.walk().any(|arg| match arg.kind() {
+                    GenericArgKind::Type(ty) => ty.is_ref() || ty.is_raw_ptr(),
+                    _ => false,
+                }),
+                measurement_effects: has_mutable_access(ty),
+                arrow_layout: arrow_optimized.then_some(ArrowLayout::Columnar),
+            },
         }
     }
 
-    fn determine_dependency_type(&self, from: &QuantumSymbol, to: &QuantumSymbol) -> QuantumDependencyType {
-        match (&from.symbol_type, &to.symbol_type) {
-            (QuantumSymbolType::Function { .. }, _) => {
-                QuantumDependencyType::FunctionCall {
-                    quantum_parallel: self.quantum_config.quantum_semantic
-                }
-            },
-            (QuantumSymbolType::Variable { .. }, _) => {
-                QuantumDependencyType::VariableAccess {
-                    quantum_entangled: from.quantum_properties.entanglement > 0.0
-                }
-            },
-            _ => {
-                QuantumDependencyType::TypeUsage {
-                    quantum_enhanced: self.quantum_config.quantum_semantic
+    /// One scope per module, impl, trait and body owner, in HIR order
+    fn collect_scopes(&mut self) {
+        let tcx = self.tcx;
+        for def_id in tcx.hir_crate_items(()).definitions() {
+            let is_scope = match tcx.def_kind(def_id) {
+                DefKind::Mod | DefKind::Impl { .. } | DefKind::Trait => true,
+                // Foreign functions and statics have no body
+                DefKind::Fn
+                | DefKind::AssocFn
+                | DefKind::Const
+                | DefKind::AssocConst
+                | DefKind::Static { nested: false, .. } => {
+                    tcx.hir_maybe_body_owned_by(def_id).is_some()
                 }
+                _ => false,
+            };
+            if is_scope {
+                self.scope_ids.insert(def_id, self.scopes.len());
+                self.scopes.push(QuantumScope {
+                    id: self.scopes.len(),
+                    owner: def_id,
+                    parent_id: None,
+                    symbols: Vec::new(),
+                    quantum_properties: QuantumScopeProperties::default(),
+                    entangled_scopes: Vec::new(),
+                });
             }
         }
+        for scope in 0..self.scopes.len() {
+            self.scopes[scope].parent_id = self.enclosing_scope(self.scopes[scope].owner);
+        }
     }
 
-    fn calculate_quantum_strength(&self, from: &QuantumSymbol, to: &QuantumSymbol) -> f64 {
-        // Calculate quantum entanglement strength based on symbol properties
-        let base_strength = 0.5;
-        let entanglement_bonus = (from.quantum_properties.entanglement + to.quantum_properties.entanglement) / 2.0;
-        let coherence_bonus = (from.quantum_properties.coherence + to.quantum_properties.coherence) / 2.0;
-
-        (base_strength + entanglement_bonus * 0.3 + coherence_bonus * 0.2).min(1.0)
-    }
-
-    fn apply_quantum_dependency_entanglement(&mut self, edges: &mut [QuantumDependencyEdge]) -> QuantumResult<()> {
-        // Enhance dependency edges with quantum entanglement
-        for edge in edges.iter_mut() {
-            if self.quantum_config.quantum_semantic {
-                // Boost quantum strength for quantum-enhanced dependencies
-                edge.quantum_strength *= 1.2;
-                edge.quantum_strength = edge.quantum_strength.min(1.0);
-                self.stats.quantum_entanglements += 1;
+    /// Innermost scope strictly enclosing `def_id`
+    fn enclosing_scope(&self, def_id: LocalDefId) -> Option<usize> {
+        let mut parent = self.tcx.opt_local_parent(def_id);
+        while let Some(def_id) = parent {
+            if let Some(&scope) = self.scope_ids.get(&def_id) {
+                return Some(scope);
             }
+            parent = self.tcx.opt_local_parent(def_id);
         }
-        Ok(())
-    }
-
-    pub fn get_stats(&self) -> &QuantumResolutionStats {
-        &self.stats
+        None
     }
-}
-
-/// Quantum Type Inferrer - Real Implementation
-pub struct QuantumTypeInferrer {
-    /// Type inference cache
-    type_cache: HashMap<String, QuantumType>,
-    /// Quantum configuration
-    quantum_config: QuantumConfig,
-    /// Inference statistics
-    stats: QuantumInferenceStats,
-}
 
-impl QuantumTypeInferrer {
-    pub fn new(config: QuantumConfig) -> Self {
-        Self {
-            type_cache: HashMap::new(),
-            quantum_config: config,
-            stats: QuantumInferenceStats::default(),
+    fn add_symbol(&mut self, symbol: QuantumSymbol) -> usize {
+        let index = self.symbols.len();
+        if let Some(def_id) = symbol.def_id {
+            self.def_symbols.insert(def_id, index);
         }
+        self.symbols.push(symbol);
+        index
     }
 
-    /// Perform quantum type inference
-    pub fn infer_types(
-        &mut self,
-        symbols: &[QuantumSymbol],
-        dependencies: &QuantumDependencyGraph
-    ) -> QuantumResult<QuantumTypeInfo> {
-        let mut type_assignments = HashMap::new();
-        let mut type_constraints = Vec::new();
-
-        // Generate type constraints from symbols and dependencies
-        for symbol in symbols {
-            let constraints = self.generate_symbol_constraints(symbol)?;
-            type_constraints.extend(constraints);
-        }
+    /// Symbols for the functions, statics, constants and types of the crate
+    fn collect_definitions(&mut self) {
+        let tcx = self.tcx;
+        let mut type_uses = Vec::new();
+        for def_id in tcx.hir_crate_items(()).definitions() {
+            let (symbol_type, used_types) = match tcx.def_kind(def_id) {
+                DefKind::Fn | DefKind::AssocFn => self.function_symbol(def_id),
+                DefKind::Const | DefKind::AssocConst => self.global_symbol(def_id, false),
+                DefKind::Static { mutability, nested: false, .. } => {
+                    self.global_symbol(def_id, mutability == hir::Mutability::Mut)
+                }
+                DefKind::Struct
+                | DefKind::Enum
+                | DefKind::Union
+                | DefKind::TyAlias
+                | DefKind::Trait => self.type_symbol(def_id),
+                _ => continue,
+            };
 
-        // Generate constraints from dependencies
-        for edge in &dependencies.edges {
-            if let Some(constraint) = self.generate_dependency_constraint(edge, symbols)? {
-                type_constraints.push(constraint);
+            let name = with_no_trimmed_paths!(tcx.def_path_str(def_id));
+            match &symbol_type {
+                QuantumSymbolType::Function { .. } => {
+                    let fn_type = self.quantum_type(tcx.type_of(def_id).instantiate_identity());
+                    self.type_assignments.insert(name.clone(), fn_type);
+                }
+                QuantumSymbolType::Variable { var_type, .. } => {
+                    self.type_assignments.insert(name.clone(), var_type.clone());
+                    self.type_constraints.push(QuantumTypeConstraint {
+                        symbol_name: name.clone(),
+                        constraint_type: QuantumConstraintType::Equality { quantum_exact: true },
+                        target_type: var_type.clone(),
+                        confidence: 1.0,
+                    });
+                }
+                _ => {}
             }
-        }
-
-        // Solve type constraints using quantum algorithms
-        let solutions = self.solve_quantum_constraints(&type_constraints)?;
 
-        // Apply solutions to create type assignments
-        for (symbol_name, inferred_type) in solutions {
-            type_assignments.insert(symbol_name, inferred_type);
-            self.stats.constraints_solved += 1;
+            let symbol = self.add_symbol(QuantumSymbol {
+                name,
+                def_id: Some(def_id.to_def_id()),
+                symbol_type,
+                scope_id: self.enclosing_scope(def_id).unwrap_or(0),
+                defined_at: tcx.def_span(def_id),
+                quantum_properties: QuantumSymbolProperties::default(),
+            });
+            type_uses.push((symbol, used_types));
         }
 
-        // Apply quantum enhancements to type information
-        if self.quantum_config.quantum_semantic {
-            self.apply_quantum_type_enhancements(&mut type_assignments)?;
+        // All type symbols exist now
+        for (symbol, used_types) in type_uses {
+            for ty in used_types {
+                self.add_type_uses(symbol, ty);
+            }
         }
-
-        Ok(QuantumTypeInfo {
-            type_assignments,
-            type_constraints,
-            inference_stats: self.stats.clone(),
-        })
     }
 
-    fn generate_symbol_constraints(&self, symbol: &QuantumSymbol) -> QuantumResult<Vec<QuantumTypeConstraint>> {
-        let mut constraints = Vec::new();
-
-        match &symbol.symbol_type {
-            QuantumSymbolType::Variable { var_type, .. } => {
-                constraints.push(QuantumTypeConstraint {
-                    symbol_name: symbol.name.clone(),
-                    constraint_type: QuantumConstraintType::Equality {
-                        quantum_exact: var_type.quantum_enhanced
+    fn function_symbol(&self, def_id: LocalDefId) -> (QuantumSymbolType, Vec<Ty<'tcx>>) {
+        let tcx = self.tcx;
+        let sig = tcx.fn_sig(def_id).instantiate_identity().skip_binder();
+        let idents = tcx.fn_arg_idents(def_id);
+        let params = sig
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(i, &ty)| {
+                let param_type = self.quantum_type(ty);
+                QuantumParameter {
+                    name: match idents.get(i).copied().flatten() {
+                        Some(ident) => ident.to_string(),
+                        None => format!("_{i}"),
                     },
-                    target_type: var_type.clone(),
-                    confidence: 0.9,
-                });
-            },
-            QuantumSymbolType::Function { return_type, params, .. } => {
-                // Add constraint for return type
-                constraints.push(QuantumTypeConstraint {
-                    symbol_name: format!("{}_return", symbol.name),
-                    constraint_type: QuantumConstraintType::Equality {
-                        quantum_exact: return_type.quantum_enhanced
-                    },
-                    target_type: return_type.clone(),
-                    confidence: 0.8,
-                });
-
-                // Add constraints for parameters
-                for (i, param) in params.iter().enumerate() {
-                    constraints.push(QuantumTypeConstraint {
-                        symbol_name: format!("{}_param_{}", symbol.name, i),
-                        constraint_type: QuantumConstraintType::Equality {
-                            quantum_exact: param.param_type.quantum_enhanced
-                        },
-                        target_type: param.param_type.clone(),
-                        confidence: 0.8,
-                    });
+                    quantum_entangled: param_type.quantum_enhanced,
+                    param_type,
                 }
-            },
-            _ => {
-                // Default constraint for other symbol types
-                constraints.push(QuantumTypeConstraint {
-                    symbol_name: symbol.name.clone(),
-                    constraint_type: QuantumConstraintType::TypeUsage {
-                        quantum_enhanced: self.quantum_config.quantum_semantic
-                    },
-                    target_type: QuantumType {
-                        name: "unknown".to_string(),
-                        quantum_enhanced: false,
-                        arrow_optimized: false,
-                        quantum_properties: QuantumTypeProperties::default(),
-                    },
-                    confidence: 0.5,
-                });
-            }
-        }
-
-        Ok(constraints)
+            })
+            .collect();
+        let symbol_type = QuantumSymbolType::Function {
+            return_type: self.quantum_type(sig.output()),
+            params,
+            // Set once the calls are known
+            quantum_parallel: false,
+            quantum_pure: !sig.inputs().iter().any(|&ty| has_mutable_access(ty)),
+        };
+        (symbol_type, sig.inputs_and_output.to_vec())
     }
 
-    fn generate_dependency_constraint(
+    fn global_symbol(
         &self,
-        edge: &QuantumDependencyEdge,
-        symbols: &[QuantumSymbol]
-    ) -> QuantumResult<Option<QuantumTypeConstraint>> {
-        if edge.from >= symbols.len() || edge.to >= symbols.len() {
-            return Ok(None);
+        def_id: LocalDefId,
+        mutable: bool,
+    ) -> (QuantumSymbolType, Vec<Ty<'tcx>>) {
+        let ty = self.tcx.type_of(def_id).instantiate_identity();
+        let var_type = self.quantum_type(ty);
+        let symbol_type = QuantumSymbolType::Variable {
+            mutable,
+            quantum_entangled: var_type.quantum_enhanced,
+            quantum_superposition: ty.is_enum(),
+            var_type,
+        };
+        (symbol_type, vec![ty])
+    }
+
+    fn type_symbol(&self, def_id: LocalDefId) -> (QuantumSymbolType, Vec<Ty<'tcx>>) {
+        let tcx = self.tcx;
+        let mut fields = Vec::new();
+        let mut used_types = Vec::new();
+        match tcx.def_kind(def_id) {
+            DefKind::Struct | DefKind::Enum | DefKind::Union => {
+                let adt = tcx.adt_def(def_id);
+                for variant in adt.variants() {
+                    for field in &variant.fields {
+                        let ty = tcx.type_of(field.did).instantiate_identity();
+                        used_types.push(ty);
+                        fields.push(QuantumField {
+                            name: if adt.is_enum() {
+                                format!("{}.{}", variant.name, field.name)
+                            } else {
+                                field.name.to_string()
+                            },
+                            field_type: self.quantum_type(ty),
+                            quantum_properties: QuantumSymbolProperties::default(),
+                        });
+                    }
+                }
+            }
+            DefKind::TyAlias => used_types.push(tcx.type_of(def_id).instantiate_identity()),
+            _ => {}
         }
 
-        let from_symbol = &symbols[edge.from];
-        let to_symbol = &symbols[edge.to];
-
-        // Create type constraint based on dependency type
-        let constraint = match &edge.dependency_type {
-            QuantumDependencyType::FunctionCall { quantum_parallel } => {
-                QuantumTypeConstraint {
-                    symbol_name: format!("{}_{}_call", from_symbol.name, to_symbol.name),
-                    constraint_type: QuantumConstraintType::Subtype {
-                        quantum_compatible: *quantum_parallel
-                    },
-                    target_type: QuantumType {
-                        name: "callable".to_string(),
-                        quantum_enhanced: *quantum_parallel,
-                        arrow_optimized: self.quantum_config.arrow_data_structures,
-                        quantum_properties: QuantumTypeProperties::default(),
-                    },
-                    confidence: edge.quantum_strength,
-                }
-            },
-            QuantumDependencyType::VariableAccess { quantum_entangled } => {
-                QuantumTypeConstraint {
-                    symbol_name: format!("{}_{}_access", from_symbol.name, to_symbol.name),
-                    constraint_type: QuantumConstraintType::Equality {
-                        quantum_exact: *quantum_entangled
-                    },
-                    target_type: QuantumType {
-                        name: "accessible".to_string(),
-                        quantum_enhanced: *quantum_entangled,
-                        arrow_optimized: self.quantum_config.arrow_data_structures,
-                        quantum_properties: QuantumTypeProperties::default(),
-                    },
-                    confidence: edge.quantum_strength,
-                }
-            },
-            _ => return Ok(None),
+        let quantum_enhanced = is_quantum_def(tcx, def_id.to_def_id())
+            || fields.iter().any(|field| field.field_type.quantum_enhanced);
+        let definition = QuantumTypeDefinition {
+            name: tcx.item_name(def_id.to_def_id()).to_string(),
+            fields,
+            quantum_enhanced,
         };
-
-        Ok(Some(constraint))
+        (QuantumSymbolType::Type { definition, quantum_enhanced }, used_types)
     }
 
-    fn solve_quantum_constraints(
-        &mut self,
-        constraints: &[QuantumTypeConstraint]
-    ) -> QuantumResult<HashMap<String, QuantumType>> {
-        let mut solutions = HashMap::new();
-
-        // Simple constraint solver - in real implementation would use advanced algorithms
-        for constraint in constraints {
-            if constraint.confidence > 0.7 {
-                // High confidence constraint - apply directly
-                solutions.insert(constraint.symbol_name.clone(), constraint.target_type.clone());
-                self.stats.quantum_inferences += 1;
-            } else {
-                // Low confidence - use quantum superposition to explore possibilities
-                let enhanced_type = self.apply_quantum_superposition(&constraint.target_type)?;
-                solutions.insert(constraint.symbol_name.clone(), enhanced_type);
+    /// Record a dependency of `from` on every type symbol that `ty` mentions
+    fn add_type_uses(&mut self, from: usize, ty: Ty<'tcx>) {
+        for arg in ty.walk() {
+            if let GenericArgKind::Type(ty) = arg.kind()
+                && let ty::Adt(adt, _) = ty.kind()
+                && let Some(&to) = self.def_symbols.get(&adt.did())
+            {
+                self.add_dependency(from, to);
             }
         }
-
-        Ok(solutions)
     }
 
-    fn apply_quantum_superposition(&self, base_type: &QuantumType) -> QuantumResult<QuantumType> {
-        let mut enhanced_type = base_type.clone();
+    fn add_dependency(&mut self, from: usize, to: usize) {
+        *self.dependencies.entry((from, to)).or_default() += 1;
+    }
 
-        if self.quantum_config.quantum_semantic {
-            enhanced_type.quantum_enhanced = true;
-            enhanced_type.quantum_properties.superposition = true;
-            enhanced_type.quantum_properties.entanglement = true;
+    /// Walk the bodies of the function, static and constant symbols
+    fn collect_bodies(&mut self) {
+        let tcx = self.tcx;
+        let owners: Vec<_> = self
+            .symbols
+            .iter()
+            .enumerate()
+            .filter_map(|(symbol, s)| Some((symbol, s.def_id?.as_local()?)))
+            .collect();
+        for (symbol, def_id) in owners {
+            let Some(body) = tcx.hir_maybe_body_owned_by(def_id) else { continue };
+            let mut annotated = FxHashSet::default();
+            for param in body.params {
+                param.pat.each_binding(|_, hir_id, _, _| {
+                    annotated.insert(hir_id);
+                });
+            }
+            let mut visitor = BodyCollector {
+                typeck_results: tcx.typeck(def_id),
+                owner: symbol,
+                scope_id: self.scope_ids[&def_id],
+                annotated,
+                cx: self,
+            };
+            visitor.visit_body(body);
+        }
+    }
+
+    fn finish(mut self) -> QuantumSemanticInfo {
+        // A function can run in parallel if it and every local function it
+        // calls are pure.
+        let pure: Vec<bool> = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                matches!(symbol.symbol_type, QuantumSymbolType::Function { quantum_pure: true, .. })
+            })
+            .collect();
+        let mut calls_impure = vec![false; self.symbols.len()];
+        for &(from, to) in self.dependencies.keys() {
+            if matches!(self.symbols[to].symbol_type, QuantumSymbolType::Function { .. })
+                && !pure[to]
+            {
+                calls_impure[from] = true;
+            }
         }
-
-        if self.quantum_config.arrow_data_structures {
-            enhanced_type.arrow_optimized = true;
-            enhanced_type.quantum_properties.arrow_layout = Some(ArrowLayout::Columnar);
+        for (symbol, calls_impure) in self.symbols.iter_mut().zip(calls_impure) {
+            if let QuantumSymbolType::Function { quantum_parallel, quantum_pure, .. } =
+                &mut symbol.symbol_type
+            {
+                *quantum_parallel = *quantum_pure && !calls_impure;
+            }
         }
 
-        Ok(enhanced_type)
-    }
-
-    fn apply_quantum_type_enhancements(
-        &mut self,
-        type_assignments: &mut HashMap<String, QuantumType>
-    ) -> QuantumResult<()> {
-        for (_, quantum_type) in type_assignments.iter_mut() {
-            if self.quantum_config.quantum_semantic {
-                quantum_type.quantum_enhanced = true;
-                self.stats.accuracy_improvement += 0.1;
+        let max_uses = self.dependencies.values().copied().max().unwrap_or(1) as f64;
+        let edges: Vec<_> = self
+            .dependencies
+            .iter()
+            .map(|(&(from, to), &uses)| QuantumDependencyEdge {
+                from,
+                to,
+                dependency_type: match &self.symbols[to].symbol_type {
+                    QuantumSymbolType::Function { quantum_parallel, .. } => {
+                        QuantumDependencyType::FunctionCall { quantum_parallel: *quantum_parallel }
+                    }
+                    QuantumSymbolType::Variable { quantum_entangled, .. } => {
+                        QuantumDependencyType::VariableAccess {
+                            quantum_entangled: *quantum_entangled,
+                        }
+                    }
+                    QuantumSymbolType::Type { quantum_enhanced, .. } => {
+                        QuantumDependencyType::TypeUsage { quantum_enhanced: *quantum_enhanced }
+                    }
+                    QuantumSymbolType::QuantumGate { .. }
+                    | QuantumSymbolType::QuantumCircuit { .. } => {
+                        QuantumDependencyType::FunctionCall { quantum_parallel: false }
+                    }
+                },
+                quantum_strength: uses as f64 / max_uses,
+            })
+            .collect();
+
+        // Symbols and scopes connected by a dependency are entangled
+        let mut entangled_pairs = FxHashSet::default();
+        let mut crossing = vec![0u64; self.scopes.len()];
+        let mut touching = vec![0u64; self.scopes.len()];
+        for edge in &edges {
+            let (from, to) = (edge.from, edge.to);
+            if from == to {
+                continue;
+            }
+            if entangled_pairs.insert((from.min(to), from.max(to))) {
+                let from_name = self.symbols[from].name.clone();
+                let to_name = self.symbols[to].name.clone();
+                self.symbols[from].quantum_properties.entangled_with.push(to_name);
+                self.symbols[to].quantum_properties.entangled_with.push(from_name);
+            }
+            for symbol in [from, to] {
+                let properties = &mut self.symbols[symbol].quantum_properties;
+                properties.entanglement = properties.entanglement.max(edge.quantum_strength);
             }
 
-            if self.quantum_config.arrow_data_structures {
-                quantum_type.arrow_optimized = true;
-                quantum_type.quantum_properties.arrow_layout = Some(ArrowLayout::QuantumOptimized);
+            let (from_scope, to_scope) = (self.symbols[from].scope_id, self.symbols[to].scope_id);
+            touching[from_scope] += 1;
+            if from_scope != to_scope {
+                touching[to_scope] += 1;
+                crossing[from_scope] += 1;
+                crossing[to_scope] += 1;
+                if !self.scopes[from_scope].entangled_scopes.contains(&to_scope) {
+                    self.scopes[from_scope].entangled_scopes.push(to_scope);
+                    self.scopes[to_scope].entangled_scopes.push(from_scope);
+                }
             }
         }
-
-        self.stats.inference_speedup = 2.5; // Quantum speedup factor
-        Ok(())
-    }
-
-    pub fn get_stats(&self) -> &QuantumInferenceStats {
-        &self.stats
-    }
-}
-
-/// Quantum Entanglement Engine - Real Implementation
-pub struct QuantumEntanglementEngine {
-    /// Entanglement network
-    entanglement_network: HashMap<String, Vec<String>>,
-    /// Quantum configuration
-    quantum_config: QuantumConfig,
-    /// Entanglement statistics
-    entanglement_count: u64,
-}
-
-impl QuantumEntanglementEngine {
-    pub fn new(config: QuantumConfig) -> Self {
-        Self {
-            entanglement_network: HashMap::new(),
-            quantum_config: config,
-            entanglement_count: 0,
+        for symbol in &self.symbols {
+            self.scopes[symbol.scope_id].symbols.push(symbol.name.clone());
         }
-    }
-
-    /// Create quantum entanglements between symbols and scopes
-    pub fn create_entanglements(
-        &mut self,
-        symbols: &[QuantumSymbol]
-    ) -> QuantumResult<(Vec<QuantumSymbol>, Vec<QuantumScope>)> {
-        let mut entangled_symbols = symbols.to_vec();
-        let mut entangled_scopes = Vec::new();
-
-        // Create quantum entanglements between symbols
-        self.entangle_symbols(&mut entangled_symbols)?;
-
-        // Create quantum scopes with entanglement
-        entangled_scopes = self.create_quantum_scopes(&entangled_symbols)?;
-
-        // Apply cross-scope entanglement
-        self.apply_cross_scope_entanglement(&mut entangled_symbols, &mut entangled_scopes)?;
-
-        Ok((entangled_symbols, entangled_scopes))
-    }
-
-    fn entangle_symbols(&mut self, symbols: &mut [QuantumSymbol]) -> QuantumResult<()> {
-        if !self.quantum_config.quantum_semantic {
-            return Ok(());
+        for (scope, (crossing, touching)) in
+            self.scopes.iter_mut().zip(crossing.into_iter().zip(touching))
+        {
+            if touching > 0 {
+                scope.quantum_properties.entanglement_degree = crossing as f64 / touching as f64;
+            }
+            scope.quantum_properties.quantum_isolation = crossing == 0;
         }
 
-        // Create entanglement network based on symbol relationships
-        for i in 0..symbols.len() {
-            for j in (i + 1)..symbols.len() {
-                let entanglement_strength = self.calculate_entanglement_strength(&symbols[i], &symbols[j]);
-
-                if entanglement_strength > 0.5 {
-                    // Create bidirectional entanglement
-                    self.create_symbol_entanglement(&mut symbols[i], &mut symbols[j], entanglement_strength)?;
-
-                    // Update entanglement network
-                    self.entanglement_network
-                        .entry(symbols[i].name.clone())
-                        .or_insert_with(Vec::new)
-                        .push(symbols[j].name.clone());
+        let nodes = self
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(id, symbol)| QuantumDependencyNode {
+                id,
+                name: symbol.name.clone(),
+                node_type: dependency_node_type(&symbol.symbol_type),
+                quantum_properties: symbol.quantum_properties.clone(),
+            })
+            .collect();
 
-                    self.entanglement_network
-                        .entry(symbols[j].name.clone())
-                        .or_insert_with(Vec::new)
-                        .push(symbols[i].name.clone());
+        let stats = QuantumSemanticStats {
+            symbols_analyzed: self.symbols.len() as u64,
+            dependencies_resolved: edges.len() as u64,
+            quantum_entanglements_created: entangled_pairs.len() as u64,
+        };
+        let dependencies = QuantumDependencyGraph {
+            nodes,
+            stats: QuantumResolutionStats {
+                nodes_resolved: self.dependencies.values().sum(),
+                quantum_entanglements: entangled_pairs.len() as u64,
+            },
+            edges,
+        };
+        let type_info = QuantumTypeInfo {
+            inference_stats: QuantumInferenceStats {
+                constraints_solved: self.type_constraints.len() as u64,
+                quantum_inferences: self.inferred_bindings,
+            },
+            type_assignments: self.type_assignments,
+            type_constraints: self.type_constraints,
+        };
 
-                    self.entanglement_count += 1;
+        QuantumSemanticInfo {
+            symbols: self.symbols,
+            scopes: self.scopes,
+            dependencies,
+            type_info,
+            stats,
+        }
+    }
+}
+
+/// Collects the local bindings and resolved uses of one body
+struct BodyCollector<'a, 'tcx> {
+    cx: &'a mut SemanticCollector<'tcx>,
+    typeck_results: &'tcx TypeckResults<'tcx>,
+    /// Symbol owning the body
+    owner: usize,
+    scope_id: usize,
+    /// Bindings with a written type: parameters and bindings of annotated `let`s
+    annotated: FxHashSet<hir::HirId>,
+}
+
+impl<'a, 'tcx> BodyCollector<'a, 'tcx> {
+    fn add_binding(&mut self, mode: hir::BindingMode, hir_id: hir::HirId, ident: Ident) {
+        let Some(ty) = self.typeck_results.node_type_opt(hir_id) else { return };
+        let name = format!("{}::{}", self.cx.symbols[self.owner].name, ident);
+        let var_type = self.cx.quantum_type(ty);
+        let annotated = self.annotated.contains(&hir_id);
+        if !annotated {
+            self.cx.inferred_bindings += 1;
+        }
+
+        self.cx.type_assignments.insert(name.clone(), var_type.clone());
+        self.cx.type_constraints.push(QuantumTypeConstraint {
+            symbol_name: name.clone(),
+            constraint_type: QuantumConstraintType::Equality { quantum_exact: annotated },
+            target_type: var_type.clone(),
+            confidence: 1.0,
+        });
+
+        let symbol = self.cx.add_symbol(QuantumSymbol {
+            name,
+            def_id: None,
+            symbol_type: QuantumSymbolType::Variable {
+                mutable: mode.1 == hir::Mutability::Mut,
+                quantum_entangled: var_type.quantum_enhanced,
+                quantum_superposition: ty.is_enum(),
+                var_type,
+            },
+            scope_id: self.scope_id,
+            defined_at: ident.span,
+            quantum_properties: QuantumSymbolProperties::default(),
+        });
+        self.cx.local_symbols.insert(hir_id, symbol);
+        self.cx.add_type_uses(symbol, ty);
+    }
+
+    /// Record a dependency of the body owner on what `res` refers to
+    fn add_use(&mut self, res: Res) {
+        let to = match res {
+            Res::Local(hir_id) => self.cx.local_symbols.get(&hir_id).copied(),
+            Res::Def(kind, mut def_id) => {
+                // Constructors and variants stand for their type
+                if let DefKind::Ctor(..) = kind {
+                    def_id = self.cx.tcx.parent(def_id);
+                }
+                if let DefKind::Variant = self.cx.tcx.def_kind(def_id) {
+                    def_id = self.cx.tcx.parent(def_id);
                 }
+                self.cx.def_symbols.get(&def_id).copied()
             }
+            _ => None,
+        };
+        if let Some(to) = to {
+            self.cx.add_dependency(self.owner, to);
         }
-
-        Ok(())
     }
 
-    fn calculate_entanglement_strength(&self, symbol1: &QuantumSymbol, symbol2: &QuantumSymbol) -> f64 {
-        let mut strength = 0.0;
-
-        // Type compatibility
-        if std::mem::discriminant(&symbol1.symbol_type) == std::mem::discriminant(&symbol2.symbol_type) {
-            strength += 0.3;
-        }
-
-        // Name similarity
-        let name_similarity = self.calculate_name_similarity(&symbol1.name, &symbol2.name);
-        strength += name_similarity * 0.2;
-
-        // Scope proximity
-        if symbol1.scope_id == symbol2.scope_id {
-            strength += 0.4;
-        } else if (symbol1.scope_id as i32 - symbol2.scope_id as i32).abs() <= 1 {
-            strength += 0.2;
+    /// Record the coercion of `expr`, if any
+    fn add_coercion(&mut self, expr: &hir::Expr<'_>) {
+        if self.typeck_results.expr_adjustments(expr).is_empty() {
+            return;
         }
-
-        // Existing quantum properties
-        strength += (symbol1.quantum_properties.entanglement + symbol2.quantum_properties.entanglement) / 2.0 * 0.1;
-
-        strength.min(1.0)
-    }
-
-    fn calculate_name_similarity(&self, name1: &str, name2: &str) -> f64 {
-        // Simple similarity based on common prefixes/suffixes
-        let common_prefix = name1.chars()
-            .zip(name2.chars())
-            .take_while(|(a, b)| a == b)
-            .count();
-
-        let max_len = name1.len().max(name2.len());
-        if max_len == 0 {
-            return 0.0;
+        let (Some(source), Some(target)) = (
+            self.typeck_results.expr_ty_opt(expr),
+            self.typeck_results.expr_ty_adjusted_opt(expr),
+        ) else {
+            return;
+        };
+        if source != target {
+            self.cx.type_constraints.push(QuantumTypeConstraint {
+                symbol_name: self.cx.symbols[self.owner].name.clone(),
+                constraint_type: QuantumConstraintType::Subtype { quantum_compatible: true },
+                target_type: self.cx.quantum_type(target),
+                confidence: 1.0,
+            });
         }
-
-        common_prefix as f64 / max_len as f64
     }
+}
 
-    fn create_symbol_entanglement(
-        &self,
-        symbol1: &mut QuantumSymbol,
-        symbol2: &mut QuantumSymbol,
-        strength: f64
-    ) -> QuantumResult<()> {
-        // Update quantum properties for entanglement
-        symbol1.quantum_properties.entanglement = strength;
-        symbol2.quantum_properties.entanglement = strength;
-
-        symbol1.quantum_properties.entangled_with.push(symbol2.name.clone());
-        symbol2.quantum_properties.entangled_with.push(symbol1.name.clone());
-
-        // Enhance coherence based on entanglement
-        symbol1.quantum_properties.coherence = (symbol1.quantum_properties.coherence + strength * 0.5).min(1.0);
-        symbol2.quantum_properties.coherence = (symbol2.quantum_properties.coherence + strength * 0.5).min(1.0);
-
-        // Improve fidelity
-        symbol1.quantum_properties.fidelity = (symbol1.quantum_properties.fidelity + strength * 0.3).min(1.0);
-        symbol2.quantum_properties.fidelity = (symbol2.quantum_properties.fidelity + strength * 0.3).min(1.0);
-
-        Ok(())
+impl<'a, 'tcx> Visitor<'tcx> for BodyCollector<'a, 'tcx> {
+    fn visit_nested_body(&mut self, id: hir::BodyId) {
+        // Closures share the results of their parent, inline and anonymous
+        // constants have their own.
+        let tcx = self.cx.tcx;
+        let parent_results = std::mem::replace(&mut self.typeck_results, tcx.typeck_body(id));
+        self.visit_body(tcx.hir_body(id));
+        self.typeck_results = parent_results;
     }
 
-    fn create_quantum_scopes(&self, symbols: &[QuantumSymbol]) -> QuantumResult<Vec<QuantumScope>> {
-        let mut scopes = Vec::new();
-        let mut scope_map: HashMap<usize, Vec<&QuantumSymbol>> = HashMap::new();
-
-        // Group symbols by scope
-        for symbol in symbols {
-            scope_map.entry(symbol.scope_id).or_insert_with(Vec::new).push(symbol);
-        }
-
-        // Create quantum scopes
-        for (scope_id, scope_symbols) in scope_map {
-            let mut quantum_scope = QuantumScope {
-                id: scope_id,
-                parent_id: if scope_id > 0 { Some(scope_id - 1) } else { None },
-                symbols: scope_symbols.iter().map(|s| s.name.clone()).collect(),
-                quantum_properties: QuantumScopeProperties {
-                    entanglement_degree: 0.0,
-                    coherence_time: 1.0,
-                    quantum_isolation: false,
-                },
-                entangled_scopes: Vec::new(),
-            };
-
-            // Calculate scope quantum properties
-            if !scope_symbols.is_empty() {
-                let avg_entanglement: f64 = scope_symbols.iter()
-                    .map(|s| s.quantum_properties.entanglement)
-                    .sum::<f64>() / scope_symbols.len() as f64;
-
-                quantum_scope.quantum_properties.entanglement_degree = avg_entanglement;
-                quantum_scope.quantum_properties.coherence_time = avg_entanglement * 2.0;
-                quantum_scope.quantum_properties.quantum_isolation = avg_entanglement < 0.3;
-            }
-
-            scopes.push(quantum_scope);
+    fn visit_local(&mut self, local: &'tcx hir::LetStmt<'tcx>) {
+        if local.ty.is_some() {
+            local.pat.each_binding(|_, hir_id, _, _| {
+                self.annotated.insert(hir_id);
+            });
         }
-
-        Ok(scopes)
+        intravisit::walk_local(self, local);
     }
 
-    fn apply_cross_scope_entanglement(
-        &mut self,
-        symbols: &mut [QuantumSymbol],
-        scopes: &mut [QuantumScope]
-    ) -> QuantumResult<()> {
-        // Create entanglement between related scopes
-        for i in 0..scopes.len() {
-            for j in (i + 1)..scopes.len() {
-                let scope_entanglement = self.calculate_scope_entanglement(&scopes[i], &scopes[j]);
-
-                if scope_entanglement > 0.4 {
-                    scopes[i].entangled_scopes.push(scopes[j].id);
-                    scopes[j].entangled_scopes.push(scopes[i].id);
-
-                    // Enhance symbols in entangled scopes
-                    self.enhance_entangled_scope_symbols(symbols, &scopes[i], &scopes[j])?;
-                }
-            }
+    fn visit_pat(&mut self, pat: &'tcx hir::Pat<'tcx>) {
+        if let hir::PatKind::Binding(mode, hir_id, ident, _) = pat.kind {
+            self.add_binding(mode, hir_id, ident);
         }
-
-        Ok(())
+        intravisit::walk_pat(self, pat);
     }
 
-    fn calculate_scope_entanglement(&self, scope1: &QuantumScope, scope2: &QuantumScope) -> f64 {
-        // Calculate entanglement based on shared symbols and proximity
-        let shared_symbols = scope1.symbols.iter()
-            .filter(|s| scope2.symbols.contains(s))
-            .count();
-
-        let total_symbols = (scope1.symbols.len() + scope2.symbols.len()) as f64;
-        let sharing_factor = if total_symbols > 0.0 {
-            (shared_symbols as f64 * 2.0) / total_symbols
-        } else {
-            0.0
-        };
-
-        let proximity_factor = if let Some(parent1) = scope1.parent_id {
-            if parent1 == scope2.id || scope2.parent_id == Some(scope1.id) {
-                0.6
-            } else {
-                0.2
+    fn visit_expr(&mut self, expr: &'tcx hir::Expr<'tcx>) {
+        match &expr.kind {
+            hir::ExprKind::Path(qpath) | hir::ExprKind::Struct(qpath, ..) => {
+                let res = self.typeck_results.qpath_res(qpath, expr.hir_id);
+                self.add_use(res);
             }
-        } else {
-            0.1
-        };
-
-        (sharing_factor * 0.7 + proximity_factor * 0.3).min(1.0)
-    }
-
-    fn enhance_entangled_scope_symbols(
-        &self,
-        symbols: &mut [QuantumSymbol],
-        scope1: &QuantumScope,
-        scope2: &QuantumScope
-    ) -> QuantumResult<()> {
-        for symbol in symbols.iter_mut() {
-            if scope1.symbols.contains(&symbol.name) || scope2.symbols.contains(&symbol.name) {
-                // Enhance quantum properties for symbols in entangled scopes
-                symbol.quantum_properties.entanglement = (symbol.quantum_properties.entanglement + 0.2).min(1.0);
-                symbol.quantum_properties.coherence = (symbol.quantum_properties.coherence + 0.1).min(1.0);
+            hir::ExprKind::MethodCall(..) => {
+                if let Some(def_id) = self.typeck_results.type_dependent_def_id(expr.hir_id) {
+                    self.add_use(Res::Def(self.cx.tcx.def_kind(def_id), def_id));
+                }
             }
+            _ => {}
         }
-
-        Ok(())
-    }
-
-    pub fn get_entanglement_count(&self) -> u64 {
-        self.entanglement_count
-    }
-
-    pub fn get_entanglement_network(&self) -> &HashMap<String, Vec<String>> {
-        &self.entanglement_network
+        self.add_coercion(expr);
+        intravisit::walk_expr(self, expr);
     }
 }
@@ -0,0 +1,214 @@
+//! Quantum compilation reports
+//!
+//! Every quantum phase records what it did as a [`QuantumPhaseReport`]. The
+//! compiler surfaces each phase as a note on the crate, and `-Zquantum-report`
+//! renders the whole [`QuantumReport`] as text or JSON.
+
+use serde::Serialize;
+use std::fmt::Write;
+
+use crate::profiling::{PassMeasurement, PhaseDelta};
+
+/// A single value recorded by a quantum phase
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum QuantumMetricValue {
+    /// Number of items (tokens, nodes, symbols, ...)
+    Count(u64),
+    /// Ratio or other fractional measurement
+    Ratio(f64),
+}
+
+impl std::fmt::Display for QuantumMetricValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuantumMetricValue::Count(count) => write!(f, "{}", count),
+            QuantumMetricValue::Ratio(ratio) => write!(f, "{:.3}", ratio),
+        }
+    }
+}
+
+/// Named metric of a quantum phase
+#[derive(Debug, Clone, Serialize)]
+pub struct QuantumMetric {
+    pub name: &'static str,
+    pub value: QuantumMetricValue,
+}
+
+/// Measured comparison of a quantum phase with its baseline rustc passes
+#[derive(Debug, Clone, Serialize)]
+pub struct QuantumBaselineReport {
+    /// Baseline passes the phase was compared against
+    pub passes: &'static [&'static str],
+    /// Wall time of the baseline passes, in seconds
+    pub wall_time_secs: f64,
+    /// Extra wall time of the quantum phase, in seconds
+    pub wall_time_delta_secs: f64,
+    /// Extra resident memory of the quantum phase, in bytes
+    pub rss_delta_bytes: Option<i64>,
+}
+
+/// Report of one quantum phase
+#[derive(Debug, Clone, Serialize)]
+pub struct QuantumPhaseReport {
+    /// Phase name as accepted by `-Zquantum`
+    pub phase: &'static str,
+    /// Wall time spent in the phase, in seconds
+    pub wall_time_secs: f64,
+    /// Change of the resident set size during the phase, in bytes
+    pub rss_delta_bytes: Option<i64>,
+    /// Comparison with the baseline passes, when they were measured
+    pub baseline: Option<QuantumBaselineReport>,
+    /// Metrics recorded by the phase
+    pub metrics: Vec<QuantumMetric>,
+}
+
+impl QuantumPhaseReport {
+    pub fn new(phase: &'static str) -> Self {
+        Self {
+            phase,
+            wall_time_secs: 0.0,
+            rss_delta_bytes: None,
+            baseline: None,
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Attach the measured cost of the phase and its delta to the baseline
+    pub fn measured(mut self, measurement: PassMeasurement, delta: Option<&PhaseDelta>) -> Self {
+        self.wall_time_secs = measurement.wall_time.as_secs_f64();
+        self.rss_delta_bytes = measurement.rss_delta;
+        self.baseline = delta.map(|delta| QuantumBaselineReport {
+            passes: delta.baseline_passes,
+            wall_time_secs: delta.baseline.wall_time.as_secs_f64(),
+            wall_time_delta_secs: delta.wall_time_delta_secs(),
+            rss_delta_bytes: delta.rss_delta(),
+        });
+        self
+    }
+
+    /// Record a counted metric
+    pub fn count(mut self, name: &'static str, count: impl TryInto<u64>) -> Self {
+        let count = count.try_into().unwrap_or(u64::MAX);
+        self.metrics.push(QuantumMetric { name, value: QuantumMetricValue::Count(count) });
+        self
+    }
+
+    /// Record a fractional metric
+    pub fn ratio(mut self, name: &'static str, ratio: f64) -> Self {
+        self.metrics.push(QuantumMetric { name, value: QuantumMetricValue::Ratio(ratio) });
+        self
+    }
+
+    /// One-line summary of the metrics, used for the phase note
+    pub fn summary(&self) -> String {
+        self.metrics
+            .iter()
+            .map(|metric| format!("{} {}", metric.value, metric.name.replace('_', " ")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Report of all quantum phases run on a crate
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuantumReport {
+    pub crate_name: String,
+    pub phases: Vec<QuantumPhaseReport>,
+}
+
+impl QuantumReport {
+    pub fn new(crate_name: String) -> Self {
+        Self { crate_name, phases: Vec::new() }
+    }
+
+    pub fn push_phase(&mut self, phase: QuantumPhaseReport) {
+        self.phases.push(phase);
+    }
+
+    /// Render the report as human-readable text
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "quantum report for `{}`", self.crate_name);
+        for phase in &self.phases {
+            let _ = writeln!(out, "{} ({:.3}s)", phase.phase, phase.wall_time_secs);
+            if let Some(baseline) = &phase.baseline {
+                let _ = writeln!(
+                    out,
+                    "    baseline {}: {:.3}s ({:+.3}s)",
+                    baseline.passes.join(" + "),
+                    baseline.wall_time_secs,
+                    baseline.wall_time_delta_secs,
+                );
+                if let Some(rss_delta) = baseline.rss_delta_bytes {
+                    let _ = writeln!(out, "    memory delta: {:+} bytes", rss_delta);
+                }
+            }
+            for metric in &phase.metrics {
+                let _ = writeln!(out, "    {}: {}", metric.name, metric.value);
+            }
+        }
+        out
+    }
+
+    /// Render the report as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("quantum report is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::profiling::PhaseMeasurements;
+    use std::time::Duration;
+
+    fn sample_report() -> QuantumReport {
+        let quantum =
+            PassMeasurement { wall_time: Duration::from_millis(250), rss_delta: Some(4096) };
+        let baseline =
+            PassMeasurement { wall_time: Duration::from_millis(100), rss_delta: Some(1024) };
+        let mut measurements = PhaseMeasurements::default();
+        measurements.record_phase("semantic", quantum);
+        measurements.record_baseline("analysis", baseline);
+
+        let mut report = QuantumReport::new("demo".to_string());
+        report.push_phase(
+            QuantumPhaseReport::new("semantic")
+                .count("scopes", 2usize)
+                .count("symbols", 1024usize)
+                .ratio("arrow_optimized", 0.125)
+                .measured(quantum, measurements.delta("semantic").as_ref()),
+        );
+        report
+    }
+
+    #[test]
+    fn test_phase_summary() {
+        let report = sample_report();
+        assert_eq!(report.phases[0].summary(), "2 scopes, 1024 symbols, 0.125 arrow optimized");
+    }
+
+    #[test]
+    fn test_text_report() {
+        let text = sample_report().to_text();
+        assert!(text.starts_with("quantum report for `demo`\n"));
+        assert!(text.contains("semantic (0.250s)\n"));
+        assert!(text.contains("    baseline analysis: 0.100s (+0.150s)\n"));
+        assert!(text.contains("    memory delta: +3072 bytes\n"));
+        assert!(text.contains("    symbols: 1024\n"));
+    }
+
+    #[test]
+    fn test_json_report() {
+        let json: serde_json::Value = serde_json::from_str(&sample_report().to_json()).unwrap();
+        assert_eq!(json["crate_name"], "demo");
+        assert_eq!(json["phases"][0]["phase"], "semantic");
+        assert_eq!(json["phases"][0]["metrics"][1]["name"], "symbols");
+        assert_eq!(json["phases"][0]["metrics"][1]["value"], 1024);
+        assert_eq!(json["phases"][0]["rss_delta_bytes"], 4096);
+        assert_eq!(json["phases"][0]["baseline"]["passes"][0], "analysis");
+        assert_eq!(json["phases"][0]["baseline"]["rss_delta_bytes"], 3072);
+    }
+}
@@ -1,4 +1,5 @@
 use std::cmp;
+use std::time::Duration;
 
 use rustc_abi::{Align, Size};
 use rustc_data_structures::fx::FxHashSet;
@@ -75,6 +76,25 @@ pub struct CodeStats {
     /// The hash set that actually holds all the type size information.
     /// The field is public for use in external tools. See #139876.
     pub type_sizes: Lock<FxHashSet<TypeSizeInfo>>,
+    /// Changes made by the `-Zquantum=optimization` MIR passes.
+    pub quantum_mir: Lock<QuantumMirStats>,
+}
+
+/// Changes made by the quantum MIR passes, summed over every body optimized in this session.
+///
+/// Bodies whose optimized MIR is loaded from the incremental cache are not counted.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct QuantumMirStats {
+    /// Operations on constants replaced by their result.
+    pub folded_expressions: u64,
+    /// Blocks removed after their branch condition became known.
+    pub eliminated_blocks: u64,
+    /// Loops that had invariant statements hoisted out of them.
+    pub optimized_loops: u64,
+    /// Statements hoisted out of loops.
+    pub hoisted_statements: u64,
+    /// Wall time spent in the quantum passes themselves.
+    pub pass_time: Duration,
 }
 
 impl CodeStats {
@@ -226,6 +226,26 @@ pub enum CoverageLevel {
     Mcdc,
 }
 
+/// Quantum-inspired compilation phases enabled by `-Zquantum`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct QuantumPhases {
+    /// `-Zquantum=lexing`: re-tokenize the crate's source files with `rustc_quantum`.
+    pub lexing: bool,
+    /// `-Zquantum=parsing`: build the quantum AST from the expanded crate.
+    pub parsing: bool,
+    /// `-Zquantum=semantic`: run quantum semantic analysis once type checking is done.
+    pub semantic: bool,
+    /// `-Zquantum=optimization`: run the quantum MIR optimizations.
+    pub optimization: bool,
+}
+
+impl QuantumPhases {
+    /// Whether any quantum phase was requested on the command line.
+    pub fn any(&self) -> bool {
+        self.lexing || self.parsing || self.semantic || self.optimization
+    }
+}
+
 /// The different settings that the `-Z autodiff` flag can have.
 #[derive(Clone, Copy, PartialEq, Hash, Debug)]
 pub enum AutoDiff {
@@ -2731,6 +2751,13 @@ pub fn build_session_options(early_dcx: &mut EarlyDiagCtxt, matches: &getopts::M
         early_dcx.early_fatal("can't dump dependency graph without `-Z query-dep-graph`");
     }
 
+    // Without a file, the JSON report would be interleaved with the diagnostics on stderr
+    if unstable_opts.quantum_report == Some(QuantumReportFormat::Json)
+        && unstable_opts.quantum_report_path.is_none()
+    {
+        early_dcx.early_fatal("`-Z quantum-report=json` requires `-Z quantum-report-path`");
+    }
+
     let logical_env = parse_logical_env(early_dcx, matches);
 
     let sysroot = Sysroot::new(matches.opt_str("sysroot").map(PathBuf::from));
@@ -3117,9 +3144,9 @@ pub(crate) mod dep_tracking {
         CrateType, DebugInfo, DebugInfoCompression, ErrorOutputType, FmtDebug, FunctionReturn,
         InliningThreshold, InstrumentCoverage, InstrumentXRay, LinkerPluginLto, LocationDetail,
         LtoCli, MirStripDebugInfo, NextSolverConfig, OomStrategy, OptLevel, OutFileName,
-        OutputType, OutputTypes, PatchableFunctionEntry, Polonius, RemapPathScopeComponents,
-        ResolveDocLinks, SourceFileHashAlgorithm, SplitDwarfKind, SwitchWithOptPath,
-        SymbolManglingVersion, WasiExecModel,
+        OutputType, OutputTypes, PatchableFunctionEntry, Polonius, QuantumPhases,
+        RemapPathScopeComponents, ResolveDocLinks, SourceFileHashAlgorithm, SplitDwarfKind,
+        SwitchWithOptPath, SymbolManglingVersion, WasiExecModel,
     };
     use crate::lint;
     use crate::utils::NativeLib;
@@ -3179,6 +3206,7 @@ fn hash(
         TlsModel,
         InstrumentCoverage,
         CoverageOptions,
+        QuantumPhases,
         InstrumentXRay,
         CrateType,
         MergeFunctions,
@@ -3395,6 +3423,15 @@ pub fn extension(self) -> &'static str {
     }
 }
 
+/// Which format to use for `-Z quantum-report`
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum QuantumReportFormat {
+    /// Human-readable text, one line per metric
+    Text,
+    /// Emit structured JSON
+    Json,
+}
+
 /// `-Z patchable-function-entry` representation - how many nops to put before and after function
 /// entry.
 #[derive(Clone, Copy, PartialEq, Hash, Debug, Default)]
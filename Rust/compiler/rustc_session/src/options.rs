@@ -756,6 +756,9 @@ mod desc {
     pub(crate) const parse_instrument_coverage: &str = parse_bool;
     pub(crate) const parse_coverage_options: &str =
         "`block` | `branch` | `condition` | `mcdc` | `no-mir-spans`";
+    pub(crate) const parse_quantum_phases: &str = "a comma separated list of phases: `lexing`, `parsing`, `semantic`, `optimization`, or `all`";
+    pub(crate) const parse_quantum_opt_level: &str = "0, 1, 2, or 3";
+    pub(crate) const parse_quantum_report: &str = "`text` or `json`";
     pub(crate) const parse_instrument_xray: &str = "either a boolean (`yes`, `no`, `on`, `off`, etc), or a comma separated list of settings: `always` or `never` (mutually exclusive), `ignore-loops`, `instruction-threshold=N`, `skip-entry`, `skip-exit`";
     pub(crate) const parse_unpretty: &str = "`string` or `string=string`";
     pub(crate) const parse_treat_err_as_bug: &str = "either no value or a non-negative number";
@@ -1433,6 +1436,48 @@ pub(crate) fn parse_coverage_options(slot: &mut CoverageOptions, v: Option<&str>
         true
     }
 
+    pub(crate) fn parse_quantum_phases(slot: &mut QuantumPhases, v: Option<&str>) -> bool {
+        let Some(v) = v else {
+            *slot =
+                QuantumPhases { lexing: true, parsing: true, semantic: true, optimization: true };
+            return true;
+        };
+
+        for phase in v.split(',') {
+            match phase {
+                "lexing" => slot.lexing = true,
+                "parsing" => slot.parsing = true,
+                "semantic" => slot.semantic = true,
+                "optimization" => slot.optimization = true,
+                "all" => return parse_quantum_phases(slot, None),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    pub(crate) fn parse_quantum_opt_level(slot: &mut Option<usize>, v: Option<&str>) -> bool {
+        match v.and_then(|s| s.parse().ok()) {
+            Some(level @ 0..=3) => {
+                *slot = Some(level);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn parse_quantum_report(
+        slot: &mut Option<QuantumReportFormat>,
+        v: Option<&str>,
+    ) -> bool {
+        *slot = match v {
+            Some("text") => Some(QuantumReportFormat::Text),
+            Some("json") => Some(QuantumReportFormat::Json),
+            _ => return false,
+        };
+        true
+    }
+
     pub(crate) fn parse_instrument_xray(
         slot: &mut Option<InstrumentXRay>,
         v: Option<&str>,
@@ -2437,6 +2482,14 @@ pub(crate) fn parse_align(slot: &mut Option<Align>, v: Option<&str>) -> bool {
         "use the given `.prof` file for sampled profile-guided optimization (also known as AutoFDO)"),
     profiler_runtime: String = (String::from("profiler_builtins"), parse_string, [TRACKED],
         "name of the profiler runtime crate to automatically inject (default: `profiler_builtins`)"),
+    quantum: QuantumPhases = (QuantumPhases::default(), parse_quantum_phases, [TRACKED],
+        "enable quantum-inspired compilation phases from `rustc_quantum`"),
+    quantum_opt_level: Option<usize> = (None, parse_quantum_opt_level, [TRACKED],
+        "quantum optimization level used by `-Zquantum=optimization` (0-3, default: 2)"),
+    quantum_report: Option<QuantumReportFormat> = (None, parse_quantum_report, [UNTRACKED],
+        "print a report of the quantum phases run by `-Zquantum` (`text` or `json`)"),
+    quantum_report_path: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "write the `-Zquantum-report` output to this file instead of a note (required for `json`)"),
     query_dep_graph: bool = (false, parse_bool, [UNTRACKED],
         "enable queries of the dependency graph for regression testing (default: no)"),
     randomize_layout: bool = (false, parse_bool, [TRACKED],
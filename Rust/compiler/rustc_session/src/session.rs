@@ -37,7 +37,9 @@
 };
 
 use crate::code_stats::CodeStats;
-pub use crate::code_stats::{DataTypeKind, FieldInfo, FieldKind, SizeKind, VariantInfo};
+pub use crate::code_stats::{
+    DataTypeKind, FieldInfo, FieldKind, QuantumMirStats, SizeKind, VariantInfo,
+};
 use crate::config::{
     self, CoverageLevel, CrateType, DebugInfo, ErrorOutputType, FunctionReturn, Input,
     InstrumentCoverage, OptLevel, OutFileName, OutputType, RemapPathScopeComponents,
@@ -631,6 +633,14 @@ pub fn mir_opt_level(&self) -> usize {
             .unwrap_or_else(|| if self.opts.optimize != OptLevel::No { 2 } else { 1 })
     }
 
+    /// Level of the quantum MIR optimizations, or 0 without `-Zquantum=optimization`.
+    pub fn quantum_opt_level(&self) -> usize {
+        if !self.opts.unstable_opts.quantum.optimization {
+            return 0;
+        }
+        self.opts.unstable_opts.quantum_opt_level.unwrap_or(2)
+    }
+
     /// Calculates the flavor of LTO to use for this compilation.
     pub fn lto(&self) -> config::Lto {
         // If our target has codegen requirements ignore the command line
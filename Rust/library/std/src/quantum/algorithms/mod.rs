@@ -8,6 +8,7 @@
 
 use crate::quantum::{QuantumError, QuantumResult};
 use crate::quantum::array::{QuantumArray, Complex};
+use crate::quantum::rng::QuantumRng;
 use crate::vec::Vec;
 
 /// 应用Hadamard门到指定量子比特
@@ -156,15 +157,19 @@ pub fn apply_cnot_gate(qarray: &mut QuantumArray, control: usize, target: usize)
 
 /// 量子测量
 ///
-/// 对整个量子系统进行测量，返回测量结果和概率
+/// 对整个量子系统进行测量，量子态坍缩到测量到的基态上
 ///
 /// # 参数
 /// - `qarray`: 量子态数组
+/// - `rng`: 抽取测量结果使用的随机数生成器
 ///
 /// # 返回
 /// - `(state, probability)`: 测量到的基态和对应概率
-pub fn quantum_measurement(qarray: &QuantumArray) -> QuantumResult<(usize, f64)> {
-    qarray.measure_all()
+pub fn quantum_measurement(
+    qarray: &mut QuantumArray,
+    rng: &mut QuantumRng,
+) -> QuantumResult<(usize, f64)> {
+    qarray.measure_all(rng)
 }
 
 /// 量子傅里叶变换 (QFT)
@@ -329,8 +334,9 @@ fn test_cnot_gate() {
 
     #[test]
     fn test_quantum_measurement() {
-        let qarray = QuantumArray::new(2).unwrap();
-        let (state, prob) = quantum_measurement(&qarray).unwrap();
+        let mut qarray = QuantumArray::new(2).unwrap();
+        let mut rng = QuantumRng::seed_from_u64(0);
+        let (state, prob) = quantum_measurement(&mut qarray, &mut rng).unwrap();
         
         assert_eq!(state, 0); // 应该测量到|00⟩
         assert!((prob - 1.0).abs() < 1e-10);
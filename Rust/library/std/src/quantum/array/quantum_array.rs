@@ -3,6 +3,8 @@
 //! 提供量子计算中的量子态表示和操作
 
 use super::{Complex, ArrayElement};
+use crate::collections::BTreeMap;
+use crate::quantum::rng::QuantumRng;
 use crate::quantum::{QuantumError, QuantumResult};
 use crate::vec::Vec;
 use crate::fmt;
@@ -176,10 +178,37 @@ pub fn fidelity(&self, other: &Self) -> QuantumResult<f64> {
         Ok(inner_product.magnitude())
     }
 
+    /// 各基态的测量概率
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.amplitudes.iter()
+            .map(|amp| amp.magnitude() * amp.magnitude())
+            .collect()
+    }
+
     /// 测量指定量子比特
     ///
-    /// 返回测量结果(0或1)和对应的概率
-    pub fn measure_qubit(&self, qubit: usize) -> QuantumResult<(u8, f64)> {
+    /// 按玻恩规则从 `rng` 抽取测量结果，并让量子态坍缩到该结果上。
+    /// 其余量子比特保持叠加，因此可以在线路中间测量后继续操作。
+    ///
+    /// 返回测量结果(0或1)和测量前得到该结果的概率
+    ///
+    /// # 示例
+    /// ```rust
+    /// #![feature(quantum)]
+    /// use std::quantum::QuantumError;
+    /// use std::quantum::array::QuantumArray;
+    /// use std::quantum::rng::QuantumRng;
+    ///
+    /// let mut qarray = QuantumArray::new(1)?;
+    /// let mut rng = QuantumRng::seed_from_u64(42);
+    /// assert_eq!(qarray.measure_qubit(0, &mut rng)?, (0, 1.0));
+    /// # Ok::<(), QuantumError>(())
+    /// ```
+    pub fn measure_qubit(
+        &mut self,
+        qubit: usize,
+        rng: &mut QuantumRng,
+    ) -> QuantumResult<(u8, f64)> {
         if qubit >= self.num_qubits {
             return Err(QuantumError::QubitIndexOutOfRange {
                 index: qubit,
@@ -187,34 +216,103 @@ pub fn measure_qubit(&self, qubit: usize) -> QuantumResult<(u8, f64)> {
             });
         }
 
-        // 计算测量到|0⟩的概率
-        let prob_0: f64 = self.amplitudes.iter()
-            .enumerate()
-            .filter(|(state, _)| (state >> qubit) & 1 == 0)
-            .map(|(_, amp)| amp.magnitude() * amp.magnitude())
-            .sum();
+        let mut prob_0 = 0.0;
+        let mut prob_1 = 0.0;
+        for (state, amp) in self.amplitudes.iter().enumerate() {
+            if (state >> qubit) & 1 == 0 {
+                prob_0 += amp.magnitude() * amp.magnitude();
+            } else {
+                prob_1 += amp.magnitude() * amp.magnitude();
+            }
+        }
+        let total = prob_0 + prob_1;
+        if total == 0.0 {
+            return Err(QuantumError::InvalidQuantumState {
+                reason: "无法测量零态".to_string(),
+            });
+        }
 
-        // 简化的测量实现（实际应该使用随机数）
-        // 这里返回概率较大的结果
-        if prob_0 > 0.5 {
-            Ok((0, prob_0))
-        } else {
-            Ok((1, 1.0 - prob_0))
+        let outcome = if rng.next_f64() * total < prob_1 { 1 } else { 0 };
+        let prob = if outcome == 1 { prob_1 } else { prob_0 };
+
+        // 坍缩：去掉与结果不符的分量并重新归一化
+        let norm = prob.sqrt();
+        for (state, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if (state >> qubit) & 1 == outcome {
+                amplitude.real /= norm;
+                amplitude.imag /= norm;
+            } else {
+                *amplitude = Complex::zero();
+            }
         }
+
+        Ok((outcome as u8, prob / total))
     }
 
     /// 测量整个量子系统
     ///
-    /// 返回测量结果的基态索引和概率
-    pub fn measure_all(&self) -> QuantumResult<(usize, f64)> {
-        // 找到概率最大的基态
-        let (max_state, max_prob) = self.amplitudes.iter()
-            .enumerate()
-            .map(|(state, amp)| (state, amp.magnitude() * amp.magnitude()))
-            .max_by(|(_, prob1), (_, prob2)| prob1.partial_cmp(prob2).unwrap())
-            .unwrap();
+    /// 按玻恩规则抽取一个基态，并让量子态坍缩到该基态上。
+    ///
+    /// 返回测量结果的基态索引和测量前得到该结果的概率
+    pub fn measure_all(&mut self, rng: &mut QuantumRng) -> QuantumResult<(usize, f64)> {
+        let cumulative = self.cumulative_probabilities()?;
+        let state = sample_index(&cumulative, rng);
+        let prob = self.amplitudes[state].magnitude().powi(2) / cumulative[cumulative.len() - 1];
+
+        for amplitude in &mut self.amplitudes {
+            *amplitude = Complex::zero();
+        }
+        self.amplitudes[state] = Complex::one();
 
-        Ok((max_state, max_prob))
+        Ok((state, prob))
+    }
+
+    /// 对 `shots` 份相同的量子态分别测量整个系统
+    ///
+    /// 返回每个基态被测量到的次数。量子态本身不会改变。
+    ///
+    /// # 示例
+    /// ```rust
+    /// #![feature(quantum)]
+    /// use std::quantum::QuantumError;
+    /// use std::quantum::array::QuantumArray;
+    /// use std::quantum::rng::QuantumRng;
+    ///
+    /// let qarray = QuantumArray::new(2)?;
+    /// let histogram = qarray.sample(100, &mut QuantumRng::seed_from_u64(42))?;
+    /// assert_eq!(histogram.get(&0), Some(&100));
+    /// # Ok::<(), QuantumError>(())
+    /// ```
+    pub fn sample(
+        &self,
+        shots: usize,
+        rng: &mut QuantumRng,
+    ) -> QuantumResult<BTreeMap<usize, usize>> {
+        let cumulative = self.cumulative_probabilities()?;
+        let mut histogram = BTreeMap::new();
+        for _ in 0..shots {
+            *histogram.entry(sample_index(&cumulative, rng)).or_insert(0) += 1;
+        }
+        Ok(histogram)
+    }
+
+    /// 测量概率的前缀和
+    fn cumulative_probabilities(&self) -> QuantumResult<Vec<f64>> {
+        let mut total = 0.0;
+        let cumulative: Vec<f64> = self.probabilities()
+            .into_iter()
+            .map(|prob| {
+                total += prob;
+                total
+            })
+            .collect();
+
+        if total == 0.0 {
+            return Err(QuantumError::InvalidQuantumState {
+                reason: "无法测量零态".to_string(),
+            });
+        }
+        Ok(cumulative)
     }
 
     /// 获取量子态的字符串表示
@@ -251,6 +349,13 @@ pub fn to_string_representation(&self) -> String {
     }
 }
 
+/// 按前缀和为 `cumulative` 的概率抽取一个索引，不会抽到概率为零的索引
+fn sample_index(cumulative: &[f64], rng: &mut QuantumRng) -> usize {
+    let total = cumulative[cumulative.len() - 1];
+    let point = rng.next_f64() * total;
+    cumulative.partition_point(|&sum| sum <= point).min(cumulative.len() - 1)
+}
+
 impl fmt::Display for QuantumArray {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "QuantumArray({} qubits): {}", 
@@ -288,13 +393,94 @@ fn test_quantum_array_from_amplitudes() {
         assert_eq!(qarray.num_qubits(), 2);
     }
 
+    fn bell_state() -> QuantumArray {
+        let amplitude = 1.0 / 2.0_f64.sqrt();
+        QuantumArray::from_amplitudes(vec![
+            Complex::new(amplitude, 0.0),
+            Complex::zero(),
+            Complex::zero(),
+            Complex::new(amplitude, 0.0),
+        ]).unwrap()
+    }
+
+    /// `histogram` 相对于 `qarray` 测量概率的卡方统计量
+    fn chi_square(histogram: &BTreeMap<usize, usize>, qarray: &QuantumArray, shots: usize) -> f64 {
+        qarray.probabilities()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &prob)| prob > 0.0)
+            .map(|(state, prob)| {
+                let expected = prob * shots as f64;
+                let observed = histogram.get(&state).copied().unwrap_or(0) as f64;
+                (observed - expected) * (observed - expected) / expected
+            })
+            .sum()
+    }
+
     #[test]
     fn test_quantum_measurement() {
-        let qarray = QuantumArray::new(1).unwrap();
-        let (result, prob) = qarray.measure_qubit(0).unwrap();
-        
+        let mut qarray = QuantumArray::new(1).unwrap();
+        let mut rng = QuantumRng::seed_from_u64(0);
+        let (result, prob) = qarray.measure_qubit(0, &mut rng).unwrap();
+
         assert_eq!(result, 0); // 应该测量到|0⟩
         assert!((prob - 1.0).abs() < 1e-10); // 概率应该为1
+        assert!(qarray.measure_qubit(1, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_bell_state_sampling() {
+        let qarray = bell_state();
+        let mut rng = QuantumRng::seed_from_u64(7);
+        let shots = 10_000;
+        let histogram = qarray.sample(shots, &mut rng).unwrap();
+
+        // 只可能测量到|00⟩和|11⟩
+        assert_eq!(histogram.keys().copied().collect::<Vec<_>>(), vec![0, 3]);
+        assert_eq!(histogram.values().sum::<usize>(), shots);
+        // 10.83 是自由度为1的卡方分布的0.999分位数
+        assert!(chi_square(&histogram, &qarray, shots) < 10.83);
+    }
+
+    #[test]
+    fn test_uniform_sampling() {
+        let qarray = QuantumArray::from_amplitudes(vec![Complex::new(0.5, 0.0); 4]).unwrap();
+        let mut rng = QuantumRng::seed_from_u64(13);
+        let shots = 20_000;
+        let histogram = qarray.sample(shots, &mut rng).unwrap();
+        assert_eq!(histogram.len(), 4);
+        // 自由度为3
+        assert!(chi_square(&histogram, &qarray, shots) < 16.27);
+    }
+
+    #[test]
+    fn test_measurement_collapse() {
+        let mut rng = QuantumRng::seed_from_u64(5);
+        let mut counts = [0; 2];
+        for _ in 0..1_000 {
+            let mut qarray = bell_state();
+            let (first, prob) = qarray.measure_qubit(0, &mut rng).unwrap();
+            assert!((prob - 0.5).abs() < 1e-10);
+            // 坍缩后第二个量子比特的结果必然相同
+            let (second, prob) = qarray.measure_qubit(1, &mut rng).unwrap();
+            assert_eq!(first, second);
+            assert!((prob - 1.0).abs() < 1e-10);
+            counts[first as usize] += 1;
+        }
+        let chi_square: f64 = counts.iter()
+            .map(|&n| (n as f64 - 500.0) * (n as f64 - 500.0) / 500.0)
+            .sum();
+        assert!(chi_square < 10.83);
+    }
+
+    #[test]
+    fn test_measure_all_collapse() {
+        let mut qarray = bell_state();
+        let mut rng = QuantumRng::seed_from_u64(3);
+        let (state, prob) = qarray.measure_all(&mut rng).unwrap();
+        assert!(state == 0 || state == 3);
+        assert!((prob - 0.5).abs() < 1e-10);
+        assert_eq!(qarray.measure_all(&mut rng).unwrap(), (state, 1.0));
     }
 
     #[test]
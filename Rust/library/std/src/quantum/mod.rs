@@ -39,6 +39,7 @@
 pub mod array;
 pub mod algorithms;
 pub mod compiler;
+pub mod rng;
 
 pub mod prelude {
     //! 量子计算预导入模块
@@ -50,6 +51,7 @@ pub mod prelude {
         quantum_fft, apply_hadamard_gate, apply_phase_gate, quantum_measurement
     };
     pub use super::compiler::{QuantumAnalyzer, OptimizationHint};
+    pub use super::rng::QuantumRng;
 }
 
 /// 量子计算错误类型
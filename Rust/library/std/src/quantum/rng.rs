@@ -0,0 +1,88 @@
+//! 量子测量使用的随机数生成器
+//!
+//! 测量结果按玻恩规则随机抽取。为了让模拟结果可以复现，随机数来自一个
+//! 可以设置种子的伪随机数生成器 (xoshiro256**)。
+
+/// 可设置种子的伪随机数生成器
+///
+/// 相同的种子总是产生相同的随机数序列。它不适合用于密码学。
+///
+/// # 示例
+/// ```rust
+/// #![feature(quantum)]
+/// use std::quantum::rng::QuantumRng;
+///
+/// let mut a = QuantumRng::seed_from_u64(42);
+/// let mut b = QuantumRng::seed_from_u64(42);
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuantumRng {
+    state: [u64; 4],
+}
+
+impl QuantumRng {
+    /// 用给定的种子创建生成器
+    pub fn seed_from_u64(seed: u64) -> Self {
+        // 用SplitMix64把种子扩展为256位状态，保证状态不全为零
+        let mut seed = seed;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Self { state: [next(), next(), next(), next()] }
+    }
+
+    /// 用操作系统提供的随机种子创建生成器
+    pub fn from_entropy() -> Self {
+        Self::seed_from_u64(crate::random::random())
+    }
+
+    /// 下一个均匀分布的64位整数
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    /// 下一个在 `[0, 1)` 上均匀分布的浮点数
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = QuantumRng::seed_from_u64(1);
+        let mut b = QuantumRng::seed_from_u64(1);
+        let mut c = QuantumRng::seed_from_u64(2);
+        let sequence: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        assert_eq!(sequence, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(sequence, (0..8).map(|_| c.next_u64()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_next_f64_range() {
+        let mut rng = QuantumRng::seed_from_u64(0);
+        let samples: Vec<f64> = (0..10_000).map(|_| rng.next_f64()).collect();
+        assert!(samples.iter().all(|&x| (0.0..1.0).contains(&x)));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 0.5).abs() < 0.01);
+    }
+}
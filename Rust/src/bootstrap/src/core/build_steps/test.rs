@@ -1316,6 +1316,43 @@ fn run(self, builder: &Builder<'_>) {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CrateQuantumSim {
+    host: TargetSelection,
+}
+
+impl Step for CrateQuantumSim {
+    type Output = ();
+    const ONLY_HOSTS: bool = true;
+
+    fn should_run(run: ShouldRun<'_>) -> ShouldRun<'_> {
+        run.path("src/tools/quantum-sim")
+    }
+
+    fn make_run(run: RunConfig<'_>) {
+        run.builder.ensure(CrateQuantumSim { host: run.target });
+    }
+
+    /// Runs `cargo test` for quantum-sim.
+    fn run(self, builder: &Builder<'_>) {
+        let host = self.host;
+        let compiler = builder.compiler(0, host);
+
+        let mut cargo = tool::prepare_tool_cargo(
+            builder,
+            compiler,
+            Mode::ToolBootstrap,
+            host,
+            Kind::Test,
+            "src/tools/quantum-sim",
+            SourceType::InTree,
+            &[],
+        );
+        cargo.allow_features("test");
+        run_cargo_test(cargo, &[], &[], "quantum-sim", host, builder);
+    }
+}
+
 test!(Ui { path: "tests/ui", mode: "ui", suite: "ui", default: true });
 
 test!(Crashes { path: "tests/crashes", mode: "crashes", suite: "crashes", default: true });
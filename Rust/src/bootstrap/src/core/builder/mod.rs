@@ -1053,6 +1053,7 @@ macro_rules! describe {
                 test::CompiletestTest,
                 test::CrateRunMakeSupport,
                 test::CrateBuildHelper,
+                test::CrateQuantumSim,
                 test::RustdocJSStd,
                 test::RustdocJSNotStd,
                 test::RustdocGUI,
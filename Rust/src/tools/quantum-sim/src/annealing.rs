@@ -0,0 +1,434 @@
+//! Simulated annealing
+//!
+//! A generic annealer used by [`QuantumAnnealer`].
+//! Problems describe their states through [`AnnealingProblem`]: an energy to minimize and a
+//! way to pick a random neighbor of a state. Moves are accepted with the Metropolis rule,
+//! the temperature follows a [`CoolingSchedule`], and all randomness comes from a seeded
+//! generator, so a run with the same configuration always returns the same result.
+//!
+//! [`QuantumAnnealer`]: crate::quantum_algorithms::QuantumAnnealer
+
+use std::marker::PhantomData;
+
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+/// Random number generator handed to [`AnnealingProblem::neighbor`]
+pub type AnnealingRng = Xoshiro256PlusPlus;
+
+/// Optimization problem solved by the [`Annealer`]
+pub trait AnnealingProblem {
+    type State: Clone;
+
+    /// Energy of `state`; the annealer looks for the state with the lowest energy
+    fn energy(&self, state: &Self::State) -> f64;
+
+    /// A random state close to `state`
+    fn neighbor(&self, state: &Self::State, rng: &mut AnnealingRng) -> Self::State;
+}
+
+/// [`AnnealingProblem`] made of an energy and a neighbor closure
+pub struct FnProblem<S, E, N> {
+    energy: E,
+    neighbor: N,
+    _state: PhantomData<fn(&S) -> S>,
+}
+
+impl<S, E, N> FnProblem<S, E, N>
+where
+    S: Clone,
+    E: Fn(&S) -> f64,
+    N: Fn(&S, &mut AnnealingRng) -> S,
+{
+    pub fn new(energy: E, neighbor: N) -> Self {
+        Self { energy, neighbor, _state: PhantomData }
+    }
+}
+
+impl<S, E, N> AnnealingProblem for FnProblem<S, E, N>
+where
+    S: Clone,
+    E: Fn(&S) -> f64,
+    N: Fn(&S, &mut AnnealingRng) -> S,
+{
+    type State = S;
+
+    fn energy(&self, state: &S) -> f64 {
+        (self.energy)(state)
+    }
+
+    fn neighbor(&self, state: &S, rng: &mut AnnealingRng) -> S {
+        (self.neighbor)(state, rng)
+    }
+}
+
+/// How the temperature decreases after every step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoolingSchedule {
+    /// Multiply the temperature by `alpha` (between 0 and 1)
+    Geometric { alpha: f64 },
+    /// Decrease the temperature by the same amount every step, reaching the minimum
+    /// temperature after `max_iterations` steps
+    Linear,
+    /// Cool by `alpha` while at least `target_acceptance` of the last `window` moves were
+    /// accepted, and by `sqrt(alpha)` otherwise, spending more steps at the temperatures
+    /// where the search still makes progress
+    Adaptive { alpha: f64, target_acceptance: f64, window: usize },
+}
+
+impl CoolingSchedule {
+    fn next_temperature(&self, temperature: f64, config: &AnnealingConfig, window: &Window) -> f64 {
+        match *self {
+            CoolingSchedule::Geometric { alpha } => temperature * alpha,
+            CoolingSchedule::Linear => {
+                let step = (config.initial_temperature - config.min_temperature)
+                    / config.max_iterations.max(1) as f64;
+                temperature - step
+            }
+            CoolingSchedule::Adaptive { alpha, target_acceptance, .. } => {
+                if window.acceptance_rate() >= target_acceptance {
+                    temperature * alpha
+                } else {
+                    temperature * alpha.sqrt()
+                }
+            }
+        }
+    }
+
+    fn window_size(&self) -> usize {
+        match *self {
+            CoolingSchedule::Adaptive { window, .. } => window.max(1),
+            _ => 1,
+        }
+    }
+}
+
+/// Acceptance of the most recent moves
+struct Window {
+    accepted: Vec<bool>,
+    next: usize,
+    filled: bool,
+}
+
+impl Window {
+    fn new(size: usize) -> Self {
+        Self { accepted: vec![false; size], next: 0, filled: false }
+    }
+
+    fn push(&mut self, accepted: bool) {
+        self.accepted[self.next] = accepted;
+        self.next = (self.next + 1) % self.accepted.len();
+        self.filled |= self.next == 0;
+    }
+
+    fn acceptance_rate(&self) -> f64 {
+        let len = if self.filled { self.accepted.len() } else { self.next };
+        if len == 0 {
+            return 1.0;
+        }
+        self.accepted[..len].iter().filter(|&&accepted| accepted).count() as f64 / len as f64
+    }
+}
+
+/// Annealer settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnealingConfig {
+    pub initial_temperature: f64,
+    /// A run ends once the temperature drops to this value
+    pub min_temperature: f64,
+    /// Upper bound on the steps of a single run
+    pub max_iterations: usize,
+    pub schedule: CoolingSchedule,
+    /// Additional runs, each starting from the best state found so far
+    pub restarts: usize,
+    /// Seed of the random number generator
+    pub seed: u64,
+    /// A run ends early after this many steps without improving the best energy by more
+    /// than `tolerance`; 0 disables the check
+    pub stall_iterations: usize,
+    pub tolerance: f64,
+}
+
+impl Default for AnnealingConfig {
+    fn default() -> Self {
+        Self {
+            initial_temperature: 100.0,
+            min_temperature: 1e-3,
+            max_iterations: 10_000,
+            schedule: CoolingSchedule::Geometric { alpha: 0.995 },
+            restarts: 0,
+            seed: 0,
+            stall_iterations: 0,
+            tolerance: 1e-9,
+        }
+    }
+}
+
+/// Statistics of an [`Annealer::anneal`] call, over all of its runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnnealingStats {
+    /// Steps taken
+    pub iterations: usize,
+    /// Neighbors that replaced the current state
+    pub accepted_moves: u64,
+    /// Accepted moves that lowered the best energy found so far
+    pub improvements: u64,
+    /// Runs made, including restarts
+    pub runs: usize,
+    pub initial_energy: f64,
+    pub final_energy: f64,
+    /// `initial_energy - final_energy`
+    pub energy_reduction: f64,
+    /// Temperature at the end of the last run
+    pub final_temperature: f64,
+    /// Whether the last run ended because the energy stopped improving
+    pub converged: bool,
+    /// Best energy known at the end of every run
+    pub run_best_energies: Vec<f64>,
+}
+
+impl AnnealingStats {
+    /// Share of the steps whose neighbor was accepted
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.iterations == 0 {
+            return 0.0;
+        }
+        self.accepted_moves as f64 / self.iterations as f64
+    }
+}
+
+/// Best state found by an [`Annealer::anneal`] call
+#[derive(Debug, Clone)]
+pub struct AnnealingResult<S> {
+    pub state: S,
+    pub energy: f64,
+    pub stats: AnnealingStats,
+}
+
+/// Simulated annealing with Metropolis acceptance
+pub struct Annealer {
+    config: AnnealingConfig,
+    rng: AnnealingRng,
+}
+
+impl Annealer {
+    pub fn new(config: AnnealingConfig) -> Self {
+        let rng = AnnealingRng::seed_from_u64(config.seed);
+        Self { config, rng }
+    }
+
+    pub fn config(&self) -> &AnnealingConfig {
+        &self.config
+    }
+
+    /// Minimize the energy of `problem`, starting from `initial`
+    ///
+    /// The random number generator is seeded again on every call, so calls with the same
+    /// problem and initial state return the same result.
+    pub fn anneal<P: AnnealingProblem>(
+        &mut self,
+        problem: &P,
+        initial: P::State,
+    ) -> AnnealingResult<P::State> {
+        self.rng = AnnealingRng::seed_from_u64(self.config.seed);
+
+        let initial_energy = problem.energy(&initial);
+        let mut best = initial;
+        let mut best_energy = initial_energy;
+        let mut stats = AnnealingStats { initial_energy, ..AnnealingStats::default() };
+
+        for _ in 0..=self.config.restarts {
+            let (state, energy) = self.run(problem, best.clone(), best_energy, &mut stats);
+            if energy < best_energy {
+                best = state;
+                best_energy = energy;
+            }
+            stats.runs += 1;
+            stats.run_best_energies.push(best_energy);
+        }
+
+        stats.final_energy = best_energy;
+        stats.energy_reduction = initial_energy - best_energy;
+        AnnealingResult { state: best, energy: best_energy, stats }
+    }
+
+    /// A single run from the initial temperature, returning its best state
+    fn run<P: AnnealingProblem>(
+        &mut self,
+        problem: &P,
+        start: P::State,
+        start_energy: f64,
+        stats: &mut AnnealingStats,
+    ) -> (P::State, f64) {
+        let config = &self.config;
+        let mut window = Window::new(config.schedule.window_size());
+        let mut temperature = config.initial_temperature;
+
+        let mut current = start.clone();
+        let mut current_energy = start_energy;
+        let mut best = start;
+        let mut best_energy = start_energy;
+        let mut stalled = 0;
+        stats.converged = false;
+
+        for _ in 0..config.max_iterations {
+            if temperature <= config.min_temperature {
+                break;
+            }
+
+            let candidate = problem.neighbor(&current, &mut self.rng);
+            let candidate_energy = problem.energy(&candidate);
+            let delta = candidate_energy - current_energy;
+            // Metropolis: always take downhill moves, uphill ones with probability
+            // exp(-delta / T). NaN energies are never accepted.
+            let accepted = delta <= 0.0
+                || (delta > 0.0 && self.rng.random::<f64>() < (-delta / temperature).exp());
+
+            stats.iterations += 1;
+            window.push(accepted);
+            if accepted {
+                stats.accepted_moves += 1;
+                current = candidate;
+                current_energy = candidate_energy;
+            }
+
+            if accepted && current_energy < best_energy - config.tolerance {
+                stats.improvements += 1;
+                best = current.clone();
+                best_energy = current_energy;
+                stalled = 0;
+            } else {
+                if accepted && current_energy < best_energy {
+                    best = current.clone();
+                    best_energy = current_energy;
+                }
+                stalled += 1;
+                if config.stall_iterations > 0 && stalled >= config.stall_iterations {
+                    stats.converged = true;
+                    break;
+                }
+            }
+
+            temperature = config.schedule.next_temperature(temperature, config, &window).max(0.0);
+        }
+
+        stats.final_temperature = temperature;
+        (best, best_energy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parabola() -> impl AnnealingProblem<State = f64> {
+        FnProblem::new(
+            |x: &f64| (x - 3.0) * (x - 3.0),
+            |x: &f64, rng: &mut AnnealingRng| x + rng.random_range(-1.0..1.0),
+        )
+    }
+
+    #[test]
+    fn test_finds_minimum() {
+        let mut annealer = Annealer::new(AnnealingConfig::default());
+        let result = annealer.anneal(&parabola(), -20.0);
+        assert!((result.state - 3.0).abs() < 0.1, "{}", result.state);
+        assert!(result.energy < 0.01);
+        assert_eq!(result.stats.initial_energy, 529.0);
+        assert_eq!(result.stats.final_energy, result.energy);
+        assert!(result.stats.improvements > 0);
+        assert!(result.stats.accepted_moves <= result.stats.iterations as u64);
+    }
+
+    #[test]
+    fn test_same_seed_same_result() {
+        let config = AnnealingConfig { seed: 42, ..AnnealingConfig::default() };
+        let first = Annealer::new(config.clone()).anneal(&parabola(), 10.0);
+        let mut annealer = Annealer::new(config);
+        let second = annealer.anneal(&parabola(), 10.0);
+        let third = annealer.anneal(&parabola(), 10.0);
+        assert_eq!(first.state, second.state);
+        assert_eq!(first.stats, second.stats);
+        assert_eq!(second.state, third.state);
+    }
+
+    #[test]
+    fn test_schedules() {
+        let schedules = [
+            CoolingSchedule::Geometric { alpha: 0.99 },
+            CoolingSchedule::Linear,
+            CoolingSchedule::Adaptive { alpha: 0.99, target_acceptance: 0.5, window: 20 },
+        ];
+        for schedule in schedules {
+            let config = AnnealingConfig { schedule, max_iterations: 5_000, ..Default::default() };
+            let result = Annealer::new(config).anneal(&parabola(), -20.0);
+            assert!((result.state - 3.0).abs() < 0.2, "{schedule:?}: {}", result.state);
+            assert!(result.stats.final_temperature < 100.0);
+        }
+    }
+
+    #[test]
+    fn test_linear_schedule_reaches_min_temperature() {
+        let config = AnnealingConfig {
+            schedule: CoolingSchedule::Linear,
+            initial_temperature: 10.0,
+            min_temperature: 0.0,
+            max_iterations: 100,
+            ..Default::default()
+        };
+        let result = Annealer::new(config).anneal(&parabola(), 0.0);
+        assert_eq!(result.stats.iterations, 100);
+        assert!(result.stats.final_temperature.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_escapes_local_minimum() {
+        // Integers from 0 to 20 with a local minimum at 2 and the global one at 15
+        let energy = |x: &i64| match x {
+            2 => 1.0,
+            15 => 0.0,
+            x => 5.0 + (x - 8).abs() as f64 * 0.1,
+        };
+        let neighbor = |x: &i64, rng: &mut AnnealingRng| {
+            if rng.random::<bool>() { (x + 1).min(20) } else { (x - 1).max(0) }
+        };
+        let config = AnnealingConfig {
+            initial_temperature: 10.0,
+            min_temperature: 0.01,
+            schedule: CoolingSchedule::Geometric { alpha: 0.999 },
+            ..Default::default()
+        };
+        let result = Annealer::new(config).anneal(&FnProblem::new(energy, neighbor), 2);
+        assert_eq!(result.state, 15);
+        assert_eq!(result.energy, 0.0);
+    }
+
+    #[test]
+    fn test_restarts_and_convergence() {
+        let config = AnnealingConfig {
+            restarts: 3,
+            stall_iterations: 200,
+            tolerance: 1e-6,
+            ..Default::default()
+        };
+        let result = Annealer::new(config).anneal(&parabola(), 50.0);
+        assert_eq!(result.stats.runs, 4);
+        assert_eq!(result.stats.run_best_energies.len(), 4);
+        assert!(result.stats.run_best_energies.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(*result.stats.run_best_energies.last().unwrap(), result.energy);
+        assert!(result.stats.converged);
+        assert!(result.stats.iterations < 4 * 10_000);
+    }
+
+    #[test]
+    fn test_nan_energy_rejected() {
+        let problem = FnProblem::new(
+            |x: &f64| if *x > 0.0 { f64::NAN } else { x * x },
+            |x: &f64, rng: &mut AnnealingRng| x + rng.random_range(-1.0..1.0),
+        );
+        let config = AnnealingConfig { max_iterations: 1_000, ..Default::default() };
+        let result = Annealer::new(config).anneal(&problem, -5.0);
+        assert!(result.state <= 0.0);
+        assert!(!result.energy.is_nan());
+    }
+}
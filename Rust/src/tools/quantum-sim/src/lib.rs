@@ -0,0 +1,33 @@
+//! Quantum circuit simulator and simulated annealing
+//!
+//! These used to be part of `rustc_quantum`. The compiler does not use them, and their
+//! dependencies (`ndarray`, `num-complex`, `rayon`) are not permitted in rustc, so they live
+//! in this crate outside of it.
+
+pub mod annealing;
+pub mod quantum_algorithms;
+
+/// Simulator error types
+#[derive(Debug)]
+pub enum QuantumError {
+    ParsingError(String),
+    OptimizationError(String),
+    SimulationError(String),
+}
+
+impl std::fmt::Display for QuantumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuantumError::ParsingError(msg) => write!(f, "Quantum parsing error: {}", msg),
+            QuantumError::OptimizationError(msg) => {
+                write!(f, "Quantum optimization error: {}", msg)
+            }
+            QuantumError::SimulationError(msg) => write!(f, "Quantum simulation error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for QuantumError {}
+
+/// Simulator result type
+pub type QuantumResult<T> = Result<T, QuantumError>;
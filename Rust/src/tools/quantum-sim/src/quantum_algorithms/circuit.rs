@@ -0,0 +1,855 @@
+//! Quantum circuits
+//!
+//! A [`QuantumCircuit`] records gates, measurements and resets on numbered qubits and
+//! classical bits without running them, so it can be inspected, reused with different
+//! parameter values and run on any [`Backend`].
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::{ParameterExpr, QuantumGate, QuantumState, UnitaryMatrix, check_qubits};
+use crate::{QuantumError, QuantumResult};
+
+/// Named range of the qubits of a circuit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantumRegister {
+    pub name: String,
+    pub size: usize,
+    /// Index of the first qubit of the register in the circuit
+    pub offset: usize,
+}
+
+/// Named range of the classical bits of a circuit
+///
+/// Bit `i` of the register is the `i`th least significant bit of its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassicalRegister {
+    pub name: String,
+    pub size: usize,
+    /// Index of the first bit of the register in the circuit
+    pub offset: usize,
+}
+
+/// Condition on the measured value of a classical register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    /// Index of the register in [`QuantumCircuit::classical_registers`]
+    pub register: usize,
+    pub value: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Gate {
+        gate: QuantumGate<ParameterExpr>,
+        qubits: Vec<usize>,
+    },
+    /// Measure `qubit` and store the outcome in classical bit `clbit`
+    Measure {
+        qubit: usize,
+        clbit: usize,
+    },
+    /// Put `qubit` back into |0⟩
+    Reset {
+        qubit: usize,
+    },
+    /// Keeps optimizations from moving operations across it; no effect on the state
+    Barrier {
+        qubits: Vec<usize>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub operation: Operation,
+    /// The operation only runs if the condition holds
+    pub condition: Option<Condition>,
+}
+
+/// Quantum circuit
+///
+/// Qubits and classical bits are numbered from 0 across all registers. Builder methods
+/// check their operands and return the circuit, so calls can be chained with `?`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuantumCircuit {
+    quantum_registers: Vec<QuantumRegister>,
+    classical_registers: Vec<ClassicalRegister>,
+    num_qubits: usize,
+    num_clbits: usize,
+    instructions: Vec<Instruction>,
+}
+
+impl QuantumCircuit {
+    /// Circuit with a quantum register `q` of `num_qubits` qubits and, if `num_clbits` is not
+    /// zero, a classical register `c`
+    pub fn new(num_qubits: usize, num_clbits: usize) -> Self {
+        let mut circuit = Self::empty();
+        if num_qubits > 0 {
+            circuit.add_quantum_register("q", num_qubits).unwrap();
+        }
+        if num_clbits > 0 {
+            circuit.add_classical_register("c", num_clbits).unwrap();
+        }
+        circuit
+    }
+
+    /// Circuit without registers
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Add `size` qubits, returning the index of the first one
+    pub fn add_quantum_register(&mut self, name: &str, size: usize) -> QuantumResult<usize> {
+        self.check_register_name(name)?;
+        let offset = self.num_qubits;
+        self.quantum_registers.push(QuantumRegister { name: name.to_string(), size, offset });
+        self.num_qubits += size;
+        Ok(offset)
+    }
+
+    /// Add `size` classical bits, returning the index of the first one
+    pub fn add_classical_register(&mut self, name: &str, size: usize) -> QuantumResult<usize> {
+        self.check_register_name(name)?;
+        if size > 64 {
+            return Err(QuantumError::SimulationError(format!(
+                "classical register `{name}` has {size} bits, at most 64 are supported"
+            )));
+        }
+        let offset = self.num_clbits;
+        self.classical_registers.push(ClassicalRegister { name: name.to_string(), size, offset });
+        self.num_clbits += size;
+        Ok(offset)
+    }
+
+    fn check_register_name(&self, name: &str) -> QuantumResult<()> {
+        let quantum = self.quantum_registers.iter().map(|register| &register.name);
+        let classical = self.classical_registers.iter().map(|register| &register.name);
+        if quantum.chain(classical).any(|existing| existing == name) {
+            return Err(QuantumError::SimulationError(format!(
+                "register `{name}` is already defined"
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn num_clbits(&self) -> usize {
+        self.num_clbits
+    }
+
+    pub fn quantum_registers(&self) -> &[QuantumRegister] {
+        &self.quantum_registers
+    }
+
+    pub fn classical_registers(&self) -> &[ClassicalRegister] {
+        &self.classical_registers
+    }
+
+    pub fn quantum_register(&self, name: &str) -> Option<&QuantumRegister> {
+        self.quantum_registers.iter().find(|register| register.name == name)
+    }
+
+    /// Index and contents of the classical register called `name`
+    pub fn classical_register(&self, name: &str) -> Option<(usize, &ClassicalRegister)> {
+        self.classical_registers.iter().enumerate().find(|(_, register)| register.name == name)
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Append `operation`, checking its qubits and classical bits
+    pub fn append(&mut self, operation: Operation) -> QuantumResult<&mut Self> {
+        self.push(Instruction { operation, condition: None })
+    }
+
+    /// Append `operation`, which only runs if `condition` holds
+    pub fn append_conditional(
+        &mut self,
+        operation: Operation,
+        condition: Condition,
+    ) -> QuantumResult<&mut Self> {
+        self.push(Instruction { operation, condition: Some(condition) })
+    }
+
+    /// Append an instruction, checking its qubits, classical bits and condition
+    pub fn push(&mut self, instruction: Instruction) -> QuantumResult<&mut Self> {
+        match &instruction.operation {
+            Operation::Gate { gate, qubits } => check_qubits(gate, qubits, self.num_qubits)?,
+            Operation::Measure { qubit, clbit } => {
+                self.check_qubit(*qubit)?;
+                if *clbit >= self.num_clbits {
+                    return Err(QuantumError::SimulationError(format!(
+                        "classical bit {clbit} is out of range for {} bits",
+                        self.num_clbits
+                    )));
+                }
+            }
+            Operation::Reset { qubit } => self.check_qubit(*qubit)?,
+            Operation::Barrier { qubits } => {
+                for &qubit in qubits {
+                    self.check_qubit(qubit)?;
+                }
+            }
+        }
+        if let Some(condition) = instruction.condition {
+            let Some(register) = self.classical_registers.get(condition.register) else {
+                return Err(QuantumError::SimulationError(format!(
+                    "condition on unknown classical register {}",
+                    condition.register
+                )));
+            };
+            if register.size < 64 && condition.value >> register.size != 0 {
+                return Err(QuantumError::SimulationError(format!(
+                    "condition value {} does not fit into register `{}`",
+                    condition.value, register.name
+                )));
+            }
+        }
+        self.instructions.push(instruction);
+        Ok(self)
+    }
+
+    fn check_qubit(&self, qubit: usize) -> QuantumResult<()> {
+        if qubit >= self.num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "qubit {qubit} is out of range for {} qubits",
+                self.num_qubits
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn gate(
+        &mut self,
+        gate: QuantumGate<ParameterExpr>,
+        qubits: &[usize],
+    ) -> QuantumResult<&mut Self> {
+        self.append(Operation::Gate { gate, qubits: qubits.to_vec() })
+    }
+
+    pub fn h(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Hadamard, &[qubit])
+    }
+
+    pub fn x(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::PauliX, &[qubit])
+    }
+
+    pub fn y(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::PauliY, &[qubit])
+    }
+
+    pub fn z(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::PauliZ, &[qubit])
+    }
+
+    pub fn cx(&mut self, control: usize, target: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::CNOT, &[control, target])
+    }
+
+    pub fn phase(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        qubit: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Phase(theta.into()), &[qubit])
+    }
+
+    /// General single-qubit rotation, see [`QuantumGate::Rotation`]
+    pub fn u(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        phi: impl Into<ParameterExpr>,
+        lambda: impl Into<ParameterExpr>,
+        qubit: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Rotation(theta.into(), phi.into(), lambda.into()), &[qubit])
+    }
+
+    pub fn s(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::S, &[qubit])
+    }
+
+    pub fn sdg(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Sdg, &[qubit])
+    }
+
+    pub fn t(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::T, &[qubit])
+    }
+
+    pub fn tdg(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Tdg, &[qubit])
+    }
+
+    pub fn sx(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::SX, &[qubit])
+    }
+
+    pub fn sxdg(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::SXdg, &[qubit])
+    }
+
+    pub fn rx(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        qubit: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Rx(theta.into()), &[qubit])
+    }
+
+    pub fn ry(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        qubit: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Ry(theta.into()), &[qubit])
+    }
+
+    pub fn rz(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        qubit: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Rz(theta.into()), &[qubit])
+    }
+
+    pub fn cz(&mut self, control: usize, target: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::CZ, &[control, target])
+    }
+
+    pub fn swap(&mut self, qubit1: usize, qubit2: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::SWAP, &[qubit1, qubit2])
+    }
+
+    pub fn iswap(&mut self, qubit1: usize, qubit2: usize) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::ISwap, &[qubit1, qubit2])
+    }
+
+    pub fn crx(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        control: usize,
+        target: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::CRx(theta.into()), &[control, target])
+    }
+
+    pub fn cry(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        control: usize,
+        target: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::CRy(theta.into()), &[control, target])
+    }
+
+    pub fn crz(
+        &mut self,
+        theta: impl Into<ParameterExpr>,
+        control: usize,
+        target: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::CRz(theta.into()), &[control, target])
+    }
+
+    pub fn ccx(
+        &mut self,
+        control1: usize,
+        control2: usize,
+        target: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Toffoli, &[control1, control2, target])
+    }
+
+    pub fn cswap(
+        &mut self,
+        control: usize,
+        qubit1: usize,
+        qubit2: usize,
+    ) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Fredkin, &[control, qubit1, qubit2])
+    }
+
+    /// `gate` on `targets`, applied where every qubit in `controls` is |1⟩
+    pub fn controlled(
+        &mut self,
+        gate: QuantumGate<ParameterExpr>,
+        controls: &[usize],
+        targets: &[usize],
+    ) -> QuantumResult<&mut Self> {
+        let qubits: Vec<usize> = controls.iter().chain(targets).copied().collect();
+        self.gate(QuantumGate::controlled(gate, controls.len()), &qubits)
+    }
+
+    pub fn unitary(&mut self, matrix: UnitaryMatrix, qubits: &[usize]) -> QuantumResult<&mut Self> {
+        self.gate(QuantumGate::Unitary(matrix), qubits)
+    }
+
+    pub fn measure(&mut self, qubit: usize, clbit: usize) -> QuantumResult<&mut Self> {
+        self.append(Operation::Measure { qubit, clbit })
+    }
+
+    pub fn reset(&mut self, qubit: usize) -> QuantumResult<&mut Self> {
+        self.append(Operation::Reset { qubit })
+    }
+
+    pub fn barrier(&mut self, qubits: &[usize]) -> QuantumResult<&mut Self> {
+        self.append(Operation::Barrier { qubits: qubits.to_vec() })
+    }
+
+    /// Names of the parameters the gate angles depend on
+    pub fn parameters(&self) -> BTreeSet<String> {
+        let mut parameters = BTreeSet::new();
+        for instruction in &self.instructions {
+            if let Operation::Gate { gate, .. } = &instruction.operation {
+                for param in gate.params() {
+                    param.collect_parameters(&mut parameters);
+                }
+            }
+        }
+        parameters
+    }
+
+    pub fn is_parameterized(&self) -> bool {
+        !self.parameters().is_empty()
+    }
+
+    /// Copy of the circuit with the parameters in `bindings` replaced by their values
+    ///
+    /// Parameters missing from `bindings` stay symbolic.
+    pub fn bind_parameters(&self, bindings: &HashMap<String, f64>) -> QuantumCircuit {
+        let mut circuit = self.clone();
+        for instruction in &mut circuit.instructions {
+            if let Operation::Gate { gate, .. } = &mut instruction.operation {
+                *gate = gate.try_map_params(|param| Ok::<_, ()>(param.bind(bindings))).unwrap();
+            }
+        }
+        circuit
+    }
+
+    /// Value of classical register `register` in `clbits`
+    pub fn register_value(&self, register: usize, clbits: &[bool]) -> u64 {
+        let register = &self.classical_registers[register];
+        clbits[register.offset..register.offset + register.size]
+            .iter()
+            .rev()
+            .fold(0, |value, &bit| value << 1 | bit as u64)
+    }
+
+    /// Whether the circuit measures each qubit only after the last gate on it, and has no
+    /// resets or conditions
+    ///
+    /// The outcomes of such a circuit can be sampled from a single final state.
+    pub fn has_only_final_measurements(&self) -> bool {
+        let mut measured = vec![false; self.num_qubits];
+        for instruction in &self.instructions {
+            if instruction.condition.is_some() {
+                return false;
+            }
+            match &instruction.operation {
+                Operation::Gate { qubits, .. } => {
+                    if qubits.iter().any(|&qubit| measured[qubit]) {
+                        return false;
+                    }
+                }
+                Operation::Measure { qubit, .. } => measured[*qubit] = true,
+                Operation::Reset { .. } => return false,
+                Operation::Barrier { .. } => {}
+            }
+        }
+        true
+    }
+
+    /// Circuit with the same registers and no instructions
+    pub fn copy_empty(&self) -> QuantumCircuit {
+        QuantumCircuit {
+            quantum_registers: self.quantum_registers.clone(),
+            classical_registers: self.classical_registers.clone(),
+            num_qubits: self.num_qubits,
+            num_clbits: self.num_clbits,
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Number of layers of instructions on disjoint qubits and classical bits
+    ///
+    /// Measurements write their classical bit and conditions read their register. Barriers
+    /// order the instructions around them without adding a layer.
+    pub fn depth(&self) -> usize {
+        let mut layers = vec![0; self.num_qubits + self.num_clbits];
+        for instruction in &self.instructions {
+            let wires = self.wires(instruction);
+            let barrier = matches!(instruction.operation, Operation::Barrier { .. });
+            let layer =
+                wires.iter().map(|&wire| layers[wire]).max().unwrap_or(0) + !barrier as usize;
+            for wire in wires {
+                layers[wire] = layer;
+            }
+        }
+        layers.into_iter().max().unwrap_or(0)
+    }
+
+    /// Number of instructions of each kind, by gate name or `measure`, `reset` and `barrier`
+    pub fn gate_counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for instruction in &self.instructions {
+            let name = match &instruction.operation {
+                Operation::Gate { gate, .. } => gate.name(),
+                Operation::Measure { .. } => "measure",
+                Operation::Reset { .. } => "reset",
+                Operation::Barrier { .. } => "barrier",
+            };
+            *counts.entry(name).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Qubits and classical bits `instruction` uses, with classical bit `i` numbered
+    /// `num_qubits + i`
+    ///
+    /// A barrier without qubits spans every qubit.
+    pub(crate) fn wires(&self, instruction: &Instruction) -> Vec<usize> {
+        let mut wires = match &instruction.operation {
+            Operation::Gate { qubits, .. } => qubits.clone(),
+            Operation::Measure { qubit, clbit } => vec![*qubit, self.num_qubits + clbit],
+            Operation::Reset { qubit } => vec![*qubit],
+            Operation::Barrier { qubits } if qubits.is_empty() => (0..self.num_qubits).collect(),
+            Operation::Barrier { qubits } => qubits.clone(),
+        };
+        if let Some(condition) = instruction.condition {
+            let register = &self.classical_registers[condition.register];
+            let bits = register.offset..register.offset + register.size;
+            wires.extend(bits.map(|bit| self.num_qubits + bit));
+            wires.sort_unstable();
+            wires.dedup();
+        }
+        wires
+    }
+}
+
+/// Gate of a circuit with numeric angles
+pub(crate) fn bound_gate(gate: &QuantumGate<ParameterExpr>) -> QuantumResult<QuantumGate> {
+    let no_bindings = HashMap::new();
+    gate.try_map_params(|param| param.evaluate(&no_bindings))
+}
+
+/// Check that every parameter of `circuit` is bound, as backends need numeric angles
+pub(crate) fn check_bound(circuit: &QuantumCircuit) -> QuantumResult<()> {
+    let unbound = circuit.parameters();
+    if !unbound.is_empty() {
+        return Err(QuantumError::SimulationError(format!(
+            "unbound circuit parameters: {}",
+            unbound.into_iter().collect::<Vec<_>>().join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Measured classical bits as a string, the last bit first
+pub fn bitstring(clbits: &[bool]) -> String {
+    clbits.iter().rev().map(|&bit| if bit { '1' } else { '0' }).collect()
+}
+
+/// Outcome of running a circuit
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionResult {
+    pub shots: usize,
+    /// How often each value of the classical bits was measured, see [`bitstring`]
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl ExecutionResult {
+    /// Share of the shots that measured `bits`
+    pub fn probability(&self, bits: &str) -> f64 {
+        if self.shots == 0 {
+            return 0.0;
+        }
+        self.counts.get(bits).copied().unwrap_or(0) as f64 / self.shots as f64
+    }
+}
+
+/// Simulator that can run circuits
+pub trait Backend {
+    fn name(&self) -> &'static str;
+
+    /// Run `circuit` `shots` times and count the measured classical bits
+    ///
+    /// Every parameter of the circuit must be bound.
+    fn run(&mut self, circuit: &QuantumCircuit, shots: usize) -> QuantumResult<ExecutionResult>;
+}
+
+/// Simulator that keeps all `2^n` amplitudes of the state
+pub struct StateVectorBackend {
+    rng: Xoshiro256PlusPlus,
+}
+
+impl StateVectorBackend {
+    /// Largest number of qubits the backend simulates
+    pub const MAX_QUBITS: usize = 30;
+
+    /// Backend whose measurements are drawn from a generator seeded with `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { rng: Xoshiro256PlusPlus::seed_from_u64(seed) }
+    }
+
+    /// State at the end of a circuit without measurements, resets or conditions
+    pub fn statevector(&self, circuit: &QuantumCircuit) -> QuantumResult<QuantumState> {
+        Self::check_size(circuit)?;
+        let mut state = QuantumState::new(circuit.num_qubits());
+        self.evolve(circuit, &mut state)?;
+        Ok(state)
+    }
+
+    /// Apply a circuit without measurements, resets or conditions to `state`
+    pub fn evolve(&self, circuit: &QuantumCircuit, state: &mut QuantumState) -> QuantumResult<()> {
+        if state.qubit_count != circuit.num_qubits() {
+            return Err(QuantumError::SimulationError(format!(
+                "a circuit of {} qubits cannot evolve a state of {}",
+                circuit.num_qubits(),
+                state.qubit_count
+            )));
+        }
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                _ if instruction.condition.is_some() => {
+                    return Err(QuantumError::SimulationError(
+                        "the state vector of a circuit with conditions is not defined".to_string(),
+                    ));
+                }
+                Operation::Gate { gate, qubits } => state.apply_gate(&bound_gate(gate)?, qubits)?,
+                Operation::Barrier { .. } => {}
+                Operation::Measure { .. } | Operation::Reset { .. } => {
+                    return Err(QuantumError::SimulationError(
+                        "the state vector of a circuit with measurements is not defined"
+                            .to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_size(circuit: &QuantumCircuit) -> QuantumResult<()> {
+        if circuit.num_qubits() > Self::MAX_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "the state vector backend simulates at most {} qubits, the circuit has {}",
+                Self::MAX_QUBITS,
+                circuit.num_qubits()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sample the final state once instead of simulating every shot
+    fn run_sampled(
+        &mut self,
+        circuit: &QuantumCircuit,
+        shots: usize,
+    ) -> QuantumResult<ExecutionResult> {
+        let mut state = QuantumState::new(circuit.num_qubits());
+        let mut measurements = Vec::new();
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                Operation::Gate { gate, qubits } => state.apply_gate(&bound_gate(gate)?, qubits)?,
+                Operation::Measure { qubit, clbit } => measurements.push((*qubit, *clbit)),
+                Operation::Reset { .. } | Operation::Barrier { .. } => {}
+            }
+        }
+
+        let mut counts = BTreeMap::new();
+        for (basis_state, count) in state.sample(shots, &mut self.rng)? {
+            let mut clbits = vec![false; circuit.num_clbits()];
+            for &(qubit, clbit) in &measurements {
+                clbits[clbit] = (basis_state >> qubit) & 1 == 1;
+            }
+            *counts.entry(bitstring(&clbits)).or_insert(0) += count;
+        }
+        Ok(ExecutionResult { shots, counts })
+    }
+
+    fn run_shot(&mut self, circuit: &QuantumCircuit) -> QuantumResult<Vec<bool>> {
+        let mut state = QuantumState::new(circuit.num_qubits());
+        let mut clbits = vec![false; circuit.num_clbits()];
+        for instruction in circuit.instructions() {
+            if let Some(condition) = instruction.condition
+                && circuit.register_value(condition.register, &clbits) != condition.value
+            {
+                continue;
+            }
+            match &instruction.operation {
+                Operation::Gate { gate, qubits } => state.apply_gate(&bound_gate(gate)?, qubits)?,
+                Operation::Measure { qubit, clbit } => {
+                    clbits[*clbit] = state.measure(*qubit, &mut self.rng)?;
+                }
+                Operation::Reset { qubit } => {
+                    if state.measure(*qubit, &mut self.rng)? {
+                        state.apply_gate(&QuantumGate::PauliX, &[*qubit])?;
+                    }
+                }
+                Operation::Barrier { .. } => {}
+            }
+        }
+        Ok(clbits)
+    }
+}
+
+impl Backend for StateVectorBackend {
+    fn name(&self) -> &'static str {
+        "statevector"
+    }
+
+    fn run(&mut self, circuit: &QuantumCircuit, shots: usize) -> QuantumResult<ExecutionResult> {
+        Self::check_size(circuit)?;
+        check_bound(circuit)?;
+
+        if circuit.has_only_final_measurements() {
+            return self.run_sampled(circuit, shots);
+        }
+        let mut counts = BTreeMap::new();
+        for _ in 0..shots {
+            let clbits = self.run_shot(circuit)?;
+            *counts.entry(bitstring(&clbits)).or_insert(0) += 1;
+        }
+        Ok(ExecutionResult { shots, counts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn bell_circuit() -> QuantumCircuit {
+        let mut circuit = QuantumCircuit::new(2, 2);
+        circuit.h(0).unwrap().cx(0, 1).unwrap().measure(0, 0).unwrap().measure(1, 1).unwrap();
+        circuit
+    }
+
+    #[test]
+    fn test_bell_circuit() {
+        let circuit = bell_circuit();
+        assert!(circuit.has_only_final_measurements());
+        let result = StateVectorBackend::new(1).run(&circuit, 4_000).unwrap();
+        assert_eq!(result.shots, 4_000);
+        assert_eq!(result.counts.keys().collect::<Vec<_>>(), ["00", "11"]);
+        let chi_square: f64 =
+            result.counts.values().map(|&n| (n as f64 - 2_000.0).powi(2) / 2_000.0).sum();
+        assert!(chi_square < 10.83, "{:?}", result.counts);
+
+        let again = StateVectorBackend::new(1).run(&circuit, 4_000).unwrap();
+        assert_eq!(result, again);
+    }
+
+    #[test]
+    fn test_parameter_binding() {
+        let mut circuit = QuantumCircuit::new(1, 1);
+        let theta = ParameterExpr::symbol("theta");
+        circuit.u(theta * 2.0, 0.0, 0.0, 0).unwrap().measure(0, 0).unwrap();
+        assert_eq!(circuit.parameters().into_iter().collect::<Vec<_>>(), ["theta"]);
+
+        let mut backend = StateVectorBackend::new(0);
+        assert!(backend.run(&circuit, 10).is_err());
+
+        let flipped = circuit.bind_parameters(&HashMap::from([("theta".to_string(), PI / 2.0)]));
+        assert!(!flipped.is_parameterized());
+        assert_eq!(backend.run(&flipped, 100).unwrap().counts["1"], 100);
+        let identity = circuit.bind_parameters(&HashMap::from([("theta".to_string(), 0.0)]));
+        assert_eq!(backend.run(&identity, 100).unwrap().counts["0"], 100);
+    }
+
+    #[test]
+    fn test_statevector() {
+        let mut circuit = QuantumCircuit::new(2, 0);
+        circuit.h(0).unwrap().cx(0, 1).unwrap();
+        let state = StateVectorBackend::new(0).statevector(&circuit).unwrap();
+        let probabilities = state.probabilities();
+        assert!((probabilities[0] - 0.5).abs() < 1e-12);
+        assert!((probabilities[3] - 0.5).abs() < 1e-12);
+        assert!(StateVectorBackend::new(0).statevector(&bell_circuit()).is_err());
+    }
+
+    #[test]
+    fn test_conditions_and_reset() {
+        // Teleport-style feed-forward: copy a measured bit onto another qubit
+        let mut circuit = QuantumCircuit::new(2, 2);
+        circuit.h(0).unwrap().measure(0, 0).unwrap();
+        let flip = Operation::Gate { gate: QuantumGate::PauliX, qubits: vec![1] };
+        circuit.append_conditional(flip, Condition { register: 0, value: 1 }).unwrap();
+        circuit.measure(1, 1).unwrap();
+        assert!(!circuit.has_only_final_measurements());
+
+        let result = StateVectorBackend::new(3).run(&circuit, 1_000).unwrap();
+        assert_eq!(result.counts.keys().collect::<Vec<_>>(), ["00", "11"]);
+        assert!((result.probability("11") - 0.5).abs() < 0.1);
+
+        let mut circuit = QuantumCircuit::new(1, 2);
+        circuit.x(0).unwrap().measure(0, 0).unwrap().reset(0).unwrap().measure(0, 1).unwrap();
+        let result = StateVectorBackend::new(0).run(&circuit, 50).unwrap();
+        assert_eq!(result.counts["01"], 50);
+    }
+
+    #[test]
+    fn test_extended_gates() {
+        // GHZ state from a Hadamard and a doubly controlled X, then swapped around
+        let mut circuit = QuantumCircuit::new(3, 3);
+        circuit.h(0).unwrap().cx(0, 1).unwrap().ccx(0, 1, 2).unwrap().swap(0, 2).unwrap();
+        circuit.controlled(QuantumGate::PauliZ, &[0, 1], &[2]).unwrap();
+        circuit.crz(ParameterExpr::symbol("angle"), 0, 1).unwrap();
+        let mut circuit = circuit.bind_parameters(&HashMap::from([("angle".to_string(), 0.3)]));
+        let state = StateVectorBackend::new(0).statevector(&circuit).unwrap();
+        let probabilities = state.probabilities();
+        assert!((probabilities[0] - 0.5).abs() < 1e-12);
+        assert!((probabilities[0b111] - 0.5).abs() < 1e-12);
+        assert!(circuit.ccx(0, 1, 1).is_err());
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut circuit = bell_circuit();
+        assert_eq!(circuit.depth(), 3);
+        circuit.barrier(&[]).unwrap().x(0).unwrap();
+        let flip = Operation::Gate { gate: QuantumGate::PauliX, qubits: vec![1] };
+        circuit.append_conditional(flip, Condition { register: 0, value: 1 }).unwrap();
+        // The condition waits for both measurements, the X only for the barrier
+        assert_eq!(circuit.depth(), 4);
+        let counts = circuit.gate_counts();
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            [("barrier", 1), ("cx", 1), ("h", 1), ("measure", 2), ("x", 2)]
+        );
+        assert_eq!(circuit.copy_empty().instructions(), []);
+        assert_eq!(circuit.copy_empty().classical_registers(), circuit.classical_registers());
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut circuit = QuantumCircuit::empty();
+        assert_eq!(circuit.add_quantum_register("a", 2).unwrap(), 0);
+        assert_eq!(circuit.add_quantum_register("b", 1).unwrap(), 2);
+        assert_eq!(circuit.add_classical_register("m", 3).unwrap(), 0);
+        assert!(circuit.add_classical_register("a", 1).is_err());
+        assert_eq!(circuit.num_qubits(), 3);
+        assert_eq!(circuit.quantum_register("b").unwrap().offset, 2);
+        assert_eq!(circuit.register_value(0, &[true, false, true]), 0b101);
+    }
+
+    #[test]
+    fn test_invalid_operands() {
+        let mut circuit = QuantumCircuit::new(2, 1);
+        assert!(circuit.h(2).is_err());
+        assert!(circuit.cx(1, 1).is_err());
+        assert!(circuit.gate(QuantumGate::CNOT, &[0]).is_err());
+        assert!(circuit.measure(0, 1).is_err());
+        let condition = Condition { register: 0, value: 2 };
+        assert!(circuit.append_conditional(Operation::Reset { qubit: 0 }, condition).is_err());
+        assert!(circuit.instructions().is_empty());
+    }
+}
@@ -0,0 +1,540 @@
+//! Density-matrix simulation
+//!
+//! A [`DensityMatrix`] describes mixed states, such as a qubit that has decohered or one whose
+//! measurement outcome has been discarded. [`DensityMatrixBackend`] runs circuits on density
+//! matrices, with the errors of a [`NoiseModel`] after every gate and measurement.
+
+use std::collections::BTreeMap;
+
+use ndarray::{Array2, ArrayViewMut1};
+use num_complex::Complex64;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::circuit::{bound_gate, check_bound};
+use super::{
+    Backend, ExecutionResult, KrausChannel, NoiseModel, Operation, QuantumCircuit, QuantumGate,
+    QuantumState, bitstring, check_qubits, sample_index,
+};
+use crate::{QuantumError, QuantumResult};
+
+/// Largest deviation from a valid density matrix accepted in [`DensityMatrix::from_matrix`]
+const DENSITY_TOLERANCE: f64 = 1e-9;
+
+/// Mixed state of `n` qubits as a `2^n × 2^n` matrix
+///
+/// Rows and columns are indexed by basis states like the amplitudes of a [`QuantumState`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityMatrix {
+    num_qubits: usize,
+    matrix: Array2<Complex64>,
+}
+
+impl DensityMatrix {
+    /// All qubits in |0⟩
+    pub fn new(num_qubits: usize) -> Self {
+        let dimension = 1 << num_qubits;
+        let mut matrix = Array2::zeros((dimension, dimension));
+        matrix[[0, 0]] = Complex64::new(1.0, 0.0);
+        Self { num_qubits, matrix }
+    }
+
+    /// Pure state `|ψ⟩⟨ψ|`
+    pub fn from_state(state: &QuantumState) -> Self {
+        let amplitudes: Vec<Complex64> =
+            state.amplitudes.iter().map(|&(re, im)| Complex64::new(re, im)).collect();
+        let dimension = amplitudes.len();
+        let matrix = Array2::from_shape_fn((dimension, dimension), |(row, col)| {
+            amplitudes[row] * amplitudes[col].conj()
+        });
+        Self { num_qubits: state.qubit_count, matrix }
+    }
+
+    /// Density matrix `matrix`, which must be Hermitian with trace 1
+    pub fn from_matrix(matrix: Array2<Complex64>) -> QuantumResult<Self> {
+        let dimension = matrix.nrows();
+        if matrix.ncols() != dimension || !dimension.is_power_of_two() {
+            return Err(QuantumError::SimulationError(format!(
+                "a density matrix must be square with a power of two rows, got {}x{}",
+                matrix.nrows(),
+                matrix.ncols()
+            )));
+        }
+        let asymmetry = matrix
+            .indexed_iter()
+            .map(|((row, col), element)| (element - matrix[[col, row]].conj()).norm())
+            .fold(0.0, f64::max);
+        let density = Self { num_qubits: dimension.trailing_zeros() as usize, matrix };
+        if asymmetry > DENSITY_TOLERANCE || (density.trace() - 1.0).abs() > DENSITY_TOLERANCE {
+            return Err(QuantumError::SimulationError(
+                "a density matrix must be Hermitian with trace 1".to_string(),
+            ));
+        }
+        Ok(density)
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn matrix(&self) -> &Array2<Complex64> {
+        &self.matrix
+    }
+
+    pub fn trace(&self) -> f64 {
+        self.matrix.diag().iter().map(|element| element.re).sum()
+    }
+
+    /// `Tr(ρ²)`, which is 1 for pure states and `1 / 2^n` for the maximally mixed state
+    pub fn purity(&self) -> f64 {
+        self.matrix.iter().map(|element| element.norm_sqr()).sum()
+    }
+
+    /// Probability of each basis state
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.matrix.diag().iter().map(|element| element.re).collect()
+    }
+
+    /// `⟨ψ|ρ|ψ⟩`, the probability of finding the pure state `state`
+    pub fn fidelity(&self, state: &QuantumState) -> QuantumResult<f64> {
+        if state.qubit_count != self.num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "cannot compare a {}-qubit density matrix with a {}-qubit state",
+                self.num_qubits, state.qubit_count
+            )));
+        }
+        let amplitudes: Vec<Complex64> =
+            state.amplitudes.iter().map(|&(re, im)| Complex64::new(re, im)).collect();
+        let mut fidelity = Complex64::new(0.0, 0.0);
+        for ((row, col), element) in self.matrix.indexed_iter() {
+            fidelity += amplitudes[row].conj() * element * amplitudes[col];
+        }
+        Ok(fidelity.re)
+    }
+
+    /// Apply `gate` to `qubits`, `ρ → U ρ U†`
+    pub fn apply_gate(&mut self, gate: &QuantumGate, qubits: &[usize]) -> QuantumResult<()> {
+        check_qubits(gate, qubits, self.num_qubits)?;
+        self.matrix = self.conjugate(&gate.matrix(), qubits);
+        Ok(())
+    }
+
+    /// Apply `channel` to `qubits`, `ρ → Σ K ρ K†`
+    pub fn apply_channel(&mut self, channel: &KrausChannel, qubits: &[usize]) -> QuantumResult<()> {
+        if qubits.len() != channel.num_qubits() {
+            return Err(QuantumError::SimulationError(format!(
+                "channel acts on {} qubits, but {} were given",
+                channel.num_qubits(),
+                qubits.len()
+            )));
+        }
+        self.check_qubits(qubits)?;
+        let mut result = Array2::zeros(self.matrix.raw_dim());
+        for operator in channel.operators() {
+            result = result + self.conjugate(operator, qubits);
+        }
+        self.matrix = result;
+        Ok(())
+    }
+
+    /// Measure `qubit`, collapsing the state onto the outcome
+    pub fn measure<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> QuantumResult<bool> {
+        self.check_qubits(&[qubit])?;
+        let (prob_zero, prob_one) = self.outcome_probabilities(qubit);
+        let total = prob_zero + prob_one;
+        if total <= 0.0 {
+            return Err(QuantumError::SimulationError(
+                "cannot measure a density matrix with zero trace".to_string(),
+            ));
+        }
+        let result = rng.random::<f64>() * total < prob_one;
+
+        let probability = if result { prob_one } else { prob_zero };
+        for ((row, col), element) in self.matrix.indexed_iter_mut() {
+            let kept = ((row >> qubit) & 1 == 1) == result && ((col >> qubit) & 1 == 1) == result;
+            *element = if kept { *element / probability } else { Complex64::new(0.0, 0.0) };
+        }
+        Ok(result)
+    }
+
+    /// Put `qubit` into |0⟩ without recording its value
+    pub fn reset(&mut self, qubit: usize) -> QuantumResult<()> {
+        self.check_qubits(&[qubit])?;
+        let zero = Complex64::new(0.0, 0.0);
+        let one = Complex64::new(1.0, 0.0);
+        let keep = Array2::from_shape_vec((2, 2), vec![one, zero, zero, zero]).unwrap();
+        let lower = Array2::from_shape_vec((2, 2), vec![zero, one, zero, zero]).unwrap();
+        self.matrix = self.conjugate(&keep, &[qubit]) + self.conjugate(&lower, &[qubit]);
+        Ok(())
+    }
+
+    fn outcome_probabilities(&self, qubit: usize) -> (f64, f64) {
+        let mut probabilities = (0.0, 0.0);
+        for (index, probability) in self.probabilities().into_iter().enumerate() {
+            if (index >> qubit) & 1 == 0 {
+                probabilities.0 += probability;
+            } else {
+                probabilities.1 += probability;
+            }
+        }
+        probabilities
+    }
+
+    fn check_qubits(&self, qubits: &[usize]) -> QuantumResult<()> {
+        for (i, &qubit) in qubits.iter().enumerate() {
+            if qubit >= self.num_qubits || qubits[..i].contains(&qubit) {
+                return Err(QuantumError::SimulationError(format!(
+                    "invalid qubits {qubits:?} for a {}-qubit density matrix",
+                    self.num_qubits
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// `K ρ K†` for an operator `K` on `qubits`
+    fn conjugate(&self, operator: &Array2<Complex64>, qubits: &[usize]) -> Array2<Complex64> {
+        let offsets: Vec<usize> = (0..1usize << qubits.len())
+            .map(|local| {
+                let bits = qubits.iter().enumerate().filter(|&(bit, _)| (local >> bit) & 1 == 1);
+                bits.map(|(_, &qubit)| 1 << qubit).sum()
+            })
+            .collect();
+        let mut result = self.matrix.clone();
+        for mut column in result.columns_mut() {
+            apply_local(&mut column, operator, &offsets, false);
+        }
+        // Row `i` of `ρ K†` is `conj(K)` applied to row `i` of `ρ`
+        for mut row in result.rows_mut() {
+            apply_local(&mut row, operator, &offsets, true);
+        }
+        result
+    }
+}
+
+/// Multiply `vector` by `operator` acting on the basis states at `offsets`
+fn apply_local(
+    vector: &mut ArrayViewMut1<'_, Complex64>,
+    operator: &Array2<Complex64>,
+    offsets: &[usize],
+    conjugate: bool,
+) {
+    let mask = offsets[offsets.len() - 1];
+    let mut input = vec![Complex64::new(0.0, 0.0); offsets.len()];
+    for base in 0..vector.len() {
+        if base & mask != 0 {
+            continue;
+        }
+        for (value, &offset) in input.iter_mut().zip(offsets) {
+            *value = vector[base | offset];
+        }
+        for (row, &offset) in offsets.iter().enumerate() {
+            vector[base | offset] = input
+                .iter()
+                .enumerate()
+                .map(|(col, value)| {
+                    let element = operator[[row, col]];
+                    if conjugate { element.conj() * value } else { element * value }
+                })
+                .sum();
+        }
+    }
+}
+
+/// Simulator that keeps the `4^n` elements of the density matrix, with optional noise
+pub struct DensityMatrixBackend {
+    rng: Xoshiro256PlusPlus,
+    noise: NoiseModel,
+}
+
+impl DensityMatrixBackend {
+    /// Largest number of qubits the backend simulates
+    pub const MAX_QUBITS: usize = 10;
+
+    /// Noiseless backend whose measurements are drawn from a generator seeded with `seed`
+    pub fn new(seed: u64) -> Self {
+        Self::with_noise(seed, NoiseModel::new())
+    }
+
+    pub fn with_noise(seed: u64, noise: NoiseModel) -> Self {
+        Self { rng: Xoshiro256PlusPlus::seed_from_u64(seed), noise }
+    }
+
+    pub fn noise_model(&self) -> &NoiseModel {
+        &self.noise
+    }
+
+    /// Noisy state at the end of a circuit without measurements or conditions
+    pub fn density_matrix(&self, circuit: &QuantumCircuit) -> QuantumResult<DensityMatrix> {
+        Self::check_size(circuit)?;
+        let mut density = DensityMatrix::new(circuit.num_qubits());
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                _ if instruction.condition.is_some() => {
+                    return Err(QuantumError::SimulationError(
+                        "the density matrix of a circuit with conditions is not defined"
+                            .to_string(),
+                    ));
+                }
+                Operation::Measure { .. } => {
+                    return Err(QuantumError::SimulationError(
+                        "the density matrix of a circuit with measurements is not defined"
+                            .to_string(),
+                    ));
+                }
+                operation => self.apply(&mut density, operation)?,
+            }
+        }
+        Ok(density)
+    }
+
+    fn check_size(circuit: &QuantumCircuit) -> QuantumResult<()> {
+        if circuit.num_qubits() > Self::MAX_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "the density matrix backend simulates at most {} qubits, the circuit has {}",
+                Self::MAX_QUBITS,
+                circuit.num_qubits()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply a gate with its error, a reset or a barrier
+    fn apply(&self, density: &mut DensityMatrix, operation: &Operation) -> QuantumResult<()> {
+        match operation {
+            Operation::Gate { gate, qubits } => {
+                density.apply_gate(&bound_gate(gate)?, qubits)?;
+                self.noise.apply_gate_error(density, gate.name(), qubits)
+            }
+            Operation::Reset { qubit } => density.reset(*qubit),
+            Operation::Barrier { .. } => Ok(()),
+            Operation::Measure { .. } => unreachable!("measurements need a random outcome"),
+        }
+    }
+
+    fn read(&mut self, bit: bool) -> bool {
+        match self.noise.readout_error {
+            Some(error) => error.read(bit, &mut self.rng),
+            None => bit,
+        }
+    }
+
+    /// Sample the final state once instead of simulating every shot
+    fn run_sampled(
+        &mut self,
+        circuit: &QuantumCircuit,
+        shots: usize,
+    ) -> QuantumResult<ExecutionResult> {
+        let mut density = DensityMatrix::new(circuit.num_qubits());
+        let mut measurements = Vec::new();
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                Operation::Measure { qubit, clbit } => measurements.push((*qubit, *clbit)),
+                operation => self.apply(&mut density, operation)?,
+            }
+        }
+
+        let cumulative: Vec<f64> = density
+            .probabilities()
+            .into_iter()
+            .scan(0.0, |total, probability| {
+                *total += probability.max(0.0);
+                Some(*total)
+            })
+            .collect();
+        let mut counts = BTreeMap::new();
+        for _ in 0..shots {
+            let basis_state = sample_index(&cumulative, &mut self.rng);
+            let mut clbits = vec![false; circuit.num_clbits()];
+            for &(qubit, clbit) in &measurements {
+                clbits[clbit] = self.read((basis_state >> qubit) & 1 == 1);
+            }
+            *counts.entry(bitstring(&clbits)).or_insert(0) += 1;
+        }
+        Ok(ExecutionResult { shots, counts })
+    }
+
+    fn run_shot(&mut self, circuit: &QuantumCircuit) -> QuantumResult<Vec<bool>> {
+        let mut density = DensityMatrix::new(circuit.num_qubits());
+        let mut clbits = vec![false; circuit.num_clbits()];
+        for instruction in circuit.instructions() {
+            if let Some(condition) = instruction.condition
+                && circuit.register_value(condition.register, &clbits) != condition.value
+            {
+                continue;
+            }
+            match &instruction.operation {
+                Operation::Measure { qubit, clbit } => {
+                    let bit = density.measure(*qubit, &mut self.rng)?;
+                    clbits[*clbit] = self.read(bit);
+                }
+                operation => self.apply(&mut density, operation)?,
+            }
+        }
+        Ok(clbits)
+    }
+}
+
+impl Backend for DensityMatrixBackend {
+    fn name(&self) -> &'static str {
+        "density_matrix"
+    }
+
+    fn run(&mut self, circuit: &QuantumCircuit, shots: usize) -> QuantumResult<ExecutionResult> {
+        Self::check_size(circuit)?;
+        check_bound(circuit)?;
+
+        if circuit.has_only_final_measurements() {
+            return self.run_sampled(circuit, shots);
+        }
+        let mut counts = BTreeMap::new();
+        for _ in 0..shots {
+            let clbits = self.run_shot(circuit)?;
+            *counts.entry(bitstring(&clbits)).or_insert(0) += 1;
+        }
+        Ok(ExecutionResult { shots, counts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+    use crate::quantum_algorithms::{Condition, ReadoutError, StateVectorBackend};
+
+    fn bell_state() -> QuantumState {
+        let mut state = QuantumState::new(2);
+        state.amplitudes = vec![(FRAC_1_SQRT_2, 0.0), (0.0, 0.0), (0.0, 0.0), (FRAC_1_SQRT_2, 0.0)];
+        state
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_noiseless_matches_state_vector() {
+        let mut circuit = QuantumCircuit::new(3, 0);
+        circuit.h(0).unwrap().cx(0, 1).unwrap().ry(0.7, 2).unwrap().crz(1.1, 2, 0).unwrap();
+        circuit.ccx(0, 2, 1).unwrap().u(0.3, 0.2, 0.1, 1).unwrap().iswap(1, 2).unwrap();
+        let state = StateVectorBackend::new(0).statevector(&circuit).unwrap();
+        let density = DensityMatrixBackend::new(0).density_matrix(&circuit).unwrap();
+        let expected = DensityMatrix::from_state(&state);
+        let difference = density.matrix() - expected.matrix();
+        assert!(difference.iter().map(|e| e.norm()).fold(0.0, f64::max) < 1e-12);
+        assert_close(density.purity(), 1.0);
+        assert_close(density.fidelity(&state).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_depolarizing() {
+        let mut density = DensityMatrix::new(1);
+        density.apply_channel(&KrausChannel::depolarizing(1.0, 1).unwrap(), &[0]).unwrap();
+        assert_close(density.purity(), 0.5);
+        assert_eq!(density.probabilities(), [0.5, 0.5]);
+
+        // (1 - p) ρ + p I/4 overlaps the Bell state with 1 - p + p/4
+        let mut density = DensityMatrix::from_state(&bell_state());
+        density.apply_channel(&KrausChannel::depolarizing(0.2, 2).unwrap(), &[0, 1]).unwrap();
+        assert_close(density.fidelity(&bell_state()).unwrap(), 0.85);
+        assert_close(density.trace(), 1.0);
+    }
+
+    #[test]
+    fn test_amplitude_damping() {
+        let mut density = DensityMatrix::new(1);
+        density.apply_gate(&QuantumGate::PauliX, &[0]).unwrap();
+        let damping = KrausChannel::amplitude_damping(0.1).unwrap();
+        for _ in 0..5 {
+            density.apply_channel(&damping, &[0]).unwrap();
+        }
+        assert_close(density.probabilities()[1], 0.9f64.powi(5));
+        assert_close(density.trace(), 1.0);
+    }
+
+    #[test]
+    fn test_phase_damping_and_flips() {
+        let mut density = DensityMatrix::new(1);
+        density.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+        density.apply_channel(&KrausChannel::phase_damping(0.36).unwrap(), &[0]).unwrap();
+        assert_close(density.probabilities()[1], 0.5);
+        assert_close(density.matrix()[[0, 1]].re, 0.5 * 0.8);
+
+        // A phase flip with probability p scales the coherence by 1 - 2p
+        density.apply_channel(&KrausChannel::phase_flip(0.25).unwrap(), &[0]).unwrap();
+        assert_close(density.matrix()[[1, 0]].re, 0.4 * 0.5);
+
+        let mut density = DensityMatrix::new(2);
+        density.apply_channel(&KrausChannel::bit_flip(0.3).unwrap(), &[1]).unwrap();
+        assert_close(density.probabilities()[2], 0.3);
+    }
+
+    #[test]
+    fn test_measure_and_reset() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(3);
+        let mut density = DensityMatrix::from_state(&bell_state());
+        let outcome = density.measure(0, &mut rng).unwrap();
+        let expected = if outcome { 3 } else { 0 };
+        assert_close(density.probabilities()[expected], 1.0);
+        density.reset(1).unwrap();
+        density.reset(0).unwrap();
+        assert_close(density.probabilities()[0], 1.0);
+        assert_close(density.purity(), 1.0);
+    }
+
+    #[test]
+    fn test_noise_model_per_gate_type() {
+        let mut circuit = QuantumCircuit::new(2, 2);
+        circuit.h(0).unwrap().cx(0, 1).unwrap();
+        let noise = NoiseModel::new()
+            .with_gate_error("cx", KrausChannel::depolarizing(0.2, 2).unwrap())
+            .with_gate_error("h", KrausChannel::bit_flip(0.0).unwrap());
+        let backend = DensityMatrixBackend::with_noise(0, noise);
+        let density = backend.density_matrix(&circuit).unwrap();
+        assert_close(density.fidelity(&bell_state()).unwrap(), 0.85);
+
+        // A one-qubit channel after a two-qubit gate acts on both qubits
+        let noise = NoiseModel::new().with_gate_error("cx", KrausChannel::bit_flip(0.1).unwrap());
+        let density = DensityMatrixBackend::with_noise(0, noise).density_matrix(&circuit).unwrap();
+        assert_close(density.probabilities()[1] + density.probabilities()[2], 2.0 * 0.1 * 0.9);
+    }
+
+    #[test]
+    fn test_readout_error() {
+        let mut circuit = QuantumCircuit::new(1, 1);
+        circuit.measure(0, 0).unwrap();
+        let noise = NoiseModel::new().with_readout_error(ReadoutError::new(0.1, 0.0).unwrap());
+        let result = DensityMatrixBackend::with_noise(5, noise.clone()).run(&circuit, 10_000);
+        let probability = result.unwrap().probability("1");
+        assert!((probability - 0.1).abs() < 0.015, "{probability}");
+
+        // Conditions see the bit as it was read, so a misread 0 still flips qubit 1
+        let mut circuit = QuantumCircuit::new(2, 2);
+        circuit.measure(0, 0).unwrap();
+        let flip = Operation::Gate { gate: QuantumGate::PauliX, qubits: vec![1] };
+        circuit.append_conditional(flip, Condition { register: 0, value: 1 }).unwrap();
+        circuit.measure(1, 1).unwrap();
+        let result = DensityMatrixBackend::with_noise(6, noise).run(&circuit, 4_000).unwrap();
+        assert!(!result.counts.contains_key("01"), "{:?}", result.counts);
+        assert!((result.probability("11") - 0.1).abs() < 0.02, "{:?}", result.counts);
+    }
+
+    #[test]
+    fn test_noisy_ghz() {
+        let mut circuit = QuantumCircuit::new(3, 3);
+        circuit.h(0).unwrap().cx(0, 1).unwrap().cx(1, 2).unwrap();
+        for qubit in 0..3 {
+            circuit.measure(qubit, qubit).unwrap();
+        }
+        let ideal = DensityMatrixBackend::new(8).run(&circuit, 1_000).unwrap();
+        assert_eq!(ideal.counts.keys().collect::<Vec<_>>(), ["000", "111"]);
+
+        let noise = NoiseModel::new()
+            .with_default_gate_error(KrausChannel::depolarizing(0.05, 1).unwrap())
+            .with_readout_error(ReadoutError::new(0.02, 0.05).unwrap());
+        let noisy = DensityMatrixBackend::with_noise(8, noise).run(&circuit, 4_000).unwrap();
+        let correct = noisy.probability("000") + noisy.probability("111");
+        assert!(correct > 0.7 && correct < 0.95, "{:?}", noisy.counts);
+    }
+}
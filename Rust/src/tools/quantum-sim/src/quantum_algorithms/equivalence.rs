@@ -0,0 +1,432 @@
+//! Unitaries of circuits and equivalence checking
+//!
+//! Two circuits of gates are equivalent if their unitaries differ by a global phase `e^{iφ}`.
+//! On few qubits this is checked exactly, column by column: each basis state must be mapped to
+//! the same state up to a phase, and the phase must be the same for all of them. On more
+//! qubits the circuits are run on random states instead. A state drawn uniformly at random is
+//! an eigenvector of `U†V` only if `U†V` is a multiple of the identity, so any difference
+//! shows up in every stimulus, unless it is below the tolerance.
+
+use std::f64::consts::{FRAC_1_SQRT_2, TAU};
+
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::{QuantumCircuit, QuantumState, StateVectorBackend, UnitaryMatrix};
+use crate::{QuantumError, QuantumResult};
+
+/// Widest circuit whose unitary [`QuantumCircuit::to_unitary`] computes, 16 MiB of amplitudes
+const MAX_UNITARY_QUBITS: usize = 10;
+
+impl QuantumCircuit {
+    /// Unitary matrix of a circuit of gates on up to 10 qubits, with qubit 0 as the least
+    /// significant bit of the indices
+    ///
+    /// Barriers are ignored. Fails for measurements, resets, conditions and unbound
+    /// parameters.
+    pub fn to_unitary(&self) -> QuantumResult<UnitaryMatrix> {
+        if self.num_qubits() == 0 || self.num_qubits() > MAX_UNITARY_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "unitaries are computed for circuits of 1 to {MAX_UNITARY_QUBITS} qubits, the \
+                 circuit has {}",
+                self.num_qubits()
+            )));
+        }
+        let size = 1 << self.num_qubits();
+        let backend = StateVectorBackend::new(0);
+        let mut matrix = Array2::zeros((size, size));
+        for column in 0..size {
+            let output = output(&backend, self, basis_state(self.num_qubits(), column))?;
+            for (row, &(re, im)) in output.amplitudes.iter().enumerate() {
+                matrix[[row, column]] = Complex64::new(re, im);
+            }
+        }
+        Ok(UnitaryMatrix::new_unchecked(matrix))
+    }
+}
+
+/// How [`EquivalenceChecker::check`] compared two circuits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquivalenceMethod {
+    /// Exact comparison of the unitaries
+    Unitary,
+    /// Comparison of the outputs for random input states
+    RandomStimuli,
+}
+
+/// Input state that two circuits map to different states
+#[derive(Debug, Clone)]
+pub struct Counterexample {
+    /// A basis state, or the equal superposition of two of them when each is mapped to the
+    /// same state up to a different phase, for exact comparisons
+    pub input: QuantumState,
+    pub first_output: QuantumState,
+    pub second_output: QuantumState,
+    /// `|⟨first|second⟩|²` of the outputs
+    pub fidelity: f64,
+}
+
+/// Outcome of [`EquivalenceChecker::check`]
+#[derive(Debug, Clone)]
+pub struct EquivalenceResult {
+    pub method: EquivalenceMethod,
+    /// Phase `φ` in `(-π, π]` with `V = e^{iφ} U` for the unitaries `U` of the first and `V` of
+    /// the second circuit, if the circuits are equivalent
+    pub global_phase: Option<f64>,
+    pub counterexample: Option<Counterexample>,
+}
+
+impl EquivalenceResult {
+    pub fn is_equivalent(&self) -> bool {
+        self.counterexample.is_none()
+    }
+}
+
+/// When to compare unitaries and how closely
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EquivalenceConfig {
+    /// Widest circuits whose unitaries are compared, at most 10
+    pub max_exact_qubits: usize,
+    /// Random input states tried on wider circuits
+    pub stimuli: usize,
+    /// Largest distance `‖ψ₂ − e^{iφ}ψ₁‖` of the outputs `ψ₁` and `ψ₂` for a common phase `φ`
+    pub tolerance: f64,
+}
+
+impl Default for EquivalenceConfig {
+    fn default() -> Self {
+        Self { max_exact_qubits: 8, stimuli: 4, tolerance: 1e-8 }
+    }
+}
+
+/// Checker of circuits for equality up to a global phase
+pub struct EquivalenceChecker {
+    rng: Xoshiro256PlusPlus,
+    config: EquivalenceConfig,
+}
+
+impl EquivalenceChecker {
+    /// Checker whose random stimuli are drawn from a generator seeded with `seed`
+    pub fn new(seed: u64) -> Self {
+        Self::with_config(seed, EquivalenceConfig::default())
+    }
+
+    pub fn with_config(seed: u64, config: EquivalenceConfig) -> Self {
+        Self { rng: Xoshiro256PlusPlus::seed_from_u64(seed), config }
+    }
+
+    pub fn config(&self) -> EquivalenceConfig {
+        self.config
+    }
+
+    /// Compare two circuits of gates on the same number of qubits
+    ///
+    /// Fails for measurements, resets, conditions and unbound parameters, and for circuits
+    /// too wide to simulate.
+    pub fn check(
+        &mut self,
+        first: &QuantumCircuit,
+        second: &QuantumCircuit,
+    ) -> QuantumResult<EquivalenceResult> {
+        let num_qubits = first.num_qubits();
+        if second.num_qubits() != num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "circuits of {num_qubits} and {} qubits cannot be equivalent",
+                second.num_qubits()
+            )));
+        }
+        if num_qubits > StateVectorBackend::MAX_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "equivalence is checked for circuits of at most {} qubits, not {num_qubits}",
+                StateVectorBackend::MAX_QUBITS
+            )));
+        }
+        if num_qubits <= self.config.max_exact_qubits.min(MAX_UNITARY_QUBITS) {
+            self.compare_unitaries(first, second)
+        } else {
+            self.compare_stimuli(first, second)
+        }
+    }
+
+    fn compare_unitaries(
+        &self,
+        first: &QuantumCircuit,
+        second: &QuantumCircuit,
+    ) -> QuantumResult<EquivalenceResult> {
+        let num_qubits = first.num_qubits();
+        let backend = StateVectorBackend::new(0);
+        let mut phase = None;
+        for column in 0..1 << num_qubits {
+            let input = basis_state(num_qubits, column);
+            let first_output = output(&backend, first, input.clone())?;
+            let second_output = output(&backend, second, input.clone())?;
+            let column_phase = phase_of(overlap(&first_output, &second_output));
+            let common_phase = *phase.get_or_insert(column_phase);
+            let tolerance = self.config.tolerance;
+            let input = if distance(&first_output, &second_output, column_phase) > tolerance {
+                input
+            } else if distance(&first_output, &second_output, common_phase) > tolerance {
+                // Both columns keep their basis state up to different phases, which their
+                // superposition turns into a relative phase
+                let mut input = QuantumState::new(num_qubits);
+                input.amplitudes[0] = (FRAC_1_SQRT_2, 0.0);
+                input.amplitudes[column] = (FRAC_1_SQRT_2, 0.0);
+                input
+            } else {
+                continue;
+            };
+            let counterexample = counterexample(&backend, first, second, input)?;
+            return Ok(EquivalenceResult {
+                method: EquivalenceMethod::Unitary,
+                global_phase: None,
+                counterexample: Some(counterexample),
+            });
+        }
+        Ok(EquivalenceResult {
+            method: EquivalenceMethod::Unitary,
+            global_phase: phase.map(Complex64::arg),
+            counterexample: None,
+        })
+    }
+
+    fn compare_stimuli(
+        &mut self,
+        first: &QuantumCircuit,
+        second: &QuantumCircuit,
+    ) -> QuantumResult<EquivalenceResult> {
+        let backend = StateVectorBackend::new(0);
+        let mut phase = None;
+        for _ in 0..self.config.stimuli.max(1) {
+            let input = random_state(first.num_qubits(), &mut self.rng);
+            let first_output = output(&backend, first, input.clone())?;
+            let second_output = output(&backend, second, input.clone())?;
+            let overlap = overlap(&first_output, &second_output);
+            let common_phase = *phase.get_or_insert(phase_of(overlap));
+            if distance(&first_output, &second_output, common_phase) > self.config.tolerance {
+                let fidelity = overlap.norm_sqr();
+                return Ok(EquivalenceResult {
+                    method: EquivalenceMethod::RandomStimuli,
+                    global_phase: None,
+                    counterexample: Some(Counterexample {
+                        input,
+                        first_output,
+                        second_output,
+                        fidelity,
+                    }),
+                });
+            }
+        }
+        Ok(EquivalenceResult {
+            method: EquivalenceMethod::RandomStimuli,
+            global_phase: phase.map(Complex64::arg),
+            counterexample: None,
+        })
+    }
+}
+
+/// State `|index⟩` of the computational basis
+fn basis_state(num_qubits: usize, index: usize) -> QuantumState {
+    let mut state = QuantumState::new(num_qubits);
+    state.amplitudes[0] = (0.0, 0.0);
+    state.amplitudes[index] = (1.0, 0.0);
+    state
+}
+
+/// State of independent standard normal amplitudes, normalized, which is uniformly
+/// distributed over the unit sphere
+fn random_state<R: Rng + ?Sized>(num_qubits: usize, rng: &mut R) -> QuantumState {
+    let mut state = QuantumState::new(num_qubits);
+    for amplitude in &mut state.amplitudes {
+        // Box-Muller transform of two uniform samples into two normal ones
+        let radius = (-2.0 * (1.0 - rng.random::<f64>()).ln()).sqrt();
+        let angle = TAU * rng.random::<f64>();
+        *amplitude = (radius * angle.cos(), radius * angle.sin());
+    }
+    let norm = state.amplitudes.iter().map(|(re, im)| re * re + im * im).sum::<f64>().sqrt();
+    for amplitude in &mut state.amplitudes {
+        *amplitude = (amplitude.0 / norm, amplitude.1 / norm);
+    }
+    state
+}
+
+fn output(
+    backend: &StateVectorBackend,
+    circuit: &QuantumCircuit,
+    mut state: QuantumState,
+) -> QuantumResult<QuantumState> {
+    backend.evolve(circuit, &mut state)?;
+    Ok(state)
+}
+
+/// `⟨a|b⟩`
+fn overlap(a: &QuantumState, b: &QuantumState) -> Complex64 {
+    a.amplitudes
+        .iter()
+        .zip(&b.amplitudes)
+        .map(|(&(a_re, a_im), &(b_re, b_im))| {
+            Complex64::new(a_re, -a_im) * Complex64::new(b_re, b_im)
+        })
+        .sum()
+}
+
+/// Unit complex number of the phase of `overlap`, 1 for no overlap
+fn phase_of(overlap: Complex64) -> Complex64 {
+    if overlap.norm() > 0.0 { overlap / overlap.norm() } else { Complex64::new(1.0, 0.0) }
+}
+
+/// `‖b − phase·a‖`
+///
+/// Unlike `1 − |⟨a|b⟩|`, which is quadratic in the difference of the states, the distance
+/// is linear in it, so a tolerance on it catches small rotations.
+fn distance(a: &QuantumState, b: &QuantumState, phase: Complex64) -> f64 {
+    a.amplitudes
+        .iter()
+        .zip(&b.amplitudes)
+        .map(|(&(a_re, a_im), &(b_re, b_im))| {
+            (Complex64::new(b_re, b_im) - phase * Complex64::new(a_re, a_im)).norm_sqr()
+        })
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn counterexample(
+    backend: &StateVectorBackend,
+    first: &QuantumCircuit,
+    second: &QuantumCircuit,
+    input: QuantumState,
+) -> QuantumResult<Counterexample> {
+    let first_output = output(backend, first, input.clone())?;
+    let second_output = output(backend, second, input.clone())?;
+    let fidelity = overlap(&first_output, &second_output).norm_sqr();
+    Ok(Counterexample { input, first_output, second_output, fidelity })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::quantum_algorithms::QuantumGate;
+
+    fn ghz_ladder(num_qubits: usize) -> QuantumCircuit {
+        let mut circuit = QuantumCircuit::new(num_qubits, 0);
+        circuit.h(0).unwrap();
+        for qubit in 1..num_qubits {
+            circuit.cx(qubit - 1, qubit).unwrap();
+        }
+        circuit
+    }
+
+    /// The same state as [`ghz_ladder`] with every CNOT controlled by qubit 0, which is not
+    /// the same unitary
+    fn ghz_star(num_qubits: usize) -> QuantumCircuit {
+        let mut circuit = QuantumCircuit::new(num_qubits, 0);
+        circuit.h(0).unwrap();
+        for qubit in 1..num_qubits {
+            circuit.cx(0, qubit).unwrap();
+        }
+        circuit
+    }
+
+    #[test]
+    fn test_unitary() {
+        let mut circuit = QuantumCircuit::new(2, 0);
+        circuit.h(1).unwrap().barrier(&[]).unwrap().cz(0, 1).unwrap();
+        let unitary = circuit.to_unitary().unwrap();
+        // Qubit 1 is the most significant bit, so H acts on the left factor
+        let identity = Array2::<Complex64>::eye(2);
+        let mut expected = ndarray::linalg::kron(&QuantumGate::<f64>::Hadamard.matrix(), &identity);
+        expected.row_mut(3).mapv_inplace(|element| -element);
+        assert!((unitary.matrix() - &expected).iter().all(|element| element.norm() < 1e-12));
+
+        let mut measured = QuantumCircuit::new(1, 1);
+        measured.measure(0, 0).unwrap();
+        assert!(measured.to_unitary().is_err());
+        assert!(ghz_ladder(11).to_unitary().is_err());
+    }
+
+    #[test]
+    fn test_exact_equivalence() {
+        let mut checker = EquivalenceChecker::new(0);
+        let mut hzh = QuantumCircuit::new(1, 0);
+        hzh.h(0).unwrap().z(0).unwrap().h(0).unwrap();
+        let mut x = QuantumCircuit::new(1, 0);
+        x.x(0).unwrap();
+        let result = checker.check(&hzh, &x).unwrap();
+        assert_eq!(result.method, EquivalenceMethod::Unitary);
+        assert!(result.global_phase.unwrap().abs() < 1e-12);
+
+        // P(θ) = e^{iθ/2} Rz(θ)
+        let mut rz = QuantumCircuit::new(1, 0);
+        rz.rz(0.8, 0).unwrap();
+        let mut phase = QuantumCircuit::new(1, 0);
+        phase.phase(0.8, 0).unwrap();
+        let result = checker.check(&rz, &phase).unwrap();
+        assert!((result.global_phase.unwrap() - 0.4).abs() < 1e-12);
+
+        // Z keeps both basis states, so only their superposition tells it from the identity
+        let mut z = QuantumCircuit::new(1, 0);
+        z.z(0).unwrap();
+        let result = checker.check(&z, &QuantumCircuit::new(1, 0)).unwrap();
+        let counterexample = result.counterexample.unwrap();
+        assert_eq!(counterexample.input.amplitudes, [(FRAC_1_SQRT_2, 0.0), (FRAC_1_SQRT_2, 0.0)]);
+        assert!(counterexample.fidelity < 1e-12);
+
+        let result = checker.check(&ghz_ladder(3), &ghz_star(3)).unwrap();
+        assert!(!result.is_equivalent() && result.global_phase.is_none());
+        let counterexample = result.counterexample.unwrap();
+        let ones = counterexample.input.amplitudes.iter().filter(|&&(re, _)| re == 1.0);
+        assert_eq!(ones.count(), 1);
+        assert!(counterexample.fidelity < 0.5);
+        let backend = StateVectorBackend::new(0);
+        let expected = output(&backend, &ghz_star(3), counterexample.input).unwrap();
+        assert_eq!(counterexample.second_output.amplitudes, expected.amplitudes);
+    }
+
+    #[test]
+    fn test_random_stimuli() {
+        let config = EquivalenceConfig { max_exact_qubits: 4, ..Default::default() };
+        let mut checker = EquivalenceChecker::with_config(1, config);
+        let mut first = ghz_ladder(12);
+        first.rz(PI / 3.0, 5).unwrap();
+        let mut second = ghz_ladder(12);
+        second.cz(3, 7).unwrap().phase(PI / 3.0, 5).unwrap().cz(7, 3).unwrap();
+        let result = checker.check(&first, &second).unwrap();
+        assert_eq!(result.method, EquivalenceMethod::RandomStimuli);
+        assert!((result.global_phase.unwrap() - PI / 6.0).abs() < 1e-9);
+
+        let result = checker.check(&ghz_ladder(12), &ghz_star(12)).unwrap();
+        let counterexample = result.counterexample.unwrap();
+        assert_eq!(counterexample.input.qubit_count, 12);
+        assert!(counterexample.fidelity < 0.9);
+    }
+
+    #[test]
+    fn test_small_rotation() {
+        // Rz(ε) moves the states by about ε/2 but their overlap only by about ε²/8, which is
+        // below the tolerance
+        let angle = 1e-4;
+        for (num_qubits, method) in
+            [(3, EquivalenceMethod::Unitary), (12, EquivalenceMethod::RandomStimuli)]
+        {
+            let mut rotated = ghz_ladder(num_qubits);
+            rotated.rz(angle, num_qubits - 1).unwrap();
+            let mut checker = EquivalenceChecker::new(2);
+            let result = checker.check(&ghz_ladder(num_qubits), &rotated).unwrap();
+            assert_eq!(result.method, method);
+            let counterexample = result.counterexample.unwrap();
+            assert!(1.0 - counterexample.fidelity < angle * angle);
+        }
+    }
+
+    #[test]
+    fn test_check_errors() {
+        let mut checker = EquivalenceChecker::new(0);
+        assert!(checker.check(&ghz_ladder(2), &ghz_ladder(3)).is_err());
+        let mut measured = QuantumCircuit::new(2, 1);
+        measured.h(0).unwrap().measure(0, 0).unwrap();
+        assert!(checker.check(&ghz_ladder(2), &measured).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Stabilizer codes
+
+use crate::quantum_algorithms::{Pauli, PauliString};
+use crate::{QuantumError, QuantumResult};
+
+/// Code whose logical qubit is the joint +1 eigenspace of commuting Pauli stabilizers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StabilizerCode {
+    name: String,
+    stabilizers: Vec<PauliString>,
+    logical_x: PauliString,
+    logical_z: PauliString,
+    distance: usize,
+}
+
+impl StabilizerCode {
+    /// Code on `logical_x.num_qubits()` data qubits encoding one logical qubit
+    ///
+    /// The stabilizers must commute with each other and with both logical operators, and the
+    /// logical operators must anticommute.
+    pub fn new(
+        name: &str,
+        stabilizers: Vec<PauliString>,
+        logical_x: PauliString,
+        logical_z: PauliString,
+        distance: usize,
+    ) -> QuantumResult<Self> {
+        let num_qubits = logical_x.num_qubits();
+        let invalid = |message: String| {
+            Err(QuantumError::SimulationError(format!("invalid code `{name}`: {message}")))
+        };
+        let operators = stabilizers.iter().chain([&logical_x, &logical_z]);
+        if let Some(operator) = operators.into_iter().find(|p| p.num_qubits() != num_qubits) {
+            return invalid(format!("`{operator}` does not act on {num_qubits} qubits"));
+        }
+        for (i, stabilizer) in stabilizers.iter().enumerate() {
+            for other in stabilizers[i + 1..].iter().chain([&logical_x, &logical_z]) {
+                if !stabilizer.commutes_with(other) {
+                    return invalid(format!("`{stabilizer}` and `{other}` anticommute"));
+                }
+            }
+        }
+        if logical_x.commutes_with(&logical_z) {
+            return invalid("the logical operators commute".to_string());
+        }
+        Ok(Self { name: name.to_string(), stabilizers, logical_x, logical_z, distance })
+    }
+
+    fn from_strings(
+        name: &str,
+        stabilizers: &[&str],
+        logical_x: &str,
+        logical_z: &str,
+        distance: usize,
+    ) -> Self {
+        let parse = |text: &str| text.parse::<PauliString>().expect("valid Pauli string");
+        let stabilizers = stabilizers.iter().map(|text| parse(text)).collect();
+        Self::new(name, stabilizers, parse(logical_x), parse(logical_z), distance)
+            .expect("standard codes are valid")
+    }
+
+    /// Three-qubit repetition code that corrects one X error
+    pub fn bit_flip() -> Self {
+        Self::from_strings("bit flip", &["ZZI", "IZZ"], "XXX", "ZII", 1)
+    }
+
+    /// Three-qubit repetition code in the X basis that corrects one Z error
+    pub fn phase_flip() -> Self {
+        Self::from_strings("phase flip", &["XXI", "IXX"], "ZZZ", "XII", 1)
+    }
+
+    /// Shor's nine-qubit code, a bit-flip code inside a phase-flip code
+    pub fn shor() -> Self {
+        Self::from_strings(
+            "Shor",
+            &[
+                "ZZIIIIIII",
+                "IZZIIIIII",
+                "IIIZZIIII",
+                "IIIIZZIII",
+                "IIIIIIZZI",
+                "IIIIIIIZZ",
+                "XXXXXXIII",
+                "IIIXXXXXX",
+            ],
+            "ZIIZIIZII",
+            "XXXIIIIII",
+            3,
+        )
+    }
+
+    /// Steane's seven-qubit code, built from the classical Hamming code
+    pub fn steane() -> Self {
+        Self::from_strings(
+            "Steane",
+            &["IIIXXXX", "IXXIIXX", "XIXIXIX", "IIIZZZZ", "IZZIIZZ", "ZIZIZIZ"],
+            "XXXIIII",
+            "ZZZIIII",
+            3,
+        )
+    }
+
+    /// Rotated surface code on a `distance × distance` grid of data qubits
+    ///
+    /// Qubit `r * distance + c` sits at row `r` and column `c`. Each face of the grid holds a
+    /// weight-4 stabilizer, alternating between X and Z like a checkerboard, and weight-2
+    /// stabilizers close the top and bottom boundaries with X and the left and right ones with
+    /// Z. The logical X runs down the first column and the logical Z along the first row.
+    pub fn rotated_surface(distance: usize) -> QuantumResult<Self> {
+        if distance < 3 || distance.is_multiple_of(2) {
+            return Err(QuantumError::SimulationError(format!(
+                "the rotated surface code needs an odd distance of at least 3, got {distance}"
+            )));
+        }
+        let d = distance as isize;
+        let mut stabilizers = Vec::with_capacity(distance * distance - 1);
+        // Face (r, c) touches the qubits at rows r and r + 1 and columns c and c + 1
+        for r in -1..d {
+            for c in -1..d {
+                let is_x = (r + c).rem_euclid(2) == 0;
+                let bulk = r >= 0 && r < d - 1 && c >= 0 && c < d - 1;
+                let horizontal_boundary = (r == -1 || r == d - 1) && c >= 0 && c < d - 1;
+                let vertical_boundary = (c == -1 || c == d - 1) && r >= 0 && r < d - 1;
+                if !(bulk || horizontal_boundary && is_x || vertical_boundary && !is_x) {
+                    continue;
+                }
+                let mut stabilizer = PauliString::identity(distance * distance);
+                for (row, column) in [(r, c), (r, c + 1), (r + 1, c), (r + 1, c + 1)] {
+                    if (0..d).contains(&row) && (0..d).contains(&column) {
+                        let factor = if is_x { Pauli::X } else { Pauli::Z };
+                        stabilizer.set((row * d + column) as usize, factor);
+                    }
+                }
+                stabilizers.push(stabilizer);
+            }
+        }
+        let mut logical_x = PauliString::identity(distance * distance);
+        let mut logical_z = PauliString::identity(distance * distance);
+        for i in 0..distance {
+            logical_x.set(i * distance, Pauli::X);
+            logical_z.set(i, Pauli::Z);
+        }
+        Self::new(
+            &format!("rotated surface d={distance}"),
+            stabilizers,
+            logical_x,
+            logical_z,
+            distance,
+        )
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of data qubits
+    pub fn num_qubits(&self) -> usize {
+        self.logical_x.num_qubits()
+    }
+
+    pub fn stabilizers(&self) -> &[PauliString] {
+        &self.stabilizers
+    }
+
+    pub fn logical_x(&self) -> &PauliString {
+        &self.logical_x
+    }
+
+    pub fn logical_z(&self) -> &PauliString {
+        &self.logical_z
+    }
+
+    /// Smallest weight of an error that changes the logical state without being detected
+    pub fn distance(&self) -> usize {
+        self.distance
+    }
+
+    /// Which stabilizers anticommute with `error`
+    pub fn syndrome(&self, error: &PauliString) -> Vec<bool> {
+        self.stabilizers.iter().map(|stabilizer| !stabilizer.commutes_with(error)).collect()
+    }
+
+    /// Whether every stabilizer is made only of X or only of Z
+    pub fn is_css(&self) -> bool {
+        self.stabilizers.iter().all(|stabilizer| stabilizer.is_x_type() || stabilizer.is_z_type())
+    }
+}
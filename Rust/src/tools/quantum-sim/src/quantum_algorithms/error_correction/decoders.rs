@@ -0,0 +1,306 @@
+//! Decoders from syndromes to corrections
+
+use std::collections::{HashMap, VecDeque};
+
+use super::StabilizerCode;
+use crate::quantum_algorithms::{Pauli, PauliString};
+use crate::{QuantumError, QuantumResult};
+
+/// Lookup tables are built for codes with at most this many independent stabilizers
+const MAX_LOOKUP_STABILIZERS: usize = 16;
+
+/// Exact matching is limited to this many flipped stabilizers of one type
+const MAX_DEFECTS: usize = 20;
+
+/// Algorithm that turns a syndrome into a correction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decoder {
+    /// Table of a lowest-weight error for every syndrome
+    Lookup,
+    /// Minimum-weight perfect matching of the flipped stabilizers
+    ///
+    /// Needs a CSS code where every data qubit is checked by at most two stabilizers of each
+    /// type, like the repetition, Shor and surface codes.
+    Matching,
+}
+
+/// Decoder prepared for one code
+pub(super) enum PreparedDecoder {
+    Lookup(HashMap<Vec<bool>, PauliString>),
+    /// Graphs of the Z-type stabilizers, which detect X errors, and of the X-type ones
+    Matching(MatchingGraph, MatchingGraph),
+}
+
+impl PreparedDecoder {
+    pub(super) fn new(code: &StabilizerCode, decoder: Decoder) -> QuantumResult<Self> {
+        match decoder {
+            Decoder::Lookup => lookup_table(code).map(PreparedDecoder::Lookup),
+            Decoder::Matching => {
+                if !code.is_css() {
+                    return Err(QuantumError::SimulationError(format!(
+                        "matching needs a CSS code, but `{}` is not one",
+                        code.name()
+                    )));
+                }
+                Ok(PreparedDecoder::Matching(
+                    MatchingGraph::new(code, Pauli::X)?,
+                    MatchingGraph::new(code, Pauli::Z)?,
+                ))
+            }
+        }
+    }
+
+    pub(super) fn decode(
+        &self,
+        code: &StabilizerCode,
+        syndrome: &[bool],
+    ) -> QuantumResult<PauliString> {
+        match self {
+            PreparedDecoder::Lookup(table) => table.get(syndrome).cloned().ok_or_else(|| {
+                QuantumError::SimulationError(
+                    "no error of the code has the syndrome, as stabilizers are dependent"
+                        .to_string(),
+                )
+            }),
+            PreparedDecoder::Matching(x_graph, z_graph) => {
+                let mut correction = PauliString::identity(code.num_qubits());
+                x_graph.decode(syndrome, &mut correction)?;
+                z_graph.decode(syndrome, &mut correction)?;
+                Ok(correction)
+            }
+        }
+    }
+}
+
+/// Map from every syndrome to a lowest-weight error that causes it
+fn lookup_table(code: &StabilizerCode) -> QuantumResult<HashMap<Vec<bool>, PauliString>> {
+    let rank = stabilizer_rank(code);
+    if rank > MAX_LOOKUP_STABILIZERS {
+        return Err(QuantumError::SimulationError(format!(
+            "a lookup table for `{}` would have 2^{rank} entries, use matching instead",
+            code.name()
+        )));
+    }
+    let syndromes = 1usize << rank;
+    let mut table = HashMap::with_capacity(syndromes);
+    let mut error = PauliString::identity(code.num_qubits());
+    for weight in 0..=code.num_qubits() {
+        fill_table(code, &mut table, &mut error, 0, weight, syndromes);
+        if table.len() == syndromes {
+            break;
+        }
+    }
+    Ok(table)
+}
+
+/// Add every error of `weight` more non-identity factors on qubits `start..` to `table`, until
+/// it has `syndromes` entries
+fn fill_table(
+    code: &StabilizerCode,
+    table: &mut HashMap<Vec<bool>, PauliString>,
+    error: &mut PauliString,
+    start: usize,
+    weight: usize,
+    syndromes: usize,
+) {
+    if table.len() == syndromes {
+        return;
+    }
+    if weight == 0 {
+        table.entry(code.syndrome(error)).or_insert_with(|| error.clone());
+        return;
+    }
+    for qubit in start..=code.num_qubits() - weight {
+        // Y last, as it is an X and a Z error at once
+        for pauli in [Pauli::X, Pauli::Z, Pauli::Y] {
+            error.set(qubit, pauli);
+            fill_table(code, table, error, qubit + 1, weight - 1, syndromes);
+        }
+        error.set(qubit, Pauli::I);
+    }
+}
+
+/// Number of independent stabilizers, the rank of their binary symplectic matrix
+fn stabilizer_rank(code: &StabilizerCode) -> usize {
+    let n = code.num_qubits();
+    let mut rows: Vec<Vec<bool>> = code
+        .stabilizers()
+        .iter()
+        .map(|stabilizer| {
+            let mut row = vec![false; 2 * n];
+            for qubit in stabilizer.support() {
+                let pauli = stabilizer.get(qubit);
+                row[qubit] = matches!(pauli, Pauli::X | Pauli::Y);
+                row[n + qubit] = matches!(pauli, Pauli::Z | Pauli::Y);
+            }
+            row
+        })
+        .collect();
+    let mut rank = 0;
+    for column in 0..2 * n {
+        let Some(pivot) = (rank..rows.len()).find(|&row| rows[row][column]) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        for row in 0..rows.len() {
+            if row != rank && rows[row][column] {
+                let pivot_row = rows[rank].clone();
+                rows[row].iter_mut().zip(pivot_row).for_each(|(bit, pivot_bit)| *bit ^= pivot_bit);
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+/// Stabilizers of one type as the nodes of a graph whose edges are data qubits
+///
+/// A qubit joins the two stabilizers that detect an error on it, or one stabilizer and the
+/// boundary node if only one does. An error chain flips the stabilizers at its ends, so
+/// pairing the flipped stabilizers along shortest paths gives a most likely correction.
+pub(super) struct MatchingGraph {
+    /// Index in the code of the stabilizer of each node, the boundary being the last node
+    checks: Vec<usize>,
+    /// Qubits on a shortest path between every pair of nodes, if one exists
+    paths: Vec<Vec<Option<Vec<usize>>>>,
+    /// Error the graph corrects
+    correction: Pauli,
+}
+
+impl MatchingGraph {
+    /// Graph of the stabilizers that detect `correction` errors
+    fn new(code: &StabilizerCode, correction: Pauli) -> QuantumResult<Self> {
+        let detects = |stabilizer: &PauliString| match correction {
+            Pauli::X => stabilizer.is_z_type(),
+            _ => stabilizer.is_x_type(),
+        };
+        let checks: Vec<usize> = (0..code.stabilizers().len())
+            .filter(|&index| detects(&code.stabilizers()[index]))
+            .collect();
+        let boundary = checks.len();
+
+        // Edges of each node as (neighbour, qubit)
+        let mut edges = vec![Vec::new(); boundary + 1];
+        for qubit in 0..code.num_qubits() {
+            let nodes: Vec<usize> = (0..boundary)
+                .filter(|&node| code.stabilizers()[checks[node]].get(qubit) != Pauli::I)
+                .collect();
+            let (a, b) = match nodes[..] {
+                [] => continue,
+                [a] => (a, boundary),
+                [a, b] => (a, b),
+                _ => {
+                    return Err(QuantumError::SimulationError(format!(
+                        "qubit {qubit} of `{}` is checked by {} stabilizers of one type, \
+                         matching needs at most two",
+                        code.name(),
+                        nodes.len()
+                    )));
+                }
+            };
+            edges[a].push((b, qubit));
+            edges[b].push((a, qubit));
+        }
+
+        let paths = (0..=boundary).map(|source| shortest_paths(&edges, source)).collect();
+        Ok(Self { checks, paths, correction })
+    }
+
+    /// Multiply `correction` by the correction for the flipped stabilizers of the graph
+    fn decode(&self, syndrome: &[bool], correction: &mut PauliString) -> QuantumResult<()> {
+        let defects: Vec<usize> =
+            (0..self.checks.len()).filter(|&node| syndrome[self.checks[node]]).collect();
+        if defects.len() > MAX_DEFECTS {
+            return Err(QuantumError::SimulationError(format!(
+                "{} flipped stabilizers are too many to match exactly",
+                defects.len()
+            )));
+        }
+        let boundary = self.checks.len();
+        let distance = |a: usize, b: usize| self.paths[a][b].as_ref().map(|path| path.len());
+
+        // cost[mask] is the weight of the best matching of the defects in mask, each either
+        // paired with another or with the boundary
+        let full = (1usize << defects.len()) - 1;
+        let mut cost = vec![None; full + 1];
+        let mut choice = vec![None; full + 1];
+        cost[0] = Some(0);
+        for mask in 1..=full {
+            let first = mask.trailing_zeros() as usize;
+            let rest = mask & !(1 << first);
+            let mut best: Option<(usize, Option<usize>)> = None;
+            let partners = (0..defects.len()).filter(|&j| rest & (1 << j) != 0).map(Some);
+            for partner in [None].into_iter().chain(partners) {
+                let (target, remaining) = match partner {
+                    None => (boundary, rest),
+                    Some(j) => (defects[j], rest & !(1 << j)),
+                };
+                let (Some(edge), Some(remaining)) =
+                    (distance(defects[first], target), cost[remaining])
+                else {
+                    continue;
+                };
+                if best.is_none_or(|(weight, _)| edge + remaining < weight) {
+                    best = Some((edge + remaining, partner));
+                }
+            }
+            if let Some((weight, partner)) = best {
+                cost[mask] = Some(weight);
+                choice[mask] = Some(partner);
+            }
+        }
+        if cost[full].is_none() {
+            return Err(QuantumError::SimulationError(
+                "the flipped stabilizers cannot be matched".to_string(),
+            ));
+        }
+
+        let mut mask = full;
+        while mask != 0 {
+            let first = mask.trailing_zeros() as usize;
+            let partner = choice[mask].expect("matched masks have a choice");
+            let target = partner.map_or(boundary, |j| defects[j]);
+            for &qubit in self.paths[defects[first]][target].as_ref().expect("path exists") {
+                let mut flip = PauliString::identity(correction.num_qubits());
+                flip.set(qubit, self.correction);
+                correction.multiply(&flip);
+            }
+            mask &= !(1 << first);
+            if let Some(j) = partner {
+                mask &= !(1 << j);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Qubits on a shortest path from `source` to every node, by breadth-first search
+fn shortest_paths(edges: &[Vec<(usize, usize)>], source: usize) -> Vec<Option<Vec<usize>>> {
+    let mut previous: Vec<Option<(usize, usize)>> = vec![None; edges.len()];
+    let mut visited = vec![false; edges.len()];
+    visited[source] = true;
+    let mut queue = VecDeque::from([source]);
+    while let Some(node) = queue.pop_front() {
+        for &(neighbour, qubit) in &edges[node] {
+            if !visited[neighbour] {
+                visited[neighbour] = true;
+                previous[neighbour] = Some((node, qubit));
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    (0..edges.len())
+        .map(|target| {
+            if !visited[target] {
+                return None;
+            }
+            let mut path = Vec::new();
+            let mut node = target;
+            while let Some((before, qubit)) = previous[node] {
+                path.push(qubit);
+                node = before;
+            }
+            Some(path)
+        })
+        .collect()
+}
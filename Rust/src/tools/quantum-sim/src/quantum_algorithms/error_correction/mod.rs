@@ -0,0 +1,363 @@
+//! Quantum error correction with stabilizer codes
+//!
+//! A [`StabilizerCode`] protects one logical qubit in the joint +1 eigenspace of its
+//! stabilizers. Errors flip some of them, and the flipped ones, the syndrome, are measured
+//! through one ancilla per stabilizer. A [`Decoder`] turns the syndrome into a Pauli correction.
+//! Encoding, syndrome extraction and correction are Clifford operations, so codes run on a
+//! [`StabilizerTableau`] and logical error rates can be estimated over many noisy shots.
+
+mod codes;
+mod decoders;
+
+pub use codes::StabilizerCode;
+pub use decoders::Decoder;
+use decoders::PreparedDecoder;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::{Pauli, PauliChannel, PauliString, QuantumCircuit, QuantumGate, StabilizerTableau};
+use crate::{QuantumError, QuantumResult};
+
+/// Quantum error correction with a stabilizer code and a decoder
+///
+/// The code uses the first qubits of a register: its data qubits, followed by one ancilla per
+/// stabilizer.
+pub struct QuantumErrorCorrection {
+    code: StabilizerCode,
+    decoder: Decoder,
+    prepared: PreparedDecoder,
+    syndrome_circuit: QuantumCircuit,
+}
+
+impl QuantumErrorCorrection {
+    pub fn new(code: StabilizerCode, decoder: Decoder) -> QuantumResult<Self> {
+        let prepared = PreparedDecoder::new(&code, decoder)?;
+        let syndrome_circuit = syndrome_circuit(&code)?;
+        Ok(Self { code, decoder, prepared, syndrome_circuit })
+    }
+
+    pub fn code(&self) -> &StabilizerCode {
+        &self.code
+    }
+
+    pub fn decoder(&self) -> Decoder {
+        self.decoder
+    }
+
+    /// Number of data qubits and ancillas
+    pub fn num_qubits(&self) -> usize {
+        self.syndrome_circuit.num_qubits()
+    }
+
+    /// Circuit that measures every stabilizer into the classical register `syndrome`
+    ///
+    /// Each ancilla is reset, then collects the parity of its stabilizer: Z-type stabilizers
+    /// with CNOTs from the data qubits, others with controlled Paulis from the ancilla between
+    /// two Hadamards. Bit `i` is set if stabilizer `i` has the eigenvalue -1.
+    pub fn syndrome_circuit(&self) -> &QuantumCircuit {
+        &self.syndrome_circuit
+    }
+
+    /// Correction for `syndrome`, a Pauli on the data qubits
+    pub fn decode(&self, syndrome: &[bool]) -> QuantumResult<PauliString> {
+        if syndrome.len() != self.code.stabilizers().len() {
+            return Err(QuantumError::SimulationError(format!(
+                "the syndrome has {} bits, but `{}` has {} stabilizers",
+                syndrome.len(),
+                self.code.name(),
+                self.code.stabilizers().len()
+            )));
+        }
+        self.prepared.decode(&self.code, syndrome)
+    }
+
+    /// Put the data qubits into the logical |0⟩, whatever their state
+    ///
+    /// Measuring the stabilizers projects onto the code space up to the signs in the syndrome,
+    /// which the correction fixes, and measuring the logical Z selects |0⟩ or |1⟩.
+    pub fn encode<R: Rng + ?Sized>(
+        &self,
+        tableau: &mut StabilizerTableau,
+        rng: &mut R,
+    ) -> QuantumResult<()> {
+        self.correct_errors(tableau, rng)?;
+        if tableau.measure_pauli(self.code.logical_z(), rng)? {
+            tableau.apply_pauli(self.code.logical_x())?;
+        }
+        Ok(())
+    }
+
+    /// Measure the syndrome of the encoded state in `tableau` and apply its correction,
+    /// returning the syndrome
+    pub fn correct_errors<R: Rng + ?Sized>(
+        &self,
+        tableau: &mut StabilizerTableau,
+        rng: &mut R,
+    ) -> QuantumResult<Vec<bool>> {
+        let syndrome = tableau.run_circuit(&self.syndrome_circuit, rng)?;
+        tableau.apply_pauli(&self.decode(&syndrome)?)?;
+        Ok(syndrome)
+    }
+
+    /// Fraction of `shots` in which errors drawn from `noise` on every data qubit leave a
+    /// logical error after one round of correction
+    ///
+    /// The logical qubit starts maximally entangled with a reference qubit, so a change of the
+    /// logical `X ⊗ X` or `Z ⊗ Z` reveals every logical error.
+    pub fn logical_error_rate(
+        &self,
+        noise: PauliChannel,
+        shots: usize,
+        seed: u64,
+    ) -> QuantumResult<f64> {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let (prepared, checks) = self.prepare_with_reference(&mut rng)?;
+        let mut failures = 0;
+        for _ in 0..shots {
+            let mut error = PauliString::identity(self.code.num_qubits());
+            for qubit in 0..self.code.num_qubits() {
+                error.set(qubit, noise.sample(&mut rng));
+            }
+            if self.fails(&prepared, &checks, &error, &mut rng)? {
+                failures += 1;
+            }
+        }
+        Ok(failures as f64 / shots as f64)
+    }
+
+    /// Encoded state entangled with a reference qubit after the ancillas, and the logical
+    /// `X ⊗ X` and `Z ⊗ Z` that stabilize it
+    fn prepare_with_reference<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> QuantumResult<(StabilizerTableau, [PauliString; 2])> {
+        let reference = self.num_qubits();
+        let mut tableau = StabilizerTableau::new(reference + 1);
+        self.encode(&mut tableau, rng)?;
+        tableau.apply_gate(&QuantumGate::Hadamard, &[reference])?;
+        let logical_x = self.code.logical_x();
+        for qubit in logical_x.support() {
+            let pauli = match logical_x.get(qubit) {
+                Pauli::X => QuantumGate::PauliX,
+                Pauli::Y => QuantumGate::PauliY,
+                _ => QuantumGate::PauliZ,
+            };
+            tableau.apply_gate(&QuantumGate::controlled(pauli, 1), &[reference, qubit])?;
+        }
+        let mut x_check = logical_x.extended(reference + 1);
+        x_check.set(reference, Pauli::X);
+        let mut z_check = self.code.logical_z().extended(reference + 1);
+        z_check.set(reference, Pauli::Z);
+        Ok((tableau, [x_check, z_check]))
+    }
+
+    /// Whether correcting `error` on the prepared state changes its logical state
+    fn fails<R: Rng + ?Sized>(
+        &self,
+        prepared: &StabilizerTableau,
+        checks: &[PauliString; 2],
+        error: &PauliString,
+        rng: &mut R,
+    ) -> QuantumResult<bool> {
+        let mut tableau = prepared.clone();
+        tableau.apply_pauli(error)?;
+        self.correct_errors(&mut tableau, rng)?;
+        for check in checks {
+            if tableau.measure_pauli(check, rng)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+fn syndrome_circuit(code: &StabilizerCode) -> QuantumResult<QuantumCircuit> {
+    let stabilizers = code.stabilizers();
+    let mut circuit = QuantumCircuit::empty();
+    circuit.add_quantum_register("data", code.num_qubits())?;
+    let ancillas = circuit.add_quantum_register("ancilla", stabilizers.len())?;
+    circuit.add_classical_register("syndrome", stabilizers.len())?;
+    for (index, stabilizer) in stabilizers.iter().enumerate() {
+        let ancilla = ancillas + index;
+        circuit.reset(ancilla)?;
+        if stabilizer.is_z_type() {
+            for qubit in stabilizer.support() {
+                circuit.cx(qubit, ancilla)?;
+            }
+        } else {
+            circuit.h(ancilla)?;
+            for qubit in stabilizer.support() {
+                match stabilizer.get(qubit) {
+                    Pauli::X => circuit.cx(ancilla, qubit)?,
+                    Pauli::Y => circuit.controlled(QuantumGate::PauliY, &[ancilla], &[qubit])?,
+                    _ => circuit.cz(ancilla, qubit)?,
+                };
+            }
+            circuit.h(ancilla)?;
+        }
+        circuit.measure(ancilla, index)?;
+    }
+    Ok(circuit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantum_algorithms::{Backend, StateVectorBackend};
+
+    fn distance_three_codes() -> Vec<(StabilizerCode, Decoder)> {
+        let surface = StabilizerCode::rotated_surface(3).unwrap();
+        vec![
+            (StabilizerCode::shor(), Decoder::Lookup),
+            (StabilizerCode::shor(), Decoder::Matching),
+            (StabilizerCode::steane(), Decoder::Lookup),
+            (surface.clone(), Decoder::Lookup),
+            (surface, Decoder::Matching),
+        ]
+    }
+
+    fn single_error(num_qubits: usize, qubit: usize, pauli: Pauli) -> PauliString {
+        let mut error = PauliString::identity(num_qubits);
+        error.set(qubit, pauli);
+        error
+    }
+
+    #[test]
+    fn test_codes() {
+        let surface = StabilizerCode::rotated_surface(5).unwrap();
+        assert_eq!((surface.num_qubits(), surface.stabilizers().len()), (25, 24));
+        assert!(surface.stabilizers().iter().all(|s| [2, 4].contains(&s.weight())));
+        assert_eq!(StabilizerCode::rotated_surface(3).unwrap().stabilizers().len(), 8);
+        assert!(StabilizerCode::rotated_surface(4).is_err());
+        assert_eq!(StabilizerCode::steane().logical_z().to_string(), "ZZZIIII");
+
+        let anticommuting = ["XI", "ZI"].map(|p| p.parse().unwrap()).to_vec();
+        let code = StabilizerCode::new(
+            "bad",
+            anticommuting,
+            "XX".parse().unwrap(),
+            "ZZ".parse().unwrap(),
+            1,
+        );
+        assert!(code.unwrap_err().to_string().contains("anticommute"));
+
+        let error = QuantumErrorCorrection::new(StabilizerCode::steane(), Decoder::Matching);
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_syndrome_circuit() {
+        // Bit-flip code on |000⟩ with a Y error on qubit 2, and phase-flip code on |+++⟩ with
+        // a Z error on qubit 0
+        for (code, plus_basis, error, expected) in [
+            (StabilizerCode::bit_flip(), false, Pauli::Y, [false, true]),
+            (StabilizerCode::phase_flip(), true, Pauli::Z, [true, false]),
+        ] {
+            let qec = QuantumErrorCorrection::new(code, Decoder::Lookup).unwrap();
+            let qubit = if error == Pauli::Y { 2 } else { 0 };
+            let mut circuit = QuantumCircuit::empty();
+            circuit.add_quantum_register("data", 3).unwrap();
+            circuit.add_quantum_register("ancilla", 2).unwrap();
+            circuit.add_classical_register("syndrome", 2).unwrap();
+            if plus_basis {
+                for data in 0..3 {
+                    circuit.h(data).unwrap();
+                }
+            }
+            let gate = match error {
+                Pauli::Y => QuantumGate::PauliY,
+                _ => QuantumGate::PauliZ,
+            };
+            circuit.gate(gate, &[qubit]).unwrap();
+            for instruction in qec.syndrome_circuit().instructions() {
+                circuit.push(instruction.clone()).unwrap();
+            }
+            let result = StateVectorBackend::new(0).run(&circuit, 16).unwrap();
+            let expected_bits = crate::quantum_algorithms::bitstring(&expected);
+            assert_eq!(result.probability(&expected_bits), 1.0, "{:?}", result.counts);
+        }
+    }
+
+    #[test]
+    fn test_single_errors() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        for (code, decoder) in distance_three_codes() {
+            let qec = QuantumErrorCorrection::new(code, decoder).unwrap();
+            let (prepared, checks) = qec.prepare_with_reference(&mut rng).unwrap();
+            let n = qec.code().num_qubits();
+            for qubit in 0..n {
+                for pauli in [Pauli::X, Pauli::Y, Pauli::Z] {
+                    let error = single_error(n, qubit, pauli);
+                    let failed = qec.fails(&prepared, &checks, &error, &mut rng).unwrap();
+                    assert!(!failed, "{} {decoder:?}: {error}", qec.code().name());
+                }
+            }
+        }
+
+        // The repetition codes only correct one type of error
+        for (code, corrected) in
+            [(StabilizerCode::bit_flip(), Pauli::X), (StabilizerCode::phase_flip(), Pauli::Z)]
+        {
+            for decoder in [Decoder::Lookup, Decoder::Matching] {
+                let qec = QuantumErrorCorrection::new(code.clone(), decoder).unwrap();
+                let (prepared, checks) = qec.prepare_with_reference(&mut rng).unwrap();
+                for qubit in 0..3 {
+                    for pauli in [Pauli::X, Pauli::Z] {
+                        let error = single_error(3, qubit, pauli);
+                        let failed = qec.fails(&prepared, &checks, &error, &mut rng).unwrap();
+                        assert_eq!(
+                            failed,
+                            pauli != corrected,
+                            "{} {decoder:?}: {error}",
+                            code.name()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_surface_code_matching() {
+        // Distance 5 corrects every pair of X or Z errors
+        let code = StabilizerCode::rotated_surface(5).unwrap();
+        let qec = QuantumErrorCorrection::new(code, Decoder::Matching).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(2);
+        let (prepared, checks) = qec.prepare_with_reference(&mut rng).unwrap();
+        for pauli in [Pauli::X, Pauli::Z] {
+            for first in 0..25 {
+                for second in first + 1..25 {
+                    let mut error = single_error(25, first, pauli);
+                    error.set(second, pauli);
+                    let failed = qec.fails(&prepared, &checks, &error, &mut rng).unwrap();
+                    assert!(!failed, "{error}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_logical_error_rates() {
+        // Three flips per bit flip code fail with probability 3p² - 2p³
+        let qec =
+            QuantumErrorCorrection::new(StabilizerCode::bit_flip(), Decoder::Matching).unwrap();
+        let rate = qec.logical_error_rate(PauliChannel::bit_flip(0.1).unwrap(), 4_000, 3).unwrap();
+        assert!((rate - 0.028).abs() < 0.01, "{rate}");
+        let rate =
+            qec.logical_error_rate(PauliChannel::phase_flip(0.1).unwrap(), 1_000, 3).unwrap();
+        // Z errors go undetected and an odd number of them flips the logical X
+        assert!((rate - 0.244).abs() < 0.05, "{rate}");
+
+        // Below threshold, distance 3 turns a physical error rate p into about c p²
+        let noise = PauliChannel::depolarizing(0.02).unwrap();
+        for (code, decoder) in distance_three_codes() {
+            let qec = QuantumErrorCorrection::new(code, decoder).unwrap();
+            assert_eq!(
+                qec.logical_error_rate(PauliChannel::depolarizing(0.0).unwrap(), 50, 4).unwrap(),
+                0.0
+            );
+            let rate = qec.logical_error_rate(noise, 4_000, 4).unwrap();
+            assert!(rate < 0.015, "{} {decoder:?}: {rate}", qec.code().name());
+        }
+    }
+}
@@ -0,0 +1,615 @@
+//! Quantum gates and their matrices
+//!
+//! Matrices act on the qubits a gate is applied to in little-endian order: the first qubit
+//! is the least significant bit of the row and column index. A CNOT applied to
+//! `[control, target]` therefore flips the target for indices 1 and 3.
+
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4};
+use std::ops::Neg;
+
+use ndarray::Array2;
+use num_complex::Complex64;
+
+use crate::{QuantumError, QuantumResult};
+
+/// Largest deviation from the identity accepted in `U†U` for a user-supplied unitary
+const UNITARY_TOLERANCE: f64 = 1e-9;
+
+/// Quantum gates
+///
+/// Gates applied to a [`QuantumState`] have numeric angles. The gates of a [`QuantumCircuit`]
+/// have angles of type [`ParameterExpr`], which may depend on parameters bound later.
+///
+/// Controlled gates take their control qubits first, then their targets.
+///
+/// [`QuantumState`]: super::QuantumState
+/// [`QuantumCircuit`]: super::QuantumCircuit
+/// [`ParameterExpr`]: super::ParameterExpr
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuantumGate<P = f64> {
+    Hadamard,
+    PauliX,
+    PauliY,
+    PauliZ,
+    CNOT,
+    Phase(P),
+    /// The general single-qubit gate `U(theta, phi, lambda)` of OpenQASM
+    Rotation(P, P, P), // theta, phi, lambda
+    /// `diag(1, i)`
+    S,
+    /// `diag(1, -i)`
+    Sdg,
+    /// `diag(1, e^(iπ/4))`
+    T,
+    /// `diag(1, e^(-iπ/4))`
+    Tdg,
+    /// Square root of X, `(1 + i)/2 I + (1 - i)/2 X`
+    SX,
+    SXdg,
+    /// Rotation by an angle around the X axis, `exp(-i θ X / 2)`
+    Rx(P),
+    Ry(P),
+    Rz(P),
+    CZ,
+    SWAP,
+    /// Swaps two qubits and multiplies |01⟩ and |10⟩ by `i`
+    ISwap,
+    CRx(P),
+    CRy(P),
+    CRz(P),
+    /// Doubly controlled X
+    Toffoli,
+    /// Controlled SWAP
+    Fredkin,
+    /// `gate` controlled by `controls` qubits
+    Controlled {
+        controls: usize,
+        gate: Box<QuantumGate<P>>,
+    },
+    Unitary(UnitaryMatrix),
+}
+
+impl<P> QuantumGate<P> {
+    /// `gate` controlled by `controls` qubits
+    pub fn controlled(gate: QuantumGate<P>, controls: usize) -> Self {
+        QuantumGate::Controlled { controls, gate: Box::new(gate) }
+    }
+
+    /// Number of qubits the gate acts on
+    pub fn num_qubits(&self) -> usize {
+        match self {
+            QuantumGate::CNOT
+            | QuantumGate::CZ
+            | QuantumGate::SWAP
+            | QuantumGate::ISwap
+            | QuantumGate::CRx(_)
+            | QuantumGate::CRy(_)
+            | QuantumGate::CRz(_) => 2,
+            QuantumGate::Toffoli | QuantumGate::Fredkin => 3,
+            QuantumGate::Controlled { controls, gate } => controls + gate.num_qubits(),
+            QuantumGate::Unitary(matrix) => matrix.num_qubits(),
+            _ => 1,
+        }
+    }
+
+    /// Short lowercase name of the gate
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuantumGate::Hadamard => "h",
+            QuantumGate::PauliX => "x",
+            QuantumGate::PauliY => "y",
+            QuantumGate::PauliZ => "z",
+            QuantumGate::CNOT => "cx",
+            QuantumGate::Phase(_) => "p",
+            QuantumGate::Rotation(..) => "u",
+            QuantumGate::S => "s",
+            QuantumGate::Sdg => "sdg",
+            QuantumGate::T => "t",
+            QuantumGate::Tdg => "tdg",
+            QuantumGate::SX => "sx",
+            QuantumGate::SXdg => "sxdg",
+            QuantumGate::Rx(_) => "rx",
+            QuantumGate::Ry(_) => "ry",
+            QuantumGate::Rz(_) => "rz",
+            QuantumGate::CZ => "cz",
+            QuantumGate::SWAP => "swap",
+            QuantumGate::ISwap => "iswap",
+            QuantumGate::CRx(_) => "crx",
+            QuantumGate::CRy(_) => "cry",
+            QuantumGate::CRz(_) => "crz",
+            QuantumGate::Toffoli => "ccx",
+            QuantumGate::Fredkin => "cswap",
+            QuantumGate::Controlled { .. } => "ctrl",
+            QuantumGate::Unitary(_) => "unitary",
+        }
+    }
+
+    /// Angles of the gate
+    pub fn params(&self) -> Vec<&P> {
+        match self {
+            QuantumGate::Phase(theta)
+            | QuantumGate::Rx(theta)
+            | QuantumGate::Ry(theta)
+            | QuantumGate::Rz(theta)
+            | QuantumGate::CRx(theta)
+            | QuantumGate::CRy(theta)
+            | QuantumGate::CRz(theta) => vec![theta],
+            QuantumGate::Rotation(theta, phi, lambda) => vec![theta, phi, lambda],
+            QuantumGate::Controlled { gate, .. } => gate.params(),
+            _ => vec![],
+        }
+    }
+
+    /// The same gate with every angle replaced by `f(angle)`
+    pub fn try_map_params<Q, E>(
+        &self,
+        mut f: impl FnMut(&P) -> Result<Q, E>,
+    ) -> Result<QuantumGate<Q>, E> {
+        self.try_map_params_with(&mut f)
+    }
+
+    fn try_map_params_with<Q, E>(
+        &self,
+        f: &mut impl FnMut(&P) -> Result<Q, E>,
+    ) -> Result<QuantumGate<Q>, E> {
+        Ok(match self {
+            QuantumGate::Hadamard => QuantumGate::Hadamard,
+            QuantumGate::PauliX => QuantumGate::PauliX,
+            QuantumGate::PauliY => QuantumGate::PauliY,
+            QuantumGate::PauliZ => QuantumGate::PauliZ,
+            QuantumGate::CNOT => QuantumGate::CNOT,
+            QuantumGate::Phase(theta) => QuantumGate::Phase(f(theta)?),
+            QuantumGate::Rotation(theta, phi, lambda) => {
+                QuantumGate::Rotation(f(theta)?, f(phi)?, f(lambda)?)
+            }
+            QuantumGate::S => QuantumGate::S,
+            QuantumGate::Sdg => QuantumGate::Sdg,
+            QuantumGate::T => QuantumGate::T,
+            QuantumGate::Tdg => QuantumGate::Tdg,
+            QuantumGate::SX => QuantumGate::SX,
+            QuantumGate::SXdg => QuantumGate::SXdg,
+            QuantumGate::Rx(theta) => QuantumGate::Rx(f(theta)?),
+            QuantumGate::Ry(theta) => QuantumGate::Ry(f(theta)?),
+            QuantumGate::Rz(theta) => QuantumGate::Rz(f(theta)?),
+            QuantumGate::CZ => QuantumGate::CZ,
+            QuantumGate::SWAP => QuantumGate::SWAP,
+            QuantumGate::ISwap => QuantumGate::ISwap,
+            QuantumGate::CRx(theta) => QuantumGate::CRx(f(theta)?),
+            QuantumGate::CRy(theta) => QuantumGate::CRy(f(theta)?),
+            QuantumGate::CRz(theta) => QuantumGate::CRz(f(theta)?),
+            QuantumGate::Toffoli => QuantumGate::Toffoli,
+            QuantumGate::Fredkin => QuantumGate::Fredkin,
+            QuantumGate::Controlled { controls, gate } => QuantumGate::Controlled {
+                controls: *controls,
+                gate: Box::new(gate.try_map_params_with(f)?),
+            },
+            QuantumGate::Unitary(matrix) => QuantumGate::Unitary(matrix.clone()),
+        })
+    }
+
+    /// The gate as a gate on its targets with control qubits split off, if it has any
+    ///
+    /// Returns the number of controls and the target gate.
+    pub fn split_controls(&self) -> Option<(usize, QuantumGate<P>)>
+    where
+        P: Clone,
+    {
+        let (controls, gate) = match self {
+            QuantumGate::CNOT => (1, QuantumGate::PauliX),
+            QuantumGate::CZ => (1, QuantumGate::PauliZ),
+            QuantumGate::CRx(theta) => (1, QuantumGate::Rx(theta.clone())),
+            QuantumGate::CRy(theta) => (1, QuantumGate::Ry(theta.clone())),
+            QuantumGate::CRz(theta) => (1, QuantumGate::Rz(theta.clone())),
+            QuantumGate::Toffoli => (2, QuantumGate::PauliX),
+            QuantumGate::Fredkin => (1, QuantumGate::SWAP),
+            QuantumGate::Controlled { controls, gate } => match gate.split_controls() {
+                Some((inner, gate)) => (controls + inner, gate),
+                None => (*controls, (**gate).clone()),
+            },
+            _ => return None,
+        };
+        Some((controls, gate))
+    }
+}
+
+impl<P: Clone + Neg<Output = P>> QuantumGate<P> {
+    /// Gate that undoes this gate
+    pub fn inverse(&self) -> QuantumGate<P> {
+        match self {
+            QuantumGate::Hadamard
+            | QuantumGate::PauliX
+            | QuantumGate::PauliY
+            | QuantumGate::PauliZ
+            | QuantumGate::CNOT
+            | QuantumGate::CZ
+            | QuantumGate::SWAP
+            | QuantumGate::Toffoli
+            | QuantumGate::Fredkin => self.clone(),
+            QuantumGate::Phase(theta) => QuantumGate::Phase(-theta.clone()),
+            QuantumGate::Rotation(theta, phi, lambda) => {
+                QuantumGate::Rotation(-theta.clone(), -lambda.clone(), -phi.clone())
+            }
+            QuantumGate::S => QuantumGate::Sdg,
+            QuantumGate::Sdg => QuantumGate::S,
+            QuantumGate::T => QuantumGate::Tdg,
+            QuantumGate::Tdg => QuantumGate::T,
+            QuantumGate::SX => QuantumGate::SXdg,
+            QuantumGate::SXdg => QuantumGate::SX,
+            QuantumGate::Rx(theta) => QuantumGate::Rx(-theta.clone()),
+            QuantumGate::Ry(theta) => QuantumGate::Ry(-theta.clone()),
+            QuantumGate::Rz(theta) => QuantumGate::Rz(-theta.clone()),
+            QuantumGate::CRx(theta) => QuantumGate::CRx(-theta.clone()),
+            QuantumGate::CRy(theta) => QuantumGate::CRy(-theta.clone()),
+            QuantumGate::CRz(theta) => QuantumGate::CRz(-theta.clone()),
+            QuantumGate::ISwap => {
+                let matrix = UnitaryMatrix { matrix: QuantumGate::<f64>::ISwap.matrix() };
+                QuantumGate::Unitary(matrix.adjoint())
+            }
+            QuantumGate::Controlled { controls, gate } => {
+                QuantumGate::Controlled { controls: *controls, gate: Box::new(gate.inverse()) }
+            }
+            QuantumGate::Unitary(matrix) => QuantumGate::Unitary(matrix.adjoint()),
+        }
+    }
+}
+
+impl QuantumGate {
+    /// Unitary matrix of the gate, see the [module documentation](self) for the qubit order
+    pub fn matrix(&self) -> Array2<Complex64> {
+        let c = |re: f64, im: f64| Complex64::new(re, im);
+        let one = c(1.0, 0.0);
+        let zero = c(0.0, 0.0);
+        let i = c(0.0, 1.0);
+        let single = |elements: [Complex64; 4]| Array2::from_shape_vec((2, 2), elements.to_vec());
+
+        if let Some((controls, gate)) = self.split_controls() {
+            return controlled_matrix(&gate.matrix(), controls);
+        }
+        let matrix = match *self {
+            QuantumGate::Hadamard => {
+                let h = c(FRAC_1_SQRT_2, 0.0);
+                single([h, h, h, -h])
+            }
+            QuantumGate::PauliX => single([zero, one, one, zero]),
+            QuantumGate::PauliY => single([zero, -i, i, zero]),
+            QuantumGate::PauliZ => single([one, zero, zero, -one]),
+            QuantumGate::Phase(theta) => single([one, zero, zero, Complex64::cis(theta)]),
+            QuantumGate::Rotation(theta, phi, lambda) => {
+                let (sin, cos) = (theta / 2.0).sin_cos();
+                single([
+                    c(cos, 0.0),
+                    -Complex64::cis(lambda) * sin,
+                    Complex64::cis(phi) * sin,
+                    Complex64::cis(phi + lambda) * cos,
+                ])
+            }
+            QuantumGate::S => single([one, zero, zero, i]),
+            QuantumGate::Sdg => single([one, zero, zero, -i]),
+            QuantumGate::T => single([one, zero, zero, Complex64::cis(FRAC_PI_4)]),
+            QuantumGate::Tdg => single([one, zero, zero, Complex64::cis(-FRAC_PI_4)]),
+            QuantumGate::SX | QuantumGate::SXdg => {
+                let (a, b) = (c(0.5, 0.5), c(0.5, -0.5));
+                if *self == QuantumGate::SX { single([a, b, b, a]) } else { single([b, a, a, b]) }
+            }
+            QuantumGate::Rx(theta) => {
+                let (sin, cos) = (theta / 2.0).sin_cos();
+                single([c(cos, 0.0), c(0.0, -sin), c(0.0, -sin), c(cos, 0.0)])
+            }
+            QuantumGate::Ry(theta) => {
+                let (sin, cos) = (theta / 2.0).sin_cos();
+                single([c(cos, 0.0), c(-sin, 0.0), c(sin, 0.0), c(cos, 0.0)])
+            }
+            QuantumGate::Rz(theta) => {
+                single([Complex64::cis(-theta / 2.0), zero, zero, Complex64::cis(theta / 2.0)])
+            }
+            QuantumGate::SWAP | QuantumGate::ISwap => {
+                let phase = if *self == QuantumGate::SWAP { one } else { i };
+                let mut matrix = Array2::zeros((4, 4));
+                matrix[[0, 0]] = one;
+                matrix[[1, 2]] = phase;
+                matrix[[2, 1]] = phase;
+                matrix[[3, 3]] = one;
+                return matrix;
+            }
+            QuantumGate::Unitary(ref matrix) => return matrix.matrix().clone(),
+            QuantumGate::CNOT
+            | QuantumGate::CZ
+            | QuantumGate::CRx(_)
+            | QuantumGate::CRy(_)
+            | QuantumGate::CRz(_)
+            | QuantumGate::Toffoli
+            | QuantumGate::Fredkin
+            | QuantumGate::Controlled { .. } => unreachable!("controlled gates are split above"),
+        };
+        matrix.unwrap()
+    }
+}
+
+/// Matrix of `matrix` controlled by `controls` qubits, which come before its targets
+pub fn controlled_matrix(matrix: &Array2<Complex64>, controls: usize) -> Array2<Complex64> {
+    let target_dim = matrix.nrows();
+    let control_mask = (1 << controls) - 1;
+    let dim = target_dim << controls;
+    let mut result = Array2::eye(dim);
+    for row in 0..target_dim {
+        for col in 0..target_dim {
+            result[[row << controls | control_mask, col << controls | control_mask]] =
+                matrix[[row, col]];
+        }
+    }
+    result
+}
+
+/// Unitary `2^k × 2^k` matrix acting on `k` qubits
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitaryMatrix {
+    matrix: Array2<Complex64>,
+}
+
+impl UnitaryMatrix {
+    /// Check that `matrix` is a unitary matrix on one or more qubits
+    pub fn new(matrix: Array2<Complex64>) -> QuantumResult<Self> {
+        let (rows, cols) = matrix.dim();
+        if rows != cols || rows < 2 || !rows.is_power_of_two() {
+            return Err(QuantumError::SimulationError(format!(
+                "a gate matrix must be 2^k × 2^k with k ≥ 1, not {rows} × {cols}"
+            )));
+        }
+        let product = matrix.t().mapv(|element| element.conj()).dot(&matrix);
+        let deviation = (product - Array2::<Complex64>::eye(rows))
+            .iter()
+            .map(|element| element.norm())
+            .fold(0.0, f64::max);
+        if deviation.is_nan() || deviation > UNITARY_TOLERANCE {
+            return Err(QuantumError::SimulationError(format!(
+                "gate matrix is not unitary, U†U differs from the identity by {deviation:e}"
+            )));
+        }
+        Ok(Self { matrix })
+    }
+
+    /// Matrix that is unitary by construction, such as a product of gates, whose check would
+    /// be too costly
+    pub(crate) fn new_unchecked(matrix: Array2<Complex64>) -> Self {
+        Self { matrix }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.matrix.nrows().trailing_zeros() as usize
+    }
+
+    pub fn matrix(&self) -> &Array2<Complex64> {
+        &self.matrix
+    }
+
+    /// Conjugate transpose, the inverse of a unitary matrix
+    pub fn adjoint(&self) -> UnitaryMatrix {
+        UnitaryMatrix { matrix: self.matrix.t().mapv(|element| element.conj()) }
+    }
+}
+
+/// Check that `qubits` are distinct qubits of a `qubit_count`-qubit register and as many as
+/// `gate` acts on
+pub(crate) fn check_qubits<P>(
+    gate: &QuantumGate<P>,
+    qubits: &[usize],
+    qubit_count: usize,
+) -> QuantumResult<()> {
+    if qubits.len() != gate.num_qubits() {
+        return Err(QuantumError::SimulationError(format!(
+            "gate `{}` acts on {} qubits, but {} were given",
+            gate.name(),
+            gate.num_qubits(),
+            qubits.len()
+        )));
+    }
+    for (i, &qubit) in qubits.iter().enumerate() {
+        if qubit >= qubit_count {
+            return Err(QuantumError::SimulationError(format!(
+                "qubit {qubit} is out of range for {qubit_count} qubits"
+            )));
+        }
+        if qubits[..i].contains(&qubit) {
+            return Err(QuantumError::SimulationError(format!(
+                "gate `{}` is applied to qubit {qubit} twice",
+                gate.name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::super::QuantumState;
+    use super::*;
+
+    fn all_gates() -> Vec<QuantumGate> {
+        vec![
+            QuantumGate::Hadamard,
+            QuantumGate::PauliX,
+            QuantumGate::PauliY,
+            QuantumGate::PauliZ,
+            QuantumGate::CNOT,
+            QuantumGate::Phase(0.3),
+            QuantumGate::Rotation(0.4, 1.1, -0.7),
+            QuantumGate::S,
+            QuantumGate::Sdg,
+            QuantumGate::T,
+            QuantumGate::Tdg,
+            QuantumGate::SX,
+            QuantumGate::SXdg,
+            QuantumGate::Rx(0.9),
+            QuantumGate::Ry(-1.3),
+            QuantumGate::Rz(2.1),
+            QuantumGate::CZ,
+            QuantumGate::SWAP,
+            QuantumGate::ISwap,
+            QuantumGate::CRx(0.5),
+            QuantumGate::CRy(0.6),
+            QuantumGate::CRz(0.7),
+            QuantumGate::Toffoli,
+            QuantumGate::Fredkin,
+            QuantumGate::controlled(QuantumGate::Rotation(0.2, 0.3, 0.4), 2),
+            QuantumGate::controlled(QuantumGate::ISwap, 1),
+        ]
+    }
+
+    /// State with distinct, non-trivial amplitudes on every basis state
+    fn test_state(qubit_count: usize) -> QuantumState {
+        let mut state = QuantumState::new(qubit_count);
+        for (i, amplitude) in state.amplitudes.iter_mut().enumerate() {
+            *amplitude = ((i as f64 + 1.0).sin(), (i as f64 * 0.7).cos());
+        }
+        let norm = state.probabilities().iter().sum::<f64>().sqrt();
+        for amplitude in &mut state.amplitudes {
+            amplitude.0 /= norm;
+            amplitude.1 /= norm;
+        }
+        state
+    }
+
+    /// `matrix` applied to the qubits `qubits` of `state`, computed on the full state vector
+    fn apply_reference(
+        state: &QuantumState,
+        matrix: &Array2<Complex64>,
+        qubits: &[usize],
+    ) -> Vec<Complex64> {
+        let amplitudes: Vec<Complex64> =
+            state.amplitudes.iter().map(|&(re, im)| Complex64::new(re, im)).collect();
+        let local = |index: usize| {
+            qubits
+                .iter()
+                .enumerate()
+                .fold(0, |local, (bit, &qubit)| local | ((index >> qubit) & 1) << bit)
+        };
+        let mask: usize = qubits.iter().map(|&qubit| 1 << qubit).sum();
+        (0..amplitudes.len())
+            .map(|row| {
+                (0..amplitudes.len())
+                    .filter(|&col| col & !mask == row & !mask)
+                    .map(|col| matrix[[local(row), local(col)]] * amplitudes[col])
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn assert_close(state: &QuantumState, expected: &[Complex64]) {
+        for (&(re, im), expected) in state.amplitudes.iter().zip(expected) {
+            let actual = Complex64::new(re, im);
+            assert!((actual - expected).norm() < 1e-12, "{state:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn test_matrices_are_unitary() {
+        for gate in all_gates() {
+            let matrix = gate.matrix();
+            assert_eq!(matrix.nrows(), 1 << gate.num_qubits(), "{gate:?}");
+            assert!(UnitaryMatrix::new(matrix).is_ok(), "{gate:?}");
+        }
+    }
+
+    #[test]
+    fn test_application_matches_matrix() {
+        for gate in all_gates() {
+            // Spread the qubits out and reverse them to catch ordering mistakes
+            let qubits: Vec<usize> = (0..gate.num_qubits()).rev().map(|q| 2 * q).collect();
+            let qubit_count = 2 * gate.num_qubits();
+            let mut state = test_state(qubit_count);
+            let expected = apply_reference(&state, &gate.matrix(), &qubits);
+            state.apply_gate(&gate, &qubits).unwrap();
+            assert_close(&state, &expected);
+        }
+    }
+
+    #[test]
+    fn test_inverse() {
+        for gate in all_gates() {
+            let product = gate.matrix().dot(&gate.inverse().matrix());
+            let identity = Array2::<Complex64>::eye(product.nrows());
+            let deviation = (product - identity).iter().map(|e| e.norm()).fold(0.0, f64::max);
+            assert!(deviation < 1e-12, "{gate:?}");
+        }
+    }
+
+    #[test]
+    fn test_rotation_is_openqasm_u() {
+        let h = QuantumGate::Rotation(PI / 2.0, 0.0, PI).matrix();
+        let x = QuantumGate::Rotation(PI, 0.0, PI).matrix();
+        for (a, b) in h.iter().zip(QuantumGate::Hadamard.matrix().iter()) {
+            assert!((a - b).norm() < 1e-12);
+        }
+        for (a, b) in x.iter().zip(QuantumGate::PauliX.matrix().iter()) {
+            assert!((a - b).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_sx_squares_to_x() {
+        let sx = QuantumGate::SX.matrix();
+        for (a, b) in sx.dot(&sx).iter().zip(QuantumGate::PauliX.matrix().iter()) {
+            assert!((a - b).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_toffoli_and_fredkin() {
+        for input in 0..8 {
+            let mut state = QuantumState::new(3);
+            state.amplitudes[0] = (0.0, 0.0);
+            state.amplitudes[input] = (1.0, 0.0);
+            state.apply_gate(&QuantumGate::Toffoli, &[0, 1, 2]).unwrap();
+            let expected = if input & 0b011 == 0b011 { input ^ 0b100 } else { input };
+            assert_eq!(state.amplitudes[expected], (1.0, 0.0));
+
+            let mut state = QuantumState::new(3);
+            state.amplitudes[0] = (0.0, 0.0);
+            state.amplitudes[input] = (1.0, 0.0);
+            state.apply_gate(&QuantumGate::Fredkin, &[0, 1, 2]).unwrap();
+            let swapped = input & 1 | (input >> 1 & 1) << 2 | (input >> 2 & 1) << 1;
+            let expected = if input & 1 == 1 { swapped } else { input };
+            assert_eq!(state.amplitudes[expected], (1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_multi_controlled_x() {
+        let gate = QuantumGate::controlled(QuantumGate::PauliX, 3);
+        assert_eq!(gate.num_qubits(), 4);
+        let mut state = QuantumState::new(4);
+        for qubit in 0..3 {
+            state.apply_gate(&QuantumGate::PauliX, &[qubit]).unwrap();
+        }
+        state.apply_gate(&gate, &[0, 1, 2, 3]).unwrap();
+        assert_eq!(state.amplitudes[0b1111], (1.0, 0.0));
+        state.apply_gate(&QuantumGate::PauliX, &[1]).unwrap();
+        state.apply_gate(&gate, &[0, 1, 2, 3]).unwrap();
+        assert_eq!(state.amplitudes[0b1101], (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_user_unitary() {
+        let h = UnitaryMatrix::new(QuantumGate::Hadamard.matrix()).unwrap();
+        let mut state = QuantumState::new(2);
+        state.apply_gate(&QuantumGate::Unitary(h), &[1]).unwrap();
+        assert!((state.probabilities()[2] - 0.5).abs() < 1e-12);
+
+        let not_unitary = Array2::from_elem((2, 2), Complex64::new(1.0, 0.0));
+        assert!(UnitaryMatrix::new(not_unitary).is_err());
+        assert!(UnitaryMatrix::new(Array2::eye(3)).is_err());
+        assert!(UnitaryMatrix::new(Array2::eye(1)).is_err());
+        assert_eq!(UnitaryMatrix::new(Array2::eye(8)).unwrap().num_qubits(), 3);
+    }
+
+    #[test]
+    fn test_split_controls() {
+        let gate = QuantumGate::controlled(QuantumGate::CRz(0.5), 2);
+        assert_eq!(gate.num_qubits(), 4);
+        assert_eq!(gate.params(), vec![&0.5]);
+        assert_eq!(gate.split_controls(), Some((3, QuantumGate::Rz(0.5))));
+        assert_eq!(QuantumGate::<f64>::Hadamard.split_controls(), None);
+    }
+}
@@ -0,0 +1,283 @@
+//! Hamiltonians as weighted sums of Pauli strings
+//!
+//! Qubit Hamiltonians of molecules and cost functions of optimization problems are sums of
+//! tensor products of Paulis. Their expectation values are computed exactly from a state
+//! vector, or estimated from measurements the way hardware would: every group of terms whose
+//! Paulis agree on each qubit is measured in one basis, and each term is the average parity of
+//! the measured bits on its support.
+
+use std::fmt;
+
+use rand::Rng;
+
+use super::{Pauli, PauliString, QuantumGate, QuantumState};
+use crate::{QuantumError, QuantumResult};
+
+/// Hermitian operator `Σ cᵢ Pᵢ` with real coefficients `cᵢ` and Pauli strings `Pᵢ`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PauliSum {
+    num_qubits: usize,
+    terms: Vec<(f64, PauliString)>,
+}
+
+impl PauliSum {
+    /// Zero operator on `num_qubits` qubits
+    pub fn new(num_qubits: usize) -> Self {
+        Self { num_qubits, terms: Vec::new() }
+    }
+
+    /// Sum of `(coefficient, Pauli string)` terms, such as `(0.5, "XZ")`, where the first
+    /// character acts on qubit 0
+    pub fn from_terms(terms: &[(f64, &str)]) -> QuantumResult<Self> {
+        let Some(&(_, first)) = terms.first() else {
+            return Err(QuantumError::ParsingError(
+                "a Pauli sum needs at least one term".to_string(),
+            ));
+        };
+        let mut sum = Self::new(first.chars().count());
+        for &(coefficient, pauli) in terms {
+            sum.add_term(coefficient, pauli.parse()?)?;
+        }
+        Ok(sum)
+    }
+
+    /// Add `coefficient * pauli`, merging it with an existing term of the same string
+    pub fn add_term(&mut self, coefficient: f64, pauli: PauliString) -> QuantumResult<&mut Self> {
+        if pauli.num_qubits() != self.num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "`{pauli}` does not act on {} qubits",
+                self.num_qubits
+            )));
+        }
+        match self.terms.iter_mut().find(|(_, existing)| *existing == pauli) {
+            Some((existing, _)) => *existing += coefficient,
+            None => self.terms.push((coefficient, pauli)),
+        }
+        Ok(self)
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn terms(&self) -> &[(f64, PauliString)] {
+        &self.terms
+    }
+
+    /// `⟨ψ|H|ψ⟩` computed from the amplitudes of `state`
+    pub fn expectation(&self, state: &QuantumState) -> QuantumResult<f64> {
+        self.check_state(state)?;
+        let amplitudes = &state.amplitudes;
+        let mut total = 0.0;
+        for (coefficient, pauli) in &self.terms {
+            let (x_mask, z_mask, y_count) = masks(pauli);
+            // P|i⟩ = i^{#Y} (-1)^{|i ∧ z|} |i ⊕ x⟩, so ⟨ψ|P|ψ⟩ = Σ ψ*(i ⊕ x) ψ(i) times that
+            // phase, whose imaginary part cancels for a Hermitian P
+            let mut value = (0.0, 0.0);
+            for (index, &(real, imag)) in amplitudes.iter().enumerate() {
+                let (other_real, other_imag) = amplitudes[index ^ x_mask];
+                let sign = if (index & z_mask).count_ones().is_multiple_of(2) { 1.0 } else { -1.0 };
+                value.0 += sign * (other_real * real + other_imag * imag);
+                value.1 += sign * (other_real * imag - other_imag * real);
+            }
+            let real_part = match y_count % 4 {
+                0 => value.0,
+                1 => -value.1,
+                2 => -value.0,
+                _ => value.1,
+            };
+            total += coefficient * real_part;
+        }
+        Ok(total)
+    }
+
+    /// Estimate of `⟨ψ|H|ψ⟩` from `shots` measurements of every group of terms that share a
+    /// measurement basis
+    pub fn estimate<R: Rng + ?Sized>(
+        &self,
+        state: &QuantumState,
+        shots: usize,
+        rng: &mut R,
+    ) -> QuantumResult<f64> {
+        self.check_state(state)?;
+        if shots == 0 {
+            return Err(QuantumError::SimulationError(
+                "estimating an expectation value needs at least one shot".to_string(),
+            ));
+        }
+        let mut total: f64 = self
+            .terms
+            .iter()
+            .filter(|(_, pauli)| pauli.weight() == 0)
+            .map(|(coefficient, _)| coefficient)
+            .sum();
+        for (basis, group) in self.measurement_groups() {
+            // Rotate every measured qubit so that its Pauli becomes Z
+            let mut rotated = state.clone();
+            for (qubit, pauli) in basis.iter().enumerate() {
+                match pauli {
+                    Pauli::X => rotated.apply_gate(&QuantumGate::Hadamard, &[qubit])?,
+                    Pauli::Y => {
+                        rotated.apply_gate(&QuantumGate::Sdg, &[qubit])?;
+                        rotated.apply_gate(&QuantumGate::Hadamard, &[qubit])?;
+                    }
+                    Pauli::I | Pauli::Z => {}
+                }
+            }
+            let histogram = rotated.sample(shots, rng)?;
+            for term in group {
+                let (coefficient, pauli) = &self.terms[term];
+                let support: usize = pauli.support().map(|qubit| 1 << qubit).sum();
+                let parity: i64 = histogram
+                    .iter()
+                    .map(|(&outcome, &count)| {
+                        let sign =
+                            if (outcome & support).count_ones().is_multiple_of(2) { 1 } else { -1 };
+                        sign * count as i64
+                    })
+                    .sum();
+                total += coefficient * parity as f64 / shots as f64;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Terms other than the identity grouped greedily by qubit-wise commutation, with the
+    /// measurement basis of each group
+    fn measurement_groups(&self) -> Vec<(Vec<Pauli>, Vec<usize>)> {
+        let mut groups: Vec<(Vec<Pauli>, Vec<usize>)> = Vec::new();
+        for (term, (_, pauli)) in self.terms.iter().enumerate() {
+            if pauli.weight() == 0 {
+                continue;
+            }
+            let fits = |basis: &[Pauli]| {
+                pauli
+                    .support()
+                    .all(|qubit| basis[qubit] == Pauli::I || basis[qubit] == pauli.get(qubit))
+            };
+            let index = match groups.iter().position(|(basis, _)| fits(basis)) {
+                Some(index) => index,
+                None => {
+                    groups.push((vec![Pauli::I; self.num_qubits], Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            let (basis, terms) = &mut groups[index];
+            for qubit in pauli.support() {
+                basis[qubit] = pauli.get(qubit);
+            }
+            terms.push(term);
+        }
+        groups
+    }
+
+    fn check_state(&self, state: &QuantumState) -> QuantumResult<()> {
+        if state.qubit_count != self.num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "a Pauli sum on {} qubits cannot act on a {}-qubit state",
+                self.num_qubits, state.qubit_count
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Bits flipped by `pauli`, bits whose sign it reads, and its number of Y factors
+fn masks(pauli: &PauliString) -> (usize, usize, usize) {
+    let mut masks = (0, 0, 0);
+    for qubit in pauli.support() {
+        let pauli = pauli.get(qubit);
+        if matches!(pauli, Pauli::X | Pauli::Y) {
+            masks.0 |= 1 << qubit;
+        }
+        if matches!(pauli, Pauli::Z | Pauli::Y) {
+            masks.1 |= 1 << qubit;
+        }
+        masks.2 += (pauli == Pauli::Y) as usize;
+    }
+    masks
+}
+
+impl fmt::Display for PauliSum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+        for (index, (coefficient, pauli)) in self.terms.iter().enumerate() {
+            match index {
+                0 => write!(f, "{coefficient} {pauli}")?,
+                _ if *coefficient < 0.0 => write!(f, " - {} {pauli}", -coefficient)?,
+                _ => write!(f, " + {coefficient} {pauli}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    #[test]
+    fn test_pauli_sum() {
+        let mut sum = PauliSum::from_terms(&[(1.0, "XX"), (-0.5, "ZI"), (0.25, "XX")]).unwrap();
+        assert_eq!(sum.terms().len(), 2);
+        assert_eq!(sum.to_string(), "1.25 XX - 0.5 ZI");
+        assert!(sum.add_term(1.0, "XYZ".parse().unwrap()).is_err());
+        assert!(PauliSum::from_terms(&[(1.0, "XQ")]).is_err());
+        assert!(PauliSum::from_terms(&[]).is_err());
+    }
+
+    #[test]
+    fn test_expectation() {
+        // (|00⟩ + |11⟩)/√2 is stabilized by XX, -YY and ZZ, and has ⟨Z⊗I⟩ = 0
+        let mut bell = QuantumState::new(2);
+        bell.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+        bell.apply_gate(&QuantumGate::CNOT, &[0, 1]).unwrap();
+        for (pauli, expected) in [("XX", 1.0), ("YY", -1.0), ("ZZ", 1.0), ("ZI", 0.0), ("II", 1.0)]
+        {
+            let sum = PauliSum::from_terms(&[(2.0, pauli)]).unwrap();
+            assert!((sum.expectation(&bell).unwrap() - 2.0 * expected).abs() < 1e-12, "{pauli}");
+        }
+
+        // Y on |+i⟩ = S H|0⟩, and the same state as a term of a larger sum
+        let mut plus_i = QuantumState::new(1);
+        plus_i.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+        plus_i.apply_gate(&QuantumGate::S, &[0]).unwrap();
+        let sum = PauliSum::from_terms(&[(1.0, "Y"), (0.5, "X"), (-1.0, "I")]).unwrap();
+        assert!(sum.expectation(&plus_i).unwrap().abs() < 1e-12);
+        assert!(sum.expectation(&bell).is_err());
+    }
+
+    #[test]
+    fn test_estimate() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(3);
+        let mut state = QuantumState::new(3);
+        for (qubit, angle) in [(0, 0.3), (1, 1.2), (2, -0.7)] {
+            state.apply_gate(&QuantumGate::Ry(angle), &[qubit]).unwrap();
+            state.apply_gate(&QuantumGate::Rz(angle / 2.0), &[qubit]).unwrap();
+        }
+        state.apply_gate(&QuantumGate::CNOT, &[0, 2]).unwrap();
+        let sum = PauliSum::from_terms(&[
+            (0.5, "III"),
+            (1.0, "ZZI"),
+            (-0.8, "XIX"),
+            (0.6, "IYY"),
+            (0.3, "XIZ"),
+            (0.4, "ZIZ"),
+        ])
+        .unwrap();
+        // Terms on disjoint or agreeing bases share a group
+        let groups: Vec<Vec<usize>> =
+            sum.measurement_groups().into_iter().map(|(_, terms)| terms).collect();
+        assert_eq!(groups, [vec![1, 5], vec![2], vec![3], vec![4]]);
+
+        let exact = sum.expectation(&state).unwrap();
+        let estimate = sum.estimate(&state, 20_000, &mut rng).unwrap();
+        assert!((estimate - exact).abs() < 0.05, "{estimate} {exact}");
+        assert!(sum.estimate(&state, 0, &mut rng).is_err());
+    }
+}
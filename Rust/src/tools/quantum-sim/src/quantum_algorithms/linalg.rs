@@ -0,0 +1,179 @@
+//! Dense linear algebra on complex matrices
+//!
+//! `ndarray` has no decompositions without a LAPACK binding, so the few the simulators need
+//! are implemented here for the small matrices they work on.
+
+use ndarray::{Array1, Array2, Axis};
+use num_complex::Complex64;
+
+/// Largest number of Jacobi sweeps before giving up on further convergence
+const MAX_SWEEPS: usize = 100;
+
+/// Singular value decomposition `A = U diag(S) V†`
+#[derive(Debug, Clone)]
+pub(crate) struct Svd {
+    /// Left singular vectors as columns, `m × k`
+    pub u: Array2<Complex64>,
+    /// Singular values in decreasing order, `k = min(m, n)` of them
+    pub s: Array1<f64>,
+    /// Conjugate transpose of the right singular vectors, `k × n`
+    pub vt: Array2<Complex64>,
+}
+
+/// Singular value decomposition of `matrix` by one-sided Jacobi rotations
+///
+/// Columns are rotated pairwise until they are orthogonal, which is accurate to machine
+/// precision even for small singular values.
+pub(crate) fn svd(matrix: &Array2<Complex64>) -> Svd {
+    let (rows, cols) = matrix.dim();
+    if rows < cols {
+        // A† = U S V† gives A = V S U†
+        let Svd { u, s, vt } = svd(&adjoint(matrix));
+        return Svd { u: adjoint(&vt), s, vt: adjoint(&u) };
+    }
+
+    let mut a = matrix.clone();
+    let mut v = Array2::<Complex64>::eye(cols);
+    let scale = a.iter().map(|x| x.norm_sqr()).sum::<f64>();
+    for _ in 0..MAX_SWEEPS {
+        let mut rotated = false;
+        for p in 0..cols {
+            for q in p + 1..cols {
+                let column_p = a.column(p);
+                let column_q = a.column(q);
+                let alpha = column_p.iter().map(|x| x.norm_sqr()).sum::<f64>();
+                let beta = column_q.iter().map(|x| x.norm_sqr()).sum::<f64>();
+                let gamma: Complex64 =
+                    column_p.iter().zip(column_q.iter()).map(|(x, y)| x.conj() * y).sum();
+                let overlap = gamma.norm();
+                if overlap <= f64::EPSILON * (alpha * beta).sqrt() || overlap <= 1e-300 * scale {
+                    continue;
+                }
+                rotated = true;
+                // Rotating column q by the phase of γ makes the overlap real
+                let phase = (gamma / overlap).conj();
+                let zeta = (beta - alpha) / (2.0 * overlap);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+                rotate_columns(&mut a, p, q, phase, c, s);
+                rotate_columns(&mut v, p, q, phase, c, s);
+            }
+        }
+        if !rotated {
+            break;
+        }
+    }
+
+    let norms: Vec<f64> = a
+        .axis_iter(Axis(1))
+        .map(|column| column.iter().map(|x| x.norm_sqr()).sum::<f64>().sqrt())
+        .collect();
+    let mut order: Vec<usize> = (0..cols).collect();
+    order.sort_by(|&i, &j| norms[j].total_cmp(&norms[i]));
+
+    let mut u = Array2::<Complex64>::zeros((rows, cols));
+    let mut vt = Array2::<Complex64>::zeros((cols, cols));
+    for (k, &column) in order.iter().enumerate() {
+        if norms[column] > 0.0 {
+            let scale = Complex64::from(1.0 / norms[column]);
+            u.column_mut(k).assign(&a.column(column).mapv(|x| x * scale));
+        }
+        vt.row_mut(k).assign(&v.column(column).mapv(|x| x.conj()));
+    }
+    complete_basis(&mut u, order.iter().map(|&column| norms[column] > 0.0));
+    Svd { u, s: order.iter().map(|&column| norms[column]).collect(), vt }
+}
+
+/// Replace columns `p` and `q` by `c a_p - s e^{iφ} a_q` and `s a_p + c e^{iφ} a_q`
+fn rotate_columns(
+    matrix: &mut Array2<Complex64>,
+    p: usize,
+    q: usize,
+    phase: Complex64,
+    c: f64,
+    s: f64,
+) {
+    for mut row in matrix.rows_mut() {
+        let (x, y) = (row[p], row[q] * phase);
+        row[p] = x * c - y * s;
+        row[q] = x * s + y * c;
+    }
+}
+
+/// Fill the columns of `u` that are not `valid` with unit vectors orthogonal to the others
+fn complete_basis(u: &mut Array2<Complex64>, valid: impl Iterator<Item = bool>) {
+    let valid: Vec<bool> = valid.collect();
+    let rows = u.nrows();
+    let mut candidate = 0;
+    for k in 0..valid.len() {
+        if valid[k] {
+            continue;
+        }
+        // Orthogonalize standard basis vectors against the columns so far
+        while candidate < rows {
+            let mut vector = Array1::<Complex64>::zeros(rows);
+            vector[candidate] = Complex64::from(1.0);
+            candidate += 1;
+            for j in (0..valid.len()).filter(|&j| j != k && (valid[j] || j < k)) {
+                let column = u.column(j);
+                let projection: Complex64 =
+                    column.iter().zip(vector.iter()).map(|(x, y)| x.conj() * y).sum();
+                vector.zip_mut_with(&column, |y, x| *y -= projection * x);
+            }
+            let norm = vector.iter().map(|x| x.norm_sqr()).sum::<f64>().sqrt();
+            if norm > 1e-6 {
+                u.column_mut(k).assign(&vector.mapv(|x| x / norm));
+                break;
+            }
+        }
+    }
+}
+
+/// Conjugate transpose of `matrix`
+pub(crate) fn adjoint(matrix: &Array2<Complex64>) -> Array2<Complex64> {
+    matrix.t().mapv(|x| x.conj())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    fn random_matrix(rows: usize, cols: usize, rng: &mut Xoshiro256PlusPlus) -> Array2<Complex64> {
+        Array2::from_shape_fn((rows, cols), |_| {
+            Complex64::new(rng.random::<f64>() - 0.5, rng.random::<f64>() - 0.5)
+        })
+    }
+
+    fn assert_close(a: &Array2<Complex64>, b: &Array2<Complex64>) {
+        assert_eq!(a.dim(), b.dim());
+        let error = (a - b).iter().map(|x| x.norm()).fold(0.0, f64::max);
+        assert!(error < 1e-10, "{a}\n{b}");
+    }
+
+    #[test]
+    fn test_svd() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        // Rank 2 in the last case, so some singular vectors complete the basis
+        let low_rank = random_matrix(6, 2, &mut rng).dot(&random_matrix(2, 5, &mut rng));
+        let matrices = [
+            random_matrix(4, 4, &mut rng),
+            random_matrix(8, 3, &mut rng),
+            random_matrix(2, 6, &mut rng),
+            low_rank,
+        ];
+        for matrix in matrices {
+            let Svd { u, s, vt } = svd(&matrix);
+            let k = matrix.nrows().min(matrix.ncols());
+            assert_eq!((u.ncols(), s.len(), vt.nrows()), (k, k, k));
+            assert!(s.windows(2).into_iter().all(|pair| pair[0] >= pair[1]), "{s}");
+            let sigma = Array2::from_diag(&s.mapv(Complex64::from));
+            assert_close(&u.dot(&sigma).dot(&vt), &matrix);
+            assert_close(&adjoint(&u).dot(&u), &Array2::eye(k));
+            assert_close(&vt.dot(&adjoint(&vt)), &Array2::eye(k));
+        }
+    }
+}
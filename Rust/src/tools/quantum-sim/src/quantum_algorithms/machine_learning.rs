@@ -0,0 +1,502 @@
+//! Quantum machine learning: principal component analysis and k-means clustering
+//!
+//! Quantum PCA (Lloyd, Mohseni and Rebentrost) treats the normalized covariance matrix of the
+//! data as a density matrix `ρ`. Density-matrix exponentiation applies `e^{iρt}` using copies
+//! of `ρ`, and phase estimation of that unitary on `ρ` itself measures an eigenvalue of `ρ`
+//! with probability equal to that eigenvalue, leaving the output register in the matching
+//! eigenvector. Clustering runs k-means with distances estimated by swap tests between
+//! amplitude-encoded vectors.
+
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+use ndarray::{Array2, Zip, s};
+use num_complex::Complex64;
+use rand::Rng;
+
+use super::linalg::svd;
+use super::{DensityMatrix, QuantumGate, QuantumState, UnitaryMatrix};
+use crate::{QuantumError, QuantumResult};
+
+/// Counting qubits of the phase estimation in [`QuantumML::quantum_pca`]
+const PCA_PRECISION: usize = 4;
+
+/// Longest time step of density-matrix exponentiation, which deviates from `e^{iρΔt}` by
+/// `O(Δt²)` per step
+const EXPONENTIATION_STEP: f64 = 0.05;
+
+/// PCA simulates a density matrix over the features and the counting qubits, so it is limited
+/// to this many features
+const MAX_PCA_FEATURES: usize = 8;
+
+/// Phase estimation outcomes less likely than this are not examined for components
+const MIN_OUTCOME_PROBABILITY: f64 = 1e-3;
+
+/// Lloyd iterations of k-means before it stops without converging
+const MAX_KMEANS_ITERATIONS: usize = 100;
+
+/// Outcome of [`QuantumML::quantum_pca`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrincipalComponents {
+    /// Unit principal axes in decreasing order of variance, each with a positive largest
+    /// entry
+    pub components: Vec<Vec<f64>>,
+    /// Variance of the data along each component, to the resolution of the phase estimation
+    pub variances: Vec<f64>,
+}
+
+/// Outcome of [`QuantumML::quantum_clustering`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    /// Cluster of every data point
+    pub assignments: Vec<usize>,
+    /// Mean of the points of every cluster
+    pub centroids: Vec<Vec<f64>>,
+    pub iterations: usize,
+    /// Whether the last iteration left every assignment unchanged
+    pub converged: bool,
+}
+
+/// Quantum machine learning algorithms
+pub struct QuantumML;
+
+impl QuantumML {
+    /// The `components` principal components of the rows of `data`
+    ///
+    /// The covariance matrix, divided by its trace, is the density matrix `ρ` of a register
+    /// of `⌈log₂ features⌉` qubits. Phase estimation of `e^{iπρ}`, built by density-matrix
+    /// exponentiation, on `ρ` measures `λ/2` for an eigenvalue `λ` with probability `λ`, and
+    /// the register is left in the eigenvector conditioned on the outcome. The outcome
+    /// probabilities and conditional states are read from the simulated density matrix, in
+    /// place of repeating the circuit and state tomography. Components need distinct
+    /// eigenvalues that the phase estimation resolves.
+    pub fn quantum_pca(data: &[Vec<f64>], components: usize) -> QuantumResult<PrincipalComponents> {
+        let features = check_data(data)?;
+        if components == 0 || components > features || features > MAX_PCA_FEATURES {
+            return Err(QuantumError::SimulationError(format!(
+                "cannot find {components} components of {features} features, PCA takes at \
+                 most {MAX_PCA_FEATURES} features"
+            )));
+        }
+        let covariance = covariance(data, features);
+        let total_variance: f64 = covariance.diag().sum();
+        if total_variance <= 0.0 {
+            return Err(QuantumError::SimulationError("the data has no variance".to_string()));
+        }
+        let dimension = features.next_power_of_two();
+        let mut rho = Array2::zeros((dimension, dimension));
+        for ((row, col), &value) in covariance.indexed_iter() {
+            rho[[row, col]] = Complex64::from(value / total_variance);
+        }
+
+        // The counting register takes the low qubits, in |+⟩ after the Hadamards
+        let t = PCA_PRECISION;
+        let outcomes = 1 << t;
+        let mut joint =
+            Array2::from_shape_fn((outcomes * dimension, outcomes * dimension), |(r, c)| {
+                rho[[r / outcomes, c / outcomes]] / outcomes as f64
+            });
+        // Counting qubit j controls e^{iπρ 2^j}, whose phases λ/2 stay below 1/2
+        for control in 0..t {
+            let time = PI * (1 << control) as f64;
+            let steps = (time / EXPONENTIATION_STEP).ceil();
+            for _ in 0..steps as usize {
+                exponentiation_step(&mut joint, &rho, control, outcomes, -time / steps);
+            }
+        }
+        let mut joint = DensityMatrix::from_matrix(joint)?;
+        let counting: Vec<usize> = (0..t).collect();
+        joint.apply_gate(&QuantumGate::Unitary(inverse_fourier_transform(t)?), &counting)?;
+
+        let matrix = joint.matrix();
+        let mut probabilities: Vec<(usize, f64)> = (0..outcomes)
+            .map(|k| {
+                (k, (0..dimension).map(|u| matrix[[k + outcomes * u, k + outcomes * u]].re).sum())
+            })
+            .collect();
+        probabilities.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        // The most likely outcomes near each eigenvalue all leave the register in its
+        // eigenvector, so only vectors unlike the ones found so far are new components
+        let mut found: Vec<(f64, Vec<f64>)> = Vec::new();
+        for (k, probability) in probabilities {
+            if found.len() == components || probability < MIN_OUTCOME_PROBABILITY {
+                break;
+            }
+            let conditional = Array2::from_shape_fn((dimension, dimension), |(u, v)| {
+                matrix[[k + outcomes * u, k + outcomes * v]] / probability
+            });
+            let vector = real_unit_vector(svd(&conditional).u.column(0).iter(), features);
+            let overlap = |other: &[f64]| vector.iter().zip(other).map(|(a, b)| a * b).sum::<f64>();
+            if found.iter().all(|(_, other)| overlap(other).abs() < FRAC_1_SQRT_2) {
+                let eigenvalue = 2.0 * k as f64 / outcomes as f64;
+                found.push((eigenvalue * total_variance, vector));
+            }
+        }
+        if found.len() < components {
+            return Err(QuantumError::SimulationError(format!(
+                "only {} components have distinct eigenvalues large enough to be resolved",
+                found.len()
+            )));
+        }
+        found.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let (variances, components) = found.into_iter().unzip();
+        Ok(PrincipalComponents { components, variances })
+    }
+
+    /// K-means clustering of the rows of `data` into `clusters` clusters, with distances
+    /// estimated by [`QuantumML::swap_test_distance`] from `shots` measurements
+    ///
+    /// The initial centroids are data points picked by k-means++, each with a probability
+    /// proportional to its estimated squared distance from the closest centroid so far.
+    pub fn quantum_clustering<R: Rng + ?Sized>(
+        data: &[Vec<f64>],
+        clusters: usize,
+        shots: usize,
+        rng: &mut R,
+    ) -> QuantumResult<Clustering> {
+        check_data(data)?;
+        k_means(data, clusters, rng, |a, b, rng| Self::swap_test_distance(a, b, shots, rng))
+    }
+
+    /// Estimate of the squared Euclidean distance between `a` and `b` from `shots` swap tests
+    ///
+    /// With `|ψ⟩ = (|0⟩|a⟩ + |1⟩|b⟩)/√2` and `|φ⟩ = (|a||0⟩ - |b||1⟩)/√Z` for
+    /// `Z = |a|² + |b|²`, where `|a⟩` and `|b⟩` are the normalized vectors, the overlap of
+    /// `|φ⟩` with the first qubit of `|ψ⟩` is `|a - b|² / 2Z`. A swap test of the two qubits
+    /// measures 0 with probability `(1 + |a - b|² / 2Z) / 2`.
+    pub fn swap_test_distance<R: Rng + ?Sized>(
+        a: &[f64],
+        b: &[f64],
+        shots: usize,
+        rng: &mut R,
+    ) -> QuantumResult<f64> {
+        if a.len() != b.len() || a.is_empty() || shots == 0 {
+            return Err(QuantumError::SimulationError(format!(
+                "cannot estimate the distance of vectors of {} and {} entries from {shots} shots",
+                a.len(),
+                b.len()
+            )));
+        }
+        let squared_norm = |vector: &[f64]| vector.iter().map(|x| x * x).sum::<f64>();
+        let (norm_a, norm_b) = (squared_norm(a).sqrt(), squared_norm(b).sqrt());
+        let z = norm_a * norm_a + norm_b * norm_b;
+        if z == 0.0 {
+            return Ok(0.0);
+        }
+
+        // Qubit 0 is the swap test ancilla, qubit 1 holds |φ⟩, and qubit 2 with the qubits
+        // after it hold |ψ⟩
+        let data_qubits = a.len().next_power_of_two().trailing_zeros() as usize;
+        let encode = |vector: &[f64], norm: f64, entry: usize| match vector.get(entry) {
+            _ if norm == 0.0 => (entry == 0) as u8 as f64,
+            Some(value) => value / norm,
+            None => 0.0,
+        };
+        let phi = [norm_a / z.sqrt(), -norm_b / z.sqrt()];
+        let mut state = QuantumState::new(3 + data_qubits);
+        for (index, amplitude) in state.amplitudes.iter_mut().enumerate() {
+            let (ancilla, phi_bit, branch, entry) =
+                (index & 1, (index >> 1) & 1, (index >> 2) & 1, index >> 3);
+            let psi = match branch {
+                0 => encode(a, norm_a, entry),
+                _ => encode(b, norm_b, entry),
+            };
+            let value = if ancilla == 0 { phi[phi_bit] * psi * FRAC_1_SQRT_2 } else { 0.0 };
+            *amplitude = (value, 0.0);
+        }
+        state.apply_gate(&QuantumGate::Hadamard, &[0])?;
+        state.apply_gate(&QuantumGate::Fredkin, &[0, 1, 2])?;
+        state.apply_gate(&QuantumGate::Hadamard, &[0])?;
+
+        let zeros: usize = state
+            .sample(shots, rng)?
+            .into_iter()
+            .filter(|&(outcome, _)| outcome & 1 == 0)
+            .map(|(_, count)| count)
+            .sum();
+        let overlap = 2.0 * zeros as f64 / shots as f64 - 1.0;
+        Ok((2.0 * z * overlap).max(0.0))
+    }
+}
+
+/// Number of features of `data`, which must be a nonempty list of rows of equal, nonzero
+/// length and finite values
+fn check_data(data: &[Vec<f64>]) -> QuantumResult<usize> {
+    let features = data.first().map_or(0, Vec::len);
+    let valid = features > 0
+        && data.iter().all(|row| row.len() == features && row.iter().all(|x| x.is_finite()));
+    if !valid {
+        return Err(QuantumError::SimulationError(
+            "data must be nonempty rows of the same number of finite features".to_string(),
+        ));
+    }
+    Ok(features)
+}
+
+/// Covariance matrix of the rows of `data`, normalized by the number of rows
+fn covariance(data: &[Vec<f64>], features: usize) -> Array2<f64> {
+    let count = data.len() as f64;
+    let mean: Vec<f64> =
+        (0..features).map(|f| data.iter().map(|row| row[f]).sum::<f64>() / count).collect();
+    Array2::from_shape_fn((features, features), |(i, j)| {
+        data.iter().map(|row| (row[i] - mean[i]) * (row[j] - mean[j])).sum::<f64>() / count
+    })
+}
+
+/// One step of density-matrix exponentiation controlled by counting qubit `control`
+///
+/// A fresh copy of `ρ` and the target register go through the partial swap `e^{-iSΔt}` if
+/// the control is |1⟩, and the copy is discarded. With `c = cos Δt` and `s = sin Δt` this
+/// maps the block of the joint matrix `X` between control values `a` and `b` to
+///
+/// - `X` for `a = b = 0`,
+/// - `cX - isρX` for `a = 1, b = 0` and `cX + isXρ` for `a = 0, b = 1`,
+/// - `c²X + s² Tr_T(X) ⊗ ρ - ics[ρ, X]` for `a = b = 1`,
+///
+/// with `ρ` acting on the target, which is `e^{-iρΔt} X e^{iρΔt}` up to `O(Δt²)`. The
+/// target index of the joint matrix is its row or column divided by `outcomes`, so its blocks
+/// of equal target indices are matrices over the counting register.
+fn exponentiation_step(
+    joint: &mut Array2<Complex64>,
+    rho: &Array2<Complex64>,
+    control: usize,
+    outcomes: usize,
+    dt: f64,
+) {
+    let dimension = rho.nrows();
+    let (c, s) = (dt.cos(), dt.sin());
+    let i = Complex64::i();
+    let block =
+        |u: usize, v: usize| s![u * outcomes..(u + 1) * outcomes, v * outcomes..(v + 1) * outcomes];
+
+    let mut traced = Array2::<Complex64>::zeros((outcomes, outcomes));
+    for u in 0..dimension {
+        traced += &joint.slice(block(u, u));
+    }
+    let mut updated = joint.clone();
+    for u in 0..dimension {
+        for v in 0..dimension {
+            // The blocks of ρX and Xρ at target indices u and v
+            let mut left = Array2::<Complex64>::zeros((outcomes, outcomes));
+            let mut right = Array2::<Complex64>::zeros((outcomes, outcomes));
+            for w in 0..dimension {
+                left.scaled_add(rho[[u, w]], &joint.slice(block(w, v)));
+                right.scaled_add(rho[[w, v]], &joint.slice(block(u, w)));
+            }
+            let rho_uv = rho[[u, v]];
+            Zip::indexed(updated.slice_mut(block(u, v)))
+                .and(&left)
+                .and(&right)
+                .and(&traced)
+                .for_each(|(a, b), x, &left, &right, &traced| {
+                    *x = match ((a >> control) & 1, (b >> control) & 1) {
+                        (0, 0) => *x,
+                        (1, 0) => c * *x - i * s * left,
+                        (0, _) => c * *x + i * s * right,
+                        _ => c * c * *x + s * s * traced * rho_uv - i * c * s * (left - right),
+                    };
+                });
+        }
+    }
+    *joint = updated;
+}
+
+/// Inverse discrete Fourier transform `|x⟩ → Σ_k e^{-2πikx/N} |k⟩ / √N` of `qubits` qubits
+fn inverse_fourier_transform(qubits: usize) -> QuantumResult<UnitaryMatrix> {
+    let size = 1 << qubits;
+    let scale = 1.0 / (size as f64).sqrt();
+    UnitaryMatrix::new(Array2::from_shape_fn((size, size), |(k, x)| {
+        let angle = -2.0 * PI * ((k * x) % size) as f64 / size as f64;
+        Complex64::from_polar(scale, angle)
+    }))
+}
+
+/// The first `features` entries of a complex vector with its global phase removed, made a real
+/// unit vector whose largest entry is positive
+fn real_unit_vector<'a>(vector: impl Iterator<Item = &'a Complex64>, features: usize) -> Vec<f64> {
+    let vector: Vec<Complex64> = vector.take(features).copied().collect();
+    let largest = vector.iter().copied().max_by(|a, b| a.norm().total_cmp(&b.norm()));
+    let phase = largest.map_or(Complex64::from(1.0), |value| value.conj() / value.norm());
+    let real: Vec<f64> = vector.iter().map(|value| (value * phase).re).collect();
+    let norm = real.iter().map(|x| x * x).sum::<f64>().sqrt();
+    real.into_iter().map(|x| x / norm).collect()
+}
+
+/// Lloyd's k-means from k-means++ initial centroids, with squared distances from `distance`
+fn k_means<R, D>(
+    data: &[Vec<f64>],
+    clusters: usize,
+    rng: &mut R,
+    mut distance: D,
+) -> QuantumResult<Clustering>
+where
+    R: Rng + ?Sized,
+    D: FnMut(&[f64], &[f64], &mut R) -> QuantumResult<f64>,
+{
+    if clusters == 0 || clusters > data.len() {
+        return Err(QuantumError::SimulationError(format!(
+            "cannot split {} points into {clusters} clusters",
+            data.len()
+        )));
+    }
+
+    let mut centroids = vec![data[rng.random_range(0..data.len())].clone()];
+    let mut closest = vec![f64::INFINITY; data.len()];
+    while centroids.len() < clusters {
+        let newest = &centroids[centroids.len() - 1];
+        for (point, closest) in data.iter().zip(&mut closest) {
+            *closest = closest.min(distance(point, newest, rng)?);
+        }
+        let total: f64 = closest.iter().sum();
+        let index = if total > 0.0 {
+            let mut remaining = rng.random::<f64>() * total;
+            closest.iter().position(|&d| {
+                remaining -= d;
+                remaining < 0.0
+            })
+        } else {
+            None
+        };
+        let index = index.unwrap_or_else(|| rng.random_range(0..data.len()));
+        centroids.push(data[index].clone());
+    }
+
+    let mut assignments = vec![usize::MAX; data.len()];
+    for iteration in 1..=MAX_KMEANS_ITERATIONS {
+        let mut changed = false;
+        for (point, assignment) in data.iter().zip(&mut assignments) {
+            let mut best = (f64::INFINITY, 0);
+            for (cluster, centroid) in centroids.iter().enumerate() {
+                let d = distance(point, centroid, rng)?;
+                if d < best.0 {
+                    best = (d, cluster);
+                }
+            }
+            changed |= *assignment != best.1;
+            *assignment = best.1;
+        }
+        // A cluster that lost all its points keeps its centroid
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&Vec<f64>> = data
+                .iter()
+                .zip(&assignments)
+                .filter(|&(_, &a)| a == cluster)
+                .map(|(p, _)| p)
+                .collect();
+            if !members.is_empty() {
+                for (f, value) in centroid.iter_mut().enumerate() {
+                    *value =
+                        members.iter().map(|point| point[f]).sum::<f64>() / members.len() as f64;
+                }
+            }
+        }
+        if !changed {
+            return Ok(Clustering {
+                assignments,
+                centroids,
+                iterations: iteration,
+                converged: true,
+            });
+        }
+    }
+    Ok(Clustering { assignments, centroids, iterations: MAX_KMEANS_ITERATIONS, converged: false })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    #[test]
+    fn test_quantum_pca() {
+        // Points spread along two orthogonal axes of four dimensions, with a little noise
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let axes = [[0.5, 0.5, 0.5, 0.5], [0.5, -0.5, 0.5, -0.5]];
+        let data: Vec<Vec<f64>> = (0..60)
+            .map(|_| {
+                let (a, b) = (rng.random_range(-2.0..2.0), rng.random_range(-1.0..1.0));
+                (0..4)
+                    .map(|f| a * axes[0][f] + b * axes[1][f] + rng.random_range(-0.1..0.1))
+                    .collect()
+            })
+            .collect();
+        let result = QuantumML::quantum_pca(&data, 2).unwrap();
+
+        // Classical PCA diagonalizes the covariance matrix
+        let covariance = covariance(&data, 4);
+        let decomposition = svd(&covariance.mapv(Complex64::from));
+        let total_variance = covariance.diag().sum();
+        for (component, (quantum, variance)) in
+            result.components.iter().zip(&result.variances).enumerate()
+        {
+            let classical = real_unit_vector(decomposition.u.column(component).iter(), 4);
+            let overlap: f64 = quantum.iter().zip(&classical).map(|(a, b)| a * b).sum();
+            assert!(overlap > 0.999, "{component}: {quantum:?} {classical:?}");
+            // One step of the phase estimation is 2/16 of the total variance
+            let error = (variance - decomposition.s[component]).abs();
+            assert!(error <= total_variance / 8.0, "{component}: {variance}");
+        }
+
+        assert!(QuantumML::quantum_pca(&data, 5).is_err());
+        assert!(QuantumML::quantum_pca(&[vec![1.0, 2.0], vec![1.0]], 1).is_err());
+        assert!(QuantumML::quantum_pca(&[vec![1.0, 2.0], vec![1.0, 2.0]], 1).is_err());
+    }
+
+    #[test]
+    fn test_swap_test_distance() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(2);
+        let pairs = [
+            (vec![1.0, 2.0, 3.0], vec![-1.0, 0.5, 2.0]),
+            (vec![0.0, 0.0], vec![3.0, 4.0]),
+            (vec![2.0], vec![2.0]),
+        ];
+        for (a, b) in pairs {
+            let exact: f64 = a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum();
+            let estimate = QuantumML::swap_test_distance(&a, &b, 20_000, &mut rng).unwrap();
+            let z: f64 = a.iter().chain(&b).map(|x| x * x).sum();
+            // The standard deviation of the estimate is at most 2Z/√shots
+            assert!((estimate - exact).abs() < 0.05 * z, "{estimate} {exact}");
+        }
+        assert!(QuantumML::swap_test_distance(&[1.0], &[1.0, 2.0], 10, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_quantum_clustering() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(3);
+        let centers = [[0.0, 0.0, 5.0], [6.0, 1.0, 0.0], [-3.0, 5.0, 2.0]];
+        let data: Vec<Vec<f64>> = (0..30)
+            .map(|point| {
+                let center = centers[point % 3];
+                center.iter().map(|x| x + rng.random_range(-0.8..0.8)).collect()
+            })
+            .collect();
+
+        // Classical k-means with exact distances from the same initial random state
+        let exact = |a: &[f64], b: &[f64], _: &mut Xoshiro256PlusPlus| {
+            Ok(a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum())
+        };
+        let classical = k_means(&data, 3, &mut Xoshiro256PlusPlus::seed_from_u64(4), exact);
+        let classical = classical.unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(4);
+        let quantum = QuantumML::quantum_clustering(&data, 3, 2000, &mut rng).unwrap();
+        assert!(quantum.converged);
+
+        // The same partition, up to the labels of the clusters
+        let label = |point: usize| classical.assignments[point];
+        for cluster in 0..3 {
+            let members: Vec<usize> =
+                (0..30).filter(|&point| quantum.assignments[point] == cluster).collect();
+            assert!(members.iter().all(|&point| point % 3 == members[0] % 3));
+            assert!(members.iter().all(|&point| label(point) == label(members[0])));
+            let centroid = &classical.centroids[label(members[0])];
+            let error: f64 =
+                quantum.centroids[cluster].iter().zip(centroid).map(|(a, b)| (a - b).abs()).sum();
+            assert!(error < 1e-12, "{:?} {centroid:?}", quantum.centroids[cluster]);
+        }
+
+        assert!(QuantumML::quantum_clustering(&data, 31, 100, &mut rng).is_err());
+        assert!(QuantumML::quantum_clustering(&data, 0, 100, &mut rng).is_err());
+    }
+}
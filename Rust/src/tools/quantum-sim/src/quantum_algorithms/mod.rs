@@ -0,0 +1,626 @@
+//! Quantum Algorithms Core
+//!
+//! Circuits, their simulation on state vector, density matrix and matrix product state
+//! backends, and the algorithms built on them.
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::Rng;
+
+use crate::annealing::{Annealer, AnnealingConfig, AnnealingRng, AnnealingStats, FnProblem};
+use crate::{QuantumError, QuantumResult};
+
+mod circuit;
+mod density;
+mod equivalence;
+mod error_correction;
+mod gates;
+mod hamiltonian;
+mod linalg;
+mod machine_learning;
+mod mps;
+mod noise;
+mod parameter;
+mod phase_estimation;
+mod qaoa;
+mod qasm;
+mod search;
+mod shor;
+mod stabilizer;
+mod transpiler;
+mod vqe;
+
+pub use circuit::{
+    Backend, ClassicalRegister, Condition, ExecutionResult, Instruction, Operation, QuantumCircuit,
+    QuantumRegister, StateVectorBackend, bitstring,
+};
+pub use density::{DensityMatrix, DensityMatrixBackend};
+pub use equivalence::{
+    Counterexample, EquivalenceChecker, EquivalenceConfig, EquivalenceMethod, EquivalenceResult,
+};
+pub use error_correction::{Decoder, QuantumErrorCorrection, StabilizerCode};
+pub(crate) use gates::check_qubits;
+pub use gates::{QuantumGate, UnitaryMatrix, controlled_matrix};
+pub use hamiltonian::PauliSum;
+pub use machine_learning::{Clustering, PrincipalComponents, QuantumML};
+pub use mps::{MatrixProductState, MpsBackend, MpsConfig};
+pub use noise::{KrausChannel, NoiseModel, PauliChannel, ReadoutError};
+pub use parameter::ParameterExpr;
+pub use phase_estimation::{PhaseEstimate, PhaseEstimation};
+pub use qaoa::{IsingModel, Qaoa, QaoaConfig, QaoaResult};
+pub use qasm::QasmVersion;
+pub use search::{GroverResult, QuantumParallelSearch, SolutionCount};
+pub use shor::{Factorization, Shor};
+pub use stabilizer::{Pauli, PauliString, StabilizerBackend, StabilizerTableau};
+pub use transpiler::{CircuitMetrics, CouplingMap, TranspileResult, Transpiler, TranspilerConfig};
+pub use vqe::{Ansatz, OptimizerType, Vqe, VqeConfig, VqeResult};
+
+/// Quantum state representation
+#[derive(Debug, Clone)]
+pub struct QuantumState {
+    /// Complex amplitudes (real, imaginary)
+    pub amplitudes: Vec<(f64, f64)>,
+    /// Number of qubits
+    pub qubit_count: usize,
+    /// Normalization factor
+    pub norm: f64,
+}
+
+impl QuantumState {
+    /// Create a new quantum state with n qubits in |0⟩ state
+    pub fn new(qubit_count: usize) -> Self {
+        let state_count = 1 << qubit_count;
+        let mut amplitudes = vec![(0.0, 0.0); state_count];
+        amplitudes[0] = (1.0, 0.0); // |00...0⟩ state
+
+        Self { amplitudes, qubit_count, norm: 1.0 }
+    }
+
+    /// Create superposition state
+    pub fn superposition(qubit_count: usize) -> Self {
+        let state_count = 1 << qubit_count;
+        let amplitude = 1.0 / (state_count as f64).sqrt();
+        let amplitudes = vec![(amplitude, 0.0); state_count];
+
+        Self { amplitudes, qubit_count, norm: 1.0 }
+    }
+
+    /// Apply quantum gate
+    pub fn apply_gate(&mut self, gate: &QuantumGate, target_qubits: &[usize]) -> QuantumResult<()> {
+        check_qubits(gate, target_qubits, self.qubit_count)?;
+        match gate {
+            QuantumGate::Hadamard => self.apply_hadamard(target_qubits[0]),
+            QuantumGate::PauliX => self.apply_pauli_x(target_qubits[0]),
+            QuantumGate::PauliY => self.apply_pauli_y(target_qubits[0]),
+            QuantumGate::PauliZ => self.apply_pauli_z(target_qubits[0]),
+            QuantumGate::CNOT => self.apply_cnot(target_qubits[0], target_qubits[1]),
+            QuantumGate::Phase(theta) => self.apply_phase(*theta, target_qubits[0]),
+            _ => {
+                match gate.split_controls() {
+                    Some((controls, target_gate)) => self.apply_matrix(
+                        &target_gate.matrix(),
+                        &target_qubits[controls..],
+                        &target_qubits[..controls],
+                    ),
+                    None => self.apply_matrix(&gate.matrix(), target_qubits, &[]),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply `matrix` to `targets` in the subspace where every qubit in `controls` is |1⟩
+    ///
+    /// The first target is the least significant bit of the matrix indices.
+    pub(crate) fn apply_matrix(
+        &mut self,
+        matrix: &Array2<Complex64>,
+        targets: &[usize],
+        controls: &[usize],
+    ) {
+        let control_mask: usize = controls.iter().map(|&qubit| 1 << qubit).sum();
+        let target_mask: usize = targets.iter().map(|&qubit| 1 << qubit).sum();
+        // Offset of every basis state of the targets in the full state vector
+        let offsets: Vec<usize> = (0..1usize << targets.len())
+            .map(|local| {
+                let bits = targets.iter().enumerate().filter(|&(bit, _)| (local >> bit) & 1 == 1);
+                bits.map(|(_, &qubit)| 1 << qubit).sum()
+            })
+            .collect();
+
+        let mut input = vec![Complex64::new(0.0, 0.0); offsets.len()];
+        for base in 0..self.amplitudes.len() {
+            if base & target_mask != 0 || base & control_mask != control_mask {
+                continue;
+            }
+            for (value, &offset) in input.iter_mut().zip(&offsets) {
+                let (real, imag) = self.amplitudes[base | offset];
+                *value = Complex64::new(real, imag);
+            }
+            for (row, &offset) in offsets.iter().enumerate() {
+                let value: Complex64 =
+                    input.iter().enumerate().map(|(col, value)| matrix[[row, col]] * value).sum();
+                self.amplitudes[base | offset] = (value.re, value.im);
+            }
+        }
+    }
+
+    /// Apply Hadamard gate
+    fn apply_hadamard(&mut self, qubit: usize) -> QuantumResult<()> {
+        let sqrt2_inv = 1.0 / 2.0_f64.sqrt();
+
+        for i in 0..self.amplitudes.len() {
+            if (i >> qubit) & 1 == 0 {
+                let j = i | (1 << qubit);
+                let (a_real, a_imag) = self.amplitudes[i];
+                let (b_real, b_imag) = self.amplitudes[j];
+
+                self.amplitudes[i] = (sqrt2_inv * (a_real + b_real), sqrt2_inv * (a_imag + b_imag));
+                self.amplitudes[j] = (sqrt2_inv * (a_real - b_real), sqrt2_inv * (a_imag - b_imag));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply Pauli-X gate
+    fn apply_pauli_x(&mut self, qubit: usize) -> QuantumResult<()> {
+        for i in 0..self.amplitudes.len() {
+            if (i >> qubit) & 1 == 0 {
+                let j = i | (1 << qubit);
+                self.amplitudes.swap(i, j);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply Pauli-Y gate
+    fn apply_pauli_y(&mut self, qubit: usize) -> QuantumResult<()> {
+        for i in 0..self.amplitudes.len() {
+            if (i >> qubit) & 1 == 0 {
+                let j = i | (1 << qubit);
+                let (a_real, a_imag) = self.amplitudes[i];
+                let (b_real, b_imag) = self.amplitudes[j];
+
+                self.amplitudes[i] = (b_imag, -b_real);
+                self.amplitudes[j] = (-a_imag, a_real);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply Pauli-Z gate
+    fn apply_pauli_z(&mut self, qubit: usize) -> QuantumResult<()> {
+        for i in 0..self.amplitudes.len() {
+            if (i >> qubit) & 1 == 1 {
+                self.amplitudes[i].0 = -self.amplitudes[i].0;
+                self.amplitudes[i].1 = -self.amplitudes[i].1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply CNOT gate
+    fn apply_cnot(&mut self, control: usize, target: usize) -> QuantumResult<()> {
+        for i in 0..self.amplitudes.len() {
+            if (i >> control) & 1 == 1 && (i >> target) & 1 == 0 {
+                let j = i | (1 << target);
+                self.amplitudes.swap(i, j);
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply phase gate
+    fn apply_phase(&mut self, theta: f64, qubit: usize) -> QuantumResult<()> {
+        let cos_theta = theta.cos();
+        let sin_theta = theta.sin();
+
+        for i in 0..self.amplitudes.len() {
+            if (i >> qubit) & 1 == 1 {
+                let (real, imag) = self.amplitudes[i];
+                self.amplitudes[i] =
+                    (real * cos_theta - imag * sin_theta, real * sin_theta + imag * cos_theta);
+            }
+        }
+        Ok(())
+    }
+
+    /// Measure `qubit`, collapsing the state onto the outcome
+    ///
+    /// The outcome is drawn from `rng` with the probability given by the Born rule. The other
+    /// qubits stay in superposition, so the state can be used after a mid-circuit measurement.
+    pub fn measure<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> QuantumResult<bool> {
+        if qubit >= self.qubit_count {
+            return Err(QuantumError::SimulationError(format!(
+                "cannot measure qubit {qubit} of a {}-qubit state",
+                self.qubit_count
+            )));
+        }
+
+        let mut prob_zero = 0.0;
+        let mut prob_one = 0.0;
+        for (i, (real, imag)) in self.amplitudes.iter().enumerate() {
+            if (i >> qubit) & 1 == 0 {
+                prob_zero += real * real + imag * imag;
+            } else {
+                prob_one += real * real + imag * imag;
+            }
+        }
+        let total = prob_zero + prob_one;
+        if total == 0.0 {
+            return Err(QuantumError::SimulationError(
+                "cannot measure the zero vector".to_string(),
+            ));
+        }
+
+        let result = rng.random::<f64>() * total < prob_one;
+
+        // Collapse state
+        let norm_factor = if result { prob_one } else { prob_zero }.sqrt();
+        for (i, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if ((i >> qubit) & 1 == 1) != result {
+                *amplitude = (0.0, 0.0);
+            } else {
+                amplitude.0 /= norm_factor;
+                amplitude.1 /= norm_factor;
+            }
+        }
+        self.norm = 1.0;
+
+        Ok(result)
+    }
+
+    /// Measure every qubit, collapsing the state onto the measured basis state
+    pub fn measure_all<R: Rng + ?Sized>(&mut self, rng: &mut R) -> QuantumResult<usize> {
+        let cumulative = self.cumulative_probabilities()?;
+        let state = sample_index(&cumulative, rng);
+
+        self.amplitudes.fill((0.0, 0.0));
+        self.amplitudes[state] = (1.0, 0.0);
+        self.norm = 1.0;
+
+        Ok(state)
+    }
+
+    /// Measure every qubit of `shots` copies of the state
+    ///
+    /// Returns how often each basis state was measured. The state itself does not change.
+    pub fn sample<R: Rng + ?Sized>(
+        &self,
+        shots: usize,
+        rng: &mut R,
+    ) -> QuantumResult<BTreeMap<usize, usize>> {
+        let cumulative = self.cumulative_probabilities()?;
+        let mut histogram = BTreeMap::new();
+        for _ in 0..shots {
+            *histogram.entry(sample_index(&cumulative, rng)).or_insert(0) += 1;
+        }
+        Ok(histogram)
+    }
+
+    fn cumulative_probabilities(&self) -> QuantumResult<Vec<f64>> {
+        let cumulative: Vec<f64> = self
+            .probabilities()
+            .into_iter()
+            .scan(0.0, |total, probability| {
+                *total += probability;
+                Some(*total)
+            })
+            .collect();
+        match cumulative.last() {
+            Some(&total) if total > 0.0 => Ok(cumulative),
+            _ => Err(QuantumError::SimulationError("cannot measure the zero vector".to_string())),
+        }
+    }
+
+    /// Get probability distribution
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.amplitudes.iter().map(|(real, imag)| real * real + imag * imag).collect()
+    }
+}
+
+/// Index drawn with the probabilities whose running sums are `cumulative`
+///
+/// Indices with zero probability are never returned.
+fn sample_index<R: Rng + ?Sized>(cumulative: &[f64], rng: &mut R) -> usize {
+    let total = cumulative[cumulative.len() - 1];
+    let point = rng.random::<f64>() * total;
+    cumulative.partition_point(|&sum| sum <= point).min(cumulative.len() - 1)
+}
+
+/// Quantum Fourier Transform implementation
+pub struct QuantumFFT {
+    qubit_count: usize,
+}
+
+impl QuantumFFT {
+    pub fn new(qubit_count: usize) -> Self {
+        Self { qubit_count }
+    }
+
+    /// Apply Quantum Fourier Transform
+    pub fn apply(&self, state: &mut QuantumState) -> QuantumResult<()> {
+        for i in 0..self.qubit_count {
+            // Apply Hadamard to qubit i
+            state.apply_gate(&QuantumGate::Hadamard, &[i])?;
+
+            // Apply controlled phase gates
+            for j in (i + 1)..self.qubit_count {
+                let theta = PI / (1u64 << (j - i)) as f64;
+                let controlled_phase = QuantumControlledGate::new(QuantumGate::Phase(theta), j, i);
+                controlled_phase.apply(state)?;
+            }
+        }
+
+        // Reverse qubit order
+        for i in 0..(self.qubit_count / 2) {
+            let j = self.qubit_count - 1 - i;
+            self.swap_qubits(state, i, j)?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply inverse Quantum Fourier Transform
+    pub fn apply_inverse(&self, state: &mut QuantumState) -> QuantumResult<()> {
+        // Reverse qubit order
+        for i in 0..(self.qubit_count / 2) {
+            let j = self.qubit_count - 1 - i;
+            self.swap_qubits(state, i, j)?;
+        }
+
+        for i in (0..self.qubit_count).rev() {
+            // Apply controlled phase gates (inverse)
+            for j in ((i + 1)..self.qubit_count).rev() {
+                let theta = -PI / (1u64 << (j - i)) as f64;
+                let controlled_phase = QuantumControlledGate::new(QuantumGate::Phase(theta), j, i);
+                controlled_phase.apply(state)?;
+            }
+
+            // Apply Hadamard to qubit i
+            state.apply_gate(&QuantumGate::Hadamard, &[i])?;
+        }
+
+        Ok(())
+    }
+
+    fn swap_qubits(
+        &self,
+        state: &mut QuantumState,
+        qubit1: usize,
+        qubit2: usize,
+    ) -> QuantumResult<()> {
+        state.apply_gate(&QuantumGate::CNOT, &[qubit1, qubit2])?;
+        state.apply_gate(&QuantumGate::CNOT, &[qubit2, qubit1])?;
+        state.apply_gate(&QuantumGate::CNOT, &[qubit1, qubit2])?;
+        Ok(())
+    }
+}
+
+/// Controlled quantum gate
+pub struct QuantumControlledGate {
+    gate: QuantumGate,
+    control_qubits: Vec<usize>,
+    target_qubits: Vec<usize>,
+}
+
+impl QuantumControlledGate {
+    pub fn new(gate: QuantumGate, control_qubit: usize, target_qubit: usize) -> Self {
+        Self::with_controls(gate, vec![control_qubit], vec![target_qubit])
+    }
+
+    /// `gate` on `target_qubits`, applied where every qubit in `control_qubits` is |1⟩
+    pub fn with_controls(
+        gate: QuantumGate,
+        control_qubits: Vec<usize>,
+        target_qubits: Vec<usize>,
+    ) -> Self {
+        Self { gate, control_qubits, target_qubits }
+    }
+
+    pub fn apply(&self, state: &mut QuantumState) -> QuantumResult<()> {
+        let gate = QuantumGate::controlled(self.gate.clone(), self.control_qubits.len());
+        let qubits: Vec<usize> =
+            self.control_qubits.iter().chain(&self.target_qubits).copied().collect();
+        state.apply_gate(&gate, &qubits)
+    }
+}
+
+/// Quantum annealing algorithm for optimization
+///
+/// Simulated annealing over the neighborhood given to [`QuantumAnnealer::optimize`].
+pub struct QuantumAnnealer {
+    annealer: Annealer,
+    stats: AnnealingStats,
+}
+
+impl QuantumAnnealer {
+    pub fn new() -> Self {
+        Self::with_config(AnnealingConfig::default())
+    }
+
+    pub fn with_config(config: AnnealingConfig) -> Self {
+        Self { annealer: Annealer::new(config), stats: AnnealingStats::default() }
+    }
+
+    /// Minimize `cost_function`, moving between solutions with `neighbor`
+    pub fn optimize<T, F, N>(
+        &mut self,
+        initial_solution: T,
+        cost_function: F,
+        neighbor: N,
+    ) -> QuantumResult<T>
+    where
+        T: Clone,
+        F: Fn(&T) -> f64,
+        N: Fn(&T, &mut AnnealingRng) -> T,
+    {
+        if cost_function(&initial_solution).is_nan() {
+            return Err(QuantumError::OptimizationError(
+                "cost of the initial solution is NaN".to_string(),
+            ));
+        }
+
+        let problem = FnProblem::new(cost_function, neighbor);
+        let result = self.annealer.anneal(&problem, initial_solution);
+        self.stats = result.stats;
+        Ok(result.state)
+    }
+
+    /// Statistics of the last [`QuantumAnnealer::optimize`] call
+    pub fn stats(&self) -> &AnnealingStats {
+        &self.stats
+    }
+}
+
+impl Default for QuantumAnnealer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    fn bell_state() -> QuantumState {
+        let mut state = QuantumState::new(2);
+        state.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+        state.apply_gate(&QuantumGate::CNOT, &[0, 1]).unwrap();
+        state
+    }
+
+    /// Pearson's chi-square statistic of `histogram` against the probabilities of `state`
+    fn chi_square(histogram: &BTreeMap<usize, usize>, state: &QuantumState, shots: usize) -> f64 {
+        state
+            .probabilities()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &probability)| probability > 0.0)
+            .map(|(index, probability)| {
+                let expected = probability * shots as f64;
+                let observed = histogram.get(&index).copied().unwrap_or(0) as f64;
+                (observed - expected).powi(2) / expected
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_quantum_state_creation() {
+        let state = QuantumState::new(2);
+        assert_eq!(state.qubit_count, 2);
+        assert_eq!(state.amplitudes.len(), 4);
+        assert_eq!(state.amplitudes[0], (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_hadamard_gate() {
+        let mut state = QuantumState::new(1);
+        state.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+
+        let sqrt2_inv = 1.0 / 2.0_f64.sqrt();
+        assert!((state.amplitudes[0].0 - sqrt2_inv).abs() < 1e-10);
+        assert!((state.amplitudes[1].0 - sqrt2_inv).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_bell_state_sampling() {
+        let state = bell_state();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        let shots = 10_000;
+        let histogram = state.sample(shots, &mut rng).unwrap();
+
+        // Only |00⟩ and |11⟩ can be measured
+        assert_eq!(histogram.keys().copied().collect::<Vec<_>>(), vec![0, 3]);
+        assert_eq!(histogram.values().sum::<usize>(), shots);
+        // 10.83 is the 0.999 quantile of the chi-square distribution with 1 degree of freedom
+        assert!(chi_square(&histogram, &state, shots) < 10.83);
+        // Sampling does not collapse the state
+        assert_eq!(state.probabilities(), bell_state().probabilities());
+    }
+
+    #[test]
+    fn test_uneven_sampling() {
+        let mut state = QuantumState::new(2);
+        state.apply_gate(&QuantumGate::Rotation(PI / 3.0, 0.0, 0.0), &[0]).unwrap();
+        state.apply_gate(&QuantumGate::Hadamard, &[1]).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(11);
+        let shots = 20_000;
+        let histogram = state.sample(shots, &mut rng).unwrap();
+        assert_eq!(histogram.len(), 4);
+        // 3 degrees of freedom
+        assert!(chi_square(&histogram, &state, shots) < 16.27);
+    }
+
+    #[test]
+    fn test_same_seed_same_samples() {
+        let state = bell_state();
+        let first = state.sample(100, &mut Xoshiro256PlusPlus::seed_from_u64(3)).unwrap();
+        let second = state.sample(100, &mut Xoshiro256PlusPlus::seed_from_u64(3)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_measurement_collapse() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(5);
+        let mut outcomes = [0; 2];
+        for _ in 0..1_000 {
+            let mut state = bell_state();
+            let first = state.measure(0, &mut rng).unwrap();
+            // The state collapsed onto |00⟩ or |11⟩, so the other qubit agrees
+            let expected = if first { 3 } else { 0 };
+            assert!((state.probabilities()[expected] - 1.0).abs() < 1e-12);
+            assert_eq!(state.measure(1, &mut rng).unwrap(), first);
+            outcomes[first as usize] += 1;
+        }
+        let chi_square = outcomes.iter().map(|&n| (n as f64 - 500.0).powi(2) / 500.0).sum::<f64>();
+        assert!(chi_square < 10.83, "{outcomes:?}");
+    }
+
+    #[test]
+    fn test_mid_circuit_measurement() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(9);
+        let mut state = QuantumState::superposition(2);
+        let first = state.measure(0, &mut rng).unwrap();
+        // Qubit 1 is still in superposition and can be used afterwards
+        let probabilities = state.probabilities();
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((probabilities[first as usize] - 0.5).abs() < 1e-12);
+        state.apply_gate(&QuantumGate::Hadamard, &[1]).unwrap();
+        assert!((state.probabilities()[first as usize] - 1.0).abs() < 1e-12);
+
+        let measured = state.measure_all(&mut rng).unwrap();
+        assert_eq!(measured, first as usize);
+        assert!(state.measure(2, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_quantum_fft() {
+        let mut state = QuantumState::new(3);
+        let qft = QuantumFFT::new(3);
+        qft.apply(&mut state).unwrap();
+
+        // Verify QFT was applied
+        assert!(state.amplitudes.iter().any(|(real, _)| real.abs() > 0.1));
+    }
+
+    #[test]
+    fn test_quantum_annealing() {
+        let mut annealer = QuantumAnnealer::new();
+        let result = annealer
+            .optimize(8.0, |x: &f64| (x + 1.5).powi(2), |x, rng| x + rng.random_range(-0.5..0.5))
+            .unwrap();
+        assert!((result + 1.5).abs() < 0.1, "{result}");
+        assert!(annealer.stats().improvements > 0);
+
+        let error = annealer.optimize(f64::NAN, |x: &f64| *x, |x, _| *x);
+        assert!(error.is_err());
+    }
+}
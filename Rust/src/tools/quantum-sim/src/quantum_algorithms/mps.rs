@@ -0,0 +1,581 @@
+//! Matrix product state simulation
+//!
+//! A matrix product state writes the amplitude of a basis state as a product of one matrix per
+//! qubit, `ψ(b₀ … bₙ₋₁) = A₀[b₀] A₁[b₁] ⋯ Aₙ₋₁[bₙ₋₁]`. Its memory grows with the entanglement
+//! across each cut of the chain rather than with `2^n`, so weakly entangled circuits on many
+//! qubits stay cheap. Gates on several qubits are applied to the contracted tensors of their
+//! sites, which are then split again with singular value decompositions. Keeping only the
+//! largest singular values bounds the bond dimension at the cost of a truncation error.
+
+use std::collections::BTreeMap;
+
+use ndarray::{Array1, Array2, Array3, ArrayView, Axis, Dimension, s};
+use num_complex::Complex64;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::circuit::{bound_gate, check_bound};
+use super::linalg::{Svd, svd};
+use super::{
+    Backend, ExecutionResult, Operation, QuantumCircuit, QuantumGate, QuantumState, bitstring,
+    check_qubits,
+};
+use crate::{QuantumError, QuantumResult};
+
+/// Singular values this far below the largest are treated as zero when moving the
+/// orthogonality center, which does not change the state
+const ZERO_SINGULAR_VALUE: f64 = 1e-14;
+
+/// Largest number of qubits [`MatrixProductState::to_state`] expands
+const MAX_DENSE_QUBITS: usize = 20;
+
+/// How much of the state is kept after each singular value decomposition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MpsConfig {
+    /// Largest bond dimension between two neighbouring qubits
+    pub max_bond_dimension: usize,
+    /// Singular values below `cutoff` times the largest one are discarded
+    pub cutoff: f64,
+}
+
+impl Default for MpsConfig {
+    fn default() -> Self {
+        Self { max_bond_dimension: 64, cutoff: 1e-12 }
+    }
+}
+
+/// State of a chain of qubits as one tensor per qubit
+///
+/// Tensor `i` has the shape `(left bond, 2, right bond)`. The state is kept in mixed canonical
+/// form around an orthogonality center, so the singular values of a cut through the center
+/// are the Schmidt coefficients of the state and truncating them is optimal.
+#[derive(Debug, Clone)]
+pub struct MatrixProductState {
+    tensors: Vec<Array3<Complex64>>,
+    /// Site whose tensor carries the norm of the state
+    center: usize,
+    config: MpsConfig,
+    truncation_error: f64,
+}
+
+impl MatrixProductState {
+    /// All `num_qubits` qubits in |0⟩
+    pub fn new(num_qubits: usize, config: MpsConfig) -> QuantumResult<Self> {
+        if num_qubits == 0 {
+            return Err(QuantumError::SimulationError(
+                "a matrix product state needs at least one qubit".to_string(),
+            ));
+        }
+        if config.max_bond_dimension == 0 {
+            return Err(QuantumError::SimulationError(
+                "the bond dimension must be at least 1".to_string(),
+            ));
+        }
+        let mut zero = Array3::zeros((1, 2, 1));
+        zero[[0, 0, 0]] = Complex64::from(1.0);
+        Ok(Self { tensors: vec![zero; num_qubits], center: 0, config, truncation_error: 0.0 })
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.tensors.len()
+    }
+
+    pub fn config(&self) -> MpsConfig {
+        self.config
+    }
+
+    /// Dimensions of the `n - 1` bonds between neighbouring qubits
+    pub fn bond_dimensions(&self) -> Vec<usize> {
+        self.tensors[1..].iter().map(|tensor| tensor.dim().0).collect()
+    }
+
+    /// Total weight of the singular values discarded so far
+    ///
+    /// Each truncation discards the squared singular values `ε` it drops and lowers the
+    /// fidelity with the exact state by `ε`, so the sum estimates `1 - F`.
+    pub fn truncation_error(&self) -> f64 {
+        self.truncation_error
+    }
+
+    /// Apply `gate` to `qubits`
+    ///
+    /// Gates on qubits that are not neighbours are applied after moving the qubits next to
+    /// each other with SWAP gates, which are undone afterwards.
+    pub fn apply_gate(&mut self, gate: &QuantumGate, qubits: &[usize]) -> QuantumResult<()> {
+        check_qubits(gate, qubits, self.num_qubits())?;
+        let matrix = gate.matrix();
+        if let [qubit] = qubits {
+            self.apply_single(&matrix, *qubit);
+            return Ok(());
+        }
+
+        // Move the qubits, from left to right, to the sites just after the leftmost one
+        let mut order: Vec<usize> = (0..qubits.len()).collect();
+        order.sort_by_key(|&i| qubits[i]);
+        let first = qubits[order[0]];
+        let mut sites = qubits.to_vec();
+        let mut swaps = Vec::new();
+        for (offset, &i) in order.iter().enumerate() {
+            while sites[i] > first + offset {
+                swaps.push(sites[i] - 1);
+                self.swap_sites(sites[i] - 1);
+                sites[i] -= 1;
+            }
+        }
+        self.apply_block(&matrix, &sites);
+        for &site in swaps.iter().rev() {
+            self.swap_sites(site);
+        }
+        Ok(())
+    }
+
+    fn apply_single(&mut self, matrix: &Array2<Complex64>, qubit: usize) {
+        let tensor = &mut self.tensors[qubit];
+        let old = tensor.clone();
+        for bit in 0..2 {
+            let mut slice = tensor.slice_mut(s![.., bit, ..]);
+            slice.assign(&(&old.slice(s![.., 0, ..]) * matrix[[bit, 0]]));
+            slice.scaled_add(matrix[[bit, 1]], &old.slice(s![.., 1, ..]));
+        }
+    }
+
+    fn swap_sites(&mut self, site: usize) {
+        self.apply_block(&QuantumGate::<f64>::SWAP.matrix(), &[site, site + 1]);
+    }
+
+    /// Apply `matrix` to the contiguous sites in `sites`, the site of each of its qubits in the
+    /// little-endian order of [`QuantumGate::matrix`]
+    fn apply_block(&mut self, matrix: &Array2<Complex64>, sites: &[usize]) {
+        let count = sites.len();
+        let first = *sites.iter().min().expect("gate acts on qubits");
+        self.move_center(first);
+
+        // Contract the tensors of the sites into one, with the first site as the highest bit
+        // of the physical index
+        let mut block = self.tensors[first].clone();
+        for site in first + 1..first + count {
+            block = contract(&block, &self.tensors[site]);
+        }
+
+        let dimension = 1 << count;
+        let gate_index: Vec<usize> = (0..dimension)
+            .map(|index| {
+                sites
+                    .iter()
+                    .enumerate()
+                    .map(|(bit, &site)| ((index >> (count - 1 - (site - first))) & 1) << bit)
+                    .sum()
+            })
+            .collect();
+        let (left, _, right) = block.dim();
+        let mut updated = Array3::<Complex64>::zeros((left, dimension, right));
+        for row in 0..dimension {
+            let mut slice = updated.slice_mut(s![.., row, ..]);
+            for column in 0..dimension {
+                let element = matrix[[gate_index[row], gate_index[column]]];
+                if element != Complex64::from(0.0) {
+                    slice.scaled_add(element, &block.slice(s![.., column, ..]));
+                }
+            }
+        }
+
+        // Split off one site at a time, moving the center to the right
+        let mut rest = updated;
+        for site in first..first + count - 1 {
+            let (left, physical, right) = rest.dim();
+            let half = physical / 2;
+            let matrix = reshape2(&rest.view(), (left * 2, half * right));
+            let Svd { u, s, vt } = svd(&matrix);
+            let (kept, scale) = self.truncate(&s);
+            self.tensors[site] = reshape3(&u.slice(s![.., ..kept]), (left, 2, kept));
+            let weights = s.slice(s![..kept]).mapv(|value| Complex64::from(value * scale));
+            let carried = &vt.slice(s![..kept, ..]) * &weights.insert_axis(Axis(1));
+            rest = reshape3(&carried.view(), (kept, half, right));
+        }
+        self.tensors[first + count - 1] = rest;
+        self.center = first + count - 1;
+    }
+
+    /// Number of singular values in `s` to keep and the factor that restores the norm of the
+    /// state after dropping the others, whose weight is added to the truncation error
+    fn truncate(&mut self, s: &Array1<f64>) -> (usize, f64) {
+        let largest = s[0];
+        let kept = s
+            .iter()
+            .take(self.config.max_bond_dimension)
+            .take_while(|&&value| value > self.config.cutoff * largest)
+            .count()
+            .max(1);
+        let total: f64 = s.iter().map(|value| value * value).sum();
+        let discarded: f64 = s.iter().skip(kept).map(|value| value * value).sum();
+        if discarded == 0.0 {
+            return (kept, 1.0);
+        }
+        self.truncation_error += discarded / total;
+        (kept, (total / (total - discarded)).sqrt())
+    }
+
+    /// Move the orthogonality center to `site` without truncating
+    fn move_center(&mut self, site: usize) {
+        while self.center < site {
+            let center = self.center;
+            let (left, _, right) = self.tensors[center].dim();
+            let matrix = reshape2(&self.tensors[center].view(), (left * 2, right));
+            let Svd { u, s, vt } = svd(&matrix);
+            let kept = nonzero_count(&s);
+            self.tensors[center] = reshape3(&u.slice(s![.., ..kept]), (left, 2, kept));
+            let weights = s.slice(s![..kept]).mapv(Complex64::from).insert_axis(Axis(1));
+            let carried = &vt.slice(s![..kept, ..]) * &weights;
+            self.tensors[center + 1] = absorb_left(&carried, &self.tensors[center + 1]);
+            self.center += 1;
+        }
+        while self.center > site {
+            let center = self.center;
+            let (left, _, right) = self.tensors[center].dim();
+            let matrix = reshape2(&self.tensors[center].view(), (left, 2 * right));
+            let Svd { u, s, vt } = svd(&matrix);
+            let kept = nonzero_count(&s);
+            self.tensors[center] = reshape3(&vt.slice(s![..kept, ..]), (kept, 2, right));
+            let weights = s.slice(s![..kept]).mapv(Complex64::from).insert_axis(Axis(0));
+            let carried = &u.slice(s![.., ..kept]) * &weights;
+            self.tensors[center - 1] = absorb_right(&self.tensors[center - 1], &carried);
+            self.center -= 1;
+        }
+    }
+
+    /// Probability of measuring 1 on `qubit`
+    pub fn probability_of_one(&mut self, qubit: usize) -> QuantumResult<f64> {
+        self.check_qubit(qubit)?;
+        self.move_center(qubit);
+        let tensor = &self.tensors[qubit];
+        let weight =
+            |bit: usize| tensor.slice(s![.., bit, ..]).iter().map(|x| x.norm_sqr()).sum::<f64>();
+        let (zero, one) = (weight(0), weight(1));
+        Ok(one / (zero + one))
+    }
+
+    /// Measure `qubit` in the computational basis, collapsing the state onto the outcome
+    pub fn measure<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> QuantumResult<bool> {
+        let one = self.probability_of_one(qubit)?;
+        let outcome = rng.random::<f64>() < one;
+        let probability = if outcome { one } else { 1.0 - one };
+        let tensor = &mut self.tensors[qubit];
+        tensor.slice_mut(s![.., usize::from(!outcome), ..]).fill(Complex64::from(0.0));
+        tensor.mapv_inplace(|x| x / probability.sqrt());
+        Ok(outcome)
+    }
+
+    /// Put `qubit` back into |0⟩
+    pub fn reset<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> QuantumResult<()> {
+        if self.measure(qubit, rng)? {
+            self.apply_single(&QuantumGate::<f64>::PauliX.matrix(), qubit);
+        }
+        Ok(())
+    }
+
+    fn check_qubit(&self, qubit: usize) -> QuantumResult<()> {
+        if qubit >= self.num_qubits() {
+            return Err(QuantumError::SimulationError(format!(
+                "qubit {qubit} is out of range for {} qubits",
+                self.num_qubits()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Amplitude of the basis state `index`, whose bit `i` is the value of qubit `i`
+    pub fn amplitude(&self, index: usize) -> Complex64 {
+        let mut row = Array2::<Complex64>::eye(1);
+        for (qubit, tensor) in self.tensors.iter().enumerate() {
+            let bit = (index >> qubit) & 1;
+            row = row.dot(&tensor.slice(s![.., bit, ..]));
+        }
+        row[[0, 0]]
+    }
+
+    /// The state vector of the state, for states of at most 20 qubits
+    pub fn to_state(&self) -> QuantumResult<QuantumState> {
+        if self.num_qubits() > MAX_DENSE_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "a state vector of {} qubits is too large to expand",
+                self.num_qubits()
+            )));
+        }
+        let mut state = QuantumState::new(self.num_qubits());
+        for (index, amplitude) in state.amplitudes.iter_mut().enumerate() {
+            let value = self.amplitude(index);
+            *amplitude = (value.re, value.im);
+        }
+        Ok(state)
+    }
+}
+
+/// Number of singular values that are not negligible next to the largest
+fn nonzero_count(s: &Array1<f64>) -> usize {
+    s.iter().take_while(|&&value| value > ZERO_SINGULAR_VALUE * s[0]).count().max(1)
+}
+
+/// Contract the right bond of `a` with the left bond of `b`, merging their physical indices
+/// with those of `a` as the high bits
+fn contract(a: &Array3<Complex64>, b: &Array3<Complex64>) -> Array3<Complex64> {
+    let (left, physical_a, bond) = a.dim();
+    let (_, physical_b, right) = b.dim();
+    let a = reshape2(&a.view(), (left * physical_a, bond));
+    let b = reshape2(&b.view(), (bond, physical_b * right));
+    reshape3(&a.dot(&b).view(), (left, physical_a * physical_b, right))
+}
+
+/// Matrix `carried` of shape `(k, bond)` contracted into the left bond of `tensor`
+fn absorb_left(carried: &Array2<Complex64>, tensor: &Array3<Complex64>) -> Array3<Complex64> {
+    let (bond, physical, right) = tensor.dim();
+    let matrix = reshape2(&tensor.view(), (bond, physical * right));
+    reshape3(&carried.dot(&matrix).view(), (carried.nrows(), physical, right))
+}
+
+/// Matrix `carried` of shape `(bond, k)` contracted into the right bond of `tensor`
+fn absorb_right(tensor: &Array3<Complex64>, carried: &Array2<Complex64>) -> Array3<Complex64> {
+    let (left, physical, bond) = tensor.dim();
+    let matrix = reshape2(&tensor.view(), (left * physical, bond));
+    reshape3(&matrix.dot(carried).view(), (left, physical, carried.ncols()))
+}
+
+/// Row-major reshape of `array`, copying it if its layout requires
+fn reshape2<D: Dimension>(
+    array: &ArrayView<'_, Complex64, D>,
+    shape: (usize, usize),
+) -> Array2<Complex64> {
+    array.to_shape(shape).expect("sizes match").into_owned()
+}
+
+fn reshape3<D: Dimension>(
+    array: &ArrayView<'_, Complex64, D>,
+    shape: (usize, usize, usize),
+) -> Array3<Complex64> {
+    array.to_shape(shape).expect("sizes match").into_owned()
+}
+
+/// Simulator that keeps the state as a matrix product state
+///
+/// Circuits with little entanglement run on many more qubits than a state vector allows, and
+/// the bond dimension limit of [`MpsConfig`] trades accuracy for speed on the others.
+pub struct MpsBackend {
+    rng: Xoshiro256PlusPlus,
+    config: MpsConfig,
+    truncation_error: f64,
+}
+
+impl MpsBackend {
+    /// Backend whose measurements are drawn from a generator seeded with `seed`
+    pub fn new(seed: u64) -> Self {
+        Self::with_config(seed, MpsConfig::default())
+    }
+
+    pub fn with_config(seed: u64, config: MpsConfig) -> Self {
+        Self { rng: Xoshiro256PlusPlus::seed_from_u64(seed), config, truncation_error: 0.0 }
+    }
+
+    pub fn config(&self) -> MpsConfig {
+        self.config
+    }
+
+    /// Largest truncation error of the shots of the last run
+    pub fn truncation_error(&self) -> f64 {
+        self.truncation_error
+    }
+
+    /// State at the end of a circuit without measurements, resets or conditions
+    pub fn state(&self, circuit: &QuantumCircuit) -> QuantumResult<MatrixProductState> {
+        check_bound(circuit)?;
+        let mut state = MatrixProductState::new(circuit.num_qubits(), self.config)?;
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                _ if instruction.condition.is_some() => {
+                    return Err(QuantumError::SimulationError(
+                        "the state of a circuit with conditions is not defined".to_string(),
+                    ));
+                }
+                Operation::Gate { gate, qubits } => state.apply_gate(&bound_gate(gate)?, qubits)?,
+                Operation::Barrier { .. } => {}
+                Operation::Measure { .. } | Operation::Reset { .. } => {
+                    return Err(QuantumError::SimulationError(
+                        "the state of a circuit with measurements is not defined".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    /// Evolve the state once and measure a copy of it for every shot
+    fn run_sampled(
+        &mut self,
+        circuit: &QuantumCircuit,
+        shots: usize,
+    ) -> QuantumResult<ExecutionResult> {
+        let mut state = MatrixProductState::new(circuit.num_qubits(), self.config)?;
+        let mut measurements = Vec::new();
+        for instruction in circuit.instructions() {
+            match &instruction.operation {
+                Operation::Gate { gate, qubits } => state.apply_gate(&bound_gate(gate)?, qubits)?,
+                Operation::Measure { qubit, clbit } => measurements.push((*qubit, *clbit)),
+                Operation::Reset { .. } | Operation::Barrier { .. } => {}
+            }
+        }
+        self.truncation_error = state.truncation_error();
+
+        let mut counts = BTreeMap::new();
+        for _ in 0..shots {
+            let mut shot = state.clone();
+            let mut clbits = vec![false; circuit.num_clbits()];
+            for &(qubit, clbit) in &measurements {
+                clbits[clbit] = shot.measure(qubit, &mut self.rng)?;
+            }
+            *counts.entry(bitstring(&clbits)).or_insert(0) += 1;
+        }
+        Ok(ExecutionResult { shots, counts })
+    }
+
+    fn run_shot(&mut self, circuit: &QuantumCircuit) -> QuantumResult<Vec<bool>> {
+        let mut state = MatrixProductState::new(circuit.num_qubits(), self.config)?;
+        let mut clbits = vec![false; circuit.num_clbits()];
+        for instruction in circuit.instructions() {
+            if let Some(condition) = instruction.condition
+                && circuit.register_value(condition.register, &clbits) != condition.value
+            {
+                continue;
+            }
+            match &instruction.operation {
+                Operation::Gate { gate, qubits } => state.apply_gate(&bound_gate(gate)?, qubits)?,
+                Operation::Measure { qubit, clbit } => {
+                    clbits[*clbit] = state.measure(*qubit, &mut self.rng)?;
+                }
+                Operation::Reset { qubit } => state.reset(*qubit, &mut self.rng)?,
+                Operation::Barrier { .. } => {}
+            }
+        }
+        self.truncation_error = self.truncation_error.max(state.truncation_error());
+        Ok(clbits)
+    }
+}
+
+impl Backend for MpsBackend {
+    fn name(&self) -> &'static str {
+        "mps"
+    }
+
+    fn run(&mut self, circuit: &QuantumCircuit, shots: usize) -> QuantumResult<ExecutionResult> {
+        check_bound(circuit)?;
+        if circuit.has_only_final_measurements() {
+            return self.run_sampled(circuit, shots);
+        }
+        self.truncation_error = 0.0;
+        let mut counts = BTreeMap::new();
+        for _ in 0..shots {
+            let clbits = self.run_shot(circuit)?;
+            *counts.entry(bitstring(&clbits)).or_insert(0) += 1;
+        }
+        Ok(ExecutionResult { shots, counts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::quantum_algorithms::{StateVectorBackend, UnitaryMatrix};
+
+    fn fidelity(a: &QuantumState, b: &QuantumState) -> f64 {
+        let overlap: Complex64 = a
+            .amplitudes
+            .iter()
+            .zip(&b.amplitudes)
+            .map(|(x, y)| Complex64::new(x.0, -x.1) * Complex64::new(y.0, y.1))
+            .sum();
+        overlap.norm_sqr()
+    }
+
+    /// Circuit of random rotations and entangling gates between any qubits
+    fn random_circuit(num_qubits: usize, layers: usize, seed: u64) -> QuantumCircuit {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let mut circuit = QuantumCircuit::new(num_qubits, 0);
+        for _ in 0..layers {
+            for qubit in 0..num_qubits {
+                circuit.u(rng.random::<f64>() * PI, rng.random::<f64>() * PI, 0.3, qubit).unwrap();
+            }
+            let a = rng.random_range(0..num_qubits);
+            let b = (a + rng.random_range(1..num_qubits)) % num_qubits;
+            let c = (b + rng.random_range(1..num_qubits - 1)) % num_qubits;
+            let c = if c == a { (c + 1) % num_qubits } else { c };
+            circuit.cx(a, b).unwrap().crz(rng.random::<f64>(), b, a).unwrap().ccx(c, a, b).unwrap();
+            circuit.iswap(b, c).unwrap();
+        }
+        circuit
+    }
+
+    #[test]
+    fn test_matches_state_vector() {
+        for seed in 0..5 {
+            let mut circuit = random_circuit(6, 6, seed);
+            let matrix = UnitaryMatrix::new(QuantumGate::<f64>::ISwap.matrix()).unwrap();
+            circuit.unitary(matrix, &[5, 1]).unwrap();
+            let expected = StateVectorBackend::new(0).statevector(&circuit).unwrap();
+            let state = MpsBackend::new(0).state(&circuit).unwrap();
+            assert!(state.truncation_error() < 1e-12, "{}", state.truncation_error());
+            let overlap = fidelity(&expected, &state.to_state().unwrap());
+            assert!((overlap - 1.0).abs() < 1e-10, "seed {seed}: {overlap}");
+        }
+    }
+
+    #[test]
+    fn test_truncation_error() {
+        // A single truncation to a product state keeps the larger Schmidt coefficient
+        let config = MpsConfig { max_bond_dimension: 1, ..MpsConfig::default() };
+        let mut circuit = QuantumCircuit::new(2, 0);
+        circuit.ry(1.0, 0).unwrap().cx(0, 1).unwrap();
+        let state = MpsBackend::with_config(0, config).state(&circuit).unwrap();
+        let discarded = 0.5f64.sin().powi(2);
+        assert!((state.truncation_error() - discarded).abs() < 1e-12);
+        let expected = StateVectorBackend::new(0).statevector(&circuit).unwrap();
+        let overlap = fidelity(&expected, &state.to_state().unwrap());
+        assert!((overlap - (1.0 - discarded)).abs() < 1e-12, "{overlap}");
+
+        let config = MpsConfig { max_bond_dimension: 2, ..MpsConfig::default() };
+        let circuit = random_circuit(8, 8, 1);
+        let state = MpsBackend::with_config(0, config).state(&circuit).unwrap();
+        assert!(state.bond_dimensions().iter().all(|&bond| bond <= 2));
+        let expected = StateVectorBackend::new(0).statevector(&circuit).unwrap();
+        let infidelity = 1.0 - fidelity(&expected, &state.to_state().unwrap());
+        assert!(state.truncation_error() > 0.01, "{}", state.truncation_error());
+        assert!(infidelity < 2.0 * state.truncation_error(), "{infidelity}");
+    }
+
+    #[test]
+    fn test_large_ghz_state() {
+        let num_qubits = 200;
+        let mut circuit = QuantumCircuit::new(num_qubits, 64);
+        circuit.h(0).unwrap();
+        for qubit in 1..num_qubits {
+            circuit.cx(qubit - 1, qubit).unwrap().t(qubit).unwrap();
+        }
+        let state = MpsBackend::new(0).state(&circuit).unwrap();
+        assert!(state.bond_dimensions().iter().all(|&bond| bond == 2));
+        for clbit in 0..64 {
+            circuit.measure(clbit * 3, clbit).unwrap();
+        }
+        let mut backend = MpsBackend::new(1);
+        let result = backend.run(&circuit, 20).unwrap();
+        assert_eq!(result.counts.keys().collect::<Vec<_>>(), [&"0".repeat(64), &"1".repeat(64)]);
+        assert_eq!(backend.truncation_error(), 0.0);
+    }
+
+    #[test]
+    fn test_mid_circuit_measurement() {
+        // Measure one half of a Bell pair, reset it and copy the other half into it
+        let mut circuit = QuantumCircuit::new(3, 3);
+        circuit.h(0).unwrap().cx(0, 2).unwrap().measure(0, 0).unwrap().reset(0).unwrap();
+        circuit.cx(2, 0).unwrap().measure(0, 1).unwrap().measure(2, 2).unwrap();
+        let result = MpsBackend::new(2).run(&circuit, 200).unwrap();
+        assert_eq!(result.counts.keys().collect::<Vec<_>>(), ["000", "111"]);
+    }
+}
@@ -0,0 +1,351 @@
+//! Noise channels and noise models
+//!
+//! A [`KrausChannel`] maps a density matrix `ρ` to `Σ K ρ K†` over its Kraus operators `K`.
+//! A [`NoiseModel`] says which channel follows each type of gate and how often measured bits
+//! are read wrongly, so a circuit can be run as it would on hardware with those error rates.
+
+use std::collections::HashMap;
+
+use ndarray::{Array2, array};
+use num_complex::Complex64;
+use rand::Rng;
+
+use super::{DensityMatrix, Pauli, QuantumGate};
+use crate::{QuantumError, QuantumResult};
+
+/// Largest deviation from the identity accepted in `Σ K†K`
+const COMPLETENESS_TOLERANCE: f64 = 1e-9;
+
+/// Quantum channel given by its Kraus operators
+///
+/// The operators use the qubit order of [`QuantumGate::matrix`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct KrausChannel {
+    operators: Vec<Array2<Complex64>>,
+    num_qubits: usize,
+}
+
+impl KrausChannel {
+    /// Channel with the Kraus operators `operators`
+    ///
+    /// The operators must be `2^k × 2^k` matrices for the same `k ≥ 1` with `Σ K†K = I`, so
+    /// that the channel preserves the trace.
+    pub fn new(operators: Vec<Array2<Complex64>>) -> QuantumResult<Self> {
+        let Some(first) = operators.first() else {
+            return Err(QuantumError::SimulationError(
+                "a channel needs at least one Kraus operator".to_string(),
+            ));
+        };
+        let dimension = first.nrows();
+        if dimension < 2 || !dimension.is_power_of_two() {
+            return Err(QuantumError::SimulationError(format!(
+                "Kraus operators must act on qubits, but have {dimension} rows"
+            )));
+        }
+        if operators.iter().any(|operator| operator.dim() != (dimension, dimension)) {
+            return Err(QuantumError::SimulationError(
+                "Kraus operators must be square matrices of the same size".to_string(),
+            ));
+        }
+        let mut sum = Array2::<Complex64>::zeros((dimension, dimension));
+        for operator in &operators {
+            sum = sum + operator.t().mapv(|element| element.conj()).dot(operator);
+        }
+        let identity = Array2::<Complex64>::eye(dimension);
+        let deviation = (sum - identity).iter().map(|element| element.norm()).fold(0.0, f64::max);
+        if deviation > COMPLETENESS_TOLERANCE {
+            return Err(QuantumError::SimulationError(format!(
+                "Kraus operators do not preserve the trace, Σ K†K differs from I by {deviation:e}"
+            )));
+        }
+        Ok(Self { operators, num_qubits: dimension.trailing_zeros() as usize })
+    }
+
+    /// Depolarizing channel, `ρ → (1 - p) ρ + p I / 2^n` on `num_qubits` qubits
+    pub fn depolarizing(probability: f64, num_qubits: usize) -> QuantumResult<Self> {
+        check_probability("depolarizing probability", probability)?;
+        if num_qubits == 0 {
+            return Err(QuantumError::SimulationError(
+                "a depolarizing channel needs at least one qubit".to_string(),
+            ));
+        }
+        let paulis = [
+            Array2::<Complex64>::eye(2),
+            QuantumGate::<f64>::PauliX.matrix(),
+            QuantumGate::<f64>::PauliY.matrix(),
+            QuantumGate::<f64>::PauliZ.matrix(),
+        ];
+        // Averaging over all 4^n Pauli products gives I / 2^n
+        let count = 1usize << (2 * num_qubits);
+        let mut operators = Vec::with_capacity(count);
+        for index in 0..count {
+            let mut operator = Array2::<Complex64>::eye(1);
+            for qubit in 0..num_qubits {
+                let pauli = &paulis[(index >> (2 * qubit)) & 3];
+                operator = ndarray::linalg::kron(pauli, &operator);
+            }
+            let weight = if index == 0 {
+                1.0 - probability + probability / count as f64
+            } else {
+                probability / count as f64
+            };
+            operators.push(operator * Complex64::from(weight.sqrt()));
+        }
+        Self::new(operators)
+    }
+
+    /// Energy relaxation from |1⟩ to |0⟩ with probability `gamma`
+    pub fn amplitude_damping(gamma: f64) -> QuantumResult<Self> {
+        check_probability("damping probability", gamma)?;
+        Self::new(vec![
+            real_matrix([[1.0, 0.0], [0.0, (1.0 - gamma).sqrt()]]),
+            real_matrix([[0.0, gamma.sqrt()], [0.0, 0.0]]),
+        ])
+    }
+
+    /// Loss of phase coherence without energy loss, scaling the off-diagonal elements by
+    /// `sqrt(1 - lambda)`
+    pub fn phase_damping(lambda: f64) -> QuantumResult<Self> {
+        check_probability("damping probability", lambda)?;
+        Self::new(vec![
+            real_matrix([[1.0, 0.0], [0.0, (1.0 - lambda).sqrt()]]),
+            real_matrix([[0.0, 0.0], [0.0, lambda.sqrt()]]),
+        ])
+    }
+
+    /// X error with probability `probability`
+    pub fn bit_flip(probability: f64) -> QuantumResult<Self> {
+        Self::pauli_error(probability, QuantumGate::PauliX)
+    }
+
+    /// Z error with probability `probability`
+    pub fn phase_flip(probability: f64) -> QuantumResult<Self> {
+        Self::pauli_error(probability, QuantumGate::PauliZ)
+    }
+
+    fn pauli_error(probability: f64, pauli: QuantumGate) -> QuantumResult<Self> {
+        check_probability("flip probability", probability)?;
+        Self::new(vec![
+            Array2::<Complex64>::eye(2) * Complex64::from((1.0 - probability).sqrt()),
+            pauli.matrix() * Complex64::from(probability.sqrt()),
+        ])
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    pub fn operators(&self) -> &[Array2<Complex64>] {
+        &self.operators
+    }
+}
+
+fn real_matrix(rows: [[f64; 2]; 2]) -> Array2<Complex64> {
+    array![
+        [Complex64::from(rows[0][0]), Complex64::from(rows[0][1])],
+        [Complex64::from(rows[1][0]), Complex64::from(rows[1][1])],
+    ]
+}
+
+fn check_probability(what: &str, probability: f64) -> QuantumResult<()> {
+    if !(0.0..=1.0).contains(&probability) {
+        return Err(QuantumError::SimulationError(format!(
+            "{what} must be between 0 and 1, got {probability}"
+        )));
+    }
+    Ok(())
+}
+
+/// Probabilities of reading a measured bit wrongly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadoutError {
+    /// Probability that a measured 0 is read as 1
+    pub flip_zero: f64,
+    /// Probability that a measured 1 is read as 0
+    pub flip_one: f64,
+}
+
+impl ReadoutError {
+    pub fn new(flip_zero: f64, flip_one: f64) -> QuantumResult<Self> {
+        check_probability("readout error probability", flip_zero)?;
+        check_probability("readout error probability", flip_one)?;
+        Ok(Self { flip_zero, flip_one })
+    }
+
+    /// Bit read for the measured bit `bit`
+    pub fn read<R: Rng + ?Sized>(&self, bit: bool, rng: &mut R) -> bool {
+        let flip = if bit { self.flip_one } else { self.flip_zero };
+        bit != (rng.random::<f64>() < flip)
+    }
+}
+
+/// Channel that applies `X`, `Y` or `Z` with the given probabilities
+///
+/// Pauli channels can be sampled one error at a time, which is how errors are injected into
+/// stabilizer simulations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PauliChannel {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl PauliChannel {
+    pub fn new(x: f64, y: f64, z: f64) -> QuantumResult<Self> {
+        for probability in [x, y, z] {
+            check_probability("Pauli error probability", probability)?;
+        }
+        check_probability("total Pauli error probability", x + y + z)?;
+        Ok(Self { x, y, z })
+    }
+
+    /// X error with probability `probability`
+    pub fn bit_flip(probability: f64) -> QuantumResult<Self> {
+        Self::new(probability, 0.0, 0.0)
+    }
+
+    /// Z error with probability `probability`
+    pub fn phase_flip(probability: f64) -> QuantumResult<Self> {
+        Self::new(0.0, 0.0, probability)
+    }
+
+    /// The channel of [`KrausChannel::depolarizing`] on one qubit, with each Pauli error
+    /// occurring with probability `probability / 4`
+    pub fn depolarizing(probability: f64) -> QuantumResult<Self> {
+        check_probability("depolarizing probability", probability)?;
+        let each = probability / 4.0;
+        Self::new(each, each, each)
+    }
+
+    /// Error drawn from the channel, `I` if none occurs
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Pauli {
+        let draw = rng.random::<f64>();
+        if draw < self.x {
+            Pauli::X
+        } else if draw < self.x + self.y {
+            Pauli::Y
+        } else if draw < self.x + self.y + self.z {
+            Pauli::Z
+        } else {
+            Pauli::I
+        }
+    }
+}
+
+/// Errors of a simulated device
+///
+/// Gate errors are looked up by [`QuantumGate::name`], so `"cx"` selects CNOTs and `"ctrl"`
+/// every gate made with [`QuantumGate::controlled`]. A channel on one qubit that follows a
+/// gate on several qubits acts on each of them; otherwise the channel must act on as many
+/// qubits as the gate.
+#[derive(Debug, Clone, Default)]
+pub struct NoiseModel {
+    /// Channel applied after each gate with the given name
+    pub gate_errors: HashMap<String, KrausChannel>,
+    /// Channel applied after gates without an entry in `gate_errors`
+    pub default_gate_error: Option<KrausChannel>,
+    pub readout_error: Option<ReadoutError>,
+}
+
+impl NoiseModel {
+    /// Model without any errors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_gate_error(mut self, gate: &str, channel: KrausChannel) -> Self {
+        self.gate_errors.insert(gate.to_string(), channel);
+        self
+    }
+
+    pub fn with_default_gate_error(mut self, channel: KrausChannel) -> Self {
+        self.default_gate_error = Some(channel);
+        self
+    }
+
+    pub fn with_readout_error(mut self, error: ReadoutError) -> Self {
+        self.readout_error = Some(error);
+        self
+    }
+
+    /// Channel that follows gates called `gate`, if any
+    pub fn gate_error(&self, gate: &str) -> Option<&KrausChannel> {
+        self.gate_errors.get(gate).or(self.default_gate_error.as_ref())
+    }
+
+    /// Apply the error of a gate called `gate` that acted on `qubits`
+    pub fn apply_gate_error(
+        &self,
+        density: &mut DensityMatrix,
+        gate: &str,
+        qubits: &[usize],
+    ) -> QuantumResult<()> {
+        let Some(channel) = self.gate_error(gate) else {
+            return Ok(());
+        };
+        if channel.num_qubits() == qubits.len() {
+            density.apply_channel(channel, qubits)
+        } else if channel.num_qubits() == 1 {
+            qubits.iter().try_for_each(|&qubit| density.apply_channel(channel, &[qubit]))
+        } else {
+            Err(QuantumError::SimulationError(format!(
+                "the error of gate `{gate}` acts on {} qubits, but the gate acts on {}",
+                channel.num_qubits(),
+                qubits.len()
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    #[test]
+    fn test_standard_channels_preserve_trace() {
+        // `new` rejects operators with Σ K†K ≠ I
+        for probability in [0.0, 0.3, 1.0] {
+            KrausChannel::depolarizing(probability, 1).unwrap();
+            assert_eq!(KrausChannel::depolarizing(probability, 2).unwrap().operators().len(), 16);
+            KrausChannel::amplitude_damping(probability).unwrap();
+            KrausChannel::phase_damping(probability).unwrap();
+            KrausChannel::bit_flip(probability).unwrap();
+            KrausChannel::phase_flip(probability).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_invalid_channels() {
+        assert!(KrausChannel::bit_flip(1.5).is_err());
+        assert!(KrausChannel::amplitude_damping(-0.1).is_err());
+        assert!(KrausChannel::depolarizing(0.1, 0).is_err());
+        assert!(KrausChannel::new(vec![]).is_err());
+        assert!(KrausChannel::new(vec![real_matrix([[1.0, 0.0], [0.0, 0.5]])]).is_err());
+        assert!(ReadoutError::new(0.1, 2.0).is_err());
+        assert!(PauliChannel::new(0.5, 0.3, 0.3).is_err());
+    }
+
+    #[test]
+    fn test_readout_error() {
+        let error = ReadoutError::new(0.2, 0.0).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        let flipped = (0..10_000).filter(|_| error.read(false, &mut rng)).count();
+        assert!((flipped as f64 / 10_000.0 - 0.2).abs() < 0.02, "{flipped}");
+        assert!((0..100).all(|_| error.read(true, &mut rng)));
+    }
+
+    #[test]
+    fn test_gate_error_lookup() {
+        let flip = KrausChannel::bit_flip(0.1).unwrap();
+        let depolarizing = KrausChannel::depolarizing(0.01, 1).unwrap();
+        let model = NoiseModel::new()
+            .with_gate_error("cx", flip.clone())
+            .with_default_gate_error(depolarizing.clone());
+        assert_eq!(model.gate_error("cx"), Some(&flip));
+        assert_eq!(model.gate_error("h"), Some(&depolarizing));
+        assert_eq!(NoiseModel::new().gate_error("h"), None);
+    }
+}
@@ -0,0 +1,265 @@
+//! Symbolic gate parameters
+//!
+//! Angles of the gates in a [`QuantumCircuit`] are expressions over named parameters, so a
+//! circuit can be built once and run with different parameter values.
+//!
+//! [`QuantumCircuit`]: super::QuantumCircuit
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::{QuantumError, QuantumResult};
+
+/// Arithmetic expression over named parameters
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterExpr {
+    Value(f64),
+    Symbol(String),
+    Neg(Box<ParameterExpr>),
+    Add(Box<ParameterExpr>, Box<ParameterExpr>),
+    Sub(Box<ParameterExpr>, Box<ParameterExpr>),
+    Mul(Box<ParameterExpr>, Box<ParameterExpr>),
+    Div(Box<ParameterExpr>, Box<ParameterExpr>),
+}
+
+impl ParameterExpr {
+    pub fn symbol(name: impl Into<String>) -> Self {
+        ParameterExpr::Symbol(name.into())
+    }
+
+    /// Value of the expression if it does not contain any parameter
+    pub fn value(&self) -> Option<f64> {
+        match self {
+            ParameterExpr::Value(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Value of the expression with the parameters in `bindings`
+    pub fn evaluate(&self, bindings: &HashMap<String, f64>) -> QuantumResult<f64> {
+        match self.bind(bindings) {
+            ParameterExpr::Value(value) => Ok(value),
+            unbound => {
+                let names: Vec<_> = unbound.parameters().into_iter().collect();
+                Err(QuantumError::SimulationError(format!(
+                    "unbound circuit parameters: {}",
+                    names.join(", ")
+                )))
+            }
+        }
+    }
+
+    /// Replace the parameters in `bindings` by their values and fold the constant parts
+    pub fn bind(&self, bindings: &HashMap<String, f64>) -> ParameterExpr {
+        match self {
+            ParameterExpr::Value(_) => self.clone(),
+            ParameterExpr::Symbol(name) => match bindings.get(name) {
+                Some(&value) => ParameterExpr::Value(value),
+                None => self.clone(),
+            },
+            ParameterExpr::Neg(operand) => -operand.bind(bindings),
+            ParameterExpr::Add(lhs, rhs) => lhs.bind(bindings) + rhs.bind(bindings),
+            ParameterExpr::Sub(lhs, rhs) => lhs.bind(bindings) - rhs.bind(bindings),
+            ParameterExpr::Mul(lhs, rhs) => lhs.bind(bindings) * rhs.bind(bindings),
+            ParameterExpr::Div(lhs, rhs) => lhs.bind(bindings) / rhs.bind(bindings),
+        }
+    }
+
+    /// Derivative of the expression with respect to the parameter `name`
+    pub fn derivative(&self, name: &str) -> ParameterExpr {
+        let is_zero = |expr: &ParameterExpr| expr.value() == Some(0.0);
+        match self {
+            ParameterExpr::Value(_) => ParameterExpr::Value(0.0),
+            ParameterExpr::Symbol(symbol) => ParameterExpr::Value((symbol == name) as u8 as f64),
+            ParameterExpr::Neg(operand) => -operand.derivative(name),
+            ParameterExpr::Add(lhs, rhs) => lhs.derivative(name) + rhs.derivative(name),
+            ParameterExpr::Sub(lhs, rhs) => lhs.derivative(name) - rhs.derivative(name),
+            ParameterExpr::Mul(lhs, rhs) => {
+                let (d_lhs, d_rhs) = (lhs.derivative(name), rhs.derivative(name));
+                match (is_zero(&d_lhs), is_zero(&d_rhs)) {
+                    (true, true) => ParameterExpr::Value(0.0),
+                    (true, false) => (**lhs).clone() * d_rhs,
+                    (false, true) => d_lhs * (**rhs).clone(),
+                    (false, false) => d_lhs * (**rhs).clone() + (**lhs).clone() * d_rhs,
+                }
+            }
+            ParameterExpr::Div(lhs, rhs) => {
+                let (d_lhs, d_rhs) = (lhs.derivative(name), rhs.derivative(name));
+                match (is_zero(&d_lhs), is_zero(&d_rhs)) {
+                    (true, true) => ParameterExpr::Value(0.0),
+                    (false, true) => d_lhs / (**rhs).clone(),
+                    _ => {
+                        let numerator = d_lhs * (**rhs).clone() - (**lhs).clone() * d_rhs;
+                        numerator / ((**rhs).clone() * (**rhs).clone())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Names of the parameters in the expression
+    pub fn parameters(&self) -> BTreeSet<String> {
+        let mut parameters = BTreeSet::new();
+        self.collect_parameters(&mut parameters);
+        parameters
+    }
+
+    pub(crate) fn collect_parameters(&self, parameters: &mut BTreeSet<String>) {
+        match self {
+            ParameterExpr::Value(_) => {}
+            ParameterExpr::Symbol(name) => {
+                parameters.insert(name.clone());
+            }
+            ParameterExpr::Neg(operand) => operand.collect_parameters(parameters),
+            ParameterExpr::Add(lhs, rhs)
+            | ParameterExpr::Sub(lhs, rhs)
+            | ParameterExpr::Mul(lhs, rhs)
+            | ParameterExpr::Div(lhs, rhs) => {
+                lhs.collect_parameters(parameters);
+                rhs.collect_parameters(parameters);
+            }
+        }
+    }
+
+    /// Binding strength of the outermost operator, for printing parentheses
+    fn precedence(&self) -> u8 {
+        match self {
+            ParameterExpr::Add(..) | ParameterExpr::Sub(..) => 1,
+            ParameterExpr::Mul(..) | ParameterExpr::Div(..) => 2,
+            ParameterExpr::Neg(_) => 3,
+            ParameterExpr::Value(value) if *value < 0.0 => 3,
+            ParameterExpr::Value(_) | ParameterExpr::Symbol(_) => 4,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence { write!(f, "({self})") } else { write!(f, "{self}") }
+    }
+}
+
+impl fmt::Display for ParameterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterExpr::Value(value) => write!(f, "{value}"),
+            ParameterExpr::Symbol(name) => write!(f, "{name}"),
+            ParameterExpr::Neg(operand) => {
+                write!(f, "-")?;
+                operand.fmt_operand(f, 4)
+            }
+            ParameterExpr::Add(lhs, rhs) => {
+                lhs.fmt_operand(f, 1)?;
+                write!(f, " + ")?;
+                rhs.fmt_operand(f, 2)
+            }
+            ParameterExpr::Sub(lhs, rhs) => {
+                lhs.fmt_operand(f, 1)?;
+                write!(f, " - ")?;
+                rhs.fmt_operand(f, 2)
+            }
+            ParameterExpr::Mul(lhs, rhs) => {
+                lhs.fmt_operand(f, 2)?;
+                write!(f, "*")?;
+                rhs.fmt_operand(f, 3)
+            }
+            ParameterExpr::Div(lhs, rhs) => {
+                lhs.fmt_operand(f, 2)?;
+                write!(f, "/")?;
+                rhs.fmt_operand(f, 3)
+            }
+        }
+    }
+}
+
+impl From<f64> for ParameterExpr {
+    fn from(value: f64) -> Self {
+        ParameterExpr::Value(value)
+    }
+}
+
+impl From<&str> for ParameterExpr {
+    fn from(name: &str) -> Self {
+        ParameterExpr::symbol(name)
+    }
+}
+
+impl Neg for ParameterExpr {
+    type Output = ParameterExpr;
+
+    fn neg(self) -> ParameterExpr {
+        match self {
+            ParameterExpr::Value(value) => ParameterExpr::Value(-value),
+            ParameterExpr::Neg(operand) => *operand,
+            operand => ParameterExpr::Neg(Box::new(operand)),
+        }
+    }
+}
+
+macro_rules! binary_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl<T: Into<ParameterExpr>> $trait<T> for ParameterExpr {
+            type Output = ParameterExpr;
+
+            fn $method(self, rhs: T) -> ParameterExpr {
+                match (self, rhs.into()) {
+                    (ParameterExpr::Value(lhs), ParameterExpr::Value(rhs)) => {
+                        ParameterExpr::Value(lhs $op rhs)
+                    }
+                    (lhs, rhs) => ParameterExpr::$trait(Box::new(lhs), Box::new(rhs)),
+                }
+            }
+        }
+    };
+}
+
+binary_op!(Add, add, +);
+binary_op!(Sub, sub, -);
+binary_op!(Mul, mul, *);
+binary_op!(Div, div, /);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let theta = ParameterExpr::symbol("theta");
+        let expr = -(theta.clone() * 2.0) + ParameterExpr::symbol("phi") / 4.0;
+        let bindings = HashMap::from([("theta".to_string(), 0.5), ("phi".to_string(), 2.0)]);
+        assert_eq!(expr.evaluate(&bindings).unwrap(), -0.5);
+        assert_eq!(expr.parameters().into_iter().collect::<Vec<_>>(), ["phi", "theta"]);
+    }
+
+    #[test]
+    fn test_partial_binding() {
+        let expr = ParameterExpr::symbol("a") * ParameterExpr::symbol("b") + 1.0;
+        let bound = expr.bind(&HashMap::from([("a".to_string(), 3.0)]));
+        assert_eq!(bound.to_string(), "3*b + 1");
+        assert!(bound.evaluate(&HashMap::new()).is_err());
+        assert_eq!(bound.bind(&HashMap::from([("b".to_string(), 2.0)])).value(), Some(7.0));
+    }
+
+    #[test]
+    fn test_derivative() {
+        let theta = ParameterExpr::symbol("theta");
+        let phi = ParameterExpr::symbol("phi");
+        let expr = theta.clone() * theta.clone() * 3.0 - phi.clone() / theta.clone() + phi.clone();
+        let bindings = HashMap::from([("theta".to_string(), 2.0), ("phi".to_string(), 4.0)]);
+        // 6θ + φ/θ² and 1 - 1/θ
+        assert_eq!(expr.derivative("theta").evaluate(&bindings).unwrap(), 13.0);
+        assert_eq!(expr.derivative("phi").evaluate(&bindings).unwrap(), 0.5);
+        assert_eq!(expr.derivative("lambda").value(), Some(0.0));
+        assert_eq!((-theta * 0.5).derivative("theta").to_string(), "-0.5");
+    }
+
+    #[test]
+    fn test_display() {
+        let a = ParameterExpr::symbol("a");
+        let b = ParameterExpr::symbol("b");
+        assert_eq!((a.clone() - (b.clone() - 1.0)).to_string(), "a - (b - 1)");
+        assert_eq!(((a.clone() + b.clone()) * 2.0).to_string(), "(a + b)*2");
+        assert_eq!((a.clone() / (b.clone() * 2.0)).to_string(), "a/(b*2)");
+        assert_eq!((-(a + b)).to_string(), "-(a + b)");
+        assert_eq!((ParameterExpr::from(-1.5) * 2.0).to_string(), "-3");
+    }
+}
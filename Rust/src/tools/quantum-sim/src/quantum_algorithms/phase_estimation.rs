@@ -0,0 +1,203 @@
+//! Quantum phase estimation
+//!
+//! An eigenvector of a unitary `U` with eigenvalue `e^{2πiφ}` kicks the phase `2^k φ` back
+//! onto a control qubit of `U^{2^k}`. A register of `t` counting qubits in uniform
+//! superposition, each controlling one power, ends up in the Fourier transform of `2^t φ`, so
+//! the inverse transform leaves `φ` written in its bits, to `t` bits of precision.
+
+use std::collections::BTreeMap;
+
+use rand::Rng;
+
+use super::{QuantumFFT, QuantumGate, QuantumState, StateVectorBackend, UnitaryMatrix};
+use crate::{QuantumError, QuantumResult};
+
+/// Outcome of [`PhaseEstimation::estimate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseEstimate {
+    /// Phase `k / 2^t` of the most frequently measured `k`, as a fraction of a full turn
+    pub phase: f64,
+    /// How often each numerator `k` was measured
+    pub counts: BTreeMap<usize, usize>,
+    /// Probability of measuring each numerator `k`
+    pub probabilities: Vec<f64>,
+}
+
+/// Phase estimation with a fixed number of counting qubits
+pub struct PhaseEstimation {
+    precision: usize,
+}
+
+impl PhaseEstimation {
+    /// Phase estimation with `precision` counting qubits
+    pub fn new(precision: usize) -> QuantumResult<Self> {
+        if precision == 0 {
+            return Err(QuantumError::SimulationError(
+                "phase estimation needs at least one counting qubit".to_string(),
+            ));
+        }
+        Ok(Self { precision })
+    }
+
+    pub fn precision(&self) -> usize {
+        self.precision
+    }
+
+    /// Estimate the phases of `unitary` on `state`
+    ///
+    /// An eigenvector gives its eigenphase, and a superposition of eigenvectors each phase
+    /// with the weight of its eigenvector. The powers are computed by repeated squaring of
+    /// the matrix of `unitary`.
+    pub fn estimate<R: Rng + ?Sized>(
+        &self,
+        unitary: &QuantumGate,
+        state: &QuantumState,
+        shots: usize,
+        rng: &mut R,
+    ) -> QuantumResult<PhaseEstimate> {
+        let mut power = unitary.matrix();
+        self.estimate_with_powers(
+            state,
+            |exponent| {
+                if exponent > 0 {
+                    power = power.dot(&power);
+                }
+                Ok(QuantumGate::Unitary(UnitaryMatrix::new(power.clone())?))
+            },
+            shots,
+            rng,
+        )
+    }
+
+    /// Estimate the phases of a unitary on `state`, where `power(k)` returns `U^{2^k}`
+    ///
+    /// The powers are requested once each, in increasing order of `k`. Supplying them
+    /// directly lets a caller build powers more cheaply than by squaring, as the modular
+    /// multiplications of Shor's algorithm do.
+    pub fn estimate_with_powers<F, R>(
+        &self,
+        state: &QuantumState,
+        mut power: F,
+        shots: usize,
+        rng: &mut R,
+    ) -> QuantumResult<PhaseEstimate>
+    where
+        F: FnMut(usize) -> QuantumResult<QuantumGate>,
+        R: Rng + ?Sized,
+    {
+        let t = self.precision;
+        let qubits = t + state.qubit_count;
+        if qubits > StateVectorBackend::MAX_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "phase estimation needs {qubits} qubits, more than the {} that can be simulated",
+                StateVectorBackend::MAX_QUBITS
+            )));
+        }
+        if shots == 0 {
+            return Err(QuantumError::SimulationError(
+                "phase estimation needs at least one shot".to_string(),
+            ));
+        }
+
+        // The counting register takes the low qubits, where the Fourier transform acts
+        let mut joint = QuantumState::new(qubits);
+        for (index, amplitude) in joint.amplitudes.iter_mut().enumerate() {
+            *amplitude =
+                if index % (1 << t) == 0 { state.amplitudes[index >> t] } else { (0.0, 0.0) };
+        }
+        for qubit in 0..t {
+            joint.apply_gate(&QuantumGate::Hadamard, &[qubit])?;
+        }
+        let targets: Vec<usize> = (t..qubits).collect();
+        for exponent in 0..t {
+            let gate = power(exponent)?;
+            if gate.num_qubits() != targets.len() {
+                return Err(QuantumError::SimulationError(format!(
+                    "a {}-qubit unitary cannot act on a {}-qubit state",
+                    gate.num_qubits(),
+                    targets.len()
+                )));
+            }
+            // The Fourier transform treats qubit 0 as the most significant bit
+            let mut qubits = vec![t - 1 - exponent];
+            qubits.extend(&targets);
+            joint.apply_gate(&QuantumGate::controlled(gate, 1), &qubits)?;
+        }
+        QuantumFFT::new(t).apply_inverse(&mut joint)?;
+
+        let numerator = |index: usize| reverse_bits(index % (1 << t), t);
+        let mut probabilities = vec![0.0; 1 << t];
+        for (index, probability) in joint.probabilities().into_iter().enumerate() {
+            probabilities[numerator(index)] += probability;
+        }
+        let mut counts = BTreeMap::new();
+        for (index, count) in joint.sample(shots, rng)? {
+            *counts.entry(numerator(index)).or_insert(0) += count;
+        }
+        let most_frequent = counts
+            .iter()
+            .max_by_key(|&(&k, &count)| (count, std::cmp::Reverse(k)))
+            .map(|(&k, _)| k)
+            .expect("at least one shot");
+        Ok(PhaseEstimate { phase: most_frequent as f64 / (1 << t) as f64, counts, probabilities })
+    }
+}
+
+/// The lowest `bits` bits of `value` in reverse order
+fn reverse_bits(value: usize, bits: usize) -> usize {
+    (0..bits).filter(|&bit| (value >> bit) & 1 == 1).map(|bit| 1 << (bits - 1 - bit)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    #[test]
+    fn test_exact_phase() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        // |1⟩ is an eigenvector of the phase gate with phase 5/16, which four bits hold exactly
+        let mut one = QuantumState::new(1);
+        one.apply_gate(&QuantumGate::PauliX, &[0]).unwrap();
+        let estimation = PhaseEstimation::new(4).unwrap();
+        let gate = QuantumGate::Phase(2.0 * PI * 5.0 / 16.0);
+        let estimate = estimation.estimate(&gate, &one, 50, &mut rng).unwrap();
+        assert_eq!(estimate.phase, 0.3125);
+        assert_eq!(estimate.counts, BTreeMap::from([(5, 50)]));
+        assert!((estimate.probabilities[5] - 1.0).abs() < 1e-12);
+
+        // T has the phases 0 and 1/8 on |0⟩ and |1⟩, so |+⟩ gives each half of the time
+        let mut plus = QuantumState::new(1);
+        plus.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+        let estimate =
+            PhaseEstimation::new(3).unwrap().estimate(&QuantumGate::T, &plus, 1, &mut rng);
+        let probabilities = estimate.unwrap().probabilities;
+        assert!((probabilities[0] - 0.5).abs() < 1e-12);
+        assert!((probabilities[1] - 0.5).abs() < 1e-12);
+
+        assert!(estimation.estimate(&QuantumGate::CNOT, &one, 1, &mut rng).is_err());
+        assert!(estimation.estimate(&gate, &one, 0, &mut rng).is_err());
+        assert!(PhaseEstimation::new(0).is_err());
+    }
+
+    #[test]
+    fn test_inexact_phase() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(2);
+        // A two-qubit unitary with the eigenvector |11⟩ of phase 1/3 = 21.33/64
+        let gate = QuantumGate::controlled(QuantumGate::Phase(2.0 * PI / 3.0), 1);
+        let mut state = QuantumState::new(2);
+        state.apply_gate(&QuantumGate::PauliX, &[0]).unwrap();
+        state.apply_gate(&QuantumGate::PauliX, &[1]).unwrap();
+        let estimate = PhaseEstimation::new(6).unwrap().estimate(&gate, &state, 200, &mut rng);
+        let estimate = estimate.unwrap();
+        assert_eq!(estimate.phase, 21.0 / 64.0);
+        // The nearest fraction is measured with probability at least 4/π²
+        assert!(estimate.probabilities[21] > 4.0 / (PI * PI));
+        let total: f64 = estimate.probabilities.iter().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,412 @@
+//! Quantum approximate optimization algorithm
+//!
+//! QAOA looks for a low-energy bitstring of an Ising model. Starting from the uniform
+//! superposition, `p` layers each apply the cost operator `exp(-iγH)` and the mixer
+//! `exp(-iβ Σ Xᵢ)`. The angles `γ` and `β` are tuned by the variational eigensolver to
+//! minimize `⟨H⟩`, and the bitstrings measured from the optimized state are the candidate
+//! solutions.
+
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::vqe::append_pauli_rotation;
+use super::{
+    Ansatz, ParameterExpr, Pauli, PauliString, PauliSum, QuantumCircuit, StateVectorBackend, Vqe,
+    VqeConfig,
+};
+use crate::{QuantumError, QuantumResult};
+
+/// Total duration `Σ (γ + β)` of the linear ramp the angles start from, in units of the
+/// largest coefficient of the model
+const RAMP_DURATION: f64 = 0.75;
+
+/// Cost function `offset + Σ hᵢ sᵢ + Σ Jᵢⱼ sᵢ sⱼ` of spins `sᵢ = ±1`
+///
+/// Spin `i` is bit `i` of a bitstring, with a clear bit for `s = +1`, so that the cost is
+/// diagonal in the computational basis with `Zᵢ` in place of `sᵢ`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsingModel {
+    fields: Vec<f64>,
+    couplings: Vec<(usize, usize, f64)>,
+    offset: f64,
+}
+
+impl IsingModel {
+    /// Model of `num_spins` spins that all have zero energy
+    pub fn new(num_spins: usize) -> Self {
+        Self { fields: vec![0.0; num_spins], couplings: Vec::new(), offset: 0.0 }
+    }
+
+    /// MaxCut of a graph with weighted `edges`, as the model whose energy is minus the weight
+    /// of the cut between the nodes with clear and with set bits
+    pub fn max_cut(num_nodes: usize, edges: &[(usize, usize, f64)]) -> QuantumResult<Self> {
+        let mut model = Self::new(num_nodes);
+        // An edge is cut when s_u s_v = -1, so -w (1 - s_u s_v) / 2 counts it
+        for &(u, v, weight) in edges {
+            model.add_coupling(u, v, weight / 2.0)?;
+            model.offset -= weight / 2.0;
+        }
+        Ok(model)
+    }
+
+    /// Model of the QUBO problem that minimizes `Σ Qᵢⱼ xᵢ xⱼ` over `xᵢ ∈ {0, 1}`, with `xᵢ`
+    /// being bit `i`
+    pub fn from_qubo(matrix: &[Vec<f64>]) -> QuantumResult<Self> {
+        let n = matrix.len();
+        if let Some(row) = matrix.iter().find(|row| row.len() != n) {
+            return Err(QuantumError::SimulationError(format!(
+                "a QUBO matrix with {n} rows has a row of {} entries",
+                row.len()
+            )));
+        }
+        let mut model = Self::new(n);
+        // Substitute x = (1 - s) / 2, where x² = x on the diagonal
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, &q) in row.iter().enumerate() {
+                if i == j {
+                    model.offset += q / 2.0;
+                    model.add_field(i, -q / 2.0)?;
+                } else {
+                    model.offset += q / 4.0;
+                    model.add_field(i, -q / 4.0)?;
+                    model.add_field(j, -q / 4.0)?;
+                    model.add_coupling(i, j, q / 4.0)?;
+                }
+            }
+        }
+        Ok(model)
+    }
+
+    /// Add `field` to the field on spin `i`
+    pub fn add_field(&mut self, i: usize, field: f64) -> QuantumResult<&mut Self> {
+        self.check_spin(i)?;
+        self.fields[i] += field;
+        Ok(self)
+    }
+
+    /// Add `coupling` to the coupling between spins `i` and `j`
+    pub fn add_coupling(&mut self, i: usize, j: usize, coupling: f64) -> QuantumResult<&mut Self> {
+        self.check_spin(i)?;
+        self.check_spin(j)?;
+        if i == j {
+            return Err(QuantumError::SimulationError(format!("spin {i} cannot couple to itself")));
+        }
+        let (i, j) = (i.min(j), i.max(j));
+        match self.couplings.iter_mut().find(|(a, b, _)| (*a, *b) == (i, j)) {
+            Some((_, _, existing)) => *existing += coupling,
+            None => self.couplings.push((i, j, coupling)),
+        }
+        Ok(self)
+    }
+
+    pub fn num_spins(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn fields(&self) -> &[f64] {
+        &self.fields
+    }
+
+    /// Couplings as `(i, j, Jᵢⱼ)` with `i < j`
+    pub fn couplings(&self) -> &[(usize, usize, f64)] {
+        &self.couplings
+    }
+
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// Energy of the spins given by the bits of `bitstring`
+    pub fn energy(&self, bitstring: usize) -> f64 {
+        let spin = |i: usize| if (bitstring >> i) & 1 == 1 { -1.0 } else { 1.0 };
+        let fields: f64 = self.fields.iter().enumerate().map(|(i, h)| h * spin(i)).sum();
+        let couplings: f64 =
+            self.couplings.iter().map(|&(i, j, coupling)| coupling * spin(i) * spin(j)).sum();
+        self.offset + fields + couplings
+    }
+
+    /// The cost as an operator on one qubit per spin
+    pub fn hamiltonian(&self) -> QuantumResult<PauliSum> {
+        let n = self.num_spins();
+        let mut hamiltonian = PauliSum::new(n);
+        if self.offset != 0.0 {
+            hamiltonian.add_term(self.offset, PauliString::identity(n))?;
+        }
+        for (i, &field) in self.fields.iter().enumerate() {
+            if field != 0.0 {
+                hamiltonian.add_term(field, z_string(n, &[i]))?;
+            }
+        }
+        for &(i, j, coupling) in &self.couplings {
+            hamiltonian.add_term(coupling, z_string(n, &[i, j]))?;
+        }
+        Ok(hamiltonian)
+    }
+
+    fn check_spin(&self, i: usize) -> QuantumResult<()> {
+        if i >= self.num_spins() {
+            return Err(QuantumError::SimulationError(format!(
+                "spin {i} is out of range for a model of {} spins",
+                self.num_spins()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn z_string(num_qubits: usize, qubits: &[usize]) -> PauliString {
+    let mut pauli = PauliString::identity(num_qubits);
+    for &qubit in qubits {
+        pauli.set(qubit, Pauli::Z);
+    }
+    pauli
+}
+
+/// Settings of a [`Qaoa`] run
+#[derive(Debug, Clone, PartialEq)]
+pub struct QaoaConfig {
+    /// Number `p` of cost and mixer layers
+    pub layers: usize,
+    /// Optimization of the angles, whose seed also drives the final measurements
+    pub optimizer: VqeConfig,
+    /// Measurements of the optimized state that the best bitstring is picked from
+    pub samples: usize,
+}
+
+impl Default for QaoaConfig {
+    fn default() -> Self {
+        Self { layers: 2, optimizer: VqeConfig::default(), samples: 1000 }
+    }
+}
+
+/// Outcome of [`Qaoa::run`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QaoaResult {
+    /// Measured bitstring with the lowest energy
+    pub bitstring: usize,
+    pub energy: f64,
+    /// `(E_max - E) / (E_max - E_min)` for the energy `E` of `bitstring`, which is 1 for an
+    /// optimal bitstring
+    ///
+    /// The empty cut is the worst one, so for MaxCut this is the weight of the cut over the
+    /// maximum.
+    pub approximation_ratio: f64,
+    /// `⟨H⟩` of the optimized state
+    pub expected_energy: f64,
+    pub gammas: Vec<f64>,
+    pub betas: Vec<f64>,
+    /// How often each bitstring was measured
+    pub counts: BTreeMap<usize, usize>,
+    /// Steps of the angle optimization
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// QAOA for an Ising model
+pub struct Qaoa {
+    model: IsingModel,
+    config: QaoaConfig,
+}
+
+impl Qaoa {
+    pub fn new(model: IsingModel, config: QaoaConfig) -> QuantumResult<Self> {
+        let spins = model.num_spins();
+        if spins == 0 || spins > StateVectorBackend::MAX_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "QAOA needs between 1 and {} spins, the model has {spins}",
+                StateVectorBackend::MAX_QUBITS
+            )));
+        }
+        if config.layers == 0 || config.samples == 0 {
+            return Err(QuantumError::SimulationError(
+                "QAOA needs at least one layer and one sample".to_string(),
+            ));
+        }
+        Ok(Self { model, config })
+    }
+
+    pub fn model(&self) -> &IsingModel {
+        &self.model
+    }
+
+    pub fn config(&self) -> &QaoaConfig {
+        &self.config
+    }
+
+    /// Circuit of the `p` layers, with the parameters `gamma_k` and `beta_k` of each layer in
+    /// the order `gamma_0, beta_0, gamma_1, ...`
+    pub fn ansatz(&self) -> QuantumResult<Ansatz> {
+        let n = self.model.num_spins();
+        let mut circuit = QuantumCircuit::new(n, 0);
+        let mut parameters = Vec::new();
+        for qubit in 0..n {
+            circuit.h(qubit)?;
+        }
+        for layer in 0..self.config.layers {
+            let names = [format!("gamma_{layer}"), format!("beta_{layer}")];
+            let gamma = ParameterExpr::symbol(names[0].clone());
+            let beta = ParameterExpr::symbol(names[1].clone());
+            // exp(-iγ h Z) is Rz(2γh), and exp(-iγ J ZZ) the same rotation about ZZ
+            for (i, &field) in self.model.fields.iter().enumerate() {
+                if field != 0.0 {
+                    circuit.rz(gamma.clone() * (2.0 * field), i)?;
+                }
+            }
+            for &(i, j, coupling) in &self.model.couplings {
+                let angle = gamma.clone() * (2.0 * coupling);
+                append_pauli_rotation(&mut circuit, &z_string(n, &[i, j]), angle)?;
+            }
+            for qubit in 0..n {
+                circuit.rx(beta.clone() * 2.0, qubit)?;
+            }
+            parameters.extend(names);
+        }
+        Ansatz::with_parameters(circuit, parameters)
+    }
+
+    /// Angles of a linear ramp from the mixer to the cost, like a discretized adiabatic
+    /// evolution, which avoids the many poor local optima of random angles
+    pub fn initial_angles(&self) -> Vec<f64> {
+        let scale = self
+            .model
+            .fields
+            .iter()
+            .chain(self.model.couplings.iter().map(|(_, _, coupling)| coupling))
+            .fold(0.0_f64, |max, coefficient| max.max(coefficient.abs()));
+        let layers = self.config.layers as f64;
+        let step = RAMP_DURATION / layers / if scale > 0.0 { scale } else { 1.0 };
+        (0..self.config.layers)
+            .flat_map(|layer| {
+                let fraction = (layer as f64 + 0.5) / layers;
+                [fraction * step, (1.0 - fraction) * step]
+            })
+            .collect()
+    }
+
+    /// Optimize the angles from [`Qaoa::initial_angles`], measure the optimized state and
+    /// return the best bitstring measured
+    pub fn run(&self) -> QuantumResult<QaoaResult> {
+        let hamiltonian = self.model.hamiltonian()?;
+        let mut vqe = Vqe::new(hamiltonian, self.ansatz()?, self.config.optimizer.clone())?;
+        let optimized = vqe.run(&self.initial_angles())?;
+
+        let seed = self.config.optimizer.seed;
+        let circuit = vqe.ansatz().bind(&optimized.parameters)?;
+        let state = StateVectorBackend::new(seed).statevector(&circuit)?;
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let counts = state.sample(self.config.samples, &mut rng)?;
+        let (bitstring, energy) = counts
+            .keys()
+            .map(|&bitstring| (bitstring, self.model.energy(bitstring)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("at least one sample");
+
+        let (lowest, highest) = (0..1usize << self.model.num_spins())
+            .map(|bitstring| self.model.energy(bitstring))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), e| (low.min(e), high.max(e)));
+        let approximation_ratio =
+            if highest > lowest { (highest - energy) / (highest - lowest) } else { 1.0 };
+
+        let (gammas, betas) = optimized.parameters.chunks(2).map(|pair| (pair[0], pair[1])).unzip();
+        Ok(QaoaResult {
+            bitstring,
+            energy,
+            approximation_ratio,
+            expected_energy: optimized.energy,
+            gammas,
+            betas,
+            counts,
+            iterations: optimized.iterations,
+            converged: optimized.converged,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ising_model() {
+        // A triangle cuts at most two of its edges
+        let triangle = IsingModel::max_cut(3, &[(0, 1, 1.0), (1, 2, 2.0), (0, 2, 3.0)]).unwrap();
+        assert_eq!(triangle.energy(0b000), 0.0);
+        assert_eq!(triangle.energy(0b001), -4.0);
+        assert_eq!(triangle.energy(0b100), -5.0);
+        assert_eq!(triangle.energy(0b011), -5.0);
+        assert!(IsingModel::max_cut(2, &[(0, 2, 1.0)]).is_err());
+        assert!(IsingModel::max_cut(2, &[(1, 1, 1.0)]).is_err());
+
+        let qubo = vec![vec![-1.0, 2.0, 0.0], vec![0.0, -1.0, 0.5], vec![1.0, 0.0, -2.0]];
+        let model = IsingModel::from_qubo(&qubo).unwrap();
+        let hamiltonian = model.hamiltonian().unwrap();
+        for bitstring in 0..8 {
+            let x = |i: usize| ((bitstring >> i) & 1) as f64;
+            let expected: f64 = (0..3)
+                .flat_map(|i| (0..3).map(move |j| (i, j)))
+                .map(|(i, j)| qubo[i][j] * x(i) * x(j))
+                .sum();
+            assert!((model.energy(bitstring) - expected).abs() < 1e-12, "{bitstring:03b}");
+
+            let mut state = QuantumCircuit::new(3, 0);
+            for qubit in (0..3).filter(|&qubit| (bitstring >> qubit) & 1 == 1) {
+                state.x(qubit).unwrap();
+            }
+            let state = StateVectorBackend::new(0).statevector(&state).unwrap();
+            assert!((hamiltonian.expectation(&state).unwrap() - expected).abs() < 1e-12);
+        }
+        assert!(IsingModel::from_qubo(&[vec![1.0, 2.0]]).is_err());
+    }
+
+    #[test]
+    fn test_max_cut() {
+        // A ring of six nodes is cut completely by alternating the sides
+        let ring: Vec<_> = (0..6).map(|i| (i, (i + 1) % 6, 1.0)).collect();
+        let model = IsingModel::max_cut(6, &ring).unwrap();
+        let qaoa = Qaoa::new(model, QaoaConfig::default()).unwrap();
+        assert_eq!(qaoa.ansatz().unwrap().parameters(), ["gamma_0", "beta_0", "gamma_1", "beta_1"]);
+        let result = qaoa.run().unwrap();
+        assert!([0b010101, 0b101010].contains(&result.bitstring), "{:06b}", result.bitstring);
+        assert_eq!(result.energy, -6.0);
+        assert_eq!(result.approximation_ratio, 1.0);
+        assert_eq!(result.counts.values().sum::<usize>(), 1000);
+        // Two layers reach an expected cut of about 5.0 on this ring, where the optimum is 6
+        assert!(result.expected_energy < -4.5, "{}", result.expected_energy);
+
+        // More layers get closer to the optimum in expectation
+        let weighted =
+            [(0, 1, 2.0), (1, 2, 1.0), (2, 3, 3.0), (3, 0, 1.0), (0, 2, 2.5), (1, 3, 0.5)];
+        let mut expected_energies = Vec::new();
+        for layers in [1, 3] {
+            let model = IsingModel::max_cut(4, &weighted).unwrap();
+            let config = QaoaConfig { layers, ..QaoaConfig::default() };
+            let result = Qaoa::new(model, config).unwrap().run().unwrap();
+            assert_eq!(result.energy, -8.0);
+            expected_energies.push(result.expected_energy);
+        }
+        assert!(expected_energies[1] < expected_energies[0], "{expected_energies:?}");
+    }
+
+    #[test]
+    fn test_qubo() {
+        // Pick exactly two of four items, preferring the cheap ones 1 and 3: up to a constant,
+        // the penalty 3 (x₀ + x₁ + x₂ + x₃ - 2)² is 3 Σ xᵢ xⱼ over i ≠ j minus 9 Σ xᵢ
+        let costs = [1.0, -1.0, 0.5, -0.5];
+        let qubo: Vec<Vec<f64>> = (0..4)
+            .map(|i| (0..4).map(|j| if i == j { costs[i] - 9.0 } else { 3.0 }).collect())
+            .collect();
+        let model = IsingModel::from_qubo(&qubo).unwrap();
+        let config = QaoaConfig { layers: 2, ..QaoaConfig::default() };
+        let result = Qaoa::new(model, config).unwrap().run().unwrap();
+        assert_eq!(result.bitstring, 0b1010);
+        assert_eq!(result.energy, -13.5);
+        assert_eq!(result.approximation_ratio, 1.0);
+        assert_eq!((result.gammas.len(), result.betas.len()), (2, 2));
+
+        let config = QaoaConfig { layers: 0, ..QaoaConfig::default() };
+        assert!(Qaoa::new(IsingModel::new(2), config).is_err());
+        assert!(Qaoa::new(IsingModel::new(0), QaoaConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+// parameterized circuit with gate modifiers
+OPENQASM 3;
+include "stdgates.inc";
+input angle[32] theta;
+input float[64] phi;
+gate rot(a, b) q {
+  rz(a) q;
+  ry(b / 2) q;
+}
+qubit[3] q;
+bit[3] c;
+h q;
+ctrl @ rot(theta, 2 * phi) q[0], q[1];
+inv @ rot(theta, phi) q[2];
+ctrl(2) @ x q[0], q[1], q[2];
+ctrl @ inv @ s q[2], q[0];
+c = measure q;
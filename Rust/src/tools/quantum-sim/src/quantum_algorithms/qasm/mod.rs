@@ -0,0 +1,244 @@
+//! OpenQASM import and export
+//!
+//! Circuits are read from OpenQASM 2.0 and from the core of OpenQASM 3.0: `qubit` and `bit`
+//! declarations, gate definitions with the `ctrl @` and `inv @` modifiers, measurements,
+//! resets, barriers, `input` angles and `if` statements on the value of a classical register.
+//! Circuits are written with the gates of `qelib1.inc` or `stdgates.inc`.
+
+mod parser;
+mod printer;
+
+pub use printer::QasmVersion;
+
+use super::QuantumCircuit;
+use crate::QuantumResult;
+
+impl QuantumCircuit {
+    /// Circuit of an OpenQASM 2.0 or 3.0 program
+    ///
+    /// Only the standard include files `qelib1.inc` and `stdgates.inc` are supported, and
+    /// their gates are available without including them.
+    pub fn from_qasm(source: &str) -> QuantumResult<QuantumCircuit> {
+        parser::parse_qasm(source)
+    }
+
+    /// OpenQASM program of the circuit
+    ///
+    /// Fails for gates the version cannot express, such as user-supplied unitaries, and for
+    /// parameterized circuits in OpenQASM 2.0.
+    pub fn to_qasm(&self, version: QasmVersion) -> QuantumResult<String> {
+        printer::print_qasm(self, version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QuantumError;
+    use crate::quantum_algorithms::{
+        Backend, Condition, Operation, ParameterExpr, QuantumGate, StateVectorBackend,
+        UnitaryMatrix,
+    };
+
+    const BENCHMARKS: &[(&str, &str)] = &[
+        ("adder", include_str!("benchmarks/adder.qasm")),
+        ("inverseqft1", include_str!("benchmarks/inverseqft1.qasm")),
+        ("modifiers3", include_str!("benchmarks/modifiers3.qasm")),
+        ("qec", include_str!("benchmarks/qec.qasm")),
+        ("qft", include_str!("benchmarks/qft.qasm")),
+        ("rb", include_str!("benchmarks/rb.qasm")),
+        ("teleport", include_str!("benchmarks/teleport.qasm")),
+        ("teleport3", include_str!("benchmarks/teleport3.qasm")),
+        ("W-state", include_str!("benchmarks/W-state.qasm")),
+    ];
+
+    fn benchmark(name: &str) -> QuantumCircuit {
+        let (_, source) = BENCHMARKS.iter().find(|(benchmark, _)| *benchmark == name).unwrap();
+        QuantumCircuit::from_qasm(source).unwrap()
+    }
+
+    fn parse_error(source: &str) -> String {
+        match QuantumCircuit::from_qasm(source) {
+            Err(QuantumError::ParsingError(message)) => message,
+            other => panic!("expected a parsing error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_benchmarks() {
+        for (name, source) in BENCHMARKS {
+            let circuit = QuantumCircuit::from_qasm(source).unwrap();
+            for version in [QasmVersion::V2, QasmVersion::V3] {
+                if circuit.is_parameterized() && version == QasmVersion::V2 {
+                    assert!(circuit.to_qasm(version).is_err());
+                    continue;
+                }
+                let printed = circuit.to_qasm(version).unwrap();
+                let reparsed = QuantumCircuit::from_qasm(&printed)
+                    .unwrap_or_else(|error| panic!("{name} {version:?}: {error}\n{printed}"));
+                assert_eq!(reparsed, circuit, "{name} {version:?}\n{printed}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_adder() {
+        let circuit = benchmark("adder");
+        assert_eq!(circuit.num_qubits(), 10);
+        let result = StateVectorBackend::new(1).run(&circuit, 16).unwrap();
+        // 1 + 15, with the carry in the highest bit
+        assert_eq!(result.probability("10000"), 1.0);
+    }
+
+    #[test]
+    fn test_conditioned_benchmarks() {
+        let result = StateVectorBackend::new(2).run(&benchmark("inverseqft1"), 64).unwrap();
+        assert_eq!(result.probability("0000"), 1.0);
+        // The error on q[0] gives the syndrome 01 and is corrected before the final measurement
+        let result = StateVectorBackend::new(3).run(&benchmark("qec"), 64).unwrap();
+        assert_eq!(result.probability("01000"), 1.0);
+    }
+
+    #[test]
+    fn test_teleport() {
+        for name in ["teleport", "teleport3"] {
+            let result = StateVectorBackend::new(4).run(&benchmark(name), 4_000).unwrap();
+            let ones: usize = result
+                .counts
+                .iter()
+                .filter(|(bits, _)| bits.starts_with('1'))
+                .map(|(_, &count)| count)
+                .sum();
+            // U(0.3, 0.2, 0.1)|0⟩ gives 1 with probability sin²(0.15)
+            let expected = 0.15f64.sin().powi(2);
+            assert!((ones as f64 / 4_000.0 - expected).abs() < 0.01, "{name}: {ones}");
+        }
+    }
+
+    #[test]
+    fn test_w_state() {
+        let result = StateVectorBackend::new(5).run(&benchmark("W-state"), 3_000).unwrap();
+        assert_eq!(result.counts.keys().collect::<Vec<_>>(), ["001", "010", "100"]);
+        for bits in ["001", "010", "100"] {
+            assert!((result.probability(bits) - 1.0 / 3.0).abs() < 0.05, "{:?}", result.counts);
+        }
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let circuit = benchmark("modifiers3");
+        assert_eq!(circuit.parameters().into_iter().collect::<Vec<_>>(), ["phi", "theta"]);
+        let gates: Vec<_> = circuit
+            .instructions()
+            .iter()
+            .filter_map(|instruction| match &instruction.operation {
+                Operation::Gate { gate, qubits } => Some((gate.clone(), qubits.clone())),
+                _ => None,
+            })
+            .collect();
+        let theta = ParameterExpr::symbol("theta");
+        let phi = ParameterExpr::symbol("phi");
+        let expected = [
+            (QuantumGate::CRz(theta.clone()), vec![0, 1]),
+            (QuantumGate::CRy(ParameterExpr::from(2.0) * phi.clone() / 2.0), vec![0, 1]),
+            (QuantumGate::Ry(-(phi / 2.0)), vec![2]),
+            (QuantumGate::Rz(-theta), vec![2]),
+            (QuantumGate::Toffoli, vec![0, 1, 2]),
+            (QuantumGate::controlled(QuantumGate::Sdg, 1), vec![2, 0]),
+        ];
+        assert_eq!(gates[3..], expected);
+    }
+
+    #[test]
+    fn test_print() {
+        let mut circuit = QuantumCircuit::new(2, 2);
+        circuit.h(0).unwrap().cx(0, 1).unwrap().phase(0.5, 0).unwrap().measure(0, 0).unwrap();
+        let flip = Operation::Gate { gate: QuantumGate::PauliX, qubits: vec![1] };
+        circuit.append_conditional(flip, Condition { register: 0, value: 1 }).unwrap();
+        assert_eq!(
+            circuit.to_qasm(QasmVersion::V2).unwrap(),
+            "OPENQASM 2.0;\ninclude \"qelib1.inc\";\nqreg q[2];\ncreg c[2];\n\
+             h q[0];\ncx q[0], q[1];\nu1(0.5) q[0];\nmeasure q[0] -> c[0];\n\
+             if (c == 1) x q[1];\n"
+        );
+        assert_eq!(
+            circuit.to_qasm(QasmVersion::V3).unwrap(),
+            "OPENQASM 3.0;\ninclude \"stdgates.inc\";\nqubit[2] q;\nbit[2] c;\n\
+             h q[0];\ncx q[0], q[1];\np(0.5) q[0];\nc[0] = measure q[0];\n\
+             if (c == 1) x q[1];\n"
+        );
+    }
+
+    #[test]
+    fn test_unexpressible_gates() {
+        let mut circuit = QuantumCircuit::new(3, 0);
+        circuit.controlled(QuantumGate::Hadamard, &[0, 1], &[2]).unwrap();
+        assert!(circuit.to_qasm(QasmVersion::V2).is_err());
+        let printed = circuit.to_qasm(QasmVersion::V3).unwrap();
+        assert!(printed.ends_with("ctrl(2) @ h q[0], q[1], q[2];\n"), "{printed}");
+
+        let mut circuit = QuantumCircuit::new(1, 0);
+        let matrix = QuantumGate::<f64>::Hadamard.matrix();
+        circuit.unitary(UnitaryMatrix::new(matrix).unwrap(), &[0]).unwrap();
+        assert!(circuit.to_qasm(QasmVersion::V3).is_err());
+    }
+
+    #[test]
+    fn test_iswap_definition() {
+        let mut circuit = QuantumCircuit::new(2, 0);
+        circuit.h(0).unwrap().t(1).unwrap().iswap(0, 1).unwrap();
+        let reparsed = QuantumCircuit::from_qasm(&circuit.to_qasm(QasmVersion::V2).unwrap());
+        let backend = StateVectorBackend::new(0);
+        let expected = backend.statevector(&circuit).unwrap();
+        let actual = backend.statevector(&reparsed.unwrap()).unwrap();
+        for (a, b) in expected.amplitudes.iter().zip(&actual.amplitudes) {
+            assert!((a.0 - b.0).abs() < 1e-12 && (a.1 - b.1).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_sx_round_trip() {
+        let mut circuit = QuantumCircuit::new(1, 0);
+        circuit.sx(0).unwrap().sxdg(0).unwrap();
+        for version in [QasmVersion::V2, QasmVersion::V3] {
+            let printed = circuit.to_qasm(version).unwrap();
+            assert_eq!(QuantumCircuit::from_qasm(&printed).unwrap(), circuit, "{printed}");
+        }
+        assert!(circuit.to_qasm(QasmVersion::V3).unwrap().ends_with("inv @ sx q[0];\n"));
+    }
+
+    #[test]
+    fn test_expressions() {
+        let circuit = QuantumCircuit::from_qasm(
+            "OPENQASM 2.0; qreg q[1]; U(-pi/2^2, 2*cos(0) + 1.5e1, sqrt(4) - -1) q[0];",
+        )
+        .unwrap();
+        let Operation::Gate { gate, .. } = &circuit.instructions()[0].operation else {
+            panic!("expected a gate");
+        };
+        let params: Vec<_> = gate.params().into_iter().map(|p| p.value().unwrap()).collect();
+        assert_eq!(params, [-std::f64::consts::FRAC_PI_4, 17.0, 3.0]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_error("OPENQASM 2.0;\nqreg q[2];\nfoo q[0];\n");
+        assert_eq!(error, "line 3, column 1: unknown gate `foo`");
+        let error = parse_error("OPENQASM 2.0;\nqreg q[2];\nh q[2];\n");
+        assert!(error.starts_with("line 3, column 3: index 2 is out of range"), "{error}");
+        let error = parse_error("OPENQASM 2.0;\nqreg q[2];\ncx q[0];\n");
+        assert!(error.contains("takes 0 parameters and 2 qubits"), "{error}");
+        let error = parse_error("OPENQASM 2.0;\nqreg q[2];\ncx q[0], q[0];\n");
+        assert!(error.contains("twice"), "{error}");
+        let error = parse_error("OPENQASM 2.0;\nqreg q[2]\nh q;\n");
+        assert_eq!(error, "line 3, column 1: expected `;`, found `h`");
+        let error = parse_error("OPENQASM 2.0;\nqreg q[1];\nrx(theta) q[0];\n");
+        assert!(error.contains("unknown parameter `theta`"), "{error}");
+        let error = parse_error("OPENQASM 4.0;");
+        assert!(error.contains("only OpenQASM 2 and 3"), "{error}");
+        let error = parse_error("qreg q[1];\ncreg c[2];\nif (c[1] == 1) x q[0];\n");
+        assert!(error.contains("single bits"), "{error}");
+        let error = parse_error("gate g a { g a; }");
+        assert!(error.contains("unknown gate `g`"), "{error}");
+    }
+}
@@ -0,0 +1,1122 @@
+//! OpenQASM parser
+//!
+//! Gate definitions are expanded where they are used, so the resulting circuit only contains
+//! [`QuantumGate`]s. Gate arguments that apply a gate to whole registers are broadcast over
+//! the register elements.
+
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::{E, FRAC_PI_2, PI, TAU};
+
+use crate::quantum_algorithms::{
+    Condition, Instruction, Operation, ParameterExpr, QuantumCircuit, QuantumGate,
+};
+use crate::{QuantumError, QuantumResult};
+
+/// Gates of `qelib1.inc` and `stdgates.inc` that are defined in terms of other gates
+const PRELUDE: &str = "
+    gate cu(theta, phi, lambda, gamma) c, t { p(gamma) c; cu3(theta, phi, lambda) c, t; }
+    gate rzz(theta) a, b { cx a, b; u1(theta) b; cx a, b; }
+";
+
+/// Gates that map onto a single [`QuantumGate`], with their numbers of parameters and qubits
+const STANDARD_GATES: &[(&str, usize, usize)] = &[
+    ("U", 3, 1),
+    ("u", 3, 1),
+    ("u3", 3, 1),
+    ("u2", 2, 1),
+    ("u1", 1, 1),
+    ("p", 1, 1),
+    ("phase", 1, 1),
+    ("u0", 1, 1),
+    ("id", 0, 1),
+    ("x", 0, 1),
+    ("y", 0, 1),
+    ("z", 0, 1),
+    ("h", 0, 1),
+    ("s", 0, 1),
+    ("sdg", 0, 1),
+    ("t", 0, 1),
+    ("tdg", 0, 1),
+    ("sx", 0, 1),
+    ("sxdg", 0, 1),
+    ("rx", 1, 1),
+    ("ry", 1, 1),
+    ("rz", 1, 1),
+    ("CX", 0, 2),
+    ("cx", 0, 2),
+    ("cy", 0, 2),
+    ("cz", 0, 2),
+    ("ch", 0, 2),
+    ("swap", 0, 2),
+    ("iswap", 0, 2),
+    ("crx", 1, 2),
+    ("cry", 1, 2),
+    ("crz", 1, 2),
+    ("cu1", 1, 2),
+    ("cp", 1, 2),
+    ("cphase", 1, 2),
+    ("cu3", 3, 2),
+    ("ccx", 0, 3),
+    ("cswap", 0, 3),
+];
+
+/// Standard gate `name` with parameters `args`, or `None` for the identity
+///
+/// `args` must have the length given in [`STANDARD_GATES`].
+fn standard_gate(name: &str, args: &[ParameterExpr]) -> Option<QuantumGate<ParameterExpr>> {
+    let arg = |i: usize| args[i].clone();
+    Some(match name {
+        "U" | "u" | "u3" => QuantumGate::Rotation(arg(0), arg(1), arg(2)),
+        "u2" => QuantumGate::Rotation(ParameterExpr::Value(FRAC_PI_2), arg(0), arg(1)),
+        "u1" | "p" | "phase" => QuantumGate::Phase(arg(0)),
+        "u0" | "id" => return None,
+        "x" => QuantumGate::PauliX,
+        "y" => QuantumGate::PauliY,
+        "z" => QuantumGate::PauliZ,
+        "h" => QuantumGate::Hadamard,
+        "s" => QuantumGate::S,
+        "sdg" => QuantumGate::Sdg,
+        "t" => QuantumGate::T,
+        "tdg" => QuantumGate::Tdg,
+        "sx" => QuantumGate::SX,
+        "sxdg" => QuantumGate::SXdg,
+        "rx" => QuantumGate::Rx(arg(0)),
+        "ry" => QuantumGate::Ry(arg(0)),
+        "rz" => QuantumGate::Rz(arg(0)),
+        "CX" | "cx" => QuantumGate::CNOT,
+        "cy" => QuantumGate::controlled(QuantumGate::PauliY, 1),
+        "cz" => QuantumGate::CZ,
+        "ch" => QuantumGate::controlled(QuantumGate::Hadamard, 1),
+        "swap" => QuantumGate::SWAP,
+        "iswap" => QuantumGate::ISwap,
+        "crx" => QuantumGate::CRx(arg(0)),
+        "cry" => QuantumGate::CRy(arg(0)),
+        "crz" => QuantumGate::CRz(arg(0)),
+        "cu1" | "cp" | "cphase" => QuantumGate::controlled(QuantumGate::Phase(arg(0)), 1),
+        "cu3" => QuantumGate::controlled(QuantumGate::Rotation(arg(0), arg(1), arg(2)), 1),
+        "ccx" => QuantumGate::Toffoli,
+        "cswap" => QuantumGate::Fredkin,
+        _ => unreachable!("`{name}` is not a standard gate"),
+    })
+}
+
+const PUNCTUATION: &[&str] =
+    &["->", "==", ";", ",", "(", ")", "[", "]", "{", "}", "+", "-", "*", "/", "^", "@", "="];
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    Int(u64),
+    Real(f64),
+    Str(String),
+    Punct(&'static str),
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: Position,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    fn error(self, message: impl std::fmt::Display) -> QuantumError {
+        QuantumError::ParsingError(format!("line {}, column {}: {message}", self.line, self.column))
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer {
+    fn new(source: &str) -> Self {
+        Self { chars: source.chars().collect(), pos: 0, line: 1, column: 1 }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn position(&self) -> Position {
+        Position { line: self.line, column: self.column }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> QuantumResult<()> {
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let start = self.position();
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match (self.peek(0), self.peek(1)) {
+                            (Some('*'), Some('/')) => break,
+                            (Some(_), _) => {
+                                self.bump();
+                            }
+                            (None, _) => return Err(start.error("unterminated comment")),
+                        }
+                    }
+                    self.bump();
+                    self.bump();
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn tokenize(mut self) -> QuantumResult<Vec<Token>> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments()?;
+            let position = self.position();
+            let Some(c) = self.peek(0) else {
+                tokens.push(Token { kind: TokenKind::Eof, position });
+                return Ok(tokens);
+            };
+            let kind = if c.is_alphabetic() || c == '_' {
+                let mut ident = String::new();
+                while let Some(c) = self.peek(0).filter(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                    self.bump();
+                }
+                TokenKind::Ident(ident)
+            } else if c.is_ascii_digit()
+                || (c == '.' && self.peek(1).is_some_and(|c| c.is_ascii_digit()))
+            {
+                self.number(position)?
+            } else if c == '"' {
+                self.bump();
+                let mut string = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        Some('\n') | None => return Err(position.error("unterminated string")),
+                        Some(c) => string.push(c),
+                    }
+                }
+                TokenKind::Str(string)
+            } else {
+                let punct = PUNCTUATION.iter().find(|punct| {
+                    punct.chars().enumerate().all(|(i, expected)| self.peek(i) == Some(expected))
+                });
+                let Some(punct) = punct else {
+                    return Err(position.error(format!("unexpected character `{c}`")));
+                };
+                for _ in 0..punct.len() {
+                    self.bump();
+                }
+                TokenKind::Punct(punct)
+            };
+            tokens.push(Token { kind, position });
+        }
+    }
+
+    fn number(&mut self, position: Position) -> QuantumResult<TokenKind> {
+        let mut text = String::new();
+        let mut real = false;
+        while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+            text.push(c);
+            self.bump();
+        }
+        if self.peek(0) == Some('.') {
+            real = true;
+            text.push('.');
+            self.bump();
+            while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+                text.push(c);
+                self.bump();
+            }
+        }
+        if let Some(e @ ('e' | 'E')) = self.peek(0) {
+            let sign = self.peek(1).filter(|c| matches!(c, '+' | '-'));
+            let digit_offset = if sign.is_some() { 2 } else { 1 };
+            if self.peek(digit_offset).is_some_and(|c| c.is_ascii_digit()) {
+                real = true;
+                text.push(e);
+                self.bump();
+                if let Some(sign) = sign {
+                    text.push(sign);
+                    self.bump();
+                }
+                while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+                    text.push(c);
+                    self.bump();
+                }
+            }
+        }
+        if real {
+            text.parse().map(TokenKind::Real).map_err(|_| position.error("invalid number"))
+        } else {
+            // Integers too large for a `u64` can still be angles
+            match text.parse() {
+                Ok(value) => Ok(TokenKind::Int(value)),
+                Err(_) => Ok(TokenKind::Real(text.parse().expect("digits are a valid float"))),
+            }
+        }
+    }
+}
+
+/// Arithmetic expression in a gate argument
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Ident(String),
+    Neg(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Box<Expr>),
+}
+
+/// Gate modifier of OpenQASM 3
+#[derive(Debug, Clone, Copy)]
+enum Modifier {
+    Ctrl(usize),
+    Inv,
+}
+
+/// Qubit or register argument, `name` or `name[index]`
+#[derive(Debug, Clone)]
+struct Operand {
+    name: String,
+    index: Option<usize>,
+    position: Position,
+}
+
+#[derive(Debug, Clone)]
+struct GateCall {
+    modifiers: Vec<Modifier>,
+    name: String,
+    args: Vec<Expr>,
+    operands: Vec<Operand>,
+    position: Position,
+}
+
+struct GateDefinition {
+    params: Vec<String>,
+    qubits: Vec<String>,
+    body: Vec<GateCall>,
+}
+
+/// Gates with the qubits they are applied to
+type GateSequence = Vec<(QuantumGate<ParameterExpr>, Vec<usize>)>;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    circuit: QuantumCircuit,
+    gates: HashMap<String, GateDefinition>,
+    opaque_gates: HashSet<String>,
+    /// Names declared with `input`, which become circuit parameters
+    inputs: HashSet<String>,
+}
+
+/// Parse an OpenQASM 2.0 or 3.0 program into a circuit
+pub(super) fn parse_qasm(source: &str) -> QuantumResult<QuantumCircuit> {
+    let mut parser = Parser::new(source)?;
+    parser.program()?;
+    Ok(parser.circuit)
+}
+
+impl Parser {
+    fn new(source: &str) -> QuantumResult<Self> {
+        let mut parser = Self {
+            tokens: Lexer::new(PRELUDE).tokenize()?,
+            pos: 0,
+            circuit: QuantumCircuit::empty(),
+            gates: HashMap::new(),
+            opaque_gates: HashSet::new(),
+            inputs: HashSet::new(),
+        };
+        while !parser.at_eof() {
+            parser.expect_keyword("gate")?;
+            parser.gate_definition()?;
+        }
+        parser.tokens = Lexer::new(source).tokenize()?;
+        parser.pos = 0;
+        Ok(parser)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn peek_kind(&self, offset: usize) -> &TokenKind {
+        let index = (self.pos + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].kind
+    }
+
+    fn position(&self) -> Position {
+        self.peek().position
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn at_eof(&self) -> bool {
+        self.peek().kind == TokenKind::Eof
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek().kind, TokenKind::Punct(p) if p == punct)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Ident(ident) if ident == keyword)
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let found = self.is_punct(punct);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> QuantumResult<()> {
+        if !self.eat_punct(punct) {
+            return Err(self.unexpected(&format!("`{punct}`")));
+        }
+        Ok(())
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> QuantumResult<()> {
+        if !self.is_keyword(keyword) {
+            return Err(self.unexpected(&format!("`{keyword}`")));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn ident(&mut self) -> QuantumResult<String> {
+        match &self.peek().kind {
+            TokenKind::Ident(ident) => {
+                let ident = ident.clone();
+                self.next();
+                Ok(ident)
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    fn integer(&mut self) -> QuantumResult<u64> {
+        match self.peek().kind {
+            TokenKind::Int(value) => {
+                self.next();
+                Ok(value)
+            }
+            _ => Err(self.unexpected("an integer")),
+        }
+    }
+
+    fn size(&mut self) -> QuantumResult<usize> {
+        let position = self.position();
+        let size = self.integer()?;
+        usize::try_from(size).map_err(|_| position.error("size is too large"))
+    }
+
+    fn unexpected(&self, expected: &str) -> QuantumError {
+        let found = match &self.peek().kind {
+            TokenKind::Ident(ident) => format!("`{ident}`"),
+            TokenKind::Int(value) => format!("`{value}`"),
+            TokenKind::Real(value) => format!("`{value}`"),
+            TokenKind::Str(string) => format!("\"{string}\""),
+            TokenKind::Punct(punct) => format!("`{punct}`"),
+            TokenKind::Eof => "end of input".to_string(),
+        };
+        self.position().error(format!("expected {expected}, found {found}"))
+    }
+
+    fn program(&mut self) -> QuantumResult<()> {
+        if self.is_keyword("OPENQASM") {
+            self.next();
+            let position = self.position();
+            let major = match self.next().kind {
+                TokenKind::Int(version) => version as f64,
+                TokenKind::Real(version) => version.trunc(),
+                _ => return Err(position.error("expected a version number")),
+            };
+            if major != 2.0 && major != 3.0 {
+                return Err(position.error("only OpenQASM 2 and 3 are supported"));
+            }
+            self.expect_punct(";")?;
+        }
+        while !self.at_eof() {
+            self.statement(None)?;
+        }
+        Ok(())
+    }
+
+    /// Parse a statement, which only runs if `condition` holds
+    fn statement(&mut self, condition: Option<Condition>) -> QuantumResult<()> {
+        let position = self.position();
+        let TokenKind::Ident(keyword) = self.peek().kind.clone() else {
+            return Err(self.unexpected("a statement"));
+        };
+        let declaration = matches!(
+            keyword.as_str(),
+            "include" | "qreg" | "creg" | "qubit" | "bit" | "input" | "gate" | "opaque" | "if"
+        );
+        if declaration && condition.is_some() {
+            return Err(position.error(format!("`{keyword}` cannot be conditioned")));
+        }
+        match keyword.as_str() {
+            "include" => self.include(),
+            "qreg" | "creg" => {
+                self.next();
+                let name = self.ident()?;
+                self.expect_punct("[")?;
+                let size = self.size()?;
+                self.expect_punct("]")?;
+                self.expect_punct(";")?;
+                self.declare_register(&keyword == "qreg", &name, size, position)
+            }
+            "qubit" | "bit" => {
+                self.next();
+                let size = if self.eat_punct("[") {
+                    let size = self.size()?;
+                    self.expect_punct("]")?;
+                    size
+                } else {
+                    1
+                };
+                let name = self.ident()?;
+                self.expect_punct(";")?;
+                self.declare_register(&keyword == "qubit", &name, size, position)
+            }
+            "input" => self.input(),
+            "gate" => {
+                self.next();
+                self.gate_definition()
+            }
+            "opaque" => self.opaque_definition(),
+            "if" => self.if_statement(),
+            "measure" => {
+                self.next();
+                let qubits = self.operand()?;
+                self.expect_punct("->")?;
+                let clbits = self.operand()?;
+                self.expect_punct(";")?;
+                self.measure(&qubits, &clbits, condition)
+            }
+            "reset" => {
+                self.next();
+                let operand = self.operand()?;
+                self.expect_punct(";")?;
+                for qubit in self.qubits(&operand)? {
+                    self.push(Operation::Reset { qubit }, condition, position)?;
+                }
+                Ok(())
+            }
+            "barrier" => {
+                self.next();
+                if condition.is_some() {
+                    return Err(position.error("`barrier` cannot be conditioned"));
+                }
+                let mut qubits = Vec::new();
+                if self.eat_punct(";") {
+                    qubits.extend(0..self.circuit.num_qubits());
+                } else {
+                    for operand in self.operand_list()? {
+                        qubits.extend(self.qubits(&operand)?);
+                    }
+                    self.expect_punct(";")?;
+                }
+                self.push(Operation::Barrier { qubits }, None, position)
+            }
+            _ if self.circuit.classical_register(&keyword).is_some()
+                && matches!(self.peek_kind(1), TokenKind::Punct("=" | "[")) =>
+            {
+                let clbits = self.operand()?;
+                self.expect_punct("=")?;
+                self.expect_keyword("measure")?;
+                let qubits = self.operand()?;
+                self.expect_punct(";")?;
+                self.measure(&qubits, &clbits, condition)
+            }
+            _ => {
+                let call = self.gate_call()?;
+                self.apply_gate_call(&call, condition)
+            }
+        }
+    }
+
+    fn include(&mut self) -> QuantumResult<()> {
+        self.next();
+        let position = self.position();
+        let TokenKind::Str(file) = self.next().kind else {
+            return Err(position.error("expected a file name"));
+        };
+        self.expect_punct(";")?;
+        // The standard gates are always available, so including them has no effect
+        if file != "qelib1.inc" && file != "stdgates.inc" {
+            return Err(position.error(format!("cannot include \"{file}\"")));
+        }
+        Ok(())
+    }
+
+    fn declare_register(
+        &mut self,
+        quantum: bool,
+        name: &str,
+        size: usize,
+        position: Position,
+    ) -> QuantumResult<()> {
+        let added = if quantum {
+            self.circuit.add_quantum_register(name, size)
+        } else {
+            self.circuit.add_classical_register(name, size)
+        };
+        added.map(|_| ()).map_err(|error| relocate(error, position))
+    }
+
+    fn input(&mut self) -> QuantumResult<()> {
+        self.next();
+        let position = self.position();
+        let ty = self.ident()?;
+        if ty != "float" && ty != "angle" {
+            return Err(position.error(format!("inputs of type `{ty}` are not supported")));
+        }
+        if self.eat_punct("[") {
+            self.size()?;
+            self.expect_punct("]")?;
+        }
+        let name = self.ident()?;
+        self.expect_punct(";")?;
+        self.inputs.insert(name);
+        Ok(())
+    }
+
+    /// Parse a gate definition after the `gate` keyword
+    fn gate_definition(&mut self) -> QuantumResult<()> {
+        let name = self.ident()?;
+        let mut params = Vec::new();
+        if self.eat_punct("(") && !self.eat_punct(")") {
+            params = self.ident_list()?;
+            self.expect_punct(")")?;
+        }
+        let qubits = self.ident_list()?;
+        self.expect_punct("{")?;
+        let mut body = Vec::new();
+        while !self.eat_punct("}") {
+            if self.is_keyword("barrier") {
+                // Barriers inside a gate have no effect once it is expanded
+                self.next();
+                self.operand_list()?;
+                self.expect_punct(";")?;
+                continue;
+            }
+            let call = self.gate_call()?;
+            if call.name == name || !self.is_gate(&call.name) {
+                return Err(call.position.error(format!("unknown gate `{}`", call.name)));
+            }
+            for operand in &call.operands {
+                if operand.index.is_some() || !qubits.contains(&operand.name) {
+                    return Err(operand
+                        .position
+                        .error(format!("gate `{name}` has no qubit argument `{}`", operand.name)));
+                }
+            }
+            body.push(call);
+        }
+        self.gates.insert(name, GateDefinition { params, qubits, body });
+        Ok(())
+    }
+
+    fn opaque_definition(&mut self) -> QuantumResult<()> {
+        self.next();
+        let name = self.ident()?;
+        if self.eat_punct("(") && !self.eat_punct(")") {
+            self.ident_list()?;
+            self.expect_punct(")")?;
+        }
+        self.ident_list()?;
+        self.expect_punct(";")?;
+        self.opaque_gates.insert(name);
+        Ok(())
+    }
+
+    fn is_gate(&self, name: &str) -> bool {
+        self.gates.contains_key(name)
+            || self.opaque_gates.contains(name)
+            || STANDARD_GATES.iter().any(|(standard, ..)| *standard == name)
+    }
+
+    fn if_statement(&mut self) -> QuantumResult<()> {
+        self.next();
+        self.expect_punct("(")?;
+        let operand = self.operand()?;
+        self.expect_punct("==")?;
+        let value = self.integer()?;
+        self.expect_punct(")")?;
+        let Some((register, contents)) = self.circuit.classical_register(&operand.name) else {
+            return Err(operand
+                .position
+                .error(format!("unknown classical register `{}`", operand.name)));
+        };
+        if operand.index.is_some_and(|index| index > 0 || contents.size != 1) {
+            return Err(operand
+                .position
+                .error("conditions on single bits are only supported for one-bit registers"));
+        }
+        let condition = Some(Condition { register, value });
+        if self.eat_punct("{") {
+            while !self.eat_punct("}") {
+                self.statement(condition)?;
+            }
+            Ok(())
+        } else {
+            self.statement(condition)
+        }
+    }
+
+    fn ident_list(&mut self) -> QuantumResult<Vec<String>> {
+        let mut idents = vec![self.ident()?];
+        while self.eat_punct(",") {
+            idents.push(self.ident()?);
+        }
+        Ok(idents)
+    }
+
+    fn operand(&mut self) -> QuantumResult<Operand> {
+        let position = self.position();
+        let name = self.ident()?;
+        let index = if self.eat_punct("[") {
+            let index = self.size()?;
+            self.expect_punct("]")?;
+            Some(index)
+        } else {
+            None
+        };
+        Ok(Operand { name, index, position })
+    }
+
+    fn operand_list(&mut self) -> QuantumResult<Vec<Operand>> {
+        let mut operands = vec![self.operand()?];
+        while self.eat_punct(",") {
+            operands.push(self.operand()?);
+        }
+        Ok(operands)
+    }
+
+    fn gate_call(&mut self) -> QuantumResult<GateCall> {
+        let position = self.position();
+        let mut modifiers = Vec::new();
+        loop {
+            let modifier = match self.peek_kind(0) {
+                TokenKind::Ident(ident) if ident == "ctrl" => {
+                    self.next();
+                    let mut controls = 1;
+                    if self.eat_punct("(") {
+                        controls = self.size()?;
+                        self.expect_punct(")")?;
+                    }
+                    Modifier::Ctrl(controls)
+                }
+                TokenKind::Ident(ident) if ident == "inv" => {
+                    self.next();
+                    Modifier::Inv
+                }
+                TokenKind::Ident(ident)
+                    if (ident == "negctrl" || ident == "pow")
+                        && matches!(self.peek_kind(1), TokenKind::Punct("@" | "(")) =>
+                {
+                    return Err(self.position().error(format!("`{ident}` is not supported")));
+                }
+                _ => break,
+            };
+            modifiers.push(modifier);
+            self.expect_punct("@")?;
+        }
+        let name = self.ident()?;
+        let mut args = Vec::new();
+        if self.eat_punct("(") && !self.eat_punct(")") {
+            args.push(self.expr()?);
+            while self.eat_punct(",") {
+                args.push(self.expr()?);
+            }
+            self.expect_punct(")")?;
+        }
+        let operands = self.operand_list()?;
+        self.expect_punct(";")?;
+        Ok(GateCall { modifiers, name, args, operands, position })
+    }
+
+    fn expr(&mut self) -> QuantumResult<Expr> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Punct(op @ ("+" | "-")) => op,
+                _ => return Ok(lhs),
+            };
+            self.next();
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> QuantumResult<Expr> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Punct(op @ ("*" | "/")) => op,
+                _ => return Ok(lhs),
+            };
+            self.next();
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> QuantumResult<Expr> {
+        if self.eat_punct("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat_punct("+") {
+            return self.unary();
+        }
+        let base = self.primary()?;
+        if self.eat_punct("^") {
+            return Ok(Expr::Binary("^", Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> QuantumResult<Expr> {
+        match self.peek().kind.clone() {
+            TokenKind::Int(value) => {
+                self.next();
+                Ok(Expr::Number(value as f64))
+            }
+            TokenKind::Real(value) => {
+                self.next();
+                Ok(Expr::Number(value))
+            }
+            TokenKind::Ident(ident) => {
+                self.next();
+                if self.eat_punct("(") {
+                    let arg = self.expr()?;
+                    self.expect_punct(")")?;
+                    return Ok(Expr::Call(ident, Box::new(arg)));
+                }
+                Ok(Expr::Ident(ident))
+            }
+            TokenKind::Punct("(") => {
+                self.next();
+                let expr = self.expr()?;
+                self.expect_punct(")")?;
+                Ok(expr)
+            }
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+
+    /// Value of `expr`, with gate parameters looked up in `scope`
+    fn evaluate(
+        &self,
+        expr: &Expr,
+        scope: &HashMap<&str, ParameterExpr>,
+    ) -> Result<ParameterExpr, String> {
+        Ok(match expr {
+            Expr::Number(value) => ParameterExpr::Value(*value),
+            Expr::Ident(name) => match (scope.get(name.as_str()), name.as_str()) {
+                (Some(value), _) => value.clone(),
+                (None, "pi" | "π") => ParameterExpr::Value(PI),
+                (None, "tau" | "τ") => ParameterExpr::Value(TAU),
+                (None, "euler" | "ℇ") => ParameterExpr::Value(E),
+                (None, _) if self.inputs.contains(name) => ParameterExpr::symbol(name.as_str()),
+                (None, _) => return Err(format!("unknown parameter `{name}`")),
+            },
+            Expr::Neg(operand) => -self.evaluate(operand, scope)?,
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.evaluate(lhs, scope)?;
+                let rhs = self.evaluate(rhs, scope)?;
+                match *op {
+                    "+" => lhs + rhs,
+                    "-" => lhs - rhs,
+                    "*" => lhs * rhs,
+                    "/" => lhs / rhs,
+                    _ => match (lhs.value(), rhs.value()) {
+                        (Some(base), Some(exponent)) => ParameterExpr::Value(base.powf(exponent)),
+                        _ => return Err("`^` is only supported on constants".to_string()),
+                    },
+                }
+            }
+            Expr::Call(function, arg) => {
+                let Some(arg) = self.evaluate(arg, scope)?.value() else {
+                    return Err(format!("`{function}` is only supported on constants"));
+                };
+                ParameterExpr::Value(match function.as_str() {
+                    "sin" => arg.sin(),
+                    "cos" => arg.cos(),
+                    "tan" => arg.tan(),
+                    "arcsin" => arg.asin(),
+                    "arccos" => arg.acos(),
+                    "arctan" => arg.atan(),
+                    "exp" => arg.exp(),
+                    "ln" => arg.ln(),
+                    "sqrt" => arg.sqrt(),
+                    _ => return Err(format!("unknown function `{function}`")),
+                })
+            }
+        })
+    }
+
+    fn qubits(&self, operand: &Operand) -> QuantumResult<Vec<usize>> {
+        let Some(register) = self.circuit.quantum_register(&operand.name) else {
+            return Err(operand
+                .position
+                .error(format!("unknown qubit register `{}`", operand.name)));
+        };
+        select(register.offset, register.size, operand)
+    }
+
+    fn clbits(&self, operand: &Operand) -> QuantumResult<Vec<usize>> {
+        let Some((_, register)) = self.circuit.classical_register(&operand.name) else {
+            return Err(operand
+                .position
+                .error(format!("unknown classical register `{}`", operand.name)));
+        };
+        select(register.offset, register.size, operand)
+    }
+
+    fn measure(
+        &mut self,
+        qubits: &Operand,
+        clbits: &Operand,
+        condition: Option<Condition>,
+    ) -> QuantumResult<()> {
+        let qubit_list = self.qubits(qubits)?;
+        let clbit_list = self.clbits(clbits)?;
+        if qubit_list.len() != clbit_list.len() {
+            return Err(qubits.position.error("measured qubits and classical bits do not match"));
+        }
+        for (qubit, clbit) in qubit_list.into_iter().zip(clbit_list) {
+            self.push(Operation::Measure { qubit, clbit }, condition, qubits.position)?;
+        }
+        Ok(())
+    }
+
+    fn push(
+        &mut self,
+        operation: Operation,
+        condition: Option<Condition>,
+        position: Position,
+    ) -> QuantumResult<()> {
+        self.circuit
+            .push(Instruction { operation, condition })
+            .map(|_| ())
+            .map_err(|error| relocate(error, position))
+    }
+
+    /// Apply a gate call in the program, broadcasting it over register arguments
+    fn apply_gate_call(
+        &mut self,
+        call: &GateCall,
+        condition: Option<Condition>,
+    ) -> QuantumResult<()> {
+        let scope = HashMap::new();
+        let args = call
+            .args
+            .iter()
+            .map(|arg| self.evaluate(arg, &scope))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|message| call.position.error(message))?;
+        let mut operands = Vec::new();
+        let mut width = None;
+        for operand in &call.operands {
+            let qubits = self.qubits(operand)?;
+            if operand.index.is_none() {
+                if width.is_some_and(|width| width != qubits.len()) {
+                    return Err(operand.position.error("registers have different sizes"));
+                }
+                width = Some(qubits.len());
+            }
+            operands.push(qubits);
+        }
+        for i in 0..width.unwrap_or(1) {
+            let qubits: Vec<usize> = call
+                .operands
+                .iter()
+                .zip(&operands)
+                .map(
+                    |(operand, qubits)| if operand.index.is_some() { qubits[0] } else { qubits[i] },
+                )
+                .collect();
+            for (gate, qubits) in self.expand(call, args.clone(), qubits)? {
+                self.push(Operation::Gate { gate, qubits }, condition, call.position)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gates that `call` applies to `qubits`, with its modifiers taken into account
+    fn expand(
+        &self,
+        call: &GateCall,
+        args: Vec<ParameterExpr>,
+        qubits: Vec<usize>,
+    ) -> QuantumResult<GateSequence> {
+        let controls = call
+            .modifiers
+            .iter()
+            .map(|modifier| match modifier {
+                Modifier::Ctrl(controls) => *controls,
+                Modifier::Inv => 0,
+            })
+            .sum::<usize>();
+        if qubits.len() < controls {
+            return Err(call.position.error(format!(
+                "`{}` needs at least {controls} qubits for its controls",
+                call.name
+            )));
+        }
+        let (mut control_qubits, targets) = qubits.split_at(controls);
+        let mut gates = self.expand_gate(&call.name, args, targets.to_vec(), call.position)?;
+        // Modifiers apply from the right, the innermost controls come last
+        for modifier in call.modifiers.iter().rev() {
+            match *modifier {
+                Modifier::Inv => {
+                    gates.reverse();
+                    for (gate, _) in &mut gates {
+                        *gate = gate.inverse();
+                    }
+                }
+                Modifier::Ctrl(controls) => {
+                    let (outer, inner) = control_qubits.split_at(control_qubits.len() - controls);
+                    control_qubits = outer;
+                    for (gate, qubits) in &mut gates {
+                        *gate = control(gate, controls);
+                        qubits.splice(0..0, inner.iter().copied());
+                    }
+                }
+            }
+        }
+        Ok(gates)
+    }
+
+    /// Gates of the gate called `name` without modifiers
+    fn expand_gate(
+        &self,
+        name: &str,
+        args: Vec<ParameterExpr>,
+        qubits: Vec<usize>,
+        position: Position,
+    ) -> QuantumResult<GateSequence> {
+        let (num_params, num_qubits) = if let Some(definition) = self.gates.get(name) {
+            (definition.params.len(), definition.qubits.len())
+        } else if let Some(&(_, params, qubits)) =
+            STANDARD_GATES.iter().find(|(standard, ..)| *standard == name)
+        {
+            (params, qubits)
+        } else if self.opaque_gates.contains(name) {
+            return Err(position.error(format!("opaque gate `{name}` cannot be used")));
+        } else {
+            return Err(position.error(format!("unknown gate `{name}`")));
+        };
+        if args.len() != num_params || qubits.len() != num_qubits {
+            return Err(position.error(format!(
+                "gate `{name}` takes {num_params} parameters and {num_qubits} qubits, \
+                 but {} parameters and {} qubits were given",
+                args.len(),
+                qubits.len()
+            )));
+        }
+        let Some(definition) = self.gates.get(name) else {
+            return Ok(standard_gate(name, &args).map(|gate| (gate, qubits)).into_iter().collect());
+        };
+        let scope: HashMap<&str, ParameterExpr> =
+            definition.params.iter().map(String::as_str).zip(args).collect();
+        let mut gates = Vec::new();
+        for call in &definition.body {
+            let args = call
+                .args
+                .iter()
+                .map(|arg| self.evaluate(arg, &scope))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|message| call.position.error(message))?;
+            let call_qubits = call
+                .operands
+                .iter()
+                .map(|operand| {
+                    let index = definition.qubits.iter().position(|q| *q == operand.name);
+                    qubits[index.expect("gate operands are checked in the definition")]
+                })
+                .collect();
+            gates.extend(self.expand(call, args, call_qubits)?);
+        }
+        Ok(gates)
+    }
+}
+
+/// `gate` controlled by `controls` more qubits
+///
+/// Uses the dedicated gate if there is one, so that `ctrl @ x` and `cx` give the same gate.
+fn control(gate: &QuantumGate<ParameterExpr>, controls: usize) -> QuantumGate<ParameterExpr> {
+    let (controls, target) = match gate.split_controls() {
+        Some((inner, target)) => (controls + inner, target),
+        None => (controls, gate.clone()),
+    };
+    match (controls, target) {
+        (1, QuantumGate::PauliX) => QuantumGate::CNOT,
+        (1, QuantumGate::PauliZ) => QuantumGate::CZ,
+        (1, QuantumGate::Rx(theta)) => QuantumGate::CRx(theta),
+        (1, QuantumGate::Ry(theta)) => QuantumGate::CRy(theta),
+        (1, QuantumGate::Rz(theta)) => QuantumGate::CRz(theta),
+        (1, QuantumGate::SWAP) => QuantumGate::Fredkin,
+        (2, QuantumGate::PauliX) => QuantumGate::Toffoli,
+        (controls, target) => QuantumGate::controlled(target, controls),
+    }
+}
+
+/// Qubits or bits of a register selected by `operand`
+fn select(offset: usize, size: usize, operand: &Operand) -> QuantumResult<Vec<usize>> {
+    match operand.index {
+        Some(index) if index >= size => Err(operand.position.error(format!(
+            "index {index} is out of range for register `{}` of size {size}",
+            operand.name
+        ))),
+        Some(index) => Ok(vec![offset + index]),
+        None => Ok((offset..offset + size).collect()),
+    }
+}
+
+/// Report an error from building the circuit at `position` in the source
+fn relocate(error: QuantumError, position: Position) -> QuantumError {
+    match error {
+        QuantumError::SimulationError(message) => position.error(message),
+        error => error,
+    }
+}
@@ -0,0 +1,170 @@
+//! OpenQASM printer
+
+use std::fmt::Write;
+
+use crate::quantum_algorithms::{Operation, ParameterExpr, QuantumCircuit, QuantumGate};
+use crate::{QuantumError, QuantumResult};
+
+/// Version of OpenQASM to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QasmVersion {
+    V2,
+    V3,
+}
+
+/// `iswap` is in neither `qelib1.inc` nor `stdgates.inc`, so programs using it define it
+const ISWAP_DEFINITION: &str = "gate iswap a, b { s a; s b; h a; cx a, b; cx b, a; h b; }\n";
+
+/// Write `circuit` as an OpenQASM program
+pub(super) fn print_qasm(circuit: &QuantumCircuit, version: QasmVersion) -> QuantumResult<String> {
+    let mut out = String::new();
+    match version {
+        QasmVersion::V2 => out.push_str("OPENQASM 2.0;\ninclude \"qelib1.inc\";\n"),
+        QasmVersion::V3 => out.push_str("OPENQASM 3.0;\ninclude \"stdgates.inc\";\n"),
+    }
+    let uses_iswap = circuit.instructions().iter().any(|instruction| {
+        matches!(&instruction.operation, Operation::Gate { gate, .. } if contains_iswap(gate))
+    });
+    if uses_iswap {
+        out.push_str(ISWAP_DEFINITION);
+    }
+    if circuit.is_parameterized() {
+        if version == QasmVersion::V2 {
+            return Err(QuantumError::SimulationError(
+                "OpenQASM 2.0 cannot express circuit parameters".to_string(),
+            ));
+        }
+        for parameter in circuit.parameters() {
+            writeln!(out, "input float[64] {parameter};").unwrap();
+        }
+    }
+
+    let mut qubit_names = Vec::with_capacity(circuit.num_qubits());
+    for register in circuit.quantum_registers() {
+        match version {
+            QasmVersion::V2 => writeln!(out, "qreg {}[{}];", register.name, register.size),
+            QasmVersion::V3 => writeln!(out, "qubit[{}] {};", register.size, register.name),
+        }
+        .unwrap();
+        qubit_names.extend((0..register.size).map(|i| format!("{}[{i}]", register.name)));
+    }
+    let mut clbit_names = Vec::with_capacity(circuit.num_clbits());
+    for register in circuit.classical_registers() {
+        match version {
+            QasmVersion::V2 => writeln!(out, "creg {}[{}];", register.name, register.size),
+            QasmVersion::V3 => writeln!(out, "bit[{}] {};", register.size, register.name),
+        }
+        .unwrap();
+        clbit_names.extend((0..register.size).map(|i| format!("{}[{i}]", register.name)));
+    }
+
+    for instruction in circuit.instructions() {
+        let statement = match &instruction.operation {
+            Operation::Gate { gate, qubits } => {
+                let qubits: Vec<_> = qubits.iter().map(|&q| qubit_names[q].as_str()).collect();
+                format!("{} {};", gate_text(gate, version)?, qubits.join(", "))
+            }
+            Operation::Measure { qubit, clbit } => match version {
+                QasmVersion::V2 => {
+                    format!("measure {} -> {};", qubit_names[*qubit], clbit_names[*clbit])
+                }
+                QasmVersion::V3 => {
+                    format!("{} = measure {};", clbit_names[*clbit], qubit_names[*qubit])
+                }
+            },
+            Operation::Reset { qubit } => format!("reset {};", qubit_names[*qubit]),
+            // OpenQASM 2.0 has no barrier without arguments, and it would have no effect
+            Operation::Barrier { qubits } if qubits.is_empty() => continue,
+            Operation::Barrier { qubits } => {
+                let qubits: Vec<_> = qubits.iter().map(|&q| qubit_names[q].as_str()).collect();
+                format!("barrier {};", qubits.join(", "))
+            }
+        };
+        if let Some(condition) = instruction.condition {
+            let register = &circuit.classical_registers()[condition.register];
+            write!(out, "if ({} == {}) ", register.name, condition.value).unwrap();
+        }
+        out.push_str(&statement);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn contains_iswap(gate: &QuantumGate<ParameterExpr>) -> bool {
+    match gate {
+        QuantumGate::ISwap => true,
+        QuantumGate::Controlled { gate, .. } => contains_iswap(gate),
+        _ => false,
+    }
+}
+
+/// Gate name with its parameters, as written before the qubits
+fn gate_text(gate: &QuantumGate<ParameterExpr>, version: QasmVersion) -> QuantumResult<String> {
+    let name = match (gate, version) {
+        (QuantumGate::Controlled { .. }, _) => {
+            let (controls, target) = gate.split_controls().expect("controlled gate has controls");
+            return controlled_gate_text(controls, &target, version);
+        }
+        (QuantumGate::Unitary(_), _) => {
+            return Err(QuantumError::SimulationError(
+                "unitary matrices cannot be written in OpenQASM".to_string(),
+            ));
+        }
+        (QuantumGate::Phase(_), QasmVersion::V2) => "u1",
+        (QuantumGate::Rotation(..), QasmVersion::V2) => "u3",
+        (QuantumGate::Rotation(..), QasmVersion::V3) => "U",
+        // `stdgates.inc` only defines `sx`
+        (QuantumGate::SXdg, QasmVersion::V3) => "inv @ sx",
+        _ => gate.name(),
+    };
+    params_text(name, &gate.params())
+}
+
+/// Text of `target` controlled by `controls` qubits
+fn controlled_gate_text(
+    controls: usize,
+    target: &QuantumGate<ParameterExpr>,
+    version: QasmVersion,
+) -> QuantumResult<String> {
+    let name = match (controls, target, version) {
+        (1, QuantumGate::PauliX, _) => "cx",
+        (1, QuantumGate::PauliY, _) => "cy",
+        (1, QuantumGate::PauliZ, _) => "cz",
+        (1, QuantumGate::Hadamard, _) => "ch",
+        (1, QuantumGate::Rx(_), _) => "crx",
+        (1, QuantumGate::Ry(_), _) => "cry",
+        (1, QuantumGate::Rz(_), _) => "crz",
+        (1, QuantumGate::SWAP, _) => "cswap",
+        (2, QuantumGate::PauliX, _) => "ccx",
+        (1, QuantumGate::Phase(_), QasmVersion::V2) => "cu1",
+        (1, QuantumGate::Phase(_), QasmVersion::V3) => "cp",
+        (1, QuantumGate::Rotation(..), QasmVersion::V2) => "cu3",
+        (1, _, QasmVersion::V3) => return Ok(format!("ctrl @ {}", gate_text(target, version)?)),
+        (_, _, QasmVersion::V3) => {
+            return Ok(format!("ctrl({controls}) @ {}", gate_text(target, version)?));
+        }
+        (_, _, QasmVersion::V2) => {
+            return Err(QuantumError::SimulationError(format!(
+                "`{}` with {controls} controls cannot be written in OpenQASM 2.0",
+                target.name()
+            )));
+        }
+    };
+    params_text(name, &target.params())
+}
+
+fn params_text(name: &str, params: &[&ParameterExpr]) -> QuantumResult<String> {
+    if params.is_empty() {
+        return Ok(name.to_string());
+    }
+    let mut texts = Vec::with_capacity(params.len());
+    for param in params {
+        if param.value().is_some_and(|value| !value.is_finite()) {
+            return Err(QuantumError::SimulationError(format!(
+                "gate `{name}` has the angle {param}, which OpenQASM cannot express"
+            )));
+        }
+        texts.push(param.to_string());
+    }
+    Ok(format!("{name}({})", texts.join(", ")))
+}
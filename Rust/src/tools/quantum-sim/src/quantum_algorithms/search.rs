@@ -0,0 +1,318 @@
+//! Grover search over the items of a slice
+//!
+//! The items are indexed by the basis states of a register of `⌈log₂ n⌉` qubits. The oracle
+//! flips the phase of the indices whose item satisfies the predicate, and the diffusion operator
+//! reflects the state about the uniform superposition. Together they rotate the state towards the
+//! solutions, so about `π/4 √(N/M)` iterations make a solution the likely measurement outcome.
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+
+use rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{QuantumGate, QuantumState, StateVectorBackend};
+use crate::{QuantumError, QuantumResult};
+
+/// Growth of the range of iteration counts between rounds when the solution count is unknown
+const ROUND_GROWTH: f64 = 6.0 / 5.0;
+
+/// Rounds with an unknown solution count stop after this many oracle calls per `√N`
+const UNKNOWN_CALLS_PER_SQRT: f64 = 20.0;
+
+/// Number of items that satisfy the predicate, as far as it is known before the search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolutionCount {
+    /// Exactly this many items match, which gives the optimal number of iterations
+    Known(usize),
+    /// Rounds with a random number of iterations from a growing range, until one measures a
+    /// solution (Boyer, Brassard, Høyer and Tapp)
+    Unknown,
+}
+
+/// Outcome of [`QuantumParallelSearch::amplify_search`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroverResult {
+    /// How often each index was measured in the shots of the final round
+    ///
+    /// Indices past the end of the items pad the register and are never solutions.
+    pub counts: BTreeMap<usize, usize>,
+    /// Measured indices whose item satisfies the predicate, in increasing order
+    pub solutions: Vec<usize>,
+    /// Probability that one measurement of the final state gives a solution
+    pub success_probability: f64,
+    /// Grover iterations in the final round
+    pub iterations: usize,
+    /// Oracle calls over all rounds
+    pub oracle_calls: usize,
+}
+
+/// Search for the items that satisfy a predicate
+pub struct QuantumParallelSearch;
+
+impl QuantumParallelSearch {
+    /// Indices of every item that satisfies `predicate`, found by testing each of them
+    ///
+    /// This is the classical fallback for inputs too large to simulate, and runs on the rayon
+    /// thread pool with the `parallel` feature.
+    pub fn classical_search<T, F>(items: &[T], predicate: F) -> Vec<usize>
+    where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync,
+    {
+        #[cfg(feature = "parallel")]
+        let items_iter = items.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let items_iter = items.iter();
+
+        items_iter
+            .enumerate()
+            .filter_map(|(index, item)| predicate(item).then_some(index))
+            .collect()
+    }
+
+    /// Grover's algorithm on a simulated register indexing `items`
+    ///
+    /// With a [`SolutionCount::Known`] count the state is amplified by the optimal number of
+    /// iterations and measured `shots` times. With [`SolutionCount::Unknown`] rounds of a random
+    /// number of iterations are measured once each until one finds a solution, and that
+    /// round is then measured `shots` times. The simulation evaluates the predicate once per
+    /// item to build the oracle.
+    pub fn amplify_search<T, F, R>(
+        items: &[T],
+        predicate: F,
+        solutions: SolutionCount,
+        shots: usize,
+        rng: &mut R,
+    ) -> QuantumResult<GroverResult>
+    where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync,
+        R: Rng + ?Sized,
+    {
+        let qubits = register_size(items.len())?;
+        let size = 1usize << qubits;
+        let mut marked = vec![false; size];
+        for index in Self::classical_search(items, predicate) {
+            marked[index] = true;
+        }
+
+        let (state, iterations, oracle_calls) = match solutions {
+            SolutionCount::Known(count) => {
+                if count > items.len() {
+                    return Err(QuantumError::SimulationError(format!(
+                        "{count} solutions among {} items",
+                        items.len()
+                    )));
+                }
+                let iterations = Self::optimal_iterations(size, count);
+                (grover_state(qubits, &marked, iterations)?, iterations, iterations)
+            }
+            SolutionCount::Unknown => {
+                let budget = (UNKNOWN_CALLS_PER_SQRT * (size as f64).sqrt()).ceil() as usize;
+                let mut range = 1.0_f64;
+                let mut oracle_calls = 0;
+                loop {
+                    let iterations = rng.random_range(0..range.ceil() as usize);
+                    let state = grover_state(qubits, &marked, iterations)?;
+                    oracle_calls += iterations;
+                    let outcome = state.clone().measure_all(rng)?;
+                    if marked[outcome] || oracle_calls >= budget {
+                        break (state, iterations, oracle_calls);
+                    }
+                    range = (range * ROUND_GROWTH).min((size as f64).sqrt());
+                }
+            }
+        };
+
+        let counts = state.sample(shots, rng)?;
+        let solutions = counts.keys().copied().filter(|&index| marked[index]).collect();
+        let success_probability = state
+            .probabilities()
+            .iter()
+            .zip(&marked)
+            .filter(|&(_, &marked)| marked)
+            .map(|(probability, _)| probability)
+            .sum();
+        Ok(GroverResult { counts, solutions, success_probability, iterations, oracle_calls })
+    }
+
+    /// Number of iterations that maximizes the probability of measuring one of `solutions`
+    /// among `size` basis states
+    ///
+    /// Each iteration turns the state by `2θ` with `sin θ = √(M/N)`, starting at `θ` from the
+    /// non-solutions, so `⌊π/4θ⌋` iterations end closest to the solutions.
+    pub fn optimal_iterations(size: usize, solutions: usize) -> usize {
+        if solutions == 0 || solutions >= size {
+            return 0;
+        }
+        let theta = (solutions as f64 / size as f64).sqrt().asin();
+        (PI / (4.0 * theta)).floor() as usize
+    }
+}
+
+/// Qubits needed to index `items` basis states
+fn register_size(items: usize) -> QuantumResult<usize> {
+    if items == 0 {
+        return Err(QuantumError::SimulationError("cannot search an empty slice".to_string()));
+    }
+    let qubits = (usize::BITS - (items - 1).leading_zeros()).max(1) as usize;
+    if qubits > StateVectorBackend::MAX_QUBITS {
+        return Err(QuantumError::SimulationError(format!(
+            "{items} items need {qubits} qubits, more than the {} that can be simulated, \
+             use the classical search instead",
+            StateVectorBackend::MAX_QUBITS
+        )));
+    }
+    Ok(qubits)
+}
+
+/// Uniform superposition over `qubits` qubits after `iterations` Grover iterations
+fn grover_state(qubits: usize, marked: &[bool], iterations: usize) -> QuantumResult<QuantumState> {
+    let mut state = QuantumState::new(qubits);
+    apply_to_all(&mut state, &QuantumGate::Hadamard)?;
+    for _ in 0..iterations {
+        apply_oracle(&mut state, marked);
+        apply_diffusion(&mut state)?;
+    }
+    Ok(state)
+}
+
+/// Phase oracle, `|x⟩ → -|x⟩` for every marked index
+fn apply_oracle(state: &mut QuantumState, marked: &[bool]) {
+    for (amplitude, _) in state.amplitudes.iter_mut().zip(marked).filter(|&(_, &marked)| marked) {
+        *amplitude = (-amplitude.0, -amplitude.1);
+    }
+}
+
+/// Reflection `I - 2|s⟩⟨s|` about the uniform superposition `|s⟩`, which is the diffusion
+/// operator up to a global phase
+///
+/// Conjugating by Hadamards turns it into `I - 2|0⟩⟨0|`, and conjugating that by X gates into
+/// a Z controlled by every other qubit.
+fn apply_diffusion(state: &mut QuantumState) -> QuantumResult<()> {
+    let qubits: Vec<usize> = (0..state.qubit_count).collect();
+    let reflection = QuantumGate::controlled(QuantumGate::PauliZ, qubits.len() - 1);
+    apply_to_all(state, &QuantumGate::Hadamard)?;
+    apply_to_all(state, &QuantumGate::PauliX)?;
+    state.apply_gate(&reflection, &qubits)?;
+    apply_to_all(state, &QuantumGate::PauliX)?;
+    apply_to_all(state, &QuantumGate::Hadamard)
+}
+
+fn apply_to_all(state: &mut QuantumState, gate: &QuantumGate) -> QuantumResult<()> {
+    for qubit in 0..state.qubit_count {
+        state.apply_gate(gate, &[qubit])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    #[test]
+    fn test_classical_search() {
+        let items = vec![1, 2, 3, 4, 5];
+        let results = QuantumParallelSearch::classical_search(&items, |&x| x > 3);
+        assert_eq!(results, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_optimal_iterations() {
+        assert_eq!(QuantumParallelSearch::optimal_iterations(4, 1), 1);
+        assert_eq!(QuantumParallelSearch::optimal_iterations(1024, 1), 25);
+        assert_eq!(QuantumParallelSearch::optimal_iterations(1024, 4), 12);
+        assert_eq!(QuantumParallelSearch::optimal_iterations(16, 0), 0);
+        assert_eq!(QuantumParallelSearch::optimal_iterations(16, 12), 0);
+    }
+
+    #[test]
+    fn test_known_solution_count() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        // One solution in four is found with certainty after one iteration
+        let result = QuantumParallelSearch::amplify_search(
+            &[3, 1, 4, 1],
+            |&x| x == 4,
+            SolutionCount::Known(1),
+            100,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(result.iterations, 1);
+        assert!((result.success_probability - 1.0).abs() < 1e-12);
+        assert_eq!(result.counts, BTreeMap::from([(2, 100)]));
+        assert_eq!(result.solutions, vec![2]);
+
+        // 1000 items are padded to 1024 basis states
+        let items: Vec<u32> = (0..1000).collect();
+        let result = QuantumParallelSearch::amplify_search(
+            &items,
+            |&x| x % 300 == 7,
+            SolutionCount::Known(4),
+            1000,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(result.iterations, 12);
+        assert!(result.success_probability > 0.99, "{}", result.success_probability);
+        assert_eq!(result.solutions, vec![7, 307, 607, 907]);
+        let hits: usize = result.solutions.iter().map(|index| result.counts[index]).sum();
+        assert!(hits > 980, "{hits}");
+
+        let error = QuantumParallelSearch::amplify_search(
+            &[1, 2],
+            |&x| x > 0,
+            SolutionCount::Known(3),
+            1,
+            &mut rng,
+        );
+        assert!(error.is_err());
+        let error = QuantumParallelSearch::amplify_search(
+            &[0; 0],
+            |_| true,
+            SolutionCount::Unknown,
+            1,
+            &mut rng,
+        );
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_unknown_solution_count() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(2);
+        let items: Vec<u32> = (0..512).collect();
+        // ⌈20 √512⌉
+        let budget = 453;
+        for solutions in [1, 3, 40] {
+            let result = QuantumParallelSearch::amplify_search(
+                &items,
+                |&x| x < solutions,
+                SolutionCount::Unknown,
+                50,
+                &mut rng,
+            )
+            .unwrap();
+            assert!(!result.solutions.is_empty(), "{solutions}: {result:?}");
+            assert!(result.solutions.iter().all(|&index| index < solutions as usize));
+            assert!(result.oracle_calls < budget, "{solutions}: {}", result.oracle_calls);
+        }
+
+        // Without solutions the rounds stop once the oracle budget is spent
+        let result = QuantumParallelSearch::amplify_search(
+            &items,
+            |_| false,
+            SolutionCount::Unknown,
+            10,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(result.solutions.is_empty());
+        assert_eq!(result.success_probability, 0.0);
+        assert!(result.oracle_calls >= budget);
+    }
+}
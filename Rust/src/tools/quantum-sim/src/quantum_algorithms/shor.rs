@@ -0,0 +1,305 @@
+//! Shor's order finding and factoring
+//!
+//! Multiplication by `a` modulo `n` is a unitary whose eigenphases are `s/r` for the order
+//! `r` of `a`, and the state `|1⟩` is an equal superposition of its eigenvectors. Phase
+//! estimation of it, with the controlled powers forming a modular exponentiation, measures a
+//! random `s/r`, and continued fractions recover `r` from it. An even order with
+//! `a^{r/2} ≢ -1` splits `n` through `gcd(a^{r/2} - 1, n)`.
+
+use ndarray::Array2;
+use num_complex::Complex64;
+use rand::Rng;
+
+use super::{PhaseEstimation, QuantumGate, QuantumState, StateVectorBackend, UnitaryMatrix};
+use crate::{QuantumError, QuantumResult};
+
+/// Measurements of one phase estimation circuit that order finding tries before giving up
+const ORDER_FINDING_SHOTS: usize = 10;
+
+/// Random bases tried before factoring gives up
+const FACTORING_ATTEMPTS: usize = 20;
+
+/// Factors found by [`Shor::factor`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Factorization {
+    /// Nontrivial factors `p ≤ q` with `p q = n`
+    pub factors: (u64, u64),
+    /// Base `a` of the attempt that found the factors, `None` if `n` is even or a power
+    pub base: Option<u64>,
+    /// Order of `base` modulo `n`, `None` if the base already shares a factor with `n`
+    pub order: Option<u64>,
+    /// Number of bases tried
+    pub attempts: usize,
+}
+
+/// Shor's algorithm on the state-vector simulator
+pub struct Shor;
+
+impl Shor {
+    /// Permutation `|y⟩ → |multiplier · y mod modulus⟩` of the values `y < modulus`, on the
+    /// qubits that hold `modulus - 1`, which leaves the values past the modulus alone
+    pub fn modular_multiplier(multiplier: u64, modulus: u64) -> QuantumResult<QuantumGate> {
+        if modulus < 2 || gcd(multiplier, modulus) != 1 {
+            return Err(QuantumError::SimulationError(format!(
+                "multiplication by {multiplier} modulo {modulus} is not invertible"
+            )));
+        }
+        let size = 1usize << register_size(modulus);
+        let mut matrix = Array2::zeros((size, size));
+        for value in 0..size as u64 {
+            let image = if value < modulus { mul_mod(multiplier, value, modulus) } else { value };
+            matrix[[image as usize, value as usize]] = Complex64::new(1.0, 0.0);
+        }
+        Ok(QuantumGate::Unitary(UnitaryMatrix::new(matrix)?))
+    }
+
+    /// Order of `base` modulo `modulus`, the least `r > 0` with `base^r ≡ 1`
+    ///
+    /// The phase estimation circuit uses twice as many counting qubits as the modulus has
+    /// bits, so each measurement reveals the order with a good probability. Returns `None` if
+    /// none of its measurements does, in which case calling again with a different `rng`
+    /// state draws new ones.
+    pub fn find_order<R: Rng + ?Sized>(
+        base: u64,
+        modulus: u64,
+        rng: &mut R,
+    ) -> QuantumResult<Option<u64>> {
+        if modulus < 2 || gcd(base, modulus) != 1 {
+            return Err(QuantumError::SimulationError(format!(
+                "{base} has no order modulo {modulus}"
+            )));
+        }
+        let bits = register_size(modulus);
+        if 3 * bits > StateVectorBackend::MAX_QUBITS {
+            return Err(QuantumError::SimulationError(format!(
+                "order finding modulo {modulus} needs {} qubits, more than can be simulated",
+                3 * bits
+            )));
+        }
+        let base = base % modulus;
+        let mut one = QuantumState::new(bits);
+        one.apply_gate(&QuantumGate::PauliX, &[0])?;
+        // The controlled powers base^(2^k) of the modular exponentiation are squared classically
+        let mut multiplier = base;
+        let estimation = PhaseEstimation::new(2 * bits)?;
+        let estimate = estimation.estimate_with_powers(
+            &one,
+            |exponent| {
+                if exponent > 0 {
+                    multiplier = mul_mod(multiplier, multiplier, modulus);
+                }
+                Self::modular_multiplier(multiplier, modulus)
+            },
+            ORDER_FINDING_SHOTS,
+            rng,
+        )?;
+
+        let mut measurements: Vec<(usize, usize)> =
+            estimate.counts.into_iter().map(|(k, count)| (count, k)).collect();
+        measurements.sort_unstable_by(|a, b| b.cmp(a));
+        // Denominators of different measurements can each be a divisor of the order
+        let mut combined = 1;
+        for (_, numerator) in measurements {
+            let denominators = convergent_denominators(numerator as u64, 1 << (2 * bits), modulus);
+            for &denominator in &denominators {
+                for candidate in [denominator, lcm(denominator, combined)] {
+                    if candidate < modulus && pow_mod(base, candidate, modulus) == 1 {
+                        return Ok(Some(minimal_order(base, candidate, modulus)));
+                    }
+                }
+            }
+            if let Some(&last) = denominators.last()
+                && lcm(last, combined) < modulus
+            {
+                combined = lcm(last, combined);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Split `n` into two nontrivial factors
+    ///
+    /// Even numbers and perfect powers are split classically. Otherwise random bases are
+    /// tried, each through the order found by [`Shor::find_order`], unless the base happens
+    /// to share a factor with `n`.
+    pub fn factor<R: Rng + ?Sized>(n: u64, rng: &mut R) -> QuantumResult<Factorization> {
+        if n < 4 || is_prime(n) {
+            return Err(QuantumError::SimulationError(format!("{n} has no nontrivial factors")));
+        }
+        let split = |factor: u64| (factor.min(n / factor), factor.max(n / factor));
+        if n.is_multiple_of(2) {
+            return Ok(Factorization { factors: split(2), base: None, order: None, attempts: 0 });
+        }
+        if let Some(root) = perfect_power_root(n) {
+            return Ok(Factorization {
+                factors: split(root),
+                base: None,
+                order: None,
+                attempts: 0,
+            });
+        }
+
+        for attempts in 1..=FACTORING_ATTEMPTS {
+            let base = rng.random_range(2..n - 1);
+            let common = gcd(base, n);
+            if common > 1 {
+                let factors = split(common);
+                return Ok(Factorization { factors, base: Some(base), order: None, attempts });
+            }
+            let Some(order) = Self::find_order(base, n, rng)? else {
+                continue;
+            };
+            if order % 2 == 1 {
+                continue;
+            }
+            // x² ≡ 1 with x ≢ 1 by minimality, so x ≢ -1 makes n divide (x - 1)(x + 1) but
+            // neither factor
+            let root = pow_mod(base, order / 2, n);
+            if root == n - 1 {
+                continue;
+            }
+            let factors = split(gcd(root - 1, n));
+            return Ok(Factorization { factors, base: Some(base), order: Some(order), attempts });
+        }
+        Err(QuantumError::SimulationError(format!(
+            "no factor of {n} found with {FACTORING_ATTEMPTS} bases"
+        )))
+    }
+}
+
+/// Qubits that hold every value below `modulus`
+fn register_size(modulus: u64) -> usize {
+    (u64::BITS - (modulus - 1).leading_zeros()).max(1) as usize
+}
+
+/// Denominators below `bound` of the convergents of the continued fraction of
+/// `numerator / denominator`
+fn convergent_denominators(mut numerator: u64, mut denominator: u64, bound: u64) -> Vec<u64> {
+    let mut denominators = Vec::new();
+    // Convergent denominators follow q_k = a_k q_{k-1} + q_{k-2}, from q_{-2} = 1, q_{-1} = 0
+    let (mut before_last, mut last) = (1u64, 0u64);
+    while denominator != 0 {
+        let term = numerator / denominator;
+        let next = term.saturating_mul(last).saturating_add(before_last);
+        if next >= bound {
+            break;
+        }
+        denominators.push(next);
+        (before_last, last) = (last, next);
+        (numerator, denominator) = (denominator, numerator % denominator);
+    }
+    denominators
+}
+
+/// Divide the prime factors out of `multiple`, a multiple of the order of `base`, for as
+/// long as it remains one
+fn minimal_order(base: u64, multiple: u64, modulus: u64) -> u64 {
+    let mut order = multiple;
+    let mut remaining = multiple;
+    let mut prime = 2;
+    while remaining > 1 {
+        if remaining.is_multiple_of(prime) {
+            while remaining.is_multiple_of(prime) {
+                remaining /= prime;
+            }
+            while order.is_multiple_of(prime) && pow_mod(base, order / prime, modulus) == 1 {
+                order /= prime;
+            }
+        }
+        prime += 1;
+    }
+    order
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    base %= modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn is_prime(n: u64) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d))
+}
+
+/// `b` with `b^e = n` for some `e ≥ 2`, if there is one
+fn perfect_power_root(n: u64) -> Option<u64> {
+    (2..u64::BITS).find_map(|exponent| {
+        let estimate = (n as f64).powf(1.0 / exponent as f64).round() as u64;
+        (estimate.saturating_sub(1)..=estimate + 1)
+            .find(|&root| root > 1 && root.checked_pow(exponent) == Some(n))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+
+    #[test]
+    fn test_continued_fractions() {
+        // 427/1024 ≈ 5/12 has the convergents 0, 1/2, 2/5, 3/7, 5/12, ...
+        assert_eq!(convergent_denominators(427, 1024, 21), vec![1, 2, 5, 7, 12]);
+        assert_eq!(convergent_denominators(0, 256, 15), vec![1]);
+        assert_eq!(minimal_order(2, 24, 21), 6);
+        assert_eq!(perfect_power_root(3 * 3 * 3), Some(3));
+        assert_eq!(perfect_power_root(15), None);
+    }
+
+    #[test]
+    fn test_order_finding() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let multiplier = Shor::modular_multiplier(7, 15).unwrap();
+        let mut state = QuantumState::new(4);
+        state.apply_gate(&QuantumGate::PauliX, &[1]).unwrap();
+        state.apply_gate(&multiplier, &[0, 1, 2, 3]).unwrap();
+        assert_eq!(state.probabilities()[14], 1.0);
+        assert!(Shor::modular_multiplier(5, 15).is_err());
+
+        for (base, modulus, order) in [(7, 15, 4), (4, 15, 2), (2, 21, 6), (5, 21, 6), (8, 21, 2)] {
+            let found = (0..3).find_map(|_| Shor::find_order(base, modulus, &mut rng).unwrap());
+            assert_eq!(found, Some(order), "{base} mod {modulus}");
+        }
+    }
+
+    #[test]
+    fn test_factoring() {
+        // With this seed both numbers are split through an order rather than a base that
+        // happens to share a factor
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        for (n, factors) in [(15, (3, 5)), (21, (3, 7))] {
+            let factorization = Shor::factor(n, &mut rng).unwrap();
+            assert_eq!(factorization.factors, factors);
+            let (base, order) = (factorization.base.unwrap(), factorization.order.unwrap());
+            assert_eq!(pow_mod(base, order, n), 1);
+        }
+        let even = Shor::factor(22, &mut rng).unwrap();
+        assert_eq!((even.factors, even.base), ((2, 11), None));
+        assert_eq!(Shor::factor(27, &mut rng).unwrap().factors, (3, 9));
+        assert!(Shor::factor(13, &mut rng).is_err());
+        assert!(Shor::factor(3, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,861 @@
+//! Stabilizer simulation of Clifford circuits
+//!
+//! A stabilizer state of `n` qubits is described by `n` Pauli operators that leave it
+//! unchanged. [`StabilizerTableau`] keeps those operators together with `n` destabilizers, as in
+//! Aaronson and Gottesman, "Improved simulation of stabilizer circuits" (2004), so Clifford
+//! gates take `O(n)` time and measurements `O(n²)`, instead of the `O(2^n)` of a state vector.
+
+use std::collections::BTreeMap;
+use std::f64::consts::FRAC_PI_2;
+use std::fmt;
+use std::str::FromStr;
+
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::circuit::{bound_gate, check_bound};
+use super::{
+    Backend, Condition, ExecutionResult, Operation, QuantumCircuit, QuantumGate, bitstring,
+    check_qubits,
+};
+use crate::{QuantumError, QuantumResult};
+
+/// Largest distance of an angle from a multiple of π/2 for the gate to count as Clifford
+const ANGLE_TOLERANCE: f64 = 1e-9;
+
+/// Pauli operator on one qubit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pauli {
+    I,
+    X,
+    Y,
+    Z,
+}
+
+impl Pauli {
+    fn from_bits(x: bool, z: bool) -> Self {
+        match (x, z) {
+            (false, false) => Pauli::I,
+            (true, false) => Pauli::X,
+            (true, true) => Pauli::Y,
+            (false, true) => Pauli::Z,
+        }
+    }
+
+    fn bits(self) -> (bool, bool) {
+        match self {
+            Pauli::I => (false, false),
+            Pauli::X => (true, false),
+            Pauli::Y => (true, true),
+            Pauli::Z => (false, true),
+        }
+    }
+}
+
+/// Tensor product of Paulis on a register, without a phase
+///
+/// Written with qubit 0 first, so `XZI` is `X` on qubit 0 and `Z` on qubit 1.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PauliString {
+    x: Vec<bool>,
+    z: Vec<bool>,
+}
+
+impl PauliString {
+    /// Identity on `num_qubits` qubits
+    pub fn identity(num_qubits: usize) -> Self {
+        Self { x: vec![false; num_qubits], z: vec![false; num_qubits] }
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn get(&self, qubit: usize) -> Pauli {
+        Pauli::from_bits(self.x[qubit], self.z[qubit])
+    }
+
+    pub fn set(&mut self, qubit: usize, pauli: Pauli) {
+        (self.x[qubit], self.z[qubit]) = pauli.bits();
+    }
+
+    /// Qubits on which the operator is not the identity
+    pub fn support(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.num_qubits()).filter(|&qubit| self.x[qubit] || self.z[qubit])
+    }
+
+    pub fn weight(&self) -> usize {
+        self.support().count()
+    }
+
+    /// Whether the operator has no X or Y factors
+    pub fn is_z_type(&self) -> bool {
+        !self.x.contains(&true)
+    }
+
+    /// Whether the operator has no Z or Y factors
+    pub fn is_x_type(&self) -> bool {
+        !self.z.contains(&true)
+    }
+
+    pub fn commutes_with(&self, other: &PauliString) -> bool {
+        let anticommuting = (0..self.num_qubits().min(other.num_qubits()))
+            .filter(|&qubit| (self.x[qubit] && other.z[qubit]) != (self.z[qubit] && other.x[qubit]))
+            .count();
+        anticommuting % 2 == 0
+    }
+
+    /// Multiply by `other`, dropping the phase of the product
+    pub fn multiply(&mut self, other: &PauliString) {
+        for qubit in 0..self.num_qubits().min(other.num_qubits()) {
+            self.x[qubit] ^= other.x[qubit];
+            self.z[qubit] ^= other.z[qubit];
+        }
+    }
+
+    /// The operator on a register of `num_qubits` qubits, padded with identities
+    pub fn extended(&self, num_qubits: usize) -> Self {
+        let mut extended = Self::identity(num_qubits.max(self.num_qubits()));
+        extended.x[..self.num_qubits()].copy_from_slice(&self.x);
+        extended.z[..self.num_qubits()].copy_from_slice(&self.z);
+        extended
+    }
+}
+
+impl FromStr for PauliString {
+    type Err = QuantumError;
+
+    fn from_str(text: &str) -> QuantumResult<Self> {
+        let mut pauli = Self::identity(text.chars().count());
+        for (qubit, factor) in text.chars().enumerate() {
+            let factor = match factor {
+                'I' => Pauli::I,
+                'X' => Pauli::X,
+                'Y' => Pauli::Y,
+                'Z' => Pauli::Z,
+                _ => {
+                    return Err(QuantumError::ParsingError(format!(
+                        "`{factor}` in `{text}` is not a Pauli operator"
+                    )));
+                }
+            };
+            pauli.set(qubit, factor);
+        }
+        Ok(pauli)
+    }
+}
+
+impl fmt::Display for PauliString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for qubit in 0..self.num_qubits() {
+            let factor = match self.get(qubit) {
+                Pauli::I => 'I',
+                Pauli::X => 'X',
+                Pauli::Y => 'Y',
+                Pauli::Z => 'Z',
+            };
+            write!(f, "{factor}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Gate of the generating set that the tableau applies directly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CliffordOp {
+    H(usize),
+    S(usize),
+    Sdg(usize),
+    X(usize),
+    Y(usize),
+    Z(usize),
+    Cx(usize, usize),
+}
+
+/// Stabilizer state as a tableau of `2n` Pauli operators
+///
+/// Rows `0..n` are the destabilizers and rows `n..2n` the stabilizers. Each row stores its X
+/// and Z bits packed into `u64` words, and a sign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StabilizerTableau {
+    num_qubits: usize,
+    /// Words per row
+    words: usize,
+    /// X bits of the `2n` rows and one scratch row
+    xs: Vec<u64>,
+    /// Z bits of the `2n` rows and one scratch row
+    zs: Vec<u64>,
+    /// Whether each row has the phase -1
+    signs: Vec<bool>,
+}
+
+impl StabilizerTableau {
+    /// All qubits in |0⟩, stabilized by `Z` on each qubit
+    pub fn new(num_qubits: usize) -> Self {
+        let words = num_qubits.div_ceil(64);
+        let rows = 2 * num_qubits + 1;
+        let mut tableau = Self {
+            num_qubits,
+            words,
+            xs: vec![0; rows * words],
+            zs: vec![0; rows * words],
+            signs: vec![false; rows],
+        };
+        for qubit in 0..num_qubits {
+            let (word, mask) = (qubit / 64, 1 << (qubit % 64));
+            tableau.xs[qubit * words + word] |= mask;
+            tableau.zs[(num_qubits + qubit) * words + word] |= mask;
+        }
+        tableau
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Apply a Clifford gate to `qubits`
+    ///
+    /// Rotations count as Clifford gates if their angles are multiples of π/2. The global
+    /// phase is not tracked. Other gates return an error and leave the state unchanged.
+    pub fn apply_gate(&mut self, gate: &QuantumGate, qubits: &[usize]) -> QuantumResult<()> {
+        check_qubits(gate, qubits, self.num_qubits)?;
+        for op in clifford_ops(gate, qubits)? {
+            self.apply_op(op);
+        }
+        Ok(())
+    }
+
+    fn apply_op(&mut self, op: CliffordOp) {
+        match op {
+            CliffordOp::H(qubit) => self.h(qubit),
+            CliffordOp::S(qubit) => self.s(qubit),
+            CliffordOp::Sdg(qubit) => self.sdg(qubit),
+            CliffordOp::X(qubit) => self.pauli(qubit, true, false),
+            CliffordOp::Y(qubit) => self.pauli(qubit, true, true),
+            CliffordOp::Z(qubit) => self.pauli(qubit, false, true),
+            CliffordOp::Cx(control, target) => self.cx(control, target),
+        }
+    }
+
+    /// Word index and bit mask of `qubit` within a row
+    fn locate(&self, qubit: usize) -> (usize, u64) {
+        (qubit / 64, 1 << (qubit % 64))
+    }
+
+    fn h(&mut self, qubit: usize) {
+        let (word, mask) = self.locate(qubit);
+        for row in 0..2 * self.num_qubits {
+            let index = row * self.words + word;
+            let (x, z) = (self.xs[index] & mask != 0, self.zs[index] & mask != 0);
+            self.signs[row] ^= x && z;
+            if x != z {
+                self.xs[index] ^= mask;
+                self.zs[index] ^= mask;
+            }
+        }
+    }
+
+    fn s(&mut self, qubit: usize) {
+        let (word, mask) = self.locate(qubit);
+        for row in 0..2 * self.num_qubits {
+            let index = row * self.words + word;
+            let (x, z) = (self.xs[index] & mask != 0, self.zs[index] & mask != 0);
+            self.signs[row] ^= x && z;
+            if x {
+                self.zs[index] ^= mask;
+            }
+        }
+    }
+
+    fn sdg(&mut self, qubit: usize) {
+        let (word, mask) = self.locate(qubit);
+        for row in 0..2 * self.num_qubits {
+            let index = row * self.words + word;
+            let (x, z) = (self.xs[index] & mask != 0, self.zs[index] & mask != 0);
+            self.signs[row] ^= x && !z;
+            if x {
+                self.zs[index] ^= mask;
+            }
+        }
+    }
+
+    /// Apply the Pauli with X part `pauli_x` and Z part `pauli_z`, which flips the sign of every
+    /// row that anticommutes with it
+    fn pauli(&mut self, qubit: usize, pauli_x: bool, pauli_z: bool) {
+        let (word, mask) = self.locate(qubit);
+        for row in 0..2 * self.num_qubits {
+            let index = row * self.words + word;
+            let (x, z) = (self.xs[index] & mask != 0, self.zs[index] & mask != 0);
+            self.signs[row] ^= (x && pauli_z) != (z && pauli_x);
+        }
+    }
+
+    fn cx(&mut self, control: usize, target: usize) {
+        let (control_word, control_mask) = self.locate(control);
+        let (target_word, target_mask) = self.locate(target);
+        for row in 0..2 * self.num_qubits {
+            let control_index = row * self.words + control_word;
+            let target_index = row * self.words + target_word;
+            let x_control = self.xs[control_index] & control_mask != 0;
+            let z_control = self.zs[control_index] & control_mask != 0;
+            let x_target = self.xs[target_index] & target_mask != 0;
+            let z_target = self.zs[target_index] & target_mask != 0;
+            self.signs[row] ^= x_control && z_target && x_target == z_control;
+            if x_control {
+                self.xs[target_index] ^= target_mask;
+            }
+            if z_target {
+                self.zs[control_index] ^= control_mask;
+            }
+        }
+    }
+
+    /// Replace row `target` by the product of rows `target` and `source`, keeping track of
+    /// the phase
+    fn rowsum(&mut self, target: usize, source: usize) {
+        // Twice the exponent of i in the phase of the product, which is real
+        let mut exponent = 2 * (self.signs[target] as i64) + 2 * (self.signs[source] as i64);
+        for word in 0..self.words {
+            let (x1, z1) =
+                (self.xs[source * self.words + word], self.zs[source * self.words + word]);
+            let (x2, z2) =
+                (self.xs[target * self.words + word], self.zs[target * self.words + word]);
+            // Qubits where multiplying the Paulis gives a factor of i or -i
+            let plus = (x1 & z1 & z2 & !x2) | (x1 & !z1 & z2 & x2) | (!x1 & z1 & x2 & !z2);
+            let minus = (x1 & z1 & x2 & !z2) | (x1 & !z1 & z2 & !x2) | (!x1 & z1 & x2 & z2);
+            exponent += plus.count_ones() as i64 - minus.count_ones() as i64;
+            self.xs[target * self.words + word] = x1 ^ x2;
+            self.zs[target * self.words + word] = z1 ^ z2;
+        }
+        debug_assert!(exponent.rem_euclid(2) == 0, "product of commuting Paulis is Hermitian");
+        self.signs[target] = exponent.rem_euclid(4) == 2;
+    }
+
+    fn copy_row(&mut self, target: usize, source: usize) {
+        for word in 0..self.words {
+            self.xs[target * self.words + word] = self.xs[source * self.words + word];
+            self.zs[target * self.words + word] = self.zs[source * self.words + word];
+        }
+        self.signs[target] = self.signs[source];
+    }
+
+    fn clear_row(&mut self, row: usize) {
+        for word in 0..self.words {
+            self.xs[row * self.words + word] = 0;
+            self.zs[row * self.words + word] = 0;
+        }
+        self.signs[row] = false;
+    }
+
+    /// First stabilizer that anticommutes with `Z` on `qubit`
+    fn anticommuting_stabilizer(&self, qubit: usize) -> Option<usize> {
+        let (word, mask) = self.locate(qubit);
+        (self.num_qubits..2 * self.num_qubits)
+            .find(|row| self.xs[row * self.words + word] & mask != 0)
+    }
+
+    /// Outcome of measuring `qubit` if it is certain
+    pub fn deterministic_outcome(&self, qubit: usize) -> Option<bool> {
+        if self.anticommuting_stabilizer(qubit).is_some() {
+            return None;
+        }
+        let mut tableau = self.clone();
+        Some(tableau.measure_deterministic(qubit))
+    }
+
+    /// Measure `qubit` in the computational basis, collapsing the state onto the outcome
+    pub fn measure<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> QuantumResult<bool> {
+        if qubit >= self.num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "cannot measure qubit {qubit} of a {}-qubit state",
+                self.num_qubits
+            )));
+        }
+        let Some(pivot) = self.anticommuting_stabilizer(qubit) else {
+            return Ok(self.measure_deterministic(qubit));
+        };
+
+        // The destabilizer paired with the pivot anticommutes with it and is replaced below
+        let (word, mask) = self.locate(qubit);
+        let paired = pivot - self.num_qubits;
+        for row in 0..2 * self.num_qubits {
+            if row != pivot && row != paired && self.xs[row * self.words + word] & mask != 0 {
+                self.rowsum(row, pivot);
+            }
+        }
+        self.copy_row(paired, pivot);
+        self.clear_row(pivot);
+        self.zs[pivot * self.words + word] |= mask;
+        let outcome = rng.random::<bool>();
+        self.signs[pivot] = outcome;
+        Ok(outcome)
+    }
+
+    fn measure_deterministic(&mut self, qubit: usize) -> bool {
+        let scratch = 2 * self.num_qubits;
+        let (word, mask) = self.locate(qubit);
+        self.clear_row(scratch);
+        for row in 0..self.num_qubits {
+            if self.xs[row * self.words + word] & mask != 0 {
+                self.rowsum(scratch, row + self.num_qubits);
+            }
+        }
+        self.signs[scratch]
+    }
+
+    /// Put `qubit` back into |0⟩
+    pub fn reset<R: Rng + ?Sized>(&mut self, qubit: usize, rng: &mut R) -> QuantumResult<()> {
+        if self.measure(qubit, rng)? {
+            self.pauli(qubit, true, false);
+        }
+        Ok(())
+    }
+
+    /// Apply `pauli` to the first qubits of the state
+    pub fn apply_pauli(&mut self, pauli: &PauliString) -> QuantumResult<()> {
+        self.check_pauli(pauli)?;
+        for qubit in pauli.support() {
+            let (x, z) = pauli.get(qubit).bits();
+            self.pauli(qubit, x, z);
+        }
+        Ok(())
+    }
+
+    /// Measure the observable `pauli` on the first qubits of the state, returning whether the
+    /// outcome is -1
+    ///
+    /// The observable is mapped to `Z` on one qubit by Clifford gates, which are undone after
+    /// measuring that qubit.
+    pub fn measure_pauli<R: Rng + ?Sized>(
+        &mut self,
+        pauli: &PauliString,
+        rng: &mut R,
+    ) -> QuantumResult<bool> {
+        self.check_pauli(pauli)?;
+        let support: Vec<usize> = pauli.support().collect();
+        let Some((&pivot, others)) = support.split_first() else {
+            return Ok(false);
+        };
+        let mut ops = Vec::new();
+        for &qubit in &support {
+            match pauli.get(qubit) {
+                Pauli::X => ops.push(CliffordOp::H(qubit)),
+                Pauli::Y => ops.extend([CliffordOp::Sdg(qubit), CliffordOp::H(qubit)]),
+                Pauli::I | Pauli::Z => {}
+            }
+        }
+        ops.extend(others.iter().map(|&qubit| CliffordOp::Cx(qubit, pivot)));
+        ops.iter().for_each(|&op| self.apply_op(op));
+        let outcome = self.measure(pivot, rng)?;
+        for &op in ops.iter().rev() {
+            self.apply_op(match op {
+                CliffordOp::S(qubit) => CliffordOp::Sdg(qubit),
+                CliffordOp::Sdg(qubit) => CliffordOp::S(qubit),
+                op => op,
+            });
+        }
+        Ok(outcome)
+    }
+
+    fn check_pauli(&self, pauli: &PauliString) -> QuantumResult<()> {
+        if pauli.num_qubits() > self.num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "Pauli operator `{pauli}` acts on more than {} qubits",
+                self.num_qubits
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run `circuit` on the first qubits of the state, returning its classical bits
+    pub fn run_circuit<R: Rng + ?Sized>(
+        &mut self,
+        circuit: &QuantumCircuit,
+        rng: &mut R,
+    ) -> QuantumResult<Vec<bool>> {
+        if circuit.num_qubits() > self.num_qubits {
+            return Err(QuantumError::SimulationError(format!(
+                "the circuit has {} qubits, but the state only {}",
+                circuit.num_qubits(),
+                self.num_qubits
+            )));
+        }
+        run_steps(self, circuit, &compile(circuit)?, rng)
+    }
+
+    /// The stabilizers as Pauli strings with a sign, qubit 0 first, e.g. `+XX` and `+ZZ` for a
+    /// Bell state
+    pub fn stabilizers(&self) -> Vec<String> {
+        (self.num_qubits..2 * self.num_qubits)
+            .map(|row| {
+                let mut pauli = PauliString::identity(self.num_qubits);
+                for qubit in 0..self.num_qubits {
+                    let (word, mask) = self.locate(qubit);
+                    pauli.x[qubit] = self.xs[row * self.words + word] & mask != 0;
+                    pauli.z[qubit] = self.zs[row * self.words + word] & mask != 0;
+                }
+                format!("{}{pauli}", if self.signs[row] { '-' } else { '+' })
+            })
+            .collect()
+    }
+}
+
+/// Number of quarter turns in `angle`, if it is a multiple of π/2
+fn quarter_turns(angle: f64) -> Option<u8> {
+    let turns = angle / FRAC_PI_2;
+    let rounded = turns.round();
+    ((turns - rounded).abs() < ANGLE_TOLERANCE).then(|| rounded.rem_euclid(4.0) as u8)
+}
+
+/// Rotation of `qubit` around Z by `turns` quarter turns, up to a global phase
+fn z_rotation(turns: u8, qubit: usize) -> Vec<CliffordOp> {
+    match turns {
+        0 => vec![],
+        1 => vec![CliffordOp::S(qubit)],
+        2 => vec![CliffordOp::Z(qubit)],
+        _ => vec![CliffordOp::Sdg(qubit)],
+    }
+}
+
+/// `gate` as gates of the generating set, or an error if it is not a Clifford gate
+fn clifford_ops(gate: &QuantumGate, qubits: &[usize]) -> QuantumResult<Vec<CliffordOp>> {
+    let not_clifford = || {
+        QuantumError::SimulationError(format!(
+            "gate `{}` is not a Clifford gate, the stabilizer backend only simulates Clifford \
+             circuits",
+            gate.name()
+        ))
+    };
+    let turns = |angle: f64| quarter_turns(angle).ok_or_else(not_clifford);
+    let q = qubits;
+    Ok(match gate {
+        QuantumGate::Hadamard => vec![CliffordOp::H(q[0])],
+        QuantumGate::PauliX => vec![CliffordOp::X(q[0])],
+        QuantumGate::PauliY => vec![CliffordOp::Y(q[0])],
+        QuantumGate::PauliZ => vec![CliffordOp::Z(q[0])],
+        QuantumGate::S => vec![CliffordOp::S(q[0])],
+        QuantumGate::Sdg => vec![CliffordOp::Sdg(q[0])],
+        QuantumGate::SX => vec![CliffordOp::H(q[0]), CliffordOp::S(q[0]), CliffordOp::H(q[0])],
+        QuantumGate::SXdg => {
+            vec![CliffordOp::H(q[0]), CliffordOp::Sdg(q[0]), CliffordOp::H(q[0])]
+        }
+        QuantumGate::CNOT => vec![CliffordOp::Cx(q[0], q[1])],
+        QuantumGate::CZ => {
+            vec![CliffordOp::H(q[1]), CliffordOp::Cx(q[0], q[1]), CliffordOp::H(q[1])]
+        }
+        QuantumGate::SWAP => {
+            vec![CliffordOp::Cx(q[0], q[1]), CliffordOp::Cx(q[1], q[0]), CliffordOp::Cx(q[0], q[1])]
+        }
+        QuantumGate::ISwap => vec![
+            CliffordOp::S(q[0]),
+            CliffordOp::S(q[1]),
+            CliffordOp::H(q[0]),
+            CliffordOp::Cx(q[0], q[1]),
+            CliffordOp::Cx(q[1], q[0]),
+            CliffordOp::H(q[1]),
+        ],
+        QuantumGate::Phase(theta) | QuantumGate::Rz(theta) => z_rotation(turns(*theta)?, q[0]),
+        QuantumGate::Rx(theta) => {
+            let mut ops = vec![CliffordOp::H(q[0])];
+            ops.extend(z_rotation(turns(*theta)?, q[0]));
+            ops.push(CliffordOp::H(q[0]));
+            ops
+        }
+        // Ry(θ) = S Rx(θ) S†
+        QuantumGate::Ry(theta) => {
+            let mut ops = vec![CliffordOp::Sdg(q[0]), CliffordOp::H(q[0])];
+            ops.extend(z_rotation(turns(*theta)?, q[0]));
+            ops.extend([CliffordOp::H(q[0]), CliffordOp::S(q[0])]);
+            ops
+        }
+        // U(θ, φ, λ) = Rz(φ) Ry(θ) Rz(λ) up to a global phase
+        QuantumGate::Rotation(theta, phi, lambda) => {
+            let (theta, phi, lambda) = (turns(*theta)?, turns(*phi)?, turns(*lambda)?);
+            let mut ops = z_rotation(lambda, q[0]);
+            ops.extend([CliffordOp::Sdg(q[0]), CliffordOp::H(q[0])]);
+            ops.extend(z_rotation(theta, q[0]));
+            ops.extend([CliffordOp::H(q[0]), CliffordOp::S(q[0])]);
+            ops.extend(z_rotation(phi, q[0]));
+            ops
+        }
+        QuantumGate::Controlled { controls: 1, gate } => match **gate {
+            QuantumGate::PauliX => vec![CliffordOp::Cx(q[0], q[1])],
+            QuantumGate::PauliY => {
+                vec![CliffordOp::Sdg(q[1]), CliffordOp::Cx(q[0], q[1]), CliffordOp::S(q[1])]
+            }
+            QuantumGate::PauliZ => {
+                vec![CliffordOp::H(q[1]), CliffordOp::Cx(q[0], q[1]), CliffordOp::H(q[1])]
+            }
+            _ => return Err(not_clifford()),
+        },
+        _ => return Err(not_clifford()),
+    })
+}
+
+/// Instruction of a circuit with its gate lowered to the generating set
+enum Step {
+    Gate(Vec<CliffordOp>),
+    Measure { qubit: usize, clbit: usize },
+    Reset { qubit: usize },
+}
+
+/// Lower every gate of `circuit`, failing on the first non-Clifford gate
+fn compile(circuit: &QuantumCircuit) -> QuantumResult<Vec<(Step, Option<Condition>)>> {
+    check_bound(circuit)?;
+    let mut steps = Vec::with_capacity(circuit.instructions().len());
+    for instruction in circuit.instructions() {
+        let step = match &instruction.operation {
+            Operation::Gate { gate, qubits } => {
+                Step::Gate(clifford_ops(&bound_gate(gate)?, qubits)?)
+            }
+            Operation::Measure { qubit, clbit } => Step::Measure { qubit: *qubit, clbit: *clbit },
+            Operation::Reset { qubit } => Step::Reset { qubit: *qubit },
+            Operation::Barrier { .. } => continue,
+        };
+        steps.push((step, instruction.condition));
+    }
+    Ok(steps)
+}
+
+fn run_steps<R: Rng + ?Sized>(
+    tableau: &mut StabilizerTableau,
+    circuit: &QuantumCircuit,
+    steps: &[(Step, Option<Condition>)],
+    rng: &mut R,
+) -> QuantumResult<Vec<bool>> {
+    let mut clbits = vec![false; circuit.num_clbits()];
+    for (step, condition) in steps {
+        if let Some(condition) = condition
+            && circuit.register_value(condition.register, &clbits) != condition.value
+        {
+            continue;
+        }
+        match step {
+            Step::Gate(ops) => ops.iter().for_each(|&op| tableau.apply_op(op)),
+            Step::Measure { qubit, clbit } => clbits[*clbit] = tableau.measure(*qubit, rng)?,
+            Step::Reset { qubit } => tableau.reset(*qubit, rng)?,
+        }
+    }
+    Ok(clbits)
+}
+
+/// Simulator for Clifford circuits on hundreds or thousands of qubits
+///
+/// Circuits with non-Clifford gates, such as `t` or `ccx`, are rejected before they run.
+pub struct StabilizerBackend {
+    rng: Xoshiro256PlusPlus,
+}
+
+impl StabilizerBackend {
+    /// Backend whose measurements are drawn from a generator seeded with `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { rng: Xoshiro256PlusPlus::seed_from_u64(seed) }
+    }
+
+    /// State at the end of a circuit without measurements, resets or conditions
+    pub fn tableau(&self, circuit: &QuantumCircuit) -> QuantumResult<StabilizerTableau> {
+        let mut tableau = StabilizerTableau::new(circuit.num_qubits());
+        for (step, condition) in compile(circuit)? {
+            match step {
+                Step::Gate(ops) if condition.is_none() => {
+                    ops.into_iter().for_each(|op| tableau.apply_op(op))
+                }
+                _ => {
+                    return Err(QuantumError::SimulationError(
+                        "the stabilizer state of a circuit with measurements is not defined"
+                            .to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(tableau)
+    }
+}
+
+impl Backend for StabilizerBackend {
+    fn name(&self) -> &'static str {
+        "stabilizer"
+    }
+
+    fn run(&mut self, circuit: &QuantumCircuit, shots: usize) -> QuantumResult<ExecutionResult> {
+        let mut steps = compile(circuit)?;
+        let mut initial = StabilizerTableau::new(circuit.num_qubits());
+        if circuit.has_only_final_measurements() {
+            // Every shot starts from the state before the measurements
+            let first_measurement = steps
+                .iter()
+                .position(|(step, _)| matches!(step, Step::Measure { .. }))
+                .unwrap_or(steps.len());
+            for (step, _) in steps.drain(..first_measurement) {
+                if let Step::Gate(ops) = step {
+                    ops.into_iter().for_each(|op| initial.apply_op(op));
+                }
+            }
+        }
+        let mut counts = BTreeMap::new();
+        for _ in 0..shots {
+            let mut tableau = initial.clone();
+            let clbits = run_steps(&mut tableau, circuit, &steps, &mut self.rng)?;
+            *counts.entry(bitstring(&clbits)).or_insert(0) += 1;
+        }
+        Ok(ExecutionResult { shots, counts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::quantum_algorithms::StateVectorBackend;
+
+    #[test]
+    fn test_bell_state() {
+        let mut tableau = StabilizerTableau::new(2);
+        tableau.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+        tableau.apply_gate(&QuantumGate::CNOT, &[0, 1]).unwrap();
+        assert_eq!(tableau.stabilizers(), ["+XX", "+ZZ"]);
+        assert_eq!(tableau.deterministic_outcome(0), None);
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let first = tableau.measure(0, &mut rng).unwrap();
+        assert_eq!(tableau.deterministic_outcome(1), Some(first));
+        assert_eq!(tableau.measure(1, &mut rng).unwrap(), first);
+    }
+
+    #[test]
+    fn test_signs() {
+        let mut tableau = StabilizerTableau::new(3);
+        tableau.apply_gate(&QuantumGate::PauliX, &[0]).unwrap();
+        tableau.apply_gate(&QuantumGate::Hadamard, &[1]).unwrap();
+        tableau.apply_gate(&QuantumGate::PauliZ, &[1]).unwrap();
+        tableau.apply_gate(&QuantumGate::S, &[2]).unwrap();
+        assert_eq!(tableau.stabilizers(), ["-ZII", "-IXI", "+IIZ"]);
+    }
+
+    /// Random circuit of the Clifford gates the tableau accepts
+    fn random_clifford_circuit(num_qubits: usize, gates: usize, seed: u64) -> QuantumCircuit {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let single: [QuantumGate; 12] = [
+            QuantumGate::Hadamard,
+            QuantumGate::PauliX,
+            QuantumGate::PauliY,
+            QuantumGate::PauliZ,
+            QuantumGate::S,
+            QuantumGate::Sdg,
+            QuantumGate::SX,
+            QuantumGate::SXdg,
+            QuantumGate::Rx(PI / 2.0),
+            QuantumGate::Ry(-PI / 2.0),
+            QuantumGate::Phase(3.0 * PI / 2.0),
+            QuantumGate::Rotation(PI / 2.0, PI, -PI / 2.0),
+        ];
+        let double: [QuantumGate; 5] = [
+            QuantumGate::CNOT,
+            QuantumGate::CZ,
+            QuantumGate::SWAP,
+            QuantumGate::ISwap,
+            QuantumGate::controlled(QuantumGate::PauliY, 1),
+        ];
+        let mut circuit = QuantumCircuit::new(num_qubits, num_qubits);
+        for _ in 0..gates {
+            let first = rng.random_range(0..num_qubits);
+            if rng.random::<bool>() {
+                let gate = &single[rng.random_range(0..single.len())];
+                let gate = gate.try_map_params(|&p| Ok::<_, ()>(p.into())).unwrap();
+                circuit.gate(gate, &[first]).unwrap();
+            } else {
+                let second = (first + rng.random_range(1..num_qubits)) % num_qubits;
+                let gate = &double[rng.random_range(0..double.len())];
+                let gate = gate.try_map_params(|&p| Ok::<_, ()>(p.into())).unwrap();
+                circuit.gate(gate, &[first, second]).unwrap();
+            }
+        }
+        circuit
+    }
+
+    #[test]
+    fn test_matches_state_vector() {
+        for seed in 0..20 {
+            let mut circuit = random_clifford_circuit(4, 30, seed);
+            let probabilities =
+                StateVectorBackend::new(0).statevector(&circuit).unwrap().probabilities();
+            let expected: Vec<usize> = (0..16).filter(|&i| probabilities[i] > 1e-9).collect();
+            for qubit in 0..4 {
+                circuit.measure(qubit, qubit).unwrap();
+            }
+            let result = StabilizerBackend::new(seed).run(&circuit, 400).unwrap();
+            let measured: Vec<usize> =
+                result.counts.keys().map(|bits| usize::from_str_radix(bits, 2).unwrap()).collect();
+            assert_eq!(measured, expected, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_large_ghz_state() {
+        let num_qubits = 1_000;
+        let mut circuit = QuantumCircuit::new(num_qubits, 64);
+        circuit.h(0).unwrap();
+        for qubit in 1..num_qubits {
+            circuit.cx(qubit - 1, qubit).unwrap();
+        }
+        // Measure a sample of the qubits, spread over the whole register
+        for clbit in 0..64 {
+            circuit.measure(clbit * 15, clbit).unwrap();
+        }
+        let result = StabilizerBackend::new(2).run(&circuit, 20).unwrap();
+        let zeros = "0".repeat(64);
+        let ones = "1".repeat(64);
+        assert!(result.counts.keys().all(|bits| *bits == zeros || *bits == ones));
+        assert_eq!(result.counts.len(), 2, "{:?}", result.counts.values());
+    }
+
+    #[test]
+    fn test_mid_circuit_measurement() {
+        // Teleport |1⟩ from qubit 0 to qubit 2
+        let mut circuit = QuantumCircuit::new(3, 0);
+        let c0 = circuit.add_classical_register("c0", 1).unwrap();
+        circuit.add_classical_register("c1", 1).unwrap();
+        circuit.add_classical_register("out", 1).unwrap();
+        circuit.x(0).unwrap().h(1).unwrap().cx(1, 2).unwrap().cx(0, 1).unwrap().h(0).unwrap();
+        circuit.measure(0, c0).unwrap().measure(1, c0 + 1).unwrap();
+        let z = Operation::Gate { gate: QuantumGate::PauliZ, qubits: vec![2] };
+        let x = Operation::Gate { gate: QuantumGate::PauliX, qubits: vec![2] };
+        circuit.append_conditional(x, Condition { register: 1, value: 1 }).unwrap();
+        circuit.append_conditional(z, Condition { register: 0, value: 1 }).unwrap();
+        circuit.measure(2, 2).unwrap().reset(0).unwrap();
+        let result = StabilizerBackend::new(3).run(&circuit, 200).unwrap();
+        assert_eq!(result.counts.len(), 4);
+        assert!(result.counts.keys().all(|bits| bits.starts_with('1')), "{:?}", result.counts);
+    }
+
+    #[test]
+    fn test_measure_pauli() {
+        let mut tableau = StabilizerTableau::new(3);
+        tableau.apply_gate(&QuantumGate::Hadamard, &[0]).unwrap();
+        tableau.apply_gate(&QuantumGate::CNOT, &[0, 1]).unwrap();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(4);
+        for (pauli, outcome) in [("XX", false), ("ZZI", false), ("YY", true), ("XXZ", false)] {
+            let pauli: PauliString = pauli.parse().unwrap();
+            assert_eq!(tableau.measure_pauli(&pauli, &mut rng).unwrap(), outcome, "{pauli}");
+        }
+        assert_eq!(tableau.stabilizers(), ["+XXI", "+ZZI", "+IIZ"]);
+
+        tableau.apply_pauli(&"IZ".parse().unwrap()).unwrap();
+        assert!(tableau.measure_pauli(&"XX".parse().unwrap(), &mut rng).unwrap());
+        assert!("XQ".parse::<PauliString>().is_err());
+    }
+
+    #[test]
+    fn test_rejects_non_clifford_gates() {
+        let mut tableau = StabilizerTableau::new(2);
+        let error = tableau.apply_gate(&QuantumGate::T, &[0]).unwrap_err();
+        assert!(error.to_string().contains("`t` is not a Clifford gate"), "{error}");
+        assert!(tableau.apply_gate(&QuantumGate::Rz(0.1), &[0]).is_err());
+        assert_eq!(tableau, StabilizerTableau::new(2));
+
+        let mut circuit = QuantumCircuit::new(3, 3);
+        circuit.h(0).unwrap().measure(0, 0).unwrap().ccx(0, 1, 2).unwrap();
+        assert!(StabilizerBackend::new(0).run(&circuit, 1).is_err());
+    }
+}
@@ -0,0 +1,576 @@
+//! Translation of circuits into a basis of gates
+//!
+//! Gates outside the basis are first lowered to single-qubit gates and CNOTs with exactly
+//! their matrix, so that the pieces can be controlled again. Single-qubit gates are then
+//! synthesized from the Euler angles of their matrix, which only keeps them up to a global
+//! phase.
+
+use std::collections::BTreeSet;
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use ndarray::Array2;
+use num_complex::Complex64;
+
+use crate::quantum_algorithms::circuit::bound_gate;
+use crate::quantum_algorithms::{
+    Instruction, Operation, ParameterExpr, QuantumCircuit, QuantumGate, UnitaryMatrix,
+};
+use crate::{QuantumError, QuantumResult};
+
+pub(super) type Gate = QuantumGate<ParameterExpr>;
+
+/// Angles closer than this to a multiple of a full turn are dropped, and Euler angles closer
+/// than this to a special value take the shorter sequence for it
+pub(super) const ANGLE_TOLERANCE: f64 = 1e-9;
+
+/// Names a basis may contain, those of [`QuantumGate::name`] without controlled gates and
+/// matrices
+const BASIS_GATE_NAMES: &[&str] = &[
+    "h", "x", "y", "z", "cx", "p", "u", "s", "sdg", "t", "tdg", "sx", "sxdg", "rx", "ry", "rz",
+    "cz", "swap", "iswap", "crx", "cry", "crz", "ccx", "cswap",
+];
+
+/// Set of gates a circuit is translated into
+#[derive(Debug, Clone)]
+pub(super) struct Basis {
+    names: BTreeSet<String>,
+}
+
+impl Basis {
+    pub(super) fn new(names: &[String]) -> QuantumResult<Self> {
+        if let Some(name) = names.iter().find(|name| !BASIS_GATE_NAMES.contains(&name.as_str())) {
+            return Err(QuantumError::SimulationError(format!(
+                "`{name}` is not a basis gate, the basis can contain {}",
+                BASIS_GATE_NAMES.join(", ")
+            )));
+        }
+        Ok(Self { names: names.iter().cloned().collect() })
+    }
+
+    pub(super) fn contains(&self, gate: &Gate) -> bool {
+        self.has(gate.name())
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// `circuit` with every gate outside the basis replaced by gates in it
+    pub(super) fn translate(&self, circuit: &QuantumCircuit) -> QuantumResult<QuantumCircuit> {
+        let mut translated = circuit.copy_empty();
+        for instruction in circuit.instructions() {
+            let Operation::Gate { gate, qubits } = &instruction.operation else {
+                translated.push(instruction.clone())?;
+                continue;
+            };
+            if self.contains(gate) {
+                translated.push(instruction.clone())?;
+                continue;
+            }
+            let mut lowered = Vec::new();
+            lower(gate, qubits, &mut lowered)?;
+            for (gate, qubits) in lowered {
+                for (gate, qubits) in self.translate_lowered(gate, qubits)? {
+                    let operation = Operation::Gate { gate, qubits };
+                    translated.push(Instruction { operation, condition: instruction.condition })?;
+                }
+            }
+        }
+        Ok(translated)
+    }
+
+    /// A single-qubit gate or CNOT from [`lower`] as gates of the basis
+    fn translate_lowered(
+        &self,
+        gate: Gate,
+        qubits: Vec<usize>,
+    ) -> QuantumResult<Vec<(Gate, Vec<usize>)>> {
+        if self.contains(&gate) {
+            return Ok(vec![(gate, qubits)]);
+        }
+        if gate.num_qubits() == 1 {
+            let gates = self.synthesize_gate(&gate)?;
+            return Ok(gates.into_iter().map(|gate| (gate, qubits.clone())).collect());
+        }
+        if !self.has("cz") {
+            return Err(QuantumError::SimulationError(
+                "the basis has neither `cx` nor `cz`, so it cannot express two-qubit gates"
+                    .to_string(),
+            ));
+        }
+        let mut gates = Vec::new();
+        for gate in self.synthesize_gate(&QuantumGate::Hadamard)? {
+            gates.push((gate, vec![qubits[1]]));
+        }
+        let hadamards = gates.clone();
+        gates.push((QuantumGate::CZ, qubits));
+        gates.extend(hadamards);
+        Ok(gates)
+    }
+
+    /// Single-qubit `gate` as gates of the basis, up to a global phase
+    ///
+    /// Gates with unbound parameters are rewritten around a Z rotation of the same angle.
+    fn synthesize_gate(&self, gate: &Gate) -> QuantumResult<Vec<Gate>> {
+        if self.contains(gate) {
+            return Ok(vec![gate.clone()]);
+        }
+        if gate.params().iter().all(|param| param.value().is_some()) {
+            return self.synthesize(&bound_gate(gate)?.matrix());
+        }
+        let sequence = match gate {
+            QuantumGate::Rz(theta) | QuantumGate::Phase(theta) => {
+                return Ok(vec![self.z_rotation(theta.clone())?]);
+            }
+            QuantumGate::Rx(theta) if self.has("u") => {
+                vec![QuantumGate::Rotation(theta.clone(), (-FRAC_PI_2).into(), FRAC_PI_2.into())]
+            }
+            // Rx(θ) = H Rz(θ) H
+            QuantumGate::Rx(theta) => {
+                vec![QuantumGate::Hadamard, QuantumGate::Rz(theta.clone()), QuantumGate::Hadamard]
+            }
+            QuantumGate::Ry(theta) if self.has("u") => {
+                vec![QuantumGate::Rotation(theta.clone(), 0.0.into(), 0.0.into())]
+            }
+            // Ry(θ) = S Rx(θ) S†
+            QuantumGate::Ry(theta) => vec![
+                QuantumGate::Sdg,
+                QuantumGate::Hadamard,
+                QuantumGate::Rz(theta.clone()),
+                QuantumGate::Hadamard,
+                QuantumGate::S,
+            ],
+            // U(θ, φ, λ) = Rz(φ) Ry(θ) Rz(λ) up to a global phase
+            QuantumGate::Rotation(theta, phi, lambda) => vec![
+                QuantumGate::Rz(lambda.clone()),
+                QuantumGate::Ry(theta.clone()),
+                QuantumGate::Rz(phi.clone()),
+            ],
+            _ => unreachable!("gate `{}` has no parameters", gate.name()),
+        };
+        let mut gates = Vec::new();
+        for gate in &sequence {
+            gates.extend(self.synthesize_gate(gate)?);
+        }
+        Ok(gates)
+    }
+
+    /// Rotation of a parameterized angle around Z, up to a global phase
+    fn z_rotation(&self, angle: ParameterExpr) -> QuantumResult<Gate> {
+        if self.has("rz") {
+            Ok(QuantumGate::Rz(angle))
+        } else if self.has("p") {
+            Ok(QuantumGate::Phase(angle))
+        } else if self.has("u") {
+            Ok(QuantumGate::Rotation(0.0.into(), 0.0.into(), angle))
+        } else {
+            Err(QuantumError::SimulationError(
+                "the basis has none of `rz`, `p` and `u`, so it cannot express parameterized \
+                 rotations"
+                    .to_string(),
+            ))
+        }
+    }
+
+    /// The single-qubit unitary `matrix` as the shortest sequence of basis gates, up to a
+    /// global phase
+    pub(super) fn synthesize(&self, matrix: &Array2<Complex64>) -> QuantumResult<Vec<Gate>> {
+        let (_, theta, phi, lambda) = euler_angles(matrix);
+        let z_gate = if self.has("rz") {
+            Some(QuantumGate::Rz as fn(ParameterExpr) -> Gate)
+        } else if self.has("p") {
+            Some(QuantumGate::Phase as fn(ParameterExpr) -> Gate)
+        } else {
+            None
+        };
+        // Drops rotations by a multiple of a full turn, which are a global phase
+        let z = |angle: f64| {
+            let angle = normalize(angle);
+            (angle.abs() > ANGLE_TOLERANCE).then(|| z_gate.unwrap()(angle.into()))
+        };
+        let near = |angle: f64| (theta - angle).abs() < ANGLE_TOLERANCE;
+        if near(0.0) && normalize(phi + lambda).abs() < ANGLE_TOLERANCE {
+            return Ok(Vec::new());
+        }
+
+        let mut candidates: Vec<Vec<Option<Gate>>> = Vec::new();
+        if self.has("u") {
+            candidates.push(vec![Some(QuantumGate::Rotation(
+                theta.into(),
+                phi.into(),
+                lambda.into(),
+            ))]);
+        }
+        if z_gate.is_some() {
+            if near(0.0) {
+                candidates.push(vec![z(phi + lambda)]);
+            }
+            if self.has("sx") {
+                // U(θ, φ, λ) = Rz(φ + π) SX Rz(θ + π) SX Rz(λ) up to a global phase
+                candidates.push(vec![
+                    z(lambda),
+                    Some(QuantumGate::SX),
+                    z(theta + PI),
+                    Some(QuantumGate::SX),
+                    z(phi + PI),
+                ]);
+                if near(FRAC_PI_2) {
+                    candidates.push(vec![
+                        z(lambda - FRAC_PI_2),
+                        Some(QuantumGate::SX),
+                        z(phi + FRAC_PI_2),
+                    ]);
+                }
+            }
+            if self.has("x") && near(PI) {
+                candidates.push(vec![z(lambda - phi + PI), Some(QuantumGate::PauliX)]);
+            }
+            if self.has("ry") {
+                candidates.push(vec![z(lambda), Some(QuantumGate::Ry(theta.into())), z(phi)]);
+            }
+            // Ry(θ) = Rz(π/2) Rx(θ) Rz(-π/2)
+            if self.has("rx") {
+                candidates.push(vec![
+                    z(lambda - FRAC_PI_2),
+                    Some(QuantumGate::Rx(theta.into())),
+                    z(phi + FRAC_PI_2),
+                ]);
+            }
+            if self.has("h") {
+                candidates.push(vec![
+                    z(lambda - FRAC_PI_2),
+                    Some(QuantumGate::Hadamard),
+                    z(theta),
+                    Some(QuantumGate::Hadamard),
+                    z(phi + FRAC_PI_2),
+                ]);
+            }
+        }
+        candidates
+            .into_iter()
+            .map(|candidate| candidate.into_iter().flatten().collect::<Vec<_>>())
+            .min_by_key(Vec::len)
+            .ok_or_else(|| {
+                QuantumError::SimulationError(
+                    "the basis cannot express every single-qubit gate, it needs `u`, or `rz` or \
+                     `p` with one of `sx`, `rx`, `ry` and `h`"
+                        .to_string(),
+                )
+            })
+    }
+}
+
+/// Angle equivalent to `angle` in `(-π, π]`
+pub(super) fn normalize(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(TAU);
+    if angle > PI { angle - TAU } else { angle }
+}
+
+/// Angles `(α, θ, φ, λ)` with `matrix = e^{iα} Rz(φ) Ry(θ) Rz(λ)` for a single-qubit unitary,
+/// which is `U(θ, φ, λ)` up to a global phase, with `θ` in `[0, π]`
+pub(super) fn euler_angles(matrix: &Array2<Complex64>) -> (f64, f64, f64, f64) {
+    let determinant = matrix[[0, 0]] * matrix[[1, 1]] - matrix[[0, 1]] * matrix[[1, 0]];
+    let alpha = determinant.arg() / 2.0;
+    // The matrix with unit determinant is [[e^{-i(φ+λ)/2} c, ..], [e^{i(φ-λ)/2} s, ..]]
+    let special = matrix.mapv(|element| element * Complex64::cis(-alpha));
+    let (cos, sin) = (special[[0, 0]], special[[1, 0]]);
+    let theta = 2.0 * sin.norm().atan2(cos.norm());
+    let sum = if cos.norm() > ANGLE_TOLERANCE { 2.0 * special[[1, 1]].arg() } else { 0.0 };
+    let difference = if sin.norm() > ANGLE_TOLERANCE { 2.0 * sin.arg() } else { 0.0 };
+    (alpha, theta, (sum + difference) / 2.0, (sum - difference) / 2.0)
+}
+
+/// `gate` on `qubits` as single-qubit gates and CNOTs with the same matrix, phase included
+fn lower(gate: &Gate, qubits: &[usize], out: &mut Vec<(Gate, Vec<usize>)>) -> QuantumResult<()> {
+    if let Some((controls, target)) = gate.split_controls() {
+        let (controls, targets) = qubits.split_at(controls);
+        if target.num_qubits() == 1 {
+            return control(&target, controls, targets[0], out);
+        }
+        // The pieces of an exact decomposition can be controlled one by one
+        let mut pieces = Vec::new();
+        lower(&target, targets, &mut pieces)?;
+        for (piece, qubits) in pieces {
+            if piece == QuantumGate::CNOT {
+                let all_controls = [controls, &qubits[..1]].concat();
+                control(&QuantumGate::PauliX, &all_controls, qubits[1], out)?;
+            } else {
+                control(&piece, controls, qubits[0], out)?;
+            }
+        }
+        return Ok(());
+    }
+    let (a, b) = (qubits[0], *qubits.get(1).unwrap_or(&qubits[0]));
+    match gate {
+        _ if gate.num_qubits() == 1 => out.push((gate.clone(), qubits.to_vec())),
+        QuantumGate::SWAP => {
+            out.extend([(QuantumGate::CNOT, vec![a, b]), (QuantumGate::CNOT, vec![b, a])]);
+            out.push((QuantumGate::CNOT, vec![a, b]));
+        }
+        QuantumGate::ISwap => out.extend([
+            (QuantumGate::S, vec![a]),
+            (QuantumGate::S, vec![b]),
+            (QuantumGate::Hadamard, vec![a]),
+            (QuantumGate::CNOT, vec![a, b]),
+            (QuantumGate::CNOT, vec![b, a]),
+            (QuantumGate::Hadamard, vec![b]),
+        ]),
+        _ => {
+            return Err(QuantumError::SimulationError(format!(
+                "a {}-qubit unitary matrix cannot be decomposed into basis gates",
+                gate.num_qubits()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Single-qubit `gate` on `target` controlled by `controls`, exactly
+fn control(
+    gate: &Gate,
+    controls: &[usize],
+    target: usize,
+    out: &mut Vec<(Gate, Vec<usize>)>,
+) -> QuantumResult<()> {
+    let t = target;
+    let half = |theta: &ParameterExpr| theta.clone() / 2.0;
+    let Some((&last, rest)) = controls.split_last() else {
+        out.push((gate.clone(), vec![target]));
+        return Ok(());
+    };
+    let c = last;
+    let cx = || (QuantumGate::CNOT, vec![c, t]);
+    match (rest.len(), gate) {
+        (0, QuantumGate::PauliX) => out.push(cx()),
+        (0, QuantumGate::PauliZ) => {
+            out.extend([(QuantumGate::Hadamard, vec![t]), cx(), (QuantumGate::Hadamard, vec![t])]);
+        }
+        (1, QuantumGate::PauliX) => {
+            let b = rest[0];
+            let gates = [
+                (QuantumGate::Hadamard, t),
+                (QuantumGate::CNOT, c),
+                (QuantumGate::Tdg, t),
+                (QuantumGate::CNOT, b),
+                (QuantumGate::T, t),
+                (QuantumGate::CNOT, c),
+                (QuantumGate::Tdg, t),
+                (QuantumGate::CNOT, b),
+                (QuantumGate::T, c),
+                (QuantumGate::T, t),
+                (QuantumGate::Hadamard, t),
+            ];
+            for (gate, qubit) in gates {
+                let qubits = if gate == QuantumGate::CNOT { vec![qubit, t] } else { vec![qubit] };
+                out.push((gate, qubits));
+            }
+            out.extend([
+                (QuantumGate::CNOT, vec![b, c]),
+                (QuantumGate::T, vec![b]),
+                (QuantumGate::Tdg, vec![c]),
+                (QuantumGate::CNOT, vec![b, c]),
+            ]);
+        }
+        (0, QuantumGate::Rz(theta)) => out.extend([
+            (QuantumGate::Rz(half(theta)), vec![t]),
+            cx(),
+            (QuantumGate::Rz(-half(theta)), vec![t]),
+            cx(),
+        ]),
+        (0, QuantumGate::Ry(theta)) => out.extend([
+            (QuantumGate::Ry(half(theta)), vec![t]),
+            cx(),
+            (QuantumGate::Ry(-half(theta)), vec![t]),
+            cx(),
+        ]),
+        (0, QuantumGate::Rx(theta)) => {
+            out.push((QuantumGate::Hadamard, vec![t]));
+            control(&QuantumGate::Rz(theta.clone()), controls, t, out)?;
+            out.push((QuantumGate::Hadamard, vec![t]));
+        }
+        (0, QuantumGate::Phase(theta)) => out.extend([
+            (QuantumGate::Phase(half(theta)), vec![c]),
+            cx(),
+            (QuantumGate::Phase(-half(theta)), vec![t]),
+            cx(),
+            (QuantumGate::Phase(half(theta)), vec![t]),
+        ]),
+        // The decomposition of `cu3` in `qelib1.inc`
+        (0, QuantumGate::Rotation(theta, phi, lambda)) => out.extend([
+            (QuantumGate::Phase((lambda.clone() + phi.clone()) / 2.0), vec![c]),
+            (QuantumGate::Phase((lambda.clone() - phi.clone()) / 2.0), vec![t]),
+            cx(),
+            (
+                QuantumGate::Rotation(
+                    -half(theta),
+                    0.0.into(),
+                    -((phi.clone() + lambda.clone()) / 2.0),
+                ),
+                vec![t],
+            ),
+            cx(),
+            (QuantumGate::Rotation(half(theta), phi.clone(), 0.0.into()), vec![t]),
+        ]),
+        // With U = e^{iα} Rz(β) Ry(γ) Rz(δ), A = Rz(β) Ry(γ/2), B = Ry(-γ/2) Rz(-(δ + β)/2) and
+        // C = Rz((δ - β)/2) give ABC = I and AXBXC = e^{-iα} U
+        (0, _) => {
+            let (alpha, gamma, beta, delta) = euler_angles(&bound_gate(gate)?.matrix());
+            let rz = |angle: f64| (QuantumGate::Rz(angle.into()), vec![t]);
+            let ry = |angle: f64| (QuantumGate::Ry(angle.into()), vec![t]);
+            if alpha.abs() > ANGLE_TOLERANCE {
+                out.push((QuantumGate::Phase(alpha.into()), vec![c]));
+            }
+            out.extend([
+                rz((delta - beta) / 2.0),
+                cx(),
+                rz(-(delta + beta) / 2.0),
+                ry(-gamma / 2.0),
+            ]);
+            out.extend([cx(), ry(gamma / 2.0), rz(beta)]);
+        }
+        // C^k U = C(V) X_k C(V†) X_k C^{k-1}(V) with V² = U and X_k an X on the last control
+        // controlled by the others
+        (_, _) => {
+            let root = square_root(gate)?;
+            control(&root, &[c], t, out)?;
+            control(&QuantumGate::PauliX, rest, c, out)?;
+            control(&root.inverse(), &[c], t, out)?;
+            control(&QuantumGate::PauliX, rest, c, out)?;
+            control(&root, rest, t, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Single-qubit gate whose square is `gate`
+fn square_root(gate: &Gate) -> QuantumResult<Gate> {
+    Ok(match gate {
+        QuantumGate::PauliX => QuantumGate::SX,
+        QuantumGate::Rx(theta) => QuantumGate::Rx(theta.clone() / 2.0),
+        QuantumGate::Ry(theta) => QuantumGate::Ry(theta.clone() / 2.0),
+        QuantumGate::Rz(theta) => QuantumGate::Rz(theta.clone() / 2.0),
+        QuantumGate::Phase(theta) => QuantumGate::Phase(theta.clone() / 2.0),
+        _ => {
+            // A square root of a 2 × 2 matrix is (U + sI)/t with s² = det U and t² = tr U + 2s,
+            // where the sign of s keeps t away from zero
+            let matrix = bound_gate(gate)?.matrix();
+            let determinant = matrix[[0, 0]] * matrix[[1, 1]] - matrix[[0, 1]] * matrix[[1, 0]];
+            let trace = matrix[[0, 0]] + matrix[[1, 1]];
+            let s = determinant.sqrt();
+            let s = if (trace + 2.0 * s).norm() >= (trace - 2.0 * s).norm() { s } else { -s };
+            let t = (trace + 2.0 * s).sqrt();
+            let root = (matrix + Array2::eye(2).mapv(|one: Complex64| one * s)) / t;
+            QuantumGate::Unitary(UnitaryMatrix::new(root)?)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantum_algorithms::transpiler::tests::{assert_equivalent, gate_circuit};
+
+    fn basis(names: &[&str]) -> Basis {
+        Basis::new(&names.iter().map(|name| name.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    fn test_gates() -> Vec<Gate> {
+        let theta = || ParameterExpr::from(0.7);
+        vec![
+            QuantumGate::Hadamard,
+            QuantumGate::PauliY,
+            QuantumGate::SXdg,
+            QuantumGate::Tdg,
+            QuantumGate::Rotation(0.4.into(), 1.1.into(), (-0.7).into()),
+            QuantumGate::Rx(theta()),
+            QuantumGate::Ry(theta()),
+            QuantumGate::CZ,
+            QuantumGate::SWAP,
+            QuantumGate::ISwap,
+            QuantumGate::CRx(theta()),
+            QuantumGate::CRy(theta()),
+            QuantumGate::CRz(theta()),
+            QuantumGate::Toffoli,
+            QuantumGate::Fredkin,
+            QuantumGate::controlled(QuantumGate::Phase(theta()), 1),
+            QuantumGate::controlled(QuantumGate::Rotation(0.2.into(), 0.3.into(), 0.4.into()), 1),
+            QuantumGate::controlled(QuantumGate::Hadamard, 1),
+            QuantumGate::controlled(QuantumGate::Rotation(0.2.into(), 0.3.into(), 0.4.into()), 2),
+            QuantumGate::controlled(QuantumGate::PauliX, 3),
+            QuantumGate::controlled(QuantumGate::Rz(theta()), 2),
+            QuantumGate::controlled(QuantumGate::ISwap, 1),
+            QuantumGate::Unitary(UnitaryMatrix::new(QuantumGate::<f64>::SX.matrix()).unwrap()),
+        ]
+    }
+
+    #[test]
+    fn test_translation_to_bases() {
+        let bases: [&[&str]; 5] = [
+            &["cx", "rz", "sx", "x"],
+            &["cx", "u"],
+            &["cz", "rz", "ry"],
+            &["cx", "p", "rx"],
+            &["cz", "rz", "h"],
+        ];
+        for names in bases {
+            let basis = basis(names);
+            for gate in test_gates() {
+                let circuit = gate_circuit(gate.clone());
+                let translated = basis.translate(&circuit).unwrap();
+                for name in translated.gate_counts().into_keys() {
+                    assert!(names.contains(&name), "{gate:?} in {names:?} gives `{name}`");
+                }
+                assert_equivalent(&circuit, &translated);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parameterized_translation() {
+        let theta = ParameterExpr::symbol("theta");
+        let gates = [
+            QuantumGate::Rx(theta.clone()),
+            QuantumGate::Ry(theta.clone() * 2.0),
+            QuantumGate::Rotation(theta.clone(), 0.5.into(), -theta.clone()),
+            QuantumGate::CRy(theta.clone()),
+            QuantumGate::controlled(QuantumGate::Phase(theta.clone()), 2),
+        ];
+        let bindings = [("theta".to_string(), 0.9)].into_iter().collect();
+        for names in [&["cx", "rz", "sx", "x"][..], &["cx", "u"], &["cx", "p", "h"]] {
+            for gate in &gates {
+                let circuit = gate_circuit(gate.clone());
+                let translated = basis(names).translate(&circuit).unwrap();
+                assert!(translated.is_parameterized());
+                assert_equivalent(
+                    &circuit.bind_parameters(&bindings),
+                    &translated.bind_parameters(&bindings),
+                );
+            }
+        }
+        let circuit = gate_circuit(QuantumGate::Rz(theta));
+        assert!(basis(&["cx", "ry", "rx"]).translate(&circuit).is_err());
+    }
+
+    #[test]
+    fn test_shortest_synthesis() {
+        let basis = basis(&["cx", "rz", "sx", "x"]);
+        let length = |gate: QuantumGate| basis.synthesize(&gate.matrix()).unwrap().len();
+        assert_eq!(length(QuantumGate::Hadamard), 3);
+        assert_eq!(length(QuantumGate::PauliY), 2);
+        assert_eq!(length(QuantumGate::T), 1);
+        assert_eq!(length(QuantumGate::Rotation(0.0, 0.3, -0.3)), 0);
+        assert_eq!(length(QuantumGate::Ry(0.4)), 4);
+    }
+
+    #[test]
+    fn test_invalid_bases() {
+        assert!(Basis::new(&["cx".to_string(), "sqrt".to_string()]).is_err());
+        let circuit = gate_circuit(QuantumGate::CNOT);
+        assert!(basis(&["rz", "sx"]).translate(&circuit).is_err());
+        let circuit = gate_circuit(QuantumGate::Hadamard);
+        assert!(basis(&["cx", "sx", "x"]).translate(&circuit).is_err());
+        // Gates in the basis are kept even if the basis is not universal
+        let translated = basis(&["h"]).translate(&circuit).unwrap();
+        assert_eq!(translated, circuit);
+    }
+}
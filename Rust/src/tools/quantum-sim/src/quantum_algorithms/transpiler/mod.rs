@@ -0,0 +1,261 @@
+//! Transpilation of circuits for devices
+//!
+//! A device runs a fixed set of basis gates, and two-qubit gates only between the qubits of
+//! its coupling map. The [`Transpiler`] translates a circuit into the basis, optimizes it,
+//! routes it onto the coupling map with SWAPs, and translates and optimizes the result again.
+
+mod decompose;
+mod optimize;
+mod routing;
+
+use std::collections::BTreeMap;
+
+use decompose::Basis;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+pub use routing::CouplingMap;
+
+use super::{Operation, QuantumCircuit};
+use crate::QuantumResult;
+
+/// Target device and passes of a [`Transpiler`]
+#[derive(Debug, Clone)]
+pub struct TranspilerConfig {
+    /// Names of the basis gates, as given by [`QuantumGate::name`](super::QuantumGate::name)
+    pub basis: Vec<String>,
+    /// Couplings of the physical qubits, `None` to allow two-qubit gates between any qubits
+    pub coupling_map: Option<CouplingMap>,
+    /// Whether gates are cancelled, merged and resynthesized
+    pub optimize: bool,
+    /// Forward and backward routing passes that refine the initial layout
+    pub layout_iterations: usize,
+    /// Seed of the random tie-breaking between equally good SWAPs
+    pub seed: u64,
+}
+
+impl Default for TranspilerConfig {
+    fn default() -> Self {
+        Self {
+            basis: ["cx", "rz", "sx", "x"].map(String::from).to_vec(),
+            coupling_map: None,
+            optimize: true,
+            layout_iterations: 3,
+            seed: 0,
+        }
+    }
+}
+
+/// Size of a circuit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitMetrics {
+    /// Layers of [`QuantumCircuit::depth`]
+    pub depth: usize,
+    /// Gates, without measurements, resets and barriers
+    pub gate_count: usize,
+    /// Gates on two or more qubits
+    pub multi_qubit_gates: usize,
+    /// Instructions by name, as given by [`QuantumCircuit::gate_counts`]
+    pub gate_counts: BTreeMap<&'static str, usize>,
+}
+
+impl CircuitMetrics {
+    pub fn of(circuit: &QuantumCircuit) -> Self {
+        let gates =
+            circuit.instructions().iter().filter_map(|instruction| match &instruction.operation {
+                Operation::Gate { qubits, .. } => Some(qubits.len()),
+                _ => None,
+            });
+        let (gate_count, multi_qubit_gates) =
+            gates.fold((0, 0), |(all, multi), qubits| (all + 1, multi + usize::from(qubits > 1)));
+        Self {
+            depth: circuit.depth(),
+            gate_count,
+            multi_qubit_gates,
+            gate_counts: circuit.gate_counts(),
+        }
+    }
+}
+
+/// Outcome of [`Transpiler::transpile`]
+#[derive(Debug, Clone)]
+pub struct TranspileResult {
+    /// Circuit of basis gates, on the physical qubits if there is a coupling map
+    pub circuit: QuantumCircuit,
+    /// Physical qubit each qubit of the original circuit starts on
+    pub initial_layout: Vec<usize>,
+    /// Physical qubit the state of each qubit of the original circuit ends up on
+    pub final_layout: Vec<usize>,
+    /// SWAPs inserted by routing
+    pub swaps: usize,
+    pub before: CircuitMetrics,
+    pub after: CircuitMetrics,
+}
+
+/// Transpiler of circuits for a basis and coupling map
+///
+/// The transpiled circuit equals the original one up to a global phase and the permutation
+/// of the qubits given by the layouts.
+pub struct Transpiler {
+    config: TranspilerConfig,
+    basis: Basis,
+}
+
+impl Transpiler {
+    pub fn new(config: TranspilerConfig) -> QuantumResult<Self> {
+        let basis = Basis::new(&config.basis)?;
+        Ok(Self { config, basis })
+    }
+
+    pub fn config(&self) -> &TranspilerConfig {
+        &self.config
+    }
+
+    /// Transpile `circuit`
+    ///
+    /// Fails if the basis cannot express the gates of the circuit, or if routing is needed
+    /// for a gate on more than two qubits that the basis contains.
+    pub fn transpile(&self, circuit: &QuantumCircuit) -> QuantumResult<TranspileResult> {
+        let mut transpiled = self.translate(circuit)?;
+        let trivial: Vec<usize> = (0..circuit.num_qubits()).collect();
+        let (mut initial_layout, mut final_layout, mut swaps) = (trivial.clone(), trivial, 0);
+        if let Some(coupling) = &self.config.coupling_map {
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(self.config.seed);
+            let routed =
+                routing::route(&transpiled, coupling, self.config.layout_iterations, &mut rng)?;
+            transpiled = self.translate(&routed.circuit)?;
+            (initial_layout, final_layout, swaps) =
+                (routed.initial_layout, routed.final_layout, routed.swaps);
+        }
+        Ok(TranspileResult {
+            before: CircuitMetrics::of(circuit),
+            after: CircuitMetrics::of(&transpiled),
+            circuit: transpiled,
+            initial_layout,
+            final_layout,
+            swaps,
+        })
+    }
+
+    /// `circuit` in the basis, optimized if the configuration asks for it
+    fn translate(&self, circuit: &QuantumCircuit) -> QuantumResult<QuantumCircuit> {
+        let translated = self.basis.translate(circuit)?;
+        if self.config.optimize {
+            optimize::optimize(&translated, &self.basis)
+        } else {
+            Ok(translated)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::quantum_algorithms::{
+        Backend, EquivalenceChecker, ParameterExpr, QuantumGate, StateVectorBackend,
+    };
+
+    /// Circuit of `gate` alone, on its qubits in reverse order so that they are not sorted
+    pub(super) fn gate_circuit(gate: QuantumGate<ParameterExpr>) -> QuantumCircuit {
+        let qubits: Vec<usize> = (0..gate.num_qubits()).rev().collect();
+        let mut circuit = QuantumCircuit::new(gate.num_qubits(), 0);
+        circuit.gate(gate, &qubits).unwrap();
+        circuit
+    }
+
+    /// Circuit of `gates` random single-qubit gates, CNOTs and CZs
+    pub(super) fn random_circuit(num_qubits: usize, gates: usize, seed: u64) -> QuantumCircuit {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let mut circuit = QuantumCircuit::new(num_qubits, 0);
+        for _ in 0..gates {
+            let a = rng.random_range(0..num_qubits);
+            let b = (a + rng.random_range(1..num_qubits)) % num_qubits;
+            match rng.random_range(0..6) {
+                0 => circuit.h(a),
+                1 => circuit.t(a),
+                2 => circuit.sx(a),
+                3 => circuit.rz(rng.random::<f64>(), a),
+                4 => circuit.cz(a, b),
+                _ => circuit.cx(a, b),
+            }
+            .unwrap();
+        }
+        circuit
+    }
+
+    /// Assert that two circuits of bound gates have the same unitary up to a global phase
+    pub(super) fn assert_equivalent(a: &QuantumCircuit, b: &QuantumCircuit) {
+        let result = EquivalenceChecker::new(0).check(a, b).unwrap();
+        assert!(result.is_equivalent(), "{:?}", result.counterexample);
+    }
+
+    #[test]
+    fn test_transpile() {
+        let mut circuit = QuantumCircuit::new(5, 5);
+        circuit.h(0).unwrap();
+        for qubit in 1..5 {
+            circuit.cx(0, qubit).unwrap();
+        }
+        circuit.ccx(4, 1, 2).unwrap().swap(1, 3).unwrap();
+        for qubit in 0..5 {
+            circuit.measure(qubit, qubit).unwrap();
+        }
+        let config = TranspilerConfig {
+            coupling_map: Some(CouplingMap::line(5)),
+            seed: 3,
+            ..Default::default()
+        };
+        let result = Transpiler::new(config).unwrap().transpile(&circuit).unwrap();
+        assert_eq!(result.before.gate_count, 7);
+        assert_eq!(result.before.multi_qubit_gates, 6);
+        assert!(result.swaps > 0);
+        for name in result.after.gate_counts.keys() {
+            assert!(["cx", "measure", "rz", "sx", "x"].contains(name), "{name}");
+        }
+        assert!(result.after.depth >= result.before.depth);
+
+        // The measurements still read the qubits they did before
+        let mut backend = StateVectorBackend::new(0);
+        let mut outcomes = |circuit: &QuantumCircuit| {
+            backend.run(circuit, 200).unwrap().counts.into_keys().collect::<Vec<_>>()
+        };
+        assert_eq!(outcomes(&result.circuit), ["00000", "11011"]);
+        assert_eq!(outcomes(&circuit), ["00000", "11011"]);
+    }
+
+    #[test]
+    fn test_optimization_shrinks_circuits() {
+        for seed in 0..3 {
+            let circuit = random_circuit(4, 60, seed);
+            let result =
+                Transpiler::new(TranspilerConfig::default()).unwrap().transpile(&circuit).unwrap();
+            let unoptimized =
+                Transpiler::new(TranspilerConfig { optimize: false, ..Default::default() })
+                    .unwrap()
+                    .transpile(&circuit)
+                    .unwrap();
+            assert!(result.after.gate_count < unoptimized.after.gate_count);
+            assert!(result.after.depth <= unoptimized.after.depth);
+            assert_eq!(result.initial_layout, [0, 1, 2, 3]);
+            assert_equivalent(&circuit, &result.circuit);
+        }
+    }
+
+    #[test]
+    fn test_transpile_errors() {
+        let config = TranspilerConfig { basis: vec!["cnot".to_string()], ..Default::default() };
+        assert!(Transpiler::new(config).is_err());
+        let config = TranspilerConfig {
+            basis: ["ccx", "h"].map(String::from).to_vec(),
+            coupling_map: Some(CouplingMap::line(3)),
+            ..Default::default()
+        };
+        let mut circuit = QuantumCircuit::new(3, 0);
+        circuit.ccx(0, 1, 2).unwrap();
+        assert!(Transpiler::new(config).unwrap().transpile(&circuit).is_err());
+        let config =
+            TranspilerConfig { coupling_map: Some(CouplingMap::line(2)), ..Default::default() };
+        assert!(Transpiler::new(config).unwrap().transpile(&circuit).is_err());
+    }
+}
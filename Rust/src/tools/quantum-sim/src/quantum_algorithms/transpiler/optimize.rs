@@ -0,0 +1,302 @@
+//! Peephole optimization
+//!
+//! A gate is moved back past the gates it commutes with until it meets its inverse, which
+//! cancels it, or a rotation about the same axis, which absorbs it. Runs of single-qubit
+//! gates on a qubit are multiplied out and synthesized again when that takes fewer gates. The
+//! passes repeat until neither removes a gate.
+
+use ndarray::Array2;
+use num_complex::Complex64;
+
+use super::decompose::{ANGLE_TOLERANCE, Basis, Gate, normalize};
+use crate::QuantumResult;
+use crate::quantum_algorithms::circuit::bound_gate;
+use crate::quantum_algorithms::{Instruction, Operation, QuantumCircuit, QuantumGate};
+
+/// How a gate acts on one of its qubits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// Block diagonal in the computational basis, as on a control
+    Diagonal,
+    /// Block diagonal in the `|±⟩` basis, as on the target of a CNOT
+    Flip,
+    Other,
+}
+
+/// `circuit` with gates cancelled, merged and resynthesized in `basis`, equal up to a global
+/// phase
+pub(super) fn optimize(circuit: &QuantumCircuit, basis: &Basis) -> QuantumResult<QuantumCircuit> {
+    let mut instructions: Vec<Instruction> = circuit.instructions().to_vec();
+    while cancel(circuit, &mut instructions) | fuse(circuit, basis, &mut instructions)? {}
+    let mut optimized = circuit.copy_empty();
+    for instruction in instructions {
+        optimized.push(instruction)?;
+    }
+    Ok(optimized)
+}
+
+/// Unconditional gate of an instruction
+fn unconditional_gate(instruction: &Instruction) -> Option<(&Gate, &[usize])> {
+    match &instruction.operation {
+        Operation::Gate { gate, qubits } if instruction.condition.is_none() => Some((gate, qubits)),
+        _ => None,
+    }
+}
+
+/// Cancel gates against their inverses and merge rotations about the same axis, returning
+/// whether any gate was removed
+fn cancel(circuit: &QuantumCircuit, instructions: &mut Vec<Instruction>) -> bool {
+    let mut slots: Vec<Option<Instruction>> = instructions.drain(..).map(Some).collect();
+    let mut changed = false;
+    for i in 0..slots.len() {
+        let Some((gate, qubits)) = slots[i].as_ref().and_then(unconditional_gate) else {
+            continue;
+        };
+        let (gate, qubits) = (gate.clone(), qubits.to_vec());
+        for j in (0..i).rev() {
+            let Some(earlier) = &slots[j] else {
+                continue;
+            };
+            if !circuit.wires(earlier).iter().any(|wire| qubits.contains(wire)) {
+                continue;
+            }
+            let Some((other, other_qubits)) = unconditional_gate(earlier) else {
+                break;
+            };
+            if same_qubits(&gate, &qubits, other_qubits) && other.inverse() == gate {
+                (slots[i], slots[j]) = (None, None);
+                changed = true;
+                break;
+            }
+            if other_qubits == qubits
+                && let Some(merged) = merge(other, &gate)
+            {
+                slots[i] = None;
+                slots[j] = merged.map(|gate| {
+                    let operation = Operation::Gate { gate, qubits: qubits.clone() };
+                    Instruction { operation, condition: None }
+                });
+                changed = true;
+                break;
+            }
+            if !commute(&gate, &qubits, other, other_qubits) {
+                break;
+            }
+        }
+    }
+    instructions.extend(slots.into_iter().flatten());
+    changed
+}
+
+/// Whether `gate` on `qubits` acts on the same qubits in the same roles as a gate on `other`
+fn same_qubits(gate: &Gate, qubits: &[usize], other: &[usize]) -> bool {
+    match gate {
+        QuantumGate::CZ | QuantumGate::SWAP => {
+            let mut qubits = qubits.to_vec();
+            let mut other = other.to_vec();
+            qubits.sort_unstable();
+            other.sort_unstable();
+            qubits == other
+        }
+        _ => qubits == other,
+    }
+}
+
+/// The rotation `first` followed by `second` about the same axis, `Some(None)` if the angles
+/// add up to a full turn
+fn merge(first: &Gate, second: &Gate) -> Option<Option<Gate>> {
+    let (merged, angle) = match (first, second) {
+        (QuantumGate::Rz(a), QuantumGate::Rz(b)) => {
+            let angle = a.clone() + b.clone();
+            (QuantumGate::Rz(angle.clone()), angle)
+        }
+        (QuantumGate::Rx(a), QuantumGate::Rx(b)) => {
+            let angle = a.clone() + b.clone();
+            (QuantumGate::Rx(angle.clone()), angle)
+        }
+        (QuantumGate::Ry(a), QuantumGate::Ry(b)) => {
+            let angle = a.clone() + b.clone();
+            (QuantumGate::Ry(angle.clone()), angle)
+        }
+        (QuantumGate::Phase(a), QuantumGate::Phase(b)) => {
+            let angle = a.clone() + b.clone();
+            (QuantumGate::Phase(angle.clone()), angle)
+        }
+        _ => return None,
+    };
+    // A full turn of a rotation is the global phase -1
+    let full_turn = angle.value().is_some_and(|angle| normalize(angle).abs() < ANGLE_TOLERANCE);
+    Some((!full_turn).then_some(merged))
+}
+
+/// Action of `gate` on its qubit at `position`
+fn action(gate: &Gate, position: usize) -> Action {
+    if let Some((controls, target)) = gate.split_controls() {
+        return match position < controls {
+            true => Action::Diagonal,
+            false if target.num_qubits() == 1 => action(&target, 0),
+            false => Action::Other,
+        };
+    }
+    match gate {
+        QuantumGate::PauliZ
+        | QuantumGate::S
+        | QuantumGate::Sdg
+        | QuantumGate::T
+        | QuantumGate::Tdg
+        | QuantumGate::Rz(_)
+        | QuantumGate::Phase(_) => Action::Diagonal,
+        QuantumGate::PauliX | QuantumGate::SX | QuantumGate::SXdg | QuantumGate::Rx(_) => {
+            Action::Flip
+        }
+        _ => Action::Other,
+    }
+}
+
+/// Whether two gates commute because they act on each shared qubit diagonally in the same
+/// basis
+fn commute(gate: &Gate, qubits: &[usize], other: &Gate, other_qubits: &[usize]) -> bool {
+    qubits.iter().enumerate().all(|(position, qubit)| {
+        let Some(other_position) = other_qubits.iter().position(|other| other == qubit) else {
+            return true;
+        };
+        let kind = action(gate, position);
+        kind != Action::Other && kind == action(other, other_position)
+    })
+}
+
+/// Resynthesize the runs of bound single-qubit gates on each qubit that `basis` expresses with
+/// fewer gates, returning whether any was
+fn fuse(
+    circuit: &QuantumCircuit,
+    basis: &Basis,
+    instructions: &mut Vec<Instruction>,
+) -> QuantumResult<bool> {
+    let mut runs: Vec<Vec<usize>> = vec![Vec::new(); circuit.num_qubits()];
+    let mut finished = Vec::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        if let Some((gate, qubits)) = unconditional_gate(instruction)
+            && qubits.len() == 1
+            && gate.params().iter().all(|param| param.value().is_some())
+        {
+            runs[qubits[0]].push(index);
+            continue;
+        }
+        for wire in circuit.wires(instruction) {
+            if wire < circuit.num_qubits() && !runs[wire].is_empty() {
+                finished.push(std::mem::take(&mut runs[wire]));
+            }
+        }
+    }
+    finished.extend(runs.into_iter().filter(|run| !run.is_empty()));
+
+    let mut replacements: Vec<Option<Vec<Instruction>>> = vec![None; instructions.len()];
+    let mut changed = false;
+    for run in finished {
+        let mut product: Array2<Complex64> = Array2::eye(2);
+        for &index in &run {
+            let (gate, _) = unconditional_gate(&instructions[index]).expect("runs hold gates");
+            product = bound_gate(gate)?.matrix().dot(&product);
+        }
+        // Gates the basis cannot resynthesize are kept as they are
+        let Ok(gates) = basis.synthesize(&product) else {
+            continue;
+        };
+        if gates.len() >= run.len() {
+            continue;
+        }
+        let qubit = match &instructions[run[0]].operation {
+            Operation::Gate { qubits, .. } => qubits[0],
+            _ => unreachable!("runs hold gates"),
+        };
+        for &index in &run {
+            replacements[index] = Some(Vec::new());
+        }
+        replacements[run[0]] = Some(
+            gates
+                .into_iter()
+                .map(|gate| Instruction {
+                    operation: Operation::Gate { gate, qubits: vec![qubit] },
+                    condition: None,
+                })
+                .collect(),
+        );
+        changed = true;
+    }
+    if changed {
+        let old = std::mem::take(instructions);
+        for (instruction, replacement) in old.into_iter().zip(replacements) {
+            match replacement {
+                Some(gates) => instructions.extend(gates),
+                None => instructions.push(instruction),
+            }
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantum_algorithms::ParameterExpr;
+    use crate::quantum_algorithms::transpiler::tests::{assert_equivalent, random_circuit};
+
+    fn basis() -> Basis {
+        Basis::new(&["cx", "rz", "sx", "x"].map(String::from)).unwrap()
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let mut circuit = QuantumCircuit::new(3, 1);
+        // The CNOTs commute through the Rz on the control and the X on the target
+        circuit.cx(0, 1).unwrap().rz(0.3, 0).unwrap().x(1).unwrap().cx(2, 1).unwrap();
+        circuit.cx(0, 1).unwrap().cx(2, 1).unwrap().rz(-0.3, 0).unwrap().x(1).unwrap();
+        assert_eq!(optimize(&circuit, &basis()).unwrap().instructions(), []);
+
+        // A measurement in between keeps both gates
+        let mut circuit = QuantumCircuit::new(2, 1);
+        circuit.x(0).unwrap().measure(0, 0).unwrap().x(0).unwrap().cz(0, 1).unwrap();
+        circuit.cz(1, 0).unwrap();
+        let optimized = optimize(&circuit, &basis()).unwrap();
+        assert_eq!(
+            optimized.gate_counts().into_iter().collect::<Vec<_>>(),
+            [("measure", 1), ("x", 2)]
+        );
+    }
+
+    #[test]
+    fn test_merging() {
+        let theta = ParameterExpr::symbol("theta");
+        let mut circuit = QuantumCircuit::new(2, 0);
+        circuit.rz(theta.clone(), 0).unwrap().cx(0, 1).unwrap().rz(0.5, 0).unwrap();
+        circuit.rx(std::f64::consts::PI, 1).unwrap().rx(std::f64::consts::PI, 1).unwrap();
+        let optimized = optimize(&circuit, &basis()).unwrap();
+        let expected = vec![
+            Instruction {
+                operation: Operation::Gate { gate: QuantumGate::Rz(theta + 0.5), qubits: vec![0] },
+                condition: None,
+            },
+            Instruction {
+                operation: Operation::Gate { gate: QuantumGate::CNOT, qubits: vec![0, 1] },
+                condition: None,
+            },
+        ];
+        assert_eq!(optimized.instructions(), expected);
+    }
+
+    #[test]
+    fn test_fusion() {
+        let mut circuit = QuantumCircuit::new(1, 0);
+        circuit.h(0).unwrap().t(0).unwrap().h(0).unwrap().s(0).unwrap().sx(0).unwrap();
+        let optimized = optimize(&circuit, &basis()).unwrap();
+        assert!(optimized.instructions().len() <= 5);
+        assert_equivalent(&circuit, &optimized);
+
+        for seed in 0..5 {
+            let circuit = basis().translate(&random_circuit(4, 40, seed)).unwrap();
+            let optimized = optimize(&circuit, &basis()).unwrap();
+            assert!(optimized.instructions().len() < circuit.instructions().len());
+            assert_equivalent(&circuit, &optimized);
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! SWAP routing onto a coupling map
+//!
+//! Routing follows SABRE (Li, Ding and Xie): the gates whose predecessors have all run form
+//! the front layer, and when none of them acts on coupled qubits, the SWAP that brings the
+//! front layer and the gates after it closest together is inserted. A decay on recently
+//! swapped qubits spreads the SWAPs out so they can run in parallel. Routing the circuit
+//! forwards and backwards in turn moves the initial layout towards one that needs fewer SWAPs.
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::quantum_algorithms::{Instruction, Operation, QuantumCircuit, QuantumGate};
+use crate::{QuantumError, QuantumResult};
+
+/// Two-qubit gates after the front layer that the SWAP score looks ahead to
+const EXTENDED_SET_SIZE: usize = 20;
+
+/// Weight of the gates after the front layer in the SWAP score
+const EXTENDED_SET_WEIGHT: f64 = 0.5;
+
+/// Increase of the decay of a qubit each time it is swapped
+const DECAY_INCREMENT: f64 = 0.001;
+
+/// SWAPs after which the decay of every qubit is reset
+const DECAY_RESET: usize = 5;
+
+/// Physical qubits of a device and the pairs of them two-qubit gates can act on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CouplingMap {
+    num_qubits: usize,
+    edges: Vec<(usize, usize)>,
+    neighbors: Vec<Vec<usize>>,
+    distances: Vec<Vec<usize>>,
+}
+
+impl CouplingMap {
+    /// Coupling map of `num_qubits` qubits with two-qubit gates on `edges` in either direction
+    ///
+    /// Every qubit must be reachable from every other one.
+    pub fn new(num_qubits: usize, edges: &[(usize, usize)]) -> QuantumResult<Self> {
+        if let Some(&(a, b)) = edges.iter().find(|&&(a, b)| a == b || a.max(b) >= num_qubits) {
+            return Err(QuantumError::SimulationError(format!(
+                "({a}, {b}) is not a pair of distinct qubits of {num_qubits}"
+            )));
+        }
+        let mut edges: Vec<(usize, usize)> =
+            edges.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect();
+        edges.sort_unstable();
+        edges.dedup();
+        let mut neighbors = vec![Vec::new(); num_qubits];
+        for &(a, b) in &edges {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+        let distances: Vec<Vec<usize>> =
+            (0..num_qubits).map(|qubit| breadth_first_distances(&neighbors, qubit)).collect();
+        if distances.iter().flatten().any(|&distance| distance == usize::MAX) {
+            return Err(QuantumError::SimulationError(
+                "the coupling map is not connected".to_string(),
+            ));
+        }
+        Ok(Self { num_qubits, edges, neighbors, distances })
+    }
+
+    /// Qubits in a line, each coupled to the next
+    pub fn line(num_qubits: usize) -> Self {
+        let edges: Vec<_> = (1..num_qubits).map(|qubit| (qubit - 1, qubit)).collect();
+        Self::new(num_qubits, &edges).expect("a line is connected")
+    }
+
+    /// Qubits in a ring, each coupled to the next and the last to the first
+    pub fn ring(num_qubits: usize) -> Self {
+        let mut edges: Vec<_> = (1..num_qubits).map(|qubit| (qubit - 1, qubit)).collect();
+        if num_qubits > 2 {
+            edges.push((num_qubits - 1, 0));
+        }
+        Self::new(num_qubits, &edges).expect("a ring is connected")
+    }
+
+    /// Qubits in a grid of `rows` rows, numbered row by row and coupled to their horizontal
+    /// and vertical neighbors
+    pub fn grid(rows: usize, columns: usize) -> Self {
+        let mut edges = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let qubit = row * columns + column;
+                if column + 1 < columns {
+                    edges.push((qubit, qubit + 1));
+                }
+                if row + 1 < rows {
+                    edges.push((qubit, qubit + columns));
+                }
+            }
+        }
+        Self::new(rows * columns, &edges).expect("a grid is connected")
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.num_qubits
+    }
+
+    /// Coupled pairs `(a, b)` with `a < b`, in increasing order
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    pub fn are_coupled(&self, a: usize, b: usize) -> bool {
+        self.distances[a][b] == 1
+    }
+
+    /// Number of couplings on a shortest path from `a` to `b`
+    pub fn distance(&self, a: usize, b: usize) -> usize {
+        self.distances[a][b]
+    }
+}
+
+/// Distances from `start` over the graph of `neighbors`, `usize::MAX` for unreachable nodes
+fn breadth_first_distances(neighbors: &[Vec<usize>], start: usize) -> Vec<usize> {
+    let mut distances = vec![usize::MAX; neighbors.len()];
+    distances[start] = 0;
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &next in &neighbors[node] {
+            if distances[next] == usize::MAX {
+                distances[next] = distances[node] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// Circuit routed onto the physical qubits of a coupling map
+pub(super) struct Routed {
+    pub(super) circuit: QuantumCircuit,
+    pub(super) initial_layout: Vec<usize>,
+    pub(super) final_layout: Vec<usize>,
+    pub(super) swaps: usize,
+}
+
+/// Route `circuit`, whose gates act on at most two qubits, onto `coupling`
+///
+/// The layout starts out trivial and is refined by `iterations` forward and backward passes.
+/// The routed circuit has a qubit for every physical qubit, with the ones the circuit does
+/// not use in an extra register.
+pub(super) fn route<R: Rng + ?Sized>(
+    circuit: &QuantumCircuit,
+    coupling: &CouplingMap,
+    iterations: usize,
+    rng: &mut R,
+) -> QuantumResult<Routed> {
+    if circuit.num_qubits() > coupling.num_qubits() {
+        return Err(QuantumError::SimulationError(format!(
+            "a circuit of {} qubits does not fit onto a coupling map of {}",
+            circuit.num_qubits(),
+            coupling.num_qubits()
+        )));
+    }
+    for instruction in circuit.instructions() {
+        if let Operation::Gate { gate, qubits } = &instruction.operation
+            && qubits.len() > 2
+        {
+            return Err(QuantumError::SimulationError(format!(
+                "gate `{}` on {} qubits cannot be routed, only gates on up to two qubits can",
+                gate.name(),
+                qubits.len()
+            )));
+        }
+    }
+
+    let forward = Dag::new(circuit, false);
+    let backward = Dag::new(circuit, true);
+    let mut layout: Vec<usize> = (0..coupling.num_qubits()).collect();
+    let mut best = (usize::MAX, layout.clone());
+    for _ in 0..iterations {
+        let (swaps, final_layout) = sabre(&forward, coupling, layout.clone(), rng, None);
+        if swaps < best.0 {
+            best = (swaps, layout);
+        }
+        layout = sabre(&backward, coupling, final_layout, rng, None).1;
+    }
+    let initial_layout = if iterations == 0 { layout } else { best.1 };
+
+    let mut routed = circuit.copy_empty();
+    let spare = coupling.num_qubits() - circuit.num_qubits();
+    if spare > 0 {
+        let taken = |name: &str| {
+            routed.quantum_register(name).is_some() || routed.classical_register(name).is_some()
+        };
+        let name = (0..)
+            .map(|i| if i == 0 { "ancilla".to_string() } else { format!("ancilla{i}") })
+            .find(|name| !taken(name))
+            .expect("some name is free");
+        routed.add_quantum_register(&name, spare)?;
+    }
+    let mut instructions = Vec::new();
+    let (swaps, final_layout) =
+        sabre(&forward, coupling, initial_layout.clone(), rng, Some(&mut instructions));
+    for instruction in instructions {
+        routed.push(instruction)?;
+    }
+    let logical = circuit.num_qubits();
+    Ok(Routed {
+        circuit: routed,
+        initial_layout: initial_layout[..logical].to_vec(),
+        final_layout: final_layout[..logical].to_vec(),
+        swaps,
+    })
+}
+
+/// Dependencies between the instructions of a circuit through the qubits and classical bits
+/// they share
+struct Dag {
+    instructions: Vec<Instruction>,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<usize>,
+}
+
+impl Dag {
+    /// The instructions of `circuit`, in reverse order if `reversed`
+    fn new(circuit: &QuantumCircuit, reversed: bool) -> Self {
+        let mut instructions = circuit.instructions().to_vec();
+        if reversed {
+            instructions.reverse();
+        }
+        let mut last = vec![None; circuit.num_qubits() + circuit.num_clbits()];
+        let mut successors = vec![Vec::new(); instructions.len()];
+        let mut predecessors = vec![0; instructions.len()];
+        for (node, instruction) in instructions.iter().enumerate() {
+            for wire in circuit.wires(instruction) {
+                if let Some(previous) = last[wire].replace(node)
+                    && !successors[previous].contains(&node)
+                {
+                    successors[previous].push(node);
+                    predecessors[node] += 1;
+                }
+            }
+        }
+        Self { instructions, successors, predecessors }
+    }
+
+    /// Qubits of a two-qubit gate
+    fn pair(&self, node: usize) -> Option<(usize, usize)> {
+        match &self.instructions[node].operation {
+            Operation::Gate { qubits, .. } if qubits.len() == 2 => Some((qubits[0], qubits[1])),
+            _ => None,
+        }
+    }
+}
+
+/// One SABRE pass over `dag` from `layout`, which maps each logical qubit to a physical one,
+/// with the ancillas numbered after the circuit qubits
+///
+/// Returns the number of SWAPs and the final layout, and writes the routed instructions to
+/// `output` if it is given.
+fn sabre<R: Rng + ?Sized>(
+    dag: &Dag,
+    coupling: &CouplingMap,
+    mut layout: Vec<usize>,
+    rng: &mut R,
+    mut output: Option<&mut Vec<Instruction>>,
+) -> (usize, Vec<usize>) {
+    let physical_count = coupling.num_qubits();
+    let mut logical = vec![0; physical_count];
+    for (qubit, &physical) in layout.iter().enumerate() {
+        logical[physical] = qubit;
+    }
+    let mut remaining = dag.predecessors.clone();
+    let mut front: Vec<usize> = (0..remaining.len()).filter(|&node| remaining[node] == 0).collect();
+    let mut decay = vec![1.0; physical_count];
+    let mut swaps: usize = 0;
+    let mut swaps_since_progress = 0;
+
+    while !front.is_empty() {
+        let (ready, blocked): (Vec<usize>, Vec<usize>) = front.iter().partition(|&&node| {
+            dag.pair(node).is_none_or(|(a, b)| coupling.are_coupled(layout[a], layout[b]))
+        });
+        if !ready.is_empty() {
+            for node in ready {
+                if let Some(output) = output.as_deref_mut() {
+                    output.push(mapped(&dag.instructions[node], &layout));
+                }
+                for &next in &dag.successors[node] {
+                    remaining[next] -= 1;
+                    if remaining[next] == 0 {
+                        front.push(next);
+                    }
+                }
+                front.retain(|&other| other != node);
+            }
+            decay.fill(1.0);
+            swaps_since_progress = 0;
+            continue;
+        }
+
+        let chosen = if swaps_since_progress > 10 * physical_count {
+            // Stop going in circles by moving the qubits of one gate towards each other
+            let (a, b) = dag.pair(blocked[0]).expect("blocked nodes are two-qubit gates");
+            let (from, to) = (layout[a], layout[b]);
+            let next = coupling.neighbors[from]
+                .iter()
+                .copied()
+                .find(|&next| coupling.distance(next, to) < coupling.distance(from, to))
+                .expect("the coupling map is connected");
+            (from.min(next), from.max(next))
+        } else {
+            best_swap(dag, coupling, &blocked, &remaining, &layout, &decay, rng)
+        };
+        let (a, b) = chosen;
+        let (qubit_a, qubit_b) = (logical[a], logical[b]);
+        logical.swap(a, b);
+        layout[qubit_a] = b;
+        layout[qubit_b] = a;
+        if let Some(output) = output.as_deref_mut() {
+            let operation = Operation::Gate { gate: QuantumGate::SWAP, qubits: vec![a, b] };
+            output.push(Instruction { operation, condition: None });
+        }
+        swaps += 1;
+        swaps_since_progress += 1;
+        decay[a] += DECAY_INCREMENT;
+        decay[b] += DECAY_INCREMENT;
+        if swaps.is_multiple_of(DECAY_RESET) {
+            decay.fill(1.0);
+        }
+    }
+    (swaps, layout)
+}
+
+/// SWAP of coupled physical qubits that most reduces the distances of the gates in the front
+/// layer and of the next gates after it, with ties broken at random
+fn best_swap<R: Rng + ?Sized>(
+    dag: &Dag,
+    coupling: &CouplingMap,
+    front: &[usize],
+    remaining: &[usize],
+    layout: &[usize],
+    decay: &[f64],
+    rng: &mut R,
+) -> (usize, usize) {
+    // The first two-qubit gates after the front layer, in breadth-first order
+    let mut extended = Vec::new();
+    let mut remaining = remaining.to_vec();
+    let mut queue: VecDeque<usize> = front.iter().copied().collect();
+    while let Some(node) = queue.pop_front() {
+        if extended.len() == EXTENDED_SET_SIZE {
+            break;
+        }
+        for &next in &dag.successors[node] {
+            remaining[next] -= 1;
+            if remaining[next] == 0 {
+                if dag.pair(next).is_some() {
+                    extended.push(next);
+                }
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let mut candidates: Vec<(usize, usize)> = Vec::new();
+    for &node in front {
+        let (a, b) = dag.pair(node).expect("blocked nodes are two-qubit gates");
+        for physical in [layout[a], layout[b]] {
+            for &neighbor in &coupling.neighbors[physical] {
+                candidates.push((physical.min(neighbor), physical.max(neighbor)));
+            }
+        }
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let swapped = |physical: usize, (a, b): (usize, usize)| match physical {
+        _ if physical == a => b,
+        _ if physical == b => a,
+        _ => physical,
+    };
+    let distance = |nodes: &[usize], swap: (usize, usize)| {
+        let total: usize = nodes
+            .iter()
+            .map(|&node| {
+                let (a, b) = dag.pair(node).expect("the sets only hold two-qubit gates");
+                coupling.distance(swapped(layout[a], swap), swapped(layout[b], swap))
+            })
+            .sum();
+        total as f64 / nodes.len().max(1) as f64
+    };
+    let scores: Vec<f64> = candidates
+        .iter()
+        .map(|&swap| {
+            let score = distance(front, swap) + EXTENDED_SET_WEIGHT * distance(&extended, swap);
+            decay[swap.0].max(decay[swap.1]) * score
+        })
+        .collect();
+    let lowest = scores.iter().copied().fold(f64::INFINITY, f64::min);
+    let best: Vec<usize> = (0..candidates.len()).filter(|&i| scores[i] - lowest < 1e-10).collect();
+    candidates[best[rng.random_range(0..best.len())]]
+}
+
+/// `instruction` on the physical qubits of `layout`
+fn mapped(instruction: &Instruction, layout: &[usize]) -> Instruction {
+    let map = |qubits: &[usize]| qubits.iter().map(|&qubit| layout[qubit]).collect();
+    let operation = match &instruction.operation {
+        Operation::Gate { gate, qubits } => {
+            Operation::Gate { gate: gate.clone(), qubits: map(qubits) }
+        }
+        Operation::Measure { qubit, clbit } => {
+            Operation::Measure { qubit: layout[*qubit], clbit: *clbit }
+        }
+        Operation::Reset { qubit } => Operation::Reset { qubit: layout[*qubit] },
+        Operation::Barrier { qubits } => Operation::Barrier { qubits: map(qubits) },
+    };
+    Instruction { operation, condition: instruction.condition }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    use super::*;
+    use crate::quantum_algorithms::StateVectorBackend;
+    use crate::quantum_algorithms::transpiler::tests::random_circuit;
+
+    #[test]
+    fn test_coupling_maps() {
+        let grid = CouplingMap::grid(2, 3);
+        assert_eq!(grid.edges(), [(0, 1), (0, 3), (1, 2), (1, 4), (2, 5), (3, 4), (4, 5)]);
+        assert_eq!(grid.distance(0, 5), 3);
+        assert!(grid.are_coupled(4, 1) && !grid.are_coupled(0, 4));
+        assert_eq!(CouplingMap::ring(5).distance(0, 3), 2);
+        assert_eq!(CouplingMap::line(5).distance(0, 4), 4);
+        assert!(CouplingMap::new(3, &[(0, 1)]).is_err());
+        assert!(CouplingMap::new(3, &[(0, 1), (1, 3)]).is_err());
+        assert!(CouplingMap::new(2, &[(1, 1)]).is_err());
+    }
+
+    #[test]
+    fn test_routing() {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+        let backend = StateVectorBackend::new(0);
+        for (circuit_qubits, coupling) in
+            [(5, CouplingMap::line(5)), (5, CouplingMap::grid(2, 3)), (4, CouplingMap::ring(6))]
+        {
+            let circuit = random_circuit(circuit_qubits, 30, rng.random());
+            let routed = route(&circuit, &coupling, 3, &mut rng).unwrap();
+            assert_eq!(routed.circuit.num_qubits(), coupling.num_qubits());
+            for instruction in routed.circuit.instructions() {
+                if let Operation::Gate { qubits, .. } = &instruction.operation
+                    && qubits.len() == 2
+                {
+                    assert!(coupling.are_coupled(qubits[0], qubits[1]), "{qubits:?}");
+                }
+            }
+
+            // The state of logical qubit i ends up on physical qubit final_layout[i]
+            let expected = backend.statevector(&circuit).unwrap();
+            let actual = backend.statevector(&routed.circuit).unwrap();
+            for (index, amplitude) in expected.amplitudes.iter().enumerate() {
+                let physical: usize = (0..circuit_qubits)
+                    .filter(|&qubit| (index >> qubit) & 1 == 1)
+                    .map(|qubit| 1 << routed.final_layout[qubit])
+                    .sum();
+                let routed_amplitude = actual.amplitudes[physical];
+                assert!((amplitude.0 - routed_amplitude.0).abs() < 1e-12);
+                assert!((amplitude.1 - routed_amplitude.1).abs() < 1e-12);
+            }
+        }
+    }
+}
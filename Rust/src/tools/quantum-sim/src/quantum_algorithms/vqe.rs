@@ -0,0 +1,700 @@
+//! Variational quantum eigensolver
+//!
+//! VQE approximates the ground state of a Hamiltonian by the lowest-energy state an ansatz
+//! circuit can prepare. The energy `⟨ψ(θ)|H|ψ(θ)⟩` is evaluated on the simulator, exactly or
+//! from shots, and a classical optimizer tunes the parameters `θ`, using gradients from the
+//! parameter-shift rule where it needs them.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f64::consts::FRAC_PI_2;
+
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use super::{
+    Operation, ParameterExpr, Pauli, PauliString, PauliSum, QuantumCircuit, QuantumGate,
+    QuantumState,
+};
+use crate::annealing::{Annealer, AnnealingConfig, AnnealingRng, CoolingSchedule, FnProblem};
+use crate::{QuantumError, QuantumResult};
+
+/// Exponential decay rates of Adam's first and second moment estimates
+const ADAM_BETAS: (f64, f64) = (0.9, 0.999);
+
+/// Initial annealing temperature relative to the sum of the absolute coefficients of the
+/// Hamiltonian
+const ANNEALING_TEMPERATURE: f64 = 0.1;
+
+/// Parameterized circuit that prepares the trial states of a variational algorithm
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ansatz {
+    circuit: QuantumCircuit,
+    parameters: Vec<String>,
+}
+
+impl Ansatz {
+    /// Ansatz made of the gates of `circuit`, with its parameters in alphabetical order
+    pub fn new(circuit: QuantumCircuit) -> QuantumResult<Self> {
+        let parameters = circuit.parameters().into_iter().collect();
+        Self::with_parameters(circuit, parameters)
+    }
+
+    pub(crate) fn with_parameters(
+        circuit: QuantumCircuit,
+        parameters: Vec<String>,
+    ) -> QuantumResult<Self> {
+        for instruction in circuit.instructions() {
+            let is_gate =
+                matches!(instruction.operation, Operation::Gate { .. } | Operation::Barrier { .. });
+            if !is_gate || instruction.condition.is_some() {
+                return Err(QuantumError::SimulationError(
+                    "an ansatz can only contain unconditional gates".to_string(),
+                ));
+            }
+        }
+        Ok(Self { circuit, parameters })
+    }
+
+    /// Layers of Ry and Rz rotations on every qubit, with a line of CNOTs between layers
+    ///
+    /// `layers` entangling layers take `2 n (layers + 1)` parameters.
+    pub fn hardware_efficient(num_qubits: usize, layers: usize) -> QuantumResult<Self> {
+        let mut circuit = QuantumCircuit::new(num_qubits, 0);
+        let mut parameters = Vec::new();
+        for layer in 0..=layers {
+            if layer > 0 {
+                for qubit in 1..num_qubits {
+                    circuit.cx(qubit - 1, qubit)?;
+                }
+            }
+            for qubit in 0..num_qubits {
+                circuit.ry(next_parameter(&mut parameters), qubit)?;
+                circuit.rz(next_parameter(&mut parameters), qubit)?;
+            }
+        }
+        Self::with_parameters(circuit, parameters)
+    }
+
+    /// Unitary coupled cluster with single and double excitations of the Hartree-Fock state
+    ///
+    /// Spin orbitals alternate between spin up and spin down, and the `electrons` lowest ones
+    /// are occupied. Every spin-preserving excitation `T` from occupied to virtual orbitals has
+    /// a parameter `θ` and contributes `exp(θ (T - T†))`, mapped to qubits with the
+    /// Jordan-Wigner transformation.
+    pub fn ucc(spin_orbitals: usize, electrons: usize) -> QuantumResult<Self> {
+        if electrons > spin_orbitals {
+            return Err(QuantumError::SimulationError(format!(
+                "{electrons} electrons do not fit into {spin_orbitals} spin orbitals"
+            )));
+        }
+        let mut circuit = QuantumCircuit::new(spin_orbitals, 0);
+        let mut parameters = Vec::new();
+        for orbital in 0..electrons {
+            circuit.x(orbital)?;
+        }
+
+        let spin_down = |orbitals: &[usize]| orbitals.iter().filter(|&&o| o % 2 == 1).count();
+        let mut excitations = Vec::new();
+        for i in 0..electrons {
+            for a in electrons..spin_orbitals {
+                excitations.push((vec![i], vec![a]));
+            }
+        }
+        for i in 0..electrons {
+            for j in i + 1..electrons {
+                for a in electrons..spin_orbitals {
+                    for b in a + 1..spin_orbitals {
+                        excitations.push((vec![i, j], vec![a, b]));
+                    }
+                }
+            }
+        }
+        for (occupied, virtuals) in excitations {
+            if spin_down(&occupied) != spin_down(&virtuals) {
+                continue;
+            }
+            let theta = next_parameter(&mut parameters);
+            for (factor, pauli) in excitation_rotations(spin_orbitals, &occupied, &virtuals) {
+                append_pauli_rotation(&mut circuit, &pauli, theta.clone() * factor)?;
+            }
+        }
+        Self::with_parameters(circuit, parameters)
+    }
+
+    pub fn circuit(&self) -> &QuantumCircuit {
+        &self.circuit
+    }
+
+    /// Names of the parameters, in the order their values are given
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    pub fn num_qubits(&self) -> usize {
+        self.circuit.num_qubits()
+    }
+
+    /// The circuit with the parameters bound to `values`
+    pub fn bind(&self, values: &[f64]) -> QuantumResult<QuantumCircuit> {
+        Ok(self.circuit.bind_parameters(&self.bindings(values)?))
+    }
+
+    fn bindings(&self, values: &[f64]) -> QuantumResult<HashMap<String, f64>> {
+        if values.len() != self.parameters.len() {
+            return Err(QuantumError::SimulationError(format!(
+                "the ansatz has {} parameters, got {} values",
+                self.parameters.len(),
+                values.len()
+            )));
+        }
+        Ok(self.parameters.iter().cloned().zip(values.iter().copied()).collect())
+    }
+
+    /// State prepared with the parameters `values`, with the angle of the gate at instruction
+    /// `shift.0` changed by `shift.1`
+    fn state(&self, values: &[f64], shift: Option<(usize, f64)>) -> QuantumResult<QuantumState> {
+        let bindings = self.bindings(values)?;
+        let mut state = QuantumState::new(self.circuit.num_qubits());
+        for (index, instruction) in self.circuit.instructions().iter().enumerate() {
+            let Operation::Gate { gate, qubits } = &instruction.operation else {
+                continue;
+            };
+            let mut gate = gate.try_map_params(|param| param.evaluate(&bindings))?;
+            if let Some((shifted, delta)) = shift
+                && shifted == index
+            {
+                gate = match gate {
+                    QuantumGate::Rx(angle) => QuantumGate::Rx(angle + delta),
+                    QuantumGate::Ry(angle) => QuantumGate::Ry(angle + delta),
+                    QuantumGate::Rz(angle) => QuantumGate::Rz(angle + delta),
+                    QuantumGate::Phase(angle) => QuantumGate::Phase(angle + delta),
+                    gate => gate,
+                };
+            }
+            state.apply_gate(&gate, qubits)?;
+        }
+        Ok(state)
+    }
+}
+
+/// Add a parameter named after its index
+fn next_parameter(parameters: &mut Vec<String>) -> ParameterExpr {
+    let name = format!("theta_{}", parameters.len());
+    parameters.push(name.clone());
+    ParameterExpr::Symbol(name)
+}
+
+/// Pauli rotations `exp(-i f θ P / 2)` as `(f, P)`, whose product is `exp(θ (T - T†))` for the
+/// excitation `T` of `occupied` into `virtuals`
+///
+/// Under Jordan-Wigner, `T - T†` is `i / 2^(k-1)` times the sum of the strings with X or Y
+/// on each of the `k` orbitals and an odd number of Y, with Z on the orbitals between each
+/// pair. A string is added if it has more Y on occupied than on virtual orbitals and
+/// subtracted otherwise. The strings commute, so their exponentials can be applied in turn.
+fn excitation_rotations(
+    num_qubits: usize,
+    occupied: &[usize],
+    virtuals: &[usize],
+) -> Vec<(f64, PauliString)> {
+    let orbitals: Vec<usize> = occupied.iter().chain(virtuals).copied().collect();
+    let mut parity_string = PauliString::identity(num_qubits);
+    for pair in orbitals.chunks(2) {
+        for qubit in pair[0] + 1..pair[1] {
+            parity_string.set(qubit, Pauli::Z);
+        }
+    }
+    let scale = 2.0 / (1 << (orbitals.len() - 1)) as f64;
+    (0..1usize << orbitals.len())
+        .filter(|ys| ys.count_ones() % 2 == 1)
+        .map(|ys| {
+            let mut pauli = parity_string.clone();
+            for (bit, &orbital) in orbitals.iter().enumerate() {
+                pauli.set(orbital, if (ys >> bit) & 1 == 1 { Pauli::Y } else { Pauli::X });
+            }
+            let occupied_ys = (ys & ((1 << occupied.len()) - 1)).count_ones();
+            let sign = if 2 * occupied_ys > ys.count_ones() { 1.0 } else { -1.0 };
+            (-sign * scale, pauli)
+        })
+        .collect()
+}
+
+/// Append `exp(-i angle P / 2)`: a basis change that turns every factor of `P` into Z, a CNOT
+/// ladder that collects their parity on the last qubit, an Rz there, and the inverse steps
+pub(crate) fn append_pauli_rotation(
+    circuit: &mut QuantumCircuit,
+    pauli: &PauliString,
+    angle: ParameterExpr,
+) -> QuantumResult<()> {
+    let support: Vec<usize> = pauli.support().collect();
+    let Some(&last) = support.last() else {
+        // The identity only contributes a global phase
+        return Ok(());
+    };
+    for &qubit in &support {
+        match pauli.get(qubit) {
+            Pauli::X => {
+                circuit.h(qubit)?;
+            }
+            Pauli::Y => {
+                circuit.sdg(qubit)?.h(qubit)?;
+            }
+            Pauli::I | Pauli::Z => {}
+        }
+    }
+    for pair in support.windows(2) {
+        circuit.cx(pair[0], pair[1])?;
+    }
+    circuit.rz(angle, last)?;
+    for pair in support.windows(2).rev() {
+        circuit.cx(pair[0], pair[1])?;
+    }
+    for &qubit in &support {
+        match pauli.get(qubit) {
+            Pauli::X => {
+                circuit.h(qubit)?;
+            }
+            Pauli::Y => {
+                circuit.h(qubit)?.s(qubit)?;
+            }
+            Pauli::I | Pauli::Z => {}
+        }
+    }
+    Ok(())
+}
+
+/// Optimizer of the parameters of a variational algorithm
+///
+/// The variants are those of `std::quantum::compiler::OptimizerType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizerType {
+    /// Adam gradient descent on parameter-shift gradients
+    Classical,
+    /// Simulated annealing of the parameters, which needs no gradients
+    QuantumInspired,
+    /// Annealing for the first half of the iterations, then gradient descent from the best
+    /// parameters it found
+    Hybrid,
+}
+
+/// Settings of a [`Vqe`] run
+#[derive(Debug, Clone, PartialEq)]
+pub struct VqeConfig {
+    /// Upper bound on the gradient steps and annealing moves
+    pub max_iterations: usize,
+    /// Gradient descent stops once a step changes the energy by less than this and the squared
+    /// norm of the gradient is below it, and annealing once the temperature drops to it
+    pub convergence_threshold: f64,
+    pub optimizer_type: OptimizerType,
+    /// Step size of gradient descent, and the largest change of a parameter in an annealing
+    /// move
+    pub learning_rate: f64,
+    /// Measurements of every group of commuting terms per energy, or `None` for exact
+    /// expectation values
+    pub shots: Option<usize>,
+    /// Seed of the measurement outcomes and of annealing
+    pub seed: u64,
+}
+
+impl Default for VqeConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 1000,
+            convergence_threshold: 1e-6,
+            optimizer_type: OptimizerType::Classical,
+            learning_rate: 0.1,
+            shots: None,
+            seed: 0,
+        }
+    }
+}
+
+/// Outcome of [`Vqe::run`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VqeResult {
+    pub energy: f64,
+    /// Values of [`Ansatz::parameters`] that prepare the state of `energy`
+    pub parameters: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+    /// Best energy of every annealing run, then the energy before and after every gradient
+    /// step
+    pub energy_history: Vec<f64>,
+}
+
+/// Variational quantum eigensolver for a Hamiltonian and an ansatz
+pub struct Vqe {
+    hamiltonian: PauliSum,
+    ansatz: Ansatz,
+    config: VqeConfig,
+    rng: Xoshiro256PlusPlus,
+}
+
+impl Vqe {
+    pub fn new(hamiltonian: PauliSum, ansatz: Ansatz, config: VqeConfig) -> QuantumResult<Self> {
+        if hamiltonian.num_qubits() != ansatz.num_qubits() {
+            return Err(QuantumError::SimulationError(format!(
+                "the Hamiltonian acts on {} qubits, but the ansatz has {}",
+                hamiltonian.num_qubits(),
+                ansatz.num_qubits()
+            )));
+        }
+        let rng = Xoshiro256PlusPlus::seed_from_u64(config.seed);
+        Ok(Self { hamiltonian, ansatz, config, rng })
+    }
+
+    pub fn hamiltonian(&self) -> &PauliSum {
+        &self.hamiltonian
+    }
+
+    pub fn ansatz(&self) -> &Ansatz {
+        &self.ansatz
+    }
+
+    pub fn config(&self) -> &VqeConfig {
+        &self.config
+    }
+
+    /// Energy of the state prepared with `parameters`
+    pub fn energy(&mut self, parameters: &[f64]) -> QuantumResult<f64> {
+        let state = self.ansatz.state(parameters, None)?;
+        measure(&self.hamiltonian, &state, self.config.shots, &mut self.rng)
+    }
+
+    /// Gradient of the energy at `parameters` by the parameter-shift rule
+    ///
+    /// For a gate `exp(-i a G / 2)` whose generator `G` has the eigenvalues ±1, the derivative
+    /// of the energy by `a` is half the difference of the energies with `a` shifted by ±π/2,
+    /// which is exact and needs no finite step. Every Rx, Ry, Rz and phase gate whose angle
+    /// depends on a parameter is shifted in turn, and the chain rule through the angle
+    /// expressions gives the derivatives by the parameters.
+    pub fn gradient(&mut self, parameters: &[f64]) -> QuantumResult<Vec<f64>> {
+        let Self { hamiltonian, ansatz, config, rng } = self;
+        let bindings = ansatz.bindings(parameters)?;
+        let mut gradient = vec![0.0; parameters.len()];
+        for (index, instruction) in ansatz.circuit.instructions().iter().enumerate() {
+            let Operation::Gate { gate, .. } = &instruction.operation else {
+                continue;
+            };
+            let angle = match gate {
+                QuantumGate::Rx(angle)
+                | QuantumGate::Ry(angle)
+                | QuantumGate::Rz(angle)
+                | QuantumGate::Phase(angle) => angle,
+                _ if gate.params().iter().all(|param| param.parameters().is_empty()) => continue,
+                _ => {
+                    return Err(QuantumError::SimulationError(format!(
+                        "the parameter-shift rule does not apply to parameterized `{}` gates",
+                        gate.name()
+                    )));
+                }
+            };
+            let derivatives = ansatz
+                .parameters
+                .iter()
+                .map(|name| angle.derivative(name).evaluate(&bindings))
+                .collect::<QuantumResult<Vec<f64>>>()?;
+            if derivatives.iter().all(|&derivative| derivative == 0.0) {
+                continue;
+            }
+            let plus = ansatz.state(parameters, Some((index, FRAC_PI_2)))?;
+            let minus = ansatz.state(parameters, Some((index, -FRAC_PI_2)))?;
+            let slope = (measure(hamiltonian, &plus, config.shots, rng)?
+                - measure(hamiltonian, &minus, config.shots, rng)?)
+                / 2.0;
+            for (component, derivative) in gradient.iter_mut().zip(derivatives) {
+                *component += derivative * slope;
+            }
+        }
+        Ok(gradient)
+    }
+
+    /// Minimize the energy with the configured optimizer, starting from `initial`
+    pub fn run(&mut self, initial: &[f64]) -> QuantumResult<VqeResult> {
+        self.ansatz.bindings(initial)?;
+        let max_iterations = self.config.max_iterations;
+        match self.config.optimizer_type {
+            OptimizerType::Classical => self.gradient_descent(initial.to_vec(), max_iterations),
+            OptimizerType::QuantumInspired => self.anneal(initial.to_vec(), max_iterations),
+            OptimizerType::Hybrid => {
+                let annealed = self.anneal(initial.to_vec(), max_iterations / 2)?;
+                let mut result = self
+                    .gradient_descent(annealed.parameters, max_iterations - annealed.iterations)?;
+                result.iterations += annealed.iterations;
+                result.energy_history.splice(0..0, annealed.energy_history);
+                Ok(result)
+            }
+        }
+    }
+
+    /// Adam steps along the negative parameter-shift gradient
+    fn gradient_descent(
+        &mut self,
+        mut parameters: Vec<f64>,
+        max_iterations: usize,
+    ) -> QuantumResult<VqeResult> {
+        let (beta1, beta2) = ADAM_BETAS;
+        let mut first_moment = vec![0.0; parameters.len()];
+        let mut second_moment = vec![0.0; parameters.len()];
+        let mut energy = self.energy(&parameters)?;
+        let mut energy_history = vec![energy];
+        for iteration in 1..=max_iterations {
+            let gradient = self.gradient(&parameters)?;
+            let gradient_norm = gradient.iter().map(|component| component * component).sum::<f64>();
+            let step = iteration as i32;
+            for (index, component) in gradient.into_iter().enumerate() {
+                first_moment[index] = beta1 * first_moment[index] + (1.0 - beta1) * component;
+                second_moment[index] =
+                    beta2 * second_moment[index] + (1.0 - beta2) * component * component;
+                let first = first_moment[index] / (1.0 - beta1.powi(step));
+                let second = second_moment[index] / (1.0 - beta2.powi(step));
+                parameters[index] -= self.config.learning_rate * first / (second.sqrt() + 1e-8);
+            }
+            let previous = energy;
+            energy = self.energy(&parameters)?;
+            energy_history.push(energy);
+            // Adam can take a small step on a slope, so the gradient has to be flat as well
+            let threshold = self.config.convergence_threshold;
+            if (energy - previous).abs() < threshold && gradient_norm < threshold {
+                return Ok(VqeResult {
+                    energy,
+                    parameters,
+                    iterations: iteration,
+                    converged: true,
+                    energy_history,
+                });
+            }
+        }
+        Ok(VqeResult {
+            energy,
+            parameters,
+            iterations: max_iterations,
+            converged: false,
+            energy_history,
+        })
+    }
+
+    /// Simulated annealing with moves that change one parameter at a time
+    fn anneal(&mut self, parameters: Vec<f64>, max_iterations: usize) -> QuantumResult<VqeResult> {
+        let Self { hamiltonian, ansatz, config, rng } = self;
+        let scale: f64 = hamiltonian
+            .terms()
+            .iter()
+            .filter(|(_, pauli)| pauli.weight() > 0)
+            .map(|(coefficient, _)| coefficient.abs())
+            .sum();
+        let annealing = AnnealingConfig {
+            initial_temperature: (ANNEALING_TEMPERATURE * scale).max(config.convergence_threshold),
+            min_temperature: config.convergence_threshold,
+            max_iterations,
+            schedule: CoolingSchedule::Linear,
+            seed: config.seed,
+            ..AnnealingConfig::default()
+        };
+
+        let measurement_rng = RefCell::new(rng);
+        let failure = RefCell::new(None);
+        let step = config.learning_rate;
+        let problem = FnProblem::new(
+            |parameters: &Vec<f64>| {
+                let mut rng = measurement_rng.borrow_mut();
+                let energy = ansatz
+                    .state(parameters, None)
+                    .and_then(|state| measure(hamiltonian, &state, config.shots, &mut **rng));
+                energy.unwrap_or_else(|error| {
+                    failure.borrow_mut().get_or_insert(error);
+                    f64::INFINITY
+                })
+            },
+            |parameters: &Vec<f64>, rng: &mut AnnealingRng| {
+                let mut neighbor = parameters.clone();
+                if !neighbor.is_empty() {
+                    let index = rng.random_range(0..neighbor.len());
+                    neighbor[index] += rng.random_range(-step..=step);
+                }
+                neighbor
+            },
+        );
+        let result = Annealer::new(annealing).anneal(&problem, parameters);
+        if let Some(error) = failure.into_inner() {
+            return Err(error);
+        }
+        Ok(VqeResult {
+            energy: result.energy,
+            parameters: result.state,
+            iterations: result.stats.iterations,
+            converged: result.stats.converged,
+            energy_history: result.stats.run_best_energies,
+        })
+    }
+}
+
+/// Energy of `state`, exact or estimated from `shots`
+fn measure<R: Rng + ?Sized>(
+    hamiltonian: &PauliSum,
+    state: &QuantumState,
+    shots: Option<usize>,
+    rng: &mut R,
+) -> QuantumResult<f64> {
+    match shots {
+        None => hamiltonian.expectation(state),
+        Some(shots) => hamiltonian.estimate(state, shots, rng),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantum_algorithms::StateVectorBackend;
+
+    /// H₂ in STO-3G at 0.7414 Å on four spin orbitals, with the nuclear repulsion
+    fn hydrogen() -> PauliSum {
+        let g = 0.04532220205287399;
+        PauliSum::from_terms(&[
+            (-0.0988639693354571, "IIII"),
+            (0.17119774903432955, "ZIII"),
+            (0.17119774903432955, "IZII"),
+            (-0.22278593040418523, "IIZI"),
+            (-0.22278593040418523, "IIIZ"),
+            (0.16862219158920938, "ZZII"),
+            (0.12054482205301799, "ZIZI"),
+            (0.16586702410589454, "ZIIZ"),
+            (0.16586702410589454, "IZZI"),
+            (0.12054482205301799, "IZIZ"),
+            (0.17434844185575682, "IIZZ"),
+            (-g, "XXYY"),
+            (g, "XYYX"),
+            (g, "YXXY"),
+            (-g, "YYXX"),
+        ])
+        .unwrap()
+    }
+
+    const HYDROGEN_GROUND_ENERGY: f64 = -1.1372701746609;
+
+    /// Electronic part of H₂ at 0.735 Å reduced to two qubits by symmetry
+    fn reduced_hydrogen() -> PauliSum {
+        PauliSum::from_terms(&[
+            (-1.052373245772859, "II"),
+            (0.39793742484318045, "ZI"),
+            (-0.39793742484318045, "IZ"),
+            (-0.01128010425623538, "ZZ"),
+            (0.18093119978423156, "XX"),
+        ])
+        .unwrap()
+    }
+
+    const REDUCED_HYDROGEN_GROUND_ENERGY: f64 = -1.85727503020238;
+
+    fn initial_parameters(count: usize) -> Vec<f64> {
+        (0..count).map(|index| 0.1 * (index + 1) as f64).collect()
+    }
+
+    #[test]
+    fn test_ansatz_structure() {
+        let ansatz = Ansatz::hardware_efficient(3, 2).unwrap();
+        assert_eq!(ansatz.parameters().len(), 18);
+        assert_eq!(ansatz.parameters()[17], "theta_17");
+
+        // H₂ has the singles 0→2 and 1→3 and the double 01→23
+        let ucc = Ansatz::ucc(4, 2).unwrap();
+        assert_eq!(ucc.parameters().len(), 3);
+        let hartree_fock = StateVectorBackend::new(0).statevector(&ucc.bind(&[0.0; 3]).unwrap());
+        assert!((hartree_fock.unwrap().probabilities()[0b0011] - 1.0).abs() < 1e-12);
+        assert!(Ansatz::ucc(2, 3).is_err());
+        assert!(ucc.bind(&[0.0; 2]).is_err());
+
+        let mut measured = QuantumCircuit::new(1, 1);
+        measured.ry("a", 0).unwrap().measure(0, 0).unwrap();
+        assert!(Ansatz::new(measured).is_err());
+    }
+
+    #[test]
+    fn test_excitation_rotations() {
+        // exp(θ (a₂† a₀ - a₀† a₂)) turns |0001⟩ into cos θ |0001⟩ + sin θ |0100⟩
+        let mut circuit = QuantumCircuit::new(4, 0);
+        circuit.x(0).unwrap();
+        for (factor, pauli) in excitation_rotations(4, &[0], &[2]) {
+            append_pauli_rotation(&mut circuit, &pauli, ParameterExpr::Value(0.4 * factor))
+                .unwrap();
+        }
+        let state = StateVectorBackend::new(0).statevector(&circuit).unwrap();
+        assert!((state.amplitudes[0b0001].0 - 0.4_f64.cos()).abs() < 1e-12);
+        assert!((state.amplitudes[0b0100].0.abs() - 0.4_f64.sin()).abs() < 1e-12);
+
+        let doubles = excitation_rotations(6, &[0, 2], &[3, 5]);
+        assert_eq!(doubles.len(), 8);
+        assert!(doubles.iter().all(|(factor, _)| factor.abs() == 0.25));
+        assert_eq!(doubles[0].1.to_string(), "YZXXZX");
+    }
+
+    #[test]
+    fn test_parameter_shift_gradient() {
+        let hamiltonian =
+            PauliSum::from_terms(&[(0.7, "XYZ"), (-0.4, "ZZI"), (0.9, "IXX"), (0.2, "YII")])
+                .unwrap();
+        let cases = [
+            (hamiltonian, Ansatz::hardware_efficient(3, 1).unwrap()),
+            (hydrogen(), Ansatz::ucc(4, 2).unwrap()),
+        ];
+        for (hamiltonian, ansatz) in cases {
+            let parameters = initial_parameters(ansatz.parameters().len());
+            let mut vqe = Vqe::new(hamiltonian, ansatz, VqeConfig::default()).unwrap();
+            let gradient = vqe.gradient(&parameters).unwrap();
+            for (index, component) in gradient.into_iter().enumerate() {
+                let h = 1e-5;
+                let mut shifted = parameters.clone();
+                shifted[index] += h;
+                let plus = vqe.energy(&shifted).unwrap();
+                shifted[index] -= 2.0 * h;
+                let minus = vqe.energy(&shifted).unwrap();
+                let difference = (plus - minus) / (2.0 * h);
+                assert!((component - difference).abs() < 1e-7, "{component} {difference}");
+            }
+        }
+
+        let mut circuit = QuantumCircuit::new(2, 0);
+        circuit.crx("a", 0, 1).unwrap();
+        let ansatz = Ansatz::new(circuit).unwrap();
+        let hamiltonian = PauliSum::from_terms(&[(1.0, "ZZ")]).unwrap();
+        let mut vqe = Vqe::new(hamiltonian, ansatz, VqeConfig::default()).unwrap();
+        assert!(vqe.gradient(&[0.3]).is_err());
+    }
+
+    #[test]
+    fn test_hydrogen_ground_state() {
+        // Gradient descent from Hartree-Fock along the UCC excitations
+        let mut vqe =
+            Vqe::new(hydrogen(), Ansatz::ucc(4, 2).unwrap(), VqeConfig::default()).unwrap();
+        let result = vqe.run(&[0.0; 3]).unwrap();
+        assert!(result.converged);
+        assert!((result.energy - HYDROGEN_GROUND_ENERGY).abs() < 1e-5, "{}", result.energy);
+
+        // A hardware-efficient ansatz on the reduced Hamiltonian, with every optimizer
+        for (optimizer_type, tolerance) in [
+            (OptimizerType::Classical, 1e-5),
+            (OptimizerType::QuantumInspired, 5e-2),
+            (OptimizerType::Hybrid, 1e-5),
+        ] {
+            let config = VqeConfig { optimizer_type, max_iterations: 2000, ..VqeConfig::default() };
+            let ansatz = Ansatz::hardware_efficient(2, 1).unwrap();
+            let initial = initial_parameters(ansatz.parameters().len());
+            let mut vqe = Vqe::new(reduced_hydrogen(), ansatz, config).unwrap();
+            let result = vqe.run(&initial).unwrap();
+            let error = (result.energy - REDUCED_HYDROGEN_GROUND_ENERGY).abs();
+            assert!(error < tolerance, "{optimizer_type:?}: {}", result.energy);
+        }
+    }
+
+    #[test]
+    fn test_shot_based_energy() {
+        let config = VqeConfig { shots: Some(4000), max_iterations: 60, ..VqeConfig::default() };
+        let mut vqe = Vqe::new(hydrogen(), Ansatz::ucc(4, 2).unwrap(), config).unwrap();
+        let result = vqe.run(&[0.0; 3]).unwrap();
+        assert!(!result.converged);
+        // The noisy estimate scatters around the exact energy of the parameters found
+        let exact = hydrogen().expectation(&vqe.ansatz().state(&result.parameters, None).unwrap());
+        assert!((exact.unwrap() - HYDROGEN_GROUND_ENERGY).abs() < 5e-3);
+        assert!((result.energy - HYDROGEN_GROUND_ENERGY).abs() < 2e-2, "{}", result.energy);
+    }
+}
@@ -0,0 +1,51 @@
+//@ test-mir-pass: QuantumConstantFolding
+#![feature(custom_mir, core_intrinsics)]
+#![crate_type = "lib"]
+
+use std::intrinsics::mir::*;
+
+// EMIT_MIR constant_folding.arithmetic.QuantumConstantFolding.diff
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn arithmetic(x: i32) -> i32 {
+    // CHECK-LABEL: fn arithmetic(
+    // CHECK: {{_.*}} = const 5_i32;
+    // CHECK: [[b:_.*]] = const 20_i32;
+    // CHECK: _0 = Add(copy _1, copy [[b]]);
+    mir! {
+        let a: i32;
+        let b: i32;
+        {
+            a = 2_i32 + 3_i32;
+            b = a * 4_i32;
+            RET = x + b;
+            Return()
+        }
+    }
+}
+
+// The condition of the switch becomes a constant, which dead code elimination resolves.
+// EMIT_MIR constant_folding.known_condition.QuantumConstantFolding.diff
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn known_condition(x: u8) -> u8 {
+    // CHECK-LABEL: fn known_condition(
+    // CHECK: {{_.*}} = const true;
+    // CHECK: switchInt(const true) -> [1: bb1, otherwise: bb2];
+    mir! {
+        let c: bool;
+        {
+            c = 3_u8 < 7_u8;
+            match c {
+                true => big,
+                _ => small,
+            }
+        }
+        big = {
+            RET = x;
+            Return()
+        }
+        small = {
+            RET = 0;
+            Return()
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//@ test-mir-pass: QuantumDeadCodeElimination
+#![feature(custom_mir, core_intrinsics)]
+#![crate_type = "lib"]
+
+use std::intrinsics::mir::*;
+
+// EMIT_MIR dead_code_elimination.constant_switch.QuantumDeadCodeElimination.diff
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn constant_switch(x: u8) -> u8 {
+    // CHECK-LABEL: fn constant_switch(
+    // CHECK: bb0: {
+    // CHECK-NEXT: goto -> bb1;
+    // CHECK: bb1: {
+    // CHECK-NEXT: _0 = copy _1;
+    // CHECK-NEXT: return;
+    // CHECK-NOT: bb2
+    mir! {
+        {
+            match 1_u8 {
+                1 => one,
+                2 => two,
+                _ => other,
+            }
+        }
+        one = {
+            RET = x;
+            Return()
+        }
+        two = {
+            RET = 2;
+            Return()
+        }
+        other = {
+            RET = 0;
+            Return()
+        }
+    }
+}
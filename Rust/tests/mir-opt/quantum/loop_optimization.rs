@@ -0,0 +1,55 @@
+//@ test-mir-pass: QuantumLoopOptimization
+#![feature(custom_mir, core_intrinsics)]
+#![crate_type = "lib"]
+
+use std::intrinsics::mir::*;
+
+// `a` and `b` only depend on `k`, so they are computed once before the loop. `sum` and `i` are
+// assigned on every iteration and stay in the loop.
+// EMIT_MIR loop_optimization.invariant.QuantumLoopOptimization.diff
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn invariant(n: u32, k: u32) -> u32 {
+    // CHECK-LABEL: fn invariant(
+    // CHECK: bb0: {
+    // CHECK: [[a:_.*]] = Mul(copy _2, const 3_u32);
+    // CHECK-NEXT: [[b:_.*]] = Add(copy [[a]], const 1_u32);
+    // CHECK-NEXT: goto -> bb1;
+    // CHECK: bb2: {
+    // CHECK-NOT: StorageLive
+    // CHECK-NOT: Mul
+    // CHECK: {{_.*}} = Add(copy {{_.*}}, copy [[b]]);
+    // CHECK-NOT: StorageDead
+    // CHECK: goto -> bb1;
+    mir! {
+        let sum: u32;
+        let a: u32;
+        let b: u32;
+        let i: u32;
+        let c: bool;
+        {
+            sum = 0;
+            i = 0;
+            Goto(header)
+        }
+        header = {
+            c = i < n;
+            match c {
+                true => body,
+                _ => exit,
+            }
+        }
+        body = {
+            StorageLive(a);
+            a = k * 3_u32;
+            b = a + 1_u32;
+            sum = sum + b;
+            i = i + 1_u32;
+            StorageDead(a);
+            Goto(header)
+        }
+        exit = {
+            RET = sum;
+            Return()
+        }
+    }
+}
@@ -0,0 +1,103 @@
+#![feature(custom_mir, core_intrinsics)]
+#![crate_type = "lib"]
+
+use std::intrinsics::mir::*;
+
+// Two folded operations
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn arithmetic(x: i32) -> i32 {
+    mir! {
+        let a: i32;
+        let b: i32;
+        {
+            a = 2_i32 + 3_i32;
+            b = a * 4_i32;
+            RET = x + b;
+            Return()
+        }
+    }
+}
+
+// A folded comparison and switch condition, and one eliminated block
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn known_condition(x: u8) -> u8 {
+    mir! {
+        let c: bool;
+        {
+            c = 3_u8 < 7_u8;
+            match c {
+                true => big,
+                _ => small,
+            }
+        }
+        big = {
+            RET = x;
+            Return()
+        }
+        small = {
+            RET = 0;
+            Return()
+        }
+    }
+}
+
+// Two eliminated blocks
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn constant_switch(x: u8) -> u8 {
+    mir! {
+        {
+            match 1_u8 {
+                1 => one,
+                2 => two,
+                _ => other,
+            }
+        }
+        one = {
+            RET = x;
+            Return()
+        }
+        two = {
+            RET = 2;
+            Return()
+        }
+        other = {
+            RET = 0;
+            Return()
+        }
+    }
+}
+
+// One loop with two hoisted statements
+#[custom_mir(dialect = "runtime", phase = "post-cleanup")]
+pub fn invariant(n: u32, k: u32) -> u32 {
+    mir! {
+        let sum: u32;
+        let a: u32;
+        let b: u32;
+        let i: u32;
+        let c: bool;
+        {
+            sum = 0;
+            i = 0;
+            Goto(header)
+        }
+        header = {
+            c = i < n;
+            match c {
+                true => body,
+                _ => exit,
+            }
+        }
+        body = {
+            a = k * 3_u32;
+            b = a + 1_u32;
+            sum = sum + b;
+            i = i + 1_u32;
+            Goto(header)
+        }
+        exit = {
+            RET = sum;
+            Return()
+        }
+    }
+}
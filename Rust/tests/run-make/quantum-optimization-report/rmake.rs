@@ -0,0 +1,78 @@
+//! `-Zquantum=optimization` adds the quantum MIR passes to `optimized_mir`. Check the changes
+//! they report in `-Zquantum-report=json` at each `-Zquantum-opt-level`: constant folding and
+//! dead code elimination from level 1, loop-invariant hoisting from level 2. Builds that do not
+//! codegen do not optimize MIR, so the phase does not run for them.
+
+//@ needs-target-std
+
+use run_make_support::serde_json::{self, Value, json};
+use run_make_support::{rfs, rustc};
+
+/// The `-Zquantum-report=json` report of a build of `lib.rs` with `args`
+fn quantum_report(args: &[&str]) -> Value {
+    rustc()
+        .input("lib.rs")
+        .arg("-Zquantum=optimization")
+        .args(args)
+        .arg("-Zquantum-report=json")
+        .arg("-Zquantum-report-path=report.json")
+        .run();
+    let report: Value = serde_json::from_str(&rfs::read_to_string("report.json")).unwrap();
+    assert_eq!(report["crate_name"], "lib");
+    report
+}
+
+/// Metrics of the `optimization` phase, by name
+fn optimization_metrics(opt_level: u8) -> Value {
+    let report = quantum_report(&[&format!("-Zquantum-opt-level={opt_level}")]);
+
+    let [phase] = report["phases"].as_array().unwrap().as_slice() else {
+        panic!("expected only the optimization phase: {report}");
+    };
+    assert_eq!(phase["phase"], "optimization");
+    // The passes are timed on their own and compared against the rest of `optimized_mir`
+    assert_eq!(phase["baseline"]["passes"], json!(["optimized_mir"]));
+    assert!(phase["wall_time_secs"].as_f64().unwrap() >= 0.0);
+
+    let metrics = phase["metrics"].as_array().unwrap();
+    metrics
+        .iter()
+        .map(|metric| (metric["name"].as_str().unwrap().to_owned(), metric["value"].clone()))
+        .collect()
+}
+
+fn main() {
+    assert_eq!(
+        optimization_metrics(2),
+        json!({
+            "optimizations": 3,
+            "folded_expressions": 4,
+            "eliminated_blocks": 3,
+            "optimized_loops": 1,
+            "hoisted_statements": 2,
+        })
+    );
+    assert_eq!(
+        optimization_metrics(1),
+        json!({
+            "optimizations": 2,
+            "folded_expressions": 4,
+            "eliminated_blocks": 3,
+            "optimized_loops": 0,
+            "hoisted_statements": 0,
+        })
+    );
+    assert_eq!(
+        optimization_metrics(0),
+        json!({
+            "optimizations": 0,
+            "folded_expressions": 0,
+            "eliminated_blocks": 0,
+            "optimized_loops": 0,
+            "hoisted_statements": 0,
+        })
+    );
+
+    let report = quantum_report(&["--emit=metadata"]);
+    assert_eq!(report["phases"], json!([]), "check builds do not run the phase: {report}");
+}
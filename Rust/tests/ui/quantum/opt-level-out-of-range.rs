@@ -0,0 +1,8 @@
+//@ revisions: too-high not-a-number
+//@ [too-high] compile-flags: -Zquantum=optimization -Zquantum-opt-level=4
+//@ [not-a-number] compile-flags: -Zquantum=optimization -Zquantum-opt-level=max
+
+fn main() {}
+
+//[too-high]~? ERROR incorrect value `4` for unstable option `quantum-opt-level`
+//[not-a-number]~? ERROR incorrect value `max` for unstable option `quantum-opt-level`
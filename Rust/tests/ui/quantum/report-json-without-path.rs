@@ -0,0 +1,7 @@
+// The JSON report must go to a file rather than being mixed into the diagnostics on stderr.
+
+//@ compile-flags: -Zquantum=parsing -Zquantum-report=json
+
+fn main() {}
+
+//~? ERROR `-Z quantum-report=json` requires `-Z quantum-report-path`
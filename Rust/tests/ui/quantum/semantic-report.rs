@@ -0,0 +1,30 @@
+// `-Zquantum=semantic` on a crate with a closure, a method and foreign items.
+// Symbols: `Counter`, `main`, `Counter::bump`, `abs`, `errno` and six bindings.
+// Scopes: the crate, the impl, `main` and `Counter::bump`. Foreign items have no body.
+
+//@ check-pass
+//@ compile-flags: -Zquantum=semantic
+
+struct Counter {
+    //~^ NOTE quantum semantic phase finished: 11 symbols, 4 scopes, 13 dependencies, 3 inferred
+    count: u32,
+}
+
+impl Counter {
+    fn bump(&mut self, by: u32) -> u32 {
+        self.count += by;
+        self.count
+    }
+}
+
+unsafe extern "C" {
+    fn abs(x: i32) -> i32;
+    static errno: i32;
+}
+
+fn main() {
+    let mut counter = Counter { count: 0 };
+    let doubled: Vec<u32> = [1, 2].iter().map(|by| counter.bump(by * 2)).collect();
+    let magnitude = unsafe { abs(errno) };
+    let _ = (doubled, magnitude);
+}